@@ -0,0 +1,8 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS derived_blobs (
+    blob_view_id      TEXT      NOT NULL,
+    name              TEXT      NOT NULL,
+    mime_type         TEXT      NOT NULL,
+    PRIMARY KEY (blob_view_id, name)
+);
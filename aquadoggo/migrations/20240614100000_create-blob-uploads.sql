@@ -0,0 +1,17 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS blob_upload_sessions (
+    session_id        TEXT      NOT NULL PRIMARY KEY,
+    mime_type         TEXT      NOT NULL,
+    upload_length     BIGINT    NOT NULL,
+    upload_offset     BIGINT    NOT NULL,
+    updated_at        BIGINT    NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS blob_upload_chunks (
+    session_id        TEXT      NOT NULL,
+    chunk_offset      BIGINT    NOT NULL,
+    data              TEXT      NOT NULL,
+    PRIMARY KEY (session_id, chunk_offset),
+    FOREIGN KEY(session_id) REFERENCES blob_upload_sessions(session_id) ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS unique_index (
+    schema_id         TEXT      NOT NULL,
+    constraint_name   TEXT      NOT NULL,
+    value             TEXT      NOT NULL,
+    document_id       TEXT      NOT NULL,
+    PRIMARY KEY (schema_id, constraint_name, document_id),
+    FOREIGN KEY(document_id) REFERENCES documents(document_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_unique_index_value ON unique_index (schema_id, constraint_name, value);
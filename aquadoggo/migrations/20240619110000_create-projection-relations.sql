@@ -0,0 +1,11 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS projection_relations (
+    projection_name       TEXT      NOT NULL,
+    document_id           TEXT      NOT NULL,
+    related_document_id   TEXT      NOT NULL,
+    PRIMARY KEY (projection_name, document_id, related_document_id),
+    FOREIGN KEY(document_id) REFERENCES documents(document_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_projection_relations_related ON projection_relations (related_document_id);
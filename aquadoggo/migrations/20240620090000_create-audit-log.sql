@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    timestamp         BIGINT    NOT NULL,
+    source            TEXT      NOT NULL,
+    message           TEXT      NOT NULL
+);
+
+CREATE INDEX idx_audit_log_timestamp ON audit_log (timestamp);
@@ -0,0 +1,29 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS task_failures (
+    name              TEXT      NOT NULL,
+    document_id       TEXT      NULL,
+    document_view_id  TEXT      NULL,
+    attempts          BIGINT    NOT NULL
+);
+
+CREATE UNIQUE INDEX ux_task_failures ON task_failures (
+    name,
+    COALESCE(document_id, '0'),
+    COALESCE(document_view_id, '0')
+);
+
+CREATE TABLE IF NOT EXISTS dead_letter_tasks (
+    name              TEXT      NOT NULL,
+    document_id       TEXT      NULL,
+    document_view_id  TEXT      NULL,
+    attempts          BIGINT    NOT NULL,
+    error             TEXT      NOT NULL,
+    failed_at         BIGINT    NOT NULL
+);
+
+CREATE UNIQUE INDEX ux_dead_letter_tasks ON dead_letter_tasks (
+    name,
+    COALESCE(document_id, '0'),
+    COALESCE(document_view_id, '0')
+);
@@ -0,0 +1,6 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS blob_hashes (
+    blob_view_id      TEXT      NOT NULL PRIMARY KEY,
+    hash              TEXT      NOT NULL
+);
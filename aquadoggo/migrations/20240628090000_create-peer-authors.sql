@@ -0,0 +1,12 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS peer_authors (
+    peer_id           TEXT      NOT NULL,
+    public_key        TEXT      NOT NULL,
+    first_seen        BIGINT    NOT NULL,
+    last_seen         BIGINT    NOT NULL,
+    entries           BIGINT    NOT NULL,
+    PRIMARY KEY (peer_id, public_key)
+);
+
+CREATE INDEX idx_peer_authors_public_key ON peer_authors (public_key);
@@ -0,0 +1,7 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS allowed_operations (
+    kind              TEXT      NOT NULL,
+    value             TEXT      NOT NULL,
+    PRIMARY KEY (kind, value)
+);
@@ -0,0 +1,3 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE INDEX idx_operations_v1_public_key ON operations_v1 (public_key, schema_id, action);
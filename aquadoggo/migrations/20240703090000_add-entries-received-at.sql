@@ -0,0 +1,5 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- UNIX timestamp in seconds of when the node stored the entry, entries stored before this column
+-- existed do not have a value
+ALTER TABLE entries ADD COLUMN received_at BIGINT NULL;
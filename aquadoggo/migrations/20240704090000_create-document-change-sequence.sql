@@ -0,0 +1,15 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- Single row holding the last assigned change sequence number. Updating it locks the row until the
+-- transaction commits, this way sequence numbers become visible in increasing order
+CREATE TABLE IF NOT EXISTS document_change_sequence (
+    id                TEXT      NOT NULL,
+    value             BIGINT    NOT NULL,
+    PRIMARY KEY (id)
+);
+
+INSERT INTO document_change_sequence (id, value) VALUES ('documents', 0);
+
+ALTER TABLE documents ADD COLUMN change_seq BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_documents_change_seq ON documents (schema_id, change_seq);
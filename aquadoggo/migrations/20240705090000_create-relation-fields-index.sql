@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+-- Look up relation fields by the document or view they point at, for resolving reverse relations.
+-- Only relation values are indexed, other values can be arbitrarily long
+CREATE INDEX idx_operation_fields_v1_relations ON operation_fields_v1 (value)
+    WHERE field_type IN ('relation', 'relation_list', 'pinned_relation', 'pinned_relation_list');
+
+-- Find the document views an operation field value is part of
+CREATE INDEX idx_document_view_fields_operation ON document_view_fields (operation_id, name);
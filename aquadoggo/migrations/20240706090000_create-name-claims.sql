@@ -0,0 +1,12 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS name_claims (
+    document_id   TEXT      NOT NULL PRIMARY KEY,
+    name          TEXT      NOT NULL,
+    public_key    TEXT      NOT NULL,
+    FOREIGN KEY(document_id) REFERENCES documents(document_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_name_claims_name ON name_claims (name, document_id);
+
+CREATE INDEX idx_name_claims_public_key ON name_claims (public_key);
@@ -0,0 +1,19 @@
+-- SPDX-License-Identifier: AGPL-3.0-or-later
+
+CREATE TABLE IF NOT EXISTS aggregate_contributions (
+    aggregate_name    TEXT                NOT NULL,
+    document_id       TEXT                NOT NULL,
+    group_key         TEXT                NOT NULL,
+    field             TEXT                NOT NULL,
+    value             DOUBLE PRECISION    NOT NULL,
+    PRIMARY KEY (aggregate_name, document_id, field)
+);
+
+CREATE TABLE IF NOT EXISTS aggregate_totals (
+    aggregate_name    TEXT                NOT NULL,
+    group_key         TEXT                NOT NULL,
+    field             TEXT                NOT NULL,
+    count             BIGINT              NOT NULL,
+    sum               DOUBLE PRECISION    NOT NULL,
+    PRIMARY KEY (aggregate_name, group_key, field)
+);
@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::time::Duration;
+
+use log::info;
+
+use crate::admin::AdminError;
+use crate::db::types::AdminQueryResult;
+use crate::db::SqlStore;
+use crate::utils::now;
+
+/// Maximum number of rows returned for one query.
+const ROW_LIMIT: u64 = 1000;
+
+/// Time after which running queries get cancelled.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Source of admin console entries in the audit log.
+const AUDIT_LOG_SOURCE: &str = "admin_console";
+
+/// Executes read-only SQL queries sent by operators.
+#[derive(Debug, Clone)]
+pub struct AdminConsole {
+    store: SqlStore,
+}
+
+impl AdminConsole {
+    /// Returns a new instance of `AdminConsole`.
+    pub fn new(store: SqlStore) -> Self {
+        Self { store }
+    }
+
+    /// Runs a single `SELECT` statement against the database.
+    ///
+    /// Every statement gets written to the audit log before it is checked and executed, this
+    /// includes rejected ones.
+    pub async fn execute(&self, statement: &str) -> Result<AdminQueryResult, AdminError> {
+        let statement = statement.trim();
+
+        info!("Admin console received statement: {}", statement);
+        self.store
+            .insert_audit_log_entry(AUDIT_LOG_SOURCE, statement, now())
+            .await?;
+
+        let statement = statement.strip_suffix(';').unwrap_or(statement);
+        if !is_select(statement) {
+            return Err(AdminError::NotReadOnly);
+        }
+
+        tokio::time::timeout(
+            QUERY_TIMEOUT,
+            self.store
+                .execute_read_only_query(statement, ROW_LIMIT, QUERY_TIMEOUT),
+        )
+        .await
+        .map_err(|_| AdminError::Timeout(QUERY_TIMEOUT))?
+        .map_err(AdminError::Store)
+    }
+}
+
+/// Returns `true` if the statement is a single `SELECT` query.
+///
+/// This is only a first line of defence, the database is responsible for rejecting anything
+/// else as the statement gets wrapped into a sub-query.
+fn is_select(statement: &str) -> bool {
+    let keyword = statement
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+
+    (keyword == "SELECT" || keyword == "WITH") && !statement.contains(';')
+}
+
+/// Formats the result of a query as text sent back to the client.
+pub fn format_result(result: &AdminQueryResult) -> String {
+    let mut output = String::new();
+
+    if !result.columns.is_empty() {
+        output.push_str(&result.columns.join("\t"));
+        output.push('\n');
+    }
+
+    for row in &result.rows {
+        let values: Vec<&str> = row
+            .iter()
+            .map(|value| value.as_deref().unwrap_or("NULL"))
+            .collect();
+        output.push_str(&values.join("\t"));
+        output.push('\n');
+    }
+
+    output.push_str(&format!("-- {} rows", result.rows.len()));
+    if result.truncated {
+        output.push_str(&format!(" (truncated at {} rows)", ROW_LIMIT));
+    }
+    output.push('\n');
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::admin::AdminError;
+    use crate::test_utils::{test_runner, TestNode};
+
+    use super::{is_select, AdminConsole};
+
+    #[rstest]
+    #[case("SELECT * FROM documents", true)]
+    #[case("  with a AS (SELECT 1) SELECT * FROM a", true)]
+    #[case("DELETE FROM documents", false)]
+    #[case("SELECT 1; DELETE FROM documents", false)]
+    #[case("", false)]
+    fn select_statements(#[case] statement: &str, #[case] expected: bool) {
+        assert_eq!(is_select(statement), expected);
+    }
+
+    #[test]
+    fn audit_statements() {
+        test_runner(|node: TestNode| async move {
+            let console = AdminConsole::new(node.context.store.clone());
+
+            assert!(matches!(
+                console.execute("DROP TABLE documents").await,
+                Err(AdminError::NotReadOnly)
+            ));
+
+            let result = console
+                .execute("SELECT message FROM audit_log ORDER BY message;")
+                .await
+                .unwrap();
+
+            assert_eq!(
+                result.rows,
+                vec![
+                    vec![Some("DROP TABLE documents".to_string())],
+                    vec![Some(
+                        "SELECT message FROM audit_log ORDER BY message;".to_string()
+                    )],
+                ]
+            );
+        });
+    }
+}
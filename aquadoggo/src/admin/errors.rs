@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::db::errors::SqlStoreError;
+
+/// Errors returned from `AdminConsole` methods.
+#[derive(Error, Debug)]
+pub enum AdminError {
+    /// Statement is not a single `SELECT` query.
+    #[error("Only single SELECT statements are allowed")]
+    NotReadOnly,
+
+    /// Query did not finish in time.
+    #[error("Query did not finish within {0:?}")]
+    Timeout(Duration),
+
+    /// Error returned from the database.
+    #[error(transparent)]
+    Store(#[from] SqlStoreError),
+}
@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Admin console for operators, served on a unix socket.
+//!
+//! The console accepts read-only SQL queries against the node's database, which helps debugging
+//! data issues without stopping the node or locating the database. Every received statement is
+//! written to the audit log.
+//!
+//! Clients send one statement per line, for example with `socat - UNIX-CONNECT:<path>`. The node
+//! answers with a tab-separated header line, one tab-separated line per row and a closing line
+//! starting with `--` which contains the number of returned rows. Errors are answered with a
+//! single line starting with `ERROR:`.
+mod console;
+mod errors;
+mod service;
+
+pub use console::AdminConsole;
+pub use errors::AdminError;
+pub use service::admin_service;
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::admin::console::format_result;
+use crate::admin::AdminConsole;
+use crate::bus::ServiceSender;
+use crate::context::Context;
+use crate::info_or_print;
+use crate::manager::{ServiceReadySender, Shutdown};
+
+/// Mask of file permissions removed when creating the socket, only the owner can read and write.
+const SOCKET_UMASK: libc::mode_t = 0o177;
+
+/// Serves the admin console on the configured unix socket.
+///
+/// The socket file is only accessible by the user running the node.
+pub async fn admin_service(
+    context: Context,
+    shutdown: Shutdown,
+    _tx: ServiceSender,
+    tx_ready: ServiceReadySender,
+) -> Result<()> {
+    let socket_path = context
+        .config
+        .admin_socket_path
+        .clone()
+        .ok_or_else(|| anyhow!("No admin socket path configured"))?;
+
+    let listener = bind(&socket_path)?;
+    let console = AdminConsole::new(context.store.clone());
+
+    info_or_print(&format!(
+        "Admin console listening on {}",
+        socket_path.display()
+    ));
+
+    let handle = tokio::task::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let console = console.clone();
+                    tokio::task::spawn(async move {
+                        if let Err(err) = handle_connection(console, stream).await {
+                            warn!("Admin console connection failed: {}", err);
+                        }
+                    });
+                }
+                Err(err) => warn!("Failed accepting admin console connection: {}", err),
+            }
+        }
+    });
+
+    debug!("Admin service is ready");
+    if tx_ready.send(()).is_err() {
+        warn!("No subscriber informed about admin service being ready");
+    };
+
+    shutdown.await.ok();
+    handle.abort();
+
+    // Clean up socket file after we're done
+    let _ = std::fs::remove_file(&socket_path);
+
+    Ok(())
+}
+
+/// Binds a unix socket to the given path, replacing any stale socket file.
+///
+/// The socket file gets created under a restrictive umask, this way it is never accessible by
+/// other users, not even before its permissions could be changed.
+fn bind(socket_path: &Path) -> Result<UnixListener> {
+    if socket_path.exists() {
+        std::fs::remove_file(socket_path)?;
+    }
+
+    // The umask applies to the whole process, files created by other threads in the meantime
+    // only get more restrictive permissions
+    let previous_umask = set_umask(SOCKET_UMASK);
+    let listener = UnixListener::bind(socket_path);
+    set_umask(previous_umask);
+
+    Ok(listener?)
+}
+
+/// Sets the file mode creation mask of the process and returns the previous mask.
+#[allow(unsafe_code)]
+fn set_umask(mask: libc::mode_t) -> libc::mode_t {
+    // SAFETY: `umask` always succeeds and does not access any memory of the process
+    unsafe { libc::umask(mask) }
+}
+
+/// Answers all statements sent over one connection, one statement per line.
+async fn handle_connection(console: AdminConsole, stream: UnixStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match console.execute(&line).await {
+            Ok(result) => format_result(&result),
+            Err(err) => format!("ERROR: {}\n", err),
+        };
+
+        writer.write_all(response.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use p2panda_rs::identity::KeyPair;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+    use tokio::sync::{broadcast, oneshot};
+
+    use crate::context::Context;
+    use crate::test_utils::{test_runner, TestNode};
+
+    use super::admin_service;
+
+    #[test]
+    fn query_over_socket() {
+        test_runner(|node: TestNode| async move {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let socket_path = temp_dir.path().join("admin.sock");
+
+            let mut config = node.context.config.clone();
+            config.admin_socket_path = Some(socket_path.clone());
+            let context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let (tx, _rx) = broadcast::channel(16);
+            let (tx_ready, rx_ready) = oneshot::channel();
+            let shutdown = tokio::task::spawn(futures::future::pending::<()>());
+
+            tokio::task::spawn(admin_service(context, shutdown, tx, tx_ready));
+            rx_ready.await.unwrap();
+
+            // Only the user running the node can access the socket
+            let mode = std::fs::metadata(&socket_path)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            let stream = UnixStream::connect(&socket_path).await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            writer
+                .write_all(b"SELECT 1 AS one, 'panda' AS name\n")
+                .await
+                .unwrap();
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "one\tname");
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "1\tpanda");
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "-- 1 rows");
+
+            writer.write_all(b"DELETE FROM audit_log\n").await.unwrap();
+            assert_eq!(
+                lines.next_line().await.unwrap().unwrap(),
+                "ERROR: Only single SELECT statements are allowed"
+            );
+        });
+    }
+}
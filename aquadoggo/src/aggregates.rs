@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Incrementally maintained aggregates over the documents of a schema.
+//!
+//! Operators can define named aggregates in the node configuration which count documents and sum
+//! up numeric fields per group. Instead of computing them on every request, the materializer
+//! subtracts the previous values of a changed document from the totals of its group and adds the
+//! new ones, which keeps dashboards over high-volume schemas cheap to query.
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::operation::OperationValue;
+
+use crate::config::MaterializedAggregate;
+use crate::db::errors::SqlStoreError;
+use crate::db::models::utils::parse_value_to_string_vec;
+use crate::db::stores::document::DOCUMENTS_PAGE_SIZE;
+use crate::db::SqlStore;
+
+/// Clears all materialized aggregates and fills them with the latest views of the documents of
+/// their schemas.
+///
+/// Aggregates only contain derived data, rebuilding them on every start makes sure that they
+/// always reflect the current configuration.
+pub async fn rebuild_aggregates(
+    store: &SqlStore,
+    aggregates: &[MaterializedAggregate],
+) -> Result<(), SqlStoreError> {
+    store.clear_materialized_aggregates().await?;
+
+    for aggregate in aggregates {
+        let mut after = None;
+        loop {
+            let documents = store
+                .get_documents_by_schema_paginated(
+                    &aggregate.schema_id,
+                    DOCUMENTS_PAGE_SIZE,
+                    after.as_ref(),
+                )
+                .await?;
+
+            for document in &documents {
+                update_contribution(store, aggregate, document).await?;
+            }
+
+            match documents.last() {
+                Some(document) if documents.len() as u64 == DOCUMENTS_PAGE_SIZE => {
+                    after = Some(document.id().to_owned());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Updates all aggregates over the schema of the given document with its new view.
+pub async fn update_aggregates(
+    store: &SqlStore,
+    aggregates: &[MaterializedAggregate],
+    document: &impl AsDocument,
+) -> Result<(), SqlStoreError> {
+    for aggregate in aggregates
+        .iter()
+        .filter(|aggregate| &aggregate.schema_id == document.schema_id())
+    {
+        update_contribution(store, aggregate, document).await?;
+    }
+
+    Ok(())
+}
+
+/// Computes the group and summed values of a document and replaces its previous contribution
+/// to the aggregate.
+///
+/// Deleted documents and documents without a value for the grouping field are removed from the
+/// aggregate.
+async fn update_contribution(
+    store: &SqlStore,
+    aggregate: &MaterializedAggregate,
+    document: &impl AsDocument,
+) -> Result<(), SqlStoreError> {
+    let group_key = match (&aggregate.group_by, document.is_deleted()) {
+        (_, true) => None,
+        (None, false) => Some(String::new()),
+        (Some(group_by), false) => document.get(group_by).map(|value| {
+            parse_value_to_string_vec(value)
+                .into_iter()
+                .flatten()
+                .collect::<Vec<String>>()
+                .join(",")
+        }),
+    };
+
+    let sums: Vec<(String, f64)> = aggregate
+        .sum
+        .iter()
+        .filter_map(|field| match document.get(field)? {
+            OperationValue::Integer(value) => Some((field.to_owned(), *value as f64)),
+            OperationValue::Float(value) => Some((field.to_owned(), *value)),
+            _ => None,
+        })
+        .collect();
+
+    store
+        .update_aggregate_contribution(
+            &aggregate.name,
+            document.id(),
+            group_key
+                .as_deref()
+                .map(|group_key| (group_key, sums.as_slice())),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+
+    use crate::config::MaterializedAggregate;
+    use crate::context::Context;
+    use crate::db::types::MaterializedAggregateGroup;
+    use crate::test_utils::{
+        add_document, add_schema, delete_document, test_runner, update_document, TestNode,
+    };
+
+    use super::rebuild_aggregates;
+
+    #[rstest]
+    fn maintain_aggregate(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "readings",
+                vec![
+                    ("sensor", FieldType::String),
+                    ("temperature", FieldType::Float),
+                ],
+                &key_pair,
+            )
+            .await;
+
+            // Configure aggregate on node
+            let mut config = node.context.config.clone();
+            config.materialized_aggregates.push(MaterializedAggregate {
+                name: "temperatures".into(),
+                schema_id: schema.id().to_owned(),
+                group_by: Some("sensor".into()),
+                sum: vec!["temperature".into()],
+            });
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let mut view_ids = Vec::new();
+            for (sensor, temperature) in [("kitchen", 20.5), ("garden", 12.0), ("kitchen", 21.5)] {
+                let view_id = add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![
+                        ("sensor", sensor.into()),
+                        ("temperature", temperature.into()),
+                    ],
+                    &key_pair,
+                )
+                .await;
+                view_ids.push(view_id);
+            }
+
+            let group = |group: &str, count: u64, sum: f64| MaterializedAggregateGroup {
+                group: group.into(),
+                count,
+                sums: vec![("temperature".into(), sum)],
+            };
+            let store = node.context.store.clone();
+            assert_eq!(
+                store
+                    .get_materialized_aggregate("temperatures")
+                    .await
+                    .unwrap(),
+                vec![group("garden", 1, 12.0), group("kitchen", 2, 42.0)]
+            );
+
+            // Moving a reading to another group updates the totals of both
+            update_document(
+                &mut node,
+                schema.id(),
+                vec![("sensor", "garden".into()), ("temperature", 14.0.into())],
+                &view_ids[2],
+                &key_pair,
+            )
+            .await;
+            assert_eq!(
+                store
+                    .get_materialized_aggregate("temperatures")
+                    .await
+                    .unwrap(),
+                vec![group("garden", 2, 26.0), group("kitchen", 1, 20.5)]
+            );
+
+            // Deleted documents are removed, empty groups disappear
+            delete_document(&mut node, schema.id(), &view_ids[0], &key_pair).await;
+            assert_eq!(
+                store
+                    .get_materialized_aggregate("temperatures")
+                    .await
+                    .unwrap(),
+                vec![group("garden", 2, 26.0)]
+            );
+
+            // Rebuilding the aggregate from all documents results in the same totals
+            rebuild_aggregates(&store, &node.context.config.materialized_aggregates)
+                .await
+                .unwrap();
+            assert_eq!(
+                store
+                    .get_materialized_aggregate("temperatures")
+                    .await
+                    .unwrap(),
+                vec![group("garden", 2, 26.0)]
+            );
+        })
+    }
+}
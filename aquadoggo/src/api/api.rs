@@ -1,11 +1,17 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::collections::BTreeMap;
+
 use anyhow::{bail, Result};
 use tokio::sync::mpsc::Receiver;
 
 use crate::api::{migrate, LockFile};
+use crate::blobs::BlobCacheMetrics;
 use crate::bus::{ServiceMessage, ServiceSender};
 use crate::context::Context;
+use crate::document_hooks::DocumentHook;
+use crate::graphql::GraphQLSchemaDiff;
+use crate::materializer::TaskMetrics;
 
 /// Node events which can be interesting for clients, for example when peers connect or disconnect.
 #[derive(Debug, Clone)]
@@ -15,6 +21,10 @@ pub enum NodeEvent {
 
     /// A peer disconnected from our node.
     PeerDisconnected,
+
+    /// The GraphQL API got rebuilt after schemas changed, types which were removed or changed
+    /// might break queries of connected clients.
+    GraphQLSchemaChanged(GraphQLSchemaDiff),
 }
 
 /// Interface to interact with the node in a programmatic, "low-level" way.
@@ -54,6 +64,22 @@ impl NodeInterface {
         Ok(did_migration_happen)
     }
 
+    pub fn blob_cache_metrics(&self) -> BlobCacheMetrics {
+        self.context.blob_integrity.metrics()
+    }
+
+    pub fn register_document_hook(&self, hook: impl DocumentHook + 'static) {
+        self.context.config.document_hooks.register(hook);
+    }
+
+    pub fn materializer_metrics(&self) -> BTreeMap<String, TaskMetrics> {
+        self.context.status.materializer_metrics().snapshot()
+    }
+
+    pub async fn pending_tasks(&self) -> Result<u64> {
+        Ok(self.context.store.count_tasks().await?)
+    }
+
     pub async fn subscribe(&self) -> Receiver<NodeEvent> {
         let mut rx = self.tx.subscribe();
         let (events_tx, events_rx) = tokio::sync::mpsc::channel::<NodeEvent>(256);
@@ -67,6 +93,9 @@ impl NodeInterface {
                     Ok(ServiceMessage::PeerDisconnected(_)) => {
                         let _ = events_tx.send(NodeEvent::PeerDisconnected).await;
                     }
+                    Ok(ServiceMessage::GraphQLSchemaChanged(diff)) => {
+                        let _ = events_tx.send(NodeEvent::GraphQLSchemaChanged(diff)).await;
+                    }
                     Ok(_) => continue,
                     Err(_) => break,
                 }
@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
+use std::num::NonZeroU64;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use libp2p::{pnet::PreSharedKey, PeerId};
@@ -11,7 +14,13 @@ use p2panda_rs::schema::SchemaId;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tempfile::TempDir;
 
-use crate::{AllowList, Configuration, NetworkConfiguration, Transport};
+use crate::materializer::TASK_NAMES;
+use crate::{
+    AllowList, BlobBackendConfiguration, BlobHooks, BlobLimits, Compression, Configuration,
+    DocumentHooks, Error, MaterializedAggregate, NameClaims, NetworkConfiguration, PageSizes,
+    Projection, ProjectionField, S3Configuration, SchemaBoundLogIds, SequentialLogIds,
+    SharedLogIdPolicy, Transport, UniqueConstraint,
+};
 
 const WILDCARD: &str = "*";
 
@@ -19,14 +28,66 @@ const DEFAULT_LOG_LEVEL: &str = "off";
 
 const DEFAULT_MAX_DATABASE_CONNECTIONS: u32 = 32;
 
+const DEFAULT_QUERY_TIMEOUT: u64 = 30;
+
+const DEFAULT_MASK_ERRORS: bool = true;
+
+const DEFAULT_MAX_QUERY_DEPTH: usize = 0;
+
+const DEFAULT_MAX_QUERY_COMPLEXITY: usize = 0;
+
+const DEFAULT_PAGE_SIZE: u64 = 25;
+
+const DEFAULT_OPERATION_ALLOW_LIST: bool = false;
+
+const DEFAULT_MAX_BATCH_ENTRIES: usize = 100;
+
 const DEFAULT_HTTP_PORT: u16 = 2020;
 
 const DEFAULT_NODE_PORT: u16 = 2022;
 
 const DEFAULT_WORKER_POOL_SIZE: u32 = 16;
 
+const DEFAULT_BLOB_WORKER_POOL_SIZE: u32 = 2;
+
+const DEFAULT_MAX_TASK_ATTEMPTS: u32 = 3;
+
+const DEFAULT_TASK_RETRY_BACKOFF: u64 = 1;
+
+const DEFAULT_TASK_QUEUE_LIMIT: u64 = 10_000;
+
 const DEFAULT_MDNS: bool = true;
 
+const DEFAULT_MAX_CONNECTIONS_IN: u32 = 16;
+
+const DEFAULT_MAX_CONNECTIONS_OUT: u32 = 16;
+
+const DEFAULT_MAX_CONNECTIONS_PENDING_IN: u32 = 8;
+
+const DEFAULT_MAX_CONNECTIONS_PENDING_OUT: u32 = 8;
+
+const DEFAULT_MAX_CONNECTIONS_PER_PEER: u32 = 2;
+
+const DEFAULT_DIAL_CONCURRENCY_FACTOR: u8 = 8;
+
+const DEFAULT_BLOBS_BACKEND: &str = "filesystem";
+
+const DEFAULT_BLOBS_S3_REGION: &str = "us-east-1";
+
+const DEFAULT_LOG_ID_POLICY: &str = "sequential";
+
+const DEFAULT_REPLICATION_COMPRESSION: &str = "none";
+
+const DEFAULT_REPLICATION_WARMUP: u64 = 0;
+
+const DEFAULT_REPLICATION_SHALLOW: bool = false;
+
+const DEFAULT_REPLICATION_DRY_RUN: bool = false;
+
+const DEFAULT_IDLE_TIMEOUT: u64 = 0;
+
+const DEFAULT_BLOB_CACHE_SIZE_BYTES: u64 = 16 * 1024 * 1024;
+
 static TMP_DIR: OnceLock<TempDir> = OnceLock::new();
 
 fn default_log_level() -> String {
@@ -37,6 +98,62 @@ fn default_max_database_connections() -> u32 {
     DEFAULT_MAX_DATABASE_CONNECTIONS
 }
 
+fn default_query_timeout() -> u64 {
+    DEFAULT_QUERY_TIMEOUT
+}
+
+fn default_mask_errors() -> bool {
+    DEFAULT_MASK_ERRORS
+}
+
+fn default_max_query_depth() -> usize {
+    DEFAULT_MAX_QUERY_DEPTH
+}
+
+fn default_max_query_complexity() -> usize {
+    DEFAULT_MAX_QUERY_COMPLEXITY
+}
+
+fn default_page_size() -> u64 {
+    DEFAULT_PAGE_SIZE
+}
+
+fn default_operation_allow_list() -> bool {
+    DEFAULT_OPERATION_ALLOW_LIST
+}
+
+fn default_max_batch_entries() -> usize {
+    DEFAULT_MAX_BATCH_ENTRIES
+}
+
+fn default_log_id_policy() -> String {
+    DEFAULT_LOG_ID_POLICY.to_string()
+}
+
+fn default_replication_compression() -> String {
+    DEFAULT_REPLICATION_COMPRESSION.to_string()
+}
+
+fn default_replication_warmup() -> u64 {
+    DEFAULT_REPLICATION_WARMUP
+}
+
+fn default_replication_shallow() -> bool {
+    DEFAULT_REPLICATION_SHALLOW
+}
+
+fn default_replication_dry_run() -> bool {
+    DEFAULT_REPLICATION_DRY_RUN
+}
+
+fn default_idle_timeout() -> u64 {
+    DEFAULT_IDLE_TIMEOUT
+}
+
+fn default_blob_cache_size_bytes() -> u64 {
+    DEFAULT_BLOB_CACHE_SIZE_BYTES
+}
+
 fn default_http_port() -> u16 {
     DEFAULT_HTTP_PORT
 }
@@ -64,10 +181,58 @@ fn default_worker_pool_size() -> u32 {
     DEFAULT_WORKER_POOL_SIZE
 }
 
+fn default_blob_worker_pool_size() -> u32 {
+    DEFAULT_BLOB_WORKER_POOL_SIZE
+}
+
+fn default_task_queue_limit() -> u64 {
+    DEFAULT_TASK_QUEUE_LIMIT
+}
+
+fn default_max_task_attempts() -> u32 {
+    DEFAULT_MAX_TASK_ATTEMPTS
+}
+
+fn default_task_retry_backoff() -> u64 {
+    DEFAULT_TASK_RETRY_BACKOFF
+}
+
 fn default_mdns() -> bool {
     DEFAULT_MDNS
 }
 
+fn default_max_connections_in() -> u32 {
+    DEFAULT_MAX_CONNECTIONS_IN
+}
+
+fn default_max_connections_out() -> u32 {
+    DEFAULT_MAX_CONNECTIONS_OUT
+}
+
+fn default_max_connections_pending_in() -> u32 {
+    DEFAULT_MAX_CONNECTIONS_PENDING_IN
+}
+
+fn default_max_connections_pending_out() -> u32 {
+    DEFAULT_MAX_CONNECTIONS_PENDING_OUT
+}
+
+fn default_max_connections_per_peer() -> u32 {
+    DEFAULT_MAX_CONNECTIONS_PER_PEER
+}
+
+fn default_dial_concurrency_factor() -> u8 {
+    DEFAULT_DIAL_CONCURRENCY_FACTOR
+}
+
+fn default_blobs_backend() -> String {
+    DEFAULT_BLOBS_BACKEND.to_string()
+}
+
+fn default_blobs_s3_region() -> String {
+    DEFAULT_BLOBS_S3_REGION.to_string()
+}
+
 /// Node configuration which can be de/serialized from a config file.
 ///
 /// See https://github.com/p2panda/aquadoggo/blob/main/aquadoggo_cli/config.toml for example
@@ -112,6 +277,52 @@ pub struct ConfigFile {
     #[serde(default = "default_max_database_connections")]
     pub database_max_connections: u32,
 
+    /// Path of the operation journal. Disabled by default.
+    ///
+    /// Accepted entries and operations are written to this file before they get inserted into the
+    /// database and replayed from it on startup. Recommended for SQLite databases on SD cards.
+    #[serde(default)]
+    pub journal_path: Option<PathBuf>,
+
+    /// Maximum duration of a GraphQL query or SQL statement in seconds, defaults to 30. Set to 0
+    /// to disable the timeout.
+    #[serde(default = "default_query_timeout")]
+    pub query_timeout: u64,
+
+    /// Replace messages of internal errors in GraphQL responses with a generic message and an
+    /// error id, defaults to true. Disable this during development.
+    #[serde(default = "default_mask_errors")]
+    pub mask_errors: bool,
+
+    /// Maximum nesting depth of GraphQL queries, defaults to 0. Set to 0 to not limit the depth.
+    #[serde(default = "default_max_query_depth")]
+    pub max_query_depth: usize,
+
+    /// Maximum complexity of GraphQL queries, defaults to 0. Set to 0 to not limit the
+    /// complexity.
+    #[serde(default = "default_max_query_complexity")]
+    pub max_query_complexity: usize,
+
+    /// Number of documents returned by collection queries which don't specify `first`, defaults
+    /// to 25.
+    #[serde(default = "default_page_size")]
+    pub default_page_size: u64,
+
+    /// Page sizes of collection queries for documents of certain schemas, overriding
+    /// `default_page_size`. Defaults to none.
+    #[serde(default)]
+    pub page_sizes: Vec<UncheckedPageSize>,
+
+    /// Only execute GraphQL operations which were registered in advance with the `allowOperation`
+    /// mutation, defaults to false.
+    #[serde(default = "default_operation_allow_list")]
+    pub operation_allow_list: bool,
+
+    /// Maximum number of entries published at once with the `publishMany` mutation, defaults to
+    /// 100.
+    #[serde(default = "default_max_batch_entries")]
+    pub max_batch_entries: usize,
+
     /// HTTP port for client-node communication, serving the GraphQL API. Defaults to 2020.
     #[serde(default = "default_http_port")]
     pub http_port: u16,
@@ -141,6 +352,76 @@ pub struct ConfigFile {
     #[serde(default)]
     pub blobs_base_path: Option<PathBuf>,
 
+    /// Storage backend for blob files, either "filesystem" or "s3". Defaults to "filesystem".
+    ///
+    /// When set to "s3", blob files are stored in an S3-compatible object storage bucket which
+    /// needs to be configured with the `blobs_s3_*` values.
+    #[serde(default = "default_blobs_backend")]
+    pub blobs_backend: String,
+
+    /// Endpoint URL of the S3-compatible object storage, for example
+    /// "https://s3.eu-central-1.amazonaws.com".
+    #[serde(default)]
+    pub blobs_s3_endpoint: Option<String>,
+
+    /// Name of the bucket blob files are stored in.
+    #[serde(default)]
+    pub blobs_s3_bucket: Option<String>,
+
+    /// Region of the bucket. Defaults to "us-east-1".
+    #[serde(default = "default_blobs_s3_region")]
+    pub blobs_s3_region: String,
+
+    /// Access key id to authenticate with the object storage.
+    #[serde(default)]
+    pub blobs_s3_access_key_id: Option<String>,
+
+    /// Secret access key to authenticate with the object storage.
+    #[serde(default)]
+    pub blobs_s3_secret_access_key: Option<String>,
+
+    /// Public base URL of the bucket, for example a CDN origin. When set, HTTP requests for blobs
+    /// are redirected to it instead of being streamed through the node.
+    #[serde(default)]
+    pub blobs_s3_public_url: Option<String>,
+
+    /// List of origins of sites which are allowed to embed blobs served by this node, for example
+    /// "https://example.org". Defaults to "*" which allows any origin.
+    ///
+    /// Requests from other sites are rejected unless they use a signed URL which was minted via
+    /// the GraphQL API.
+    #[serde(default)]
+    pub blobs_allowed_origins: UncheckedAllowList,
+
+    /// Log every HTTP request for a blob together with its origin. Disabled by default.
+    #[serde(default)]
+    pub blobs_access_log: bool,
+
+    /// Verify assembled blob files against the hash of their pieces before serving them for the
+    /// first time. Missing or corrupted files get assembled again. Disabled by default.
+    #[serde(default)]
+    pub verify_blobs: bool,
+
+    /// Maximum number of pieces of a blob, defaults to 0. Set to 0 to not limit the number of
+    /// pieces.
+    #[serde(default)]
+    pub blob_max_pieces: usize,
+
+    /// Maximum size of a blob in bytes, defaults to 0. Set to 0 to not limit the size.
+    #[serde(default)]
+    pub blob_max_size_bytes: u64,
+
+    /// Maximum total size in bytes of recently assembled blobs kept in memory, defaults to 16MiB.
+    /// Set to 0 to disable the cache.
+    #[serde(default = "default_blob_cache_size_bytes")]
+    pub blob_cache_size_bytes: u64,
+
+    /// Thumbnail sizes generated for PNG and JPEG blobs, mapping their names to the maximum width
+    /// and height in pixels, for example `{ small = 128 }`. Thumbnails are served under
+    /// `/blobs/<document_id>?size=<name>`. Requires the "thumbnails" feature, defaults to none.
+    #[serde(default)]
+    pub thumbnail_sizes: BTreeMap<String, u32>,
+
     /// Path to persist your ed25519 private key file. Defaults to an ephemeral key only for this
     /// current session.
     ///
@@ -198,6 +479,19 @@ pub struct ConfigFile {
     #[serde(default)]
     pub block_peer_ids: Vec<PeerId>,
 
+    /// List of trusted peers forming a federation group with your node.
+    ///
+    /// Nodes of the same federation group automatically share their schema allow-lists and the
+    /// addresses of the peers they know about with each other. Schema ids allowed by any member
+    /// get added to the allow-list of your node, this way a group of nodes can be kept in sync
+    /// without configuring every one of them by hand.
+    ///
+    /// Members are identified by their peer id, which gets authenticated during the handshake of
+    /// every connection. Federation peers are always allowed to connect to your node, even when
+    /// they are not contained in the allow list of peers.
+    #[serde(default)]
+    pub federation_peer_ids: Vec<PeerId>,
+
     /// List of relay addresses.
     ///
     /// A relay helps discover other nodes on the internet (also known as "rendesvouz" or
@@ -219,9 +513,159 @@ pub struct ConfigFile {
     #[serde(default)]
     pub relay_mode: bool,
 
+    /// Maximum number of established incoming connections, defaults to 16.
+    #[serde(default = "default_max_connections_in")]
+    pub max_connections_in: u32,
+
+    /// Maximum number of established outgoing connections, defaults to 16.
+    #[serde(default = "default_max_connections_out")]
+    pub max_connections_out: u32,
+
+    /// Maximum number of incoming connections which are still being negotiated, defaults to 8.
+    #[serde(default = "default_max_connections_pending_in")]
+    pub max_connections_pending_in: u32,
+
+    /// Maximum number of outgoing dials which did not succeed yet, defaults to 8.
+    ///
+    /// Further peers discovered in the meantime are not dialed until a slot becomes free again.
+    #[serde(default = "default_max_connections_pending_out")]
+    pub max_connections_pending_out: u32,
+
+    /// Maximum number of established connections with a single peer (incoming and outgoing),
+    /// defaults to 2.
+    #[serde(default = "default_max_connections_per_peer")]
+    pub max_connections_per_peer: u32,
+
+    /// Number of addresses which are dialed concurrently when connecting to a peer, defaults
+    /// to 8.
+    #[serde(default = "default_dial_concurrency_factor")]
+    pub dial_concurrency_factor: u8,
+
+    /// Compression algorithm for entries exchanged during replication, either "none" or
+    /// "deflate". Defaults to "none".
+    ///
+    /// Entries are only compressed when the remote peer supports the same algorithm.
+    #[serde(default = "default_replication_compression")]
+    pub replication_compression: String,
+
+    /// Duration in seconds after startup over which announcements and first replication attempts
+    /// with connected peers are spread, defaults to 0 (disabled).
+    #[serde(default = "default_replication_warmup")]
+    pub replication_warmup: u64,
+
+    /// Request only the current state of documents from other nodes during replication, defaults
+    /// to false.
+    #[serde(default = "default_replication_shallow")]
+    pub replication_shallow: bool,
+
+    /// Take part in replication sessions without persisting any received entries, defaults to
+    /// false.
+    #[serde(default = "default_replication_dry_run")]
+    pub replication_dry_run: bool,
+
+    /// List of supported schema ids which the node replicates with other peers. Defaults to
+    /// replicate all supported schemas ("*"), an empty list disables replication.
+    ///
+    /// Documents of all other supported schemas are still persisted and exposed on the GraphQL
+    /// API, but they are not announced to or requested from peers.
+    #[serde(default)]
+    pub replicate_schema_ids: UncheckedAllowList,
+
+    /// List of peers the node replicates with. Defaults to replicate with any peer ("*").
+    ///
+    /// Other peers can still connect to the node, but the node neither announces itself to them
+    /// nor accepts replication sessions from them. Use `allow_peer_ids` to reject their
+    /// connections right away.
+    #[serde(default)]
+    pub replication_allow_peer_ids: UncheckedAllowList,
+
+    /// List of peers the node never replicates with.
+    ///
+    /// Use `block_peer_ids` to reject their connections right away.
+    #[serde(default)]
+    pub replication_block_peer_ids: Vec<PeerId>,
+
+    /// Duration in seconds without any requests or network activity after which idle database
+    /// connections get closed and in-memory caches dropped, defaults to 0 (disabled).
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout: u64,
+
     /// Worker pool size, defaults to 16.
     #[serde(default = "default_worker_pool_size")]
     pub worker_pool_size: u32,
+
+    /// Maximum number of blobs which are materialized at the same time, defaults to 2.
+    #[serde(default = "default_blob_worker_pool_size")]
+    pub blob_worker_pool_size: u32,
+
+    /// Number of workers of certain task types, for example `{ reduce = 32 }`, overriding
+    /// `worker_pool_size` and `blob_worker_pool_size`. Defaults to none.
+    #[serde(default)]
+    pub worker_pool_sizes: BTreeMap<String, u32>,
+
+    /// Priorities of certain task types, for example `{ blob = 1 }`. Tasks with a higher priority
+    /// are started first. Defaults to 2 for "schema", 1 for "reduce" and "dependency" and 0 for all
+    /// other tasks.
+    #[serde(default)]
+    pub task_priorities: BTreeMap<String, u8>,
+
+    /// Maximum number of tasks waiting to be processed before publishing and replicating new
+    /// entries pauses, defaults to 10000. Setting this to 0 disables the limit.
+    #[serde(default = "default_task_queue_limit")]
+    pub task_queue_limit: u64,
+
+    /// Number of attempts after which a failing task is moved into the dead-letter queue,
+    /// defaults to 3. Setting this to 0 disables the dead-letter queue.
+    #[serde(default = "default_max_task_attempts")]
+    pub max_task_attempts: u32,
+
+    /// Seconds to wait before retrying a failed task, doubling with every further attempt up to
+    /// one minute. Defaults to 1.
+    #[serde(default = "default_task_retry_backoff")]
+    pub task_retry_backoff: u64,
+
+    /// Fields which need to be unique across all documents of a schema, defaults to none.
+    ///
+    /// Documents sharing the same values for these fields are flagged as conflicting, the one
+    /// with the lowest document id wins.
+    #[serde(default)]
+    pub unique_constraints: Vec<UncheckedUniqueConstraint>,
+
+    /// Aggregates over the documents of a schema which are maintained by the node, defaults to
+    /// none.
+    #[serde(default)]
+    pub materialized_aggregates: Vec<UncheckedMaterializedAggregate>,
+
+    /// Schema and field of documents in which authors claim human-readable names, disabled by
+    /// default.
+    #[serde(default)]
+    pub name_claims: Option<UncheckedNameClaims>,
+
+    /// Named projections of documents which are maintained by the node, defaults to none.
+    #[serde(default)]
+    pub projections: Vec<UncheckedProjection>,
+
+    /// Policy allocating log ids for new documents, either "sequential" or "schema_bound".
+    /// Defaults to "sequential".
+    ///
+    /// When set to "schema_bound", every schema gets its own range of log ids, derived from the
+    /// schema id.
+    #[serde(default = "default_log_id_policy")]
+    pub log_id_policy: String,
+
+    /// Path of the unix socket serving the admin console. Disabled by default.
+    ///
+    /// The admin console accepts read-only SQL queries against the database for debugging
+    /// purposes. All received statements are written to the audit log.
+    #[serde(default)]
+    pub admin_socket_path: Option<PathBuf>,
+
+    /// Token authenticating admin requests to the GraphQL API. Disabled by default.
+    ///
+    /// Requests need to send it in an `Authorization: Bearer <token>` header to run admin
+    /// mutations, for example `purgeDocument`.
+    #[serde(default)]
+    pub admin_token: Option<String>,
 }
 
 impl Default for ConfigFile {
@@ -233,110 +677,565 @@ impl Default for ConfigFile {
             allow_schema_ids: UncheckedAllowList::default(),
             database_url: default_database_url(),
             database_max_connections: default_max_database_connections(),
+            journal_path: None,
+            query_timeout: default_query_timeout(),
+            mask_errors: default_mask_errors(),
+            max_query_depth: default_max_query_depth(),
+            max_query_complexity: default_max_query_complexity(),
+            default_page_size: default_page_size(),
+            page_sizes: Vec::new(),
+            operation_allow_list: default_operation_allow_list(),
+            max_batch_entries: default_max_batch_entries(),
             http_port: default_http_port(),
             node_port: default_node_port(),
             blobs_base_path: None,
+            blobs_backend: default_blobs_backend(),
+            blobs_s3_endpoint: None,
+            blobs_s3_bucket: None,
+            blobs_s3_region: default_blobs_s3_region(),
+            blobs_s3_access_key_id: None,
+            blobs_s3_secret_access_key: None,
+            blobs_s3_public_url: None,
+            blobs_allowed_origins: UncheckedAllowList::default(),
+            blobs_access_log: false,
+            verify_blobs: false,
+            blob_max_pieces: 0,
+            blob_max_size_bytes: 0,
+            blob_cache_size_bytes: default_blob_cache_size_bytes(),
+            thumbnail_sizes: BTreeMap::new(),
             mdns: default_mdns(),
             private_key: None,
             direct_node_addresses: vec![],
             allow_peer_ids: UncheckedAllowList::default(),
             block_peer_ids: vec![],
+            federation_peer_ids: vec![],
             relay_addresses: vec![],
             relay_mode: false,
+            max_connections_in: default_max_connections_in(),
+            max_connections_out: default_max_connections_out(),
+            max_connections_pending_in: default_max_connections_pending_in(),
+            max_connections_pending_out: default_max_connections_pending_out(),
+            max_connections_per_peer: default_max_connections_per_peer(),
+            dial_concurrency_factor: default_dial_concurrency_factor(),
+            replication_compression: default_replication_compression(),
+            replication_warmup: default_replication_warmup(),
+            replication_shallow: default_replication_shallow(),
+            replication_dry_run: default_replication_dry_run(),
+            replicate_schema_ids: UncheckedAllowList::default(),
+            replication_allow_peer_ids: UncheckedAllowList::default(),
+            replication_block_peer_ids: vec![],
+            idle_timeout: default_idle_timeout(),
             worker_pool_size: default_worker_pool_size(),
+            blob_worker_pool_size: default_blob_worker_pool_size(),
+            worker_pool_sizes: BTreeMap::new(),
+            task_priorities: BTreeMap::new(),
+            task_queue_limit: default_task_queue_limit(),
+            max_task_attempts: default_max_task_attempts(),
+            task_retry_backoff: default_task_retry_backoff(),
+            unique_constraints: Vec::new(),
+            materialized_aggregates: Vec::new(),
+            name_claims: None,
+            projections: Vec::new(),
+            log_id_policy: default_log_id_policy(),
+            admin_socket_path: None,
+            admin_token: None,
         }
     }
 }
 
 impl TryFrom<ConfigFile> for Configuration {
-    type Error = anyhow::Error;
+    type Error = Error;
 
     fn try_from(value: ConfigFile) -> Result<Self, Self::Error> {
-        // Check if given schema ids are valid
-        let allow_schema_ids = match value.allow_schema_ids {
-            UncheckedAllowList::Wildcard => AllowList::<SchemaId>::Wildcard,
-            UncheckedAllowList::Set(str_values) => {
-                let schema_ids: Result<Vec<SchemaId>, anyhow::Error> = str_values
-                    .iter()
-                    .map(|str_value| {
-                        SchemaId::from_str(str_value).map_err(|_| {
-                            anyhow!(
-                                "Invalid schema id '{str_value}' found in 'allow_schema_ids' list"
-                            )
-                        })
+        parse_config_file(value).map_err(|err| Error::config("Invalid configuration", err))
+    }
+}
+
+/// Checks all values of the config file and converts them into the node configuration.
+fn parse_config_file(value: ConfigFile) -> Result<Configuration> {
+    // Check if given schema ids are valid
+    let allow_schema_ids = match value.allow_schema_ids {
+        UncheckedAllowList::Wildcard => AllowList::<SchemaId>::Wildcard,
+        UncheckedAllowList::Set(str_values) => {
+            let schema_ids: Result<Vec<SchemaId>, anyhow::Error> = str_values
+                .iter()
+                .map(|str_value| {
+                    SchemaId::from_str(str_value).map_err(|_| {
+                        anyhow!("Invalid schema id '{str_value}' found in 'allow_schema_ids' list")
                     })
-                    .collect();
+                })
+                .collect();
 
-                AllowList::Set(schema_ids?)
-            }
-        };
-
-        // Check if given peer ids are valid
-        let allow_peer_ids = match value.allow_peer_ids {
-            UncheckedAllowList::Wildcard => AllowList::<PeerId>::Wildcard,
-            UncheckedAllowList::Set(str_values) => {
-                let peer_ids: Result<Vec<PeerId>, anyhow::Error> = str_values
-                    .iter()
-                    .map(|str_value| {
-                        PeerId::from_str(str_value).map_err(|_| {
-                            anyhow!("Invalid peer id '{str_value}' found in 'allow_peer_ids' list")
-                        })
+            AllowList::Set(schema_ids?)
+        }
+    };
+
+    // Check if given schema ids to replicate are valid
+    let replicate_schema_ids = match value.replicate_schema_ids {
+        UncheckedAllowList::Wildcard => AllowList::<SchemaId>::Wildcard,
+        UncheckedAllowList::Set(str_values) => {
+            let schema_ids: Result<Vec<SchemaId>, anyhow::Error> = str_values
+                .iter()
+                .map(|str_value| {
+                    SchemaId::from_str(str_value).map_err(|_| {
+                        anyhow!(
+                            "Invalid schema id '{str_value}' found in 'replicate_schema_ids' list"
+                        )
+                    })
+                })
+                .collect();
+
+            AllowList::Set(schema_ids?)
+        }
+    };
+
+    // Check if given peer ids are valid
+    let allow_peer_ids = match value.allow_peer_ids {
+        UncheckedAllowList::Wildcard => AllowList::<PeerId>::Wildcard,
+        UncheckedAllowList::Set(str_values) => {
+            let peer_ids: Result<Vec<PeerId>, anyhow::Error> = str_values
+                .iter()
+                .map(|str_value| {
+                    PeerId::from_str(str_value).map_err(|_| {
+                        anyhow!("Invalid peer id '{str_value}' found in 'allow_peer_ids' list")
                     })
-                    .collect();
+                })
+                .collect();
+
+            AllowList::Set(peer_ids?)
+        }
+    };
+
+    // Check if given peer ids to replicate with are valid
+    let replication_allow_peer_ids = match value.replication_allow_peer_ids {
+        UncheckedAllowList::Wildcard => AllowList::<PeerId>::Wildcard,
+        UncheckedAllowList::Set(str_values) => {
+            let peer_ids: Result<Vec<PeerId>, anyhow::Error> = str_values
+                .iter()
+                .map(|str_value| {
+                    PeerId::from_str(str_value).map_err(|_| {
+                        anyhow!(
+                            "Invalid peer id '{str_value}' found in 'replication_allow_peer_ids' list"
+                        )
+                    })
+                })
+                .collect();
+
+            AllowList::Set(peer_ids?)
+        }
+    };
+
+    let blobs_allowed_origins = match value.blobs_allowed_origins {
+        UncheckedAllowList::Wildcard => AllowList::<String>::Wildcard,
+        UncheckedAllowList::Set(origins) => AllowList::Set(origins),
+    };
+
+    // Create a temporary blobs directory when none was given
+    let blobs_base_path = match value.blobs_base_path {
+        Some(path) => path,
+        None => TMP_DIR
+            .get_or_init(|| {
+                // Initialise a `TempDir` instance globally to make sure it does not run out of
+                // scope and gets deleted before the end of the application runtime
+                TempDir::new().expect("Could not create temporary directory to store blobs")
+            })
+            .path()
+            .to_path_buf(),
+    };
+
+    let blob_hooks = thumbnail_hooks(&value.thumbnail_sizes)?;
+
+    // Check if worker pool sizes and priorities are given for known task types
+    check_task_names("worker_pool_sizes", value.worker_pool_sizes.keys())?;
+    check_task_names("task_priorities", value.task_priorities.keys())?;
+
+    for (task_name, pool_size) in &value.worker_pool_sizes {
+        if *pool_size == 0 {
+            return Err(anyhow!(
+                "Worker pool size of '{task_name}' tasks needs to be larger than 0"
+            ));
+        }
+    }
+
+    // Check if blob backend and its settings are valid
+    let blobs_backend = match value.blobs_backend.as_str() {
+        "filesystem" => BlobBackendConfiguration::Filesystem,
+        "s3" => {
+            let required = |value: Option<String>, key: &str| {
+                value
+                    .ok_or_else(|| anyhow!("'{key}' needs to be set when using 's3' blobs backend"))
+            };
+
+            BlobBackendConfiguration::S3(S3Configuration {
+                endpoint: required(value.blobs_s3_endpoint, "blobs_s3_endpoint")?,
+                bucket: required(value.blobs_s3_bucket, "blobs_s3_bucket")?,
+                region: value.blobs_s3_region,
+                access_key_id: required(value.blobs_s3_access_key_id, "blobs_s3_access_key_id")?,
+                secret_access_key: required(
+                    value.blobs_s3_secret_access_key,
+                    "blobs_s3_secret_access_key",
+                )?,
+                public_url: value.blobs_s3_public_url,
+            })
+        }
+        backend => {
+            return Err(anyhow!(
+                "Invalid blobs backend '{backend}', needs to be either 'filesystem' or 's3'"
+            ))
+        }
+    };
 
-                AllowList::Set(peer_ids?)
+    let log_id_policy: SharedLogIdPolicy = match value.log_id_policy.as_str() {
+        "sequential" => Arc::new(SequentialLogIds),
+        "schema_bound" => Arc::new(SchemaBoundLogIds),
+        policy => {
+            return Err(anyhow!(
+                "Invalid log id policy '{policy}', needs to be either 'sequential' or \
+                'schema_bound'"
+            ))
+        }
+    };
+
+    if value.dial_concurrency_factor == 0 {
+        return Err(anyhow!(
+            "'dial_concurrency_factor' needs to be larger than 0"
+        ));
+    }
+
+    let replication_compression = match value.replication_compression.as_str() {
+        "none" => None,
+        compression => Some(compression.parse::<Compression>().map_err(|_| {
+            anyhow!(
+                "Invalid replication compression '{compression}', needs to be either 'none' \
+                or 'deflate'"
+            )
+        })?),
+    };
+
+    // Check if given unique constraints are valid
+    let unique_constraints: Result<Vec<UniqueConstraint>, anyhow::Error> = value
+        .unique_constraints
+        .into_iter()
+        .map(|constraint| {
+            let schema_id = SchemaId::from_str(&constraint.schema_id).map_err(|_| {
+                anyhow!(
+                    "Invalid schema id '{}' found in 'unique_constraints' list",
+                    constraint.schema_id
+                )
+            })?;
+
+            if constraint.fields.is_empty() {
+                return Err(anyhow!(
+                    "Unique constraint for schema '{schema_id}' needs at least one field"
+                ));
             }
-        };
-
-        // Create a temporary blobs directory when none was given
-        let blobs_base_path = match value.blobs_base_path {
-            Some(path) => path,
-            None => TMP_DIR
-                .get_or_init(|| {
-                    // Initialise a `TempDir` instance globally to make sure it does not run out of
-                    // scope and gets deleted before the end of the application runtime
-                    TempDir::new().expect("Could not create temporary directory to store blobs")
+
+            Ok(UniqueConstraint {
+                schema_id,
+                fields: constraint.fields,
+            })
+        })
+        .collect();
+
+    let default_page_size = NonZeroU64::new(value.default_page_size)
+        .ok_or_else(|| anyhow!("'default_page_size' needs to be larger than 0"))?;
+
+    // Check if given page sizes are valid
+    let schema_page_sizes: Result<Vec<(SchemaId, NonZeroU64)>, anyhow::Error> = value
+        .page_sizes
+        .into_iter()
+        .map(|page_size| {
+            let schema_id = SchemaId::from_str(&page_size.schema_id).map_err(|_| {
+                anyhow!(
+                    "Invalid schema id '{}' found in 'page_sizes' list",
+                    page_size.schema_id
+                )
+            })?;
+
+            let size = NonZeroU64::new(page_size.page_size).ok_or_else(|| {
+                anyhow!("Page size for schema '{schema_id}' needs to be larger than 0")
+            })?;
+
+            Ok((schema_id, size))
+        })
+        .collect();
+
+    // Check if given materialized aggregates are valid
+    let mut materialized_aggregates: Vec<MaterializedAggregate> = Vec::new();
+    for aggregate in value.materialized_aggregates {
+        let schema_id = SchemaId::from_str(&aggregate.schema_id).map_err(|_| {
+            anyhow!(
+                "Invalid schema id '{}' found in 'materialized_aggregates' list",
+                aggregate.schema_id
+            )
+        })?;
+
+        if materialized_aggregates
+            .iter()
+            .any(|other| other.name == aggregate.name)
+        {
+            return Err(anyhow!(
+                "Duplicate name '{}' found in 'materialized_aggregates' list",
+                aggregate.name
+            ));
+        }
+
+        materialized_aggregates.push(MaterializedAggregate {
+            name: aggregate.name,
+            schema_id,
+            group_by: aggregate.group_by,
+            sum: aggregate.sum,
+        });
+    }
+
+    let name_claims = value
+        .name_claims
+        .map(|name_claims| {
+            let schema_id = SchemaId::from_str(&name_claims.schema_id).map_err(|_| {
+                anyhow!(
+                    "Invalid schema id '{}' found in 'name_claims'",
+                    name_claims.schema_id
+                )
+            })?;
+
+            Ok::<NameClaims, anyhow::Error>(NameClaims {
+                schema_id,
+                field: name_claims.field,
+            })
+        })
+        .transpose()?;
+
+    let projections: Result<Vec<Projection>, anyhow::Error> = value
+        .projections
+        .into_iter()
+        .map(|projection| {
+            let schema_id = SchemaId::from_str(&projection.schema_id).map_err(|_| {
+                anyhow!(
+                    "Invalid schema id '{}' found in 'projections' list",
+                    projection.schema_id
+                )
+            })?;
+
+            let projection_name = projection.name;
+            let fields: Result<Vec<ProjectionField>, anyhow::Error> = projection
+                .fields
+                .into_iter()
+                .map(|(name, expression)| {
+                    let expression = expression.parse().map_err(|err| {
+                        anyhow!("Invalid field '{name}' in projection '{projection_name}': {err}")
+                    })?;
+                    Ok(ProjectionField { name, expression })
                 })
-                .path()
-                .to_path_buf(),
-        };
-
-        let relay_addresses = value.relay_addresses.into_iter().map(From::from).collect();
-        let direct_node_addresses = value
-            .direct_node_addresses
-            .into_iter()
-            .map(From::from)
-            .collect();
-
-        // `PreSharedKey` expects to parse key string from a multi-line string in the following format.
-        let psk = if let Some(psk) = value.psk {
-            let formatted_psk = format!("/key/swarm/psk/1.0.0/\n/base16/\n{}", psk);
-            Some(PreSharedKey::from_str(&formatted_psk)?)
-        } else {
-            None
-        };
-
-        Ok(Configuration {
-            allow_schema_ids,
-            database_url: value.database_url,
-            database_max_connections: value.database_max_connections,
-            http_port: value.http_port,
-            blobs_base_path,
-            worker_pool_size: value.worker_pool_size,
-            network: NetworkConfiguration {
-                transport: value.transport,
-                psk,
-                port: value.node_port,
-                mdns: value.mdns,
-                direct_node_addresses,
-                allow_peer_ids,
-                block_peer_ids: value.block_peer_ids,
-                relay_addresses,
-                relay_mode: value.relay_mode,
-                ..Default::default()
-            },
+                .collect();
+
+            Ok(Projection::new(&projection_name, schema_id, fields?)?)
         })
+        .collect();
+
+    let relay_addresses = value.relay_addresses.into_iter().map(From::from).collect();
+    let direct_node_addresses = value
+        .direct_node_addresses
+        .into_iter()
+        .map(From::from)
+        .collect();
+
+    // `PreSharedKey` expects to parse key string from a multi-line string in the following format.
+    let psk = if let Some(psk) = value.psk {
+        let formatted_psk = format!("/key/swarm/psk/1.0.0/\n/base16/\n{}", psk);
+        Some(PreSharedKey::from_str(&formatted_psk)?)
+    } else {
+        None
+    };
+
+    Ok(Configuration {
+        allow_schema_ids,
+        database_url: value.database_url,
+        database_max_connections: value.database_max_connections,
+        journal_path: value.journal_path,
+        query_timeout: match value.query_timeout {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        },
+        mask_errors: value.mask_errors,
+        max_query_depth: match value.max_query_depth {
+            0 => None,
+            depth => Some(depth),
+        },
+        max_query_complexity: match value.max_query_complexity {
+            0 => None,
+            complexity => Some(complexity),
+        },
+        page_sizes: PageSizes {
+            default: default_page_size,
+            schemas: schema_page_sizes?,
+        },
+        operation_allow_list: value.operation_allow_list,
+        max_batch_entries: value.max_batch_entries,
+        http_port: value.http_port,
+        blobs_base_path,
+        blobs_backend,
+        blob_hooks,
+        document_hooks: DocumentHooks::default(),
+        blobs_allowed_origins,
+        blobs_access_log: value.blobs_access_log,
+        verify_blobs: value.verify_blobs,
+        blob_limits: BlobLimits {
+            max_pieces: match value.blob_max_pieces {
+                0 => None,
+                max_pieces => Some(max_pieces),
+            },
+            max_size_bytes: match value.blob_max_size_bytes {
+                0 => None,
+                max_size_bytes => Some(max_size_bytes),
+            },
+        },
+        blob_cache_size_bytes: value.blob_cache_size_bytes,
+        worker_pool_size: value.worker_pool_size,
+        blob_worker_pool_size: value.blob_worker_pool_size,
+        worker_pool_sizes: value.worker_pool_sizes,
+        task_priorities: value.task_priorities,
+        task_queue_limit: value.task_queue_limit,
+        max_task_attempts: value.max_task_attempts,
+        task_retry_backoff: Duration::from_secs(value.task_retry_backoff),
+        unique_constraints: unique_constraints?,
+        materialized_aggregates,
+        name_claims,
+        projections: projections?,
+        log_id_policy,
+        admin_socket_path: value.admin_socket_path,
+        admin_token: value.admin_token,
+        replication_compression,
+        replication_warmup: Duration::from_secs(value.replication_warmup),
+        replication_shallow: value.replication_shallow,
+        replication_dry_run: value.replication_dry_run,
+        replicate_schema_ids,
+        replication_allow_peer_ids,
+        replication_block_peer_ids: value.replication_block_peer_ids,
+        idle_timeout: match value.idle_timeout {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        },
+        network: NetworkConfiguration {
+            transport: value.transport,
+            psk,
+            port: value.node_port,
+            mdns: value.mdns,
+            direct_node_addresses,
+            allow_peer_ids,
+            block_peer_ids: value.block_peer_ids,
+            federation_peer_ids: value.federation_peer_ids,
+            relay_addresses,
+            relay_mode: value.relay_mode,
+            dial_concurrency_factor: value.dial_concurrency_factor,
+            max_connections_in: value.max_connections_in,
+            max_connections_out: value.max_connections_out,
+            max_connections_pending_in: value.max_connections_pending_in,
+            max_connections_pending_out: value.max_connections_pending_out,
+            max_connections_per_peer: value.max_connections_per_peer,
+            ..Default::default()
+        },
+    })
+}
+
+/// Checks if the task types used as keys of the given setting are known.
+fn check_task_names<'a>(setting: &str, task_names: impl Iterator<Item = &'a String>) -> Result<()> {
+    for task_name in task_names {
+        if !TASK_NAMES.contains(&task_name.as_str()) {
+            return Err(anyhow!(
+                "Unknown task type '{task_name}' in '{setting}', expected one of {}",
+                TASK_NAMES.join(", ")
+            ));
+        }
     }
+
+    Ok(())
+}
+
+/// Returns blob hooks generating thumbnails of the configured sizes.
+#[cfg(feature = "thumbnails")]
+fn thumbnail_hooks(sizes: &BTreeMap<String, u32>) -> Result<BlobHooks> {
+    let mut blob_hooks = BlobHooks::default();
+
+    for (size, max_dimension) in sizes {
+        if size.is_empty()
+            || !size
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+        {
+            return Err(anyhow!("Invalid thumbnail size name '{size}'"));
+        }
+
+        if *max_dimension == 0 {
+            return Err(anyhow!("Thumbnail size '{size}' needs to be larger than 0"));
+        }
+
+        blob_hooks.register(crate::ThumbnailHook::new(size, *max_dimension));
+    }
+
+    Ok(blob_hooks)
+}
+
+/// Returns blob hooks generating thumbnails of the configured sizes.
+#[cfg(not(feature = "thumbnails"))]
+fn thumbnail_hooks(sizes: &BTreeMap<String, u32>) -> Result<BlobHooks> {
+    if !sizes.is_empty() {
+        return Err(anyhow!(
+            "Thumbnail sizes are configured but the node was built without the 'thumbnails' feature"
+        ));
+    }
+
+    Ok(BlobHooks::default())
+}
+
+/// Helper struct to deserialize a unique constraint.
+///
+/// The schema id is not checked yet and needs to be validated in a succeeding step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncheckedUniqueConstraint {
+    pub schema_id: String,
+    pub fields: Vec<String>,
+}
+
+/// Helper struct to deserialize a materialized aggregate.
+///
+/// The schema id is not checked yet and needs to be validated in a succeeding step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncheckedMaterializedAggregate {
+    pub name: String,
+    pub schema_id: String,
+    #[serde(default)]
+    pub group_by: Option<String>,
+    #[serde(default)]
+    pub sum: Vec<String>,
+}
+
+/// Helper struct to deserialize the schema and field of name claims.
+///
+/// The schema id is not checked yet and needs to be validated in a succeeding step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncheckedNameClaims {
+    pub schema_id: String,
+    pub field: String,
+}
+
+/// Helper struct to deserialize the page size of a schema.
+///
+/// The schema id is not checked yet and needs to be validated in a succeeding step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncheckedPageSize {
+    pub schema_id: String,
+    pub page_size: u64,
+}
+
+/// Helper struct to deserialize a projection.
+///
+/// Fields map the names of the projected fields to their expressions. The schema id and
+/// expressions are not checked yet and need to be validated in a succeeding step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncheckedProjection {
+    pub name: String,
+    pub schema_id: String,
+    pub fields: BTreeMap<String, String>,
 }
 
 /// Helper struct to deserialize from either a wildcard string "*" or a list of string values.
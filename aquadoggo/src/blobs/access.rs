@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use log::info;
+use p2panda_rs::identity::KeyPair;
+use sha2::{Digest, Sha256};
+
+use crate::config::{AllowList, Configuration};
+use crate::utils::now;
+
+/// Domain separator for deriving the secret of signed blob URLs from the node's private key.
+const SIGNED_URL_CONTEXT: &[u8] = b"aquadoggo-signed-blob-urls";
+
+/// Result of checking a request for a blob served via HTTP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobAccessDecision {
+    /// Request is allowed, either because it did not come from another site or the site is
+    /// allowed to embed blobs.
+    Allowed,
+
+    /// Request came with a valid signature which has not expired yet.
+    Signed,
+
+    /// Request came from a site which is not allowed to embed blobs of this node.
+    Denied,
+}
+
+impl BlobAccessDecision {
+    /// Returns `true` if the blob can be served.
+    pub fn is_allowed(&self) -> bool {
+        self != &BlobAccessDecision::Denied
+    }
+}
+
+/// Controls which requests are allowed to access blobs served via HTTP.
+///
+/// Public nodes can restrict the origins of sites embedding their blobs to protect themselves
+/// from third-party sites "hotlinking" large media at the expense of the operator's bandwidth.
+/// Requests from other origins can still be served when they use a signed URL which was minted
+/// via the GraphQL API and did not expire yet.
+#[derive(Clone)]
+pub struct BlobAccess {
+    /// Origins of sites which are allowed to embed blobs.
+    allowed_origins: AllowList<String>,
+
+    /// Log every request for a blob when enabled.
+    access_log: bool,
+
+    /// Secret used to sign blob URLs, derived from the node's private key so signatures stay valid
+    /// after a restart.
+    secret: Vec<u8>,
+}
+
+impl BlobAccess {
+    /// Returns a new instance of `BlobAccess` based on the node's configuration.
+    pub fn new(config: &Configuration, key_pair: &KeyPair) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(SIGNED_URL_CONTEXT);
+        hasher.update(key_pair.private_key().as_bytes());
+
+        Self {
+            allowed_origins: config.blobs_allowed_origins.clone(),
+            access_log: config.blobs_access_log,
+            secret: hasher.finalize().to_vec(),
+        }
+    }
+
+    /// Returns a signed version of the given blob path which is valid until the given UNIX
+    /// timestamp.
+    pub fn sign_path(&self, path: &str, expires: u64) -> String {
+        format!(
+            "{}?expires={}&signature={}",
+            path,
+            expires,
+            hex::encode(self.mac(path, expires).finalize().into_bytes())
+        )
+    }
+
+    /// Returns a signed version of the given blob path which is valid for the given duration.
+    pub fn sign_path_for(&self, path: &str, lifetime: Duration) -> String {
+        self.sign_path(path, now() + lifetime.as_secs())
+    }
+
+    /// Decides if a request for a blob is allowed.
+    ///
+    /// Requests are checked against the `Origin` header or, if not given, the origin of the
+    /// `Referer` header. Requests without any of these headers (for example coming from native
+    /// applications) or coming from the same host as the node are always allowed.
+    pub fn check(
+        &self,
+        path: &str,
+        host: Option<&str>,
+        origin: Option<&str>,
+        signature: Option<(u64, &str)>,
+    ) -> BlobAccessDecision {
+        let decision = match signature {
+            Some((expires, signature)) if self.verify(path, expires, signature) => {
+                BlobAccessDecision::Signed
+            }
+            _ => match origin.map(origin_of) {
+                Some(origin) if !self.is_allowed_origin(origin, host) => BlobAccessDecision::Denied,
+                _ => BlobAccessDecision::Allowed,
+            },
+        };
+
+        if self.access_log {
+            info!(
+                "Blob request {} from origin {}: {:?}",
+                path,
+                origin.unwrap_or("-"),
+                decision
+            );
+        }
+
+        decision
+    }
+
+    fn is_allowed_origin(&self, origin: &str, host: Option<&str>) -> bool {
+        // Requests from sites hosted by the node itself are always allowed
+        if let Some(host) = host {
+            if origin.split_once("://").map(|(_, origin_host)| origin_host) == Some(host) {
+                return true;
+            }
+        }
+
+        match &self.allowed_origins {
+            AllowList::Wildcard => true,
+            AllowList::Set(origins) => origins
+                .iter()
+                .any(|allowed| allowed.trim_end_matches('/') == origin),
+        }
+    }
+
+    fn verify(&self, path: &str, expires: u64, signature: &str) -> bool {
+        if expires < now() {
+            return false;
+        }
+
+        match hex::decode(signature) {
+            Ok(signature) => self.mac(path, expires).verify_slice(&signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    fn mac(&self, path: &str, expires: u64) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any size");
+        mac.update(path.as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+impl std::fmt::Debug for BlobAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Do not print the secret
+        f.debug_struct("BlobAccess")
+            .field("allowed_origins", &self.allowed_origins)
+            .field("access_log", &self.access_log)
+            .finish()
+    }
+}
+
+/// Returns the origin (scheme, host and port) of an `Origin` or `Referer` header value.
+fn origin_of(value: &str) -> &str {
+    match value.find("://") {
+        Some(index) => match value[index + 3..].find('/') {
+            Some(end) => &value[..index + 3 + end],
+            None => value,
+        },
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::config::{AllowList, Configuration};
+
+    use super::{now, BlobAccess, BlobAccessDecision};
+
+    fn blob_access(allowed_origins: AllowList<String>) -> BlobAccess {
+        let config = Configuration {
+            blobs_allowed_origins: allowed_origins,
+            ..Configuration::default()
+        };
+        BlobAccess::new(&config, &KeyPair::new())
+    }
+
+    #[test]
+    fn check_origins() {
+        let access = blob_access(AllowList::Set(vec!["https://panda.org/".into()]));
+        let path = "/blobs/0020abc";
+
+        assert_eq!(
+            access.check(path, None, None, None),
+            BlobAccessDecision::Allowed
+        );
+        assert_eq!(
+            access.check(path, None, Some("https://panda.org"), None),
+            BlobAccessDecision::Allowed
+        );
+        assert_eq!(
+            access.check(path, None, Some("https://panda.org/gallery?page=2"), None),
+            BlobAccessDecision::Allowed
+        );
+        assert_eq!(
+            access.check(path, None, Some("https://hotlink.com/page"), None),
+            BlobAccessDecision::Denied
+        );
+
+        // Sites hosted by the node itself are always allowed
+        assert_eq!(
+            access.check(
+                path,
+                Some("localhost:2020"),
+                Some("http://localhost:2020/graphql"),
+                None
+            ),
+            BlobAccessDecision::Allowed
+        );
+
+        // Any origin is allowed by default
+        let access = blob_access(AllowList::Wildcard);
+        assert_eq!(
+            access.check(path, None, Some("https://hotlink.com/page"), None),
+            BlobAccessDecision::Allowed
+        );
+    }
+
+    #[test]
+    fn signed_paths() {
+        let access = blob_access(AllowList::Set(vec![]));
+        let path = "/blobs/0020abc";
+        let origin = Some("https://hotlink.com");
+
+        let expires = now() + 60;
+        let signed_path = access.sign_path(path, expires);
+        let signature = signed_path.split("signature=").nth(1).unwrap();
+
+        assert_eq!(
+            access.check(path, None, origin, Some((expires, signature))),
+            BlobAccessDecision::Signed
+        );
+
+        // Signatures are only valid for the given path and expiry date
+        assert_eq!(
+            access.check("/blobs/0020def", None, origin, Some((expires, signature))),
+            BlobAccessDecision::Denied
+        );
+        assert_eq!(
+            access.check(path, None, origin, Some((expires + 1, signature))),
+            BlobAccessDecision::Denied
+        );
+
+        // Expired signatures are rejected
+        let expires = now() - 1;
+        let signed_path = access.sign_path(path, expires);
+        let signature = signed_path.split("signature=").nth(1).unwrap();
+        assert_eq!(
+            access.check(path, None, origin, Some((expires, signature))),
+            BlobAccessDecision::Denied
+        );
+    }
+}
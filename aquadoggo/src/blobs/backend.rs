@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::fmt::{Debug, Display};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::Stream;
+use p2panda_rs::document::DocumentViewId;
+
+use crate::blobs::{BlobBackendConfiguration, BlobBackendError, FilesystemBackend, S3Backend};
+use crate::config::Configuration;
+use crate::db::errors::BlobStoreError;
+
+/// Stream of blob data chunks which get written into a backend.
+pub type BlobWriter<'a> = Pin<Box<dyn Stream<Item = Result<Vec<u8>, BlobStoreError>> + Send + 'a>>;
+
+/// Stream of blob data chunks read from a backend.
+pub type BlobReader = Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>;
+
+/// Blob backend which can be shared across services.
+pub type SharedBlobBackend = Arc<dyn BlobBackend>;
+
+/// Identifier of a file in a blob storage backend.
+///
+/// Blobs are stored under their document view id, derived blobs (results of blob hooks) under the
+/// document view id of their original blob with the name of the hook appended.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlobKey(String);
+
+impl BlobKey {
+    /// Returns the key of a blob derived from the given blob view by a hook.
+    pub fn derived(view_id: &DocumentViewId, name: &str) -> Self {
+        Self(format!("{}.{}", view_id, name))
+    }
+}
+
+impl From<&DocumentViewId> for BlobKey {
+    fn from(view_id: &DocumentViewId) -> Self {
+        Self(view_id.to_string())
+    }
+}
+
+impl Display for BlobKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Storage for assembled blob files, identified by a `BlobKey`.
+#[async_trait]
+pub trait BlobBackend: Debug + Send + Sync {
+    /// Returns the size in bytes of a stored blob or `None` if it does not exist.
+    async fn size(&self, key: &BlobKey) -> Result<Option<u64>, BlobBackendError>;
+
+    /// Persist a blob by consuming the given stream of data chunk by chunk.
+    ///
+    /// The total length of the blob in bytes needs to be known upfront, this allows backends to
+    /// pass the data on without buffering it.
+    async fn put(
+        &self,
+        key: &BlobKey,
+        length: u64,
+        data: BlobWriter<'_>,
+    ) -> Result<(), BlobBackendError>;
+
+    /// Returns a stream of the blob's data or `None` if it does not exist.
+    async fn get(&self, key: &BlobKey) -> Result<Option<BlobReader>, BlobBackendError>;
+
+    /// Remove a blob from the backend.
+    ///
+    /// Returns `true` if the blob existed and was removed.
+    async fn delete(&self, key: &BlobKey) -> Result<bool, BlobBackendError>;
+
+    /// Returns an URL from where the blob can be fetched directly, bypassing the node.
+    ///
+    /// Backends returning `None` get their blobs streamed through the node's HTTP server instead.
+    fn public_url(&self, _key: &BlobKey) -> Option<String> {
+        None
+    }
+}
+
+/// Returns the blob backend selected in the node configuration.
+pub fn new_blob_backend(config: &Configuration) -> SharedBlobBackend {
+    match &config.blobs_backend {
+        BlobBackendConfiguration::Filesystem => {
+            Arc::new(FilesystemBackend::new(&config.blobs_base_path))
+        }
+        BlobBackendConfiguration::S3(s3_config) => Arc::new(S3Backend::new(s3_config)),
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// Storage backend where assembled blob files are kept and served from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum BlobBackendConfiguration {
+    /// Store blob files in a folder on the local filesystem, see `blobs_base_path`.
+    #[default]
+    Filesystem,
+
+    /// Store blob files in an S3-compatible object storage bucket.
+    S3(S3Configuration),
+}
+
+/// Connection settings for an S3-compatible object storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Configuration {
+    /// Endpoint URL of the object storage, for example "https://s3.eu-central-1.amazonaws.com".
+    ///
+    /// Buckets are always addressed in "path-style" (`<endpoint>/<bucket>/<key>`), which is
+    /// supported by most S3-compatible services like MinIO or Garage.
+    pub endpoint: String,
+
+    /// Name of the bucket blob files are stored in.
+    pub bucket: String,
+
+    /// Region of the bucket, used for signing requests.
+    pub region: String,
+
+    /// Access key id used for authenticating requests.
+    pub access_key_id: String,
+
+    /// Secret access key used for authenticating requests.
+    pub secret_access_key: String,
+
+    /// Optional public base URL under which the bucket contents can be fetched directly, for
+    /// example through a CDN.
+    ///
+    /// When set, HTTP requests for blobs are redirected to `<public_url>/<view_id>` instead of
+    /// being streamed through the node.
+    pub public_url: Option<String>,
+}
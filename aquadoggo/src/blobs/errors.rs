@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::schema::SchemaId;
+use thiserror::Error;
+
+use crate::db::errors::{BlobStoreError, SqlStoreError};
+
+/// Errors returned from `BlobBackend` methods.
+#[derive(Error, Debug)]
+pub enum BlobBackendError {
+    /// Blob data read from the database was invalid, for example because of missing pieces.
+    #[error(transparent)]
+    InvalidData(#[from] BlobStoreError),
+
+    /// Reading or writing from the filesystem failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// Request to the object storage could not be sent.
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+
+    /// Object storage responded with an unexpected status code.
+    #[error("Object storage responded with status {0}")]
+    UnexpectedStatus(u16),
+}
+
+/// Errors returned from `BlobUploads` methods.
+#[derive(Error, Debug)]
+pub enum BlobUploadError {
+    /// Upload session does not exist or was already removed.
+    #[error("Upload session not found")]
+    NotFound,
+
+    /// Chunk does not start at the offset the node expected.
+    #[error("Upload needs to continue at offset {0}")]
+    OffsetMismatch(u64),
+
+    /// Claimed blob length is invalid or was exceeded by the uploaded data.
+    #[error("Invalid upload length")]
+    InvalidLength,
+
+    /// Claimed blob length exceeds the maximum size of blobs accepted by this node.
+    #[error("Blob exceeds maximum size of {0} bytes")]
+    TooLarge(u64),
+
+    /// Blob would consist of more pieces than accepted by this node.
+    #[error("Blob exceeds maximum number of {0} pieces")]
+    TooManyPieces(usize),
+
+    /// Node does not support the system schemas required to publish blobs.
+    #[error("Schema {0} is not supported by this node")]
+    UnsupportedSchema(SchemaId),
+
+    /// Blob pieces or blob document could not be published.
+    #[error("Could not publish blob: {0}")]
+    Publish(anyhow::Error),
+
+    /// Error returned from the database.
+    #[error(transparent)]
+    Store(#[from] SqlStoreError),
+}
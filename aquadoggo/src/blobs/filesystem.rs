@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
+
+use crate::blobs::{BlobBackend, BlobBackendError, BlobKey, BlobReader, BlobWriter};
+
+/// Blob backend storing every blob as a file in a folder on the local filesystem.
+///
+/// Files are named after the document view id of the blob.
+#[derive(Debug, Clone)]
+pub struct FilesystemBackend {
+    base_path: PathBuf,
+}
+
+impl FilesystemBackend {
+    /// Returns a new filesystem backend storing blobs in the given folder.
+    pub fn new(base_path: &Path) -> Self {
+        Self {
+            base_path: base_path.to_path_buf(),
+        }
+    }
+
+    fn blob_path(&self, key: &BlobKey) -> PathBuf {
+        self.base_path.join(key.to_string())
+    }
+}
+
+#[async_trait]
+impl BlobBackend for FilesystemBackend {
+    async fn size(&self, key: &BlobKey) -> Result<Option<u64>, BlobBackendError> {
+        match fs::metadata(self.blob_path(key)).await {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn put(
+        &self,
+        key: &BlobKey,
+        _length: u64,
+        mut data: BlobWriter<'_>,
+    ) -> Result<(), BlobBackendError> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(self.blob_path(key))
+            .await?;
+
+        // Write every chunk directly to the file, this keeps the memory footprint small even for
+        // large blobs
+        while let Some(chunk) = data.next().await {
+            file.write_all(&chunk?).await?;
+        }
+
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &BlobKey) -> Result<Option<BlobReader>, BlobBackendError> {
+        match File::open(self.blob_path(key)).await {
+            Ok(file) => Ok(Some(Box::pin(ReaderStream::new(file)))),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &BlobKey) -> Result<bool, BlobBackendError> {
+        match fs::remove_file(self.blob_path(key)).await {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream, StreamExt};
+    use p2panda_rs::document::DocumentViewId;
+    use p2panda_rs::test_utils::fixtures::random_document_view_id;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    use crate::blobs::{BlobBackend, BlobKey};
+    use crate::db::errors::BlobStoreError;
+
+    use super::FilesystemBackend;
+
+    #[rstest]
+    #[tokio::test]
+    async fn put_get_and_delete_blobs(#[from(random_document_view_id)] view_id: DocumentViewId) {
+        let key = BlobKey::from(&view_id);
+        let tmp_dir = TempDir::new().unwrap();
+        let backend = FilesystemBackend::new(tmp_dir.path());
+
+        assert_eq!(backend.size(&key).await.unwrap(), None);
+        assert!(backend.get(&key).await.unwrap().is_none());
+
+        let chunks: Vec<Result<Vec<u8>, BlobStoreError>> =
+            vec![Ok(b"Hello, ".to_vec()), Ok(b"World!".to_vec())];
+        backend
+            .put(&key, 13, Box::pin(stream::iter(chunks)))
+            .await
+            .unwrap();
+
+        assert_eq!(backend.size(&key).await.unwrap(), Some(13));
+
+        let mut reader = backend.get(&key).await.unwrap().unwrap();
+        let mut data = vec![];
+        while let Some(chunk) = reader.next().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(data, b"Hello, World!");
+
+        assert!(backend.delete(&key).await.unwrap());
+        assert!(!backend.delete(&key).await.unwrap());
+        assert_eq!(backend.size(&key).await.unwrap(), None);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn invalid_data_fails_put(#[from(random_document_view_id)] view_id: DocumentViewId) {
+        let key = BlobKey::from(&view_id);
+        let tmp_dir = TempDir::new().unwrap();
+        let backend = FilesystemBackend::new(tmp_dir.path());
+
+        let chunks: Vec<Result<Vec<u8>, BlobStoreError>> =
+            vec![Ok(b"Hello".to_vec()), Err(BlobStoreError::MissingPieces)];
+        let result = backend.put(&key, 5, Box::pin(stream::iter(chunks))).await;
+
+        assert!(result.is_err());
+    }
+}
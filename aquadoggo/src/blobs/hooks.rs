@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::blobs::BlobReader;
+
+/// Result of a blob hook which gets stored as a derived blob next to the original one.
+#[derive(Debug, Clone)]
+pub struct DerivedBlob {
+    /// MIME type of the derived blob.
+    pub mime_type: String,
+
+    /// Data of the derived blob.
+    pub data: Vec<u8>,
+}
+
+/// Post-processing step for newly materialized blobs.
+///
+/// Hooks can be used to for example strip EXIF data from images, transcode videos into a
+/// web-friendly codec or generate waveforms of audio files. Their results are stored as derived
+/// blobs which are linked to the original blob view and served under
+/// `/blobs/<document_id>/<view_id>/<hook_name>`.
+#[async_trait]
+pub trait BlobHook: Debug + Send + Sync {
+    /// Name of this hook, used to address the derived blob.
+    ///
+    /// Names need to be unique and can only contain ASCII alphanumeric characters, dashes and
+    /// underscores.
+    fn name(&self) -> &str;
+
+    /// Returns `true` if this hook should run for blobs of the given MIME type.
+    fn accepts(&self, mime_type: &str) -> bool;
+
+    /// Process the data of a blob.
+    ///
+    /// Returning `None` does not store any derived blob, for example when the hook decided that
+    /// nothing needs to be done.
+    async fn process(&self, mime_type: &str, data: BlobReader) -> Result<Option<DerivedBlob>>;
+}
+
+/// Collection of blob hooks registered on a node.
+#[derive(Debug, Clone, Default)]
+pub struct BlobHooks(Vec<Arc<dyn BlobHook>>);
+
+impl BlobHooks {
+    /// Register a new blob hook.
+    ///
+    /// Panics if the name of the hook is invalid or already taken by another hook.
+    pub fn register(&mut self, hook: impl BlobHook + 'static) {
+        let name = hook.name();
+
+        assert!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_'),
+            "Invalid blob hook name '{}'",
+            name
+        );
+
+        assert!(
+            self.get(name).is_none(),
+            "Blob hook with name '{}' was already registered",
+            name
+        );
+
+        self.0.push(Arc::new(hook));
+    }
+
+    /// Returns the hook with the given name.
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn BlobHook>> {
+        self.0.iter().find(|hook| hook.name() == name)
+    }
+
+    /// Returns all hooks which accept blobs of the given MIME type.
+    pub fn for_mime_type<'a>(
+        &'a self,
+        mime_type: &'a str,
+    ) -> impl Iterator<Item = &'a Arc<dyn BlobHook>> + 'a {
+        self.0.iter().filter(move |hook| hook.accepts(mime_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use async_trait::async_trait;
+
+    use crate::blobs::BlobReader;
+
+    use super::{BlobHook, BlobHooks, DerivedBlob};
+
+    #[derive(Debug)]
+    struct TestHook(&'static str);
+
+    #[async_trait]
+    impl BlobHook for TestHook {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn accepts(&self, mime_type: &str) -> bool {
+            mime_type.starts_with("image/")
+        }
+
+        async fn process(
+            &self,
+            _mime_type: &str,
+            _data: BlobReader,
+        ) -> Result<Option<DerivedBlob>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn filter_by_mime_type() {
+        let mut hooks = BlobHooks::default();
+        hooks.register(TestHook("thumbnail"));
+
+        assert_eq!(hooks.for_mime_type("image/png").count(), 1);
+        assert_eq!(hooks.for_mime_type("text/plain").count(), 0);
+        assert!(hooks.get("thumbnail").is_some());
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_names() {
+        let mut hooks = BlobHooks::default();
+        hooks.register(TestHook("thumbnail"));
+        hooks.register(TestHook("thumbnail"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_names() {
+        let mut hooks = BlobHooks::default();
+        hooks.register(TestHook("../thumbnail"));
+    }
+}
@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::StreamExt;
+use log::{debug, warn};
+use p2panda_rs::document::DocumentViewId;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::blobs::{BlobBackendError, BlobKey, SharedBlobBackend};
+use crate::config::Configuration;
+use crate::db::errors::BlobStoreError;
+use crate::db::SqlStore;
+
+/// Counters of blob files verified before they got served via HTTP.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlobCacheMetrics {
+    /// Number of blob files which were checked against their expected hash.
+    pub verified: u64,
+
+    /// Number of blob files which were missing or did not match their expected hash.
+    pub corrupted: u64,
+
+    /// Number of corrupted blob files which were successfully assembled again from their pieces.
+    pub reassembled: u64,
+}
+
+/// Verifies assembled blob files against the hash of their pieces before they get served.
+///
+/// Every blob file is only verified once during the lifetime of the node, as hashing large files
+/// on every request would be costly. Corrupted files are assembled again from the pieces in the
+/// database.
+#[derive(Debug, Clone)]
+pub struct BlobIntegrity {
+    /// Verification is enabled in the configuration.
+    enabled: bool,
+
+    /// Blob files which have already been verified.
+    verified_keys: Arc<Mutex<HashSet<BlobKey>>>,
+
+    verified: Arc<AtomicU64>,
+    corrupted: Arc<AtomicU64>,
+    reassembled: Arc<AtomicU64>,
+}
+
+impl BlobIntegrity {
+    /// Returns a new instance of `BlobIntegrity` based on the node's configuration.
+    pub fn new(config: &Configuration) -> Self {
+        Self {
+            enabled: config.verify_blobs,
+            verified_keys: Arc::new(Mutex::new(HashSet::new())),
+            verified: Arc::new(AtomicU64::new(0)),
+            corrupted: Arc::new(AtomicU64::new(0)),
+            reassembled: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns the current state of all counters.
+    pub fn metrics(&self) -> BlobCacheMetrics {
+        BlobCacheMetrics {
+            verified: self.verified.load(Ordering::Relaxed),
+            corrupted: self.corrupted.load(Ordering::Relaxed),
+            reassembled: self.reassembled.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Forgets which blob files were already verified, they get verified again when they are
+    /// served the next time.
+    pub async fn clear_cache(&self) {
+        let mut verified_keys = self.verified_keys.lock().await;
+        verified_keys.clear();
+        verified_keys.shrink_to_fit();
+    }
+
+    /// Makes sure that the assembled file of a blob view matches the hash of its pieces.
+    ///
+    /// Blobs served directly by the storage backend, for example from a CDN, are not verified.
+    pub async fn verify(
+        &self,
+        store: &SqlStore,
+        blobs: &SharedBlobBackend,
+        view_id: &DocumentViewId,
+        length: u64,
+    ) -> Result<()> {
+        let blob_key = BlobKey::from(view_id);
+        if !self.enabled || blobs.public_url(&blob_key).is_some() {
+            return Ok(());
+        }
+
+        // Hold the lock during verification, this way corrupted files are not assembled multiple
+        // times by concurrent requests
+        let mut verified_keys = self.verified_keys.lock().await;
+        if verified_keys.contains(&blob_key) {
+            return Ok(());
+        }
+
+        // Calculate the expected hash from the pieces if it was not stored during materialization
+        let expected_hash = match store.get_blob_hash(view_id).await? {
+            Some(hash) => hash,
+            None => {
+                let hash = hash_pieces(store, view_id).await?;
+                store.insert_blob_hash(view_id, &hash).await?;
+                hash
+            }
+        };
+
+        let hash = match blobs.get(&blob_key).await? {
+            Some(mut reader) => {
+                let mut hasher = Sha256::new();
+                while let Some(chunk) = reader.next().await {
+                    hasher.update(&chunk?);
+                }
+                Some(hex::encode(hasher.finalize()))
+            }
+            None => None,
+        };
+
+        self.verified.fetch_add(1, Ordering::Relaxed);
+
+        if hash.as_ref() != Some(&expected_hash) {
+            warn!(
+                "Blob file {} is missing or corrupted, assembling it again from its pieces",
+                blob_key
+            );
+            self.corrupted.fetch_add(1, Ordering::Relaxed);
+
+            blobs.delete(&blob_key).await?;
+            let hash = assemble_blob(store, blobs, view_id, length).await?;
+            store.insert_blob_hash(view_id, &hash).await?;
+
+            self.reassembled.fetch_add(1, Ordering::Relaxed);
+        } else {
+            debug!("Verified blob file {}", blob_key);
+        }
+
+        verified_keys.insert(blob_key);
+
+        Ok(())
+    }
+}
+
+/// Assembles the pieces of a blob view into a file in the storage backend.
+///
+/// Returns the hex-encoded SHA256 hash of the blob data.
+pub async fn assemble_blob(
+    store: &SqlStore,
+    blobs: &SharedBlobBackend,
+    view_id: &DocumentViewId,
+    length: u64,
+) -> Result<String, BlobBackendError> {
+    let mut blob_stream = store
+        .get_blob_by_view_id(view_id)
+        .await?
+        .ok_or(BlobStoreError::NotBlobDocument)?;
+
+    // Calculate the hash while the data streams through, this way we only need to read the
+    // pieces once
+    let mut hasher = Sha256::new();
+    let stream = blob_stream.read_all().inspect(|chunk| {
+        if let Ok(chunk) = chunk {
+            hasher.update(chunk);
+        }
+    });
+
+    blobs
+        .put(&BlobKey::from(view_id), length, Box::pin(stream))
+        .await?;
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Calculates the hex-encoded SHA256 hash of the data of all pieces of a blob view.
+async fn hash_pieces(store: &SqlStore, view_id: &DocumentViewId) -> Result<String> {
+    let mut blob_stream = store
+        .get_blob_by_view_id(view_id)
+        .await?
+        .ok_or(BlobStoreError::NotBlobDocument)?;
+
+    let mut hasher = Sha256::new();
+    let stream = blob_stream.read_all();
+    futures::pin_mut!(stream);
+    while let Some(chunk) = stream.next().await {
+        hasher.update(chunk?);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use tokio::fs;
+
+    use crate::blobs::BlobKey;
+    use crate::config::Configuration;
+    use crate::materializer::tasks::blob_task;
+    use crate::materializer::TaskInput;
+    use crate::test_utils::{add_blob, test_runner_with_manager, TestNodeManager};
+
+    use super::{BlobCacheMetrics, BlobIntegrity};
+
+    #[rstest]
+    fn reassemble_corrupted_blob(key_pair: KeyPair) {
+        test_runner_with_manager(|manager: TestNodeManager| async move {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let config = Configuration {
+                blobs_base_path: temp_dir.path().to_path_buf(),
+                verify_blobs: true,
+                ..Configuration::default()
+            };
+            let mut node = manager.create_with_config(config).await;
+
+            let blob_data = "Hello, World!";
+            let view_id =
+                add_blob(&mut node, blob_data.as_bytes(), 5, "text/plain", &key_pair).await;
+            blob_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(view_id.clone()),
+            )
+            .await
+            .unwrap();
+
+            // Corrupt blob file in the cache
+            let blob_path = node
+                .context
+                .config
+                .blobs_base_path
+                .join(BlobKey::from(&view_id).to_string());
+            fs::write(&blob_path, "Hello, Panda!").await.unwrap();
+
+            let integrity = BlobIntegrity::new(&node.context.config);
+            integrity
+                .verify(
+                    &node.context.store,
+                    &node.context.blobs,
+                    &view_id,
+                    blob_data.len() as u64,
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(fs::read_to_string(&blob_path).await.unwrap(), blob_data);
+            assert_eq!(
+                integrity.metrics(),
+                BlobCacheMetrics {
+                    verified: 1,
+                    corrupted: 1,
+                    reassembled: 1,
+                }
+            );
+
+            // Blob files are only verified once
+            integrity
+                .verify(
+                    &node.context.store,
+                    &node.context.blobs,
+                    &view_id,
+                    blob_data.len() as u64,
+                )
+                .await
+                .unwrap();
+            assert_eq!(integrity.metrics().verified, 1);
+        });
+    }
+}
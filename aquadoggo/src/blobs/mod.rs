@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Storage backends for assembled blob files.
+//!
+//! Blob pieces are always persisted in the database, but after materialization the complete blob
+//! data gets written into a backend from where it is served via HTTP. Depending on the
+//! configuration this is either a folder on the local filesystem or an S3-compatible object
+//! storage bucket.
+//!
+//! Large blobs can also be uploaded to the node directly in resumable chunks, the node then
+//! publishes them as blob documents on behalf of the client.
+mod access;
+mod backend;
+mod config;
+mod errors;
+mod filesystem;
+mod hooks;
+mod integrity;
+mod s3;
+mod thumbnails;
+mod uploads;
+
+pub use access::BlobAccess;
+pub use backend::{
+    new_blob_backend, BlobBackend, BlobKey, BlobReader, BlobWriter, SharedBlobBackend,
+};
+pub use config::{BlobBackendConfiguration, S3Configuration};
+pub use errors::{BlobBackendError, BlobUploadError};
+pub use filesystem::FilesystemBackend;
+pub use hooks::{BlobHook, BlobHooks, DerivedBlob};
+pub use integrity::{assemble_blob, BlobCacheMetrics, BlobIntegrity};
+pub use s3::S3Backend;
+pub use thumbnails::thumbnail_name;
+#[cfg(feature = "thumbnails")]
+pub use thumbnails::ThumbnailHook;
+pub use uploads::{BlobUploadProgress, BlobUploads};
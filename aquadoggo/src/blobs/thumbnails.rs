@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// Prefix of the names of blob hooks generating thumbnails.
+const THUMBNAIL_HOOK_PREFIX: &str = "thumbnail_";
+
+/// Returns the name of the derived blob holding the thumbnail of the given size.
+///
+/// Thumbnails are served next to the original blob under `/blobs/<document_id>?size=<size>`.
+pub fn thumbnail_name(size: &str) -> String {
+    format!("{THUMBNAIL_HOOK_PREFIX}{size}")
+}
+
+#[cfg(feature = "thumbnails")]
+pub use hook::ThumbnailHook;
+
+#[cfg(feature = "thumbnails")]
+mod hook {
+    use std::io::Cursor;
+
+    use anyhow::{Context, Result};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use image::{ImageFormat, ImageOutputFormat};
+    use tokio::task;
+
+    use crate::blobs::{BlobHook, BlobReader, DerivedBlob};
+
+    use super::thumbnail_name;
+
+    /// Blob hook scaling down PNG and JPEG images to fit into a square of the given dimension.
+    ///
+    /// The thumbnail keeps the aspect ratio and format of the original image. Images which
+    /// already fit are not scaled up, no thumbnail is stored for them.
+    ///
+    /// ```rust
+    /// use aquadoggo::{Configuration, ThumbnailHook};
+    ///
+    /// let mut config = Configuration::default();
+    /// config.blob_hooks.register(ThumbnailHook::new("small", 128));
+    /// config.blob_hooks.register(ThumbnailHook::new("large", 1024));
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct ThumbnailHook {
+        /// Name of the derived blob.
+        name: String,
+
+        /// Maximum width and height of the thumbnail in pixels.
+        max_dimension: u32,
+    }
+
+    impl ThumbnailHook {
+        /// Returns a hook generating thumbnails served with the given size name.
+        pub fn new(size: &str, max_dimension: u32) -> Self {
+            Self {
+                name: thumbnail_name(size),
+                max_dimension,
+            }
+        }
+    }
+
+    /// Returns the image format of the given MIME type if we can generate thumbnails for it.
+    fn image_format(mime_type: &str) -> Option<(ImageFormat, ImageOutputFormat)> {
+        match mime_type {
+            "image/png" => Some((ImageFormat::Png, ImageOutputFormat::Png)),
+            "image/jpeg" => Some((ImageFormat::Jpeg, ImageOutputFormat::Jpeg(85))),
+            _ => None,
+        }
+    }
+
+    #[async_trait]
+    impl BlobHook for ThumbnailHook {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn accepts(&self, mime_type: &str) -> bool {
+            image_format(mime_type).is_some()
+        }
+
+        async fn process(
+            &self,
+            mime_type: &str,
+            mut data: BlobReader,
+        ) -> Result<Option<DerivedBlob>> {
+            let (input_format, output_format) =
+                image_format(mime_type).context("Unsupported image format")?;
+
+            let mut buf = vec![];
+            while let Some(chunk) = data.next().await {
+                buf.extend_from_slice(&chunk?);
+            }
+
+            // Decoding and scaling images is CPU-heavy and runs in a blocking thread
+            let max_dimension = self.max_dimension;
+            let thumbnail = task::spawn_blocking(move || -> Result<Option<Vec<u8>>> {
+                let image = image::load_from_memory_with_format(&buf, input_format)?;
+                if image.width() <= max_dimension && image.height() <= max_dimension {
+                    return Ok(None);
+                }
+
+                let mut thumbnail = Cursor::new(vec![]);
+                image
+                    .thumbnail(max_dimension, max_dimension)
+                    .write_to(&mut thumbnail, output_format)?;
+
+                Ok(Some(thumbnail.into_inner()))
+            })
+            .await??;
+
+            Ok(thumbnail.map(|data| DerivedBlob {
+                mime_type: mime_type.to_owned(),
+                data,
+            }))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::Cursor;
+
+        use bytes::Bytes;
+        use futures::stream;
+        use image::{ImageOutputFormat, RgbImage};
+
+        use crate::blobs::{BlobHook, BlobReader};
+
+        use super::ThumbnailHook;
+
+        fn png(width: u32, height: u32) -> BlobReader {
+            let mut data = Cursor::new(vec![]);
+            RgbImage::new(width, height)
+                .write_to(&mut data, ImageOutputFormat::Png)
+                .unwrap();
+            Box::pin(stream::iter(vec![Ok(Bytes::from(data.into_inner()))]))
+        }
+
+        #[tokio::test]
+        async fn scale_down_images() {
+            let hook = ThumbnailHook::new("small", 16);
+            assert_eq!(hook.name(), "thumbnail_small");
+            assert!(hook.accepts("image/jpeg"));
+            assert!(!hook.accepts("text/plain"));
+
+            let thumbnail = hook
+                .process("image/png", png(64, 32))
+                .await
+                .unwrap()
+                .expect("Thumbnail to be generated");
+            assert_eq!(thumbnail.mime_type, "image/png");
+
+            let image = image::load_from_memory(&thumbnail.data).unwrap();
+            assert_eq!((image.width(), image.height()), (16, 8));
+
+            // Small images are not scaled up
+            assert!(hook
+                .process("image/png", png(8, 8))
+                .await
+                .unwrap()
+                .is_none());
+        }
+    }
+}
@@ -0,0 +1,510 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use log::{debug, info};
+use p2panda_rs::document::DocumentViewId;
+use p2panda_rs::entry::encode::sign_and_encode_entry;
+use p2panda_rs::entry::traits::AsEncodedEntry;
+use p2panda_rs::operation::encode::encode_operation;
+use p2panda_rs::operation::plain::PlainOperation;
+use p2panda_rs::operation::{OperationBuilder, OperationValue};
+use p2panda_rs::schema::validate::MAX_BLOB_PIECE_LENGTH;
+use p2panda_rs::schema::SchemaId;
+use rand::RngCore;
+use tokio::sync::Mutex;
+
+use crate::blobs::BlobUploadError;
+use crate::bus::{ServiceMessage, ServiceSender};
+use crate::context::Context;
+use crate::db::models::BlobUploadSessionRow;
+use crate::log_ids::next_args;
+use crate::utils::now;
+
+/// Duration after which upload sessions without any new data are considered abandoned.
+const UPLOAD_SESSION_TIMEOUT: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// State of a resumable blob upload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobUploadSession {
+    /// Identifier of the upload session.
+    pub id: String,
+
+    /// MIME type of the blob.
+    pub mime_type: String,
+
+    /// Total length of the blob in bytes.
+    pub length: u64,
+
+    /// Number of bytes received so far.
+    pub offset: u64,
+}
+
+impl From<BlobUploadSessionRow> for BlobUploadSession {
+    fn from(row: BlobUploadSessionRow) -> Self {
+        Self {
+            id: row.session_id,
+            mime_type: row.mime_type,
+            length: row.upload_length as u64,
+            offset: row.upload_offset as u64,
+        }
+    }
+}
+
+/// Progress of an upload session after a chunk of data was appended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlobUploadProgress {
+    /// More data is expected, starting at the given offset.
+    InProgress(u64),
+
+    /// All data was received and the blob got published under the given document view id.
+    Completed(DocumentViewId),
+}
+
+/// Resumable blob uploads which get published by the node once they are complete.
+///
+/// Clients can upload large blobs in multiple chunks over HTTP and resume after a lost connection
+/// by continuing from the last offset the node confirmed. Received chunks are kept in the database
+/// until all data arrived, then the node splits it into blob pieces and publishes them together
+/// with the blob document, signed with the key pair of the node.
+#[derive(Debug, Clone)]
+pub struct BlobUploads {
+    context: Context,
+    tx: ServiceSender,
+
+    /// Makes sure the node only publishes one operation at a time, otherwise concurrently
+    /// completed uploads could try to write into the same log.
+    publish_lock: Arc<Mutex<()>>,
+}
+
+impl BlobUploads {
+    /// Returns a new handler for upload sessions, publishing new blobs on the given service bus.
+    pub fn new(context: Context, tx: ServiceSender) -> Self {
+        Self {
+            context,
+            tx,
+            publish_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Start a new upload session for a blob of the given length and MIME type.
+    pub async fn create(
+        &self,
+        length: u64,
+        mime_type: &str,
+    ) -> Result<BlobUploadSession, BlobUploadError> {
+        if length == 0 || length > i64::MAX as u64 {
+            return Err(BlobUploadError::InvalidLength);
+        }
+
+        // Refuse blobs which would get rejected after uploading them anyways
+        let limits = &self.context.config.blob_limits;
+        if let Some(max_size_bytes) = limits.max_size_bytes {
+            if length > max_size_bytes {
+                return Err(BlobUploadError::TooLarge(max_size_bytes));
+            }
+        }
+
+        if let Some(max_pieces) = limits.max_pieces {
+            let num_pieces = length.div_ceil(MAX_BLOB_PIECE_LENGTH as u64);
+            if num_pieces > max_pieces as u64 {
+                return Err(BlobUploadError::TooManyPieces(max_pieces));
+            }
+        }
+
+        // Refuse uploads when this node does not support blobs anyways
+        for schema_id in [SchemaId::Blob(1), SchemaId::BlobPiece(1)] {
+            if self.context.schema_provider.get(&schema_id).await.is_none() {
+                return Err(BlobUploadError::UnsupportedSchema(schema_id));
+            }
+        }
+
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+        let id = hex::encode(id);
+
+        self.context
+            .store
+            .insert_blob_upload_session(&id, mime_type, length, now())
+            .await?;
+
+        debug!("Started blob upload session {}", id);
+
+        Ok(BlobUploadSession {
+            id,
+            mime_type: mime_type.to_owned(),
+            length,
+            offset: 0,
+        })
+    }
+
+    /// Returns the current state of an upload session.
+    pub async fn get(&self, id: &str) -> Result<Option<BlobUploadSession>, BlobUploadError> {
+        let session = self.context.store.get_blob_upload_session(id).await?;
+        Ok(session.map(BlobUploadSession::from))
+    }
+
+    /// Append a chunk of data to an upload session, starting at the given offset.
+    ///
+    /// The offset needs to match the number of bytes the node received so far. The blob gets
+    /// published as soon as the last chunk arrived. Sending an empty chunk for a complete session
+    /// re-attempts publishing it, in case this failed before.
+    pub async fn append(
+        &self,
+        id: &str,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<BlobUploadProgress, BlobUploadError> {
+        let session = self.get(id).await?.ok_or(BlobUploadError::NotFound)?;
+
+        if offset != session.offset {
+            return Err(BlobUploadError::OffsetMismatch(session.offset));
+        }
+
+        if offset + data.len() as u64 > session.length {
+            return Err(BlobUploadError::InvalidLength);
+        }
+
+        if !data.is_empty() {
+            let accepted = self
+                .context
+                .store
+                .insert_blob_upload_chunk(id, offset, data, now())
+                .await?;
+
+            // Another chunk was appended concurrently
+            if !accepted {
+                let session = self.get(id).await?.ok_or(BlobUploadError::NotFound)?;
+                return Err(BlobUploadError::OffsetMismatch(session.offset));
+            }
+        }
+
+        let offset = offset + data.len() as u64;
+        if offset < session.length {
+            return Ok(BlobUploadProgress::InProgress(offset));
+        }
+
+        let view_id = self.complete(&session).await?;
+        Ok(BlobUploadProgress::Completed(view_id))
+    }
+
+    /// Cancel an upload session and remove all data received so far.
+    ///
+    /// Returns `true` if the session existed.
+    pub async fn terminate(&self, id: &str) -> Result<bool, BlobUploadError> {
+        Ok(self.context.store.delete_blob_upload_session(id).await?)
+    }
+
+    /// Remove all upload sessions which did not receive any data within the session timeout.
+    ///
+    /// Returns the number of removed sessions.
+    pub async fn purge_abandoned(&self) -> Result<usize, BlobUploadError> {
+        let deadline = now().saturating_sub(UPLOAD_SESSION_TIMEOUT.as_secs());
+        let removed = self
+            .context
+            .store
+            .delete_blob_upload_sessions_before(deadline)
+            .await?;
+
+        if !removed.is_empty() {
+            info!("Removed {} abandoned blob upload sessions", removed.len());
+        }
+
+        Ok(removed.len())
+    }
+
+    /// Split received data into blob pieces and publish them together with the blob document.
+    async fn complete(
+        &self,
+        session: &BlobUploadSession,
+    ) -> Result<DocumentViewId, BlobUploadError> {
+        let _guard = self.publish_lock.lock().await;
+
+        // Session might have been completed concurrently while we were waiting for the lock
+        if self.get(&session.id).await?.is_none() {
+            return Err(BlobUploadError::NotFound);
+        }
+
+        let mut pieces: Vec<DocumentViewId> = vec![];
+        let mut buf: Vec<u8> = Vec::with_capacity(MAX_BLOB_PIECE_LENGTH);
+        let mut offset = 0;
+
+        // Read chunks one-by-one, only keeping at most one piece in memory
+        while let Some((chunk_offset, chunk)) = self
+            .context
+            .store
+            .get_blob_upload_chunk(&session.id, offset)
+            .await?
+        {
+            offset = chunk_offset + chunk.len() as u64;
+
+            for data in chunk.chunks(MAX_BLOB_PIECE_LENGTH) {
+                let split_at = data.len().min(MAX_BLOB_PIECE_LENGTH - buf.len());
+                buf.extend_from_slice(&data[..split_at]);
+
+                if buf.len() == MAX_BLOB_PIECE_LENGTH {
+                    pieces.push(self.publish_piece(&buf).await?);
+                    buf.clear();
+                }
+
+                buf.extend_from_slice(&data[split_at..]);
+            }
+        }
+
+        if !buf.is_empty() {
+            pieces.push(self.publish_piece(&buf).await?);
+        }
+
+        let view_id = self
+            .publish(
+                SchemaId::Blob(1),
+                vec![
+                    ("length", (session.length as i64).into()),
+                    ("mime_type", session.mime_type.as_str().into()),
+                    ("pieces", pieces.into()),
+                ],
+            )
+            .await?;
+
+        self.context
+            .store
+            .delete_blob_upload_session(&session.id)
+            .await?;
+
+        info!(
+            "Published blob {} from upload session {}",
+            view_id, session.id
+        );
+
+        Ok(view_id)
+    }
+
+    async fn publish_piece(&self, data: &[u8]) -> Result<DocumentViewId, BlobUploadError> {
+        self.publish(SchemaId::BlobPiece(1), vec![("data", data.into())])
+            .await
+    }
+
+    /// Sign and publish a CREATE operation with the node's key pair and inform the materializer
+    /// about it.
+    async fn publish(
+        &self,
+        schema_id: SchemaId,
+        fields: Vec<(&str, OperationValue)>,
+    ) -> Result<DocumentViewId, BlobUploadError> {
+        let schema = self
+            .context
+            .schema_provider
+            .get(&schema_id)
+            .await
+            .ok_or_else(|| BlobUploadError::UnsupportedSchema(schema_id.clone()))?;
+
+        let result: anyhow::Result<DocumentViewId> = async {
+            let operation = OperationBuilder::new(schema.id()).fields(&fields).build()?;
+            let encoded_operation = encode_operation(&operation)?;
+
+            // Don't publish more pieces while the materializer can't keep up with them
+            self.context.status.task_backlog().wait_for_capacity().await;
+
+            let public_key = self.context.key_pair.public_key();
+            let _guard = self.context.store.lock_public_key(&public_key).await;
+
+            let (backlink, skiplink, seq_num, log_id) = next_args(
+                &self.context.store,
+                self.context.config.log_id_policy.as_ref(),
+                &public_key,
+                None,
+                Some(schema.id()),
+            )
+            .await?;
+
+            let encoded_entry = sign_and_encode_entry(
+                &log_id,
+                &seq_num,
+                skiplink.as_ref(),
+                backlink.as_ref(),
+                &encoded_operation,
+                &self.context.key_pair,
+            )?;
+
+            let plain_operation: PlainOperation = (&operation).into();
+            self.context
+                .store
+                .publish(
+                    &schema,
+                    &encoded_entry,
+                    &plain_operation,
+                    &encoded_operation,
+                )
+                .await
+                .map_err(|err| anyhow!(err))?;
+
+            if self
+                .tx
+                .send(ServiceMessage::NewOperation(encoded_entry.hash().into()))
+                .is_err()
+            {
+                // Silently fail here as we don't mind if there are no subscribers
+            }
+
+            Ok(encoded_entry.hash().into())
+        }
+        .await;
+
+        result.map_err(BlobUploadError::Publish)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::document::traits::AsDocument;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::traits::{AsOperation, WithPublicKey};
+    use p2panda_rs::operation::OperationValue;
+    use p2panda_rs::schema::validate::MAX_BLOB_PIECE_LENGTH;
+    use p2panda_rs::storage_provider::traits::{DocumentStore, OperationStore};
+    use tokio::sync::broadcast;
+
+    use crate::blobs::{BlobUploadError, BlobUploadProgress};
+    use crate::context::Context;
+    use crate::materializer::tasks::reduce_task;
+    use crate::materializer::TaskInput;
+    use crate::test_utils::{test_runner, TestNode};
+
+    use super::BlobUploads;
+
+    #[test]
+    fn publishes_completed_uploads() {
+        test_runner(|node: TestNode| async move {
+            let (tx, _rx) = broadcast::channel(16);
+            let uploads = BlobUploads::new(node.context.clone(), tx);
+
+            // Blob spans over multiple pieces
+            let blob_data = vec![7u8; MAX_BLOB_PIECE_LENGTH + 100];
+            let session = uploads
+                .create(blob_data.len() as u64, "application/octet-stream")
+                .await
+                .unwrap();
+
+            let (first, second) = blob_data.split_at(1000);
+            assert_eq!(
+                uploads.append(&session.id, 0, first).await.unwrap(),
+                BlobUploadProgress::InProgress(1000)
+            );
+
+            // Resuming from a wrong offset fails
+            assert!(matches!(
+                uploads.append(&session.id, 0, second).await,
+                Err(BlobUploadError::OffsetMismatch(1000))
+            ));
+
+            let view_id = match uploads.append(&session.id, 1000, second).await.unwrap() {
+                BlobUploadProgress::Completed(view_id) => view_id,
+                progress => panic!("Unexpected progress {:?}", progress),
+            };
+
+            // Session got removed after publishing
+            assert_eq!(uploads.get(&session.id).await.unwrap(), None);
+
+            // Operations got published with the node's key pair
+            let operation = node
+                .context
+                .store
+                .get_operation(&view_id.to_string().parse().unwrap())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(operation.public_key(), &node.context.key_pair.public_key());
+
+            let pieces = match operation.fields().unwrap().get("pieces").unwrap() {
+                OperationValue::PinnedRelationList(list) => list.len(),
+                _ => panic!("Expected pinned relation list"),
+            };
+            assert_eq!(pieces, 2);
+
+            // Blob document can be materialized
+            reduce_task(
+                node.context.clone(),
+                TaskInput::DocumentId(view_id.to_string().parse().unwrap()),
+            )
+            .await
+            .unwrap();
+            let document = node
+                .context
+                .store
+                .get_document_by_view_id(&view_id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                document.get("length"),
+                Some(&OperationValue::Integer(blob_data.len() as i64))
+            );
+        })
+    }
+
+    #[test]
+    fn rejects_invalid_uploads() {
+        test_runner(|node: TestNode| async move {
+            let (tx, _rx) = broadcast::channel(16);
+            let uploads = BlobUploads::new(node.context.clone(), tx);
+
+            assert!(matches!(
+                uploads.create(0, "text/plain").await,
+                Err(BlobUploadError::InvalidLength)
+            ));
+
+            let session = uploads.create(5, "text/plain").await.unwrap();
+            assert!(matches!(
+                uploads.append(&session.id, 0, b"Hello, World!").await,
+                Err(BlobUploadError::InvalidLength)
+            ));
+            assert!(matches!(
+                uploads.append("unknown", 0, b"Hello").await,
+                Err(BlobUploadError::NotFound)
+            ));
+
+            assert!(uploads.terminate(&session.id).await.unwrap());
+            assert!(matches!(
+                uploads.append(&session.id, 0, b"Hello").await,
+                Err(BlobUploadError::NotFound)
+            ));
+        })
+    }
+
+    #[test]
+    fn rejects_uploads_exceeding_blob_limits() {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.blob_limits.max_size_bytes = Some(MAX_BLOB_PIECE_LENGTH as u64 * 3);
+            config.blob_limits.max_pieces = Some(2);
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let (tx, _rx) = broadcast::channel(16);
+            let uploads = BlobUploads::new(node.context.clone(), tx);
+
+            assert!(uploads
+                .create(MAX_BLOB_PIECE_LENGTH as u64 * 2, "text/plain")
+                .await
+                .is_ok());
+            assert!(matches!(
+                uploads
+                    .create(MAX_BLOB_PIECE_LENGTH as u64 * 2 + 1, "text/plain")
+                    .await,
+                Err(BlobUploadError::TooManyPieces(2))
+            ));
+            assert!(matches!(
+                uploads
+                    .create(MAX_BLOB_PIECE_LENGTH as u64 * 3 + 1, "text/plain")
+                    .await,
+                Err(BlobUploadError::TooLarge(_))
+            ));
+        })
+    }
+}
@@ -2,7 +2,9 @@
 
 use p2panda_rs::operation::OperationId;
 
+use crate::graphql::GraphQLSchemaDiff;
 use crate::manager::Sender;
+use crate::materializer::{Task, TaskInput};
 use crate::network::{Peer, PeerMessage};
 
 /// Sender for cross-service communication bus.
@@ -28,4 +30,14 @@ pub enum ServiceMessage {
 
     /// Replication protocol failed with an critical error.
     ReplicationFailed(Peer),
+
+    /// The materializer finished reducing a document, the current views of documents might have
+    /// changed.
+    DocumentsChanged,
+
+    /// A task got taken out of the dead-letter queue and should be processed again.
+    RetryTask(Task<TaskInput>),
+
+    /// GraphQL schema got rebuilt and its types changed.
+    GraphQLSchemaChanged(GraphQLSchemaDiff),
 }
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Fault injection for storage and network, enabled with the `chaos` feature flag.
+//!
+//! Faults are configured per node through a shared [`Faults`] handle. Tests can use it to make SQL
+//! queries fail or slow them down and to drop messages exchanged with other peers, to assert that
+//! the node degrades gracefully.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::debug;
+
+/// Settings determining which faults get injected.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultSettings {
+    /// Probability between `0.0` and `1.0` that a SQL query fails with an error.
+    pub sql_error_rate: f64,
+
+    /// Delay before every SQL query.
+    pub sql_delay: Option<Duration>,
+
+    /// Probability between `0.0` and `1.0` that a message sent to or received from a peer gets
+    /// dropped.
+    pub message_drop_rate: f64,
+}
+
+/// Handle to control injected faults, cloned instances share the same settings.
+///
+/// By default no faults get injected.
+#[derive(Debug, Clone, Default)]
+pub struct Faults(Arc<Mutex<FaultSettings>>);
+
+impl Faults {
+    /// Replace the current settings.
+    #[allow(dead_code)]
+    pub fn set(&self, settings: FaultSettings) {
+        *self
+            .0
+            .lock()
+            .expect("Could not acquire lock on fault settings") = settings;
+    }
+
+    /// Stop injecting any faults.
+    #[allow(dead_code)]
+    pub fn reset(&self) {
+        self.set(FaultSettings::default());
+    }
+
+    /// Returns the current settings.
+    pub fn settings(&self) -> FaultSettings {
+        self.0
+            .lock()
+            .expect("Could not acquire lock on fault settings")
+            .clone()
+    }
+
+    /// Delays the following SQL query or returns an error instead of running it, depending on the
+    /// current settings.
+    pub async fn sql(&self) -> Result<(), sqlx::Error> {
+        let settings = self.settings();
+
+        if let Some(delay) = settings.sql_delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        if happens(settings.sql_error_rate) {
+            debug!("Injected SQL error");
+            return Err(sqlx::Error::Protocol("injected fault".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the following network message should be dropped, depending on the current
+    /// settings.
+    pub fn drop_message(&self) -> bool {
+        let dropped = happens(self.settings().message_drop_rate);
+
+        if dropped {
+            debug!("Injected dropped network message");
+        }
+
+        dropped
+    }
+}
+
+/// Returns true with the given probability.
+fn happens(rate: f64) -> bool {
+    rate > 0.0 && rand::random::<f64>() < rate
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::storage_provider::traits::LogStore;
+    use serde_json::json;
+
+    use crate::test_utils::{
+        http_test_client, inject_faults, reset_faults, test_runner, FaultSettings, TestNode,
+    };
+
+    use super::Faults;
+
+    #[tokio::test]
+    async fn inject_faults_into_handle() {
+        let faults = Faults::default();
+        assert!(faults.sql().await.is_ok());
+        assert!(!faults.drop_message());
+
+        // Settings are shared between cloned handles
+        faults.clone().set(FaultSettings {
+            sql_error_rate: 1.0,
+            message_drop_rate: 1.0,
+            ..FaultSettings::default()
+        });
+        assert!(faults.sql().await.is_err());
+        assert!(faults.drop_message());
+
+        faults.reset();
+        assert!(faults.sql().await.is_ok());
+        assert!(!faults.drop_message());
+    }
+
+    #[test]
+    fn store_errors_on_injected_faults() {
+        test_runner(|node: TestNode| async move {
+            let public_key = KeyPair::new().public_key();
+
+            inject_faults(
+                &node,
+                FaultSettings {
+                    sql_error_rate: 1.0,
+                    ..FaultSettings::default()
+                },
+            );
+            assert!(node.context.store.latest_log_id(&public_key).await.is_err());
+
+            // Queries only get slower when a delay is set
+            inject_faults(
+                &node,
+                FaultSettings {
+                    sql_delay: Some(Duration::from_millis(10)),
+                    ..FaultSettings::default()
+                },
+            );
+            assert!(node.context.store.latest_log_id(&public_key).await.is_ok());
+
+            reset_faults(&node);
+            assert!(node.context.store.latest_log_id(&public_key).await.is_ok());
+        });
+    }
+
+    #[test]
+    fn graphql_responds_with_errors_on_injected_faults() {
+        test_runner(|node: TestNode| async move {
+            let client = http_test_client(&node).await;
+            let next_args = || async {
+                client
+                    .post("/graphql")
+                    .json(&json!({
+                        "query": r#"{
+                            nextArgs(
+                                publicKey: "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a"
+                            ) {
+                                logId
+                            }
+                        }"#,
+                    }))
+                    .send()
+                    .await
+                    .json::<Response>()
+                    .await
+            };
+
+            inject_faults(
+                &node,
+                FaultSettings {
+                    sql_error_rate: 1.0,
+                    ..FaultSettings::default()
+                },
+            );
+            assert!(!next_args().await.errors.is_empty());
+
+            // Node recovers as soon as the database is healthy again
+            reset_faults(&node);
+            assert!(next_args().await.errors.is_empty());
+        });
+    }
+}
@@ -1,10 +1,24 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::collections::BTreeMap;
+use std::num::NonZeroU64;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
+use libp2p::PeerId;
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::operation::OperationValue;
 use p2panda_rs::schema::SchemaId;
 
+use crate::blobs::{BlobBackendConfiguration, BlobHooks};
+use crate::db::models::utils::parse_value_to_string_vec;
+use crate::db::query::DEFAULT_PAGE_SIZE;
+use crate::document_hooks::DocumentHooks;
+use crate::log_ids::{SequentialLogIds, SharedLogIdPolicy};
 use crate::network::NetworkConfiguration;
+use crate::projections::Projection;
+use crate::replication::Compression;
 
 /// Configuration object holding all important variables throughout the application.
 #[derive(Debug, Clone)]
@@ -31,8 +45,68 @@ pub struct Configuration {
     /// Be mindful of the connection limits for the database as well as other applications which
     /// may want to connect to the same database (or even multiple instances of the same
     /// application in high-availability deployments).
+    ///
+    /// With PostgreSQL and a query timeout, half of these connections go to the separate pool of
+    /// GraphQL statements.
     pub database_max_connections: u32,
 
+    /// Path of the operation journal, disabled when not set.
+    ///
+    /// Accepted entries and operations are appended to this file and flushed to disk before they
+    /// get inserted into the database. Entries missing in the database are replayed from the
+    /// journal on startup, this protects SQLite databases on unreliable storage like SD cards
+    /// against data loss after a power failure.
+    pub journal_path: Option<PathBuf>,
+
+    /// Maximum duration of a single GraphQL request and of its SQL statements, no timeout when not
+    /// set. Defaults to 30 seconds.
+    ///
+    /// Requests which exceed this duration are aborted and all database queries they started get
+    /// cancelled, so they do not hold on to connections of the pool. With PostgreSQL GraphQL
+    /// statements run on a separate pool which aborts them on the server side as well.
+    pub query_timeout: Option<Duration>,
+
+    /// Replace messages of internal errors in GraphQL responses with a generic message and an
+    /// error id. Defaults to true.
+    ///
+    /// The full error is logged together with the error id, so operators can look it up without
+    /// exposing SQL statements or file paths to clients. Disable this during development.
+    pub mask_errors: bool,
+
+    /// Maximum nesting depth of GraphQL queries, no limit when not set. Defaults to no limit.
+    ///
+    /// Queries exceeding this depth are rejected with an error before they get executed, this
+    /// bounds expensive traversals over many levels of nested relations.
+    pub max_query_depth: Option<usize>,
+
+    /// Maximum complexity of GraphQL queries, no limit when not set. Defaults to no limit.
+    ///
+    /// Every selected field counts one towards the complexity, fields following relations to
+    /// other documents count ten. Queries exceeding this complexity are rejected before they get
+    /// executed, so a single query can not occupy the database with many nested lookups.
+    pub max_query_complexity: Option<usize>,
+
+    /// Number of documents returned by collection queries which don't specify `first`.
+    ///
+    /// Clients can still request more documents explicitly, the `hasNextPage` field of the
+    /// response tells them if further pages exist. Defaults to 25 for all schemas.
+    pub page_sizes: PageSizes,
+
+    /// Only execute GraphQL operations which were registered in advance with the `allowOperation`
+    /// mutation, identified by the SHA256 hash of their query or their name. Defaults to false.
+    ///
+    /// This rejects ad-hoc queries on public nodes while a paired frontend keeps working. The
+    /// mutations managing the allow-list require the admin token, requests sending it are not
+    /// subject to the allow-list.
+    pub operation_allow_list: bool,
+
+    /// Maximum number of entries published at once with the `publishMany` mutation. Defaults to
+    /// 100.
+    ///
+    /// Longer batches are rejected before any of their entries get decoded, all authors of a
+    /// batch are locked until it was published.
+    pub max_batch_entries: usize,
+
     /// HTTP port, serving the GraphQL API (for example hosted under
     /// http://localhost:2020/graphql). This API is used for client-node communication. Defaults to
     /// 2020.
@@ -44,6 +118,54 @@ pub struct Configuration {
     /// not persisted, otherwise you will run into data inconsistencies.
     pub blobs_base_path: PathBuf,
 
+    /// Storage backend for assembled blob files. Defaults to the filesystem, using
+    /// `blobs_base_path`.
+    ///
+    /// Large-media deployments can use an S3-compatible object storage instead to keep the
+    /// filesystem of the node small and serve blobs from a CDN origin.
+    pub blobs_backend: BlobBackendConfiguration,
+
+    /// Post-processing hooks which run on newly materialized blobs, for example to generate
+    /// thumbnails or transcode videos.
+    ///
+    /// Hooks run in their own worker pool and do not block the materialization of other
+    /// documents. Their results are stored as derived blobs next to the original blob.
+    pub blob_hooks: BlobHooks,
+
+    /// Hooks which run after documents got materialized, for example to keep a search index
+    /// up-to-date.
+    ///
+    /// Hooks run in their own worker pool and do not block the materialization of other
+    /// documents. Further hooks can be registered on a running node with
+    /// `Node::register_document_hook`.
+    pub document_hooks: DocumentHooks,
+
+    /// Origins of sites which are allowed to embed blobs served by this node, for example
+    /// `https://example.org`. Defaults to allowing any origin.
+    ///
+    /// Requests from other sites are rejected unless they use a signed URL which was minted via
+    /// the GraphQL API with the admin token, this protects public nodes from third-party sites hotlinking large media.
+    pub blobs_allowed_origins: AllowList<String>,
+
+    /// Log every HTTP request for a blob together with its origin. Defaults to false.
+    pub blobs_access_log: bool,
+
+    /// Verify assembled blob files against the hash of their pieces before serving them for the
+    /// first time. Missing or corrupted files get assembled again. Defaults to false.
+    pub verify_blobs: bool,
+
+    /// Maximum number of pieces and size of blobs, defaults to no limits.
+    ///
+    /// Blob documents exceeding them are rejected when they get published or replicated and are
+    /// not assembled.
+    pub blob_limits: BlobLimits,
+
+    /// Maximum total size in bytes of recently assembled blobs kept in memory, defaults to 16MiB.
+    ///
+    /// Cached blobs are served without querying their pieces from the database again. Set to 0 to
+    /// disable the cache.
+    pub blob_cache_size_bytes: u64,
+
     /// Number of concurrent workers which defines the maximum of materialization tasks which can
     /// be worked on simultaneously.
     ///
@@ -51,6 +173,157 @@ pub struct Configuration {
     /// number for low-energy devices with limited resources.
     pub worker_pool_size: u32,
 
+    /// Maximum number of blobs which are materialized at the same time.
+    ///
+    /// Every blob task streams the pieces of a blob from the database into the storage backend,
+    /// limiting the number of these tasks keeps the memory usage low when many large blobs
+    /// arrive at once, for example during replication.
+    pub blob_worker_pool_size: u32,
+
+    /// Number of workers of certain materialization task types, overriding `worker_pool_size` or
+    /// `blob_worker_pool_size` for them.
+    ///
+    /// Task types are "reduce", "dependency", "schema", "blob", "blob_hooks", "document_hooks"
+    /// and "garbage_collection". Raising the number of "reduce" workers for example speeds up large
+    /// imports of documents.
+    pub worker_pool_sizes: BTreeMap<String, u32>,
+
+    /// Priorities of certain materialization task types, tasks with a higher priority are started
+    /// first.
+    ///
+    /// Workers hold back tasks while tasks with a higher priority are waiting. By default "schema"
+    /// tasks have a priority of 2, "reduce" and "dependency" tasks of 1 and all other tasks of 0,
+    /// this way large blob imports do not delay documents becoming queryable.
+    pub task_priorities: BTreeMap<String, u8>,
+
+    /// Maximum number of materialization tasks waiting to be processed, 0 disables the limit.
+    ///
+    /// Publishing and replicating new entries pauses while the limit is reached, this keeps the
+    /// memory usage bounded when entries arrive faster than they can be materialized.
+    pub task_queue_limit: u64,
+
+    /// Number of attempts after which a critically failing materializer task is moved into the
+    /// dead-letter queue instead of crashing the node.
+    ///
+    /// Failed tasks are retried before with an exponential backoff, see `task_retry_backoff`.
+    ///
+    /// Tasks in the dead-letter queue can be inspected, retried or discarded via the GraphQL API
+    /// by requests sending the admin token.
+    /// Setting this to `0` disables the dead-letter queue.
+    pub max_task_attempts: u32,
+
+    /// Time to wait before retrying a critically failed materializer task, doubling with every
+    /// further attempt up to one minute.
+    pub task_retry_backoff: Duration,
+
+    /// Fields which need to be unique across all documents of a schema.
+    ///
+    /// The materializer keeps an index of the values of these fields and flags documents sharing
+    /// the same values as conflicting. Out of these, the document with the lowest document id
+    /// wins, which makes the outcome the same on every node.
+    ///
+    /// The index is rebuilt from all existing documents on every start, changed constraints apply
+    /// to documents created before as well.
+    pub unique_constraints: Vec<UniqueConstraint>,
+
+    /// Aggregates over the documents of a schema which are maintained by the node.
+    ///
+    /// The materializer updates the document count and sums of every group whenever a document
+    /// changes, instead of computing them on every request. They are exposed via the
+    /// `materializedAggregate` query on the GraphQL API.
+    pub materialized_aggregates: Vec<MaterializedAggregate>,
+
+    /// Schema of documents in which authors claim human-readable names, disabled when not set.
+    ///
+    /// The materializer keeps a lookup table of the claimed names, which can be resolved to
+    /// public keys and back via the GraphQL API. Names claimed by several authors belong to the
+    /// one who created the document with the lowest document id, which makes the outcome the
+    /// same on every node.
+    pub name_claims: Option<NameClaims>,
+
+    /// Named projections of documents which are maintained by the node.
+    ///
+    /// The materializer keeps every projection in a dedicated database table which gets rebuilt
+    /// on start. Projections are exposed as read-only types on the GraphQL API.
+    pub projections: Vec<Projection>,
+
+    /// Policy allocating log ids for new documents. Defaults to `SequentialLogIds`.
+    ///
+    /// Clients which assign log ids themselves should use the same policy as the node, otherwise
+    /// the log ids returned by `nextArgs` might collide with the ones they picked.
+    pub log_id_policy: SharedLogIdPolicy,
+
+    /// Path of the unix socket serving the admin console, disabled when not set.
+    ///
+    /// The admin console accepts read-only SQL queries against the database for debugging
+    /// purposes. All received statements are written to the audit log.
+    pub admin_socket_path: Option<PathBuf>,
+
+    /// Token authenticating admin requests to the GraphQL API, disabled when not set.
+    ///
+    /// Requests need to send it in an `Authorization: Bearer <token>` header to run admin
+    /// mutations, for example `purgeDocument`, and to upload blobs which get published by the
+    /// node. Blob uploads are not possible when no token is set.
+    pub admin_token: Option<String>,
+
+    /// Compression algorithm for entries exchanged during replication, disabled when not set.
+    ///
+    /// Entries are only compressed when the remote peer announced support for the same
+    /// algorithm, otherwise they are sent uncompressed.
+    pub replication_compression: Option<Compression>,
+
+    /// Window after startup over which announcements and first replication attempts with peers
+    /// are spread, disabled when zero.
+    ///
+    /// Peers connecting during this window are served at a random point in time within the
+    /// remaining window instead of all at once. This avoids CPU and bandwidth spikes on low-power
+    /// devices rejoining a large network.
+    pub replication_warmup: Duration,
+
+    /// Request only the current state of documents from peers during replication.
+    ///
+    /// Deleted documents are left out unless we hold parts of them already, which saves bandwidth
+    /// on light nodes at the cost of not being able to audit or serve the full history of the
+    /// network. Peers which don't support this mode are replicated with as usual.
+    pub replication_shallow: bool,
+
+    /// Take part in replication sessions without persisting any received entries.
+    ///
+    /// The node only records the number and size of entries per schema it would have ingested,
+    /// this helps to evaluate the cost of joining a network or enabling a schema before committing
+    /// disk space.
+    pub replication_dry_run: bool,
+
+    /// List of supported schema ids which the node replicates with other peers.
+    ///
+    /// Documents of all other supported schemas are still persisted and exposed on the GraphQL
+    /// API, but they are not announced to or requested from peers anymore. This allows light
+    /// nodes to only sync the collections they care about. An empty set disables replication.
+    ///
+    /// When set to `AllowList::Wildcard` all supported schema ids are replicated.
+    pub replicate_schema_ids: AllowList<SchemaId>,
+
+    /// List of peers the node replicates with.
+    ///
+    /// Other peers can still connect to the node, for example to use it as a relay, but the node
+    /// neither announces itself to them nor accepts replication sessions from them. Use
+    /// `NetworkConfiguration::allow_peer_ids` to reject their connections right away.
+    ///
+    /// When set to `AllowList::Wildcard` the node replicates with any peer.
+    pub replication_allow_peer_ids: AllowList<PeerId>,
+
+    /// List of peers the node never replicates with.
+    ///
+    /// Use `NetworkConfiguration::block_peer_ids` to reject their connections right away.
+    pub replication_block_peer_ids: Vec<PeerId>,
+
+    /// Duration without any requests or network activity after which the node releases resources,
+    /// disabled when not set.
+    ///
+    /// Idle database connections get closed and in-memory caches dropped to save RAM on devices
+    /// which host other services as well. Both are restored on demand when traffic returns.
+    pub idle_timeout: Option<Duration>,
+
     /// Network configuration.
     pub network: NetworkConfiguration,
 }
@@ -61,14 +334,179 @@ impl Default for Configuration {
             allow_schema_ids: AllowList::Wildcard,
             database_url: "sqlite::memory:".into(),
             database_max_connections: 32,
+            journal_path: None,
+            query_timeout: Some(Duration::from_secs(30)),
+            mask_errors: true,
+            max_query_depth: None,
+            max_query_complexity: None,
+            page_sizes: PageSizes::default(),
+            operation_allow_list: false,
+            max_batch_entries: 100,
             http_port: 2020,
             blobs_base_path: PathBuf::new(),
+            blobs_backend: BlobBackendConfiguration::default(),
+            blob_hooks: BlobHooks::default(),
+            document_hooks: DocumentHooks::default(),
+            blobs_allowed_origins: AllowList::Wildcard,
+            blobs_access_log: false,
+            verify_blobs: false,
+            blob_limits: BlobLimits::default(),
+            blob_cache_size_bytes: 16 * 1024 * 1024,
             worker_pool_size: 16,
+            blob_worker_pool_size: 2,
+            worker_pool_sizes: BTreeMap::new(),
+            task_priorities: BTreeMap::new(),
+            task_queue_limit: 10_000,
+            max_task_attempts: 3,
+            task_retry_backoff: Duration::from_secs(1),
+            unique_constraints: Vec::new(),
+            materialized_aggregates: Vec::new(),
+            name_claims: None,
+            projections: Vec::new(),
+            log_id_policy: Arc::new(SequentialLogIds),
+            admin_socket_path: None,
+            admin_token: None,
+            replication_compression: None,
+            replication_warmup: Duration::ZERO,
+            replication_shallow: false,
+            replication_dry_run: false,
+            replicate_schema_ids: AllowList::Wildcard,
+            replication_allow_peer_ids: AllowList::Wildcard,
+            replication_block_peer_ids: Vec::new(),
+            idle_timeout: None,
             network: NetworkConfiguration::default(),
         }
     }
 }
 
+/// Default page sizes of collection queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageSizes {
+    /// Page size of collections without a page size of their own.
+    pub default: NonZeroU64,
+
+    /// Page sizes of the collections of certain schemas.
+    pub schemas: Vec<(SchemaId, NonZeroU64)>,
+}
+
+impl PageSizes {
+    /// Returns the page size of collections of documents following the given schema.
+    pub fn get(&self, schema_id: &SchemaId) -> NonZeroU64 {
+        self.schemas
+            .iter()
+            .find(|(id, _)| id == schema_id)
+            .map(|(_, page_size)| *page_size)
+            .unwrap_or(self.default)
+    }
+}
+
+impl Default for PageSizes {
+    fn default() -> Self {
+        Self {
+            default: NonZeroU64::new(DEFAULT_PAGE_SIZE).expect("Page size is not zero"),
+            schemas: Vec::new(),
+        }
+    }
+}
+
+/// Limits of blobs accepted by this node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlobLimits {
+    /// Maximum number of pieces of a blob, no limit when not set.
+    pub max_pieces: Option<usize>,
+
+    /// Maximum size of a blob in bytes, no limit when not set.
+    pub max_size_bytes: Option<u64>,
+}
+
+/// Field or tuple of fields which needs to be unique across all documents of a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniqueConstraint {
+    /// Schema of the documents this constraint applies to.
+    pub schema_id: SchemaId,
+
+    /// Names of the fields which values need to be unique in combination.
+    pub fields: Vec<String>,
+}
+
+impl UniqueConstraint {
+    /// Returns an identifier for this constraint within its schema.
+    pub fn name(&self) -> String {
+        self.fields.join(",")
+    }
+
+    /// Returns the combined values of the constrained fields of the given document.
+    ///
+    /// Every value is prefixed with its length to make sure that different combinations of values
+    /// never result in the same string. Deleted documents and documents which do not contain all
+    /// fields of this constraint don't have a value.
+    pub fn value(&self, document: &impl AsDocument) -> Option<String> {
+        if document.is_deleted() {
+            return None;
+        }
+
+        let mut value = String::new();
+
+        for field in &self.fields {
+            for field_value in parse_value_to_string_vec(document.get(field)?) {
+                match field_value {
+                    Some(field_value) => {
+                        value.push_str(&format!("{}:{}", field_value.len(), field_value))
+                    }
+                    None => value.push('-'),
+                }
+            }
+            value.push(';');
+        }
+
+        Some(value)
+    }
+}
+
+/// Named aggregate over the documents of one schema, maintained incrementally by the
+/// materializer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaterializedAggregate {
+    /// Name of the aggregate, used to query it.
+    pub name: String,
+
+    /// Schema of the aggregated documents.
+    pub schema_id: SchemaId,
+
+    /// Field by which the documents are grouped, all documents form one group when not set.
+    pub group_by: Option<String>,
+
+    /// Numeric fields which values are summed up per group.
+    pub sum: Vec<String>,
+}
+
+/// Schema and field of documents in which authors claim human-readable names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameClaims {
+    /// Schema of the documents holding the claims.
+    pub schema_id: SchemaId,
+
+    /// Name of the string field holding the claimed name.
+    pub field: String,
+}
+
+impl NameClaims {
+    /// Returns the name claimed by the author of the given document.
+    ///
+    /// Documents of other schemas, deleted documents and documents without a string value for
+    /// the configured field don't claim any name.
+    pub fn claimed_name(&self, document: &impl AsDocument) -> Option<String> {
+        if document.schema_id() != &self.schema_id || document.is_deleted() {
+            return None;
+        }
+
+        match document.get(&self.field)? {
+            OperationValue::String(name) => Some(name.to_owned()),
+            _ => None,
+        }
+    }
+}
+
 /// Set a configuration value to either allow a defined set of elements or to a wildcard (*).
 #[derive(Debug, Clone)]
 pub enum AllowList<T> {
@@ -6,9 +6,12 @@ use std::sync::Arc;
 use p2panda_rs::identity::KeyPair;
 use p2panda_rs::storage_provider::traits::{DocumentStore, EntryStore, LogStore, OperationStore};
 
+use crate::blobs::{new_blob_backend, BlobAccess, BlobIntegrity, SharedBlobBackend};
 use crate::config::Configuration;
 use crate::db::SqlStore;
+use crate::idle::Activity;
 use crate::schema::SchemaProvider;
+use crate::status::NodeStatus;
 
 /// Inner data shared across all services.
 #[derive(Debug)]
@@ -27,6 +30,21 @@ where
 
     /// Schema provider gives access to system and application schemas.
     pub schema_provider: SchemaProvider,
+
+    /// Storage backend for assembled blob files.
+    pub blobs: SharedBlobBackend,
+
+    /// Access control for blobs served via HTTP.
+    pub blob_access: BlobAccess,
+
+    /// Verification of assembled blob files before they get served via HTTP.
+    pub blob_integrity: BlobIntegrity,
+
+    /// Status of the node, for example the number of connected peers.
+    pub status: NodeStatus,
+
+    /// Time of the last request or network activity.
+    pub activity: Activity,
 }
 
 impl<S> Data<S>
@@ -39,11 +57,20 @@ where
         config: Configuration,
         schema_provider: SchemaProvider,
     ) -> Self {
+        let blobs = new_blob_backend(&config);
+        let blob_access = BlobAccess::new(&config, &key_pair);
+        let blob_integrity = BlobIntegrity::new(&config);
+
         Self {
             key_pair,
             config,
             store,
             schema_provider,
+            blobs,
+            blob_access,
+            blob_integrity,
+            status: NodeStatus::new(),
+            activity: Activity::new(),
         }
     }
 }
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use p2panda_rs::document::{DocumentId, DocumentViewId};
+
+/// In-memory cache of assembled and validated blobs, bounded by their total size in bytes.
+///
+/// Blobs are looked up by their document id and only returned when the cached data belongs to
+/// the requested document view, this way updated blobs are never served from stale data. The
+/// least recently used blobs get dropped when the cache is full.
+#[derive(Debug, Clone)]
+pub struct BlobCache {
+    /// Maximum total size of all cached blobs in bytes, caching is disabled when zero.
+    max_size_bytes: u64,
+
+    inner: Arc<Mutex<BlobCacheInner>>,
+}
+
+#[derive(Debug)]
+struct BlobCacheInner {
+    entries: LruCache<DocumentId, (DocumentViewId, Arc<Vec<u8>>)>,
+
+    /// Total size of all cached blobs in bytes.
+    size_bytes: u64,
+}
+
+impl BlobCache {
+    /// Returns a new cache holding blobs up to the given total size in bytes.
+    pub fn new(max_size_bytes: u64) -> Self {
+        Self {
+            max_size_bytes,
+            inner: Arc::new(Mutex::new(BlobCacheInner {
+                entries: LruCache::unbounded(),
+                size_bytes: 0,
+            })),
+        }
+    }
+
+    /// Returns true if a blob of the given length can be cached.
+    pub fn fits(&self, length: usize) -> bool {
+        self.max_size_bytes > 0 && length as u64 <= self.max_size_bytes
+    }
+
+    /// Returns the cached data of a blob document view.
+    pub fn get(&self, document_id: &DocumentId, view_id: &DocumentViewId) -> Option<Arc<Vec<u8>>> {
+        let mut inner = self
+            .inner
+            .lock()
+            .expect("Could not acquire lock on blob cache");
+
+        match inner.entries.get(document_id) {
+            Some((cached_view_id, data)) if cached_view_id == view_id => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    /// Adds the data of a blob document view, replacing any previously cached view of the same
+    /// blob.
+    pub fn insert(&self, document_id: &DocumentId, view_id: &DocumentViewId, data: Vec<u8>) {
+        if !self.fits(data.len()) {
+            return;
+        }
+
+        let mut inner = self
+            .inner
+            .lock()
+            .expect("Could not acquire lock on blob cache");
+        inner.size_bytes += data.len() as u64;
+
+        if let Some((_, previous)) = inner
+            .entries
+            .put(document_id.to_owned(), (view_id.to_owned(), Arc::new(data)))
+        {
+            inner.size_bytes -= previous.len() as u64;
+        }
+
+        while inner.size_bytes > self.max_size_bytes {
+            match inner.entries.pop_lru() {
+                Some((_, (_, evicted))) => inner.size_bytes -= evicted.len() as u64,
+                None => break,
+            }
+        }
+    }
+
+    /// Removes the cached data of a blob, for example after it got updated.
+    pub fn invalidate(&self, document_id: &DocumentId) {
+        let mut inner = self
+            .inner
+            .lock()
+            .expect("Could not acquire lock on blob cache");
+
+        if let Some((_, data)) = inner.entries.pop(document_id) {
+            inner.size_bytes -= data.len() as u64;
+        }
+    }
+
+    /// Removes all cached blobs.
+    pub fn clear(&self) {
+        let mut inner = self
+            .inner
+            .lock()
+            .expect("Could not acquire lock on blob cache");
+        inner.entries.clear();
+        inner.size_bytes = 0;
+    }
+}
+
+impl Default for BlobCache {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::document::{DocumentId, DocumentViewId};
+    use p2panda_rs::test_utils::fixtures::random_document_view_id;
+    use rstest::rstest;
+
+    use super::BlobCache;
+
+    #[rstest]
+    fn evict_least_recently_used_blobs(
+        #[from(random_document_view_id)] view_id_a: DocumentViewId,
+        #[from(random_document_view_id)] view_id_b: DocumentViewId,
+        #[from(random_document_view_id)] view_id_c: DocumentViewId,
+    ) {
+        let cache = BlobCache::new(10);
+        let id = |view_id: &DocumentViewId| -> DocumentId { view_id.to_string().parse().unwrap() };
+
+        cache.insert(&id(&view_id_a), &view_id_a, vec![0; 4]);
+        cache.insert(&id(&view_id_b), &view_id_b, vec![1; 4]);
+
+        // Blobs are only returned for the cached view
+        assert!(cache.get(&id(&view_id_a), &view_id_a).is_some());
+        assert!(cache.get(&id(&view_id_a), &view_id_b).is_none());
+
+        // "b" was used least recently and gets dropped
+        cache.insert(&id(&view_id_c), &view_id_c, vec![2; 4]);
+        assert!(cache.get(&id(&view_id_a), &view_id_a).is_some());
+        assert!(cache.get(&id(&view_id_b), &view_id_b).is_none());
+        assert!(cache.get(&id(&view_id_c), &view_id_c).is_some());
+
+        // Blobs larger than the whole cache are not cached
+        cache.insert(&id(&view_id_b), &view_id_b, vec![1; 11]);
+        assert!(cache.get(&id(&view_id_b), &view_id_b).is_none());
+
+        cache.invalidate(&id(&view_id_a));
+        assert!(cache.get(&id(&view_id_a), &view_id_a).is_none());
+    }
+}
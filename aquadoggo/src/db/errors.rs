@@ -61,6 +61,14 @@ pub enum BlobStoreError {
     #[error("The combined pieces length and claimed blob length don't match")]
     IncorrectLength,
 
+    /// Error when a blob consists of more pieces than allowed on this node.
+    #[error("Blob exceeds maximum number of {0} pieces")]
+    TooManyPieces(usize),
+
+    /// Error when a blob is larger than allowed on this node.
+    #[error("Blob exceeds maximum size of {0} bytes")]
+    TooLarge(u64),
+
     /// Error returned from `DocumentStore` methods.
     #[error(transparent)]
     DocumentStorageError(#[from] DocumentStorageError),
@@ -0,0 +1,416 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Append-only journal of accepted entries and operations.
+//!
+//! Every entry is appended to the journal file and flushed to disk _before_ it gets inserted into
+//! the database. When the node starts, entries which are missing in the database get replayed
+//! from the journal. This protects against data loss when writes to a SQLite database got lost or
+//! only partially persisted after a power failure, which happens for example on SD cards.
+//!
+//! Every line of the journal holds the hex-encoded entry and operation, separated by a
+//! whitespace. Lines which got cut off by a power failure fail to decode or validate and are
+//! skipped during replay. Entries which got removed from the database again are marked with a
+//! line starting with `-` followed by their hash, they are not replayed.
+use std::collections::HashSet;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::{debug, info, warn};
+use p2panda_rs::api::DomainError;
+use p2panda_rs::entry::traits::AsEncodedEntry;
+use p2panda_rs::entry::EncodedEntry;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::operation::decode::decode_operation;
+use p2panda_rs::operation::plain::PlainOperation;
+use p2panda_rs::operation::traits::Schematic;
+use p2panda_rs::operation::EncodedOperation;
+use p2panda_rs::schema::Schema;
+use p2panda_rs::storage_provider::error::EntryStorageError;
+use p2panda_rs::storage_provider::traits::EntryStore;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::db::stores::NextArgs;
+use crate::db::SqlStore;
+use crate::schema::SchemaProvider;
+
+/// Size of the journal file in bytes after which it gets truncated as soon as all journaled
+/// entries are inserted into the database.
+const MAX_JOURNAL_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+struct JournalFile {
+    /// Path of the journal file.
+    path: PathBuf,
+
+    /// Handle of the opened journal file, opened when the first entry gets appended.
+    file: Option<File>,
+
+    /// Number of appended entries which are not inserted into the database yet.
+    pending: usize,
+}
+
+impl JournalFile {
+    /// Appends a line to the journal file and waits until it is flushed to disk.
+    async fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            self.file = Some(file);
+        }
+
+        let file = self.file.as_mut().expect("Journal file was opened above");
+        file.write_all(format!("{}\n", line).as_bytes()).await?;
+        file.sync_data().await
+    }
+}
+
+/// Append-only journal of accepted entries and operations, disabled when no path was configured.
+#[derive(Debug, Clone, Default)]
+pub struct Journal(Option<Arc<Mutex<JournalFile>>>);
+
+impl Journal {
+    /// Returns a new journal writing to the given path, or a disabled journal when no path is set.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self(path.map(|path| {
+            Arc::new(Mutex::new(JournalFile {
+                path,
+                file: None,
+                pending: 0,
+            }))
+        }))
+    }
+
+    /// Appends an entry and its operation to the journal and waits until they are flushed to disk.
+    ///
+    /// Every call needs to be followed by `complete` after the entry was handled, independent of
+    /// it getting inserted into the database or not.
+    async fn append(
+        &self,
+        encoded_entry: &EncodedEntry,
+        encoded_operation: &EncodedOperation,
+    ) -> io::Result<()> {
+        let Some(journal) = &self.0 else {
+            return Ok(());
+        };
+
+        let mut journal = journal.lock().await;
+        journal
+            .write_line(&format!("{} {}", encoded_entry, encoded_operation))
+            .await?;
+        journal.pending += 1;
+
+        Ok(())
+    }
+
+    /// Marks entries as removed, they will not be replayed anymore.
+    async fn revoke(&self, entry_hashes: &[Hash]) -> io::Result<()> {
+        let Some(journal) = &self.0 else {
+            return Ok(());
+        };
+
+        let mut journal = journal.lock().await;
+        for hash in entry_hashes {
+            journal.write_line(&format!("- {}", hash)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Marks an appended entry as handled.
+    ///
+    /// The journal gets truncated when it grew too large and no other entry is waiting to be
+    /// inserted into the database.
+    async fn complete(&self) -> io::Result<()> {
+        let Some(journal) = &self.0 else {
+            return Ok(());
+        };
+
+        let mut journal = journal.lock().await;
+        journal.pending = journal.pending.saturating_sub(1);
+
+        if journal.pending > 0 {
+            return Ok(());
+        }
+
+        if let Some(file) = journal.file.as_mut() {
+            if file.metadata().await?.len() > MAX_JOURNAL_SIZE {
+                debug!("Truncating operation journal");
+                file.set_len(0).await?;
+                file.sync_all().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns all entries and operations which could be read from the journal.
+    async fn records(&self) -> io::Result<Vec<(EncodedEntry, EncodedOperation)>> {
+        let Some(journal) = &self.0 else {
+            return Ok(Vec::new());
+        };
+
+        let path = journal.lock().await.path.clone();
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut records = Vec::new();
+        let mut revoked = HashSet::new();
+        for (index, line) in content.lines().enumerate() {
+            if let Some(hash) = line.strip_prefix("- ") {
+                revoked.insert(hash.to_owned());
+                continue;
+            }
+
+            match parse_record(line) {
+                Some(record) => records.push(record),
+                None => warn!("Skipping malformed line {} of operation journal", index + 1),
+            }
+        }
+
+        records.retain(|(encoded_entry, _)| !revoked.contains(encoded_entry.hash().as_str()));
+
+        Ok(records)
+    }
+
+    /// Replaces all lines of the journal with the given entries and operations.
+    async fn rewrite(&self, records: &[(EncodedEntry, EncodedOperation)]) -> io::Result<()> {
+        let Some(journal) = &self.0 else {
+            return Ok(());
+        };
+
+        let mut journal = journal.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&journal.path)
+            .await?;
+
+        for (encoded_entry, encoded_operation) in records {
+            file.write_all(format!("{} {}\n", encoded_entry, encoded_operation).as_bytes())
+                .await?;
+        }
+
+        file.sync_all().await?;
+        journal.file = None;
+
+        Ok(())
+    }
+}
+
+/// Parses the hex-encoded entry and operation of a line in the journal.
+fn parse_record(line: &str) -> Option<(EncodedEntry, EncodedOperation)> {
+    let (entry, operation) = line.split_once(' ')?;
+    let entry = hex::decode(entry).ok()?;
+    let operation = hex::decode(operation).ok()?;
+
+    Some((
+        EncodedEntry::from_bytes(&entry),
+        EncodedOperation::from_bytes(&operation),
+    ))
+}
+
+impl SqlStore {
+    /// Validates an entry and its operation and inserts them into the database.
+    ///
+    /// This wraps `publish_atomically` and writes the entry to the operation journal first, when
+    /// it is enabled.
+    pub async fn publish(
+        &self,
+        schema: &Schema,
+        encoded_entry: &EncodedEntry,
+        plain_operation: &PlainOperation,
+        encoded_operation: &EncodedOperation,
+    ) -> Result<NextArgs, DomainError> {
+        self.journal
+            .append(encoded_entry, encoded_operation)
+            .await
+            .map_err(journal_error)?;
+
+        let result = self
+            .publish_atomically(schema, encoded_entry, plain_operation, encoded_operation)
+            .await;
+
+        self.journal.complete().await.map_err(journal_error)?;
+
+        result
+    }
+
+    /// Removes entries which have not been materialized yet from the database.
+    ///
+    /// The entries get marked in the operation journal first, so they are not replayed on the
+    /// next start.
+    pub async fn unpublish(&self, entry_hashes: &[Hash]) -> Result<(), EntryStorageError> {
+        self.journal
+            .revoke(entry_hashes)
+            .await
+            .map_err(|err| EntryStorageError::Custom(err.to_string()))?;
+
+        self.remove_entries(entry_hashes).await
+    }
+
+    /// Inserts all entries from the operation journal which are missing in the database and
+    /// removes them from the journal afterwards.
+    ///
+    /// Returns the number of replayed entries. Entries of schemas which are not materialized yet
+    /// stay in the journal and are replayed on the next start, invalid entries are dropped.
+    pub async fn replay_journal(&self, schema_provider: &SchemaProvider) -> Result<usize> {
+        let mut replayed = 0;
+        let mut retained = Vec::new();
+
+        for (encoded_entry, encoded_operation) in self.journal.records().await? {
+            if self.get_entry(&encoded_entry.hash()).await?.is_some() {
+                continue;
+            }
+
+            let Ok(plain_operation) = decode_operation(&encoded_operation) else {
+                warn!(
+                    "Skipping journaled entry {} with invalid operation",
+                    encoded_entry.hash()
+                );
+                continue;
+            };
+
+            let Some(schema) = schema_provider.get(plain_operation.schema_id()).await else {
+                warn!(
+                    "Keeping journaled entry {} with unknown schema",
+                    encoded_entry.hash()
+                );
+                retained.push((encoded_entry, encoded_operation));
+                continue;
+            };
+
+            match self
+                .publish_atomically(
+                    &schema,
+                    &encoded_entry,
+                    &plain_operation,
+                    &encoded_operation,
+                )
+                .await
+            {
+                Ok(_) => replayed += 1,
+                Err(err) => warn!(
+                    "Skipping journaled entry {} which failed to publish: {}",
+                    encoded_entry.hash(),
+                    err
+                ),
+            }
+        }
+
+        if replayed > 0 {
+            info!("Replayed {} entries from operation journal", replayed);
+        }
+
+        self.journal.rewrite(&retained).await?;
+
+        Ok(replayed)
+    }
+}
+
+/// Converts a failed write to the journal into an error of the storage provider.
+fn journal_error(err: io::Error) -> DomainError {
+    DomainError::EntryStoreError(EntryStorageError::Custom(format!(
+        "Writing to operation journal failed: {}",
+        err
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::encode::sign_and_encode_entry;
+    use p2panda_rs::entry::traits::AsEncodedEntry;
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::encode::encode_operation;
+    use p2panda_rs::operation::OperationBuilder;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::storage_provider::traits::EntryStore;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    use crate::db::SqlStore;
+    use crate::test_utils::{add_schema, test_runner, TestNode};
+
+    use super::Journal;
+
+    #[rstest]
+    fn replay_missing_entries(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "messages",
+                vec![("message", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let temp_dir = TempDir::new().unwrap();
+            let path = temp_dir.path().join("journal");
+            let journal = Journal::new(Some(path.clone()));
+
+            // Write an entry to the journal without inserting it into the database, as if the
+            // node lost power while writing to the database
+            let operation = OperationBuilder::new(schema.id())
+                .fields(&[("message", "Hello!".into())])
+                .build()
+                .unwrap();
+            let encoded_operation = encode_operation(&operation).unwrap();
+            let encoded_entry = sign_and_encode_entry(
+                &LogId::default(),
+                &SeqNum::default(),
+                None,
+                None,
+                &encoded_operation,
+                &KeyPair::new(),
+            )
+            .unwrap();
+
+            journal
+                .append(&encoded_entry, &encoded_operation)
+                .await
+                .unwrap();
+
+            // Append a line which got cut off
+            journal
+                .append(&encoded_entry, &encoded_operation)
+                .await
+                .unwrap();
+            let content = std::fs::read_to_string(&path).unwrap();
+            std::fs::write(&path, &content[..content.len() - 20]).unwrap();
+
+            let store = SqlStore::new(node.context.store.pool.clone()).with_journal(journal);
+            let replayed = store
+                .replay_journal(&node.context.schema_provider)
+                .await
+                .unwrap();
+            assert_eq!(replayed, 1);
+            assert!(store
+                .get_entry(&encoded_entry.hash())
+                .await
+                .unwrap()
+                .is_some());
+
+            // Journal got cleared after replay
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+            assert_eq!(
+                store
+                    .replay_journal(&node.context.schema_provider)
+                    .await
+                    .unwrap(),
+                0
+            );
+        });
+    }
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use p2panda_rs::identity::PublicKey;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::db::SqlStore;
+
+/// Registry of async locks, one for every public key which is currently publishing.
+#[derive(Clone, Debug, Default)]
+pub struct PublicKeyLocks(Arc<Mutex<HashMap<PublicKey, Arc<AsyncMutex<()>>>>>);
+
+impl PublicKeyLocks {
+    /// Waits until the lock for the given public key was acquired.
+    ///
+    /// The lock is released when the returned guard gets dropped.
+    pub async fn lock(&self, public_key: &PublicKey) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.0.lock().expect("Public key locks got poisoned");
+
+            // Remove locks nobody holds or waits for anymore
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+
+            locks.entry(public_key.to_owned()).or_default().clone()
+        };
+
+        lock.lock_owned().await
+    }
+}
+
+impl SqlStore {
+    /// Serialises reading the log heights and publishing entries of a public key.
+    ///
+    /// Two entries of the same author can not be published at the same time, otherwise both
+    /// would be validated against the same log height. Hold the returned guard while
+    /// calculating the next arguments or publishing an entry.
+    pub async fn lock_public_key(&self, public_key: &PublicKey) -> OwnedMutexGuard<()> {
+        self.public_key_locks.lock(public_key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use p2panda_rs::identity::KeyPair;
+
+    use super::PublicKeyLocks;
+
+    #[tokio::test]
+    async fn serialise_per_public_key() {
+        let locks = PublicKeyLocks::default();
+        let panda = KeyPair::new().public_key();
+        let penguin = KeyPair::new().public_key();
+
+        let guard = locks.lock(&panda).await;
+
+        // Other public keys are not blocked
+        let _penguin_guard = locks.lock(&penguin).await;
+
+        // Same public key needs to wait until the first guard got dropped
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), locks.lock(&panda))
+                .await
+                .is_err()
+        );
+        drop(guard);
+        let _guard = locks.lock(&panda).await;
+
+        // Unused locks get cleaned up
+        drop(_penguin_guard);
+        let _guard_other = locks.lock(&KeyPair::new().public_key()).await;
+        assert_eq!(locks.0.lock().unwrap().len(), 2);
+    }
+}
@@ -4,33 +4,182 @@
 //!
 //! The main interface is [`SqlStore`] which offers an interface onto the database by implementing
 //! the storage traits defined in `p2panda-rs` as well as some implementation specific features.
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use anyhow::{Error, Result};
-use sqlx::any::{Any, AnyPool, AnyPoolOptions};
+use sqlx::any::{Any, AnyKind, AnyPool, AnyPoolOptions};
 use sqlx::migrate;
 use sqlx::migrate::MigrateDatabase;
+use sqlx::Connection;
+
+#[cfg(feature = "chaos")]
+use crate::chaos::Faults;
+use crate::config::BlobLimits;
+use crate::db::blob_cache::BlobCache;
+use crate::db::journal::Journal;
+use crate::db::locks::PublicKeyLocks;
+use crate::db::view_budget::ViewBudget;
 
+mod blob_cache;
 pub mod errors;
+pub mod journal;
+mod locks;
 pub mod models;
 pub mod query;
 pub mod stores;
 pub mod types;
+mod view_budget;
 
 /// SQL based persistent storage that implements `EntryStore`, `OperationStore`, `LogStore` and `DocumentStore`.
 #[derive(Clone, Debug)]
 pub struct SqlStore {
     pub(crate) pool: Pool,
+    public_key_locks: PublicKeyLocks,
+
+    /// Journal of accepted entries, written before they get inserted into the database.
+    journal: Journal,
+
+    /// Limits of blobs which get published and assembled.
+    pub(crate) blob_limits: BlobLimits,
+
+    /// Recently assembled blobs, served without querying their pieces again.
+    pub(crate) blob_cache: BlobCache,
+
+    /// Limit of document views which get materialised on demand when reading them.
+    pub(crate) view_budget: ViewBudget,
+
+    /// Pools of stores derived from this store which run statements with a timeout.
+    statement_timeout_pools: Arc<Mutex<Vec<Pool>>>,
+
+    /// Faults injected into queries and network messages of this node.
+    #[cfg(feature = "chaos")]
+    pub(crate) faults: Faults,
 }
 
 impl SqlStore {
     /// Create a new `SqlStore` using the provided db `Pool`.
     pub fn new(pool: Pool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            public_key_locks: PublicKeyLocks::default(),
+            journal: Journal::default(),
+            blob_limits: BlobLimits::default(),
+            blob_cache: BlobCache::default(),
+            view_budget: ViewBudget::default(),
+            statement_timeout_pools: Arc::default(),
+            #[cfg(feature = "chaos")]
+            faults: Faults::default(),
+        }
+    }
+
+    /// Writes all published entries to the given operation journal before inserting them.
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Rejects blobs exceeding the given limits when publishing and assembling them.
+    pub fn with_blob_limits(mut self, blob_limits: BlobLimits) -> Self {
+        self.blob_limits = blob_limits;
+        self
+    }
+
+    /// Keeps recently assembled blobs up to the given total size in bytes in memory.
+    pub fn with_blob_cache(mut self, max_size_bytes: u64) -> Self {
+        self.blob_cache = BlobCache::new(max_size_bytes);
+        self
+    }
+
+    /// Returns a store running its queries on a separate pool, where PostgreSQL aborts every
+    /// statement exceeding the given timeout.
+    ///
+    /// Locks, the journal and caches are shared with this store. Other users of this store are not
+    /// affected by the timeout. SQLite does not support statement timeouts, there this returns a
+    /// copy of this store using the same pool.
+    pub async fn with_statement_timeout(
+        &self,
+        url: &str,
+        max_connections: u32,
+        statement_timeout: Duration,
+    ) -> Result<Self, Error> {
+        if AnyKind::from_str(url)? != AnyKind::Postgres {
+            return Ok(self.clone());
+        }
+
+        let pool = connection_pool(url, max_connections, Some(statement_timeout)).await?;
+        self.add_statement_timeout_pool(pool.clone());
+
+        Ok(Self {
+            pool,
+            ..self.clone()
+        })
+    }
+
+    /// Keeps a handle to the pool of a derived store, to close its idle connections as well.
+    fn add_statement_timeout_pool(&self, pool: Pool) {
+        self.statement_timeout_pools
+            .lock()
+            .expect("Statement timeout pools got poisoned")
+            .push(pool);
+    }
+
+    /// Closes idle connections of the pool and of the pools of derived stores running statements
+    /// with a timeout, keeping one connection open in each of them.
+    ///
+    /// The pools open new connections again on demand. One connection is kept, as in-memory
+    /// SQLite databases are dropped when their last connection gets closed.
+    ///
+    /// Returns the number of closed connections.
+    pub async fn close_idle_connections(&self) -> usize {
+        let statement_timeout_pools = self
+            .statement_timeout_pools
+            .lock()
+            .expect("Statement timeout pools got poisoned")
+            .clone();
+
+        let mut closed = close_idle_pool_connections(&self.pool).await;
+        for pool in statement_timeout_pools {
+            closed += close_idle_pool_connections(&pool).await;
+        }
+
+        closed
+    }
+
+    /// Delays or fails before running a SQL query when faults are injected, otherwise this does
+    /// nothing.
+    pub(crate) async fn inject_sql_fault(&self) -> Result<(), sqlx::Error> {
+        #[cfg(feature = "chaos")]
+        self.faults.sql().await?;
+
+        Ok(())
     }
 }
 
 /// Re-export of generic connection pool type.
 pub type Pool = AnyPool;
 
+/// Closes idle connections of the given pool, keeping one connection open.
+///
+/// Returns the number of closed connections.
+async fn close_idle_pool_connections(pool: &Pool) -> usize {
+    let mut closed = 0;
+
+    while pool.num_idle() > 1 {
+        let Some(connection) = pool.try_acquire() else {
+            break;
+        };
+
+        // Detached connections do not count towards the size of the pool anymore
+        if connection.detach().close().await.is_ok() {
+            closed += 1;
+        }
+    }
+
+    closed
+}
+
 /// Create database when not existing.
 pub async fn create_database(url: &str) -> Result<()> {
     if !Any::database_exists(url).await? {
@@ -41,17 +190,163 @@ pub async fn create_database(url: &str) -> Result<()> {
 }
 
 /// Create a database agnostic connection pool.
-pub async fn connection_pool(url: &str, max_connections: u32) -> Result<Pool, Error> {
+///
+/// When a statement timeout is given, PostgreSQL aborts every statement exceeding it on the
+/// server side. SQLite does not support statement timeouts, there queries only get cancelled when
+/// the future executing them is dropped.
+pub async fn connection_pool(
+    url: &str,
+    max_connections: u32,
+    statement_timeout: Option<Duration>,
+) -> Result<Pool, Error> {
     let pool: Pool = AnyPoolOptions::new()
         .max_connections(max_connections)
+        .after_connect(move |conn, _meta| {
+            Box::pin(async move {
+                if let (AnyKind::Postgres, Some(timeout)) = (conn.kind(), statement_timeout) {
+                    sqlx::query(&format!("SET statement_timeout = {}", timeout.as_millis()))
+                        .execute(conn)
+                        .await?;
+                }
+
+                Ok(())
+            })
+        })
         .connect(url)
         .await?;
 
     Ok(pool)
 }
 
+/// Returns the number of connections of the pool running statements with a timeout.
+///
+/// With PostgreSQL, GraphQL statements run on a separate pool when a statement timeout is set.
+/// That pool gets half of the maximum number of connections and the main pool the other half, so
+/// together they stay within the configured limit. No connections are split off for SQLite, when
+/// no timeout is set or when the limit is too low to split it.
+pub fn statement_timeout_connections(
+    url: &str,
+    max_connections: u32,
+    statement_timeout: Option<Duration>,
+) -> u32 {
+    match (AnyKind::from_str(url), statement_timeout) {
+        (Ok(AnyKind::Postgres), Some(_)) => max_connections / 2,
+        _ => 0,
+    }
+}
+
+/// Check if the database under the given URL can be reached.
+///
+/// Returns `false` when the database does not exist yet, it gets created when the node starts.
+pub async fn check_database(url: &str) -> Result<bool> {
+    if !Any::database_exists(url).await? {
+        return Ok(false);
+    }
+
+    let pool = connection_pool(url, 1, None).await?;
+    sqlx::query("SELECT 1").execute(&pool).await?;
+    pool.close().await;
+
+    Ok(true)
+}
+
 /// Run any pending database migrations from inside the application.
 pub async fn run_pending_migrations(pool: &Pool) -> Result<()> {
     migrate!().run(pool).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    use super::{connection_pool, create_database, statement_timeout_connections, SqlStore};
+
+    #[tokio::test]
+    async fn close_idle_connections() {
+        let temp_dir = TempDir::new().unwrap();
+        let url = format!(
+            "sqlite:{}",
+            temp_dir.path().join("db.sqlite3").to_string_lossy()
+        );
+        create_database(&url).await.unwrap();
+
+        let pool = connection_pool(&url, 4, None).await.unwrap();
+        let store = SqlStore::new(pool.clone());
+
+        // Open multiple connections at the same time and return them to the pool
+        let connections = vec![
+            pool.acquire().await.unwrap(),
+            pool.acquire().await.unwrap(),
+            pool.acquire().await.unwrap(),
+        ];
+        drop(connections);
+
+        // Connections are returned to the pool in the background
+        while pool.num_idle() < 3 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(store.close_idle_connections().await, 2);
+        assert_eq!(pool.size(), 1);
+
+        // New connections are opened on demand
+        sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+        pool.close().await;
+    }
+
+    #[tokio::test]
+    async fn close_idle_connections_of_statement_timeout_pools() {
+        let temp_dir = TempDir::new().unwrap();
+        let url = format!(
+            "sqlite:{}",
+            temp_dir.path().join("db.sqlite3").to_string_lossy()
+        );
+        create_database(&url).await.unwrap();
+
+        let pool = connection_pool(&url, 4, None).await.unwrap();
+        let timeout_pool = connection_pool(&url, 4, None).await.unwrap();
+        let store = SqlStore::new(pool.clone());
+        store.add_statement_timeout_pool(timeout_pool.clone());
+
+        // Open multiple connections in the pool of the derived store and return them
+        let connections = vec![
+            timeout_pool.acquire().await.unwrap(),
+            timeout_pool.acquire().await.unwrap(),
+        ];
+        drop(connections);
+
+        while timeout_pool.num_idle() < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        // Idle connections of the derived pool get closed via the main store
+        assert_eq!(store.close_idle_connections().await, 1);
+        assert_eq!(timeout_pool.size(), 1);
+
+        pool.close().await;
+        timeout_pool.close().await;
+    }
+
+    #[rstest]
+    #[case::postgres("postgres://localhost/aquadoggo", 32, Some(30), 16)]
+    #[case::postgres_odd("postgres://localhost/aquadoggo", 5, Some(30), 2)]
+    #[case::postgres_too_low("postgres://localhost/aquadoggo", 1, Some(30), 0)]
+    #[case::postgres_no_timeout("postgres://localhost/aquadoggo", 32, None, 0)]
+    #[case::sqlite("sqlite::memory:", 32, Some(30), 0)]
+    fn split_max_connections(
+        #[case] url: &str,
+        #[case] max_connections: u32,
+        #[case] timeout_secs: Option<u64>,
+        #[case] expected: u32,
+    ) {
+        let timeout = timeout_secs.map(Duration::from_secs);
+        assert_eq!(
+            statement_timeout_connections(url, max_connections, timeout),
+            expected
+        );
+    }
+}
@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use sqlx::FromRow;
+
+/// Representation of a row from the `blob_upload_sessions` table as stored in the database.
+///
+/// This table holds all blob uploads which were started via HTTP but not completed yet.
+#[derive(FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct BlobUploadSessionRow {
+    /// Identifier of the upload session.
+    pub session_id: String,
+
+    /// MIME type of the blob.
+    pub mime_type: String,
+
+    /// Total length of the blob in bytes.
+    pub upload_length: i64,
+
+    /// Number of bytes received so far.
+    pub upload_offset: i64,
+
+    /// UNIX timestamp in seconds of the last time data was received.
+    pub updated_at: i64,
+}
@@ -46,3 +46,17 @@ pub struct DocumentRow {
     /// Flag for if this document is deleted.
     pub is_deleted: bool,
 }
+
+/// A struct representing a single row of a document view joined with one operation holding one of
+/// its field values.
+#[derive(FromRow, Debug, Clone)]
+pub struct DocumentViewOperationRow {
+    /// Id of this document view.
+    pub document_view_id: String,
+
+    /// Id of an operation holding a field value of this view.
+    pub operation_id: String,
+
+    /// Id of the current view of the document.
+    pub current_view_id: String,
+}
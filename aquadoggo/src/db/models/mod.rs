@@ -2,19 +2,23 @@
 
 //! Structs representing rows in SQL tables. Needed when coercing results returned from a
 //! query using the `sqlx` library.
+mod blob_upload;
 mod document;
 mod entry;
 mod log;
 mod operation;
+mod peer_author;
 mod query;
 mod task;
 pub mod utils;
 
 pub use self::log::LogHeightRow;
-pub use document::{DocumentRow, DocumentViewFieldRow};
+pub use blob_upload::BlobUploadSessionRow;
+pub use document::{DocumentRow, DocumentViewFieldRow, DocumentViewOperationRow};
 pub use entry::EntryRow;
 pub use operation::OperationFieldsJoinedRow;
+pub use peer_author::PeerAuthorRow;
 #[cfg(test)]
 pub use query::OptionalOwner;
 pub use query::QueryRow;
-pub use task::TaskRow;
+pub use task::{DeadLetterTaskRow, TaskRow};
@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use sqlx::FromRow;
+
+/// Representation of a row from the `peer_authors` table as stored in the database.
+///
+/// This table holds which author public keys have been received from which peers.
+#[derive(FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct PeerAuthorRow {
+    /// Id of the peer the entries were received from.
+    pub peer_id: String,
+
+    /// Public key of the author of the received entries.
+    pub public_key: String,
+
+    /// UNIX timestamp in seconds of the first received entry.
+    pub first_seen: i64,
+
+    /// UNIX timestamp in seconds of the last received entry.
+    pub last_seen: i64,
+
+    /// Number of received entries.
+    pub entries: i64,
+}
@@ -16,3 +16,27 @@ pub struct TaskRow {
     /// `DocumentViewId` of the task input.
     pub document_view_id: Option<String>,
 }
+
+/// Representation of a row from the `dead_letter_tasks` table as stored in the database.
+///
+/// This table holds all tasks which failed too many times and are not retried anymore.
+#[derive(FromRow, Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetterTaskRow {
+    /// Name of the task worker.
+    pub name: String,
+
+    /// `DocumentId` of the task input.
+    pub document_id: Option<String>,
+
+    /// `DocumentViewId` of the task input.
+    pub document_view_id: Option<String>,
+
+    /// Number of failed attempts to process this task.
+    pub attempts: i64,
+
+    /// Error message of the last failed attempt.
+    pub error: String,
+
+    /// UNIX timestamp in seconds of the last failed attempt.
+    pub failed_at: i64,
+}
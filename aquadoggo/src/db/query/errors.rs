@@ -32,4 +32,12 @@ pub enum QueryError {
     /// Search filters can only be applied on strings.
     #[error("Can't apply search filter as field '{0}' is not of type string")]
     FilterInvalidSearch(String),
+
+    /// Null filters can only be applied on document fields.
+    #[error("Can't apply null filter on meta field '{0}'")]
+    FilterInvalidNull(String),
+
+    /// Cursor-based pagination is not supported when ordering by this meta field.
+    #[error("Can't paginate with a cursor when ordering by meta field '{0}'")]
+    PaginationOrderFieldUnsupported(String),
 }
@@ -40,6 +40,9 @@ pub enum FilterBy {
 
     /// Filter elements containing the this search string.
     Contains(OperationValue),
+
+    /// Filter elements which do not have any value for this field.
+    Null,
 }
 
 /// An item representing a single filter setting.
@@ -52,6 +55,9 @@ pub struct FilterSetting {
     pub by: FilterBy,
 
     /// Flag to indicate if filter is negated / inverted.
+    ///
+    /// Negated filters on document fields also match documents which do not have a value for
+    /// this field.
     pub exclusive: bool,
 }
 
@@ -97,29 +103,38 @@ impl FilterSetting {
 /// Internally this struct merges or extends added filter settings as some of them can be optimized
 /// in simple ways.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Filter(Vec<FilterSetting>);
+pub struct Filter {
+    /// Filter settings applied on single fields.
+    settings: Vec<FilterSetting>,
+
+    /// Search string matched against all string fields of a document.
+    search: Option<String>,
+}
 
 impl Filter {
     /// Returns a new `Filter` instance.
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self {
+            settings: Vec::new(),
+            search: None,
+        }
     }
 
     /// Returns the total number of filter settings.
     #[allow(dead_code)]
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.settings.len()
     }
 
     /// Returns a filter setting from a given index.
     #[allow(dead_code)]
     pub fn get(&self, index: usize) -> Option<&FilterSetting> {
-        self.0.get(index)
+        self.settings.get(index)
     }
 
     /// Returns an iterator over all filter settings.
     pub fn iter(&self) -> Iter<FilterSetting> {
-        self.0.iter()
+        self.settings.iter()
     }
 
     /// Helper method to merge or extend existing filterings.
@@ -132,17 +147,20 @@ impl Filter {
     fn upsert_filter_item(&mut self, new_item: FilterSetting) {
         // Check if a field exists we potentially can extend. For this the field needs to:
         // - Have the same field name
-        let index = self.0.iter().position(|item| item.field == new_item.field);
+        let index = self
+            .settings
+            .iter()
+            .position(|item| item.field == new_item.field);
 
         // We haven't found anything matching, just add it to the array
         if index.is_none() {
-            self.0.push(new_item);
+            self.settings.push(new_item);
             return;
         }
 
         // Get a mutable reference to the current field, unwrap since we know that both the index
         // and the element exists at this point
-        let current_item = self.0.get_mut(index.unwrap()).unwrap();
+        let current_item = self.settings.get_mut(index.unwrap()).unwrap();
 
         // Boolean values for the same field we can always easily overwrite
         if let FilterBy::Element(OperationValue::Boolean(_)) = current_item.by {
@@ -150,10 +168,16 @@ impl Filter {
             return;
         }
 
+        // The same goes for checking if a field is set or not
+        if current_item.by == FilterBy::Null && new_item.by == FilterBy::Null {
+            *current_item = new_item;
+            return;
+        }
+
         // We don't merge other fields with different exclusivity, in this case just add it and
         // return early
         if current_item.exclusive != new_item.exclusive {
-            self.0.push(new_item);
+            self.settings.push(new_item);
             return;
         }
 
@@ -207,7 +231,7 @@ impl Filter {
                 current_item.by = filter;
             }
             None => {
-                self.0.push(new_item);
+                self.settings.push(new_item);
             }
         }
     }
@@ -327,6 +351,33 @@ impl Filter {
             true,
         ));
     }
+
+    /// Add a filter (is null) to match all items which do not have a value for this field, or
+    /// when negated (is set) to match all items which do have one.
+    pub fn add_is_null(&mut self, field: &Field, is_null: bool) {
+        self.upsert_filter_item(FilterSetting::new(field, FilterBy::Null, !is_null));
+    }
+
+    /// Remove all filter settings of a field.
+    ///
+    /// This can be used to include deleted documents in a query, which are filtered out by
+    /// default.
+    pub fn remove(&mut self, field: &Field) {
+        self.settings.retain(|setting| &setting.field != field);
+    }
+
+    /// Add a case-insensitive search to match all items which contain the given search string in
+    /// any of their string fields.
+    ///
+    /// Only one search can be set, adding another one replaces the previous search string.
+    pub fn add_search(&mut self, value: &str) {
+        self.search = Some(value.to_string());
+    }
+
+    /// Returns the search string if one was set.
+    pub fn search(&self) -> Option<&str> {
+        self.search.as_deref()
+    }
 }
 
 impl Default for Filter {
@@ -422,6 +473,24 @@ mod tests {
         assert!(filter.get(1).unwrap().exclusive);
     }
 
+    #[test]
+    fn null_filters() {
+        let mut filter = Filter::new();
+        filter.add_is_null(&"description".into(), true);
+        filter.add_is_null(&"title".into(), false);
+
+        assert_eq!(filter.len(), 2);
+        assert_eq!(filter.get(0).unwrap().by, FilterBy::Null);
+        assert!(!filter.get(0).unwrap().exclusive);
+        assert_eq!(filter.get(1).unwrap().by, FilterBy::Null);
+        assert!(filter.get(1).unwrap().exclusive);
+
+        // Checks for the same field overwrite each other
+        filter.add_is_null(&"description".into(), false);
+        assert_eq!(filter.len(), 2);
+        assert!(filter.get(0).unwrap().exclusive);
+    }
+
     #[test]
     fn convert_single_element_filter() {
         let mut filter = Filter::new();
@@ -522,6 +591,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn remove_deleted_filter() {
+        let mut filter = Filter::default();
+        filter.add(&Field::new("animal"), &"panda".into());
+        assert_eq!(filter.len(), 2);
+
+        filter.remove(&Field::Meta(MetaField::Deleted));
+        assert_eq!(filter.len(), 1);
+        assert_eq!(filter.get(0).unwrap().field, Field::new("animal"));
+    }
+
     #[test]
     fn overwrite_deleted_filter() {
         let mut filter = Filter::default();
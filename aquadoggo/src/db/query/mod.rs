@@ -13,5 +13,6 @@ mod validate;
 pub use field::{Field, MetaField};
 pub use filter::{Filter, FilterBy, FilterSetting, LowerBound, UpperBound};
 pub use order::{Direction, Order};
-pub use pagination::{Cursor, Pagination, PaginationField};
+pub use pagination::{Cursor, Pagination, PaginationField, DEFAULT_PAGE_SIZE};
 pub use select::{ApplicationFields, Select};
+pub use validate::validate_pagination;
@@ -15,10 +15,14 @@ pub enum Direction {
 /// Ordering settings which can be used further to construct a database query.
 ///
 /// An ordering determines in which direction and based on what field the results are sorted.
+/// Items sharing the same value can be further sorted by additional fields.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Order {
     pub field: Option<Field>,
     pub direction: Direction,
+
+    /// Additional fields to order by when items have equal values for all previous fields.
+    pub then_by: Vec<(Field, Direction)>,
 }
 
 impl Order {
@@ -28,8 +32,29 @@ impl Order {
         Self {
             field: Some(field.clone()),
             direction: direction.clone(),
+            then_by: Vec::new(),
         }
     }
+
+    /// Returns ordering settings with an additional field to sort items by which have equal
+    /// values for all previous fields.
+    pub fn then_by(mut self, field: &Field, direction: &Direction) -> Self {
+        self.then_by.push((field.clone(), direction.clone()));
+        self
+    }
+
+    /// Returns all fields with their direction to order by, in order of their precedence.
+    pub fn fields(&self) -> Vec<(&Field, &Direction)> {
+        self.field
+            .iter()
+            .map(|field| (field, &self.direction))
+            .chain(
+                self.then_by
+                    .iter()
+                    .map(|(field, direction)| (field, direction)),
+            )
+            .collect()
+    }
 }
 
 impl Default for Order {
@@ -37,6 +62,7 @@ impl Default for Order {
         Self {
             field: None,
             direction: Direction::Ascending,
+            then_by: Vec::new(),
         }
     }
 }
@@ -6,7 +6,7 @@ use p2panda_rs::operation::OperationValue;
 use p2panda_rs::schema::{FieldName, FieldType, Schema};
 
 use crate::db::query::errors::QueryError;
-use crate::db::query::{Field, Filter, FilterBy, MetaField, Order, Select};
+use crate::db::query::{Cursor, Field, Filter, FilterBy, MetaField, Order, Pagination, Select};
 
 /// Helper method to make sure that the chosen type in the query value matches the schema's field
 /// type.
@@ -54,17 +54,19 @@ pub fn validate_query(
         }
     }
 
-    // Make sure field to order actually exists in schema
-    match &order.field {
-        None | Some(Field::Meta(_)) => {
-            // Ordering any meta field or none is always okay
-        }
-        Some(Field::Field(field_name)) => {
-            if !schema_fields.contains_key(field_name) {
-                return Err(QueryError::OrderFieldUnknown(field_name.clone()));
+    // Make sure fields to order actually exist in schema
+    for (field, _) in order.fields() {
+        match field {
+            Field::Meta(_) => {
+                // Ordering any meta field is always okay
+            }
+            Field::Field(field_name) => {
+                if !schema_fields.contains_key(field_name) {
+                    return Err(QueryError::OrderFieldUnknown(field_name.clone()));
+                }
             }
         }
-    };
+    }
 
     // Make sure field to filter exists and filtering value is of correct type
     for field in filter.iter() {
@@ -116,6 +118,11 @@ pub fn validate_query(
                     return Err(QueryError::FilterInvalidSearch(meta_field.to_string()))
                 }
 
+                // Meta fields are always set
+                (FilterBy::Null, _) => {
+                    return Err(QueryError::FilterInvalidNull(meta_field.to_string()))
+                }
+
                 _ => (),
             },
 
@@ -172,14 +179,40 @@ pub fn validate_query(
     Ok(())
 }
 
+/// Validate that the ordering can be used together with the given pagination settings.
+///
+/// Paginating with a cursor requires looking up the ordered values of the document the cursor is
+/// pointing at, which is only supported for application fields, the document id and view id.
+pub fn validate_pagination<C>(pagination: &Pagination<C>, order: &Order) -> Result<(), QueryError>
+where
+    C: Cursor,
+{
+    if pagination.after.is_none() {
+        return Ok(());
+    }
+
+    for (field, _) in order.fields() {
+        if let Field::Meta(
+            meta_field @ (MetaField::Owner | MetaField::Edited | MetaField::Deleted),
+        ) = field
+        {
+            return Err(QueryError::PaginationOrderFieldUnsupported(
+                meta_field.to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::rstest;
 
-    use crate::db::query::{Direction, Filter, Order, Select};
+    use crate::db::query::{Direction, Field, Filter, MetaField, Order, Pagination, Select};
     use crate::test_utils::doggo_schema;
 
-    use super::validate_query;
+    use super::{validate_pagination, validate_query};
 
     #[rstest]
     #[case::defaults(Select::default(), Filter::default(), Order::default())]
@@ -226,6 +259,13 @@ mod tests {
         Order::new(&"message".into(), &Direction::Ascending),
         "Can't apply ordering on unknown field 'message'"
     )]
+    #[case::then_order_unknown_field(
+        Select::default(),
+        Filter::default(),
+        Order::new(&"username".into(), &Direction::Ascending)
+            .then_by(&"message".into(), &Direction::Descending),
+        "Can't apply ordering on unknown field 'message'"
+    )]
     #[case::invalid_meta_field_type(
         Select::default(),
         Filter::new().meta_fields(&[
@@ -266,6 +306,16 @@ mod tests {
         Order::default(),
         "Filter type 'int' for field 'username' is not matching schema type 'str'"
     )]
+    #[case::invalid_null(
+        Select::default(),
+        {
+            let mut filter = Filter::new();
+            filter.add_is_null(&Field::Meta(MetaField::Owner), true);
+            filter
+        },
+        Order::default(),
+        "Can't apply null filter on meta field 'owner'"
+    )]
     fn invalid_queries(
         #[case] select: Select,
         #[case] filter: Filter,
@@ -279,4 +329,27 @@ mod tests {
             expected
         );
     }
+
+    #[rstest]
+    #[case::owner(MetaField::Owner, "owner")]
+    #[case::edited(MetaField::Edited, "edited")]
+    #[case::deleted(MetaField::Deleted, "deleted")]
+    fn invalid_pagination(#[case] meta_field: MetaField, #[case] field_name: &str) {
+        let order = Order::new(&"username".into(), &Direction::Ascending)
+            .then_by(&Field::Meta(meta_field), &Direction::Ascending);
+
+        // Ordering by these meta fields is fine as long as no cursor is given
+        assert!(validate_pagination(&Pagination::<String>::default(), &order).is_ok());
+
+        let pagination = Pagination {
+            after: Some("cursor".to_string()),
+            ..Pagination::default()
+        };
+        assert_eq!(
+            validate_pagination(&pagination, &order)
+                .expect_err("Expect error")
+                .to_string(),
+            format!("Can't paginate with a cursor when ordering by meta field '{field_name}'")
+        );
+    }
 }
@@ -0,0 +1,268 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::time::Duration;
+
+use sqlx::any::{Any, AnyKind, AnyRow};
+use sqlx::pool::PoolConnection;
+use sqlx::{query, Column, Connection, Row};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::types::AdminQueryResult;
+use crate::db::SqlStore;
+
+/// Methods to inspect the database from the admin console and to write to the `audit_log` table.
+impl SqlStore {
+    /// Runs a single SQL `SELECT` statement and returns at most `row_limit` rows.
+    ///
+    /// The statement gets wrapped as a sub-query which makes the database reject anything but
+    /// queries. It is additionally run inside a read-only transaction which gets rolled back in
+    /// any case. PostgreSQL aborts the statement on the server side when it exceeds the given
+    /// timeout.
+    ///
+    /// The query runs in its own task, this makes sure the connection is reset before it is
+    /// returned to the pool, even when the caller stops waiting for the result.
+    pub async fn execute_read_only_query(
+        &self,
+        statement: &str,
+        row_limit: u64,
+        timeout: Duration,
+    ) -> Result<AdminQueryResult, SqlStoreError> {
+        let pool = self.pool.clone();
+        let statement = statement.to_owned();
+
+        tokio::task::spawn(async move {
+            let mut connection = pool
+                .acquire()
+                .await
+                .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+            // SQLite does not support read-only transactions, instead the whole connection is
+            // set to read-only mode until we reset it again
+            let is_sqlite = connection.kind() == AnyKind::Sqlite;
+            if is_sqlite {
+                query("PRAGMA query_only = ON")
+                    .execute(&mut connection)
+                    .await
+                    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+            }
+
+            let rows = fetch_read_only(&mut connection, &statement, row_limit, timeout).await;
+
+            if is_sqlite {
+                if let Err(err) = query("PRAGMA query_only = OFF")
+                    .execute(&mut connection)
+                    .await
+                {
+                    // Do not return a read-only connection to the pool
+                    let _ = connection.detach().close().await;
+                    return Err(SqlStoreError::Transaction(err.to_string()));
+                }
+            }
+
+            Ok(query_result(rows?, row_limit))
+        })
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?
+    }
+
+    /// Inserts an entry into the audit log.
+    pub async fn insert_audit_log_entry(
+        &self,
+        source: &str,
+        message: &str,
+        timestamp: u64,
+    ) -> Result<(), SqlStoreError> {
+        query(
+            "
+            INSERT INTO
+                audit_log (
+                    timestamp,
+                    source,
+                    message
+                )
+            VALUES
+                ($1, $2, $3)
+            ",
+        )
+        .bind(timestamp as i64)
+        .bind(source)
+        .bind(message)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Runs a statement as a sub-query inside a read-only transaction which gets rolled back.
+///
+/// Asks for one more row than `row_limit` to find out if the result was truncated.
+async fn fetch_read_only(
+    connection: &mut PoolConnection<Any>,
+    statement: &str,
+    row_limit: u64,
+    timeout: Duration,
+) -> Result<Vec<AnyRow>, SqlStoreError> {
+    let mut tx = connection
+        .begin()
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+    // Settings of PostgreSQL transactions only apply until the transaction ends
+    if tx.kind() == AnyKind::Postgres {
+        query("SET TRANSACTION READ ONLY")
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query(&format!(
+            "SET LOCAL statement_timeout = {}",
+            timeout.as_millis()
+        ))
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+    }
+
+    let rows = query(&format!(
+        "SELECT * FROM ({}) AS admin_query LIMIT $1",
+        statement
+    ))
+    .bind(row_limit as i64 + 1)
+    .fetch_all(&mut tx)
+    .await
+    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+    tx.rollback()
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+    Ok(rows)
+}
+
+/// Converts the fetched rows into a query result with at most `row_limit` rows.
+fn query_result(rows: Vec<AnyRow>, row_limit: u64) -> AdminQueryResult {
+    let truncated = rows.len() as u64 > row_limit;
+
+    let columns = rows
+        .first()
+        .map(|row| {
+            row.columns()
+                .iter()
+                .map(|column| column.name().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let rows = rows
+        .iter()
+        .take(row_limit as usize)
+        .map(|row| {
+            (0..row.columns().len())
+                .map(|index| decode_value(row, index))
+                .collect()
+        })
+        .collect();
+
+    AdminQueryResult {
+        columns,
+        rows,
+        truncated,
+    }
+}
+
+/// Converts a value of any supported column type into text.
+fn decode_value(row: &AnyRow, index: usize) -> Option<String> {
+    if let Ok(value) = row.try_get::<Option<String>, _>(index) {
+        return value;
+    }
+
+    if let Ok(value) = row.try_get::<Option<i64>, _>(index) {
+        return value.map(|value| value.to_string());
+    }
+
+    if let Ok(value) = row.try_get::<Option<i32>, _>(index) {
+        return value.map(|value| value.to_string());
+    }
+
+    if let Ok(value) = row.try_get::<Option<f64>, _>(index) {
+        return value.map(|value| value.to_string());
+    }
+
+    if let Ok(value) = row.try_get::<Option<bool>, _>(index) {
+        return value.map(|value| value.to_string());
+    }
+
+    Some("<unsupported type>".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::any::AnyKind;
+
+    use crate::test_utils::{test_runner, TestNode};
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn read_only_queries() {
+        test_runner(|node: TestNode| async move {
+            let store = &node.context.store;
+
+            for timestamp in 0..3 {
+                store
+                    .insert_audit_log_entry("test", "Hello, Panda!", timestamp)
+                    .await
+                    .unwrap();
+            }
+
+            let result = store
+                .execute_read_only_query(
+                    "SELECT timestamp, message, NULL AS empty FROM audit_log ORDER BY timestamp",
+                    2,
+                    TIMEOUT,
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(result.columns, vec!["timestamp", "message", "empty"]);
+            assert_eq!(
+                result.rows,
+                vec![
+                    vec![Some("0".into()), Some("Hello, Panda!".into()), None],
+                    vec![Some("1".into()), Some("Hello, Panda!".into()), None],
+                ]
+            );
+            assert!(result.truncated);
+
+            // Statements changing data are rejected by the database
+            assert!(store
+                .execute_read_only_query("DELETE FROM audit_log", 2, TIMEOUT)
+                .await
+                .is_err());
+
+            // Queries run in read-only mode
+            let read_only_sql = match store.pool.any_kind() {
+                AnyKind::Postgres => "SELECT current_setting('transaction_read_only') AS read_only",
+                _ => "SELECT query_only AS read_only FROM pragma_query_only",
+            };
+            let result = store
+                .execute_read_only_query(read_only_sql, 1, TIMEOUT)
+                .await
+                .unwrap();
+            assert!(matches!(
+                result.rows[0][0].as_deref(),
+                Some("on") | Some("1")
+            ));
+
+            // .. but the connections can be written to again afterwards
+            store
+                .insert_audit_log_entry("test", "Hello, Panda!", 3)
+                .await
+                .unwrap();
+        });
+    }
+}
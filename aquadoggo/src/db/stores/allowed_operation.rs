@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use sqlx::{query, query_scalar};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::types::AllowedOperation;
+use crate::db::SqlStore;
+
+/// Methods to interact with the `allowed_operations` table in the database.
+///
+/// When the node runs in allow-list mode only GraphQL operations registered in this table get
+/// executed.
+impl SqlStore {
+    /// Adds an operation to the allow-list.
+    ///
+    /// Returns `false` if the operation was already allowed.
+    pub async fn allow_operation(
+        &self,
+        operation: &AllowedOperation,
+    ) -> Result<bool, SqlStoreError> {
+        let (kind, value) = operation.columns();
+
+        let result = query(
+            "
+            INSERT INTO
+                allowed_operations (
+                    kind,
+                    value
+                )
+            VALUES
+                ($1, $2)
+            ON CONFLICT DO NOTHING
+            ",
+        )
+        .bind(kind)
+        .bind(value)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes an operation from the allow-list.
+    ///
+    /// Returns `false` if the operation was not allowed.
+    pub async fn disallow_operation(
+        &self,
+        operation: &AllowedOperation,
+    ) -> Result<bool, SqlStoreError> {
+        let (kind, value) = operation.columns();
+
+        let result = query(
+            "
+            DELETE FROM
+                allowed_operations
+            WHERE
+                kind = $1
+                AND value = $2
+            ",
+        )
+        .bind(kind)
+        .bind(value)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns true if an operation with the given query hash or operation name was allowed.
+    pub async fn is_operation_allowed(
+        &self,
+        hash: &str,
+        name: Option<&str>,
+    ) -> Result<bool, SqlStoreError> {
+        let count: i64 = query_scalar(
+            "
+            SELECT
+                COUNT(*)
+            FROM
+                allowed_operations
+            WHERE
+                (kind = 'hash' AND value = $1)
+                OR (kind = 'name' AND value = $2)
+            ",
+        )
+        .bind(hash)
+        .bind(name)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::types::AllowedOperation;
+    use crate::test_utils::{test_runner, TestNode};
+
+    #[test]
+    fn allow_and_disallow_operations() {
+        test_runner(|node: TestNode| async move {
+            let store = &node.context.store;
+            let by_hash = AllowedOperation::Hash("abcd".into());
+            let by_name = AllowedOperation::Name("Messages".into());
+
+            assert!(!store.is_operation_allowed("abcd", None).await.unwrap());
+
+            assert!(store.allow_operation(&by_hash).await.unwrap());
+            assert!(!store.allow_operation(&by_hash).await.unwrap());
+            assert!(store.allow_operation(&by_name).await.unwrap());
+
+            assert!(store.is_operation_allowed("abcd", None).await.unwrap());
+            assert!(store
+                .is_operation_allowed("ef01", Some("Messages"))
+                .await
+                .unwrap());
+            assert!(!store
+                .is_operation_allowed("ef01", Some("Other"))
+                .await
+                .unwrap());
+
+            // Hashes and names are not mixed up
+            assert!(!store.is_operation_allowed("Messages", None).await.unwrap());
+
+            assert!(store.disallow_operation(&by_hash).await.unwrap());
+            assert!(!store.disallow_operation(&by_hash).await.unwrap());
+            assert!(!store.is_operation_allowed("abcd", None).await.unwrap());
+        });
+    }
+}
@@ -1,18 +1,22 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use std::num::NonZeroU64;
+use std::sync::Arc;
 
 use async_stream::try_stream;
 use bytes::{BufMut, BytesMut};
 use futures::Stream;
 use p2panda_rs::document::traits::AsDocument;
 use p2panda_rs::document::{DocumentId, DocumentViewId};
+use p2panda_rs::operation::plain::{PlainOperation, PlainValue};
+use p2panda_rs::operation::traits::Schematic;
 use p2panda_rs::operation::OperationValue;
 use p2panda_rs::schema::validate::MAX_BLOB_PIECE_LENGTH;
 use p2panda_rs::schema::{Schema, SchemaId};
 use p2panda_rs::storage_provider::traits::DocumentStore;
-use sqlx::{query_scalar, AnyPool};
+use sqlx::{query, query_scalar, AnyPool};
 
+use crate::config::BlobLimits;
 use crate::db::errors::{BlobStoreError, SqlStoreError};
 use crate::db::query::{Filter, Order, Pagination, PaginationField, Select};
 use crate::db::stores::query::{PaginationCursor, Query, RelationList};
@@ -37,11 +41,18 @@ pub type BlobData = Vec<u8>;
 pub struct BlobStream {
     store: SqlStore,
     pagination_cursor: Option<PaginationCursor>,
+    document_id: DocumentId,
     document_view_id: DocumentViewId,
     num_pieces: usize,
     length: usize,
     expected_num_pieces: usize,
     expected_length: usize,
+
+    /// Data of the whole blob when it was found in the cache.
+    cached: Option<Arc<BlobData>>,
+
+    /// Data read so far, kept to add the blob to the cache after it was validated.
+    buffer: Option<BlobData>,
 }
 
 impl BlobStream {
@@ -62,14 +73,29 @@ impl BlobStream {
             _ => unreachable!(), // We already validated that this is a blob document
         };
 
+        // Don't assemble blobs which exceed the limits of this node, they might have been
+        // accepted before the limits were configured
+        check_blob_limits(
+            &store.blob_limits,
+            Some(expected_num_pieces),
+            Some(expected_length as u64),
+        )?;
+
+        let cached = store.blob_cache.get(document.id(), document.view_id());
+        let buffer = (cached.is_none() && store.blob_cache.fits(expected_length))
+            .then(|| Vec::with_capacity(expected_length));
+
         Ok(Self {
             store: store.to_owned(),
             pagination_cursor: None,
+            document_id: document.id().to_owned(),
             document_view_id: document.view_id().to_owned(),
             num_pieces: 0,
             length: 0,
             expected_length,
             expected_num_pieces,
+            cached,
+            buffer,
         })
     }
 
@@ -107,6 +133,12 @@ impl BlobStream {
 
         self.length += buf.len();
 
+        // Stop streaming as soon as we know that the blob is larger than claimed, there is no
+        // need to read the remaining pieces
+        if self.length > self.expected_length {
+            return Err(BlobStoreError::IncorrectLength);
+        }
+
         Ok(buf.to_vec())
     }
 
@@ -129,7 +161,8 @@ impl BlobStream {
     /// Establishes a data stream of blob data.
     ///
     /// The stream ends when all data has been written, at the end the blob data gets validated
-    /// against the expected blob length.
+    /// against the expected blob length. Validated blobs are added to the cache, cached blobs are
+    /// streamed from memory without querying their pieces.
     ///
     /// To consume this stream in form of an iterator it is required to use the `pin_mut` macro.
     // NOTE: Clippy does not understand that this macro generates code which asks for an explicit
@@ -137,26 +170,95 @@ impl BlobStream {
     #[allow(clippy::needless_lifetimes)]
     pub fn read_all<'a>(&'a mut self) -> impl Stream<Item = Result<BlobData, BlobStoreError>> + 'a {
         try_stream! {
-            loop {
-                let blob_data = self.next_chunk().await?;
-
-                if blob_data.is_empty() {
-                    self.validate()?;
-                    break;
+            if let Some(cached) = self.cached.clone() {
+                for chunk in cached.chunks(BLOB_QUERY_PAGE_SIZE as usize * MAX_BLOB_PIECE_LENGTH) {
+                    yield chunk.to_vec();
                 }
+            } else {
+                loop {
+                    let blob_data = self.next_chunk().await?;
+
+                    if blob_data.is_empty() {
+                        self.validate()?;
+
+                        if let Some(buffer) = self.buffer.take() {
+                            self.store
+                                .blob_cache
+                                .insert(&self.document_id, &self.document_view_id, buffer);
+                        }
+
+                        break;
+                    }
 
-                yield blob_data;
+                    if let Some(buffer) = self.buffer.as_mut() {
+                        buffer.extend_from_slice(&blob_data);
+                    }
+
+                    yield blob_data;
+                }
             }
         }
     }
 }
 
+/// Checks the number of pieces and length of a blob against the limits of this node.
+fn check_blob_limits(
+    limits: &BlobLimits,
+    num_pieces: Option<usize>,
+    length: Option<u64>,
+) -> Result<(), BlobStoreError> {
+    match (limits.max_pieces, num_pieces) {
+        (Some(max_pieces), Some(num_pieces)) if num_pieces > max_pieces => {
+            return Err(BlobStoreError::TooManyPieces(max_pieces))
+        }
+        _ => (),
+    }
+
+    match (limits.max_size_bytes, length) {
+        (Some(max_size_bytes), Some(length)) if length > max_size_bytes => {
+            return Err(BlobStoreError::TooLarge(max_size_bytes))
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
 impl SqlStore {
+    /// Checks if a blob operation which is about to be published exceeds the limits of this node.
+    ///
+    /// Only the fields contained in the operation are checked, update operations might change
+    /// only some of them. Operations of other schemas are always accepted.
+    pub fn validate_blob_operation(
+        &self,
+        plain_operation: &PlainOperation,
+    ) -> Result<(), BlobStoreError> {
+        if plain_operation.schema_id() != &SchemaId::Blob(1) {
+            return Ok(());
+        }
+
+        let fields = match plain_operation.fields() {
+            Some(fields) => fields,
+            None => return Ok(()),
+        };
+
+        let num_pieces = match fields.get("pieces") {
+            Some(PlainValue::PinnedRelationList(pieces)) => Some(pieces.len()),
+            _ => None,
+        };
+
+        let length = match fields.get("length") {
+            Some(PlainValue::Integer(length)) => Some((*length).max(0) as u64),
+            _ => None,
+        };
+
+        check_blob_limits(&self.blob_limits, num_pieces, length)
+    }
+
     /// Get data stream for one blob from the store, identified by it's document id.
     pub async fn get_blob(&self, id: &DocumentId) -> Result<Option<BlobStream>, BlobStoreError> {
         if let Some(document) = self.get_document(id).await? {
-            let document = validate_blob_pieces(self, document).await?;
-            Ok(Some(BlobStream::new(self, document)?))
+            Ok(Some(self.open_blob_stream(document).await?))
         } else {
             Ok(None)
         }
@@ -168,15 +270,36 @@ impl SqlStore {
         view_id: &DocumentViewId,
     ) -> Result<Option<BlobStream>, BlobStoreError> {
         if let Some(document) = self.get_document_by_view_id(view_id).await? {
-            let document = validate_blob_pieces(self, document).await?;
-            Ok(Some(BlobStream::new(self, document)?))
+            Ok(Some(self.open_blob_stream(document).await?))
         } else {
             Ok(None)
         }
     }
 
+    /// Returns a data stream for a blob document.
+    ///
+    /// The pieces of cached blobs were already validated when they got assembled and are not
+    /// queried again.
+    async fn open_blob_stream(
+        &self,
+        document: impl AsDocument,
+    ) -> Result<BlobStream, BlobStoreError> {
+        if self
+            .blob_cache
+            .get(document.id(), document.view_id())
+            .is_some()
+        {
+            return BlobStream::new(self, document);
+        }
+
+        let document = validate_blob_pieces(self, document).await?;
+        BlobStream::new(self, document)
+    }
+
     /// Purge blob data from the node _if_ it is not related to from another document.
     pub async fn purge_blob(&self, document_id: &DocumentId) -> Result<bool, SqlStoreError> {
+        self.blob_cache.invalidate(document_id);
+
         // Collect the view id of any existing document views which contain a relation to the blob
         // which is the purge target.
         let blob_reverse_relations = reverse_relations(&self.pool, document_id, None).await?;
@@ -231,6 +354,57 @@ impl SqlStore {
         Ok(should_purge)
     }
 
+    /// Purge all blob pieces which are not referred to by any blob document view anymore, for
+    /// example after a blob got updated with new pieces.
+    ///
+    /// Pieces get published before the blob document referring to them, only pieces received
+    /// before the given UNIX timestamp are considered to not purge blobs which are still being
+    /// published. Returns the ids of all purged pieces.
+    pub async fn purge_unreferenced_blob_pieces(
+        &self,
+        received_before: u64,
+    ) -> Result<Vec<DocumentId>, SqlStoreError> {
+        // Entries stored before the `received_at` column existed do not have a timestamp, they
+        // are old enough in any case
+        let blob_piece_ids: Vec<String> = query_scalar(
+            "
+            SELECT
+                documents.document_id
+            FROM
+                documents
+            LEFT JOIN
+                entries
+            ON
+                entries.entry_hash = documents.document_id
+            WHERE
+                documents.schema_id = 'blob_piece_v1'
+            AND
+                (entries.received_at IS NULL OR entries.received_at < $1)
+            ",
+        )
+        .bind(received_before as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| SqlStoreError::Transaction(e.to_string()))?;
+
+        let mut purged = Vec::new();
+        for blob_piece_id in blob_piece_ids {
+            let blob_piece_id: DocumentId = blob_piece_id
+                .parse()
+                .expect("Document Id's from the store are valid");
+
+            let blob_piece_reverse_relations =
+                reverse_relations(&self.pool, &blob_piece_id, Some(SchemaId::Blob(1))).await?;
+
+            if blob_piece_reverse_relations.is_empty() {
+                self.purge_document(&blob_piece_id).await?;
+                purged.push(blob_piece_id);
+            }
+        }
+
+        Ok(purged)
+    }
+
     /// Get ids for all blob documents which are related to from any view of the passed document.
     pub async fn get_blob_child_relations(
         &self,
@@ -282,6 +456,155 @@ impl SqlStore {
             })
             .collect())
     }
+
+    /// Insert the meta data of a blob derived from the given blob view by a blob hook.
+    ///
+    /// Existing entries with the same name get overwritten.
+    pub async fn insert_derived_blob(
+        &self,
+        view_id: &DocumentViewId,
+        name: &str,
+        mime_type: &str,
+    ) -> Result<(), SqlStoreError> {
+        query(
+            "
+            INSERT INTO
+                derived_blobs (
+                    blob_view_id,
+                    name,
+                    mime_type
+                )
+            VALUES
+                ($1, $2, $3)
+            ON CONFLICT (blob_view_id, name) DO UPDATE SET
+                mime_type = $3
+            ",
+        )
+        .bind(view_id.to_string())
+        .bind(name)
+        .bind(mime_type)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get the MIME type of a blob derived from the given blob view, identified by its name.
+    pub async fn get_derived_blob(
+        &self,
+        view_id: &DocumentViewId,
+        name: &str,
+    ) -> Result<Option<String>, SqlStoreError> {
+        query_scalar(
+            "
+            SELECT
+                mime_type
+            FROM
+                derived_blobs
+            WHERE
+                blob_view_id = $1
+                AND name = $2
+            ",
+        )
+        .bind(view_id.to_string())
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))
+    }
+
+    /// Insert the SHA256 hash of an assembled blob.
+    ///
+    /// Existing hashes of the same blob view get overwritten.
+    pub async fn insert_blob_hash(
+        &self,
+        view_id: &DocumentViewId,
+        hash: &str,
+    ) -> Result<(), SqlStoreError> {
+        query(
+            "
+            INSERT INTO
+                blob_hashes (
+                    blob_view_id,
+                    hash
+                )
+            VALUES
+                ($1, $2)
+            ON CONFLICT (blob_view_id) DO UPDATE SET
+                hash = $2
+            ",
+        )
+        .bind(view_id.to_string())
+        .bind(hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get the SHA256 hash of an assembled blob.
+    pub async fn get_blob_hash(
+        &self,
+        view_id: &DocumentViewId,
+    ) -> Result<Option<String>, SqlStoreError> {
+        query_scalar(
+            "
+            SELECT
+                hash
+            FROM
+                blob_hashes
+            WHERE
+                blob_view_id = $1
+            ",
+        )
+        .bind(view_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))
+    }
+
+    /// Delete the SHA256 hash of an assembled blob.
+    pub async fn delete_blob_hash(&self, view_id: &DocumentViewId) -> Result<(), SqlStoreError> {
+        query(
+            "
+            DELETE FROM
+                blob_hashes
+            WHERE
+                blob_view_id = $1
+            ",
+        )
+        .bind(view_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Delete the meta data of all blobs derived from the given blob view.
+    ///
+    /// Returns the names of the deleted derived blobs.
+    pub async fn delete_derived_blobs(
+        &self,
+        view_id: &DocumentViewId,
+    ) -> Result<Vec<String>, SqlStoreError> {
+        query_scalar(
+            "
+            DELETE FROM
+                derived_blobs
+            WHERE
+                blob_view_id = $1
+            RETURNING
+                name
+            ",
+        )
+        .bind(view_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))
+    }
 }
 
 /// Throws an error when database does not contain all related blob pieces yet.
@@ -371,16 +694,24 @@ async fn reverse_relations(
 
 #[cfg(test)]
 mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
     use bytes::{BufMut, BytesMut};
     use futures::{pin_mut, StreamExt};
     use p2panda_rs::document::DocumentId;
     use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::decode::decode_operation;
+    use p2panda_rs::operation::encode::encode_operation;
+    use p2panda_rs::operation::OperationBuilder;
     use p2panda_rs::schema::SchemaId;
     use p2panda_rs::test_utils::fixtures::{key_pair, random_document_view_id};
     use p2panda_rs::test_utils::generate_random_bytes;
     use rstest::rstest;
+    use sqlx::query_scalar;
 
+    use crate::config::BlobLimits;
     use crate::db::errors::BlobStoreError;
+    use crate::db::SqlStore;
     use crate::test_utils::{
         add_blob, add_document, add_schema_and_documents, assert_query, populate_and_materialize,
         populate_store_config, test_runner, update_document, PopulateStoreConfig, TestNode,
@@ -708,4 +1039,151 @@ mod tests {
             assert!(result.is_ok(), "{:#?}", result)
         })
     }
+
+    #[rstest]
+    fn purge_unreferenced_blob_pieces(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let blob_data = "Hello, World!".as_bytes();
+            let blob_view_id = add_blob(&mut node, blob_data, 7, "text/plain", &key_pair).await;
+
+            // Update the blob document to point at a new blob piece.
+            let new_blob_piece = add_document(
+                &mut node,
+                &SchemaId::BlobPiece(1),
+                vec![("data", "more blob data".as_bytes().into())],
+                &key_pair,
+            )
+            .await;
+            let _ = update_document(
+                &mut node,
+                &SchemaId::Blob(1),
+                vec![("pieces", vec![new_blob_piece.clone()].into())],
+                &blob_view_id,
+                &key_pair,
+            )
+            .await;
+
+            // The old pieces are still referred to by the previous view of the blob.
+            let store = &node.context.store;
+            let later = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                + 60;
+            let purged = store.purge_unreferenced_blob_pieces(later).await.unwrap();
+            assert!(purged.is_empty());
+
+            // After the previous view got garbage collected the old pieces are not needed anymore.
+            assert!(store.prune_document_view(&blob_view_id).await.unwrap());
+
+            // Pieces received after the given timestamp are kept.
+            let purged = store.purge_unreferenced_blob_pieces(0).await.unwrap();
+            assert!(purged.is_empty());
+
+            let purged = store.purge_unreferenced_blob_pieces(later).await.unwrap();
+            assert_eq!(purged.len(), 2);
+            assert_query(&node, "SELECT entry_hash FROM entries", 3).await;
+            assert_query(&node, "SELECT document_id FROM documents", 2).await;
+
+            // The piece of the current blob view stays.
+            let new_blob_piece_id: DocumentId = new_blob_piece.to_string().parse().unwrap();
+            assert!(!purged.contains(&new_blob_piece_id));
+        })
+    }
+
+    #[rstest]
+    fn blob_limits(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            // Blob with 13 bytes in two pieces
+            let blob_data = "Hello, World!".as_bytes();
+            let blob_view_id = add_blob(&mut node, blob_data, 7, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+
+            let with_limits = |max_pieces, max_size_bytes| {
+                SqlStore::new(node.context.store.pool.clone()).with_blob_limits(BlobLimits {
+                    max_pieces,
+                    max_size_bytes,
+                })
+            };
+
+            // Blobs within the limits get assembled
+            let store = with_limits(Some(2), Some(13));
+            let blob_stream = store.get_blob(&document_id).await.unwrap().unwrap();
+            assert_eq!(read_data_from_stream(blob_stream).await.unwrap(), blob_data);
+
+            // Blobs exceeding them are rejected
+            let store = with_limits(Some(1), None);
+            assert!(matches!(
+                store.get_blob(&document_id).await,
+                Err(BlobStoreError::TooManyPieces(1))
+            ));
+            let store = with_limits(None, Some(12));
+            assert!(matches!(
+                store.get_blob_by_view_id(&blob_view_id).await,
+                Err(BlobStoreError::TooLarge(12))
+            ));
+
+            // Operations of blobs exceeding the limits are rejected before they get published
+            let operation = OperationBuilder::new(&SchemaId::Blob(1))
+                .fields(&[
+                    ("length", 20.into()),
+                    ("mime_type", "text/plain".into()),
+                    ("pieces", vec![random_document_view_id()].into()),
+                ])
+                .build()
+                .unwrap();
+            let plain_operation = decode_operation(&encode_operation(&operation).unwrap()).unwrap();
+            assert!(node
+                .context
+                .store
+                .validate_blob_operation(&plain_operation)
+                .is_ok());
+            assert!(matches!(
+                store.validate_blob_operation(&plain_operation),
+                Err(BlobStoreError::TooLarge(12))
+            ));
+        })
+    }
+
+    #[rstest]
+    fn serve_cached_blobs(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let blob_data = "Hello, World!".as_bytes();
+            let blob_view_id = add_blob(&mut node, blob_data, 7, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+
+            let store = SqlStore::new(node.context.store.pool.clone()).with_blob_cache(1024);
+
+            // Assembling the blob adds it to the cache
+            let blob_stream = store.get_blob(&document_id).await.unwrap().unwrap();
+            assert_eq!(read_data_from_stream(blob_stream).await.unwrap(), blob_data);
+
+            // Cached blobs are served without their pieces
+            let piece_ids: Vec<String> =
+                query_scalar("SELECT document_id FROM documents WHERE schema_id = 'blob_piece_v1'")
+                    .fetch_all(&store.pool)
+                    .await
+                    .unwrap();
+            for piece_id in piece_ids {
+                store
+                    .purge_document(&piece_id.parse().unwrap())
+                    .await
+                    .unwrap();
+            }
+
+            let blob_stream = store
+                .get_blob_by_view_id(&blob_view_id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(read_data_from_stream(blob_stream).await.unwrap(), blob_data);
+
+            // After invalidating the cache the pieces are queried again
+            store.blob_cache.invalidate(&document_id);
+            assert!(matches!(
+                store.get_blob(&document_id).await,
+                Err(BlobStoreError::MissingPieces)
+            ));
+        })
+    }
 }
@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use sqlx::{query, query_as, query_scalar};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::models::BlobUploadSessionRow;
+use crate::db::SqlStore;
+
+/// Methods to interact with the `blob_upload_sessions` and `blob_upload_chunks` tables in the
+/// database.
+impl SqlStore {
+    /// Inserts a new, empty blob upload session into the database.
+    pub async fn insert_blob_upload_session(
+        &self,
+        session_id: &str,
+        mime_type: &str,
+        upload_length: u64,
+        timestamp: u64,
+    ) -> Result<(), SqlStoreError> {
+        query(
+            "
+            INSERT INTO
+                blob_upload_sessions (
+                    session_id,
+                    mime_type,
+                    upload_length,
+                    upload_offset,
+                    updated_at
+                )
+            VALUES
+                ($1, $2, $3, 0, $4)
+            ",
+        )
+        .bind(session_id)
+        .bind(mime_type)
+        .bind(upload_length as i64)
+        .bind(timestamp as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get a blob upload session by its id.
+    pub async fn get_blob_upload_session(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<BlobUploadSessionRow>, SqlStoreError> {
+        query_as::<_, BlobUploadSessionRow>(
+            "
+            SELECT
+                session_id,
+                mime_type,
+                upload_length,
+                upload_offset,
+                updated_at
+            FROM
+                blob_upload_sessions
+            WHERE
+                session_id = $1
+            ",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))
+    }
+
+    /// Appends a chunk of data to a blob upload session.
+    ///
+    /// The chunk is only accepted if it starts exactly at the current offset of the session and
+    /// does not exceed the total length of the blob. Returns `false` if the chunk was rejected.
+    pub async fn insert_blob_upload_chunk(
+        &self,
+        session_id: &str,
+        chunk_offset: u64,
+        data: &[u8],
+        timestamp: u64,
+    ) -> Result<bool, SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        // Move the offset of the session forward, this fails when another chunk was appended in
+        // the meantime
+        let result = query(
+            "
+            UPDATE
+                blob_upload_sessions
+            SET
+                upload_offset = upload_offset + $3,
+                updated_at = $4
+            WHERE
+                session_id = $1
+                AND upload_offset = $2
+                AND upload_offset + $3 <= upload_length
+            ",
+        )
+        .bind(session_id)
+        .bind(chunk_offset as i64)
+        .bind(data.len() as i64)
+        .bind(timestamp as i64)
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            tx.rollback()
+                .await
+                .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+            return Ok(false);
+        }
+
+        query(
+            "
+            INSERT INTO
+                blob_upload_chunks (
+                    session_id,
+                    chunk_offset,
+                    data
+                )
+            VALUES
+                ($1, $2, $3)
+            ",
+        )
+        .bind(session_id)
+        .bind(chunk_offset as i64)
+        .bind(hex::encode(data))
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(true)
+    }
+
+    /// Get the first chunk of a blob upload session starting at or after the given offset.
+    ///
+    /// Returns the offset of the chunk together with its data. Chunks are read one-by-one to keep
+    /// the memory footprint small when assembling large blobs.
+    pub async fn get_blob_upload_chunk(
+        &self,
+        session_id: &str,
+        from_offset: u64,
+    ) -> Result<Option<(u64, Vec<u8>)>, SqlStoreError> {
+        let row: Option<(i64, String)> = query_as(
+            "
+            SELECT
+                chunk_offset,
+                data
+            FROM
+                blob_upload_chunks
+            WHERE
+                session_id = $1
+                AND chunk_offset >= $2
+            ORDER BY
+                chunk_offset ASC
+            LIMIT
+                1
+            ",
+        )
+        .bind(session_id)
+        .bind(from_offset as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        match row {
+            Some((chunk_offset, data)) => {
+                let data =
+                    hex::decode(data).map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+                Ok(Some((chunk_offset as u64, data)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Deletes a blob upload session together with all its chunks.
+    ///
+    /// Returns `true` if the session existed.
+    pub async fn delete_blob_upload_session(
+        &self,
+        session_id: &str,
+    ) -> Result<bool, SqlStoreError> {
+        let result = query(
+            "
+            DELETE FROM
+                blob_upload_sessions
+            WHERE
+                session_id = $1
+            ",
+        )
+        .bind(session_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes all blob upload sessions which did not receive any data since the given timestamp.
+    ///
+    /// Returns the ids of the deleted sessions.
+    pub async fn delete_blob_upload_sessions_before(
+        &self,
+        timestamp: u64,
+    ) -> Result<Vec<String>, SqlStoreError> {
+        query_scalar(
+            "
+            DELETE FROM
+                blob_upload_sessions
+            WHERE
+                updated_at < $1
+            RETURNING
+                session_id
+            ",
+        )
+        .bind(timestamp as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{test_runner, TestNode};
+
+    #[test]
+    fn append_chunks_to_upload_session() {
+        test_runner(|node: TestNode| async move {
+            let store = &node.context.store;
+
+            store
+                .insert_blob_upload_session("abc", "text/plain", 13, 100)
+                .await
+                .unwrap();
+
+            // Chunks need to start at the current offset
+            assert!(store
+                .insert_blob_upload_chunk("abc", 0, b"Hello, ", 101)
+                .await
+                .unwrap());
+            assert!(!store
+                .insert_blob_upload_chunk("abc", 0, b"World!", 102)
+                .await
+                .unwrap());
+
+            // Chunks can not exceed the total length
+            assert!(!store
+                .insert_blob_upload_chunk("abc", 7, b"World!!", 102)
+                .await
+                .unwrap());
+            assert!(store
+                .insert_blob_upload_chunk("abc", 7, b"World!", 102)
+                .await
+                .unwrap());
+
+            let session = store.get_blob_upload_session("abc").await.unwrap().unwrap();
+            assert_eq!(session.upload_offset, 13);
+            assert_eq!(session.updated_at, 102);
+
+            let chunk = store.get_blob_upload_chunk("abc", 1).await.unwrap();
+            assert_eq!(chunk, Some((7, b"World!".to_vec())));
+            let chunk = store.get_blob_upload_chunk("abc", 8).await.unwrap();
+            assert_eq!(chunk, None);
+        });
+    }
+
+    #[test]
+    fn delete_abandoned_upload_sessions() {
+        test_runner(|node: TestNode| async move {
+            let store = &node.context.store;
+
+            store
+                .insert_blob_upload_session("old", "text/plain", 13, 100)
+                .await
+                .unwrap();
+            store
+                .insert_blob_upload_session("new", "text/plain", 13, 200)
+                .await
+                .unwrap();
+            store
+                .insert_blob_upload_chunk("old", 0, b"Hello", 100)
+                .await
+                .unwrap();
+
+            let deleted = store.delete_blob_upload_sessions_before(150).await.unwrap();
+            assert_eq!(deleted, vec!["old".to_string()]);
+
+            // Chunks got removed together with their session
+            assert_eq!(store.get_blob_upload_chunk("old", 0).await.unwrap(), None);
+            assert!(store
+                .get_blob_upload_session("new")
+                .await
+                .unwrap()
+                .is_some());
+
+            assert!(store.delete_blob_upload_session("new").await.unwrap());
+            assert!(!store.delete_blob_upload_session("new").await.unwrap());
+        });
+    }
+}
@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use sqlx::{query, query_as, query_scalar};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::models::DeadLetterTaskRow;
+use crate::db::stores::task::{insert_task_row, task_from_columns, task_input_columns};
+use crate::db::types::DeadLetterTask;
+use crate::db::SqlStore;
+use crate::materializer::{Task, TaskInput};
+
+/// Methods to interact with the `task_failures` and `dead_letter_tasks` tables in the database.
+impl SqlStore {
+    /// Counts a failed attempt to process a task.
+    ///
+    /// Returns the number of failed attempts for this task, including this one.
+    pub async fn record_task_failure(&self, task: &Task<TaskInput>) -> Result<u64, SqlStoreError> {
+        let (document_id, document_view_id) = task_input_columns(task.input());
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        let result = query(
+            "
+            UPDATE
+                task_failures
+            SET
+                attempts = attempts + 1
+            WHERE
+                name = $1
+                AND COALESCE(document_id, '0') = COALESCE($2, '0')
+                AND COALESCE(document_view_id, '0') = COALESCE($3, '0')
+            ",
+        )
+        .bind(task.worker_name())
+        .bind(&document_id)
+        .bind(&document_view_id)
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            query(
+                "
+                INSERT INTO
+                    task_failures (
+                        name,
+                        document_id,
+                        document_view_id,
+                        attempts
+                    )
+                VALUES
+                    ($1, $2, $3, 1)
+                ",
+            )
+            .bind(task.worker_name())
+            .bind(&document_id)
+            .bind(&document_view_id)
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+        }
+
+        let attempts: i64 = query_scalar(
+            "
+            SELECT
+                attempts
+            FROM
+                task_failures
+            WHERE
+                name = $1
+                AND COALESCE(document_id, '0') = COALESCE($2, '0')
+                AND COALESCE(document_view_id, '0') = COALESCE($3, '0')
+            ",
+        )
+        .bind(task.worker_name())
+        .bind(&document_id)
+        .bind(&document_view_id)
+        .fetch_one(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(attempts as u64)
+    }
+
+    /// Resets the number of failed attempts of a task, for example after it succeeded.
+    pub async fn clear_task_failures(&self, task: &Task<TaskInput>) -> Result<(), SqlStoreError> {
+        let (document_id, document_view_id) = task_input_columns(task.input());
+
+        query(
+            "
+            DELETE FROM
+                task_failures
+            WHERE
+                name = $1
+                AND COALESCE(document_id, '0') = COALESCE($2, '0')
+                AND COALESCE(document_view_id, '0') = COALESCE($3, '0')
+            ",
+        )
+        .bind(task.worker_name())
+        .bind(document_id)
+        .bind(document_view_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Resets the number of failed attempts of all tasks.
+    ///
+    /// Used when starting the node, failure counters of tasks interrupted by the last shutdown
+    /// would otherwise never be cleared when they succeed.
+    pub async fn clear_all_task_failures(&self) -> Result<(), SqlStoreError> {
+        query("DELETE FROM task_failures")
+            .execute(&self.pool)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Moves a task into the dead-letter queue, replacing any previous entry of the same task.
+    pub async fn insert_dead_letter_task(
+        &self,
+        dead_letter_task: &DeadLetterTask,
+    ) -> Result<(), SqlStoreError> {
+        let task = &dead_letter_task.task;
+        let (document_id, document_view_id) = task_input_columns(task.input());
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        for table in ["task_failures", "dead_letter_tasks"].iter() {
+            query(&format!(
+                "
+                DELETE FROM
+                    {table}
+                WHERE
+                    name = $1
+                    AND COALESCE(document_id, '0') = COALESCE($2, '0')
+                    AND COALESCE(document_view_id, '0') = COALESCE($3, '0')
+                "
+            ))
+            .bind(task.worker_name())
+            .bind(&document_id)
+            .bind(&document_view_id)
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+        }
+
+        query(
+            "
+            INSERT INTO
+                dead_letter_tasks (
+                    name,
+                    document_id,
+                    document_view_id,
+                    attempts,
+                    error,
+                    failed_at
+                )
+            VALUES
+                ($1, $2, $3, $4, $5, $6)
+            ",
+        )
+        .bind(task.worker_name())
+        .bind(&document_id)
+        .bind(&document_view_id)
+        .bind(dead_letter_task.attempts as i64)
+        .bind(&dead_letter_task.error)
+        .bind(dead_letter_task.failed_at as i64)
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Returns all tasks in the dead-letter queue, the most recently failed first.
+    pub async fn get_dead_letter_tasks(&self) -> Result<Vec<DeadLetterTask>, SqlStoreError> {
+        let rows = query_as::<_, DeadLetterTaskRow>(
+            "
+            SELECT
+                name,
+                document_id,
+                document_view_id,
+                attempts,
+                error,
+                failed_at
+            FROM
+                dead_letter_tasks
+            ORDER BY
+                failed_at DESC
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DeadLetterTask {
+                task: task_from_columns(&row.name, row.document_id, row.document_view_id),
+                attempts: row.attempts as u64,
+                error: row.error,
+                failed_at: row.failed_at as u64,
+            })
+            .collect())
+    }
+
+    /// Removes a task from the dead-letter queue.
+    ///
+    /// Returns `true` if the task was in the queue.
+    pub async fn remove_dead_letter_task(
+        &self,
+        task: &Task<TaskInput>,
+    ) -> Result<bool, SqlStoreError> {
+        let (document_id, document_view_id) = task_input_columns(task.input());
+
+        let result = query(
+            "
+            DELETE FROM
+                dead_letter_tasks
+            WHERE
+                name = $1
+                AND COALESCE(document_id, '0') = COALESCE($2, '0')
+                AND COALESCE(document_view_id, '0') = COALESCE($3, '0')
+            ",
+        )
+        .bind(task.worker_name())
+        .bind(document_id)
+        .bind(document_view_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Moves a task from the dead-letter queue into the pending tasks.
+    ///
+    /// Both happens in one transaction, the task is picked up again after a restart in case the
+    /// node crashes before it was dispatched.
+    ///
+    /// Returns `true` if the task was in the dead-letter queue.
+    pub async fn retry_dead_letter_task(
+        &self,
+        task: &Task<TaskInput>,
+    ) -> Result<bool, SqlStoreError> {
+        let (document_id, document_view_id) = task_input_columns(task.input());
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        let result = query(
+            "
+            DELETE FROM
+                dead_letter_tasks
+            WHERE
+                name = $1
+                AND COALESCE(document_id, '0') = COALESCE($2, '0')
+                AND COALESCE(document_view_id, '0') = COALESCE($3, '0')
+            ",
+        )
+        .bind(task.worker_name())
+        .bind(document_id)
+        .bind(document_view_id)
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        insert_task_row(&mut tx, task).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::test_utils::fixtures::document_id;
+    use rstest::rstest;
+
+    use crate::db::types::DeadLetterTask;
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{test_runner, TestNode};
+
+    #[rstest]
+    fn move_failed_task_to_dead_letters(document_id: DocumentId) {
+        test_runner(|node: TestNode| async move {
+            let store = &node.context.store;
+            let task = Task::new("reduce", TaskInput::DocumentId(document_id));
+
+            assert_eq!(store.record_task_failure(&task).await.unwrap(), 1);
+            assert_eq!(store.record_task_failure(&task).await.unwrap(), 2);
+
+            // Succeeding tasks reset their failed attempts
+            store.clear_task_failures(&task).await.unwrap();
+            assert_eq!(store.record_task_failure(&task).await.unwrap(), 1);
+
+            // Starting the node resets failed attempts of all tasks
+            store.clear_all_task_failures().await.unwrap();
+            assert_eq!(store.record_task_failure(&task).await.unwrap(), 1);
+
+            let dead_letter_task = DeadLetterTask {
+                task: task.clone(),
+                attempts: 3,
+                error: "Something went wrong".into(),
+                failed_at: 100,
+            };
+            store
+                .insert_dead_letter_task(&dead_letter_task)
+                .await
+                .unwrap();
+            assert_eq!(
+                store.get_dead_letter_tasks().await.unwrap(),
+                vec![dead_letter_task.clone()]
+            );
+
+            // Failed attempts got reset when the task moved into the dead-letter queue
+            assert_eq!(store.record_task_failure(&task).await.unwrap(), 1);
+
+            assert!(store.remove_dead_letter_task(&task).await.unwrap());
+            assert!(!store.remove_dead_letter_task(&task).await.unwrap());
+            assert_eq!(store.get_dead_letter_tasks().await.unwrap(), vec![]);
+
+            // Retried tasks become pending again
+            store
+                .insert_dead_letter_task(&dead_letter_task)
+                .await
+                .unwrap();
+            assert!(store.retry_dead_letter_task(&task).await.unwrap());
+            assert!(!store.retry_dead_letter_task(&task).await.unwrap());
+            assert_eq!(store.get_dead_letter_tasks().await.unwrap(), vec![]);
+            assert_eq!(store.get_tasks().await.unwrap(), vec![task]);
+        });
+    }
+}
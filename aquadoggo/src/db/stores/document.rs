@@ -28,22 +28,34 @@
 //! view if it has already been materialised and stored. Although it is possible to construct a
 //! document at any point in its history if all operations are retained, we use a system of "pinned
 //! relations" to identify and materialise only views we explicitly wish to keep.
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use log::debug;
 use p2panda_rs::document::traits::AsDocument;
-use p2panda_rs::document::{DocumentId, DocumentView, DocumentViewId};
+use p2panda_rs::document::{DocumentBuilder, DocumentId, DocumentView, DocumentViewId};
+use p2panda_rs::identity::PublicKey;
 use p2panda_rs::schema::SchemaId;
 use p2panda_rs::storage_provider::error::DocumentStorageError;
-use p2panda_rs::storage_provider::traits::DocumentStore;
+use p2panda_rs::storage_provider::traits::{DocumentStore, OperationStore};
 use sqlx::any::AnyQueryResult;
-use sqlx::{query, query_as, query_scalar, Any, Transaction};
+use sqlx::{query, query_as, query_scalar, Any, FromRow, Row, Transaction};
 
 use crate::db::models::utils::parse_document_view_field_rows;
-use crate::db::models::{DocumentRow, DocumentViewFieldRow};
-use crate::db::types::StorageDocument;
+use crate::db::models::{DocumentRow, DocumentViewFieldRow, DocumentViewOperationRow};
+use crate::db::query::{ApplicationFields, Select};
+use crate::db::types::{DocumentChange, StorageDocument, StorageDocumentView};
 use crate::db::Pool;
 use crate::db::SqlStore;
 
+/// Number of documents internal callers retrieve at once when iterating over all documents of a
+/// schema.
+pub const DOCUMENTS_PAGE_SIZE: u64 = 100;
+
+/// Maximum number of document view ids in one query for their field values, keeps the statements
+/// well below the length limit of SQLite.
+const VIEW_IDS_PER_QUERY: usize = 500;
+
 #[async_trait]
 impl DocumentStore for SqlStore {
     type Document = StorageDocument;
@@ -58,6 +70,208 @@ impl DocumentStore for SqlStore {
         &self,
         id: &DocumentId,
     ) -> Result<Option<Self::Document>, DocumentStorageError> {
+        self.get_document_with_fields(id, None).await
+    }
+
+    /// Get a document from the database by `DocumentViewId`.
+    ///
+    /// Get's a document at a specific point in its history. Only returns views that have already
+    /// been materialised and persisted in the store. These are likely to be "pinned views" which
+    /// are relations from other documents, in which case the materialiser service will have
+    /// identified and materialised them ready for querying.
+    ///
+    /// Any view which existed as part of a document which is now deleted is ignored.
+    ///
+    /// An error is returned only if a fatal database error occurs.
+    async fn get_document_by_view_id(
+        &self,
+        view_id: &DocumentViewId,
+    ) -> Result<Option<StorageDocument>, DocumentStorageError> {
+        self.get_document_by_view_id_with_fields(view_id, None)
+            .await
+    }
+
+    /// Get all documents which follow the passed schema id.
+    ///
+    /// Retrieves all documents, with their most current views, which follow the specified schema.
+    /// Deleted documents are not included.
+    ///
+    /// An error is returned only if a fatal database error occurs.
+    async fn get_documents_by_schema(
+        &self,
+        schema_id: &SchemaId,
+    ) -> Result<Vec<Self::Document>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        // Retrieve all rows from the document table where the passed schema_id matches.
+        let document_rows = query_as::<_, DocumentRow>(
+            "
+            SELECT
+                documents.document_id,
+                documents.document_view_id,
+                documents.schema_id,
+                operations_v1.public_key,
+                documents.is_deleted
+            FROM
+                documents
+            LEFT JOIN operations_v1
+                ON
+                    operations_v1.operation_id = documents.document_id
+            WHERE
+                documents.schema_id = $1 AND documents.is_deleted = false
+            ",
+        )
+        .bind(schema_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        get_documents_from_rows(&self.pool, document_rows).await
+    }
+}
+
+/// Storage API offering an interface for inserting documents and document views into the database.
+///
+/// These methods are specific to aquadoggos approach to document caching and are defined outside
+/// of the required `DocumentStore` trait.
+impl SqlStore {
+    /// Get a document from the store by its `DocumentId`, only retrieving the values of the
+    /// application fields mentioned in the selection.
+    ///
+    /// Behaves like `get_document` otherwise, meta data of the document is always included.
+    pub async fn get_document_with_select(
+        &self,
+        id: &DocumentId,
+        select: &Select,
+    ) -> Result<Option<StorageDocument>, DocumentStorageError> {
+        self.get_document_with_fields(id, Some(&select.application_fields()))
+            .await
+    }
+
+    /// Get a document from the store by `DocumentViewId`, only retrieving the values of the
+    /// application fields mentioned in the selection.
+    ///
+    /// Behaves like `get_document_by_view_id` otherwise, meta data of the document is always
+    /// included. Views which were never materialised are rebuilt from the operations of the
+    /// document when they are all present, see `materialize_document_view`.
+    ///
+    /// This is used when resolving pinned relations via GraphQL. `get_document_by_view_id` does
+    /// not do this, materializer tasks rely on it to find out if a view was materialised yet.
+    pub async fn get_document_by_view_id_with_select(
+        &self,
+        view_id: &DocumentViewId,
+        select: &Select,
+    ) -> Result<Option<StorageDocument>, DocumentStorageError> {
+        let fields = select.application_fields();
+
+        match self
+            .get_document_by_view_id_with_fields(view_id, Some(&fields))
+            .await?
+        {
+            Some(document) => Ok(Some(document)),
+            None => {
+                if !self.materialize_document_view(view_id).await? {
+                    return Ok(None);
+                }
+
+                self.get_document_by_view_id_with_fields(view_id, Some(&fields))
+                    .await
+            }
+        }
+    }
+
+    /// Get documents which follow the passed schema id, ordered by document id.
+    ///
+    /// Returns at most `first` documents with a document id greater than `after` when given. Use
+    /// the id of the last returned document as the cursor for the next page to iterate over large
+    /// collections without loading all of them into memory at once. Deleted documents are not
+    /// included.
+    pub async fn get_documents_by_schema_paginated(
+        &self,
+        schema_id: &SchemaId,
+        first: u64,
+        after: Option<&DocumentId>,
+    ) -> Result<Vec<StorageDocument>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        let document_rows = query_as::<_, DocumentRow>(
+            "
+            SELECT
+                documents.document_id,
+                documents.document_view_id,
+                documents.schema_id,
+                operations_v1.public_key,
+                documents.is_deleted
+            FROM
+                documents
+            LEFT JOIN operations_v1
+                ON
+                    operations_v1.operation_id = documents.document_id
+            WHERE
+                documents.schema_id = $1
+                AND documents.is_deleted = false
+                AND documents.document_id > $2
+            ORDER BY
+                documents.document_id ASC
+            LIMIT
+                $3
+            ",
+        )
+        .bind(schema_id.to_string())
+        .bind(after.map_or("", |document_id| document_id.as_str()))
+        .bind(first as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        get_documents_from_rows(&self.pool, document_rows).await
+    }
+
+    /// Count the documents which follow the passed schema id.
+    ///
+    /// Deleted documents are not included. This is a cheap alternative to fetching a collection
+    /// when only its size is of interest.
+    pub async fn count_documents_by_schema(
+        &self,
+        schema_id: &SchemaId,
+    ) -> Result<u64, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        let (count,) = query_as::<_, (i64,)>(
+            "
+            SELECT
+                COUNT(*)
+            FROM
+                documents
+            WHERE
+                documents.schema_id = $1 AND documents.is_deleted = false
+            ",
+        )
+        .bind(schema_id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        Ok(count as u64)
+    }
+
+    /// Get a document from the store by its `DocumentId` with the values of the given fields, or
+    /// all of them when none are given.
+    async fn get_document_with_fields(
+        &self,
+        id: &DocumentId,
+        fields: Option<&ApplicationFields>,
+    ) -> Result<Option<StorageDocument>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
         // Retrieve one row from the document table matching on the passed id.
         let document_row = query_as::<_, DocumentRow>(
             "
@@ -87,12 +301,12 @@ impl DocumentStore for SqlStore {
             None => return Ok(None),
         };
 
-        // We now want to retrieve the view (current key-value map) for this document, as we
-        // already filtered out deleted documents in the query above we can expect all documents
+        // We now want to retrieve the view (current key-value map) for this document, as deleted
+        // documents were already filtered out when querying the rows we can expect all documents
         // we handle here to have an associated view in the database.
         let document_view_id = document_row.document_view_id.parse().unwrap();
         let document_view_field_rows =
-            get_document_view_field_rows(&self.pool, &document_view_id).await?;
+            get_document_view_field_rows(&self.pool, &document_view_id, fields).await?;
         // this method assumes all values coming from the db are already validated and so
         // unwraps where errors might occur.
         let document_view_fields = Some(parse_document_view_field_rows(document_view_field_rows));
@@ -110,20 +324,17 @@ impl DocumentStore for SqlStore {
         Ok(Some(document))
     }
 
-    /// Get a document from the database by `DocumentViewId`.
-    ///
-    /// Get's a document at a specific point in its history. Only returns views that have already
-    /// been materialised and persisted in the store. These are likely to be "pinned views" which
-    /// are relations from other documents, in which case the materialiser service will have
-    /// identified and materialised them ready for querying.
-    ///
-    /// Any view which existed as part of a document which is now deleted is ignored.
-    ///
-    /// An error is returned only if a fatal database error occurs.
-    async fn get_document_by_view_id(
+    /// Get a document from the database by `DocumentViewId` with the values of the given fields,
+    /// or all of them when none are given.
+    async fn get_document_by_view_id_with_fields(
         &self,
         view_id: &DocumentViewId,
+        fields: Option<&ApplicationFields>,
     ) -> Result<Option<StorageDocument>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
         // Retrieve the id of the document which the passed view id comes from.
         let document_id: Option<String> = query_scalar(
             "
@@ -177,10 +388,11 @@ impl DocumentStore for SqlStore {
             None => return Ok(None),
         };
 
-        // We now want to retrieve the view (current key-value map) for this document, as we
-        // already filtered out deleted documents in the query above we can expect all documents
+        // We now want to retrieve the view (current key-value map) for this document, as deleted
+        // documents were already filtered out when querying the rows we can expect all documents
         // we handle here to have an associated view in the database.
-        let document_view_field_rows = get_document_view_field_rows(&self.pool, view_id).await?;
+        let document_view_field_rows =
+            get_document_view_field_rows(&self.pool, view_id, fields).await?;
 
         // This method assumes all values coming from the db are already validated and so
         // unwraps where errors might occur.
@@ -199,80 +411,6 @@ impl DocumentStore for SqlStore {
         Ok(Some(document))
     }
 
-    /// Get all documents which follow the passed schema id.
-    ///
-    /// Retrieves all documents, with their most current views, which follow the specified schema.
-    /// Deleted documents are not included.
-    ///
-    /// An error is returned only if a fatal database error occurs.
-    async fn get_documents_by_schema(
-        &self,
-        schema_id: &SchemaId,
-    ) -> Result<Vec<Self::Document>, DocumentStorageError> {
-        // Retrieve all rows from the document table where the passed schema_id matches.
-        let document_rows = query_as::<_, DocumentRow>(
-            "
-            SELECT
-                documents.document_id,
-                documents.document_view_id,
-                documents.schema_id,
-                operations_v1.public_key,
-                documents.is_deleted
-            FROM
-                documents
-            LEFT JOIN operations_v1
-                ON
-                    operations_v1.operation_id = documents.document_id
-            WHERE
-                documents.schema_id = $1 AND documents.is_deleted = false
-            ",
-        )
-        .bind(schema_id.to_string())
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
-
-        // If no rows were found we can already return an empty vec here.
-        if document_rows.is_empty() {
-            return Ok(vec![]);
-        }
-
-        // For every row we found we want to retrieve the current view as well.
-        let mut documents: Vec<StorageDocument> = vec![];
-        for document_row in document_rows {
-            let document_view_id = document_row.document_view_id.parse().unwrap();
-            // We now want to retrieve the view (current key-value map) for this document, as we
-            // already filtered out deleted documents in the query above we can expect all documents
-            // we handle here to have an associated view in the database.
-            let document_view_field_rows =
-                get_document_view_field_rows(&self.pool, &document_view_id).await?;
-            // this method assumes all values coming from the db are already validated and so
-            // unwraps where errors might occur.
-            let document_view_fields =
-                Some(parse_document_view_field_rows(document_view_field_rows));
-
-            // Construct a `StorageDocument` based on the retrieved values.
-            let document = StorageDocument {
-                id: document_row.document_id.parse().unwrap(),
-                view_id: document_view_id,
-                schema_id: document_row.schema_id.parse().unwrap(),
-                fields: document_view_fields,
-                author: document_row.public_key.parse().unwrap(),
-                deleted: document_row.is_deleted,
-            };
-
-            documents.push(document)
-        }
-
-        Ok(documents)
-    }
-}
-
-/// Storage API offering an interface for inserting documents and document views into the database.
-///
-/// These methods are specific to aquadoggos approach to document caching and are defined outside
-/// of the required `DocumentStore` trait.
-impl SqlStore {
     /// Insert a document into the database.
     ///
     /// This method inserts or updates a row in the documents table and then inserts the documents
@@ -287,11 +425,16 @@ impl SqlStore {
     /// An error is returned in the case of a fatal database error.
     ///
     /// Note: "out-of-date" document views will remain in storage when a document already existed
-    /// and is updated. If they are not needed for anything else they can be garbage collected.
+    /// and is updated. The "garbage_collection" materializer task removes them afterwards with
+    /// `prune_document_view` unless they are pinned by a relation.
     pub async fn insert_document(
         &self,
         document: &impl AsDocument,
     ) -> Result<(), DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
         // Start a transaction, any db insertions after this point, and before the `commit()` can
         // be rolled back in the event of an error.
         let mut tx = self
@@ -333,6 +476,10 @@ impl SqlStore {
         document_id: &DocumentId,
         schema_id: &SchemaId,
     ) -> Result<(), DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
         // Start a transaction, any db insertions after this point, and before the `commit()`
         // will be rolled back in the event of an error.
         let mut tx = self
@@ -352,22 +499,357 @@ impl SqlStore {
             }
         };
 
-        // Insert the document view fields into the `document_view_fields` table. Rollback
-        // insertions if an error occurs.
-        match insert_document_fields(&mut tx, document_view).await {
-            Ok(_) => (),
-            Err(err) => {
-                tx.rollback()
-                    .await
-                    .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
-                return Err(err);
-            }
-        };
+        // Insert the document view fields into the `document_view_fields` table. Rollback
+        // insertions if an error occurs.
+        match insert_document_fields(&mut tx, document_view).await {
+            Ok(_) => (),
+            Err(err) => {
+                tx.rollback()
+                    .await
+                    .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+                return Err(err);
+            }
+        };
+
+        // Commit the tx here as no errors occurred.
+        tx.commit()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))
+    }
+
+    /// Get all documents which follow the passed schema id and were created by the passed public
+    /// key.
+    ///
+    /// Retrieves all documents, with their most current views, which follow the specified schema
+    /// and whose CREATE operation was signed by the specified public key. Deleted documents are
+    /// not included, the returned documents are ordered by their id.
+    ///
+    /// An error is returned only if a fatal database error occurs.
+    pub async fn get_documents_by_public_key(
+        &self,
+        schema_id: &SchemaId,
+        public_key: &PublicKey,
+    ) -> Result<Vec<StorageDocument>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        // Look up the CREATE operations of the public key first, this query is backed by an index
+        // and does not need to scan the documents of all other authors.
+        let document_ids: Vec<String> = query_scalar(
+            "
+            SELECT
+                operations_v1.document_id
+            FROM
+                operations_v1
+            WHERE
+                operations_v1.public_key = $1
+                AND operations_v1.schema_id = $2
+                AND operations_v1.action = 'create'
+            ",
+        )
+        .bind(public_key.to_string())
+        .bind(schema_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        // This method assumes all values coming from the db are already validated and so unwraps
+        // where errors might occur.
+        let document_ids: Vec<DocumentId> = document_ids
+            .iter()
+            .map(|document_id| document_id.parse().unwrap())
+            .collect();
+
+        self.get_documents_by_ids(&document_ids).await
+    }
+
+    /// Get all documents of a schema which relate to the passed document.
+    ///
+    /// Only the current views of the documents are looked at. A document relates to the passed
+    /// one if any of its relation or relation list fields contains the document id, or any of its
+    /// pinned relation or pinned relation list fields contains a view of the document. Deleted
+    /// documents are not included, the returned documents are ordered by their id.
+    ///
+    /// An error is returned only if a fatal database error occurs.
+    pub async fn get_referencing_documents(
+        &self,
+        schema_id: &SchemaId,
+        document_id: &DocumentId,
+    ) -> Result<Vec<StorageDocument>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        // Look up the relation fields pointing at the document first, this query is backed by an
+        // index over relation values and does not need to scan the fields of all documents.
+        let document_ids: Vec<String> = query_scalar(
+            "
+            SELECT DISTINCT
+                documents.document_id
+            FROM
+                operation_fields_v1
+            JOIN document_view_fields
+                ON
+                    document_view_fields.operation_id = operation_fields_v1.operation_id
+                AND
+                    document_view_fields.name = operation_fields_v1.name
+            JOIN documents
+                ON
+                    documents.document_view_id = document_view_fields.document_view_id
+            WHERE
+                operation_fields_v1.field_type IN (
+                    'relation',
+                    'relation_list',
+                    'pinned_relation',
+                    'pinned_relation_list'
+                )
+                AND (
+                    operation_fields_v1.value = $1
+                    OR operation_fields_v1.value IN (
+                        SELECT
+                            document_views.document_view_id
+                        FROM
+                            document_views
+                        WHERE
+                            document_views.document_id = $1
+                    )
+                )
+                AND documents.schema_id = $2
+            ",
+        )
+        .bind(document_id.as_str())
+        .bind(schema_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        // This method assumes all values coming from the db are already validated and so unwraps
+        // where errors might occur.
+        let document_ids: Vec<DocumentId> = document_ids
+            .iter()
+            .map(|document_id| document_id.parse().unwrap())
+            .collect();
+
+        self.get_documents_by_ids(&document_ids).await
+    }
+
+    /// Get many documents from the database by their `DocumentId`s.
+    ///
+    /// Documents and their field values are retrieved within a single query instead of one query
+    /// per document. Documents which were not found or are deleted are not included in the
+    /// result, the returned documents are ordered by their id.
+    ///
+    /// An error is returned only if a fatal database error occurs.
+    pub async fn get_documents_by_ids(
+        &self,
+        ids: &[DocumentId],
+    ) -> Result<Vec<StorageDocument>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        // If no document ids were passed then don't query the database. Instead return an empty
+        // vec now already.
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let document_ids_str: String = ids
+            .iter()
+            .map(|document_id| format!("'{}'", document_id.as_str()))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        // Every row contains the document itself and one of its field values, for list values
+        // one row exists for every item in the list.
+        let rows = query(&format!(
+            "
+            SELECT
+                documents.document_id,
+                documents.document_view_id,
+                documents.schema_id,
+                operations_v1.public_key,
+                documents.is_deleted,
+                document_view_fields.operation_id,
+                document_view_fields.name,
+                operation_fields_v1.list_index,
+                operation_fields_v1.field_type,
+                operation_fields_v1.value
+            FROM
+                documents
+            LEFT JOIN operations_v1
+                ON
+                    operations_v1.operation_id = documents.document_id
+            LEFT JOIN document_view_fields
+                ON
+                    document_view_fields.document_view_id = documents.document_view_id
+            LEFT JOIN operation_fields_v1
+                ON
+                    document_view_fields.operation_id = operation_fields_v1.operation_id
+                AND
+                    document_view_fields.name = operation_fields_v1.name
+            WHERE
+                documents.document_id IN ({document_ids_str})
+                AND documents.is_deleted = false
+            ORDER BY
+                documents.document_id, operation_fields_v1.list_index ASC
+            ",
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        // Group the field rows by the document they belong to
+        let mut document_rows: Vec<(DocumentRow, Vec<DocumentViewFieldRow>)> = Vec::new();
+        for row in rows {
+            let document_row = DocumentRow::from_row(&row)
+                .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+            let field_row = DocumentViewFieldRow::from_row(&row)
+                .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+            match document_rows.last_mut() {
+                Some((current, field_rows)) if current.document_id == document_row.document_id => {
+                    field_rows.push(field_row);
+                }
+                _ => document_rows.push((document_row, vec![field_row])),
+            }
+        }
+
+        // This method assumes all values coming from the db are already validated and so unwraps
+        // where errors might occur.
+        Ok(document_rows
+            .into_iter()
+            .map(|(document_row, field_rows)| StorageDocument {
+                id: document_row.document_id.parse().unwrap(),
+                view_id: document_row.document_view_id.parse().unwrap(),
+                schema_id: document_row.schema_id.parse().unwrap(),
+                fields: Some(parse_document_view_field_rows(field_rows)),
+                author: document_row.public_key.parse().unwrap(),
+                deleted: document_row.is_deleted,
+            })
+            .collect())
+    }
+
+    /// Get all documents of a schema whose current view changed after the given change sequence
+    /// number.
+    ///
+    /// Every time the materializer inserts or updates a document it gets assigned the next number
+    /// of a monotonically increasing change sequence. Consumers keep the highest sequence number
+    /// they have seen and pass it as the cursor on their next call to only receive documents which
+    /// changed since, pass `0` to receive all documents. Changes are ordered by their sequence
+    /// number, deleted documents are included without their fields.
+    ///
+    /// At most `limit` changes are returned at once, consumers continue with the sequence number
+    /// of the last returned change to receive the next page.
+    ///
+    /// An error is returned only if a fatal database error occurs.
+    pub async fn get_documents_changed_since(
+        &self,
+        schema_id: &SchemaId,
+        cursor: u64,
+        limit: u64,
+    ) -> Result<Vec<DocumentChange>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        let rows = query(
+            "
+            SELECT
+                documents.document_id,
+                documents.document_view_id,
+                documents.schema_id,
+                operations_v1.public_key,
+                documents.is_deleted,
+                documents.change_seq
+            FROM
+                documents
+            LEFT JOIN operations_v1
+                ON
+                    operations_v1.operation_id = documents.document_id
+            WHERE
+                documents.schema_id = $1
+                AND documents.change_seq > $2
+            ORDER BY
+                documents.change_seq ASC
+            LIMIT
+                $3
+            ",
+        )
+        .bind(schema_id.to_string())
+        .bind(cursor as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        let mut changes: Vec<(i64, DocumentRow)> = Vec::with_capacity(rows.len());
+        for row in rows {
+            let change_seq: i64 = row
+                .try_get("change_seq")
+                .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+            let document_row = DocumentRow::from_row(&row)
+                .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+            changes.push((change_seq, document_row));
+        }
 
-        // Commit the tx here as no errors occurred.
-        tx.commit()
-            .await
-            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))
+        // Fetch the fields of all documents which were not deleted at once
+        let document_ids: Vec<DocumentId> = changes
+            .iter()
+            .filter(|(_, document_row)| !document_row.is_deleted)
+            .map(|(_, document_row)| document_row.document_id.parse().unwrap())
+            .collect();
+        let mut documents: HashMap<DocumentId, StorageDocument> = self
+            .get_documents_by_ids(&document_ids)
+            .await?
+            .into_iter()
+            .map(|document| (document.id.clone(), document))
+            .collect();
+
+        // This method assumes all values coming from the db are already validated and so unwraps
+        // where errors might occur.
+        Ok(changes
+            .into_iter()
+            .map(|(change_seq, document_row)| {
+                let document_id: DocumentId = document_row.document_id.parse().unwrap();
+                let document = documents.remove(&document_id).unwrap_or(StorageDocument {
+                    id: document_id,
+                    view_id: document_row.document_view_id.parse().unwrap(),
+                    schema_id: document_row.schema_id.parse().unwrap(),
+                    fields: None,
+                    author: document_row.public_key.parse().unwrap(),
+                    deleted: document_row.is_deleted,
+                });
+
+                DocumentChange {
+                    change_seq: change_seq as u64,
+                    document,
+                }
+            })
+            .collect())
+    }
+
+    /// Get the change sequence number which was assigned last.
+    ///
+    /// Passing it as the cursor to `get_documents_changed_since` only returns documents changing
+    /// from now on.
+    pub async fn get_latest_change_seq(&self) -> Result<u64, DocumentStorageError> {
+        let change_seq: i64 = query_scalar(
+            "
+            SELECT
+                value
+            FROM
+                document_change_sequence
+            WHERE
+                id = 'documents'
+            ",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        Ok(change_seq as u64)
     }
 
     /// Get the ids for all document views for a document which are currently materialized to the store.
@@ -402,6 +884,70 @@ impl SqlStore {
             .collect())
     }
 
+    /// Get all document views of a document which are currently materialized to the store.
+    ///
+    /// Every view contains the ids of the operations holding its field values and a flag
+    /// indicating if it is the current view of the document. Views are ordered by their id.
+    ///
+    /// An error is returned only if a fatal database error occurs.
+    pub async fn get_document_views(
+        &self,
+        document_id: &DocumentId,
+    ) -> Result<Vec<StorageDocumentView>, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        let rows = query_as::<_, DocumentViewOperationRow>(
+            "
+            SELECT DISTINCT
+                document_views.document_view_id,
+                document_view_fields.operation_id,
+                documents.document_view_id AS current_view_id
+            FROM
+                document_views
+            JOIN documents
+                ON
+                    documents.document_id = document_views.document_id
+            JOIN document_view_fields
+                ON
+                    document_view_fields.document_view_id = document_views.document_view_id
+            WHERE
+                document_views.document_id = $1
+            ORDER BY
+                document_views.document_view_id, document_view_fields.operation_id
+            ",
+        )
+        .bind(document_id.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
+
+        // Group the operations by the view they belong to, rows are already ordered by view id.
+        let mut views: Vec<StorageDocumentView> = Vec::new();
+        for row in rows {
+            let view_id: DocumentViewId = row
+                .document_view_id
+                .parse()
+                .expect("Document view id's coming from the store should be valid");
+            let operation_id = row
+                .operation_id
+                .parse()
+                .expect("Operation id's coming from the store should be valid");
+
+            match views.last_mut() {
+                Some(view) if view.id == view_id => view.operations.push(operation_id),
+                _ => views.push(StorageDocumentView {
+                    is_current: row.document_view_id == row.current_view_id,
+                    id: view_id,
+                    operations: vec![operation_id],
+                }),
+            }
+        }
+
+        Ok(views)
+    }
+
     /// Get the ids of all documents which are related to from another document view.
     pub async fn get_child_document_ids(
         &self,
@@ -482,6 +1028,86 @@ impl SqlStore {
             .collect())
     }
 
+    /// Materialise a document view from the operations of its document and persist it in the store.
+    /// Returns a boolean which indicates if the view is available now.
+    ///
+    /// The materialiser only stores historic views which are pinned by a relation once it learned
+    /// about them, they might be missing when they get requested before that. This rebuilds them
+    /// on demand, given the document itself was materialised already and all operations of the
+    /// view exist locally. Views of deleted documents are not materialised.
+    ///
+    /// Views built this way are "dangling" when nothing pins them, the next garbage collection of
+    /// the document will remove them again. At most ten views per second get materialised this
+    /// way, `false` is returned when this limit was reached.
+    pub async fn materialize_document_view(
+        &self,
+        view_id: &DocumentViewId,
+    ) -> Result<bool, DocumentStorageError> {
+        // Find the document this view belongs to through any of its operations
+        let operation_id = view_id.iter().next().expect("View ids are never empty");
+        let document_id = match self
+            .get_document_id_by_operation_id(operation_id)
+            .await
+            .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?
+        {
+            Some(document_id) => document_id,
+            None => return Ok(false),
+        };
+
+        // Only materialise views of documents which were already materialised and not deleted
+        let document_exists: Option<String> = query_scalar(
+            "
+            SELECT
+                document_id
+            FROM
+                documents
+            WHERE
+                document_id = $1 AND is_deleted = false
+            ",
+        )
+        .bind(document_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        if document_exists.is_none() {
+            return Ok(false);
+        }
+
+        let operations = self
+            .get_operations_by_document_id(&document_id)
+            .await
+            .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
+
+        // Building fails when operations of this view are missing or it does not belong to the
+        // document
+        let document = match DocumentBuilder::from(&operations).build_to_view_id(view_id.to_owned())
+        {
+            Ok((document, _)) if !document.is_deleted() => document,
+            _ => return Ok(false),
+        };
+
+        // Don't let clients cause a large amount of writes by requesting many old views
+        if !self.view_budget.try_acquire() {
+            debug!(
+                "Skip materialising document view {} on demand, too many requests",
+                view_id
+            );
+            return Ok(false);
+        }
+
+        self.insert_document_view(
+            &document.view().expect("Document is not deleted"),
+            document.id(),
+            document.schema_id(),
+        )
+        .await?;
+
+        debug!("Materialised document view {} on demand", view_id);
+
+        Ok(true)
+    }
+
     /// Attempt to remove a document view from the store. Returns a boolean which indicates if the
     /// removal took place.
     ///
@@ -559,6 +1185,37 @@ impl SqlStore {
         Ok(document_view_id.is_some())
     }
 
+    /// Check if a view of a non-deleted document exists in the store.
+    ///
+    /// Cheaper than `get_document_by_view_id` as no document and fields are loaded.
+    pub async fn document_view_exists(
+        &self,
+        document_view_id: &DocumentViewId,
+    ) -> Result<bool, DocumentStorageError> {
+        let exists: bool = query_scalar(
+            "
+            SELECT EXISTS (
+                SELECT
+                    1
+                FROM
+                    document_views
+                JOIN documents
+                    ON
+                        documents.document_id = document_views.document_id
+                WHERE
+                    document_views.document_view_id = $1
+                    AND documents.is_deleted = false
+            )
+            ",
+        )
+        .bind(document_view_id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
+
+        Ok(exists)
+    }
+
     /// Purge a document from the store by its id.
     ///
     /// This removes entries, operations and any materialized documents which exist.
@@ -627,11 +1284,136 @@ impl SqlStore {
     }
 }
 
+// Helper method for retrieving the current views of the documents found in the `documents` table.
+async fn get_documents_from_rows(
+    pool: &Pool,
+    document_rows: Vec<DocumentRow>,
+) -> Result<Vec<StorageDocument>, DocumentStorageError> {
+    // If no rows were found we can already return an empty vec here.
+    if document_rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // For every row we found we want to retrieve the current view as well. As deleted documents
+    // were already filtered out when querying the rows we can expect all documents we handle here
+    // to have an associated view in the database.
+    let document_view_ids: Vec<&str> = document_rows
+        .iter()
+        .map(|document_row| document_row.document_view_id.as_str())
+        .collect();
+    let mut document_view_field_rows: HashMap<String, Vec<DocumentViewFieldRow>> = HashMap::new();
+    for row in get_document_views_field_rows(pool, &document_view_ids).await? {
+        document_view_field_rows
+            .entry(row.document_view_id.clone())
+            .or_default()
+            .push(row);
+    }
+
+    let mut documents: Vec<StorageDocument> = vec![];
+    for document_row in document_rows {
+        let document_view_id = document_row.document_view_id.parse().unwrap();
+        // this method assumes all values coming from the db are already validated and so
+        // unwraps where errors might occur.
+        let document_view_fields = Some(parse_document_view_field_rows(
+            document_view_field_rows
+                .remove(&document_row.document_view_id)
+                .unwrap_or_default(),
+        ));
+
+        // Construct a `StorageDocument` based on the retrieved values.
+        let document = StorageDocument {
+            id: document_row.document_id.parse().unwrap(),
+            view_id: document_view_id,
+            schema_id: document_row.schema_id.parse().unwrap(),
+            fields: document_view_fields,
+            author: document_row.public_key.parse().unwrap(),
+            deleted: document_row.is_deleted,
+        };
+
+        documents.push(document)
+    }
+
+    Ok(documents)
+}
+
+// Helper method for getting the rows of many document views from the `document_view_fields`
+// table at once, ordered by document view id and list index.
+//
+// Rows are queried in batches of `VIEW_IDS_PER_QUERY` view ids per statement, this way loading
+// large collections takes only a few queries instead of one per document.
+async fn get_document_views_field_rows(
+    pool: &Pool,
+    ids: &[&str],
+) -> Result<Vec<DocumentViewFieldRow>, DocumentStorageError> {
+    let mut rows = Vec::new();
+
+    for ids in ids.chunks(VIEW_IDS_PER_QUERY) {
+        let ids_str = ids
+            .iter()
+            .map(|id| format!("'{id}'"))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        // See `get_document_view_field_rows` for details on this query
+        let batch = query_as::<_, DocumentViewFieldRow>(&format!(
+            "
+            SELECT
+                document_views.document_id,
+                document_view_fields.document_view_id,
+                document_view_fields.operation_id,
+                document_view_fields.name,
+                operation_fields_v1.list_index,
+                operation_fields_v1.field_type,
+                operation_fields_v1.value
+            FROM
+                document_view_fields
+            LEFT JOIN
+                operation_fields_v1
+            ON
+                document_view_fields.operation_id = operation_fields_v1.operation_id
+            AND
+                document_view_fields.name = operation_fields_v1.name
+            LEFT JOIN
+                document_views
+            ON
+                document_view_fields.document_view_id = document_views.document_view_id
+            WHERE
+                document_view_fields.document_view_id IN ({ids_str})
+            ORDER BY
+                document_view_fields.document_view_id, operation_fields_v1.list_index ASC
+            "
+        ))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        rows.extend(batch);
+    }
+
+    Ok(rows)
+}
+
 // Helper method for getting rows from the `document_view_fields` table.
+//
+// Only the rows of the given fields are returned, or of all fields when none are given.
 async fn get_document_view_field_rows(
     pool: &Pool,
     id: &DocumentViewId,
+    fields: Option<&ApplicationFields>,
 ) -> Result<Vec<DocumentViewFieldRow>, DocumentStorageError> {
+    let and_fields = match fields {
+        // No need to ask the database when no fields were selected
+        Some(fields) if fields.is_empty() => return Ok(Vec::new()),
+        Some(fields) => {
+            let fields_sql: Vec<String> = fields.iter().map(|field| format!("'{field}'")).collect();
+            format!(
+                "AND document_view_fields.name IN ({})",
+                fields_sql.join(", ")
+            )
+        }
+        None => "".to_string(),
+    };
+
     // Get all rows which match against the passed document view id.
     //
     // This query performs a join against the `operation_fields_v1` table as this is where the
@@ -641,7 +1423,7 @@ async fn get_document_view_field_rows(
     // Each field has one row, or in the case of list values (pinned relations, or relation lists)
     // then one row exists for every item in the list. The `list_index` column is used for
     // consistently ordering list items.
-    query_as::<_, DocumentViewFieldRow>(
+    query_as::<_, DocumentViewFieldRow>(&format!(
         "
         SELECT
             document_views.document_id,
@@ -665,10 +1447,11 @@ async fn get_document_view_field_rows(
             document_view_fields.document_view_id = document_views.document_view_id
         WHERE
             document_view_fields.document_view_id = $1
+            {and_fields}
         ORDER BY
             operation_fields_v1.list_index ASC
-        ",
-    )
+        "
+    ))
     .bind(id.to_string())
     .fetch_all(pool)
     .await
@@ -742,6 +1525,24 @@ async fn insert_document(
     tx: &mut Transaction<'_, Any>,
     document: &impl AsDocument,
 ) -> Result<(), DocumentStorageError> {
+    // Assign the next change sequence number. The row stays locked until the transaction commits,
+    // so changes become visible in the order of their sequence numbers.
+    let change_seq: i64 = query_scalar(
+        "
+        UPDATE
+            document_change_sequence
+        SET
+            value = value + 1
+        WHERE
+            id = 'documents'
+        RETURNING
+            value
+        ",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
+
     // Insert or update the document to the `documents` table.
     query(
         "
@@ -750,19 +1551,22 @@ async fn insert_document(
                 document_id,
                 document_view_id,
                 is_deleted,
-                schema_id
+                schema_id,
+                change_seq
             )
         VALUES
-            ($1, $2, $3, $4)
+            ($1, $2, $3, $4, $5)
         ON CONFLICT(document_id) DO UPDATE SET
             document_view_id = $2,
-            is_deleted = $3
+            is_deleted = $3,
+            change_seq = $5
         ",
     )
     .bind(document.id().as_str())
     .bind(document.view_id().to_string())
     .bind(document.is_deleted())
     .bind(document.schema_id().to_string())
+    .bind(change_seq)
     .execute(&mut *tx)
     .await
     .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
@@ -797,7 +1601,7 @@ mod tests {
     use p2panda_rs::entry::{LogId, SeqNum};
     use p2panda_rs::identity::KeyPair;
     use p2panda_rs::operation::traits::AsOperation;
-    use p2panda_rs::operation::{Operation, OperationId};
+    use p2panda_rs::operation::{Operation, OperationId, OperationValue};
     use p2panda_rs::storage_provider::traits::{DocumentStore, OperationStore};
     use p2panda_rs::test_utils::constants;
     use p2panda_rs::test_utils::fixtures::{
@@ -805,13 +1609,18 @@ mod tests {
     };
     use p2panda_rs::WithId;
     use rstest::rstest;
+    use sqlx::query;
 
+    use crate::db::query::{Field, Select};
     use crate::db::stores::document::DocumentView;
+    use crate::db::view_budget::ViewBudget;
+    use crate::db::SqlStore;
     use crate::materializer::tasks::reduce_task;
     use crate::materializer::TaskInput;
     use crate::test_utils::{
-        add_schema_and_documents, assert_query, doggo_schema, populate_and_materialize,
-        populate_store, populate_store_config, test_runner, PopulateStoreConfig, TestNode,
+        add_schema_and_documents, assert_query, delete_document, doggo_schema,
+        populate_and_materialize, populate_store, populate_store_config, test_runner,
+        update_document, PopulateStoreConfig, TestNode,
     };
 
     #[rstest]
@@ -1016,6 +1825,55 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn gets_document_with_selected_fields(
+        #[from(populate_store_config)]
+        #[with(1, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|node: TestNode| async move {
+            let documents = populate_store(&node.context.store, &config).await;
+            let document = documents.first().expect("At least one document");
+            node.context.store.insert_document(document).await.unwrap();
+
+            let select =
+                Select::new(&[Field::new("username"), Field::new("many_profile_pictures")]);
+
+            let by_id = node
+                .context
+                .store
+                .get_document_with_select(document.id(), &select)
+                .await
+                .unwrap()
+                .unwrap();
+            let by_view_id = node
+                .context
+                .store
+                .get_document_by_view_id_with_select(document.view_id(), &select)
+                .await
+                .unwrap()
+                .unwrap();
+
+            for retrieved_document in [by_id, by_view_id] {
+                assert_eq!(retrieved_document.view_id(), document.view_id());
+                assert_eq!(retrieved_document.fields().unwrap().len(), 2);
+                for key in ["username", "many_profile_pictures"] {
+                    assert_eq!(retrieved_document.get(key), document.get(key));
+                }
+            }
+
+            // Selecting only meta fields doesn't retrieve any values
+            let retrieved_document = node
+                .context
+                .store
+                .get_document_with_select(document.id(), &Select::default())
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(retrieved_document.fields().unwrap().is_empty());
+        });
+    }
+
     #[rstest]
     fn no_view_when_document_deleted(
         #[from(populate_store_config)]
@@ -1043,20 +1901,66 @@ mod tests {
             let document = node
                 .context
                 .store
-                .get_document(document.id())
+                .get_document(document.id())
+                .await
+                .unwrap();
+            assert!(document.is_none());
+
+            // We also try to retrieve the specific document view by its view id. This should also
+            // return none as it is deleted.
+            let document = node
+                .context
+                .store
+                .get_document_by_view_id(view_id)
+                .await
+                .unwrap();
+            assert!(document.is_none());
+        });
+    }
+
+    #[rstest]
+    fn count_documents_by_schema(
+        #[from(populate_store_config)]
+        #[with(2, 3, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+        key_pair: KeyPair,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            let documents = populate_and_materialize(&mut node, &config).await;
+
+            let count = node
+                .context
+                .store
+                .count_documents_by_schema(config.schema.id())
+                .await
+                .unwrap();
+            assert_eq!(count, 3);
+
+            // Deleted documents are not counted.
+            delete_document(
+                &mut node,
+                config.schema.id(),
+                documents[0].view_id(),
+                &key_pair,
+            )
+            .await;
+
+            let count = node
+                .context
+                .store
+                .count_documents_by_schema(config.schema.id())
                 .await
                 .unwrap();
-            assert!(document.is_none());
+            assert_eq!(count, 2);
 
-            // We also try to retrieve the specific document view by its view id. This should also
-            // return none as it is deleted.
-            let document = node
+            // Schemas without documents have a count of zero.
+            let count = node
                 .context
                 .store
-                .get_document_by_view_id(view_id)
+                .count_documents_by_schema(&"blob_v1".parse().unwrap())
                 .await
                 .unwrap();
-            assert!(document.is_none());
+            assert_eq!(count, 0);
         });
     }
 
@@ -1086,6 +1990,118 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn insert_document_rolls_back_on_error(
+        #[from(populate_store_config)]
+        #[with(1, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|node: TestNode| async move {
+            let documents = populate_store(&node.context.store, &config).await;
+
+            // Make inserting the view fields, the last step of an insertion, fail.
+            query("DROP TABLE document_view_fields")
+                .execute(&node.context.store.pool)
+                .await
+                .unwrap();
+
+            let result = node.context.store.insert_document(&documents[0]).await;
+            assert!(result.is_err());
+
+            // Neither the document, its view nor a change sequence number were written.
+            assert_query(&node, "SELECT document_id FROM documents", 0).await;
+            assert_query(&node, "SELECT document_id FROM document_views", 0).await;
+            let latest_change_seq = node.context.store.get_latest_change_seq().await.unwrap();
+            assert_eq!(latest_change_seq, 0);
+        });
+    }
+
+    #[rstest]
+    fn get_documents_by_schema_with_fields(
+        #[from(populate_store_config)]
+        #[with(2, 3, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            populate_and_materialize(&mut node, &config).await;
+
+            // Field values of all documents are loaded at once, they match the values of
+            // documents loaded one by one.
+            let documents = node
+                .context
+                .store
+                .get_documents_by_schema(config.schema.id())
+                .await
+                .unwrap();
+            assert_eq!(documents.len(), 3);
+
+            for document in documents {
+                let expected = node
+                    .context
+                    .store
+                    .get_document(document.id())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(document.view_id(), expected.view_id());
+                assert_eq!(document.fields(), expected.fields());
+            }
+        });
+    }
+
+    #[rstest]
+    fn get_documents_by_schema_paginated(
+        #[from(populate_store_config)]
+        #[with(1, 5, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            populate_and_materialize(&mut node, &config).await;
+
+            let mut expected: Vec<DocumentId> = node
+                .context
+                .store
+                .get_documents_by_schema(config.schema.id())
+                .await
+                .unwrap()
+                .iter()
+                .map(|document| document.id().to_owned())
+                .collect();
+            expected.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            assert_eq!(expected.len(), 5);
+
+            // Walk over all documents in pages of two, using the last id as the cursor.
+            let mut pages = vec![];
+            let mut after: Option<DocumentId> = None;
+            loop {
+                let documents = node
+                    .context
+                    .store
+                    .get_documents_by_schema_paginated(config.schema.id(), 2, after.as_ref())
+                    .await
+                    .unwrap();
+
+                match documents.last() {
+                    Some(document) => after = Some(document.id().to_owned()),
+                    None => break,
+                }
+
+                pages.push(
+                    documents
+                        .iter()
+                        .map(|document| document.id().to_owned())
+                        .collect::<Vec<DocumentId>>(),
+                );
+            }
+
+            assert_eq!(
+                pages.iter().map(|page| page.len()).collect::<Vec<usize>>(),
+                vec![2, 2, 1]
+            );
+            assert_eq!(pages.concat(), expected);
+        });
+    }
+
     #[rstest]
     fn updates_a_document(
         #[from(populate_store_config)]
@@ -1161,6 +2177,75 @@ mod tests {
         })
     }
 
+    #[rstest]
+    fn gets_document_views(
+        #[from(populate_store_config)]
+        #[with(3, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|node: TestNode| async move {
+            // Populate the store with some entries and operations but DON'T materialise any resulting documents.
+            let documents = populate_store(&node.context.store, &config).await;
+            let document = documents.first().expect("At least one document");
+
+            // Get the operations for this document and sort them into linear order.
+            let operations = node
+                .context
+                .store
+                .get_operations_by_document_id(document.id())
+                .await
+                .unwrap();
+            let document_builder = DocumentBuilder::from(&operations);
+            let (_, sorted_operations) = document_builder.build().unwrap();
+
+            // Insert one document view for every operation.
+            let mut current_operations = Vec::new();
+            for operation in sorted_operations.iter() {
+                current_operations.push(operation.clone());
+                let (document, _) = DocumentBuilder::new(current_operations.clone())
+                    .build()
+                    .expect("Build document");
+
+                node.context
+                    .store
+                    .insert_document(&document)
+                    .await
+                    .expect("Insert document");
+            }
+
+            let views = node
+                .context
+                .store
+                .get_document_views(document.id())
+                .await
+                .expect("Get document views");
+
+            // There should be one view per operation.
+            assert_eq!(views.len(), 3);
+
+            // Only the latest view is the current one, every operation updated all fields so it
+            // is also the only operation contributing to this view.
+            let current_views: Vec<_> = views.iter().filter(|view| view.is_current).collect();
+            assert_eq!(current_views.len(), 1);
+            assert_eq!(&current_views[0].id, document.view_id());
+
+            let last_operation = sorted_operations.last().unwrap();
+            assert_eq!(
+                current_views[0].operations,
+                vec![last_operation.0.to_owned()]
+            );
+
+            // A document which doesn't exist has no views.
+            let views = node
+                .context
+                .store
+                .get_document_views(&random_document_id())
+                .await
+                .expect("Get document views");
+            assert!(views.is_empty());
+        })
+    }
+
     #[rstest]
     fn gets_documents_by_schema(
         #[from(populate_store_config)]
@@ -1184,6 +2269,149 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn gets_documents_by_public_key(
+        #[from(populate_store_config)]
+        #[with(2, 5, vec![KeyPair::new(), KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            // Populate the store and materialize all documents.
+            populate_and_materialize(&mut node, &config).await;
+
+            let public_key = config.authors[0].public_key();
+
+            // Retrieve the documents created by the first key pair.
+            let documents = node
+                .context
+                .store
+                .get_documents_by_public_key(config.schema.id(), &public_key)
+                .await
+                .expect("Get documents by public key");
+
+            // There should be five, all of them created by this key pair.
+            assert_eq!(documents.len(), 5);
+            assert!(documents
+                .iter()
+                .all(|document| document.author() == &public_key));
+
+            // No documents should be returned for a key pair which didn't create any.
+            let documents = node
+                .context
+                .store
+                .get_documents_by_public_key(config.schema.id(), &KeyPair::new().public_key())
+                .await
+                .expect("Get documents by public key");
+            assert!(documents.is_empty());
+        });
+    }
+
+    #[rstest]
+    fn gets_documents_by_ids(
+        #[from(populate_store_config)]
+        #[with(2, 5, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+        #[from(random_document_id)] unknown_document_id: DocumentId,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            // Populate the store and materialize all documents.
+            let documents = populate_and_materialize(&mut node, &config).await;
+
+            let mut document_ids: Vec<DocumentId> = documents
+                .iter()
+                .map(|document| document.id().to_owned())
+                .collect();
+            document_ids.push(unknown_document_id);
+
+            // Retrieve all documents in one go, unknown ids are ignored.
+            let batch = node
+                .context
+                .store
+                .get_documents_by_ids(&document_ids)
+                .await
+                .expect("Get documents by ids");
+            assert_eq!(batch.len(), 5);
+
+            // The documents should equal the ones retrieved one by one.
+            for document in batch {
+                let expected = node
+                    .context
+                    .store
+                    .get_document(document.id())
+                    .await
+                    .expect("Get document")
+                    .expect("Document exists");
+                assert_eq!(document, expected);
+            }
+
+            // Nothing is returned when no ids are passed.
+            let batch = node.context.store.get_documents_by_ids(&[]).await.unwrap();
+            assert!(batch.is_empty());
+        });
+    }
+
+    #[rstest]
+    fn materializes_document_view_on_demand(
+        #[from(populate_store_config)]
+        #[with(2, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+        #[from(random_document_view_id)] unknown_view_id: DocumentViewId,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            // Populate the store and materialize all documents, this only stores the current view.
+            let documents = populate_and_materialize(&mut node, &config).await;
+            let document = documents[0].clone();
+            let first_document_view_id: DocumentViewId = document.id().as_str().parse().unwrap();
+
+            let document = node
+                .context
+                .store
+                .get_document_by_view_id(&first_document_view_id)
+                .await
+                .unwrap();
+            assert!(document.is_none());
+
+            // No views get materialised when the limit was reached.
+            let store = SqlStore {
+                view_budget: ViewBudget::new(0),
+                ..node.context.store.clone()
+            };
+            let document = store
+                .get_document_by_view_id_with_select(&first_document_view_id, &Select::default())
+                .await
+                .unwrap();
+            assert!(document.is_none());
+
+            // The historic view gets built from the operations of the document.
+            let document = node
+                .context
+                .store
+                .get_document_by_view_id_with_select(&first_document_view_id, &Select::default())
+                .await
+                .unwrap()
+                .expect("View to be materialised on demand");
+            assert_eq!(document.view_id(), &first_document_view_id);
+            assert_eq!(document.id(), documents[0].id());
+
+            // It was persisted in the store.
+            let document = node
+                .context
+                .store
+                .get_document_by_view_id(&first_document_view_id)
+                .await
+                .unwrap();
+            assert!(document.is_some());
+
+            // Views with unknown operations can't be materialised.
+            let result = node
+                .context
+                .store
+                .materialize_document_view(&unknown_view_id)
+                .await;
+            assert!(!result.unwrap());
+        });
+    }
+
     #[rstest]
     fn prunes_document_view(
         #[from(populate_store_config)]
@@ -1339,6 +2567,35 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn document_view_exists(
+        #[from(populate_store_config)]
+        #[with(1, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+        #[from(random_document_view_id)] unknown_view_id: DocumentViewId,
+        key_pair: KeyPair,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            let documents = populate_and_materialize(&mut node, &config).await;
+            let view_id = documents[0].view_id();
+
+            let exists = node.context.store.document_view_exists(view_id).await;
+            assert!(exists.unwrap());
+
+            let exists = node
+                .context
+                .store
+                .document_view_exists(&unknown_view_id)
+                .await;
+            assert!(!exists.unwrap());
+
+            // Views of deleted documents are not returned.
+            delete_document(&mut node, config.schema.id(), view_id, &key_pair).await;
+            let exists = node.context.store.document_view_exists(view_id).await;
+            assert!(!exists.unwrap());
+        });
+    }
+
     #[rstest]
     fn purge_document(
         #[from(populate_store_config)]
@@ -1462,4 +2719,87 @@ mod tests {
             assert!(result.is_ok());
         });
     }
+
+    #[rstest]
+    fn gets_documents_changed_since(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, view_ids) = add_schema_and_documents(
+                &mut node,
+                "posts",
+                vec![
+                    vec![("title", "Hello".into(), None)],
+                    vec![("title", "World".into(), None)],
+                ],
+                &key_pair,
+            )
+            .await;
+
+            // All documents changed since the beginning, ordered by their change
+            let changes = node
+                .context
+                .store
+                .get_documents_changed_since(schema.id(), 0, 10)
+                .await
+                .expect("Get changed documents");
+            assert_eq!(changes.len(), 2);
+            assert!(changes[0].change_seq < changes[1].change_seq);
+            let cursor = changes[1].change_seq;
+            assert_eq!(
+                node.context.store.get_latest_change_seq().await.unwrap(),
+                cursor
+            );
+
+            // Nothing changed after the last seen change
+            let changes = node
+                .context
+                .store
+                .get_documents_changed_since(schema.id(), cursor, 10)
+                .await
+                .expect("Get changed documents");
+            assert!(changes.is_empty());
+
+            // Update one document and delete the other one
+            let updated_view_id = update_document(
+                &mut node,
+                schema.id(),
+                vec![("title", "Hello again".into())],
+                &view_ids[0],
+                &key_pair,
+            )
+            .await;
+            delete_document(&mut node, schema.id(), &view_ids[1], &key_pair).await;
+
+            let changes = node
+                .context
+                .store
+                .get_documents_changed_since(schema.id(), cursor, 10)
+                .await
+                .expect("Get changed documents");
+            assert_eq!(changes.len(), 2);
+            assert!(changes[0].change_seq > cursor);
+            assert_eq!(changes[0].document.view_id(), &updated_view_id);
+            assert_eq!(
+                changes[0].document.get("title"),
+                Some(&OperationValue::String("Hello again".into()))
+            );
+            assert!(changes[1].document.is_deleted());
+            assert!(changes[1].document.fields().is_none());
+
+            // Changes can be paged through
+            let page = node
+                .context
+                .store
+                .get_documents_changed_since(schema.id(), cursor, 1)
+                .await
+                .expect("Get changed documents");
+            assert_eq!(page, changes[..1]);
+            let page = node
+                .context
+                .store
+                .get_documents_changed_since(schema.id(), page[0].change_seq, 1)
+                .await
+                .expect("Get changed documents");
+            assert_eq!(page, changes[1..]);
+        });
+    }
 }
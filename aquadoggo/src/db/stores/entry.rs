@@ -12,11 +12,12 @@ use p2panda_rs::identity::PublicKey;
 use p2panda_rs::operation::EncodedOperation;
 use p2panda_rs::storage_provider::error::EntryStorageError;
 use p2panda_rs::storage_provider::traits::EntryStore;
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, query_scalar, Any, Executor};
 
 use crate::db::models::{EntryRow, LogHeightRow};
 use crate::db::types::StorageEntry;
 use crate::db::SqlStore;
+use crate::utils::now;
 
 /// Implementation of `EntryStore` trait which is required when constructing a `StorageProvider`.
 ///
@@ -36,41 +37,11 @@ impl EntryStore for SqlStore {
         encoded_entry: &EncodedEntry,
         encoded_operation: Option<&EncodedOperation>,
     ) -> Result<(), EntryStorageError> {
-        let insert_entry_result = query(
-            "
-            INSERT INTO
-                entries (
-                    public_key,
-                    entry_bytes,
-                    entry_hash,
-                    log_id,
-                    payload_bytes,
-                    payload_hash,
-                    seq_num
-                )
-            VALUES
-                ($1, $2, $3, $4, $5, $6, $7)
-            ",
-        )
-        .bind(entry.public_key().to_string())
-        .bind(encoded_entry.into_hex())
-        .bind(encoded_entry.hash().as_str())
-        .bind(entry.log_id().as_u64().to_string())
-        .bind(encoded_operation.map(|payload| payload.to_string()))
-        .bind(entry.payload_hash().as_str())
-        .bind(entry.seq_num().as_u64().to_string())
-        .execute(&self.pool)
-        .await
-        .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
-
-        if insert_entry_result.rows_affected() != 1 {
-            return Err(EntryStorageError::Custom(format!(
-                "Unexpected number of inserts occured for entry with id: {}",
-                encoded_entry.hash()
-            )));
-        }
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
 
-        Ok(())
+        insert_entry_row(&self.pool, entry, encoded_entry, encoded_operation).await
     }
 
     /// Get an entry from storage by its hash id.
@@ -79,6 +50,10 @@ impl EntryStore for SqlStore {
     /// Returns `None` if the entry was not found in storage. Errors when a fatal storage error
     /// occured.
     async fn get_entry(&self, hash: &Hash) -> Result<Option<StorageEntry>, EntryStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
         let entry_row = query_as::<_, EntryRow>(
             "
             SELECT
@@ -114,6 +89,10 @@ impl EntryStore for SqlStore {
         log_id: &LogId,
         seq_num: &SeqNum,
     ) -> Result<Option<StorageEntry>, EntryStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
         let entry_row = query_as::<_, EntryRow>(
             "
             SELECT
@@ -152,6 +131,10 @@ impl EntryStore for SqlStore {
         public_key: &PublicKey,
         log_id: &LogId,
     ) -> Result<Option<StorageEntry>, EntryStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
         let entry_row = query_as::<_, EntryRow>(
             "
             SELECT
@@ -289,6 +272,265 @@ impl SqlStore {
 
         Ok(entries.into_iter().map(|row| row.into()).collect())
     }
+
+    /// Returns the certificate pool of an entry, `None` if the entry is not known.
+    ///
+    /// The certificate pool contains the entries on the shortest path along backlinks and
+    /// skiplinks from the latest entry of the log down to the given entry and from there down to
+    /// the first entry. With it third parties can verify that the entry is part of the author's
+    /// log without retrieving the full log. Entries are ordered by their sequence number.
+    pub async fn get_certificate_pool(
+        &self,
+        entry_hash: &Hash,
+    ) -> Result<Option<Vec<StorageEntry>>, EntryStorageError> {
+        let entry = match self.get_entry(entry_hash).await? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let latest_entry = self
+            .get_latest_entry(entry.public_key(), entry.log_id())
+            .await?
+            .expect("Log of a stored entry contains at least this entry");
+
+        let mut seq_nums = certificate_path(latest_entry.seq_num(), entry.seq_num());
+        seq_nums.extend(certificate_path(entry.seq_num(), &SeqNum::default()));
+
+        let seq_nums_str: String = seq_nums
+            .iter()
+            .map(|seq_num| format!("'{}'", seq_num.as_u64()))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let entries = query_as::<_, EntryRow>(&format!(
+            "
+            SELECT
+                public_key,
+                entry_bytes,
+                entry_hash,
+                log_id,
+                payload_bytes,
+                payload_hash,
+                seq_num
+            FROM
+                entries
+            WHERE
+                public_key = $1
+                AND log_id = $2
+                AND seq_num IN ({seq_nums_str})
+            ORDER BY
+                CAST(seq_num AS NUMERIC)
+            "
+        ))
+        .bind(entry.public_key().to_string())
+        .bind(entry.log_id().as_u64().to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        Ok(Some(entries.into_iter().map(|row| row.into()).collect()))
+    }
+
+    /// Removes published entries together with their operations, the logs they created and the
+    /// "reduce" tasks of documents which have no operations left.
+    ///
+    /// This is used to undo the publishing of entries which have not been materialized yet, for
+    /// example when a later entry of the same batch failed to validate.
+    pub async fn remove_entries(&self, entry_hashes: &[Hash]) -> Result<(), EntryStorageError> {
+        if entry_hashes.is_empty() {
+            return Ok(());
+        }
+
+        let entry_hashes_str: String = entry_hashes
+            .iter()
+            .map(|hash| format!("'{}'", hash.as_str()))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        // Delete rows from `entries` table.
+        query(&format!(
+            "
+            DELETE FROM entries
+            WHERE entries.entry_hash IN ({entry_hashes_str})
+            "
+        ))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        // Delete "reduce" tasks which were inserted together with the operations. Tasks of
+        // documents which still have other operations are kept, reducing them again is harmless.
+        query(&format!(
+            "
+            DELETE FROM tasks
+            WHERE tasks.name = 'reduce'
+                AND tasks.document_view_id IS NULL
+                AND tasks.document_id IN (
+                    SELECT operations_v1.document_id
+                    FROM operations_v1
+                    WHERE operations_v1.operation_id IN ({entry_hashes_str})
+                )
+                AND NOT EXISTS (
+                    SELECT 1
+                    FROM operations_v1
+                    WHERE operations_v1.document_id = tasks.document_id
+                        AND operations_v1.operation_id NOT IN ({entry_hashes_str})
+                )
+            "
+        ))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        // Delete rows from `operations_v1` table, this cascades up to `operation_fields_v1` table
+        // as well.
+        query(&format!(
+            "
+            DELETE FROM operations_v1
+            WHERE operations_v1.operation_id IN ({entry_hashes_str})
+            "
+        ))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        // Delete rows from `logs` table which were created by one of the entries. Logs are
+        // identified by their document id, which is the hash of the first entry.
+        query(&format!(
+            "
+            DELETE FROM logs
+            WHERE logs.document IN ({entry_hashes_str})
+            "
+        ))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Returns the time the most recent of the given entries was stored by this node, as UNIX
+    /// timestamp in seconds.
+    ///
+    /// Returns `None` if none of the entries is known or they were stored before the node kept
+    /// track of this time.
+    pub async fn get_received_at(
+        &self,
+        entry_hashes: &[Hash],
+    ) -> Result<Option<u64>, EntryStorageError> {
+        if entry_hashes.is_empty() {
+            return Ok(None);
+        }
+
+        let entry_hashes_str: String = entry_hashes
+            .iter()
+            .map(|hash| format!("'{}'", hash.as_str()))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let received_at: Option<i64> = query_scalar(&format!(
+            "
+            SELECT
+                MAX(entries.received_at)
+            FROM
+                entries
+            WHERE
+                entries.entry_hash IN ({entry_hashes_str})
+            "
+        ))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+        Ok(received_at.map(|timestamp| timestamp as u64))
+    }
+}
+
+/// Returns the sequence numbers on the shortest path along backlinks and skiplinks from one entry
+/// down to another one of the same log, including both ends.
+fn certificate_path(from: &SeqNum, to: &SeqNum) -> Vec<SeqNum> {
+    let mut seq_num = from.as_u64();
+    let mut path = vec![seq_num];
+
+    while seq_num > to.as_u64() {
+        // Follow the skiplink whenever it doesn't jump over the target
+        let skiplink = SeqNum::new(seq_num)
+            .expect("Sequence number is larger than zero")
+            .skiplink_seq_num()
+            .map(|skiplink| skiplink.as_u64())
+            .unwrap_or_default();
+
+        seq_num = if skiplink >= to.as_u64() {
+            skiplink
+        } else {
+            seq_num - 1
+        };
+
+        path.push(seq_num);
+    }
+
+    path.into_iter()
+        .map(|seq_num| SeqNum::new(seq_num).expect("Sequence number is larger than zero"))
+        .collect()
+}
+
+/// Inserts the row of an entry, using either a connection from the pool or an open transaction.
+pub(crate) async fn insert_entry_row<'c, E>(
+    executor: E,
+    entry: &Entry,
+    encoded_entry: &EncodedEntry,
+    encoded_operation: Option<&EncodedOperation>,
+) -> Result<(), EntryStorageError>
+where
+    E: Executor<'c, Database = Any>,
+{
+    let insert_entry_result = query(
+        "
+        INSERT INTO
+            entries (
+                public_key,
+                entry_bytes,
+                entry_hash,
+                log_id,
+                payload_bytes,
+                payload_hash,
+                seq_num,
+                received_at
+            )
+        VALUES
+            ($1, $2, $3, $4, $5, $6, $7, $8)
+        ",
+    )
+    .bind(entry.public_key().to_string())
+    .bind(encoded_entry.into_hex())
+    .bind(encoded_entry.hash().as_str())
+    .bind(entry.log_id().as_u64().to_string())
+    .bind(encoded_operation.map(|payload| payload.to_string()))
+    .bind(entry.payload_hash().as_str())
+    .bind(entry.seq_num().as_u64().to_string())
+    .bind(now() as i64)
+    .execute(executor)
+    .await
+    .map_err(|e| EntryStorageError::Custom(e.to_string()))?;
+
+    if insert_entry_result.rows_affected() != 1 {
+        return Err(EntryStorageError::Custom(format!(
+            "Unexpected number of inserts occured for entry with id: {}",
+            encoded_entry.hash()
+        )));
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -592,4 +834,62 @@ mod tests {
             assert_eq!(entries.len(), 11);
         });
     }
+
+    #[rstest]
+    #[case(13, 1, vec![13, 4, 1])]
+    #[case(13, 5, vec![13, 12, 8, 7, 6, 5])]
+    #[case(20, 10, vec![20, 19, 18, 17, 13, 12, 11, 10])]
+    #[case(5, 5, vec![5])]
+    fn certificate_path(#[case] from: u64, #[case] to: u64, #[case] expected: Vec<u64>) {
+        let path: Vec<u64> =
+            super::certificate_path(&SeqNum::new(from).unwrap(), &SeqNum::new(to).unwrap())
+                .iter()
+                .map(SeqNum::as_u64)
+                .collect();
+
+        assert_eq!(path, expected);
+    }
+
+    #[rstest]
+    fn get_certificate_pool(
+        #[from(populate_store_config)]
+        #[with(20, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|node: TestNode| async move {
+            let _ = populate_store(&node.context.store, &config).await;
+            let public_key = config.authors[0].public_key();
+
+            let entry = node
+                .context
+                .store
+                .get_entry_at_seq_num(&public_key, &LogId::default(), &SeqNum::new(10).unwrap())
+                .await
+                .unwrap()
+                .unwrap();
+
+            let certificate_pool = node
+                .context
+                .store
+                .get_certificate_pool(&entry.hash())
+                .await
+                .unwrap()
+                .unwrap();
+
+            let seq_nums: Vec<u64> = certificate_pool
+                .iter()
+                .map(|entry| entry.seq_num().as_u64())
+                .collect();
+            assert_eq!(seq_nums, vec![1, 4, 8, 9, 10, 11, 12, 13, 17, 18, 19, 20]);
+
+            // Unknown entries don't have a certificate pool
+            let certificate_pool = node
+                .context
+                .store
+                .get_certificate_pool(&random_hash())
+                .await
+                .unwrap();
+            assert!(certificate_pool.is_none());
+        });
+    }
 }
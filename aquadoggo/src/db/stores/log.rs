@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::ops::Range;
+
 use async_trait::async_trait;
 use p2panda_rs::document::DocumentId;
 use p2panda_rs::entry::LogId;
@@ -7,8 +9,9 @@ use p2panda_rs::identity::PublicKey;
 use p2panda_rs::schema::SchemaId;
 use p2panda_rs::storage_provider::error::LogStorageError;
 use p2panda_rs::storage_provider::traits::LogStore;
-use sqlx::{query, query_scalar};
+use sqlx::{query, query_scalar, Any, Executor};
 
+use crate::db::errors::SqlStoreError;
 use crate::db::SqlStore;
 
 /// Implementation of `LogStore` trait which is required when constructing a
@@ -27,30 +30,11 @@ impl LogStore for SqlStore {
         schema: &SchemaId,
         document: &DocumentId,
     ) -> Result<bool, LogStorageError> {
-        let rows_affected = query(
-            "
-            INSERT INTO
-                logs (
-                    public_key,
-                    log_id,
-                    document,
-                    schema
-                )
-            VALUES
-                ($1, $2, $3, $4)
-            ON CONFLICT DO NOTHING
-            ",
-        )
-        .bind(public_key.to_string())
-        .bind(log_id.as_u64().to_string())
-        .bind(document.as_str())
-        .bind(schema.to_string())
-        .execute(&self.pool)
-        .await
-        .map_err(|e| LogStorageError::Custom(e.to_string()))?
-        .rows_affected();
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| LogStorageError::Custom(e.to_string()))?;
 
-        Ok(rows_affected == 1)
+        insert_log_row(&self.pool, log_id, public_key, schema, document).await
     }
 
     /// Get a log from storage
@@ -59,6 +43,10 @@ impl LogStore for SqlStore {
         public_key: &PublicKey,
         document_id: &DocumentId,
     ) -> Result<Option<LogId>, LogStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| LogStorageError::Custom(e.to_string()))?;
+
         let result: Option<String> = query_scalar(
             "
             SELECT
@@ -93,6 +81,10 @@ impl LogStore for SqlStore {
         &self,
         public_key: &PublicKey,
     ) -> Result<Option<LogId>, LogStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| LogStorageError::Custom(e.to_string()))?;
+
         // Get all log ids from this public_key
         let result: Option<String> = query_scalar(
             "
@@ -121,6 +113,83 @@ impl LogStore for SqlStore {
     }
 }
 
+impl SqlStore {
+    /// Get the highest log id of a public key inside of the given range.
+    ///
+    /// The range needs to fit into signed 64-bit integers as that is how log ids are compared in
+    /// the database.
+    pub async fn latest_log_id_in_range(
+        &self,
+        public_key: &PublicKey,
+        range: &Range<u64>,
+    ) -> Result<Option<LogId>, SqlStoreError> {
+        let result: Option<String> = query_scalar(
+            "
+            SELECT
+                log_id
+            FROM
+                logs
+            WHERE
+                public_key = $1
+                AND CAST(log_id AS NUMERIC) >= $2
+                AND CAST(log_id AS NUMERIC) < $3
+            ORDER BY
+                CAST(log_id AS NUMERIC) DESC LIMIT 1
+            ",
+        )
+        .bind(public_key.to_string())
+        .bind(range.start as i64)
+        .bind(range.end as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(result.map(|str| {
+            str.parse()
+                .unwrap_or_else(|_| panic!("Corrupt u64 integer found in database: '{0}'", &str))
+        }))
+    }
+}
+
+/// Inserts the row of a log, using either a connection from the pool or an open transaction.
+///
+/// Returns `false` if the log already existed.
+pub(crate) async fn insert_log_row<'c, E>(
+    executor: E,
+    log_id: &LogId,
+    public_key: &PublicKey,
+    schema: &SchemaId,
+    document: &DocumentId,
+) -> Result<bool, LogStorageError>
+where
+    E: Executor<'c, Database = Any>,
+{
+    let rows_affected = query(
+        "
+        INSERT INTO
+            logs (
+                public_key,
+                log_id,
+                document,
+                schema
+            )
+        VALUES
+            ($1, $2, $3, $4)
+        ON CONFLICT DO NOTHING
+        ",
+    )
+    .bind(public_key.to_string())
+    .bind(log_id.as_u64().to_string())
+    .bind(document.as_str())
+    .bind(schema.to_string())
+    .execute(executor)
+    .await
+    .map_err(|e| LogStorageError::Custom(e.to_string()))?
+    .rows_affected();
+
+    Ok(rows_affected == 1)
+}
+
 #[cfg(test)]
 mod tests {
     use p2panda_rs::document::{DocumentId, DocumentViewId};
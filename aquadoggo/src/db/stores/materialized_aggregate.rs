@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::document::DocumentId;
+use sqlx::{query, query_as};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::types::MaterializedAggregateGroup;
+use crate::db::SqlStore;
+
+/// Field name under which every document contributes to the document count of its group.
+///
+/// Names of document fields are never empty, so this can't collide with summed fields.
+const COUNT_FIELD: &str = "";
+
+/// Methods to interact with the `aggregate_contributions` and `aggregate_totals` tables in the
+/// database.
+impl SqlStore {
+    /// Replaces the values a document contributes to a materialized aggregate.
+    ///
+    /// The previous contribution of the document gets subtracted from the totals of its group
+    /// before the new one is added, this way the totals never need to be recomputed from all
+    /// documents. Passing `None` removes the document from the aggregate, for example after it
+    /// got deleted.
+    pub async fn update_aggregate_contribution(
+        &self,
+        aggregate_name: &str,
+        document_id: &DocumentId,
+        contribution: Option<(&str, &[(String, f64)])>,
+    ) -> Result<(), SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        // Subtract the previous contribution of this document from the totals
+        let previous: Vec<(String, String, f64)> = query_as(
+            "
+            SELECT
+                group_key,
+                field,
+                value
+            FROM
+                aggregate_contributions
+            WHERE
+                aggregate_name = $1
+                AND document_id = $2
+            ",
+        )
+        .bind(aggregate_name)
+        .bind(document_id.as_str())
+        .fetch_all(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        for (group_key, field, value) in previous {
+            query(
+                "
+                UPDATE
+                    aggregate_totals
+                SET
+                    count = count - 1,
+                    sum = sum - $4
+                WHERE
+                    aggregate_name = $1
+                    AND group_key = $2
+                    AND field = $3
+                ",
+            )
+            .bind(aggregate_name)
+            .bind(group_key)
+            .bind(field)
+            .bind(value)
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+        }
+
+        query(
+            "
+            DELETE FROM
+                aggregate_totals
+            WHERE
+                aggregate_name = $1
+                AND count <= 0
+            ",
+        )
+        .bind(aggregate_name)
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query(
+            "
+            DELETE FROM
+                aggregate_contributions
+            WHERE
+                aggregate_name = $1
+                AND document_id = $2
+            ",
+        )
+        .bind(aggregate_name)
+        .bind(document_id.as_str())
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        // Add the new contribution to the totals
+        if let Some((group_key, sums)) = contribution {
+            let values = std::iter::once((COUNT_FIELD, 0.0))
+                .chain(sums.iter().map(|(field, value)| (field.as_str(), *value)));
+
+            for (field, value) in values {
+                query(
+                    "
+                    INSERT INTO
+                        aggregate_contributions (
+                            aggregate_name,
+                            document_id,
+                            group_key,
+                            field,
+                            value
+                        )
+                    VALUES
+                        ($1, $2, $3, $4, $5)
+                    ",
+                )
+                .bind(aggregate_name)
+                .bind(document_id.as_str())
+                .bind(group_key)
+                .bind(field)
+                .bind(value)
+                .execute(&mut tx)
+                .await
+                .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+                query(
+                    "
+                    INSERT INTO
+                        aggregate_totals (
+                            aggregate_name,
+                            group_key,
+                            field,
+                            count,
+                            sum
+                        )
+                    VALUES
+                        ($1, $2, $3, 1, $4)
+                    ON CONFLICT (aggregate_name, group_key, field) DO UPDATE SET
+                        count = aggregate_totals.count + 1,
+                        sum = aggregate_totals.sum + excluded.sum
+                    ",
+                )
+                .bind(aggregate_name)
+                .bind(group_key)
+                .bind(field)
+                .bind(value)
+                .execute(&mut tx)
+                .await
+                .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+            }
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Removes the contributions and totals of all materialized aggregates.
+    pub async fn clear_materialized_aggregates(&self) -> Result<(), SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query("DELETE FROM aggregate_contributions")
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query("DELETE FROM aggregate_totals")
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get the current values of all groups of a materialized aggregate, ordered by group.
+    pub async fn get_materialized_aggregate(
+        &self,
+        aggregate_name: &str,
+    ) -> Result<Vec<MaterializedAggregateGroup>, SqlStoreError> {
+        let rows: Vec<(String, String, i64, f64)> = query_as(
+            "
+            SELECT
+                group_key,
+                field,
+                count,
+                sum
+            FROM
+                aggregate_totals
+            WHERE
+                aggregate_name = $1
+            ORDER BY
+                group_key,
+                field
+            ",
+        )
+        .bind(aggregate_name)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        let mut groups: Vec<MaterializedAggregateGroup> = Vec::new();
+        for (group_key, field, count, sum) in rows {
+            // The count field sorts first within every group and starts it
+            if field == COUNT_FIELD {
+                groups.push(MaterializedAggregateGroup {
+                    group: group_key,
+                    count: count as u64,
+                    sums: Vec::new(),
+                });
+            } else if let Some(group) = groups.last_mut() {
+                group.sums.push((field, sum));
+            }
+        }
+
+        Ok(groups)
+    }
+}
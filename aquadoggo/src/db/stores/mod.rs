@@ -2,14 +2,25 @@
 
 //! Implementations of all `p2panda-rs` defined storage provider traits and additionally
 //! `aquadoggo` specific interfaces.
+mod admin;
+mod allowed_operation;
 mod blob;
+mod blob_upload;
+mod dead_letter;
 pub mod document;
 mod entry;
 mod log;
+mod materialized_aggregate;
+mod name;
 mod operation;
+mod peer_author;
+mod projection;
+mod publish;
 mod query;
 mod schema;
 mod task;
+mod unique;
 
 pub use operation::OperationCursor;
+pub use publish::NextArgs;
 pub use query::{PaginationCursor, PaginationData, Query, RelationList};
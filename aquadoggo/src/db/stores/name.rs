@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::document::DocumentId;
+use p2panda_rs::identity::PublicKey;
+use sqlx::{query, query_scalar};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::SqlStore;
+
+/// Methods to interact with the `name_claims` table in the database.
+impl SqlStore {
+    /// Sets the name claimed by the author of a document.
+    ///
+    /// Passing `None` removes the claim of the document, for example after it got deleted.
+    pub async fn update_name_claim(
+        &self,
+        document_id: &DocumentId,
+        claim: Option<(&str, &PublicKey)>,
+    ) -> Result<(), SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query(
+            "
+            DELETE FROM
+                name_claims
+            WHERE
+                document_id = $1
+            ",
+        )
+        .bind(document_id.as_str())
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        if let Some((name, public_key)) = claim {
+            query(
+                "
+                INSERT INTO
+                    name_claims (
+                        document_id,
+                        name,
+                        public_key
+                    )
+                VALUES
+                    ($1, $2, $3)
+                ",
+            )
+            .bind(document_id.as_str())
+            .bind(name)
+            .bind(public_key.to_string())
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Removes all name claims, for example before filling the table again.
+    pub async fn clear_name_claims(&self) -> Result<(), SqlStoreError> {
+        query("DELETE FROM name_claims")
+            .execute(&self.pool)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Returns the public key of the author owning the given name.
+    ///
+    /// Names claimed by several authors belong to the one who created the document with the
+    /// lowest document id.
+    pub async fn resolve_name(&self, name: &str) -> Result<Option<PublicKey>, SqlStoreError> {
+        let public_key: Option<String> = query_scalar(
+            "
+            SELECT
+                public_key
+            FROM
+                name_claims
+            WHERE
+                name = $1
+            ORDER BY
+                document_id
+            LIMIT
+                1
+            ",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(public_key.map(|public_key| {
+            PublicKey::new(&public_key).expect("Public keys coming from the store are valid")
+        }))
+    }
+
+    /// Returns the name owned by the given author.
+    ///
+    /// Names claimed by other authors first are left out. When an author owns several names, the
+    /// one claimed in the document with the lowest document id is returned.
+    pub async fn get_claimed_name(
+        &self,
+        public_key: &PublicKey,
+    ) -> Result<Option<String>, SqlStoreError> {
+        query_scalar(
+            "
+            SELECT
+                claims.name
+            FROM
+                name_claims AS claims
+            WHERE
+                claims.public_key = $1
+                AND NOT EXISTS (
+                    SELECT
+                        1
+                    FROM
+                        name_claims AS other_claims
+                    WHERE
+                        other_claims.name = claims.name
+                        AND other_claims.document_id < claims.document_id
+                        AND other_claims.public_key != claims.public_key
+                )
+            ORDER BY
+                claims.document_id
+            LIMIT
+                1
+            ",
+        )
+        .bind(public_key.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))
+    }
+}
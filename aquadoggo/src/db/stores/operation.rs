@@ -1,9 +1,10 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::collections::HashSet;
 use std::fmt::Display;
 
 use async_trait::async_trait;
-use p2panda_rs::document::DocumentId;
+use p2panda_rs::document::{DocumentId, DocumentViewId};
 use p2panda_rs::hash::Hash;
 use p2panda_rs::identity::PublicKey;
 use p2panda_rs::operation::traits::AsOperation;
@@ -11,7 +12,7 @@ use p2panda_rs::operation::{Operation, OperationId};
 use p2panda_rs::schema::SchemaId;
 use p2panda_rs::storage_provider::error::OperationStorageError;
 use p2panda_rs::storage_provider::traits::OperationStore;
-use sqlx::{query, query_as, query_scalar, Any};
+use sqlx::{query, query_as, query_scalar, Any, Transaction};
 
 use crate::db::models::utils::{parse_operation_rows, parse_value_to_string_vec};
 use crate::db::models::{DocumentViewFieldRow, OperationFieldsJoinedRow};
@@ -40,6 +41,10 @@ impl OperationStore for SqlStore {
         &self,
         id: &OperationId,
     ) -> Result<Option<DocumentId>, OperationStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
         let document_id: Option<String> = query_scalar(
             "
             SELECT
@@ -73,6 +78,10 @@ impl OperationStore for SqlStore {
         operation: &Operation,
         document_id: &DocumentId,
     ) -> Result<(), OperationStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
         self.insert_operation_with_index(id, public_key, operation, document_id, None)
             .await
     }
@@ -85,6 +94,10 @@ impl OperationStore for SqlStore {
         &self,
         id: &OperationId,
     ) -> Result<Option<StorageOperation>, OperationStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
         let operation_rows = query_as::<_, OperationFieldsJoinedRow>(
             "
             SELECT
@@ -124,6 +137,10 @@ impl OperationStore for SqlStore {
         &self,
         id: &DocumentId,
     ) -> Result<Vec<StorageOperation>, OperationStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
         let operation_rows = query_as::<_, OperationFieldsJoinedRow>(
             "
             SELECT
@@ -168,6 +185,10 @@ impl OperationStore for SqlStore {
         &self,
         id: &SchemaId,
     ) -> Result<Vec<StorageOperation>, OperationStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
         let operation_rows = query_as::<_, OperationFieldsJoinedRow>(
             "
                 SELECT
@@ -293,6 +314,51 @@ impl SqlStore {
         Ok(())
     }
 
+    /// Returns the ids of the operations no other operation of the document refers to as its
+    /// previous, sorted by id.
+    ///
+    /// These are the tips of the operation graph of a document, every concurrent branch of edits
+    /// which was not merged yet by a later operation adds one tip.
+    pub async fn get_operation_graph_tips(
+        &self,
+        document_id: &DocumentId,
+    ) -> Result<Vec<OperationId>, OperationStorageError> {
+        let rows: Vec<(String, Option<String>)> = query_as(
+            "
+            SELECT
+                operations_v1.operation_id,
+                operations_v1.previous
+            FROM
+                operations_v1
+            WHERE
+                operations_v1.document_id = $1
+            ",
+        )
+        .bind(document_id.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
+        let mut previous_ids = HashSet::new();
+        for (_, previous) in &rows {
+            if let Some(previous) = previous {
+                let previous: DocumentViewId = previous
+                    .parse()
+                    .expect("invalid previous field in database");
+                previous_ids.extend(previous.iter().map(|id| id.as_str().to_owned()));
+            }
+        }
+
+        let mut tips: Vec<OperationId> = rows
+            .into_iter()
+            .filter(|(id, _)| !previous_ids.contains(id))
+            .map(|(id, _)| id.parse().expect("invalid operation id in database"))
+            .collect();
+        tips.sort();
+
+        Ok(tips)
+    }
+
     /// Insert an operation as well as the index for its position in the document after
     /// materialization has occurred.
     async fn insert_operation_with_index(
@@ -311,80 +377,15 @@ impl SqlStore {
             .await
             .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
 
-        // Construct query for inserting operation an row, execute it and check exactly one row was
-        // affected.
-        query(
-            "
-            INSERT INTO
-                operations_v1 (
-                    public_key,
-                    document_id,
-                    operation_id,
-                    action,
-                    schema_id,
-                    previous,
-                    sorted_index
-                )
-            VALUES
-                ($1, $2, $3, $4, $5, $6, $7)
-            ",
-        )
-        .bind(public_key.to_string())
-        .bind(document_id.as_str())
-        .bind(id.as_str())
-        .bind(operation.action().as_str())
-        .bind(operation.schema_id().to_string())
-        .bind(
-            operation
-                .previous()
-                .map(|document_view_id| document_view_id.to_string()),
+        insert_operation_rows(
+            &mut tx,
+            id,
+            public_key,
+            operation,
+            document_id,
+            sorted_index,
         )
-        .bind(sorted_index)
-        .execute(&mut tx)
-        .await
-        .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
-
-        let mut results = Vec::new();
-        if let Some(fields) = operation.fields() {
-            for (name, value) in fields.iter() {
-                // If the value is a relation_list or pinned_relation_list we need to insert a new
-                // field row for every item in the list. Here we collect these items and return
-                // them in a vector. If this operation value is anything except for the above list
-                // types, we will return a vec containing a single item.
-                let db_values = parse_value_to_string_vec(value);
-
-                for (index, db_value) in db_values.into_iter().enumerate() {
-                    let cursor = OperationCursor::new(index, name, id);
-
-                    let result = query(
-                        "
-                        INSERT INTO
-                            operation_fields_v1 (
-                                operation_id,
-                                name,
-                                field_type,
-                                value,
-                                list_index,
-                                cursor
-                            )
-                        VALUES
-                            ($1, $2, $3, $4, $5, $6)
-                        ",
-                    )
-                    .bind(id.as_str().to_owned())
-                    .bind(name.to_owned())
-                    .bind(value.field_type().to_string())
-                    .bind(db_value)
-                    .bind(index as i32)
-                    .bind(cursor.to_string())
-                    .execute(&mut tx)
-                    .await
-                    .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
-
-                    results.push(result);
-                }
-            }
-        };
+        .await?;
 
         // Commit the transaction.
         tx.commit()
@@ -395,6 +396,93 @@ impl SqlStore {
     }
 }
 
+/// Inserts the rows of an operation and its fields within the given transaction.
+pub(crate) async fn insert_operation_rows(
+    tx: &mut Transaction<'_, Any>,
+    id: &OperationId,
+    public_key: &PublicKey,
+    operation: &Operation,
+    document_id: &DocumentId,
+    sorted_index: Option<i32>,
+) -> Result<(), OperationStorageError> {
+    // Construct query for inserting operation an row, execute it and check exactly one row was
+    // affected.
+    query(
+        "
+        INSERT INTO
+            operations_v1 (
+                public_key,
+                document_id,
+                operation_id,
+                action,
+                schema_id,
+                previous,
+                sorted_index
+            )
+        VALUES
+            ($1, $2, $3, $4, $5, $6, $7)
+        ",
+    )
+    .bind(public_key.to_string())
+    .bind(document_id.as_str())
+    .bind(id.as_str())
+    .bind(operation.action().as_str())
+    .bind(operation.schema_id().to_string())
+    .bind(
+        operation
+            .previous()
+            .map(|document_view_id| document_view_id.to_string()),
+    )
+    .bind(sorted_index)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
+    let mut results = Vec::new();
+    if let Some(fields) = operation.fields() {
+        for (name, value) in fields.iter() {
+            // If the value is a relation_list or pinned_relation_list we need to insert a new
+            // field row for every item in the list. Here we collect these items and return
+            // them in a vector. If this operation value is anything except for the above list
+            // types, we will return a vec containing a single item.
+            let db_values = parse_value_to_string_vec(value);
+
+            for (index, db_value) in db_values.into_iter().enumerate() {
+                let cursor = OperationCursor::new(index, name, id);
+
+                let result = query(
+                    "
+                    INSERT INTO
+                        operation_fields_v1 (
+                            operation_id,
+                            name,
+                            field_type,
+                            value,
+                            list_index,
+                            cursor
+                        )
+                    VALUES
+                        ($1, $2, $3, $4, $5, $6)
+                    ",
+                )
+                .bind(id.as_str().to_owned())
+                .bind(name.to_owned())
+                .bind(value.field_type().to_string())
+                .bind(db_value)
+                .bind(index as i32)
+                .bind(cursor.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| OperationStorageError::FatalStorageError(e.to_string()))?;
+
+                results.push(result);
+            }
+        }
+    };
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct OperationCursor(String);
 
@@ -435,23 +523,23 @@ impl From<&DocumentViewFieldRow> for OperationCursor {
 #[cfg(test)]
 mod tests {
     use p2panda_rs::document::traits::AsDocument;
-    use p2panda_rs::document::{DocumentBuilder, DocumentId};
+    use p2panda_rs::document::{DocumentBuilder, DocumentId, DocumentViewId};
     use p2panda_rs::identity::{KeyPair, PublicKey};
     use p2panda_rs::operation::traits::{AsOperation, WithPublicKey};
     use p2panda_rs::operation::{Operation, OperationAction, OperationBuilder, OperationId};
-    use p2panda_rs::schema::SchemaId;
+    use p2panda_rs::schema::{FieldType, SchemaId};
     use p2panda_rs::storage_provider::traits::OperationStore;
     use p2panda_rs::test_utils::constants::test_fields;
     use p2panda_rs::test_utils::fixtures::{
-        document_id, operation, operation_id, operation_with_schema, public_key,
+        document_id, key_pair, operation, operation_id, operation_with_schema, public_key,
         random_document_view_id, random_operation_id, random_previous_operations, schema_id,
     };
     use p2panda_rs::WithId;
     use rstest::rstest;
 
     use crate::test_utils::{
-        doggo_fields, populate_and_materialize, populate_store_config, test_runner,
-        PopulateStoreConfig, TestNode,
+        add_document, add_schema, doggo_fields, populate_and_materialize, populate_store_config,
+        test_runner, update_document, PopulateStoreConfig, TestNode,
     };
 
     use super::OperationCursor;
@@ -639,6 +727,77 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn get_operation_graph_tips(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "bear",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+            let create_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "Panda".into())],
+                &key_pair,
+            )
+            .await;
+            let document_id: DocumentId = create_id.to_string().parse().unwrap();
+
+            let tips = node
+                .context
+                .store
+                .get_operation_graph_tips(&document_id)
+                .await
+                .unwrap();
+            assert_eq!(tips, create_id.iter().cloned().collect::<Vec<_>>());
+
+            // Two concurrent updates result in two branches.
+            let mut branches = Vec::new();
+            for name in ["Brown", "Polar"] {
+                let view_id = update_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("name", name.into())],
+                    &create_id,
+                    &key_pair,
+                )
+                .await;
+                branches.extend(view_id.iter().cloned());
+            }
+            branches.sort();
+
+            let tips = node
+                .context
+                .store
+                .get_operation_graph_tips(&document_id)
+                .await
+                .unwrap();
+            assert_eq!(tips, branches);
+
+            // An update referring to both branches merges them again.
+            let merged = DocumentViewId::new(&branches);
+            let merge_id = update_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "Grizzly".into())],
+                &merged,
+                &key_pair,
+            )
+            .await;
+
+            let tips = node
+                .context
+                .store
+                .get_operation_graph_tips(&document_id)
+                .await
+                .unwrap();
+            assert_eq!(tips, merge_id.iter().cloned().collect::<Vec<_>>());
+        });
+    }
+
     #[rstest]
     fn operation_cursor(operation_id: OperationId) {
         let cursor = OperationCursor::new(5, "username", &operation_id);
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use sqlx::{query, query_as};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::models::PeerAuthorRow;
+use crate::db::types::PeerAuthor;
+use crate::db::SqlStore;
+
+/// Methods to interact with the `peer_authors` table in the database.
+///
+/// The table can be inspected with the `peerAuthors` GraphQL query or from the admin console, for
+/// example to find out which peers sent entries of an unwanted author.
+impl SqlStore {
+    /// Adds entries of authors which were received from peers during replication.
+    ///
+    /// The given first seen timestamps are only used for peer and author pairs which are not
+    /// known yet, the last seen timestamps and entry counts of known pairs get updated.
+    pub async fn record_peer_authors(
+        &self,
+        peer_authors: &[PeerAuthor],
+    ) -> Result<(), SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        for peer_author in peer_authors {
+            query(
+                "
+                INSERT INTO
+                    peer_authors (
+                        peer_id,
+                        public_key,
+                        first_seen,
+                        last_seen,
+                        entries
+                    )
+                VALUES
+                    ($1, $2, $3, $4, $5)
+                ON CONFLICT (peer_id, public_key) DO UPDATE SET
+                    last_seen = $4,
+                    entries = peer_authors.entries + $5
+                ",
+            )
+            .bind(&peer_author.peer_id)
+            .bind(peer_author.public_key.to_string())
+            .bind(peer_author.first_seen as i64)
+            .bind(peer_author.last_seen as i64)
+            .bind(peer_author.entries as i64)
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Returns all authors received from peers, ordered by peer id and public key.
+    pub async fn get_peer_authors(&self) -> Result<Vec<PeerAuthor>, SqlStoreError> {
+        let rows = query_as::<_, PeerAuthorRow>(
+            "
+            SELECT
+                peer_id,
+                public_key,
+                first_seen,
+                last_seen,
+                entries
+            FROM
+                peer_authors
+            ORDER BY
+                peer_id, public_key
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PeerAuthor {
+                peer_id: row.peer_id,
+                public_key: row
+                    .public_key
+                    .parse()
+                    .expect("Public keys coming from the store should be valid"),
+                first_seen: row.first_seen as u64,
+                last_seen: row.last_seen as u64,
+                entries: row.entries as u64,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+
+    use crate::db::types::PeerAuthor;
+    use crate::test_utils::{test_runner, TestNode};
+
+    #[rstest]
+    fn record_peer_authors(key_pair: KeyPair) {
+        test_runner(|node: TestNode| async move {
+            let store = &node.context.store;
+            let public_key = key_pair.public_key();
+
+            let peer_author = |peer_id: &str, first_seen, last_seen, entries| PeerAuthor {
+                peer_id: peer_id.into(),
+                public_key,
+                first_seen,
+                last_seen,
+                entries,
+            };
+
+            store
+                .record_peer_authors(&[
+                    peer_author("peer_a", 100, 150, 1),
+                    peer_author("peer_b", 150, 150, 1),
+                ])
+                .await
+                .unwrap();
+
+            // Known pairs keep their first seen timestamp
+            store
+                .record_peer_authors(&[peer_author("peer_a", 180, 200, 1)])
+                .await
+                .unwrap();
+
+            assert_eq!(
+                store.get_peer_authors().await.unwrap(),
+                vec![
+                    PeerAuthor {
+                        peer_id: "peer_a".into(),
+                        public_key,
+                        first_seen: 100,
+                        last_seen: 200,
+                        entries: 2,
+                    },
+                    PeerAuthor {
+                        peer_id: "peer_b".into(),
+                        public_key,
+                        first_seen: 150,
+                        last_seen: 150,
+                        entries: 1,
+                    },
+                ]
+            );
+        });
+    }
+}
@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::document::DocumentId;
+use sqlx::{query, query_as, Any, Row, Transaction};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::types::ProjectionRow;
+use crate::db::SqlStore;
+use crate::projections::Projection;
+
+/// Methods to interact with projection tables and the `projection_relations` table in the
+/// database.
+///
+/// Every projection is kept in its own table with one column per projected field. Projection
+/// and field names are validated when the configuration gets loaded, which makes it safe to use
+/// them as identifiers in these queries.
+impl SqlStore {
+    /// Drops and re-creates the table of a projection, removing all its rows.
+    pub async fn rebuild_projection_table(
+        &self,
+        projection: &Projection,
+    ) -> Result<(), SqlStoreError> {
+        let columns: String = projection
+            .fields
+            .iter()
+            .map(|field| format!("\"{}\" TEXT, ", field.name))
+            .collect();
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query(&format!("DROP TABLE IF EXISTS {}", projection.table_name()))
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query(&format!(
+            "
+            CREATE TABLE {} (
+                document_id        TEXT    NOT NULL PRIMARY KEY,
+                document_view_id   TEXT    NOT NULL,
+                {}
+                FOREIGN KEY(document_id) REFERENCES documents(document_id) ON DELETE CASCADE
+            )
+            ",
+            projection.table_name(),
+            columns
+        ))
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query(
+            "
+            DELETE FROM
+                projection_relations
+            WHERE
+                projection_name = $1
+            ",
+        )
+        .bind(&projection.name)
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Inserts or replaces the row of a document in a projection table.
+    ///
+    /// The ids of related documents the values were read from are remembered, this allows
+    /// updating the row again when one of them changes.
+    pub async fn upsert_projection_row(
+        &self,
+        projection: &Projection,
+        row: &ProjectionRow,
+        related_document_ids: &[DocumentId],
+    ) -> Result<(), SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        delete_projection_row(&mut tx, projection, &row.document_id).await?;
+
+        let columns: String = projection
+            .fields
+            .iter()
+            .map(|field| format!(", \"{}\"", field.name))
+            .collect();
+        let placeholders: String = (0..projection.fields.len())
+            .map(|index| format!(", ${}", index + 3))
+            .collect();
+
+        let sql = format!(
+            "
+            INSERT INTO
+                {} (document_id, document_view_id{})
+            VALUES
+                ($1, $2{})
+            ",
+            projection.table_name(),
+            columns,
+            placeholders
+        );
+
+        let mut insert = query(&sql)
+            .bind(row.document_id.as_str())
+            .bind(row.view_id.to_string());
+        for value in &row.values {
+            insert = insert.bind(value.as_deref());
+        }
+        insert
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        for related_document_id in related_document_ids {
+            query(
+                "
+                INSERT INTO
+                    projection_relations (
+                        projection_name,
+                        document_id,
+                        related_document_id
+                    )
+                VALUES
+                    ($1, $2, $3)
+                ON CONFLICT DO NOTHING
+                ",
+            )
+            .bind(&projection.name)
+            .bind(row.document_id.as_str())
+            .bind(related_document_id.as_str())
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Removes the row of a document from a projection table.
+    pub async fn delete_projection_row(
+        &self,
+        projection: &Projection,
+        document_id: &DocumentId,
+    ) -> Result<(), SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        delete_projection_row(&mut tx, projection, document_id).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get rows of a projection ordered by document id.
+    ///
+    /// Returns at most `first` rows with a document id greater than `after` when given.
+    pub async fn get_projection_rows(
+        &self,
+        projection: &Projection,
+        first: u64,
+        after: Option<&DocumentId>,
+    ) -> Result<Vec<ProjectionRow>, SqlStoreError> {
+        let columns: String = projection
+            .fields
+            .iter()
+            .map(|field| format!(", \"{}\"", field.name))
+            .collect();
+
+        let sql = format!(
+            "
+            SELECT
+                document_id,
+                document_view_id{}
+            FROM
+                {}
+            WHERE
+                document_id > $1
+            ORDER BY
+                document_id ASC
+            LIMIT
+                $2
+            ",
+            columns,
+            projection.table_name()
+        );
+
+        let rows = query(&sql)
+            .bind(after.map_or("", |document_id| document_id.as_str()))
+            .bind(first as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                let document_id: String = row
+                    .try_get(0)
+                    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+                let view_id: String = row
+                    .try_get(1)
+                    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+                let values = (0..projection.fields.len())
+                    .map(|index| row.try_get::<Option<String>, _>(index + 2))
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+                Ok(ProjectionRow {
+                    document_id: document_id
+                        .parse()
+                        .expect("Document ids coming from the store are valid"),
+                    view_id: view_id
+                        .parse()
+                        .expect("Document view ids coming from the store are valid"),
+                    values,
+                })
+            })
+            .collect()
+    }
+
+    /// Get the names of projections and ids of documents which read values from the given
+    /// related document.
+    pub async fn get_projected_documents_by_relation(
+        &self,
+        related_document_id: &DocumentId,
+    ) -> Result<Vec<(String, DocumentId)>, SqlStoreError> {
+        let rows: Vec<(String, String)> = query_as(
+            "
+            SELECT
+                projection_name,
+                document_id
+            FROM
+                projection_relations
+            WHERE
+                related_document_id = $1
+            ",
+        )
+        .bind(related_document_id.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(projection_name, document_id)| {
+                let document_id = document_id
+                    .parse()
+                    .expect("Document ids coming from the store are valid");
+                (projection_name, document_id)
+            })
+            .collect())
+    }
+}
+
+/// Removes the row of a document and its remembered relations from a projection.
+async fn delete_projection_row(
+    tx: &mut Transaction<'_, Any>,
+    projection: &Projection,
+    document_id: &DocumentId,
+) -> Result<(), SqlStoreError> {
+    query(&format!(
+        "DELETE FROM {} WHERE document_id = $1",
+        projection.table_name()
+    ))
+    .bind(document_id.as_str())
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+    query(
+        "
+        DELETE FROM
+            projection_relations
+        WHERE
+            projection_name = $1
+            AND document_id = $2
+        ",
+    )
+    .bind(&projection.name)
+    .bind(document_id.as_str())
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+    Ok(())
+}
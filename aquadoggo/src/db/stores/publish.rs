@@ -0,0 +1,497 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Atomic insertion of published entries and their operations.
+//!
+//! `publish` of `p2panda-rs` validates an entry and its operation against the store and inserts
+//! the log, entry and operation with separate queries afterwards. A crash in between leaves an
+//! entry without its operation behind, which confuses replication and materialization later.
+//!
+//! Here the inserts are staged while `publish` runs and written in one transaction after it
+//! succeeded, together with the task materializing the operation.
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use log::warn;
+use p2panda_rs::api::{publish, DomainError};
+use p2panda_rs::document::DocumentId;
+use p2panda_rs::entry::traits::{AsEncodedEntry, AsEntry};
+use p2panda_rs::entry::{EncodedEntry, Entry, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::PublicKey;
+use p2panda_rs::operation::plain::PlainOperation;
+use p2panda_rs::operation::{EncodedOperation, Operation, OperationId};
+use p2panda_rs::schema::{Schema, SchemaId};
+use p2panda_rs::storage_provider::error::{
+    EntryStorageError, LogStorageError, OperationStorageError,
+};
+use p2panda_rs::storage_provider::traits::{EntryStore, LogStore, OperationStore};
+use sqlx::query_as;
+
+use crate::db::models::EntryRow;
+use crate::db::stores::entry::insert_entry_row;
+use crate::db::stores::log::insert_log_row;
+use crate::db::stores::operation::insert_operation_rows;
+use crate::db::stores::task::insert_task_row;
+use crate::db::types::{StorageEntry, StorageOperation};
+use crate::db::SqlStore;
+use crate::materializer::{Task, TaskInput};
+
+/// Backlink, skiplink, sequence number and log id of the next entry, returned after publishing.
+pub type NextArgs = (Option<Hash>, Option<Hash>, SeqNum, LogId);
+
+/// Rows written by `publish` of `p2panda-rs`, they get inserted together afterwards.
+#[derive(Default)]
+struct StagedRows {
+    log: Option<(LogId, PublicKey, SchemaId, DocumentId)>,
+    entry: Option<(Entry, EncodedEntry, Option<EncodedOperation>)>,
+    operation: Option<(OperationId, PublicKey, Operation, DocumentId)>,
+}
+
+/// Store passing all reads through to the database while staging all writes.
+struct StagingStore<'a> {
+    store: &'a SqlStore,
+    rows: Mutex<StagedRows>,
+}
+
+impl<'a> StagingStore<'a> {
+    fn new(store: &'a SqlStore) -> Self {
+        Self {
+            store,
+            rows: Mutex::new(StagedRows::default()),
+        }
+    }
+
+    fn rows(&self) -> std::sync::MutexGuard<'_, StagedRows> {
+        self.rows
+            .lock()
+            .expect("Could not acquire lock on staged rows")
+    }
+}
+
+#[async_trait]
+impl LogStore for StagingStore<'_> {
+    async fn insert_log(
+        &self,
+        log_id: &LogId,
+        public_key: &PublicKey,
+        schema: &SchemaId,
+        document: &DocumentId,
+    ) -> Result<bool, LogStorageError> {
+        self.rows().log = Some((
+            log_id.to_owned(),
+            public_key.to_owned(),
+            schema.to_owned(),
+            document.to_owned(),
+        ));
+        Ok(true)
+    }
+
+    async fn get_log_id(
+        &self,
+        public_key: &PublicKey,
+        document_id: &DocumentId,
+    ) -> Result<Option<LogId>, LogStorageError> {
+        self.store.get_log_id(public_key, document_id).await
+    }
+
+    async fn latest_log_id(
+        &self,
+        public_key: &PublicKey,
+    ) -> Result<Option<LogId>, LogStorageError> {
+        self.store.latest_log_id(public_key).await
+    }
+}
+
+#[async_trait]
+impl EntryStore for StagingStore<'_> {
+    type Entry = StorageEntry;
+
+    async fn insert_entry(
+        &self,
+        entry: &Entry,
+        encoded_entry: &EncodedEntry,
+        encoded_operation: Option<&EncodedOperation>,
+    ) -> Result<(), EntryStorageError> {
+        self.rows().entry = Some((
+            entry.to_owned(),
+            encoded_entry.to_owned(),
+            encoded_operation.cloned(),
+        ));
+        Ok(())
+    }
+
+    async fn get_entry_at_seq_num(
+        &self,
+        public_key: &PublicKey,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<Option<StorageEntry>, EntryStorageError> {
+        self.store
+            .get_entry_at_seq_num(public_key, log_id, seq_num)
+            .await
+    }
+
+    async fn get_entry(&self, hash: &Hash) -> Result<Option<StorageEntry>, EntryStorageError> {
+        self.store.get_entry(hash).await
+    }
+
+    async fn get_latest_entry(
+        &self,
+        public_key: &PublicKey,
+        log_id: &LogId,
+    ) -> Result<Option<StorageEntry>, EntryStorageError> {
+        self.store.get_latest_entry(public_key, log_id).await
+    }
+}
+
+#[async_trait]
+impl OperationStore for StagingStore<'_> {
+    type Operation = StorageOperation;
+
+    async fn insert_operation(
+        &self,
+        id: &OperationId,
+        public_key: &PublicKey,
+        operation: &Operation,
+        document_id: &DocumentId,
+    ) -> Result<(), OperationStorageError> {
+        self.rows().operation = Some((
+            id.to_owned(),
+            public_key.to_owned(),
+            operation.to_owned(),
+            document_id.to_owned(),
+        ));
+        Ok(())
+    }
+
+    async fn get_operation(
+        &self,
+        id: &OperationId,
+    ) -> Result<Option<StorageOperation>, OperationStorageError> {
+        self.store.get_operation(id).await
+    }
+
+    async fn get_document_id_by_operation_id(
+        &self,
+        id: &OperationId,
+    ) -> Result<Option<DocumentId>, OperationStorageError> {
+        self.store.get_document_id_by_operation_id(id).await
+    }
+
+    async fn get_operations_by_document_id(
+        &self,
+        id: &DocumentId,
+    ) -> Result<Vec<StorageOperation>, OperationStorageError> {
+        self.store.get_operations_by_document_id(id).await
+    }
+
+    async fn get_operations_by_schema_id(
+        &self,
+        id: &SchemaId,
+    ) -> Result<Vec<StorageOperation>, OperationStorageError> {
+        self.store.get_operations_by_schema_id(id).await
+    }
+}
+
+impl SqlStore {
+    /// Validates an entry and its operation and inserts them into the database.
+    ///
+    /// The log, entry and operation rows are written in one transaction together with a "reduce"
+    /// task for the document, this way the materializer picks up the operation even if the node
+    /// stops right after.
+    pub async fn publish_atomically(
+        &self,
+        schema: &Schema,
+        encoded_entry: &EncodedEntry,
+        plain_operation: &PlainOperation,
+        encoded_operation: &EncodedOperation,
+    ) -> Result<NextArgs, DomainError> {
+        self.validate_blob_operation(plain_operation)
+            .map_err(|err| OperationStorageError::Custom(err.to_string()))?;
+
+        let staging_store = StagingStore::new(self);
+
+        let next_args = publish(
+            &staging_store,
+            schema,
+            encoded_entry,
+            plain_operation,
+            encoded_operation,
+        )
+        .await?;
+
+        let rows = staging_store
+            .rows
+            .into_inner()
+            .expect("Could not acquire lock on staged rows");
+        self.insert_staged_rows(rows).await?;
+
+        Ok(next_args)
+    }
+
+    /// Inserts all staged rows within one transaction.
+    async fn insert_staged_rows(&self, rows: StagedRows) -> Result<(), DomainError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| EntryStorageError::Custom(err.to_string()))?;
+
+        if let Some((log_id, public_key, schema_id, document_id)) = &rows.log {
+            insert_log_row(&mut tx, log_id, public_key, schema_id, document_id).await?;
+        }
+
+        if let Some((entry, encoded_entry, encoded_operation)) = &rows.entry {
+            insert_entry_row(&mut tx, entry, encoded_entry, encoded_operation.as_ref()).await?;
+        }
+
+        if let Some((operation_id, public_key, operation, document_id)) = &rows.operation {
+            insert_operation_rows(
+                &mut tx,
+                operation_id,
+                public_key,
+                operation,
+                document_id,
+                None,
+            )
+            .await?;
+
+            let task = Task::new("reduce", TaskInput::DocumentId(document_id.to_owned()));
+            insert_task_row(&mut tx, &task)
+                .await
+                .map_err(|err| OperationStorageError::FatalStorageError(err.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| EntryStorageError::Custom(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Returns the hashes of all entries whose operation is missing in the database.
+    ///
+    /// Entries published before their operations were inserted in the same transaction might
+    /// have been left behind like this when the node stopped in between.
+    pub async fn get_entries_without_operation(&self) -> Result<Vec<Hash>, EntryStorageError> {
+        let entries = self.get_orphaned_entries().await?;
+        Ok(entries.iter().map(|entry| entry.hash()).collect())
+    }
+
+    /// Removes entries whose operation is missing in the database.
+    ///
+    /// Only entries at the end of their log get removed, removing entries in the middle would
+    /// break the log. They can be published again afterwards, for example by replaying the
+    /// operation journal. Returns the hashes of the removed entries.
+    pub async fn remove_entries_without_operation(&self) -> Result<Vec<Hash>, EntryStorageError> {
+        let mut removed = Vec::new();
+
+        for entry in self.get_orphaned_entries().await? {
+            let is_latest = self
+                .get_latest_entry(entry.public_key(), entry.log_id())
+                .await?
+                .is_some_and(|latest_entry| latest_entry.hash() == entry.hash());
+
+            if is_latest {
+                removed.push(entry.hash());
+            } else {
+                warn!(
+                    "Entry {} is missing its operation but can't be removed as later entries of \
+                    its log exist",
+                    entry.hash()
+                );
+            }
+        }
+
+        self.remove_entries(&removed).await?;
+
+        Ok(removed)
+    }
+
+    /// Returns all entries whose operation is missing in the database.
+    async fn get_orphaned_entries(&self) -> Result<Vec<StorageEntry>, EntryStorageError> {
+        let entry_rows = query_as::<_, EntryRow>(
+            "
+            SELECT
+                entries.public_key,
+                entries.entry_bytes,
+                entries.entry_hash,
+                entries.log_id,
+                entries.payload_bytes,
+                entries.payload_hash,
+                entries.seq_num
+            FROM
+                entries
+            WHERE
+                NOT EXISTS (
+                    SELECT
+                        1
+                    FROM
+                        operations_v1
+                    WHERE
+                        operations_v1.operation_id = entries.entry_hash
+                )
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| EntryStorageError::Custom(err.to_string()))?;
+
+        Ok(entry_rows.into_iter().map(|row| row.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::entry::encode::sign_and_encode_entry;
+    use p2panda_rs::entry::traits::AsEncodedEntry;
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::decode::decode_operation;
+    use p2panda_rs::operation::encode::encode_operation;
+    use p2panda_rs::operation::{OperationBuilder, OperationId};
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::storage_provider::traits::{EntryStore, LogStore, OperationStore};
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use sqlx::query;
+
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{
+        add_schema, populate_store, populate_store_config, test_runner, PopulateStoreConfig,
+        TestNode,
+    };
+
+    #[rstest]
+    fn publish_atomically(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "messages",
+                vec![("message", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let author = KeyPair::new();
+            let operation = OperationBuilder::new(schema.id())
+                .fields(&[("message", "Hello!".into())])
+                .build()
+                .unwrap();
+            let encoded_operation = encode_operation(&operation).unwrap();
+            let plain_operation = decode_operation(&encoded_operation).unwrap();
+            let encoded_entry = sign_and_encode_entry(
+                &LogId::default(),
+                &SeqNum::default(),
+                None,
+                None,
+                &encoded_operation,
+                &author,
+            )
+            .unwrap();
+
+            let store = &node.context.store;
+            store
+                .publish_atomically(
+                    &schema,
+                    &encoded_entry,
+                    &plain_operation,
+                    &encoded_operation,
+                )
+                .await
+                .unwrap();
+
+            // Log, entry and operation were inserted together with the task materializing the
+            // document
+            let operation_id: OperationId = encoded_entry.hash().into();
+            let document_id = DocumentId::new(&operation_id);
+            assert_eq!(
+                store
+                    .get_log_id(&author.public_key(), &document_id)
+                    .await
+                    .unwrap(),
+                Some(LogId::default())
+            );
+            assert!(store
+                .get_entry(&encoded_entry.hash())
+                .await
+                .unwrap()
+                .is_some());
+            assert!(store.get_operation(&operation_id).await.unwrap().is_some());
+            assert!(store
+                .get_tasks()
+                .await
+                .unwrap()
+                .contains(&Task::new("reduce", TaskInput::DocumentId(document_id))));
+
+            // Publishing the same entry again fails without inserting anything
+            assert!(store
+                .publish_atomically(
+                    &schema,
+                    &encoded_entry,
+                    &plain_operation,
+                    &encoded_operation
+                )
+                .await
+                .is_err());
+            assert!(store
+                .get_entries_without_operation()
+                .await
+                .unwrap()
+                .is_empty());
+        });
+    }
+
+    #[rstest]
+    fn remove_entries_without_operation(
+        #[from(populate_store_config)]
+        #[with(3, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|node: TestNode| async move {
+            populate_store(&node.context.store, &config).await;
+            let store = &node.context.store;
+            let public_key = config.authors[0].public_key();
+
+            let mut entry_hashes = Vec::new();
+            for seq_num in 1..=3 {
+                let entry = store
+                    .get_entry_at_seq_num(
+                        &public_key,
+                        &LogId::default(),
+                        &SeqNum::new(seq_num).unwrap(),
+                    )
+                    .await
+                    .unwrap()
+                    .unwrap();
+                entry_hashes.push(entry.hash());
+            }
+
+            // Remove the operations of the last two entries, as if the node stopped right after
+            // inserting the entries
+            for hash in &entry_hashes[1..] {
+                query("DELETE FROM operations_v1 WHERE operation_id = $1")
+                    .bind(hash.as_str())
+                    .execute(&store.pool)
+                    .await
+                    .unwrap();
+            }
+
+            let mut orphaned = store.get_entries_without_operation().await.unwrap();
+            orphaned.sort();
+            let mut expected = entry_hashes[1..].to_vec();
+            expected.sort();
+            assert_eq!(orphaned, expected);
+
+            // Only the last entry of the log can be removed
+            let removed = store.remove_entries_without_operation().await.unwrap();
+            assert_eq!(removed, vec![entry_hashes[2].clone()]);
+            assert!(store.get_entry(&entry_hashes[2]).await.unwrap().is_none());
+            assert_eq!(
+                store.get_entries_without_operation().await.unwrap(),
+                vec![entry_hashes[1].clone()]
+            );
+        });
+    }
+}
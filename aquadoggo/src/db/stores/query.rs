@@ -9,19 +9,20 @@ use std::str::FromStr;
 use anyhow::bail;
 use p2panda_rs::document::DocumentViewId;
 use p2panda_rs::operation::OperationValue;
-use p2panda_rs::schema::{FieldName, Schema, SchemaId};
+use p2panda_rs::schema::{FieldName, FieldType, Schema, SchemaId};
 use p2panda_rs::storage_provider::error::DocumentStorageError;
 use sqlx::query::QueryAs;
 use sqlx::query_as;
 
 use crate::db::models::utils::parse_document_view_field_rows;
 use crate::db::models::{DocumentViewFieldRow, QueryRow};
+use crate::db::query::errors::QueryError;
 use crate::db::query::{
     ApplicationFields, Cursor, Direction, Field, Filter, FilterBy, FilterSetting, LowerBound,
     MetaField, Order, Pagination, PaginationField, Select, UpperBound,
 };
 use crate::db::stores::OperationCursor;
-use crate::db::types::StorageDocument;
+use crate::db::types::{Aggregates, FieldAggregate, StorageDocument};
 use crate::db::{Pool, SqlStore};
 
 /// Configure query to select documents based on a relation list field.
@@ -101,6 +102,12 @@ impl Display for PaginationCursor {
     }
 }
 
+/// SQL selecting the cursor of a row.
+///
+/// Deleted documents don't have any fields left, they are represented by one row without an
+/// operation field. Their document id serves as the cursor instead.
+const CURSOR_SQL: &str = "COALESCE(operation_fields_v1.cursor, documents.document_id)";
+
 // This should _not_ be an underscore character since we're also parsing document view ids in the
 // string which contain that character already
 const CURSOR_SEPARATOR: char = '-';
@@ -230,10 +237,10 @@ fn typecast_field_sql(
         .unwrap_or_else(|| panic!("Field '{}' not given in Schema", field_name));
 
     match field_type {
-        p2panda_rs::schema::FieldType::Integer => {
+        FieldType::Integer => {
             format!("CAST ({sql_field} AS INTEGER)")
         }
-        p2panda_rs::schema::FieldType::Float => {
+        FieldType::Float => {
             format!("CAST ({sql_field} AS REAL)")
         }
         // All other types (booleans, relations, etc.) we keep as strings. We can not convert
@@ -454,31 +461,66 @@ fn where_filter_sql(filter: &Filter, schema: &Schema) -> (String, Vec<BindArgume
                     cmp_sql("documents.document_view_id", filter_setting, &mut args)
                 )),
                 Field::Field(field_name) => {
-                    let field_sql = typecast_field_sql("operation_fields_v1.value", field_name, schema, true);
-                    let filter_cmp = cmp_sql(&field_sql, filter_setting, &mut args);
-
-                    Some(format!(
+                    // Documents might not have a value for every field of their schema, check if
+                    // there is one at all
+                    let field_exists_sql = format!(
                         r#"
-                        AND EXISTS (
+                        EXISTS (
                             SELECT
-                                operation_fields_v1.value
+                                document_view_fields_subquery.name
                             FROM
                                 document_view_fields AS document_view_fields_subquery
-                                JOIN operation_fields_v1
-                                    ON
-                                        document_view_fields_subquery.operation_id = operation_fields_v1.operation_id
-                                    AND
-                                        document_view_fields_subquery.name = operation_fields_v1.name
                             WHERE
-                                -- Match document_view_fields of this subquery with the parent one
                                 document_view_fields.document_view_id = document_view_fields_subquery.document_view_id
+                                AND document_view_fields_subquery.name = '{field_name}'
+                        )
+                        "#
+                    );
 
-                                -- Check if this document view fullfils this filter
-                                AND operation_fields_v1.name = '{field_name}'
-                                AND
-                                    {filter_cmp}
-                                AND
-                                    operation_fields_v1.operation_id = document_view_fields_subquery.operation_id
+                    if filter_setting.by == FilterBy::Null {
+                        return if filter_setting.exclusive {
+                            Some(format!("AND {field_exists_sql}"))
+                        } else {
+                            Some(format!("AND NOT {field_exists_sql}"))
+                        };
+                    }
+
+                    let field_sql = typecast_field_sql("operation_fields_v1.value", field_name, schema, true);
+                    let filter_cmp = cmp_sql(&field_sql, filter_setting, &mut args);
+
+                    // Negated filters also match documents without any value for this field,
+                    // missing values are never equal to, in or containing the given ones
+                    let or_missing_sql = if filter_setting.exclusive {
+                        format!("OR NOT {field_exists_sql}")
+                    } else {
+                        "".to_string()
+                    };
+
+                    Some(format!(
+                        r#"
+                        AND (
+                            EXISTS (
+                                SELECT
+                                    operation_fields_v1.value
+                                FROM
+                                    document_view_fields AS document_view_fields_subquery
+                                    JOIN operation_fields_v1
+                                        ON
+                                            document_view_fields_subquery.operation_id = operation_fields_v1.operation_id
+                                        AND
+                                            document_view_fields_subquery.name = operation_fields_v1.name
+                                WHERE
+                                    -- Match document_view_fields of this subquery with the parent one
+                                    document_view_fields.document_view_id = document_view_fields_subquery.document_view_id
+
+                                    -- Check if this document view fullfils this filter
+                                    AND operation_fields_v1.name = '{field_name}'
+                                    AND
+                                        {filter_cmp}
+                                    AND
+                                        operation_fields_v1.operation_id = document_view_fields_subquery.operation_id
+                            )
+                            {or_missing_sql}
                         )
                         "#
                     ))
@@ -488,7 +530,155 @@ fn where_filter_sql(filter: &Filter, schema: &Schema) -> (String, Vec<BindArgume
         .collect::<Vec<String>>()
         .join("\n");
 
-    (sql, args)
+    // Match documents containing the search string in any of their string fields, independent of
+    // upper- or lowercase characters
+    let search_sql = match filter.search() {
+        Some(search) => {
+            args.push(BindArgument::String(format!("%{}%", search.to_lowercase())));
+
+            format!(
+                r#"
+                AND EXISTS (
+                    SELECT
+                        operation_fields_v1.value
+                    FROM
+                        document_view_fields AS document_view_fields_subquery
+                        JOIN operation_fields_v1
+                            ON
+                                document_view_fields_subquery.operation_id = operation_fields_v1.operation_id
+                            AND
+                                document_view_fields_subquery.name = operation_fields_v1.name
+                    WHERE
+                        document_view_fields.document_view_id = document_view_fields_subquery.document_view_id
+                        AND operation_fields_v1.field_type = 'str'
+                        AND LOWER(operation_fields_v1.value) LIKE ${}
+                )
+                "#,
+                args.len()
+            )
+        }
+        None => "".to_string(),
+    };
+
+    (format!("{sql}\n{search_sql}"), args)
+}
+
+/// Selects a meta value of the document the given cursor is pointing at.
+///
+/// Returns an error when the meta field is not supported for pagination.
+async fn cursor_meta_value(
+    pool: &Pool,
+    operation_cursor: &OperationCursor,
+    meta_field: &MetaField,
+) -> Result<String, DocumentStorageError> {
+    let cmp_value_pre = match meta_field {
+        MetaField::DocumentId => {
+            // Select document_id of operation where the cursor points at
+            format!(
+                r#"
+                SELECT
+                    operations_v1.document_id
+                FROM
+                    operation_fields_v1
+                    JOIN operations_v1
+                        ON operation_fields_v1.operation_id = operations_v1.operation_id
+                WHERE
+                    operation_fields_v1.cursor = '{operation_cursor}'
+                UNION ALL
+                SELECT
+                    documents.document_id
+                FROM
+                    documents
+                WHERE
+                    documents.document_id = '{operation_cursor}'
+                LIMIT 1
+                "#
+            )
+        }
+        MetaField::DocumentViewId => {
+            // Select document_view_id of operation where the cursor points at
+            format!(
+                r#"
+                SELECT
+                    document_view_fields.document_view_id
+                FROM
+                    operation_fields_v1
+                    JOIN document_view_fields
+                        ON operation_fields_v1.operation_id = document_view_fields.operation_id
+                WHERE
+                    operation_fields_v1.cursor = '{operation_cursor}'
+                UNION ALL
+                SELECT
+                    documents.document_view_id
+                FROM
+                    documents
+                WHERE
+                    documents.document_id = '{operation_cursor}'
+                LIMIT 1
+                "#
+            )
+        }
+        MetaField::Owner | MetaField::Edited | MetaField::Deleted => {
+            // @TODO: See issue: https://github.com/p2panda/aquadoggo/issues/326
+            return Err(DocumentStorageError::FatalStorageError(
+                QueryError::PaginationOrderFieldUnsupported(meta_field.to_string()).to_string(),
+            ));
+        }
+    };
+
+    let cmp_value: (String,) = query_as(&cmp_value_pre)
+        .fetch_one(pool)
+        .await
+        .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
+
+    Ok(cmp_value.0)
+}
+
+/// Selects the value of an application field of the document the given cursor is pointing at.
+async fn cursor_field_value(
+    pool: &Pool,
+    operation_cursor: &OperationCursor,
+    field_name: &str,
+) -> Result<String, DocumentStorageError> {
+    let cmp_value_pre = format!(
+        r#"
+        SELECT
+            operation_fields_v1.value
+
+        FROM
+            operation_fields_v1
+            LEFT JOIN
+                document_view_fields
+                ON document_view_fields.operation_id = operation_fields_v1.operation_id
+
+        WHERE
+            document_view_fields.document_view_id = (
+                SELECT
+                    document_view_fields.document_view_id
+
+                FROM
+                    operation_fields_v1
+                    LEFT JOIN
+                        document_view_fields
+                        ON document_view_fields.operation_id = operation_fields_v1.operation_id
+
+                WHERE
+                    operation_fields_v1.cursor = '{operation_cursor}'
+
+                LIMIT 1
+            )
+            AND operation_fields_v1.name = '{field_name}'
+
+        LIMIT 1
+        "#
+    );
+
+    let cmp_value: (String,) = query_as(&cmp_value_pre)
+        .fetch_one(pool)
+        .await
+        .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
+
+    Ok(cmp_value.0)
 }
 
 /// Generate SQL for cursor-based pagination.
@@ -513,6 +703,10 @@ fn where_filter_sql(filter: &Filter, schema: &Schema) -> (String, Vec<BindArgume
 /// Pagination is strictly connected to the chosen ordering by the client of the results. We need
 /// to take the ordering into account to understand which "next page" to show.
 ///
+/// When ordering by a field, rows sharing the same value are always sorted by their document id
+/// (ascending, independent of the chosen direction) and then by cursor. This makes the order and
+/// with it the pagination deterministic across database backends.
+///
 /// ## Pre-Queries
 ///
 /// This method is async as it does some smaller "pre" SQL queries before the "main" query. This is
@@ -558,10 +752,23 @@ async fn where_pagination_sql(
             format!("operation_fields_v1_list.cursor > '{root_cursor}'")
         }
         None => {
-            format!("operation_fields_v1.cursor > '{operation_cursor}'")
+            format!("{CURSOR_SQL} > '{operation_cursor}'")
         }
     };
 
+    // Ordering over multiple fields compares all of them one after another
+    if !order.then_by.is_empty() {
+        return where_pagination_fields_sql(
+            pool,
+            bind_args,
+            operation_cursor,
+            &cursor_sql,
+            schema,
+            order,
+        )
+        .await;
+    }
+
     let cmp_direction = match order.direction {
         Direction::Ascending => ">",
         Direction::Descending => "<",
@@ -591,6 +798,8 @@ async fn where_pagination_sql(
                 //
                 // -> Select document_view_id at cursor 0xc2
                 // -> Show results from document_view_id > 0x01
+                //
+                // Cursors of deleted documents are their document id.
                 let cmp_value_pre = format!(
                     r#"
                     SELECT
@@ -601,6 +810,13 @@ async fn where_pagination_sql(
                             ON operation_fields_v1.operation_id = document_view_fields.operation_id
                     WHERE
                         operation_fields_v1.cursor = '{operation_cursor}'
+                    UNION ALL
+                    SELECT
+                        documents.document_view_id
+                    FROM
+                        documents
+                    WHERE
+                        documents.document_id = '{operation_cursor}'
                     LIMIT 1
                     "#
                 );
@@ -673,56 +889,22 @@ async fn where_pagination_sql(
         // We select the meta data from the document the cursor points at and use it to paginate
         // over.
         Some(Field::Meta(meta_field)) => {
-            let (cmp_value_pre, cmp_field) = match meta_field {
-                MetaField::DocumentId => {
-                    // Select document_id of operation where the cursor points at
-                    let cmp_value = format!(
-                        r#"
-                        SELECT
-                            operations_v1.document_id
-                        FROM
-                            operation_fields_v1
-                            JOIN operations_v1
-                                ON operation_fields_v1.operation_id = operations_v1.operation_id
-                        WHERE
-                            operation_fields_v1.cursor = '{operation_cursor}'
-                        LIMIT 1
-                        "#
-                    );
-
-                    (cmp_value, "documents.document_id")
-                }
-                MetaField::DocumentViewId => {
-                    // Select document_view_id of operation where the cursor points at
-                    let cmp_value = format!(
-                        r#"
-                        SELECT
-                            document_view_fields.document_view_id
-                        FROM
-                            operation_fields_v1
-                            JOIN document_view_fields
-                                ON operation_fields_v1.operation_id = document_view_fields.operation_id
-                        WHERE
-                            operation_fields_v1.cursor = '{operation_cursor}'
-                        LIMIT 1
-                        "#
-                    );
-
-                    (cmp_value, "documents.document_view_id")
-                }
+            let cmp_field = match meta_field {
+                MetaField::DocumentId => "documents.document_id",
+                MetaField::DocumentViewId => "documents.document_view_id",
                 MetaField::Owner | MetaField::Edited | MetaField::Deleted => {
                     // @TODO: See issue: https://github.com/p2panda/aquadoggo/issues/326
-                    todo!("Not implemented");
+                    return Err(DocumentStorageError::FatalStorageError(
+                        QueryError::PaginationOrderFieldUnsupported(meta_field.to_string())
+                            .to_string(),
+                    ));
                 }
             };
 
             // Make a "pre" SQL query to avoid duplicate sub SELECT's always returning the same
             // result
-            let cmp_value: (String,) = query_as(&cmp_value_pre)
-                .fetch_one(pool)
-                .await
-                .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
-            let cmp_value = format!("'{}'", cmp_value.0);
+            let cmp_value = cursor_meta_value(pool, operation_cursor, meta_field).await?;
+            let cmp_value = format!("'{cmp_value}'");
 
             if fields.is_empty() && list.is_none() {
                 // If:
@@ -778,50 +960,18 @@ async fn where_pagination_sql(
         // -> Show results from "timestamp" value > other "timestamp" values
         Some(Field::Field(order_field_name)) => {
             // Select the value we want to compare with from the document the cursor is pointing
-            // at. This is the value which we also order the whole results by
-            let cmp_value_pre = format!(
-                r#"
-                SELECT
-                    operation_fields_v1.value
-
-                FROM
-                    operation_fields_v1
-                    LEFT JOIN
-                        document_view_fields
-                        ON document_view_fields.operation_id = operation_fields_v1.operation_id
-
-                WHERE
-                    document_view_fields.document_view_id = (
-                        SELECT
-                            document_view_fields.document_view_id
-
-                        FROM
-                            operation_fields_v1
-                            LEFT JOIN
-                                document_view_fields
-                                ON document_view_fields.operation_id = operation_fields_v1.operation_id
-
-                        WHERE
-                            operation_fields_v1.cursor = '{operation_cursor}'
-
-                        LIMIT 1
-                    )
-                    AND operation_fields_v1.name = '{order_field_name}'
-
-                LIMIT 1
-                "#
-            );
-
-            // Make a "pre" SQL query to avoid duplicate sub SELECT's always returning the same
-            // result.
+            // at. This is the value which we also order the whole results by.
             //
             // The returned value is added to the bindable arguments array since this is untrusted
             // user content.
-            let operation_fields_value: (String,) = query_as(&cmp_value_pre)
-                .fetch_one(pool)
-                .await
-                .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
-            bind_args.push(BindArgument::String(operation_fields_value.0));
+            let operation_fields_value =
+                cursor_field_value(pool, operation_cursor, order_field_name).await?;
+            bind_args.push(BindArgument::String(operation_fields_value));
+
+            // Select the document id the cursor is pointing at, it is used as the tie-breaker
+            // between equal values, matching the ordering of the results
+            let cmp_document_id =
+                cursor_meta_value(pool, operation_cursor, &MetaField::DocumentId).await?;
 
             // Necessary casting for operation values of different type
             let cmp_field =
@@ -855,7 +1005,15 @@ async fn where_pagination_sql(
                                 (
                                     {cmp_field} = {bind_arg_marker}
                                     AND
-                                        {cursor_sql}
+                                    (
+                                        documents.document_id > '{cmp_document_id}'
+                                        OR
+                                        (
+                                            documents.document_id = '{cmp_document_id}'
+                                            AND
+                                                {cursor_sql}
+                                        )
+                                    )
                                 )
                             )
                 )
@@ -865,6 +1023,108 @@ async fn where_pagination_sql(
     }
 }
 
+/// Generate SQL for cursor-based pagination when ordering over multiple fields.
+///
+/// Rows come after the cursor when the first ordered field has a "greater" value (depending on
+/// the direction) than the document the cursor points at. If the values are equal, the next
+/// ordered field is compared and so on. Rows with equal values for all ordered fields are
+/// compared by their document id and cursor, matching the ordering of the results.
+async fn where_pagination_fields_sql(
+    pool: &Pool,
+    bind_args: &mut Vec<BindArgument>,
+    operation_cursor: &OperationCursor,
+    cursor_sql: &str,
+    schema: &Schema,
+    order: &Order,
+) -> Result<String, DocumentStorageError> {
+    let cmp_document_id = cursor_meta_value(pool, operation_cursor, &MetaField::DocumentId).await?;
+
+    // Start with the innermost comparison for rows with equal values in all ordered fields
+    let mut cmp_sql = format!(
+        r#"
+        documents.document_id > '{cmp_document_id}'
+        OR
+        (
+            documents.document_id = '{cmp_document_id}'
+            AND
+                {cursor_sql}
+        )
+        "#
+    );
+
+    // .. and wrap it with the comparisons of all ordered fields, starting from the last one
+    for (field, direction) in order.fields().into_iter().rev() {
+        let cmp_direction = match direction {
+            Direction::Ascending => ">",
+            Direction::Descending => "<",
+        };
+
+        let (cmp_field, cmp_value) = match field {
+            Field::Meta(meta_field) => {
+                let cmp_value = cursor_meta_value(pool, operation_cursor, meta_field).await?;
+                (order_field_sql(field, schema), format!("'{cmp_value}'"))
+            }
+            Field::Field(field_name) => {
+                // Application field values are untrusted user content and get bound as arguments
+                let cmp_value = cursor_field_value(pool, operation_cursor, field_name).await?;
+                bind_args.push(BindArgument::String(cmp_value));
+
+                let bind_arg_marker =
+                    typecast_field_sql(&format!("${}", bind_args.len()), field_name, schema, false);
+
+                (order_field_sql(field, schema), bind_arg_marker)
+            }
+        };
+
+        cmp_sql = format!(
+            r#"
+            {cmp_field} {cmp_direction} {cmp_value}
+            OR
+            (
+                {cmp_field} = {cmp_value}
+                AND
+                (
+                    {cmp_sql}
+                )
+            )
+            "#
+        );
+    }
+
+    Ok(format!("AND ({cmp_sql})"))
+}
+
+/// Returns SQL selecting the value of a field to order the documents by.
+fn order_field_sql(field: &Field, schema: &Schema) -> String {
+    match field {
+        Field::Meta(MetaField::DocumentId) => "documents.document_id".to_string(),
+        Field::Meta(MetaField::DocumentViewId) => "documents.document_view_id".to_string(),
+        Field::Meta(MetaField::Owner) => "owner".to_string(),
+        Field::Meta(MetaField::Edited) => "is_edited".to_string(),
+        Field::Meta(MetaField::Deleted) => "is_deleted".to_string(),
+        Field::Field(field_name) => {
+            format!(
+                r#"
+                (
+                    SELECT
+                        {}
+                    FROM
+                        operation_fields_v1
+                        LEFT JOIN document_view_fields
+                            ON operation_fields_v1.operation_id = document_view_fields.operation_id
+                    WHERE
+                        operation_fields_v1.name = '{}'
+                        AND document_view_fields.document_view_id = documents.document_view_id
+                    LIMIT 1
+                )
+                "#,
+                typecast_field_sql("operation_fields_v1.value", field_name, schema, false),
+                field_name,
+            )
+        }
+    }
+}
+
 fn order_sql(
     order: &Order,
     schema: &Schema,
@@ -872,44 +1132,29 @@ fn order_sql(
     fields: &ApplicationFields,
 ) -> String {
     // Create custom ordering if query set one
-    let custom = order
-        .field
-        .as_ref()
-        .map(|field| match field {
-            Field::Meta(MetaField::DocumentId) => "documents.document_id".to_string(),
-            Field::Meta(MetaField::DocumentViewId) => "documents.document_view_id".to_string(),
-            Field::Meta(MetaField::Owner) => "owner".to_string(),
-            Field::Meta(MetaField::Edited) => "is_edited".to_string(),
-            Field::Meta(MetaField::Deleted) => "is_deleted".to_string(),
-            Field::Field(field_name) => {
-                format!(
-                    r#"
-                    (
-                        SELECT
-                            {}
-                        FROM
-                            operation_fields_v1
-                            LEFT JOIN document_view_fields
-                                ON operation_fields_v1.operation_id = document_view_fields.operation_id
-                        WHERE
-                            operation_fields_v1.name = '{}'
-                            AND document_view_fields.document_view_id = documents.document_view_id
-                        LIMIT 1
-                    )
-                    "#,
-                    typecast_field_sql("operation_fields_v1.value", field_name, schema, false),
-                    field_name,
-                )
-            }
-        })
-        .map(|field| {
-            let direction = match order.direction {
+    let custom: Vec<Option<String>> = order
+        .fields()
+        .into_iter()
+        .map(|(field, direction)| {
+            let direction = match direction {
                 Direction::Ascending => "ASC",
                 Direction::Descending => "DESC",
             };
 
-            format!("{field} {direction}")
-        });
+            Some(format!("{} {direction}", order_field_sql(field, schema)))
+        })
+        .collect();
+
+    // Break ties between equal values of a custom ordering by document id. Without it the order
+    // of these rows would be up to the database, which differs between SQLite and PostgreSQL and
+    // would lead to unstable pagination. Document and view ids are unique already
+    let document_id_sql = match order.field {
+        Some(Field::Meta(MetaField::DocumentId)) | Some(Field::Meta(MetaField::DocumentViewId)) => {
+            None
+        }
+        Some(_) => Some("documents.document_id ASC".to_string()),
+        None => None,
+    };
 
     // .. and by relation list index, in case we're querying one
     let list_sql = match (&order.field, list) {
@@ -943,10 +1188,11 @@ fn order_sql(
     // equal between two rows
     let cursor_sql = match list {
         Some(_) => Some("operation_fields_v1_list.cursor ASC".to_string()),
-        None => Some("operation_fields_v1.cursor ASC".to_string()),
+        None => Some(format!("{CURSOR_SQL} ASC")),
     };
 
-    let order = concatenate_sql(&[custom, list_sql, id_sql, cursor_sql]);
+    let order =
+        concatenate_sql(&[custom, vec![document_id_sql, list_sql, id_sql, cursor_sql]].concat());
 
     format!("ORDER BY {order}")
 }
@@ -969,12 +1215,27 @@ fn where_fields_sql(fields: &ApplicationFields) -> String {
     } else {
         let fields_sql: Vec<String> = fields.iter().map(|field| format!("'{field}'")).collect();
         format!(
-            "AND operation_fields_v1.name IN ({})",
+            "AND (documents.is_deleted = true OR operation_fields_v1.name IN ({}))",
             fields_sql.join(", ")
         )
     }
 }
 
+/// Deleted documents don't have any field values, they are left out when ordering by application
+/// fields.
+fn where_order_sql(order: &Order) -> &'static str {
+    let orders_by_field = order
+        .fields()
+        .iter()
+        .any(|(field, _)| matches!(field, Field::Field(_)));
+
+    if orders_by_field {
+        "AND documents.is_deleted = false"
+    } else {
+        ""
+    }
+}
+
 fn select_edited_sql(select: &Select) -> Option<String> {
     if select.fields.contains(&Field::Meta(MetaField::Edited)) {
         let sql = r#"
@@ -1026,9 +1287,13 @@ fn select_fields_sql(fields: &ApplicationFields) -> Vec<Option<String>> {
 
     if !fields.is_empty() {
         // We get the application data by selecting the name, value and type
-        select.push(Some("operation_fields_v1.name".to_string()));
+        select.push(Some(
+            "COALESCE(operation_fields_v1.name, '') AS name".to_string(),
+        ));
         select.push(Some("operation_fields_v1.value".to_string()));
-        select.push(Some("operation_fields_v1.field_type".to_string()));
+        select.push(Some(
+            "COALESCE(operation_fields_v1.field_type, '') AS field_type".to_string(),
+        ));
     }
 
     select
@@ -1038,13 +1303,11 @@ fn select_cursor_sql(list: Option<&RelationList>) -> Vec<Option<String>> {
     match list {
         Some(_) => {
             vec![
-                Some("operation_fields_v1.cursor AS cmp_value_cursor".to_string()),
+                Some(format!("{CURSOR_SQL} AS cmp_value_cursor")),
                 Some("operation_fields_v1_list.cursor AS root_cursor".to_string()),
             ]
         }
-        None => vec![Some(
-            "operation_fields_v1.cursor AS cmp_value_cursor".to_string(),
-        )],
+        None => vec![Some(format!("{CURSOR_SQL} AS cmp_value_cursor"))],
     }
 }
 
@@ -1052,7 +1315,7 @@ fn where_sql(schema: &Schema, fields: &ApplicationFields, list: Option<&Relation
     let schema_id = schema.id();
 
     // Only one row per field: restrict relation lists to first list item
-    let list_index_sql = "AND operation_fields_v1.list_index = 0";
+    let list_index_sql = "operation_fields_v1.list_index = 0";
 
     // Always select at least one field, even if user didn't select one. If we wouldn't select a
     // field we would potentially receive multiple rows per document even though when we're only
@@ -1064,14 +1327,17 @@ fn where_sql(schema: &Schema, fields: &ApplicationFields, list: Option<&Relation
         "".to_string()
     };
 
+    // Deleted documents don't have any fields, they are represented by exactly one row
+    let fields_sql =
+        format!("AND (documents.is_deleted = true OR ({list_index_sql} {extra_field_select}))");
+
     match list {
         None => {
             // Filter by the queried schema of that collection
             format!(
                 r#"
                 documents.schema_id = '{schema_id}'
-                {list_index_sql}
-                {extra_field_select}
+                {fields_sql}
                 "#
             )
         }
@@ -1091,8 +1357,7 @@ fn where_sql(schema: &Schema, fields: &ApplicationFields, list: Option<&Relation
                     operation_fields_v1_list.field_type = '{field_type}'
                 AND
                     operation_fields_v1_list.name = '{field_name}'
-                {list_index_sql}
-                {extra_field_select}
+                {fields_sql}
                 "#
             )
         }
@@ -1121,7 +1386,7 @@ fn from_sql(list: Option<&RelationList>) -> String {
                 JOIN documents
                     ON
                         operation_fields_v1_list.value = {filter_sql}
-                JOIN document_view_fields
+                LEFT JOIN document_view_fields
                     ON documents.document_view_id = document_view_fields.document_view_id
                 "#
             )
@@ -1129,7 +1394,7 @@ fn from_sql(list: Option<&RelationList>) -> String {
         // Otherwise just query the documents directly
         None => r#"
             documents
-            JOIN document_view_fields
+            LEFT JOIN document_view_fields
                 ON documents.document_view_id = document_view_fields.document_view_id
         "#
         .to_string(),
@@ -1148,6 +1413,10 @@ impl SqlStore {
         args: &Query<PaginationCursor>,
         list: Option<&RelationList>,
     ) -> Result<QueryResponse, DocumentStorageError> {
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
         // Get all selected application fields from query
         let application_fields = args.select.application_fields();
 
@@ -1157,7 +1426,7 @@ impl SqlStore {
             // view id and id of the operation which holds the data
             Some("documents.document_id".to_string()),
             Some("documents.document_view_id".to_string()),
-            Some("document_view_fields.operation_id".to_string()),
+            Some("COALESCE(document_view_fields.operation_id, '') AS operation_id".to_string()),
             // The deletion status of a document we already store in the database, let's select it
             // in any case since we get it for free
             Some("documents.is_deleted".to_string()),
@@ -1178,6 +1447,7 @@ impl SqlStore {
 
         let where_ = where_sql(schema, &application_fields, list);
         let and_fields = where_fields_sql(&application_fields);
+        let and_order = where_order_sql(&args.order);
         let (and_filters, mut bind_args) = where_filter_sql(&args.filter, schema);
         let and_pagination = where_pagination_sql(
             &self.pool,
@@ -1222,8 +1492,9 @@ impl SqlStore {
                 -- list this is slighly more complicated and we need to do some additional JOINs
                 {from}
 
-                -- We need to add some more JOINs to get the values from the operations
-                JOIN operation_fields_v1
+                -- We need to add some more JOINs to get the values from the operations, deleted
+                -- documents don't have any
+                LEFT JOIN operation_fields_v1
                     ON
                         document_view_fields.operation_id = operation_fields_v1.operation_id
                         AND
@@ -1239,6 +1510,7 @@ impl SqlStore {
 
                 -- .. and further filter the data by custom parameters
                 {and_filters}
+                {and_order}
 
                 -- Lastly we batch all results into smaller chunks via cursor pagination
                 {and_pagination}
@@ -1264,7 +1536,7 @@ impl SqlStore {
 
         // We always query one more row than needed to find out if there's more data. This
         // information aids the user during pagination
-        let has_next_page = if rows.len() as u64 > page_size {
+        let mut has_next_page = if rows.len() as u64 > page_size {
             // Remove that last row from final results if it exists
             rows.pop();
             true
@@ -1284,7 +1556,15 @@ impl SqlStore {
         };
 
         // Finally convert everything into the right format
-        let documents = convert_rows(rows, list, &application_fields, schema.id());
+        let mut documents = convert_rows(rows, list, &application_fields, schema.id());
+
+        // Deleted documents take only one row, we might have received more documents than
+        // requested
+        let first = args.pagination.first.get() as usize;
+        if documents.len() > first {
+            documents.truncate(first);
+            has_next_page = true;
+        }
 
         // Determine cursors for pagination by looking at beginning and end of results
         let start_cursor = if args
@@ -1323,10 +1603,15 @@ impl SqlStore {
         args: &Query<PaginationCursor>,
         list: Option<&RelationList>,
     ) -> Result<u64, DocumentStorageError> {
-        let application_fields = args.select.application_fields();
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
 
         let from = from_sql(list);
-        let where_ = where_sql(schema, &application_fields, list);
+
+        // Select only one row per document by not passing any application fields
+        let where_ = where_sql(schema, &ApplicationFields::new(), list);
+        let and_order = where_order_sql(&args.order);
         let (and_filters, bind_args) = where_filter_sql(&args.filter, schema);
 
         let count_sql = format!(
@@ -1337,7 +1622,7 @@ impl SqlStore {
             FROM
                 {from}
 
-                JOIN operation_fields_v1
+                LEFT JOIN operation_fields_v1
                     ON
                         document_view_fields.operation_id = operation_fields_v1.operation_id
                         AND
@@ -1346,9 +1631,7 @@ impl SqlStore {
             WHERE
                 {where_}
                 {and_filters}
-
-            -- Group application fields by name to make sure we get actual number of documents
-            GROUP BY operation_fields_v1.name
+                {and_order}
             "#
         );
 
@@ -1369,6 +1652,103 @@ impl SqlStore {
 
         Ok(count)
     }
+
+    /// Query number of documents and aggregated values of all integer and float fields in a
+    /// filtered collection.
+    ///
+    /// All values are computed by the database, documents are not loaded into memory.
+    pub async fn aggregate(
+        &self,
+        schema: &Schema,
+        filter: &Filter,
+    ) -> Result<Aggregates, DocumentStorageError> {
+        let args = Query::new(
+            &Pagination::default(),
+            &Select::default(),
+            filter,
+            &Order::default(),
+        );
+        let count = self.count(schema, &args, None).await?;
+
+        // Only numeric fields can be aggregated
+        let numeric_fields: ApplicationFields = schema
+            .fields()
+            .iter()
+            .filter(|(_, field_type)| matches!(field_type, FieldType::Integer | FieldType::Float))
+            .map(|(field_name, _)| field_name.to_owned())
+            .collect();
+
+        let mut fields: HashMap<FieldName, FieldAggregate> = numeric_fields
+            .iter()
+            .map(|field_name| (field_name.to_owned(), FieldAggregate::default()))
+            .collect();
+
+        if numeric_fields.is_empty() {
+            return Ok(Aggregates { count, fields });
+        }
+
+        self.inject_sql_fault()
+            .await
+            .map_err(|e| DocumentStorageError::FatalStorageError(e.to_string()))?;
+
+        let from = from_sql(None);
+        let where_ = where_sql(schema, &numeric_fields, None);
+        let (and_filters, bind_args) = where_filter_sql(filter, schema);
+        let field_names = numeric_fields
+            .iter()
+            .map(|field_name| format!("'{field_name}'"))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        // Cast all values to floating point numbers to receive the same types from SQLite and
+        // PostgreSQL, independent of the field being an integer or float
+        let value_sql = "CAST(operation_fields_v1.value AS DOUBLE PRECISION)";
+
+        let aggregate_sql = format!(
+            r#"
+            SELECT
+                operation_fields_v1.name,
+                MIN({value_sql}),
+                MAX({value_sql}),
+                AVG({value_sql}),
+                SUM({value_sql})
+
+            FROM
+                {from}
+
+                JOIN operation_fields_v1
+                    ON
+                        document_view_fields.operation_id = operation_fields_v1.operation_id
+                        AND
+                            document_view_fields.name = operation_fields_v1.name
+
+            WHERE
+                {where_}
+                AND operation_fields_v1.name IN ({field_names})
+                {and_filters}
+
+            GROUP BY operation_fields_v1.name
+            "#
+        );
+
+        let mut query = query_as::<_, (String, Option<f64>, Option<f64>, Option<f64>, Option<f64>)>(
+            &aggregate_sql,
+        );
+
+        // Bind untrusted user arguments to query
+        query = bind_to_query(query, &bind_args);
+
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| DocumentStorageError::FatalStorageError(err.to_string()))?;
+
+        for (field_name, min, max, avg, sum) in rows {
+            fields.insert(field_name, FieldAggregate { min, max, avg, sum });
+        }
+
+        Ok(Aggregates { count, fields })
+    }
 }
 
 /// Merges all operation fields from the database into documents.
@@ -1446,16 +1826,39 @@ fn convert_rows(
     let mut current_fields = Vec::with_capacity(rows_per_document);
     let mut current_rows = HashMap::new();
 
-    for (index, row) in rows.into_iter().enumerate() {
+    for row in rows.into_iter() {
+        // Deleted documents don't have any fields and are represented by exactly one row
+        if row.is_deleted {
+            if !current_fields.is_empty() {
+                let (cursor, document) = finalize_document(&current, current_fields, &current_rows);
+                converted.push((cursor, document));
+                current_fields = Vec::with_capacity(rows_per_document);
+            }
+
+            let document = StorageDocument {
+                id: row.document_id.parse().unwrap(),
+                fields: None,
+                schema_id: schema_id.clone(),
+                view_id: row.document_view_id.parse().unwrap(),
+                author: (&row.owner).into(),
+                deleted: true,
+            };
+            converted.push((row_to_cursor(&row, list), document));
+
+            continue;
+        }
+
         // We observed a new document coming up in the next row, time to change
-        if index % rows_per_document == 0 && index > 0 {
+        if current_fields.len() == rows_per_document {
             // Finalize the current document, convert it and push it into the final array
             let (cursor, document) = finalize_document(&current, current_fields, &current_rows);
             converted.push((cursor, document));
+            current_fields = Vec::with_capacity(rows_per_document);
+        }
 
-            // Change the pointer to the next document
+        // Change the pointer to the next document
+        if current_fields.is_empty() {
             current = row.clone();
-            current_fields = Vec::with_capacity(rows_per_document);
         }
 
         // Collect original rows from SQL query
@@ -1474,8 +1877,10 @@ fn convert_rows(
     }
 
     // Do it one last time at the end for the last document
-    let (cursor, document) = finalize_document(&current, current_fields, &current_rows);
-    converted.push((cursor, document));
+    if !current_fields.is_empty() {
+        let (cursor, document) = finalize_document(&current, current_fields, &current_rows);
+        converted.push((cursor, document));
+    }
 
     converted
 }
@@ -1502,7 +1907,7 @@ mod tests {
     use std::num::NonZeroU64;
 
     use p2panda_rs::document::traits::AsDocument;
-    use p2panda_rs::document::DocumentViewId;
+    use p2panda_rs::document::{DocumentId, DocumentViewId};
     use p2panda_rs::hash::Hash;
     use p2panda_rs::identity::KeyPair;
     use p2panda_rs::operation::{OperationValue, PinnedRelationList};
@@ -1518,9 +1923,9 @@ mod tests {
     use crate::db::stores::{OperationCursor, RelationList};
     use crate::db::types::StorageDocument;
     use crate::test_utils::{
-        add_document, add_schema, add_schema_and_documents, doggo_fields, doggo_schema,
-        populate_and_materialize, populate_store_config, test_runner, PopulateStoreConfig,
-        TestNode,
+        add_document, add_schema, add_schema_and_documents, delete_document, doggo_fields,
+        doggo_schema, populate_and_materialize, populate_store_config, test_runner,
+        PopulateStoreConfig, TestNode,
     };
 
     use super::{convert_rows, PaginationCursor, Query};
@@ -1752,6 +2157,40 @@ mod tests {
             "Kids Bits! Chiptune for baby squirrels".into(),
         ],
     )]
+    #[case::search_all_string_fields(
+        Query::new(
+            &Pagination::default(),
+            &Select::new(&["title".into()]),
+            &{
+                let mut filter = Filter::new();
+                filter.add_search("BA");
+                filter
+            },
+            &Order::new(&"date".into(), &Direction::Ascending),
+        ),
+        "title".into(),
+        vec![
+            "Kids Bits! Chiptune for baby squirrels".into(),
+            "Bamboo-Scrumble Rumba Night - Xmas special".into(),
+        ],
+    )]
+    #[case::search_date_string(
+        Query::new(
+            &Pagination::default(),
+            &Select::new(&["title".into()]),
+            &{
+                let mut filter = Filter::new();
+                filter.add_search("2023-04");
+                filter
+            },
+            &Order::new(&"date".into(), &Direction::Ascending),
+        ),
+        "title".into(),
+        vec![
+            "The Pandadoodle Flute Trio".into(),
+            "Kids Bits! Chiptune for baby squirrels".into(),
+        ],
+    )]
     fn basic_queries(
         key_pair: KeyPair,
         #[case] args: Query<PaginationCursor>,
@@ -1780,6 +2219,63 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn filter_documents_with_missing_fields(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, view_ids) = create_events_test_data(&mut node, &key_pair).await;
+
+            // Remove the "ticket_price" field from one event, for example because it was never set
+            sqlx::query(
+                "
+                DELETE FROM
+                    document_view_fields
+                WHERE
+                    document_view_id = $1
+                    AND name = 'ticket_price'
+                ",
+            )
+            .bind(view_ids[0].to_string())
+            .execute(&node.context.store.pool)
+            .await
+            .unwrap();
+
+            let query_titles = |filter: Filter| {
+                let store = node.context.store.clone();
+                let schema = schema.clone();
+                async move {
+                    let args = Query::new(
+                        &Pagination::default(),
+                        &Select::new(&["title".into()]),
+                        &filter,
+                        &Order::default(),
+                    );
+
+                    let (_, documents) = store
+                        .query(&schema, &args, None)
+                        .await
+                        .expect("Query failed");
+
+                    documents.len()
+                }
+            };
+
+            let mut filter = Filter::new();
+            filter.add_is_null(&"ticket_price".into(), true);
+            assert_eq!(query_titles(filter).await, 1);
+
+            let mut filter = Filter::new();
+            filter.add_is_null(&"ticket_price".into(), false);
+            assert_eq!(query_titles(filter).await, 4);
+
+            // Negated filters also match documents without a value for this field
+            let filter = Filter::new().fields(&[("ticket_price_not", &[12.5.into()])]);
+            assert_eq!(query_titles(filter).await, 4);
+
+            let filter = Filter::new().fields(&[("ticket_price_gt", &[10.0.into()])]);
+            assert_eq!(query_titles(filter).await, 3);
+        });
+    }
+
     #[rstest]
     #[case::order_by_timestamp(
         Order::new(&"timestamp".into(), &Direction::Ascending),
@@ -1895,6 +2391,185 @@ mod tests {
         });
     }
 
+    #[rstest]
+    #[case::ascending_all_fields(Direction::Ascending, vec!["message", "username"])]
+    #[case::descending_all_fields(Direction::Descending, vec!["message", "username"])]
+    #[case::ascending_one_field(Direction::Ascending, vec!["username"])]
+    #[case::descending_one_field(Direction::Descending, vec!["username"])]
+    fn pagination_over_equal_values(
+        key_pair: KeyPair,
+        #[case] direction: Direction,
+        #[case] selected_fields: Vec<&'static str>,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, view_ids) = create_chat_test_data(&mut node, &key_pair).await;
+
+            // Documents with equal usernames are expected to be ordered by their document id
+            let mut panda_ids = Vec::new();
+            let mut penguin_ids = Vec::new();
+            for view_id in &view_ids {
+                let document = node
+                    .context
+                    .store
+                    .get_document_by_view_id(view_id)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                match get_document_value(&document, "username") {
+                    OperationValue::String(username) if username == "panda" => {
+                        panda_ids.push(document.id().to_owned())
+                    }
+                    _ => penguin_ids.push(document.id().to_owned()),
+                }
+            }
+            panda_ids.sort();
+            penguin_ids.sort();
+
+            let expected_ids: Vec<DocumentId> = match direction {
+                Direction::Ascending => [panda_ids, penguin_ids].concat(),
+                Direction::Descending => [penguin_ids, panda_ids].concat(),
+            };
+
+            let selected_fields: Vec<Field> = selected_fields
+                .into_iter()
+                .map(|field| Field::Field(field.into()))
+                .collect();
+
+            let mut args = Query::new(
+                &Pagination::new(
+                    &NonZeroU64::new(1).unwrap(),
+                    None,
+                    &vec![PaginationField::EndCursor],
+                ),
+                &Select::new(&selected_fields),
+                &Filter::default(),
+                &Order::new(&"username".into(), &direction),
+            );
+
+            // Go through all pages, one document at a time
+            let mut document_ids = Vec::new();
+            loop {
+                let (pagination_data, documents) = node
+                    .context
+                    .store
+                    .query(&schema, &args, None)
+                    .await
+                    .expect("Query failed");
+
+                match documents.first() {
+                    Some((_, document)) => document_ids.push(document.id().to_owned()),
+                    None => break,
+                }
+
+                args.pagination.after = pagination_data.end_cursor;
+            }
+
+            assert_eq!(document_ids, expected_ids);
+        });
+    }
+
+    #[rstest]
+    #[case::one_field(vec!["message"])]
+    #[case::all_fields(vec!["message", "username", "timestamp"])]
+    fn pagination_over_multiple_ordered_fields(
+        key_pair: KeyPair,
+        #[case] selected_fields: Vec<&'static str>,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, _) = create_chat_test_data(&mut node, &key_pair).await;
+
+            let selected_fields: Vec<Field> = selected_fields
+                .into_iter()
+                .map(|field| Field::Field(field.into()))
+                .collect();
+
+            let mut args = Query::new(
+                &Pagination::new(
+                    &NonZeroU64::new(1).unwrap(),
+                    None,
+                    &vec![PaginationField::EndCursor],
+                ),
+                &Select::new(&selected_fields),
+                &Filter::default(),
+                &Order::new(&"username".into(), &Direction::Ascending)
+                    .then_by(&"timestamp".into(), &Direction::Descending),
+            );
+
+            // Go through all pages, one document at a time
+            let mut messages = Vec::new();
+            loop {
+                let (pagination_data, documents) = node
+                    .context
+                    .store
+                    .query(&schema, &args, None)
+                    .await
+                    .expect("Query failed");
+
+                match documents.first() {
+                    Some((_, document)) => messages.push(get_document_value(document, "message")),
+                    None => break,
+                }
+
+                args.pagination.after = pagination_data.end_cursor;
+            }
+
+            assert_eq!(
+                messages,
+                vec![
+                    "I am cute and very hungry".into(),
+                    "How are you?".into(),
+                    "Oh, howdy, Pengi!".into(),
+                    "(°◇°) !!".into(),
+                    "I miss Pengolina. How about you?".into(),
+                    "Hello, Panda!".into(),
+                ]
+            );
+        });
+    }
+
+    #[rstest]
+    #[case::owner(MetaField::Owner)]
+    #[case::edited(MetaField::Edited)]
+    #[case::deleted(MetaField::Deleted)]
+    fn pagination_over_unsupported_meta_fields(key_pair: KeyPair, #[case] meta_field: MetaField) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, _) = create_chat_test_data(&mut node, &key_pair).await;
+
+            // Get a cursor from the first page
+            let mut args = Query::new(
+                &Pagination::new(
+                    &NonZeroU64::new(1).unwrap(),
+                    None,
+                    &vec![PaginationField::EndCursor],
+                ),
+                &Select::new(&[Field::Meta(meta_field.clone())]),
+                &Filter::default(),
+                &Order::default(),
+            );
+
+            let (pagination_data, _) = node
+                .context
+                .store
+                .query(&schema, &args, None)
+                .await
+                .expect("Query failed");
+            args.pagination.after = pagination_data.end_cursor;
+            assert!(args.pagination.after.is_some());
+
+            // Paginating with a cursor while ordering by this meta field returns an error
+            for order in [
+                Order::new(&Field::Meta(meta_field.clone()), &Direction::Ascending),
+                Order::new(&"username".into(), &Direction::Ascending)
+                    .then_by(&Field::Meta(meta_field.clone()), &Direction::Ascending),
+            ] {
+                args.order = order;
+
+                let result = node.context.store.query(&schema, &args, None).await;
+                assert!(result.is_err());
+            }
+        });
+    }
+
     #[rstest]
     fn pagination_over_ordered_view_ids(key_pair: KeyPair) {
         test_runner(|mut node: TestNode| async move {
@@ -1972,6 +2647,71 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn pagination_over_deleted_documents(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, view_ids) = create_events_test_data(&mut node, &key_pair).await;
+
+            // Delete two of the five documents
+            for view_id in &view_ids[1..3] {
+                delete_document(&mut node, schema.id(), view_id, &key_pair).await;
+            }
+
+            let mut cursor: Option<PaginationCursor> = None;
+            let mut documents = Vec::new();
+
+            // Go through all pages without filtering out deleted documents
+            loop {
+                let args = Query::new(
+                    &Pagination::new(
+                        &NonZeroU64::new(2).unwrap(),
+                        cursor.as_ref(),
+                        &vec![
+                            PaginationField::TotalCount,
+                            PaginationField::EndCursor,
+                            PaginationField::HasNextPage,
+                        ],
+                    ),
+                    &Select::new(&["title".into(), "date".into()]),
+                    &Filter::new(),
+                    &Order::default(),
+                );
+
+                let (pagination_data, page) = node
+                    .context
+                    .store
+                    .query(&schema, &args, None)
+                    .await
+                    .expect("Query failed");
+
+                assert_eq!(pagination_data.total_count, Some(5));
+                assert!(page.len() <= 2);
+
+                documents.extend(page.into_iter().map(|(_, document)| document));
+                cursor = pagination_data.end_cursor;
+
+                if !pagination_data.has_next_page {
+                    break;
+                }
+            }
+
+            assert_eq!(documents.len(), 5);
+
+            // Deleted documents don't have any fields
+            let deleted: Vec<&StorageDocument> = documents
+                .iter()
+                .filter(|document| document.deleted)
+                .collect();
+            assert_eq!(deleted.len(), 2);
+            assert!(deleted.iter().all(|document| document.fields.is_none()));
+
+            for document in documents.iter().filter(|document| !document.deleted) {
+                assert!(view_ids.contains(&document.view_id));
+                assert_eq!(document.fields().unwrap().len(), 2);
+            }
+        });
+    }
+
     #[rstest]
     fn pinned_relation_list(key_pair: KeyPair) {
         test_runner(|mut node: TestNode| async move {
@@ -2407,6 +3147,11 @@ mod tests {
     #[case::default(Filter::default(), 3)]
     #[case::filtered(Filter::new().fields(&[("name_contains", &["Internet".into()])]), 1)]
     #[case::no_results(Filter::new().fields(&[("name", &["doesnotexist".into()])]), 0)]
+    #[case::search({
+        let mut filter = Filter::new();
+        filter.add_search("p4p");
+        filter
+    }, 1)]
     fn count(#[case] filter: Filter, #[case] expected_result: u64, key_pair: KeyPair) {
         test_runner(move |mut node: TestNode| async move {
             let (venues_schema, _) = create_venues_test_data(&mut node, &key_pair).await;
@@ -2429,6 +3174,61 @@ mod tests {
         });
     }
 
+    #[rstest]
+    #[case::all(Filter::default(), 5, (5.75, 99.0, 30.448, 152.24))]
+    #[case::filtered(
+        Filter::new().fields(&[("ticket_price_gte", &[12.5.into()])]),
+        3,
+        (12.5, 99.0, 45.4966, 136.49),
+    )]
+    fn aggregate(
+        #[case] filter: Filter,
+        #[case] expected_count: u64,
+        #[case] expected_values: (f64, f64, f64, f64),
+        key_pair: KeyPair,
+    ) {
+        test_runner(move |mut node: TestNode| async move {
+            let (events_schema, _) = create_events_test_data(&mut node, &key_pair).await;
+
+            let aggregates = node
+                .context
+                .store
+                .aggregate(&events_schema, &filter)
+                .await
+                .unwrap();
+
+            assert_eq!(aggregates.count, expected_count);
+
+            // Only numeric fields get aggregated
+            assert_eq!(aggregates.fields.len(), 1);
+            let ticket_price = &aggregates.fields["ticket_price"];
+
+            let (min, max, avg, sum) = expected_values;
+            assert_eq!(ticket_price.min, Some(min));
+            assert_eq!(ticket_price.max, Some(max));
+            assert!((ticket_price.avg.unwrap() - avg).abs() < 0.0001);
+            assert!((ticket_price.sum.unwrap() - sum).abs() < 0.0001);
+        });
+    }
+
+    #[rstest]
+    fn aggregate_empty_collection(key_pair: KeyPair) {
+        test_runner(move |mut node: TestNode| async move {
+            let (events_schema, _) = create_events_test_data(&mut node, &key_pair).await;
+            let filter = Filter::new().fields(&[("ticket_price_gt", &[100.0.into()])]);
+
+            let aggregates = node
+                .context
+                .store
+                .aggregate(&events_schema, &filter)
+                .await
+                .unwrap();
+
+            assert_eq!(aggregates.count, 0);
+            assert_eq!(aggregates.fields["ticket_price"], Default::default());
+        });
+    }
+
     #[rstest]
     #[case::default(Filter::default(), 7)]
     #[case::filtered_1(Filter::new().fields(&[("name_contains", &["Internet".into()])]), 2)]
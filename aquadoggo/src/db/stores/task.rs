@@ -2,7 +2,7 @@
 
 use anyhow::Result;
 use p2panda_rs::document::{DocumentId, DocumentViewId};
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, Any, Executor};
 
 use crate::db::errors::SqlStoreError;
 use crate::db::models::TaskRow;
@@ -13,57 +13,13 @@ use crate::materializer::{Task, TaskInput};
 impl SqlStore {
     /// Inserts a "pending" task into the database.
     pub async fn insert_task(&self, task: &Task<TaskInput>) -> Result<(), SqlStoreError> {
-        // Convert task input to correct database types
-        let task_input = task.input();
-
-        let document_id = match task_input {
-            TaskInput::DocumentId(id) => Some(id.to_string()),
-            TaskInput::DocumentViewId(_) => None,
-        };
-
-        let document_view_id = match task_input {
-            TaskInput::DocumentViewId(view_id) => Some(view_id.to_string()),
-            TaskInput::DocumentId(_) => None,
-        };
-
-        // Insert task into database
-        query(
-            "
-            INSERT INTO
-                tasks (
-                    name,
-                    document_id,
-                    document_view_id
-                )
-            VALUES
-                ($1, $2, $3)
-            ON CONFLICT DO NOTHING
-            ",
-        )
-        .bind(task.worker_name())
-        .bind(document_id)
-        .bind(document_view_id)
-        .execute(&self.pool)
-        .await
-        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
-
-        Ok(())
+        insert_task_row(&self.pool, task).await
     }
 
     /// Removes a "pending" task from the database.
     pub async fn remove_task(&self, task: &Task<TaskInput>) -> Result<(), SqlStoreError> {
         // Convert task input to correct database types
-        let task_input = task.input();
-
-        let document_id = match task_input {
-            TaskInput::DocumentId(id) => Some(id.to_string()),
-            TaskInput::DocumentViewId(_) => None,
-        };
-
-        let document_view_id = match task_input {
-            TaskInput::DocumentViewId(view_id) => Some(view_id.to_string()),
-            TaskInput::DocumentId(_) => None,
-        };
+        let (document_id, document_view_id) = task_input_columns(task.input());
 
         // Remove task from database
         query(
@@ -105,32 +61,102 @@ impl SqlStore {
         .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
 
         // Convert database rows into correct p2panda types
-        let mut tasks: Vec<Task<TaskInput>> = Vec::new();
-        for task in task_rows {
-            let document_id: Option<DocumentId> = task.document_id.map(|id| {
-                id.parse()
-                    .unwrap_or_else(|_| panic!("Invalid document id stored in database {}", id))
-            });
-
-            let document_view_id: Option<DocumentViewId> = task.document_view_id.map(|view_id| {
-                view_id.parse().unwrap_or_else(|_| {
-                    panic!("Invalid document view id stored in database: {}", view_id)
-                })
-            });
-
-            let input = match (document_id, document_view_id) {
-                (None, Some(view_id)) => TaskInput::DocumentViewId(view_id),
-                (Some(id), None) => TaskInput::DocumentId(id),
-                _ => {
-                    panic!("Invalid task input stored in database")
-                }
-            };
-
-            tasks.push(Task::new(&task.name, input));
-        }
+        let tasks = task_rows
+            .into_iter()
+            .map(|task| task_from_columns(&task.name, task.document_id, task.document_view_id))
+            .collect();
 
         Ok(tasks)
     }
+
+    /// Returns the number of "pending" tasks of the materialization service worker.
+    pub async fn count_tasks(&self) -> Result<u64, SqlStoreError> {
+        let (count,) = query_as::<_, (i64,)>(
+            "
+            SELECT
+                COUNT(*)
+            FROM
+                tasks
+            ",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(count as u64)
+    }
+}
+
+/// Converts the input of a task into the `document_id` and `document_view_id` columns used in the
+/// database.
+pub(super) fn task_input_columns(task_input: &TaskInput) -> (Option<String>, Option<String>) {
+    match task_input {
+        TaskInput::DocumentId(id) => (Some(id.to_string()), None),
+        TaskInput::DocumentViewId(view_id) => (None, Some(view_id.to_string())),
+    }
+}
+
+/// Inserts a "pending" task, using either a connection from the pool or an open transaction.
+pub(crate) async fn insert_task_row<'c, E>(
+    executor: E,
+    task: &Task<TaskInput>,
+) -> Result<(), SqlStoreError>
+where
+    E: Executor<'c, Database = Any>,
+{
+    // Convert task input to correct database types
+    let (document_id, document_view_id) = task_input_columns(task.input());
+
+    // Insert task into database
+    query(
+        "
+        INSERT INTO
+            tasks (
+                name,
+                document_id,
+                document_view_id
+            )
+        VALUES
+            ($1, $2, $3)
+        ON CONFLICT DO NOTHING
+        ",
+    )
+    .bind(task.worker_name())
+    .bind(document_id)
+    .bind(document_view_id)
+    .execute(executor)
+    .await
+    .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Converts the columns of a task stored in the database into a task.
+pub(super) fn task_from_columns(
+    name: &str,
+    document_id: Option<String>,
+    document_view_id: Option<String>,
+) -> Task<TaskInput> {
+    let document_id: Option<DocumentId> = document_id.map(|id| {
+        id.parse()
+            .unwrap_or_else(|_| panic!("Invalid document id stored in database {}", id))
+    });
+
+    let document_view_id: Option<DocumentViewId> = document_view_id.map(|view_id| {
+        view_id
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid document view id stored in database: {}", view_id))
+    });
+
+    let input = match (document_id, document_view_id) {
+        (None, Some(view_id)) => TaskInput::DocumentViewId(view_id),
+        (Some(id), None) => TaskInput::DocumentId(id),
+        _ => {
+            panic!("Invalid task input stored in database")
+        }
+    };
+
+    Task::new(name, input)
 }
 
 #[cfg(test)]
@@ -155,6 +181,7 @@ mod tests {
             // Check if task exists in database
             let result = node.context.store.get_tasks().await;
             assert_eq!(result.unwrap(), vec![task.clone()]);
+            assert_eq!(node.context.store.count_tasks().await.unwrap(), 1);
 
             // Remove task
             let result = node.context.store.remove_task(&task).await;
@@ -163,6 +190,7 @@ mod tests {
             // Check if all tasks got removed
             let result = node.context.store.get_tasks().await;
             assert_eq!(result.unwrap(), vec![]);
+            assert_eq!(node.context.store.count_tasks().await.unwrap(), 0);
         });
     }
 
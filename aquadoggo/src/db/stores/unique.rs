@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::document::DocumentId;
+use p2panda_rs::schema::SchemaId;
+use sqlx::{query, query_as};
+
+use crate::db::errors::SqlStoreError;
+use crate::db::types::UniqueConflict;
+use crate::db::SqlStore;
+
+/// Methods to interact with the `unique_index` table in the database.
+impl SqlStore {
+    /// Sets the value of a document for the given unique constraint.
+    ///
+    /// Passing `None` removes the document from the index, for example after it got deleted.
+    pub async fn update_unique_index(
+        &self,
+        schema_id: &SchemaId,
+        constraint_name: &str,
+        document_id: &DocumentId,
+        value: Option<&str>,
+    ) -> Result<(), SqlStoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        query(
+            "
+            DELETE FROM
+                unique_index
+            WHERE
+                schema_id = $1
+                AND constraint_name = $2
+                AND document_id = $3
+            ",
+        )
+        .bind(schema_id.to_string())
+        .bind(constraint_name)
+        .bind(document_id.as_str())
+        .execute(&mut tx)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        if let Some(value) = value {
+            query(
+                "
+                INSERT INTO
+                    unique_index (
+                        schema_id,
+                        constraint_name,
+                        value,
+                        document_id
+                    )
+                VALUES
+                    ($1, $2, $3, $4)
+                ",
+            )
+            .bind(schema_id.to_string())
+            .bind(constraint_name)
+            .bind(value)
+            .bind(document_id.as_str())
+            .execute(&mut tx)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Removes all entries from the unique index, for example before filling it again.
+    pub async fn clear_unique_index(&self) -> Result<(), SqlStoreError> {
+        query("DELETE FROM unique_index")
+            .execute(&self.pool)
+            .await
+            .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get all documents of a schema which violate any of its unique constraints.
+    pub async fn get_unique_conflicts(
+        &self,
+        schema_id: &SchemaId,
+    ) -> Result<Vec<UniqueConflict>, SqlStoreError> {
+        let rows: Vec<(String, String, String)> = query_as(
+            "
+            SELECT
+                unique_index.constraint_name,
+                unique_index.value,
+                unique_index.document_id
+            FROM
+                unique_index
+            JOIN (
+                SELECT
+                    constraint_name,
+                    value
+                FROM
+                    unique_index
+                WHERE
+                    schema_id = $1
+                GROUP BY
+                    constraint_name,
+                    value
+                HAVING
+                    COUNT(*) > 1
+            ) AS conflicts
+            ON
+                unique_index.constraint_name = conflicts.constraint_name
+                AND unique_index.value = conflicts.value
+            WHERE
+                unique_index.schema_id = $1
+            ORDER BY
+                unique_index.constraint_name,
+                unique_index.value
+            ",
+        )
+        .bind(schema_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| SqlStoreError::Transaction(err.to_string()))?;
+
+        // Group rows by constraint and value, every group represents one conflict
+        let mut conflicts: Vec<(String, UniqueConflict)> = Vec::new();
+        for (constraint_name, value, document_id) in rows {
+            let document_id: DocumentId = document_id
+                .parse()
+                .expect("Document ids coming from the store are valid");
+
+            match conflicts.last_mut() {
+                Some((last_value, conflict))
+                    if last_value == &value && conflict.constraint_name == constraint_name =>
+                {
+                    conflict.document_ids.push(document_id)
+                }
+                _ => conflicts.push((
+                    value,
+                    UniqueConflict {
+                        constraint_name,
+                        document_ids: vec![document_id],
+                    },
+                )),
+            }
+        }
+
+        Ok(conflicts
+            .into_iter()
+            .map(|(_, mut conflict)| {
+                // Sort here instead of in the database to not depend on its collation
+                conflict
+                    .document_ids
+                    .sort_by(|a, b| a.as_str().cmp(b.as_str()));
+                conflict
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+
+    use crate::test_utils::{add_document, add_schema, test_runner, TestNode};
+
+    #[rstest]
+    fn find_unique_conflicts(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "usernames",
+                vec![("username", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let mut document_ids = Vec::new();
+            for _ in 0..3 {
+                let view_id = add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("username", "panda".into())],
+                    &key_pair,
+                )
+                .await;
+                let document_id: DocumentId = view_id.to_string().parse().unwrap();
+                document_ids.push(document_id);
+            }
+
+            let store = &node.context.store;
+            store
+                .update_unique_index(schema.id(), "username", &document_ids[0], Some("panda"))
+                .await
+                .unwrap();
+            store
+                .update_unique_index(schema.id(), "username", &document_ids[1], Some("bamboo"))
+                .await
+                .unwrap();
+            assert_eq!(
+                store.get_unique_conflicts(schema.id()).await.unwrap(),
+                vec![]
+            );
+
+            store
+                .update_unique_index(schema.id(), "username", &document_ids[2], Some("panda"))
+                .await
+                .unwrap();
+
+            let conflicts = store.get_unique_conflicts(schema.id()).await.unwrap();
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!(conflicts[0].constraint_name, "username");
+
+            let mut expected = vec![document_ids[0].clone(), document_ids[2].clone()];
+            expected.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            assert_eq!(conflicts[0].document_ids, expected);
+            assert_eq!(conflicts[0].winner(), &expected[0]);
+
+            // Removing a document from the index resolves the conflict
+            store
+                .update_unique_index(schema.id(), "username", &document_ids[2], None)
+                .await
+                .unwrap();
+            assert_eq!(
+                store.get_unique_conflicts(schema.id()).await.unwrap(),
+                vec![]
+            );
+        });
+    }
+}
@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// Result of a read-only SQL query sent by an operator through the admin console.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminQueryResult {
+    /// Names of the returned columns.
+    ///
+    /// This is empty when the query did not return any rows.
+    pub columns: Vec<String>,
+
+    /// Returned rows with their values converted into text, `None` for `NULL` values.
+    pub rows: Vec<Vec<Option<String>>>,
+
+    /// `true` if the query returned more rows than the given row limit.
+    pub truncated: bool,
+}
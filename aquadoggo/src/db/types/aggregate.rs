@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use p2panda_rs::schema::FieldName;
+
+/// Aggregated values of one numeric field over all documents of a collection.
+///
+/// Values are `None` when no document in the collection holds a value for this field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldAggregate {
+    /// Smallest value of this field.
+    pub min: Option<f64>,
+
+    /// Largest value of this field.
+    pub max: Option<f64>,
+
+    /// Arithmetic mean of all values of this field.
+    pub avg: Option<f64>,
+
+    /// Sum of all values of this field.
+    pub sum: Option<f64>,
+}
+
+/// Counts and numeric aggregates over all documents of a filtered collection.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Aggregates {
+    /// Number of documents in the collection.
+    pub count: u64,
+
+    /// Aggregated values of every integer and float field of the schema.
+    pub fields: HashMap<FieldName, FieldAggregate>,
+}
+
+/// Values of one group of a materialized aggregate.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterializedAggregateGroup {
+    /// Value of the grouping field shared by all documents of this group, empty when the
+    /// aggregate is not grouped.
+    pub group: String,
+
+    /// Number of documents in this group.
+    pub count: u64,
+
+    /// Sums of the configured fields over the documents of this group holding a value for them.
+    pub sums: Vec<(FieldName, f64)>,
+}
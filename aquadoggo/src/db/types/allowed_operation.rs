@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+/// GraphQL operation which is executed when the node runs in allow-list mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowedOperation {
+    /// Hex-encoded SHA256 hash of the query string, the same hash is used for automatic persisted
+    /// queries.
+    Hash(String),
+
+    /// Name of the executed operation, allows any query using this name.
+    Name(String),
+}
+
+impl AllowedOperation {
+    /// Returns the kind and value this operation is stored with in the database.
+    pub fn columns(&self) -> (&'static str, &str) {
+        match self {
+            AllowedOperation::Hash(hash) => ("hash", hash),
+            AllowedOperation::Name(name) => ("name", name),
+        }
+    }
+}
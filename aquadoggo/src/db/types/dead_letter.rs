@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::materializer::{Task, TaskInput};
+
+/// Materializer task which failed too many times and is not retried anymore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetterTask {
+    /// The failed task.
+    pub task: Task<TaskInput>,
+
+    /// Number of failed attempts to process this task.
+    pub attempts: u64,
+
+    /// Error message of the last failed attempt.
+    pub error: String,
+
+    /// UNIX timestamp in seconds of the last failed attempt.
+    pub failed_at: u64,
+}
@@ -3,6 +3,7 @@
 use p2panda_rs::document::traits::AsDocument;
 use p2panda_rs::document::{DocumentId, DocumentViewFields, DocumentViewId};
 use p2panda_rs::identity::PublicKey;
+use p2panda_rs::operation::OperationId;
 use p2panda_rs::schema::SchemaId;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -78,3 +79,27 @@ impl AsDocument for StorageDocument {
         }
     }
 }
+
+/// A materialised view of a document which is kept in the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageDocumentView {
+    /// The id of this view.
+    pub id: DocumentViewId,
+
+    /// The ids of all operations holding the field values of this view.
+    pub operations: Vec<OperationId>,
+
+    /// Flag indicating if this is the current view of the document.
+    pub is_current: bool,
+}
+
+/// A document whose current view changed, together with the position of this change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentChange {
+    /// Monotonically increasing sequence number of the change, can be used as a cursor to ask for
+    /// all following changes.
+    pub change_seq: u64,
+
+    /// The document in its state after the change. Deleted documents do not contain any fields.
+    pub document: StorageDocument,
+}
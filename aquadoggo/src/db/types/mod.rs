@@ -7,10 +7,24 @@
 //! available. For example, all `StorageOperation`s contain the `DocumentId` of the document they
 //! are associated with, this value is not encoded in an plain operation and must be derived from
 //! other values stored in the database.
+mod admin_query;
+mod aggregate;
+mod allowed_operation;
+mod dead_letter;
 mod document;
 mod entry;
 mod operation;
+mod peer_author;
+mod projection;
+mod unique;
 
-pub use document::StorageDocument;
+pub use admin_query::AdminQueryResult;
+pub use aggregate::{Aggregates, FieldAggregate, MaterializedAggregateGroup};
+pub use allowed_operation::AllowedOperation;
+pub use dead_letter::DeadLetterTask;
+pub use document::{DocumentChange, StorageDocument, StorageDocumentView};
 pub use entry::StorageEntry;
 pub use operation::StorageOperation;
+pub use peer_author::PeerAuthor;
+pub use projection::ProjectionRow;
+pub use unique::UniqueConflict;
@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::identity::PublicKey;
+
+/// Entries of one author which were received from one peer during replication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerAuthor {
+    /// Id of the peer the entries were received from.
+    pub peer_id: String,
+
+    /// Public key of the author of the received entries.
+    pub public_key: PublicKey,
+
+    /// UNIX timestamp in seconds of the first received entry.
+    pub first_seen: u64,
+
+    /// UNIX timestamp in seconds of the last received entry.
+    pub last_seen: u64,
+
+    /// Number of received entries.
+    pub entries: u64,
+}
@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::document::{DocumentId, DocumentViewId};
+
+/// Row of a projection table, derived from the latest view of one document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectionRow {
+    /// Id of the projected document.
+    pub document_id: DocumentId,
+
+    /// Id of the document view the values were derived from.
+    pub view_id: DocumentViewId,
+
+    /// Values of the projected fields in the order they are defined in the projection.
+    ///
+    /// Values are `None` when the field or related document does not exist (yet).
+    pub values: Vec<Option<String>>,
+}
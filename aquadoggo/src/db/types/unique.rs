@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::document::DocumentId;
+
+/// Documents of the same schema which violate a unique constraint by sharing the same values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniqueConflict {
+    /// Name of the violated unique constraint, the names of its fields separated by commas.
+    pub constraint_name: String,
+
+    /// Ids of all documents sharing the same values, sorted in ascending order.
+    pub document_ids: Vec<DocumentId>,
+}
+
+impl UniqueConflict {
+    /// Returns the id of the document which "wins" this conflict.
+    ///
+    /// This is always the document with the lowest id, making the outcome deterministic no matter
+    /// in which order the documents arrived at a node.
+    pub fn winner(&self) -> &DocumentId {
+        self.document_ids
+            .first()
+            .expect("Conflicts contain at least two documents")
+    }
+}
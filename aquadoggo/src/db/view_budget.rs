@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Maximum number of document views which get materialised on demand per second.
+const MAX_VIEWS_PER_SECOND: u32 = 10;
+
+/// Limits how many document views get materialised on demand when reading them.
+///
+/// Every view materialised this way gets written to the database, without a limit clients could
+/// cause a large amount of writes by requesting many old views of documents.
+#[derive(Clone, Debug)]
+pub struct ViewBudget {
+    max_per_second: u32,
+    window: Arc<Mutex<(Instant, u32)>>,
+}
+
+impl ViewBudget {
+    pub fn new(max_per_second: u32) -> Self {
+        Self {
+            max_per_second,
+            window: Arc::new(Mutex::new((Instant::now(), 0))),
+        }
+    }
+
+    /// Returns true if another view can be materialised within the current second.
+    pub fn try_acquire(&self) -> bool {
+        let mut window = self.window.lock().expect("View budget got poisoned");
+        let (started_at, used) = &mut *window;
+
+        if started_at.elapsed() >= Duration::from_secs(1) {
+            *started_at = Instant::now();
+            *used = 0;
+        }
+
+        if *used >= self.max_per_second {
+            return false;
+        }
+
+        *used += 1;
+        true
+    }
+}
+
+impl Default for ViewBudget {
+    fn default() -> Self {
+        Self::new(MAX_VIEWS_PER_SECOND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ViewBudget;
+
+    #[test]
+    fn limit_views_per_second() {
+        let budget = ViewBudget::new(2);
+        assert!(budget.try_acquire());
+        assert!(budget.clone().try_acquire());
+        assert!(!budget.try_acquire());
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Callbacks for applications embedding the node, running after documents got materialized.
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use p2panda_rs::document::{DocumentId, DocumentViewFields, DocumentViewId};
+use p2panda_rs::identity::PublicKey;
+use p2panda_rs::schema::SchemaId;
+
+/// Latest view of a document which was just materialized.
+#[derive(Debug, Clone)]
+pub struct MaterializedDocument {
+    /// Id of the document.
+    pub id: DocumentId,
+
+    /// Id of the materialized document view.
+    pub view_id: DocumentViewId,
+
+    /// Public key of the author who created the document.
+    pub author: PublicKey,
+
+    /// Schema of the document.
+    pub schema_id: SchemaId,
+
+    /// Field values of the materialized view.
+    pub fields: DocumentViewFields,
+}
+
+/// Custom work which runs every time a document got (re)materialized.
+///
+/// Hooks can be used to for example keep a search index up-to-date, send notifications or export
+/// documents into other systems. They run in their own worker pool after the latest view of a
+/// document was stored, deleted documents are not passed to hooks.
+#[async_trait]
+pub trait DocumentHook: Debug + Send + Sync {
+    /// Name of this hook, used in logs.
+    ///
+    /// Names need to be unique among all registered document hooks.
+    fn name(&self) -> &str;
+
+    /// Returns `true` if this hook should run for documents of the given schema.
+    fn accepts(&self, schema_id: &SchemaId) -> bool;
+
+    /// Process a materialized document.
+    ///
+    /// Errors are logged and do not stop other hooks from running.
+    async fn on_materialized(&self, document: &MaterializedDocument) -> Result<()>;
+}
+
+/// Collection of document hooks registered on a node.
+///
+/// All clones share the same hooks, this way hooks can still be registered after the node
+/// started.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentHooks(Arc<RwLock<Vec<Arc<dyn DocumentHook>>>>);
+
+impl DocumentHooks {
+    /// Register a new document hook.
+    ///
+    /// Panics if the name of the hook is already taken by another hook.
+    pub fn register(&self, hook: impl DocumentHook + 'static) {
+        let mut hooks = self
+            .0
+            .write()
+            .expect("Could not acquire lock on document hooks");
+
+        assert!(
+            hooks.iter().all(|other| other.name() != hook.name()),
+            "Document hook with name '{}' was already registered",
+            hook.name()
+        );
+
+        hooks.push(Arc::new(hook));
+    }
+
+    /// Returns all hooks which accept documents of the given schema.
+    pub fn for_schema(&self, schema_id: &SchemaId) -> Vec<Arc<dyn DocumentHook>> {
+        self.0
+            .read()
+            .expect("Could not acquire lock on document hooks")
+            .iter()
+            .filter(|hook| hook.accepts(schema_id))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use p2panda_rs::schema::SchemaId;
+
+    use super::{DocumentHook, DocumentHooks, MaterializedDocument};
+
+    #[derive(Debug)]
+    struct TestHook(&'static str);
+
+    #[async_trait]
+    impl DocumentHook for TestHook {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn accepts(&self, schema_id: &SchemaId) -> bool {
+            matches!(schema_id, SchemaId::Application(..))
+        }
+
+        async fn on_materialized(&self, _document: &MaterializedDocument) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn filter_by_schema() {
+        let hooks = DocumentHooks::default();
+
+        // Hooks registered on clones are shared
+        hooks.clone().register(TestHook("search_index"));
+
+        let schema_id: SchemaId =
+            "venues_0020c65567ae37efea293e34a9c7d13f8f2bf23dbdc3b5c7b9ab46293111c48fc78b"
+                .parse()
+                .unwrap();
+        assert_eq!(hooks.for_schema(&schema_id).len(), 1);
+        assert_eq!(hooks.for_schema(&SchemaId::SchemaDefinition(1)).len(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_names() {
+        let hooks = DocumentHooks::default();
+        hooks.register(TestHook("search_index"));
+        hooks.register(TestHook("search_index"));
+    }
+}
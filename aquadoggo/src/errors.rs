@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Errors returned by the public API of the node.
+//!
+//! Applications embedding a node can match on the kind of failure and inspect its cause through
+//! the `source()` chain of the error.
+use thiserror::Error;
+
+/// Underlying cause of an error.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Errors which can occur when starting or interacting with a node.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Database could not be created, migrated or accessed.
+    #[error("{message}")]
+    Storage {
+        /// Description of the failed action.
+        message: String,
+
+        /// Underlying error.
+        source: BoxError,
+    },
+
+    /// Data handed to the node was invalid, for example the operations of a lock file.
+    #[error("{message}")]
+    Validation {
+        /// Description of the failed action.
+        message: String,
+
+        /// Underlying error.
+        source: BoxError,
+    },
+
+    /// Network or HTTP service could not be started, for example because a port is already in use.
+    #[error("{message}")]
+    Network {
+        /// Description of the failed action.
+        message: String,
+    },
+
+    /// Configuration of the node is invalid.
+    #[error("{message}")]
+    Config {
+        /// Description of the failed action.
+        message: String,
+
+        /// Underlying error.
+        source: BoxError,
+    },
+
+    /// One of the internal services of the node could not be started.
+    #[error("Failed starting {0} service")]
+    Service(&'static str),
+}
+
+impl Error {
+    /// Returns a storage error with the given description and cause.
+    pub(crate) fn storage(message: &str, source: impl Into<BoxError>) -> Self {
+        Self::Storage {
+            message: message.to_owned(),
+            source: source.into(),
+        }
+    }
+
+    /// Returns a validation error with the given description and cause.
+    pub(crate) fn validation(message: &str, source: impl Into<BoxError>) -> Self {
+        Self::Validation {
+            message: message.to_owned(),
+            source: source.into(),
+        }
+    }
+
+    /// Returns a configuration error with the given description and cause.
+    pub(crate) fn config(message: &str, source: impl Into<BoxError>) -> Self {
+        Self::Config {
+            message: message.to_owned(),
+            source: source.into(),
+        }
+    }
+}
@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Limit the complexity of GraphQL queries before they get executed.
+//!
+//! Every selected field counts one towards the complexity of a query, fields following a relation
+//! to other documents count `RELATION_COMPLEXITY` as resolving them requires further queries
+//! against the database. Queries exceeding the configured maximum are rejected right after they
+//! were parsed.
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_graphql::extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery};
+use async_graphql::parser::types::{
+    ExecutableDocument, FragmentDefinition, OperationType, Selection, SelectionSet,
+};
+use async_graphql::registry::{MetaTypeName, Registry};
+use async_graphql::{Name, Positioned, ServerError, ServerResult, Variables};
+use async_trait::async_trait;
+use p2panda_rs::schema::{FieldType, Schema};
+
+use crate::graphql::utils::{
+    collection_item_name, fields_name, referenced_by_name, referencing_schemas,
+};
+
+/// Complexity of a field following a relation to one or many other documents.
+pub const RELATION_COMPLEXITY: usize = 10;
+
+/// Names of the relation fields, keyed by the name of the GraphQL object they are defined on.
+type RelationFields = HashMap<String, HashSet<String>>;
+
+/// Fragments defined in a GraphQL query, keyed by their name.
+type Fragments = HashMap<Name, Positioned<FragmentDefinition>>;
+
+/// Schema extension rejecting queries which exceed the maximum complexity.
+pub struct QueryComplexity {
+    max_complexity: usize,
+    relation_fields: Arc<RelationFields>,
+}
+
+impl QueryComplexity {
+    /// Returns an extension limiting queries to the given complexity, relation fields are taken
+    /// from the passed schemas.
+    pub fn new(max_complexity: usize, schemas: &[Schema]) -> Self {
+        let mut relation_fields: RelationFields = schemas
+            .iter()
+            .map(|schema| {
+                let fields = schema
+                    .fields()
+                    .iter()
+                    .filter(|(_, field_type)| is_relation(field_type))
+                    .map(|(field_name, _)| field_name.to_owned())
+                    .collect();
+
+                (fields_name(schema.id()), fields)
+            })
+            .collect();
+
+        // Reverse relation fields are defined on the document objects themselves
+        for schema in schemas {
+            let fields: HashSet<String> = referencing_schemas(schema.id(), schemas)
+                .map(|referencing| referenced_by_name(referencing.id()))
+                .collect();
+
+            if !fields.is_empty() {
+                relation_fields.insert(collection_item_name(schema.id()), fields.clone());
+                relation_fields.insert(schema.id().to_string(), fields);
+            }
+        }
+
+        Self {
+            max_complexity,
+            relation_fields: Arc::new(relation_fields),
+        }
+    }
+}
+
+impl ExtensionFactory for QueryComplexity {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QueryComplexityExtension {
+            max_complexity: self.max_complexity,
+            relation_fields: self.relation_fields.clone(),
+        })
+    }
+}
+
+struct QueryComplexityExtension {
+    max_complexity: usize,
+    relation_fields: Arc<RelationFields>,
+}
+
+#[async_trait]
+impl Extension for QueryComplexityExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let registry = &ctx.schema_env.registry;
+
+        // Operations which are not executed by this request count as well, this keeps the check
+        // independent of the requested operation name
+        let complexity = document
+            .operations
+            .iter()
+            .map(|(_, operation)| {
+                let root_type = match operation.node.ty {
+                    OperationType::Query => Some(registry.query_type.as_str()),
+                    OperationType::Mutation => registry.mutation_type.as_deref(),
+                    OperationType::Subscription => registry.subscription_type.as_deref(),
+                };
+
+                match root_type {
+                    Some(root_type) => selection_set_complexity(
+                        registry,
+                        &self.relation_fields,
+                        &document.fragments,
+                        root_type,
+                        &operation.node.selection_set.node,
+                        &mut Vec::new(),
+                    ),
+                    None => 0,
+                }
+            })
+            .max()
+            .unwrap_or_default();
+
+        if complexity > self.max_complexity {
+            return Err(ServerError::new(
+                format!(
+                    "Query is too complex, its complexity is {} but only {} is allowed.",
+                    complexity, self.max_complexity
+                ),
+                None,
+            ));
+        }
+
+        Ok(document)
+    }
+}
+
+/// Returns the complexity of all fields in a selection set of the given GraphQL type.
+///
+/// Unknown types and fields are counted without looking further into them, they get rejected
+/// during validation later.
+fn selection_set_complexity<'a>(
+    registry: &Registry,
+    relation_fields: &RelationFields,
+    fragments: &'a Fragments,
+    type_name: &str,
+    selection_set: &'a SelectionSet,
+    visited_fragments: &mut Vec<&'a str>,
+) -> usize {
+    let mut complexity = 0;
+
+    for selection in &selection_set.items {
+        complexity += match &selection.node {
+            Selection::Field(field) => {
+                let field = &field.node;
+                let field_name = field.name.node.as_str();
+
+                let is_relation = relation_fields
+                    .get(type_name)
+                    .is_some_and(|fields| fields.contains(field_name));
+                let field_complexity = if is_relation { RELATION_COMPLEXITY } else { 1 };
+
+                let field_type = registry
+                    .types
+                    .get(type_name)
+                    .and_then(|meta_type| meta_type.field_by_name(field_name))
+                    .map(|meta_field| MetaTypeName::concrete_typename(&meta_field.ty));
+
+                let children_complexity = match field_type {
+                    Some(field_type) => selection_set_complexity(
+                        registry,
+                        relation_fields,
+                        fragments,
+                        field_type,
+                        &field.selection_set.node,
+                        visited_fragments,
+                    ),
+                    None => 0,
+                };
+
+                field_complexity + children_complexity
+            }
+            Selection::InlineFragment(fragment) => {
+                let fragment_type = fragment
+                    .node
+                    .type_condition
+                    .as_ref()
+                    .map_or(type_name, |condition| condition.node.on.node.as_str());
+
+                selection_set_complexity(
+                    registry,
+                    relation_fields,
+                    fragments,
+                    fragment_type,
+                    &fragment.node.selection_set.node,
+                    visited_fragments,
+                )
+            }
+            Selection::FragmentSpread(spread) => {
+                let fragment_name = spread.node.fragment_name.node.as_str();
+
+                // Cyclic fragments are rejected during validation, we only need to make sure to
+                // not follow them endlessly here
+                if visited_fragments.contains(&fragment_name) {
+                    continue;
+                }
+
+                match fragments.get(fragment_name) {
+                    Some(fragment) => {
+                        visited_fragments.push(fragment_name);
+                        let complexity = selection_set_complexity(
+                            registry,
+                            relation_fields,
+                            fragments,
+                            fragment.node.type_condition.node.on.node.as_str(),
+                            &fragment.node.selection_set.node,
+                            visited_fragments,
+                        );
+                        visited_fragments.pop();
+                        complexity
+                    }
+                    None => 0,
+                }
+            }
+        };
+    }
+
+    complexity
+}
+
+/// Returns true if values of this field type point at other documents.
+fn is_relation(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Relation(_)
+            | FieldType::RelationList(_)
+            | FieldType::PinnedRelation(_)
+            | FieldType::PinnedRelationList(_)
+    )
+}
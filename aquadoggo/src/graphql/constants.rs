@@ -8,12 +8,51 @@
 /// GraphQL object representing a documents meta data.
 pub const DOCUMENT_META: &str = "DocumentMeta";
 
+/// GraphQL union of the document objects of all schemas.
+pub const DOCUMENT: &str = "Document";
+
 /// GraphQL object representing next arguments data.
 pub const NEXT_ARGS: &str = "NextArguments";
 
+/// GraphQL object representing a conflict of documents violating a unique constraint.
+pub const UNIQUE_CONFLICT: &str = "UniqueConflict";
+
+/// GraphQL object representing one group of a materialized aggregate.
+pub const MATERIALIZED_AGGREGATE_GROUP: &str = "MaterializedAggregateGroup";
+
+/// GraphQL object representing a materializer task in the dead-letter queue.
+pub const DEAD_LETTER_TASK: &str = "DeadLetterTask";
+
+/// GraphQL object representing the entries of an author received from a peer.
+pub const PEER_AUTHOR: &str = "PeerAuthor";
+
+/// GraphQL object representing an entry in a log.
+pub const LOG_ENTRY: &str = "LogEntry";
+
+/// GraphQL object representing information about the current page of a paginated collection.
+pub const PAGE_INFO: &str = "PageInfo";
+
+/// GraphQL object representing the fields of a schema.
+pub const SCHEMA_FIELDS: &str = "SchemaFields";
+
+/// GraphQL object representing the status of the node.
+pub const NODE_STATUS: &str = "NodeStatus";
+
+/// GraphQL object representing the timing of a processed materializer task.
+pub const TASK_TIMING: &str = "TaskTiming";
+
+/// GraphQL object representing a queued or running materializer task.
+pub const PENDING_TASK: &str = "PendingTask";
+
 /// GraphQL scalar type representing a public key.
 pub const PUBLIC_KEY: &str = "PublicKey";
 
+/// GraphQL scalar type representing a log id.
+pub const LOG_ID: &str = "LogId";
+
+/// GraphQL scalar representing the hash of an entry.
+pub const ENTRY_HASH: &str = "EntryHash";
+
 /// GraphQL scalar representing a document id.
 pub const DOCUMENT_ID: &str = "DocumentId";
 
@@ -25,15 +64,89 @@ pub const DOCUMENT_VIEW_ID: &str = "DocumentViewId";
 /// Prefix for query name where all documents of a particular schema can be retrieved.
 pub const QUERY_ALL_PREFIX: &str = "all_";
 
+/// Prefix for query name where aggregated values of all documents of a particular schema can be
+/// retrieved.
+pub const QUERY_AGGREGATE_PREFIX: &str = "aggregate_";
+
+/// Prefix for subscription name where changes to documents of a particular schema are delivered.
+pub const SUBSCRIPTION_CHANGES_PREFIX: &str = "changes_";
+
+/// Prefix for field name on a document where documents of a particular schema relating to it can
+/// be retrieved.
+pub const REFERENCED_BY_PREFIX: &str = "referencedBy_";
+
+/// Name of query to fetch many documents of any schema by their ids.
+pub const DOCUMENTS_QUERY: &str = "documents";
+
+/// Name of query to fetch all documents of a schema created by one author.
+pub const DOCUMENTS_BY_AUTHOR_QUERY: &str = "documentsByAuthor";
+
 /// Name of query to fetch next entry arguments.
 pub const NEXT_ARGS_QUERY: &str = "nextArgs";
 
+/// Name of query to fetch documents violating unique constraints.
+pub const UNIQUE_CONFLICTS_QUERY: &str = "uniqueConflicts";
+
+/// Name of query to fetch materializer tasks in the dead-letter queue.
+pub const DEAD_LETTER_TASKS_QUERY: &str = "deadLetterTasks";
+
+/// Name of query to fetch which authors were received from which peers.
+pub const PEER_AUTHORS_QUERY: &str = "peerAuthors";
+
+/// Name of query to mint signed URLs of blobs.
+pub const SIGNED_BLOB_URL_QUERY: &str = "signedBlobUrl";
+
+/// Name of query to fetch all entries of a log.
+pub const ENTRY_CHAIN_QUERY: &str = "entryChain";
+
+/// Name of query to fetch the certificate pool of an entry.
+pub const CERTIFICATE_POOL_QUERY: &str = "certificatePool";
+
+/// Name of query to fetch the fields of a schema.
+pub const SCHEMA_FIELDS_QUERY: &str = "schemaFields";
+
+/// Name of query to fetch the status of the node.
+pub const NODE_STATUS_QUERY: &str = "nodeStatus";
+
+/// Name of query to fetch the timing of recently processed materializer tasks.
+pub const TASK_TIMELINE_QUERY: &str = "taskTimeline";
+
+/// Name of query to fetch queued and running materializer tasks.
+pub const PENDING_TASKS_QUERY: &str = "pendingTasks";
+
+/// Name of query to fetch the groups of a materialized aggregate.
+pub const MATERIALIZED_AGGREGATE_QUERY: &str = "materializedAggregate";
+
+/// Name of query to resolve a claimed name to the public key of its owner.
+pub const RESOLVE_NAME_QUERY: &str = "resolveName";
+
+/// Argument string used for passing the lifetime of a signed URL in seconds into a query.
+pub const EXPIRES_IN_ARG: &str = "expiresIn";
+
+/// Argument string used for passing a time span in minutes into a query.
+pub const MINUTES_ARG: &str = "minutes";
+
+/// Argument string used for passing a claimed name into a query.
+pub const NAME_ARG: &str = "name";
+
+/// Argument string used for passing a schema id into a query.
+pub const SCHEMA_ID_ARG: &str = "schemaId";
+
 /// Argument string used for passing a document id into a query.
 pub const DOCUMENT_ID_ARG: &str = "id";
 
+/// Argument string used for passing a list of document ids into a query.
+pub const DOCUMENT_IDS_ARG: &str = "ids";
+
 /// Argument string used for passing a public key into a query.
 pub const PUBLIC_KEY_ARG: &str = "publicKey";
 
+/// Argument string used for passing an entry hash into a query.
+pub const ENTRY_HASH_ARG: &str = "hash";
+
+/// Argument string used for passing a log id into a query.
+pub const LOG_ID_ARG: &str = "logId";
+
 /// Argument string used for passing a document view id into a query.
 pub const DOCUMENT_VIEW_ID_ARG: &str = "viewId";
 
@@ -43,9 +156,21 @@ pub const FILTER_ARG: &str = "filter";
 /// Argument string used for passing a filter into a query.
 pub const META_FILTER_ARG: &str = "meta";
 
+/// Argument string used for passing a search string into a query.
+pub const SEARCH_ARG: &str = "search";
+
+/// Argument string used for including deleted documents in a query.
+pub const INCLUDE_DELETED_ARG: &str = "includeDeleted";
+
+/// Argument string used for passing the public key of a document owner into a query.
+pub const OWNER_ARG: &str = "owner";
+
 /// Argument string used for passing a pagination cursor into a query.
 pub const PAGINATION_AFTER_ARG: &str = "after";
 
+/// Argument string used for passing a change cursor into a subscription.
+pub const CHANGES_SINCE_ARG: &str = "since";
+
 /// Argument string used for passing number of paginated items requested to query.
 pub const PAGINATION_FIRST_ARG: &str = "first";
 
@@ -55,15 +180,27 @@ pub const ORDER_BY_ARG: &str = "orderBy";
 /// Argument string used for passing ordering direction to query.
 pub const ORDER_DIRECTION_ARG: &str = "orderDirection";
 
+/// Argument string used for passing multiple fields and directions to order by to query.
+pub const ORDER_BY_FIELDS_ARG: &str = "orderByFields";
+
 /// Name of field where a collection of documents can be accessed.
 pub const DOCUMENTS_FIELD: &str = "documents";
 
+/// Name of field on a document change which contains the changed document.
+pub const DOCUMENT_FIELD: &str = "document";
+
 /// Name of field on a document where its fields can be accessed.
 pub const FIELDS_FIELD: &str = "fields";
 
 /// Name of field on a document where its meta data can be accessed.
 pub const META_FIELD: &str = "meta";
 
+/// Name of field on a projection row where the id of the projected document can be accessed.
+pub const DOCUMENT_ID_FIELD: &str = "documentId";
+
+/// Name of field on a projection row where the id of the projected document view can be accessed.
+pub const DOCUMENT_VIEW_ID_FIELD: &str = "viewId";
+
 /// Name of field on a document where pagination cursor can be accessed.
 pub const CURSOR_FIELD: &str = "cursor";
 
@@ -81,3 +218,21 @@ pub const START_CURSOR_FIELD: &str = "startCursor";
 
 /// Name of field on a paginated response which contains the cursor for the next page.
 pub const END_CURSOR_FIELD: &str = "endCursor";
+
+/// Name of field on a paginated response which contains information about the current page.
+pub const PAGE_INFO_FIELD: &str = "pageInfo";
+
+/// Name of field on an aggregate response which contains the number of documents.
+pub const COUNT_FIELD: &str = "count";
+
+/// Name of field on an aggregate response which contains the smallest values.
+pub const MIN_FIELD: &str = "min";
+
+/// Name of field on an aggregate response which contains the largest values.
+pub const MAX_FIELD: &str = "max";
+
+/// Name of field on an aggregate response which contains the average values.
+pub const AVG_FIELD: &str = "avg";
+
+/// Name of field on an aggregate response which contains the sum of values.
+pub const SUM_FIELD: &str = "sum";
@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Compare GraphQL schemas to report changes of the API to operators.
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+/// Changed types between two versions of the GraphQL schema.
+///
+/// Types are compared by their SDL definition, any change of their fields, arguments or
+/// descriptions marks them as changed. Changes only adding to a type, like new fields, are not
+/// considered to be breaking.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphQLSchemaDiff {
+    /// Names of types which were added to the schema.
+    pub added: Vec<String>,
+
+    /// Names of types which were removed from the schema.
+    pub removed: Vec<String>,
+
+    /// Names of types which exist in both schemas but are defined differently.
+    pub changed: Vec<String>,
+
+    /// Flag indicating if any of the changed types lost a part of their previous definition, for
+    /// example a field.
+    changed_breaking: bool,
+}
+
+impl GraphQLSchemaDiff {
+    /// Compare the SDL of two GraphQL schemas.
+    pub fn new(previous_sdl: &str, sdl: &str) -> Self {
+        let previous = type_definitions(previous_sdl);
+        let current = type_definitions(sdl);
+
+        let mut diff = Self::default();
+
+        for (name, definition) in &current {
+            match previous.get(name) {
+                None => diff.added.push(name.to_string()),
+                Some(previous_definition) if previous_definition != definition => {
+                    diff.changed.push(name.to_string());
+
+                    // Types only extended by new fields or union members stay compatible with
+                    // existing queries
+                    let is_extended = definition_parts(previous_definition)
+                        .all(|part| definition_parts(definition).any(|other| other == part));
+                    if !is_extended {
+                        diff.changed_breaking = true;
+                    }
+                }
+                Some(_) => (),
+            }
+        }
+
+        for name in previous.keys() {
+            if !current.contains_key(name) {
+                diff.removed.push(name.to_string());
+            }
+        }
+
+        diff
+    }
+
+    /// Returns `true` if no types were added, removed or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns `true` if types were removed or parts of their definitions changed, which might
+    /// break queries of connected clients.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || self.changed_breaking
+    }
+}
+
+impl Display for GraphQLSchemaDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "added: [{}], removed: [{}], changed: [{}]",
+            self.added.join(", "),
+            self.removed.join(", "),
+            self.changed.join(", ")
+        )
+    }
+}
+
+/// Quotes opening and closing a block string in GraphQL.
+const BLOCK_QUOTE: &str = "\"\"\"";
+
+/// Returns the definitions of all types in a GraphQL SDL string, indexed by their name.
+///
+/// Definitions in the SDL are separated by empty lines, each of them starts with an optional
+/// description followed by the keyword and name of the type.
+fn type_definitions(sdl: &str) -> BTreeMap<&str, &str> {
+    sdl.split("\n\n")
+        .map(|definition| definition.trim())
+        .filter_map(|definition| {
+            let mut in_description = false;
+
+            let signature = definition.lines().map(|line| line.trim()).find(|line| {
+                if in_description {
+                    in_description = !line.ends_with(BLOCK_QUOTE);
+                    false
+                } else if let Some(description) = line.strip_prefix(BLOCK_QUOTE) {
+                    // Block descriptions can span multiple lines
+                    in_description = !description.ends_with(BLOCK_QUOTE);
+                    false
+                } else {
+                    !line.starts_with('"') && !line.is_empty()
+                }
+            })?;
+
+            let mut tokens = signature.split_whitespace();
+            match tokens.next()? {
+                "type" | "input" | "enum" | "union" | "scalar" | "interface" => {
+                    let name = tokens.next()?.trim_end_matches('{');
+                    Some((name, definition))
+                }
+                // Directives and the schema definition itself are not types
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Splits a type definition into its lines, union members are regarded as separate parts.
+fn definition_parts(definition: &str) -> impl Iterator<Item = &str> {
+    definition
+        .lines()
+        .flat_map(|line| line.split(" = "))
+        .flat_map(|part| part.split(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphQLSchemaDiff;
+
+    const PREVIOUS_SDL: &str = r#"
+"""
+A panda.
+"""
+type Panda {
+	name: String!
+	age: Int
+}
+
+type Query {
+	panda: Panda
+	bamboo: Bamboo
+}
+
+type Bamboo {
+	height: Float!
+}
+
+scalar Cursor
+"#;
+
+    const SDL: &str = r#"
+"""
+A panda.
+"""
+type Panda {
+	name: String!
+}
+
+type Query {
+	panda: Panda
+	bamboo: Bamboo
+	duration: Duration
+}
+
+type Bamboo {
+	height: Float!
+}
+
+"The time a panda needs to eat."
+scalar Duration
+
+scalar Cursor
+"#;
+
+    #[test]
+    fn diff_types() {
+        let diff = GraphQLSchemaDiff::new(PREVIOUS_SDL, SDL);
+        assert_eq!(diff.added, vec!["Duration".to_string()]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, vec!["Panda".to_string(), "Query".to_string()]);
+        assert!(diff.is_breaking());
+
+        let diff = GraphQLSchemaDiff::new(SDL, PREVIOUS_SDL);
+        assert_eq!(diff.removed, vec!["Duration".to_string()]);
+        assert_eq!(
+            diff.to_string(),
+            "added: [], removed: [Duration], changed: [Panda, Query]"
+        );
+
+        let diff = GraphQLSchemaDiff::new(SDL, SDL);
+        assert!(diff.is_empty());
+        assert!(!diff.is_breaking());
+    }
+
+    #[test]
+    fn extended_types_are_not_breaking() {
+        let previous_sdl = "type Query {\n\tpanda: String\n}\n\nunion Animal = Panda\n";
+        let sdl = "type Query {\n\tpanda: String\n\tbamboo: String\n}\n\nunion Animal = Bear | Panda\n\nscalar Duration\n";
+
+        let diff = GraphQLSchemaDiff::new(previous_sdl, sdl);
+        assert_eq!(diff.added, vec!["Duration".to_string()]);
+        assert_eq!(
+            diff.changed,
+            vec!["Animal".to_string(), "Query".to_string()]
+        );
+        assert!(!diff.is_breaking());
+    }
+}
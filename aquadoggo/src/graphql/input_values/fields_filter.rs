@@ -175,6 +175,14 @@ pub struct StringFilter {
     /// Filter for items which don't contain given value.
     #[graphql(name = "notContains")]
     not_contains: Option<String>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for bytes field values.
@@ -188,6 +196,14 @@ pub struct HexBytesFilter {
     /// Filter by not equal to.
     #[graphql(name = "notEq")]
     not_eq: Option<HexBytesScalar>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for integer field values.
@@ -221,6 +237,14 @@ pub struct IntegerFilter {
 
     /// Filter by less than.
     lt: Option<u64>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for float field values.
@@ -254,6 +278,14 @@ pub struct FloatFilter {
 
     /// Filter by less than.
     lt: Option<f64>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for boolean field values.
@@ -267,6 +299,14 @@ pub struct BooleanFilter {
     /// Filter by not equal to.
     #[graphql(name = "notEq")]
     not_eq: Option<bool>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for relation field values.
@@ -288,6 +328,14 @@ pub struct RelationFilter {
     /// Filter by values not in set.
     #[graphql(name = "notIn")]
     is_not_in: Option<Vec<DocumentIdScalar>>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for pinned relation field values.
@@ -309,6 +357,14 @@ pub struct PinnedRelationFilter {
     /// Filter by values not in set.
     #[graphql(name = "notIn")]
     is_not_in: Option<Vec<DocumentViewIdScalar>>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for relation list field values.
@@ -322,6 +378,14 @@ pub struct RelationListFilter {
     /// Filter by values not in set.
     #[graphql(name = "notIn")]
     not_in: Option<Vec<DocumentIdScalar>>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
 
 /// A filter input type for pinned relation list field values.
@@ -335,4 +399,12 @@ pub struct PinnedRelationListFilter {
     /// Filter by values not in set.
     #[graphql(name = "notIn")]
     not_in: Option<Vec<DocumentViewIdScalar>>,
+
+    /// Filter for items which do not have a value for this field.
+    #[graphql(name = "isNull")]
+    is_null: Option<bool>,
+
+    /// Filter for items which have a value for this field.
+    #[graphql(name = "isSet")]
+    is_set: Option<bool>,
 }
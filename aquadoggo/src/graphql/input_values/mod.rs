@@ -10,4 +10,4 @@ pub use fields_filter::{
     RelationFilter, RelationListFilter, StringFilter,
 };
 pub use meta_filter::MetaFilterInputObject;
-pub use order::{build_order_enum_value, OrderDirection};
+pub use order::{build_order_by_fields_input_object, build_order_enum_value, OrderDirection};
@@ -1,11 +1,11 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 //! Input value types used when specifying ordering parameters on collection queries.
-use async_graphql::dynamic::Enum;
+use async_graphql::dynamic::{Enum, InputObject, InputValue, TypeRef};
 use dynamic_graphql::Enum;
 use p2panda_rs::schema::Schema;
 
-use crate::graphql::utils::order_by_name;
+use crate::graphql::utils::{order_by_fields_name, order_by_name};
 
 /// Meta fields by which a collection of documents can be sorted.
 // @TODO: Add more fields, see related issue: https://github.com/p2panda/aquadoggo/issues/326
@@ -43,3 +43,21 @@ pub fn build_order_enum_value(schema: &Schema) -> Enum {
     }
     input_values
 }
+
+/// Dynamically build an input object which sets one field and direction a collection of documents
+/// can be ordered by.
+///
+/// A list of these input objects is passed to the `orderByFields` argument to order by multiple
+/// fields, items with equal values are ordered by the next field in the list.
+pub fn build_order_by_fields_input_object(schema: &Schema) -> InputObject {
+    InputObject::new(order_by_fields_name(schema.id()))
+        .field(
+            InputValue::new("field", TypeRef::named_nn(order_by_name(schema.id())))
+                .description("Field by which items in the collection will be ordered"),
+        )
+        .field(
+            InputValue::new("direction", TypeRef::named("OrderDirection"))
+                .description("Direction which items in the collection will be ordered")
+                .default_value("ASC"),
+        )
+}
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod complexity;
 pub mod constants;
+mod diff;
 pub mod input_values;
 pub mod mutations;
 pub mod objects;
@@ -9,8 +11,11 @@ pub mod resolvers;
 pub mod responses;
 pub mod scalars;
 mod schema;
+pub mod subscriptions;
 #[cfg(test)]
 mod tests;
 pub mod utils;
 
+pub use diff::GraphQLSchemaDiff;
+pub use mutations::AdminRequest;
 pub use schema::GraphQLSchemaManager;
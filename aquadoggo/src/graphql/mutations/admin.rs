@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use anyhow::anyhow;
+use dynamic_graphql::{Context, Mutation, MutationFields, Result};
+use log::{debug, info};
+use p2panda_rs::document::DocumentId;
+use p2panda_rs::storage_provider::traits::OperationStore;
+
+use crate::db::SqlStore;
+use crate::graphql::mutations::MutationRoot;
+use crate::graphql::scalars::DocumentIdScalar;
+use crate::utils::now;
+
+/// Source of admin mutation entries in the audit log.
+const AUDIT_LOG_SOURCE: &str = "graphql_admin";
+
+/// Marks GraphQL requests which were authenticated with the admin token of the node.
+#[derive(Debug, Clone, Copy)]
+pub struct AdminRequest;
+
+/// GraphQL mutations for operators, only available to requests sending the admin token.
+#[derive(Mutation, Default, Debug, Copy, Clone)]
+pub struct Admin(MutationRoot);
+
+#[MutationFields]
+impl Admin {
+    /// Remove a document with all its views, operations and entries from the node.
+    ///
+    /// Use this to comply with requests to actually delete data. Derived data in projections and
+    /// aggregates is rebuilt without the document when the node restarts. Peers which still hold
+    /// the document can replicate it again.
+    ///
+    /// Returns `false` if the node does not know the document.
+    #[graphql(name = "purgeDocument")]
+    async fn purge_document(
+        ctx: &Context<'_>,
+        // Id of the document to purge
+        #[graphql(name = "documentId")] document_id: DocumentIdScalar,
+    ) -> Result<bool> {
+        if ctx.data_opt::<AdminRequest>().is_none() {
+            return Err(anyhow!("Admin token required").into());
+        }
+
+        let store = ctx.data::<SqlStore>()?;
+        let document_id = DocumentId::from(&document_id);
+        debug!("Query to purgeDocument received for {}", document_id);
+
+        store
+            .insert_audit_log_entry(
+                AUDIT_LOG_SOURCE,
+                &format!("purgeDocument {}", document_id),
+                now(),
+            )
+            .await?;
+
+        if store
+            .get_operations_by_document_id(&document_id)
+            .await?
+            .is_empty()
+        {
+            return Ok(false);
+        }
+
+        store.purge_document(&document_id).await?;
+        store.blob_cache.invalidate(&document_id);
+        info!("Purged document {}", document_id);
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::storage_provider::traits::DocumentStore;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::test_utils::{
+        add_document, add_schema, assert_query, http_test_client, test_runner, TestNode,
+    };
+
+    #[rstest]
+    fn purge_document_with_admin_token(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.admin_token = Some("secret".into());
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let schema = add_schema(
+                &mut node,
+                "puppies",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+            let view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "Bubu".into())],
+                &key_pair,
+            )
+            .await;
+            let document_id: DocumentId = view_id.to_string().parse().unwrap();
+
+            let client = http_test_client(&node).await;
+            let mutation = json!({
+                "query": format!(
+                    r#"mutation {{ result: purgeDocument(documentId: "{}") }}"#,
+                    document_id
+                ),
+            });
+
+            // Requests without the admin token are rejected
+            for authorization in [None, Some("Bearer wrong")] {
+                let mut request = client.post("/graphql").json(&mutation);
+                if let Some(authorization) = authorization {
+                    request = request.header("Authorization", authorization);
+                }
+                let response: Response = request.send().await.json().await;
+                assert_eq!(response.errors[0].message, "Admin token required");
+            }
+            assert!(node
+                .context
+                .store
+                .get_document(&document_id)
+                .await
+                .unwrap()
+                .is_some());
+
+            // The document and everything it consists of is gone after purging it
+            let response: Response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&mutation)
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({ "result": true })
+            );
+            assert!(node
+                .context
+                .store
+                .get_document(&document_id)
+                .await
+                .unwrap()
+                .is_none());
+            assert_query(
+                &node,
+                &format!(
+                    "SELECT operation_id FROM operations_v1 WHERE document_id = '{}'",
+                    document_id
+                ),
+                0,
+            )
+            .await;
+            assert_query(
+                &node,
+                &format!(
+                    "SELECT entry_hash FROM entries WHERE entry_hash = '{}'",
+                    document_id
+                ),
+                0,
+            )
+            .await;
+
+            // Purging happens once, all admin mutations are audited
+            let response: Response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&mutation)
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({ "result": false })
+            );
+            assert_query(
+                &node,
+                "SELECT message FROM audit_log WHERE source = 'graphql_admin'",
+                2,
+            )
+            .await;
+        })
+    }
+}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use anyhow::anyhow;
+use dynamic_graphql::{Context, Mutation, MutationFields, Result};
+use log::debug;
+
+use crate::db::types::AllowedOperation;
+use crate::db::SqlStore;
+use crate::graphql::mutations::{AdminRequest, MutationRoot};
+
+/// GraphQL mutations to manage the operations executed when the node runs in allow-list mode.
+///
+/// Only available to requests sending the admin token, these are always executed, even when they
+/// are not part of the allow-list themselves.
+#[derive(Mutation, Default, Debug, Copy, Clone)]
+pub struct AllowedOperations(MutationRoot);
+
+#[MutationFields]
+impl AllowedOperations {
+    /// Add a GraphQL operation to the allow-list.
+    ///
+    /// Returns `false` if the operation was already allowed.
+    #[graphql(name = "allowOperation")]
+    async fn allow_operation(
+        ctx: &Context<'_>,
+        // Hex-encoded SHA256 hash of the query string
+        hash: Option<String>,
+        // Name of the operation
+        name: Option<String>,
+    ) -> Result<bool> {
+        if ctx.data_opt::<AdminRequest>().is_none() {
+            return Err(anyhow!("Admin token required").into());
+        }
+
+        let store = ctx.data::<SqlStore>()?;
+
+        let operation = parse_operation(hash, name)?;
+        debug!("Query to allowOperation received for {:?}", operation);
+
+        Ok(store.allow_operation(&operation).await?)
+    }
+
+    /// Remove a GraphQL operation from the allow-list.
+    ///
+    /// Returns `false` if the operation was not allowed.
+    #[graphql(name = "disallowOperation")]
+    async fn disallow_operation(
+        ctx: &Context<'_>,
+        // Hex-encoded SHA256 hash of the query string
+        hash: Option<String>,
+        // Name of the operation
+        name: Option<String>,
+    ) -> Result<bool> {
+        if ctx.data_opt::<AdminRequest>().is_none() {
+            return Err(anyhow!("Admin token required").into());
+        }
+
+        let store = ctx.data::<SqlStore>()?;
+
+        let operation = parse_operation(hash, name)?;
+        debug!("Query to disallowOperation received for {:?}", operation);
+
+        Ok(store.disallow_operation(&operation).await?)
+    }
+}
+
+/// Build operation from mutation arguments, exactly one of hash or name needs to be set.
+fn parse_operation(hash: Option<String>, name: Option<String>) -> Result<AllowedOperation> {
+    match (hash, name) {
+        (Some(hash), None) => {
+            let is_sha256 = hash.len() == 64 && hash.chars().all(|char| char.is_ascii_hexdigit());
+            if !is_sha256 {
+                return Err(anyhow!("Hash needs to be a hex-encoded SHA256 hash").into());
+            }
+
+            Ok(AllowedOperation::Hash(hash.to_lowercase()))
+        }
+        (None, Some(name)) => Ok(AllowedOperation::Name(name)),
+        _ => Err(anyhow!("Either hash or name needs to be given").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use sha2::{Digest, Sha256};
+    use tokio::sync::broadcast;
+
+    use crate::blobs::BlobUploads;
+    use crate::config::Configuration;
+    use crate::db::types::AllowedOperation;
+    use crate::graphql::GraphQLSchemaManager;
+    use crate::http::{build_server, HttpServiceContext};
+    use crate::test_utils::{test_runner, TestClient, TestNode};
+
+    #[test]
+    fn execute_allowed_operations_only() {
+        test_runner(|node: TestNode| async move {
+            let config = Configuration {
+                operation_allow_list: true,
+                admin_token: Some("secret".into()),
+                ..node.context.config.clone()
+            };
+
+            let (tx, _) = broadcast::channel(120);
+            let manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx.clone(),
+                node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
+            let context = HttpServiceContext::new(
+                node.context.store.clone(),
+                manager,
+                node.context.blobs.clone(),
+                node.context.blob_access.clone(),
+                node.context.blob_integrity.clone(),
+                BlobUploads::new(node.context.clone(), tx),
+                &config,
+            );
+            let client = TestClient::new(build_server(context));
+
+            let send = |body: Value, authorization: Option<&'static str>| {
+                let client = &client;
+                async move {
+                    let mut request = client.post("/graphql").json(&body);
+                    if let Some(authorization) = authorization {
+                        request = request.header("Authorization", authorization);
+                    }
+                    let response: Value = request.send().await.json().await;
+                    response
+                }
+            };
+
+            let query = "{ __typename }";
+            let hash = hex::encode(Sha256::digest(query.as_bytes()));
+            let allow_query =
+                "mutation AllowOperation($hash: String) { allowOperation(hash: $hash) }";
+            let allow_request = json!({ "query": allow_query, "variables": { "hash": hash } });
+
+            // Nothing is allowed yet
+            let response = send(json!({ "query": query }), None).await;
+            assert_eq!(
+                response["errors"][0]["message"],
+                json!("Operation is not allowed on this node")
+            );
+
+            // Managing the allow-list requires the admin token, even when the mutation itself
+            // got allowed
+            node.context
+                .store
+                .allow_operation(&AllowedOperation::Name("AllowOperation".into()))
+                .await
+                .unwrap();
+
+            let response = send(allow_request.clone(), Some("Bearer wrong")).await;
+            assert_eq!(
+                response["errors"][0]["message"],
+                json!("Admin token required")
+            );
+
+            // Admin requests are executed in allow-list mode without being registered
+            node.context
+                .store
+                .disallow_operation(&AllowedOperation::Name("AllowOperation".into()))
+                .await
+                .unwrap();
+
+            let response = send(allow_request, Some("Bearer secret")).await;
+            assert_eq!(response, json!({ "data": { "allowOperation": true } }));
+
+            // The registered query gets executed, others are still rejected
+            let response = send(json!({ "query": query }), None).await;
+            assert_eq!(response, json!({ "data": { "__typename": "Query" } }));
+
+            let response = send(json!({ "query": "{ __schema { __typename } }" }), None).await;
+            assert_eq!(
+                response["errors"][0]["message"],
+                json!("Operation is not allowed on this node")
+            );
+        })
+    }
+}
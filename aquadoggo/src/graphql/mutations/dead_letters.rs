@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use anyhow::anyhow;
+use dynamic_graphql::{Context, Mutation, MutationFields, Result};
+use log::debug;
+use p2panda_rs::document::{DocumentId, DocumentViewId};
+
+use crate::bus::{ServiceMessage, ServiceSender};
+use crate::db::SqlStore;
+use crate::graphql::mutations::{AdminRequest, MutationRoot};
+use crate::graphql::scalars::{DocumentIdScalar, DocumentViewIdScalar};
+use crate::materializer::{Task, TaskInput};
+
+/// GraphQL mutations to manage the dead-letter queue of the materializer.
+///
+/// Both mutations require the admin token of the node.
+#[derive(Mutation, Default, Debug, Copy, Clone)]
+pub struct DeadLetters(MutationRoot);
+
+#[MutationFields]
+impl DeadLetters {
+    /// Take a task out of the dead-letter queue and dispatch it again.
+    ///
+    /// Returns `false` if the task was not in the dead-letter queue.
+    #[graphql(name = "retryDeadLetterTask")]
+    async fn retry_dead_letter_task(
+        ctx: &Context<'_>,
+        // Name of the worker which processes the task
+        name: String,
+        // Document id the task was dispatched for
+        #[graphql(name = "documentId")] document_id: Option<DocumentIdScalar>,
+        // Document view id the task was dispatched for
+        #[graphql(name = "viewId")] view_id: Option<DocumentViewIdScalar>,
+    ) -> Result<bool> {
+        if ctx.data_opt::<AdminRequest>().is_none() {
+            return Err(anyhow!("Admin token required").into());
+        }
+
+        let store = ctx.data::<SqlStore>()?;
+        let tx = ctx.data::<ServiceSender>()?;
+
+        let task = parse_task(name, document_id, view_id)?;
+        debug!("Query to retryDeadLetterTask received for {:?}", task);
+
+        if !store.retry_dead_letter_task(&task).await? {
+            return Ok(false);
+        }
+
+        if tx.send(ServiceMessage::RetryTask(task)).is_err() {
+            // Silently fail here as we don't mind if there are no subscribers
+        }
+
+        Ok(true)
+    }
+
+    /// Remove a task from the dead-letter queue without dispatching it again.
+    ///
+    /// Returns `false` if the task was not in the dead-letter queue.
+    #[graphql(name = "discardDeadLetterTask")]
+    async fn discard_dead_letter_task(
+        ctx: &Context<'_>,
+        // Name of the worker which processes the task
+        name: String,
+        // Document id the task was dispatched for
+        #[graphql(name = "documentId")] document_id: Option<DocumentIdScalar>,
+        // Document view id the task was dispatched for
+        #[graphql(name = "viewId")] view_id: Option<DocumentViewIdScalar>,
+    ) -> Result<bool> {
+        if ctx.data_opt::<AdminRequest>().is_none() {
+            return Err(anyhow!("Admin token required").into());
+        }
+
+        let store = ctx.data::<SqlStore>()?;
+
+        let task = parse_task(name, document_id, view_id)?;
+        debug!("Query to discardDeadLetterTask received for {:?}", task);
+
+        Ok(store.remove_dead_letter_task(&task).await?)
+    }
+}
+
+/// Build task from mutation arguments, exactly one of document id or view id needs to be set.
+fn parse_task(
+    name: String,
+    document_id: Option<DocumentIdScalar>,
+    view_id: Option<DocumentViewIdScalar>,
+) -> Result<Task<TaskInput>> {
+    let input = match (document_id, view_id) {
+        (Some(document_id), None) => TaskInput::DocumentId(DocumentId::from(&document_id)),
+        (None, Some(view_id)) => TaskInput::DocumentViewId(DocumentViewId::from(view_id)),
+        _ => return Err(anyhow!("Either documentId or viewId needs to be given").into()),
+    };
+
+    Ok(Task::new(&name, input))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Request;
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::test_utils::fixtures::document_id;
+    use rstest::rstest;
+    use serde_json::json;
+    use tokio::sync::broadcast;
+
+    use crate::blobs::BlobUploads;
+    use crate::bus::ServiceMessage;
+    use crate::db::types::DeadLetterTask;
+    use crate::graphql::{AdminRequest, GraphQLSchemaManager};
+    use crate::http::HttpServiceContext;
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{test_runner, TestNode};
+
+    #[rstest]
+    fn retry_and_discard_dead_letter_tasks(document_id: DocumentId) {
+        test_runner(|node: TestNode| async move {
+            let task = Task::new("reduce", TaskInput::DocumentId(document_id.clone()));
+            let dead_letter_task = DeadLetterTask {
+                task: task.clone(),
+                attempts: 3,
+                error: "Something went wrong".into(),
+                failed_at: 100,
+            };
+            node.context
+                .store
+                .insert_dead_letter_task(&dead_letter_task)
+                .await
+                .unwrap();
+
+            let (tx, mut rx) = broadcast::channel(120);
+            let manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx.clone(),
+                node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
+            let context = HttpServiceContext::new(
+                node.context.store.clone(),
+                manager,
+                node.context.blobs.clone(),
+                node.context.blob_access.clone(),
+                node.context.blob_integrity.clone(),
+                BlobUploads::new(node.context.clone(), tx),
+                &node.context.config,
+            );
+
+            let mutation = |name: &str| {
+                Request::new(format!(
+                    r#"mutation {{ result: {}(name: "reduce", documentId: "{}") }}"#,
+                    name, document_id
+                ))
+                .data(AdminRequest)
+            };
+
+            // Requests without the admin token are rejected
+            for name in ["retryDeadLetterTask", "discardDeadLetterTask"] {
+                let response = context
+                    .schema
+                    .execute(Request::new(format!(
+                        r#"mutation {{ result: {}(name: "reduce", documentId: "{}") }}"#,
+                        name, document_id
+                    )))
+                    .await;
+                assert_eq!(response.errors[0].message, "Admin token required");
+            }
+            assert_eq!(
+                node.context.store.get_dead_letter_tasks().await.unwrap(),
+                vec![dead_letter_task.clone()]
+            );
+
+            // Retrying removes the task from the queue and dispatches it again
+            let response = context
+                .schema
+                .execute(mutation("retryDeadLetterTask"))
+                .await;
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({ "result": true })
+            );
+            assert_eq!(
+                rx.recv().await.unwrap(),
+                ServiceMessage::RetryTask(task.clone())
+            );
+            assert!(node
+                .context
+                .store
+                .get_dead_letter_tasks()
+                .await
+                .unwrap()
+                .is_empty());
+            assert_eq!(node.context.store.get_tasks().await.unwrap(), vec![task]);
+
+            // Discarding only removes the task
+            node.context
+                .store
+                .insert_dead_letter_task(&dead_letter_task)
+                .await
+                .unwrap();
+
+            for expected in [true, false].iter() {
+                let response = context
+                    .schema
+                    .execute(mutation("discardDeadLetterTask"))
+                    .await;
+                assert_eq!(
+                    response.data.into_json().unwrap(),
+                    json!({ "result": expected })
+                );
+            }
+            assert!(rx.try_recv().is_err());
+        })
+    }
+}
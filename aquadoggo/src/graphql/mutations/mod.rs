@@ -1,5 +1,13 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod admin;
+mod allowed_operations;
+mod dead_letters;
 mod publish;
+mod publish_many;
 
+pub use admin::{Admin, AdminRequest};
+pub use allowed_operations::AllowedOperations;
+pub use dead_letters::DeadLetters;
 pub use publish::{MutationRoot, Publish};
+pub use publish_many::{MaxBatchEntries, PublishInput, PublishMany};
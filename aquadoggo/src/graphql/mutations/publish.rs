@@ -1,20 +1,28 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use anyhow::anyhow;
+use async_graphql::{value, Error, ErrorExtensions};
 use dynamic_graphql::{Context, Mutation, MutationFields, MutationRoot, Result};
 use log::debug;
-use p2panda_rs::api::publish;
-use p2panda_rs::entry::traits::AsEncodedEntry;
-use p2panda_rs::entry::EncodedEntry;
+use p2panda_rs::entry::decode::decode_entry;
+use p2panda_rs::entry::traits::{AsEncodedEntry, AsEntry};
+use p2panda_rs::entry::{EncodedEntry, Entry, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
 use p2panda_rs::operation::decode::decode_operation;
-use p2panda_rs::operation::traits::Schematic;
+use p2panda_rs::operation::traits::{Actionable, Schematic};
 use p2panda_rs::operation::{EncodedOperation, OperationId};
+use p2panda_rs::storage_provider::traits::EntryStore;
 
 use crate::bus::{ServiceMessage, ServiceSender};
 use crate::db::SqlStore;
 use crate::graphql::responses::NextArguments;
 use crate::graphql::scalars::{EncodedEntryScalar, EncodedOperationScalar};
+use crate::log_ids::{next_args, SharedLogIdPolicy};
 use crate::schema::SchemaProvider;
+use crate::status::NodeStatus;
+
+/// Error code returned when another entry was published at the same position of the log.
+pub const CONCURRENT_PUBLISH_ERROR: &str = "CONCURRENT_PUBLISH";
 
 /// GraphQL mutation root.
 #[derive(MutationRoot, Default, Debug, Copy, Clone)]
@@ -39,6 +47,8 @@ impl Publish {
         let store = ctx.data::<SqlStore>()?;
         let tx = ctx.data::<ServiceSender>()?;
         let schema_provider = ctx.data::<SchemaProvider>()?;
+        let log_id_policy = ctx.data::<SharedLogIdPolicy>()?;
+        let status = ctx.data::<NodeStatus>()?;
 
         let encoded_entry: EncodedEntry = entry.into();
         let encoded_operation: EncodedOperation = operation.into();
@@ -48,6 +58,7 @@ impl Publish {
             encoded_entry.hash()
         );
 
+        let entry = decode_entry(&encoded_entry)?;
         let operation = decode_operation(&encoded_operation)?;
 
         let schema = schema_provider
@@ -59,14 +70,37 @@ impl Publish {
         // PUBLISH THE ENTRY AND OPERATION //
         /////////////////////////////////////
 
-        let (backlink, skiplink, seq_num, log_id) = publish(
-            store,
-            &schema,
-            &encoded_entry,
-            &operation,
-            &encoded_operation,
-        )
-        .await?;
+        // Don't accept more entries while the materializer can't keep up with them
+        status.task_backlog().wait_for_capacity().await;
+
+        // Entries of the same author are published one after another
+        let _guard = store.lock_public_key(entry.public_key()).await;
+
+        let (backlink, skiplink, seq_num, log_id) = match store
+            .publish(&schema, &encoded_entry, &operation, &encoded_operation)
+            .await
+        {
+            Ok(next_args) => next_args,
+            Err(err) => {
+                let error = Error::new(err.to_string());
+                return Err(
+                    match is_concurrent_publish(store, &entry, &encoded_entry).await? {
+                        true => {
+                            let next_args = next_args(
+                                store,
+                                log_id_policy.as_ref(),
+                                entry.public_key(),
+                                operation.previous(),
+                                Some(operation.schema_id()),
+                            )
+                            .await?;
+                            concurrent_publish_error(error, next_args)
+                        }
+                        false => error,
+                    },
+                );
+            }
+        };
 
         ////////////////////////////////////////
         // SEND THE OPERATION TO MATERIALIZER //
@@ -91,9 +125,44 @@ impl Publish {
     }
 }
 
+/// Returns `true` if another entry was already published at the same position of the log.
+///
+/// This happens when a client published multiple entries based on the same arguments, for example
+/// because it requested `nextArgs` again before the previous entry arrived at the node.
+async fn is_concurrent_publish(
+    store: &SqlStore,
+    entry: &Entry,
+    encoded_entry: &EncodedEntry,
+) -> Result<bool> {
+    let existing_entry = store
+        .get_entry_at_seq_num(entry.public_key(), entry.log_id(), entry.seq_num())
+        .await?;
+
+    Ok(existing_entry.is_some_and(|existing_entry| existing_entry.hash() != encoded_entry.hash()))
+}
+
+/// Extends the error with the `CONCURRENT_PUBLISH` code and refreshed arguments for the entry.
+fn concurrent_publish_error(
+    error: Error,
+    (backlink, skiplink, seq_num, log_id): (Option<Hash>, Option<Hash>, SeqNum, LogId),
+) -> Error {
+    let next_args = value!({
+        "logId": log_id.as_u64().to_string(),
+        "seqNum": seq_num.as_u64().to_string(),
+        "backlink": backlink.map(|hash| hash.to_string()),
+        "skiplink": skiplink.map(|hash| hash.to_string()),
+    });
+
+    error.extend_with(|_, extensions| {
+        extensions.set("code", CONCURRENT_PUBLISH_ERROR);
+        extensions.set("nextArgs", next_args);
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
+    use std::time::Duration;
 
     use async_graphql::{value, Request, Variables};
     use ciborium::cbor;
@@ -121,7 +190,9 @@ mod tests {
     use rstest::{fixture, rstest};
     use serde_json::json;
     use tokio::sync::broadcast;
+    use tokio::time::timeout;
 
+    use crate::blobs::BlobUploads;
     use crate::bus::ServiceMessage;
     use crate::graphql::GraphQLSchemaManager;
     use crate::http::HttpServiceContext;
@@ -232,14 +303,21 @@ mod tests {
             let (tx, _rx) = broadcast::channel(120);
             let manager = GraphQLSchemaManager::new(
                 node.context.store.clone(),
-                tx,
+                tx.clone(),
                 node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
             )
             .await;
             let context = HttpServiceContext::new(
                 node.context.store.clone(),
                 manager,
-                node.context.config.blobs_base_path.to_path_buf(),
+                node.context.blobs.clone(),
+                node.context.blob_access.clone(),
+                node.context.blob_integrity.clone(),
+                BlobUploads::new(node.context.clone(), tx),
+                &node.context.config,
             );
 
             let response = context.schema.execute(publish_request).await;
@@ -258,6 +336,97 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn wait_for_materializer_backlog(
+        #[from(populate_store_config)]
+        #[with(0, 0, vec![], false, test_schema())]
+        config: PopulateStoreConfig,
+        publish_request: Request,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            // Adds the test_schema to the store and schema provider.
+            populate_and_materialize(&mut node, &config).await;
+
+            let (tx, _rx) = broadcast::channel(120);
+            let manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx.clone(),
+                node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
+
+            // The materializer is busy with as many tasks as we allow
+            let backlog = node.context.status.task_backlog();
+            backlog.set_limit(1);
+            backlog.push();
+
+            let mut response = Box::pin(manager.execute(publish_request));
+            assert!(timeout(Duration::from_millis(50), &mut response)
+                .await
+                .is_err());
+
+            // Publishing continues as soon as the materializer caught up
+            backlog.pop();
+            let response = response.await;
+            assert!(response.errors.is_empty());
+            assert_eq!(
+                response.data.into_json().unwrap()["publish"]["seqNum"],
+                json!("2")
+            );
+        });
+    }
+
+    #[rstest]
+    fn cancel_publish_waiting_for_materializer_backlog(
+        #[from(populate_store_config)]
+        #[with(0, 0, vec![], false, test_schema())]
+        config: PopulateStoreConfig,
+        publish_request: Request,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            // Adds the test_schema to the store and schema provider.
+            populate_and_materialize(&mut node, &config).await;
+
+            let (tx, _rx) = broadcast::channel(120);
+            let manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx.clone(),
+                node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
+
+            let backlog = node.context.status.task_backlog();
+            backlog.set_limit(1);
+            backlog.push();
+
+            // Request gets dropped while waiting, for example because the query timeout was
+            // reached or the client disconnected
+            assert!(
+                timeout(Duration::from_millis(50), manager.execute(publish_request))
+                    .await
+                    .is_err()
+            );
+
+            // Entry does not get published after the materializer caught up
+            backlog.pop();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let entry_encoded = EncodedEntry::from_bytes(&ENTRY_ENCODED);
+            assert!(node
+                .context
+                .store
+                .get_entry(&entry_encoded.hash())
+                .await
+                .unwrap()
+                .is_none());
+        });
+    }
+
     #[rstest]
     fn publish_entry_with_empty_relation_list(
         #[from(populate_store_config)]
@@ -297,14 +466,21 @@ mod tests {
             let (tx, _rx) = broadcast::channel(120);
             let manager = GraphQLSchemaManager::new(
                 node.context.store.clone(),
-                tx,
+                tx.clone(),
                 node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
             )
             .await;
             let context = HttpServiceContext::new(
                 node.context.store.clone(),
                 manager,
-                node.context.config.blobs_base_path.to_path_buf(),
+                node.context.blobs.clone(),
+                node.context.blob_access.clone(),
+                node.context.blob_integrity.clone(),
+                BlobUploads::new(node.context.clone(), tx),
+                &node.context.config,
             );
 
             let response = context
@@ -329,14 +505,21 @@ mod tests {
             let (tx, mut rx) = broadcast::channel(120);
             let manager = GraphQLSchemaManager::new(
                 node.context.store.clone(),
-                tx,
+                tx.clone(),
                 node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
             )
             .await;
             let context = HttpServiceContext::new(
                 node.context.store.clone(),
                 manager,
-                node.context.config.blobs_base_path.to_path_buf(),
+                node.context.blobs.clone(),
+                node.context.blob_access.clone(),
+                node.context.blob_integrity.clone(),
+                BlobUploads::new(node.context.clone(), tx),
+                &node.context.config,
             );
 
             context.schema.execute(publish_request).await;
@@ -909,4 +1092,76 @@ mod tests {
             }
         });
     }
+
+    #[rstest]
+    fn publish_concurrently(
+        #[from(populate_store_config)]
+        #[with(0, 0, vec![], false, doggo_schema())]
+        config: PopulateStoreConfig,
+        key_pair: KeyPair,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            // Adds the test_schema to the store and schema provider.
+            populate_and_materialize(&mut node, &config).await;
+
+            let client = http_test_client(&node).await;
+
+            // Two entries which were created with the same arguments
+            let (_, _, seq_num, log_id) =
+                next_args(&node.context.store, &key_pair.public_key(), None)
+                    .await
+                    .unwrap();
+            let requests: Vec<Request> = vec!["Gilberto", "Gertrude"]
+                .into_iter()
+                .map(|name| {
+                    let mut fields = doggo_fields();
+                    fields[0] = ("username", OperationValue::String(name.to_string()));
+                    let operation = create_operation(fields, doggo_schema().id().to_owned());
+                    let encoded_operation = encode_operation(&operation).unwrap();
+                    let encoded_entry = sign_and_encode_entry(
+                        &log_id,
+                        &seq_num,
+                        None,
+                        None,
+                        &encoded_operation,
+                        &key_pair,
+                    )
+                    .unwrap();
+                    publish_request(&encoded_entry.to_string(), &encoded_operation.to_string())
+                })
+                .collect();
+
+            let publish = |request: &Request| {
+                client
+                    .post("/graphql")
+                    .json(&json!({
+                        "query": request.query,
+                        "variables": request.variables
+                    }))
+                    .send()
+            };
+            let (first, second) = tokio::join!(publish(&requests[0]), publish(&requests[1]));
+            let first = first.json::<serde_json::Value>().await;
+            let second = second.json::<serde_json::Value>().await;
+
+            // Only one of both entries can be published, the other receives refreshed arguments
+            let errors: Vec<serde_json::Value> = vec![first, second]
+                .into_iter()
+                .filter_map(|response| response.get("errors").cloned())
+                .collect();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(
+                errors[0][0]["extensions"],
+                json!({
+                    "code": "CONCURRENT_PUBLISH",
+                    "nextArgs": {
+                        "logId": "1",
+                        "seqNum": "1",
+                        "backlink": null,
+                        "skiplink": null,
+                    }
+                })
+            );
+        });
+    }
 }
@@ -0,0 +1,391 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::Error;
+use dynamic_graphql::{Context, InputObject, Mutation, MutationFields, Result};
+use log::{debug, warn};
+use p2panda_rs::entry::decode::decode_entry;
+use p2panda_rs::entry::traits::{AsEncodedEntry, AsEntry};
+use p2panda_rs::entry::EncodedEntry;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::operation::decode::decode_operation;
+use p2panda_rs::operation::traits::Schematic;
+use p2panda_rs::operation::EncodedOperation;
+
+use crate::bus::{ServiceMessage, ServiceSender};
+use crate::db::SqlStore;
+use crate::graphql::mutations::MutationRoot;
+use crate::graphql::responses::NextArguments;
+use crate::graphql::scalars::{EncodedEntryScalar, EncodedOperationScalar};
+use crate::schema::SchemaProvider;
+use crate::status::NodeStatus;
+
+/// Entry and its operation to publish with the `publishMany` mutation.
+#[derive(InputObject)]
+#[graphql(name = "PublishInput")]
+pub struct PublishInput {
+    /// Signed and encoded entry to publish.
+    entry: EncodedEntryScalar,
+
+    /// p2panda operation representing the entry payload.
+    operation: EncodedOperationScalar,
+}
+
+/// Maximum number of entries accepted by one "publishMany" request.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxBatchEntries(pub usize);
+
+/// GraphQL "publishMany" mutation.
+#[derive(Mutation, Default, Debug, Copy, Clone)]
+pub struct PublishMany(MutationRoot);
+
+#[MutationFields]
+impl PublishMany {
+    /// Publish many entries at once, for example when importing larger datasets.
+    ///
+    /// Entries are published in the given order, an entry can therefore depend on entries which
+    /// come before it in the list. Either all entries are published or none of them: if one
+    /// entry fails to validate, all previous entries of the list get removed again.
+    ///
+    /// Entries are inserted one after another, until the whole batch was published earlier
+    /// entries are already visible to queries and replication. The number of entries per request
+    /// is limited by the `max_batch_entries` setting of the node.
+    ///
+    /// Returns arguments for publishing the next entry in the same log for every entry.
+    #[graphql(name = "publishMany")]
+    async fn publish_many(
+        ctx: &Context<'_>,
+        // List of signed and encoded entries with their operations
+        entries: Vec<PublishInput>,
+    ) -> Result<Vec<NextArguments>> {
+        let store = ctx.data::<SqlStore>()?;
+        let tx = ctx.data::<ServiceSender>()?;
+        let schema_provider = ctx.data::<SchemaProvider>()?;
+        let status = ctx.data::<NodeStatus>()?;
+        let max_batch_entries = ctx.data::<MaxBatchEntries>()?;
+
+        debug!(
+            "Query to publishMany received containing {} entries",
+            entries.len()
+        );
+
+        if entries.len() > max_batch_entries.0 {
+            return Err(Error::new(format!(
+                "Batch contains {} entries, at most {} are allowed",
+                entries.len(),
+                max_batch_entries.0
+            )));
+        }
+
+        // Decode all entries and look up their schemas before anything gets inserted
+        let mut batch = Vec::with_capacity(entries.len());
+        for (index, input) in entries.into_iter().enumerate() {
+            let encoded_entry: EncodedEntry = input.entry.into();
+            let encoded_operation: EncodedOperation = input.operation.into();
+
+            let entry = decode_entry(&encoded_entry).map_err(|err| batch_error(index, err))?;
+            let operation =
+                decode_operation(&encoded_operation).map_err(|err| batch_error(index, err))?;
+            let schema = schema_provider
+                .get(operation.schema_id())
+                .await
+                .ok_or_else(|| batch_error(index, "Schema not found"))?;
+
+            batch.push((entry, encoded_entry, operation, encoded_operation, schema));
+        }
+
+        // Don't accept more entries while the materializer can't keep up with them
+        status.task_backlog().wait_for_capacity().await;
+
+        // Entries of the same author are published one after another. Locks are acquired in a
+        // fixed order to not deadlock with other batches
+        let mut public_keys: Vec<_> = batch
+            .iter()
+            .map(|(entry, ..)| entry.public_key().to_owned())
+            .collect();
+        public_keys.sort_by_key(|public_key| public_key.to_string());
+        public_keys.dedup();
+
+        let mut _guards = Vec::with_capacity(public_keys.len());
+        for public_key in &public_keys {
+            _guards.push(store.lock_public_key(public_key).await);
+        }
+
+        ////////////////////////////////////////
+        // PUBLISH THE ENTRIES AND OPERATIONS //
+        ////////////////////////////////////////
+
+        let mut published: Vec<Hash> = Vec::with_capacity(batch.len());
+        let mut results = Vec::with_capacity(batch.len());
+        for (index, (_, encoded_entry, operation, encoded_operation, schema)) in
+            batch.iter().enumerate()
+        {
+            match store
+                .publish(schema, encoded_entry, operation, encoded_operation)
+                .await
+            {
+                Ok((backlink, skiplink, seq_num, log_id)) => {
+                    published.push(encoded_entry.hash());
+                    results.push(NextArguments {
+                        log_id: log_id.into(),
+                        seq_num: seq_num.into(),
+                        backlink: backlink.map(|hash| hash.into()),
+                        skiplink: skiplink.map(|hash| hash.into()),
+                    });
+                }
+                Err(err) => {
+                    // Remove all entries of this batch again together with their tasks, they have
+                    // not been sent to the materializer yet
+                    if let Err(err) = store.unpublish(&published).await {
+                        warn!("Failed removing entries of rejected batch: {}", err);
+                    }
+
+                    return Err(batch_error(index, err));
+                }
+            }
+        }
+
+        /////////////////////////////////////////
+        // SEND THE OPERATIONS TO MATERIALIZER //
+        /////////////////////////////////////////
+
+        for hash in published {
+            if tx.send(ServiceMessage::NewOperation(hash.into())).is_err() {
+                // Silently fail here as we don't mind if there are no subscribers. We have
+                // tests in other places to check if messages arrive.
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Returns an error mentioning the position of the failed entry in the batch.
+fn batch_error(index: usize, err: impl ToString) -> Error {
+    Error::new(format!(
+        "Entry at index {} could not be published: {}",
+        index,
+        err.to_string()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::entry::encode::sign_and_encode_entry;
+    use p2panda_rs::entry::traits::AsEncodedEntry;
+    use p2panda_rs::entry::{EncodedEntry, LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::encode::encode_operation;
+    use p2panda_rs::operation::{EncodedOperation, OperationAction, OperationBuilder};
+    use p2panda_rs::schema::{FieldType, Schema};
+    use p2panda_rs::storage_provider::traits::EntryStore;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use crate::context::Context;
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{add_schema, http_test_client, test_runner, TestNode};
+
+    // Query string for a publishMany request.
+    const PUBLISH_MANY_QUERY: &str = r#"
+        mutation TestPublishMany($entries: [PublishInput!]!) {
+            publishMany(entries: $entries) {
+                logId,
+                seqNum,
+                backlink
+            }
+        }"#;
+
+    /// Creates a document and updates it with the given messages, returning all entries.
+    fn create_and_update(
+        schema: &Schema,
+        messages: &[&str],
+        key_pair: &KeyPair,
+    ) -> Vec<(EncodedEntry, EncodedOperation)> {
+        let mut entries: Vec<(EncodedEntry, EncodedOperation)> = Vec::new();
+
+        for (index, message) in messages.iter().enumerate() {
+            let builder =
+                OperationBuilder::new(schema.id()).fields(&[("message", (*message).into())]);
+            let operation = match entries.last() {
+                Some((previous, _)) => builder
+                    .action(OperationAction::Update)
+                    .previous(&previous.hash().into()),
+                None => builder,
+            }
+            .build()
+            .unwrap();
+            let encoded_operation = encode_operation(&operation).unwrap();
+
+            let backlink = entries.last().map(|(entry, _)| entry.hash());
+            let encoded_entry = sign_and_encode_entry(
+                &LogId::default(),
+                &SeqNum::new(index as u64 + 1).unwrap(),
+                None,
+                backlink.as_ref(),
+                &encoded_operation,
+                key_pair,
+            )
+            .unwrap();
+
+            entries.push((encoded_entry, encoded_operation));
+        }
+
+        entries
+    }
+
+    fn variables(entries: &[(EncodedEntry, EncodedOperation)]) -> Value {
+        json!({
+            "entries": entries
+                .iter()
+                .map(|(entry, operation)| json!({
+                    "entry": entry.to_string(),
+                    "operation": operation.to_string(),
+                }))
+                .collect::<Vec<Value>>()
+        })
+    }
+
+    #[rstest]
+    fn publish_batch(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "messages",
+                vec![("message", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let entries = create_and_update(&schema, &["one", "two", "three"], &KeyPair::new());
+
+            let client = http_test_client(&node).await;
+            let response: Response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": PUBLISH_MANY_QUERY,
+                    "variables": variables(&entries),
+                }))
+                .send()
+                .await
+                .json()
+                .await;
+
+            assert!(response.is_ok(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "publishMany": [
+                        { "logId": "0", "seqNum": "2", "backlink": entries[0].0.hash().to_string() },
+                        { "logId": "0", "seqNum": "3", "backlink": entries[1].0.hash().to_string() },
+                        { "logId": "0", "seqNum": "4", "backlink": entries[2].0.hash().to_string() },
+                    ]
+                })
+            );
+
+            for (entry, _) in &entries {
+                assert!(node
+                    .context
+                    .store
+                    .get_entry(&entry.hash())
+                    .await
+                    .unwrap()
+                    .is_some());
+            }
+        });
+    }
+
+    #[rstest]
+    fn reject_whole_batch(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "messages",
+                vec![("message", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            // The last entry is invalid as it skips a sequence number
+            let mut entries = create_and_update(&schema, &["one", "two", "three"], &KeyPair::new());
+            entries.remove(1);
+
+            let client = http_test_client(&node).await;
+            let response: Response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": PUBLISH_MANY_QUERY,
+                    "variables": variables(&entries),
+                }))
+                .send()
+                .await
+                .json()
+                .await;
+
+            assert!(response.errors[0]
+                .message
+                .starts_with("Entry at index 1 could not be published"));
+
+            // The first entry got removed again together with its task
+            let hash: Hash = entries[0].0.hash();
+            assert!(node.context.store.get_entry(&hash).await.unwrap().is_none());
+
+            let document_id: DocumentId = hash.into();
+            let task = Task::new("reduce", TaskInput::DocumentId(document_id));
+            assert!(!node
+                .context
+                .store
+                .get_tasks()
+                .await
+                .unwrap()
+                .contains(&task));
+        });
+    }
+
+    #[rstest]
+    fn reject_too_long_batch(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.max_batch_entries = 2;
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let schema = add_schema(
+                &mut node,
+                "messages",
+                vec![("message", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let entries = create_and_update(&schema, &["one", "two", "three"], &KeyPair::new());
+
+            let client = http_test_client(&node).await;
+            let response: Response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": PUBLISH_MANY_QUERY,
+                    "variables": variables(&entries),
+                }))
+                .send()
+                .await
+                .json()
+                .await;
+
+            assert_eq!(
+                response.errors[0].message,
+                "Batch contains 3 entries, at most 2 are allowed"
+            );
+
+            let hash: Hash = entries[0].0.hash();
+            assert!(node.context.store.get_entry(&hash).await.unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, ResolverContext, TypeRef};
+use async_graphql::Value;
+use p2panda_rs::schema::{FieldName, FieldType, Schema};
+
+use crate::db::types::{Aggregates, FieldAggregate};
+use crate::graphql::constants;
+use crate::graphql::utils::{aggregate_fields_name, aggregate_name};
+
+/// Aggregated values of one kind (for example all minimum values) keyed by field name.
+type AggregateValues = HashMap<FieldName, Option<f64>>;
+
+/// Function picking one aggregated value of a field.
+type AggregateFn = fn(&FieldAggregate) -> Option<f64>;
+
+/// Dynamically build the object containing the aggregated values of a collection of documents.
+///
+/// Each object contains a `count` field. For schemas with integer or float fields it also
+/// contains `min`, `max`, `avg` and `sum` fields, each of them holding an object with the
+/// aggregated values of all numeric fields.
+///
+/// Each generated object has a type name with the formatting `<schema_id>Aggregate`.
+pub fn build_aggregate_object(schema: &Schema) -> Object {
+    let mut object = Object::new(aggregate_name(schema.id()))
+        .field(
+            Field::new(
+                constants::COUNT_FIELD,
+                TypeRef::named_nn(TypeRef::INT),
+                |ctx| {
+                    FieldFuture::new(async move {
+                        let aggregates = downcast_aggregates(&ctx);
+                        Ok(Some(FieldValue::value(aggregates.count)))
+                    })
+                },
+            )
+            .description("The number of documents matching the filter."),
+        )
+        .description(format!(
+            "Aggregated values of all `{}` documents matching the filter.",
+            schema.id().name()
+        ));
+
+    if !has_numeric_fields(schema) {
+        return object;
+    }
+
+    let aggregates: [(&str, AggregateFn, &str); 4] = [
+        (
+            constants::MIN_FIELD,
+            |aggregate| aggregate.min,
+            "Smallest value of every numeric field.",
+        ),
+        (
+            constants::MAX_FIELD,
+            |aggregate| aggregate.max,
+            "Largest value of every numeric field.",
+        ),
+        (
+            constants::AVG_FIELD,
+            |aggregate| aggregate.avg,
+            "Average value of every numeric field.",
+        ),
+        (
+            constants::SUM_FIELD,
+            |aggregate| aggregate.sum,
+            "Sum of values of every numeric field.",
+        ),
+    ];
+
+    for (name, value_fn, description) in aggregates {
+        object = object.field(
+            Field::new(
+                name,
+                TypeRef::named_nn(aggregate_fields_name(schema.id())),
+                move |ctx| {
+                    FieldFuture::new(async move {
+                        let aggregates = downcast_aggregates(&ctx);
+
+                        let values: AggregateValues = aggregates
+                            .fields
+                            .iter()
+                            .map(|(field_name, aggregate)| {
+                                (field_name.to_owned(), value_fn(aggregate))
+                            })
+                            .collect();
+
+                        Ok(Some(FieldValue::owned_any(values)))
+                    })
+                },
+            )
+            .description(description),
+        );
+    }
+
+    object
+}
+
+/// Dynamically build the object containing one aggregated value for every integer and float field
+/// of a schema.
+///
+/// Returns `None` if the schema does not contain any numeric fields.
+///
+/// Each generated object has a type name with the formatting `<schema_id>AggregateFields`.
+pub fn build_aggregate_fields_object(schema: &Schema) -> Option<Object> {
+    if !has_numeric_fields(schema) {
+        return None;
+    }
+
+    let mut object = Object::new(aggregate_fields_name(schema.id())).description(format!(
+        "Aggregated values of the numeric fields of `{}` documents. Values are null if no \
+        document holds a value for the field.",
+        schema.id().name()
+    ));
+
+    for (field_name, field_type) in schema.fields().iter() {
+        if !is_numeric(field_type) {
+            continue;
+        }
+
+        object = object.field(Field::new(
+            field_name,
+            TypeRef::named(TypeRef::FLOAT),
+            move |ctx| {
+                FieldFuture::new(async move {
+                    let values = ctx
+                        .parent_value
+                        .downcast_ref::<AggregateValues>()
+                        .expect("Values passed from aggregate query");
+
+                    let value = match values.get(ctx.field().name()) {
+                        Some(Some(value)) => Value::from(*value),
+                        _ => Value::Null,
+                    };
+
+                    Ok(Some(FieldValue::value(value)))
+                })
+            },
+        ));
+    }
+
+    Some(object)
+}
+
+/// Get the aggregates passed down from the query resolver.
+fn downcast_aggregates<'a>(ctx: &'a ResolverContext) -> &'a Aggregates {
+    ctx.parent_value
+        .downcast_ref::<Aggregates>()
+        .expect("Aggregates passed from aggregate query")
+}
+
+/// Returns true if values of this field type can be aggregated.
+fn is_numeric(field_type: &FieldType) -> bool {
+    matches!(field_type, FieldType::Integer | FieldType::Float)
+}
+
+/// Returns true if the schema contains any integer or float fields.
+fn has_numeric_fields(schema: &Schema) -> bool {
+    schema
+        .fields()
+        .iter()
+        .any(|(_, field_type)| is_numeric(field_type))
+}
@@ -1,23 +1,28 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, TypeRef};
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, TypeRef, Union};
 use async_graphql::Value;
 use p2panda_rs::schema::Schema;
 
 use crate::db::query::Cursor;
+use crate::db::SqlStore;
 use crate::graphql::constants;
 use crate::graphql::resolvers::{resolve_document_meta, Resolved};
-use crate::graphql::utils::{collection_item_name, fields_name};
+use crate::graphql::utils::{
+    collection_item_name, fields_name, referenced_by_name, referencing_schemas,
+};
 
 /// Dynamically build GraphQL objects describing documents which conform to the shape of a p2panda
 /// schema.
 ///
 /// Constructs resolvers for both `fields` and `meta` fields. The former simply passes up the query
 /// arguments to its children query fields. The latter calls the `resolve` method defined on
-/// `DocumentMeta` type.
-pub fn build_document_object(schema: &Schema) -> Object {
+/// `DocumentMeta` type. Documents of other schemas relating to this document can be retrieved
+/// via `referencedBy_<schema_id>` fields, they are added for every passed schema with relation
+/// fields pointing at this schema.
+pub fn build_document_object(schema: &Schema, schemas: &[Schema]) -> Object {
     let fields = Object::new(schema.id().to_string());
-    with_document_fields(fields, schema)
+    with_document_fields(fields, schema, schemas)
 }
 
 /// Dynamically build GraphQL objects describing documents which conform to the shape of a p2panda
@@ -26,10 +31,10 @@ pub fn build_document_object(schema: &Schema) -> Object {
 /// Contains resolvers for `cursor`, `fields` and `meta`. `fields` simply passes up the query
 /// arguments to its children query fields. `meta` calls the `resolve` method defined on
 /// `DocumentMeta` type.
-pub fn build_paginated_document_object(schema: &Schema) -> Object {
+pub fn build_paginated_document_object(schema: &Schema, schemas: &[Schema]) -> Object {
     let fields = Object::new(collection_item_name(schema.id()));
 
-    with_document_fields(fields, schema).field(
+    with_document_fields(fields, schema, schemas).field(
         Field::new(
             constants::CURSOR_FIELD,
             TypeRef::named(TypeRef::STRING),
@@ -53,8 +58,25 @@ pub fn build_paginated_document_object(schema: &Schema) -> Object {
     )
 }
 
-/// Add application `fields` and `meta` fields to a GraphQL object.
-fn with_document_fields(fields: Object, schema: &Schema) -> Object {
+/// Dynamically build a GraphQL union of the document objects of all passed schemas.
+///
+/// Queries returning documents of different schemas use this type, clients can select fields of
+/// a specific schema with inline fragments.
+pub fn build_document_union(schemas: &[Schema]) -> Union {
+    schemas
+        .iter()
+        .fold(Union::new(constants::DOCUMENT), |union, schema| {
+            union.possible_type(schema.id().to_string())
+        })
+        .description("A document of any schema.")
+}
+
+/// Add application `fields`, `meta` and reverse relation fields to a GraphQL object.
+fn with_document_fields(fields: Object, schema: &Schema, schemas: &[Schema]) -> Object {
+    let fields = referencing_schemas(schema.id(), schemas).fold(fields, |fields, referencing| {
+        with_referenced_by_field(fields, schema, referencing)
+    });
+
     fields
         // The `fields` field passes down the parent value to its children
         .field(
@@ -67,6 +89,16 @@ fn with_document_fields(fields: Object, schema: &Schema) -> Object {
                         // the root query resolver and passed down to the `fields` field here
                         let document = Resolved::downcast(&ctx);
 
+                        // Deleted documents don't have any field values left
+                        let is_deleted = match &document {
+                            Resolved::Document(document)
+                            | Resolved::CollectionDocument(_, document) => document.deleted,
+                            Resolved::Collection(_, _) => false,
+                        };
+                        if is_deleted {
+                            return Ok(FieldValue::NONE);
+                        }
+
                         // We continue to pass it down to all the fields' children
                         Ok(Some(FieldValue::owned_any(document)))
                     })
@@ -91,3 +123,41 @@ fn with_document_fields(fields: Object, schema: &Schema) -> Object {
         )
         .description(schema.description().to_string())
 }
+
+/// Add a field returning all documents of the referencing schema which relate to this document.
+fn with_referenced_by_field(fields: Object, schema: &Schema, referencing: &Schema) -> Object {
+    let referencing_schema_id = referencing.id().clone();
+
+    fields.field(
+        Field::new(
+            referenced_by_name(referencing.id()),
+            TypeRef::named_nn_list_nn(referencing.id().to_string()),
+            move |ctx| {
+                let schema_id = referencing_schema_id.clone();
+
+                FieldFuture::new(async move {
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    let document_id = match Resolved::downcast(&ctx) {
+                        Resolved::Document(document)
+                        | Resolved::CollectionDocument(_, document) => document.id,
+                        Resolved::Collection(_, _) => panic!("Single document expected"),
+                    };
+
+                    let documents = store
+                        .get_referencing_documents(&schema_id, &document_id)
+                        .await?
+                        .into_iter()
+                        .map(|document| FieldValue::owned_any(Resolved::Document(document)));
+
+                    Ok(Some(FieldValue::list(documents)))
+                })
+            },
+        )
+        .description(format!(
+            "`{}` documents with relation fields pointing at this `{}` document.",
+            referencing.id().name(),
+            schema.id().name()
+        )),
+    )
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, ResolverContext, TypeRef};
+use p2panda_rs::schema::Schema;
+
+use crate::db::types::DocumentChange;
+use crate::graphql::constants;
+use crate::graphql::resolvers::Resolved;
+use crate::graphql::utils::change_name;
+
+/// Dynamically build the object delivered by document change subscriptions.
+///
+/// Each object contains a `cursor` field and a `document` field holding the changed document.
+/// Deleted documents are delivered without their fields.
+///
+/// Each generated object has a type name with the formatting `<schema_id>Change`.
+pub fn build_document_change_object(schema: &Schema) -> Object {
+    Object::new(change_name(schema.id()))
+        .field(
+            Field::new(
+                constants::CURSOR_FIELD,
+                TypeRef::named_nn(TypeRef::STRING),
+                |ctx| {
+                    FieldFuture::new(async move {
+                        let change = downcast_change(&ctx);
+                        Ok(Some(FieldValue::value(change.change_seq.to_string())))
+                    })
+                },
+            )
+            .description(
+                "Position of this change, pass it as `since` argument when subscribing again to \
+                continue right after it.",
+            ),
+        )
+        .field(
+            Field::new(
+                constants::DOCUMENT_FIELD,
+                TypeRef::named_nn(schema.id().to_string()),
+                |ctx| {
+                    FieldFuture::new(async move {
+                        let change = downcast_change(&ctx);
+                        let document = Resolved::Document(change.document.clone());
+                        Ok(Some(FieldValue::owned_any(document)))
+                    })
+                },
+            )
+            .description("The document in its state after the change."),
+        )
+        .description(format!(
+            "Change of the current view of a `{}` document.",
+            schema.id().name()
+        ))
+}
+
+/// Downcast the document change passed up by the subscription.
+fn downcast_change<'a>(ctx: &'a ResolverContext) -> &'a DocumentChange {
+    ctx.parent_value
+        .downcast_ref::<DocumentChange>()
+        .expect("Document change passed from subscription")
+}
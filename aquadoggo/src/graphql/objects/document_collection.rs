@@ -7,12 +7,13 @@ use p2panda_rs::schema::Schema;
 use crate::db::query::Cursor;
 use crate::graphql::constants;
 use crate::graphql::resolvers::Resolved;
+use crate::graphql::responses::PageInfoResponse;
 use crate::graphql::utils::{collection_item_name, collection_name};
 
 /// Dynamically build objects describing a paginated collection of documents.
 ///
-/// Each object contains `documents`, `totalCount` and `hasNextPage` fields and defines their
-/// resolution logic.
+/// Each object contains `documents`, `totalCount`, `hasNextPage`, `endCursor` and `pageInfo`
+/// fields and defines their resolution logic.
 ///
 /// Each generated object has a type name with the formatting `<schema_id>Collection`.
 pub fn build_document_collection_object(schema: &Schema) -> Object {
@@ -83,6 +84,27 @@ pub fn build_document_collection_object(schema: &Schema) -> Object {
                 "Boolean value denoting whether there is a next page available on this query.",
             ),
         )
+        .field(
+            Field::new(
+                constants::PAGE_INFO_FIELD,
+                TypeRef::named_nn(constants::PAGE_INFO),
+                move |ctx| {
+                    FieldFuture::new(async move {
+                        let collection = Resolved::downcast(&ctx);
+
+                        let page_info = match collection {
+                            Resolved::Collection(page_info, _) => page_info,
+                            _ => panic!("Expected document collection"),
+                        };
+
+                        Ok(Some(FieldValue::owned_any(PageInfoResponse::from(
+                            page_info,
+                        ))))
+                    })
+                },
+            )
+            .description("Information about the current page to paginate through the collection."),
+        )
         .field(
             Field::new(
                 constants::DOCUMENTS_FIELD,
@@ -114,7 +136,8 @@ pub fn build_document_collection_object(schema: &Schema) -> Object {
             .description("Field containing the actual document fields."),
         )
         .description(format!(
-            "A single page response returned when querying a collection of `{}` documents.",
-            schema.id().name()
+            "A single page response returned when querying a collection of `{}` documents: {}",
+            schema.id().name(),
+            schema.description()
         ))
 }
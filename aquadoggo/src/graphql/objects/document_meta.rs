@@ -1,8 +1,16 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use dynamic_graphql::SimpleObject;
+use dynamic_graphql::{Context, ExpandObject, ExpandObjectFields, Result, SimpleObject};
+use log::debug;
+use p2panda_rs::document::{DocumentId, DocumentViewId};
+use p2panda_rs::hash::{Hash, HashId};
+use p2panda_rs::identity::PublicKey;
 
-use crate::graphql::scalars::{DocumentIdScalar, DocumentViewIdScalar, PublicKeyScalar};
+use crate::db::types::StorageDocumentView;
+use crate::db::SqlStore;
+use crate::graphql::scalars::{
+    DateTimeScalar, DocumentIdScalar, DocumentViewIdScalar, EntryHashScalar, PublicKeyScalar,
+};
 
 /// Meta fields of a document, contains id and authorship information.
 #[derive(SimpleObject)]
@@ -17,4 +25,135 @@ pub struct DocumentMeta {
 
     /// The public key of the author who first created this document.
     pub owner: PublicKeyScalar,
+
+    /// Flag indicating if this document was deleted.
+    pub deleted: bool,
+}
+
+/// Adds the `history` field to the document meta fields.
+#[derive(ExpandObject)]
+pub struct DocumentMetaHistory<'a>(&'a DocumentMeta);
+
+#[ExpandObjectFields]
+impl DocumentMetaHistory<'_> {
+    /// All views of this document which are materialised on this node.
+    async fn history(&self, ctx: &Context<'_>) -> Result<Vec<DocumentHistoryItem>> {
+        let store = ctx.data::<SqlStore>()?;
+
+        let document_id = DocumentId::from(&self.0.document_id);
+        debug!("Query to history received for document {}", document_id);
+
+        let views = store.get_document_views(&document_id).await?;
+        Ok(views.into_iter().map(DocumentHistoryItem::from).collect())
+    }
+}
+
+/// Adds the `branches` field to the document meta fields.
+#[derive(ExpandObject)]
+pub struct DocumentMetaBranches<'a>(&'a DocumentMeta);
+
+#[ExpandObjectFields]
+impl DocumentMetaBranches<'_> {
+    /// Ids of the latest operation of every branch of this document, more than one id indicates
+    /// concurrent edits which were not merged yet by a later operation.
+    async fn branches(&self, ctx: &Context<'_>) -> Result<Vec<EntryHashScalar>> {
+        let store = ctx.data::<SqlStore>()?;
+
+        let document_id = DocumentId::from(&self.0.document_id);
+        debug!("Query to branches received for document {}", document_id);
+
+        let tips = store.get_operation_graph_tips(&document_id).await?;
+        Ok(tips
+            .iter()
+            .map(|operation_id| operation_id.as_hash().to_owned().into())
+            .collect())
+    }
+}
+
+/// Adds the `createdAt` and `updatedAt` fields to the document meta fields.
+///
+/// Entries do not contain a timestamp, the values are derived from the time this node stored the
+/// entries of the document. They are empty for entries stored before the node kept track of it.
+#[derive(ExpandObject)]
+pub struct DocumentMetaTimestamps<'a>(&'a DocumentMeta);
+
+#[ExpandObjectFields]
+impl DocumentMetaTimestamps<'_> {
+    /// Time when the entry creating this document was stored by this node.
+    #[graphql(name = "createdAt")]
+    async fn created_at(&self, ctx: &Context<'_>) -> Result<Option<DateTimeScalar>> {
+        let store = ctx.data::<SqlStore>()?;
+
+        let document_id = DocumentId::from(&self.0.document_id);
+        let received_at = store
+            .get_received_at(&[document_id.as_hash().to_owned()])
+            .await?;
+
+        received_at
+            .map(DateTimeScalar::from_unix_timestamp)
+            .transpose()
+    }
+
+    /// Time when the latest entry of this document view was stored by this node.
+    #[graphql(name = "updatedAt")]
+    async fn updated_at(&self, ctx: &Context<'_>) -> Result<Option<DateTimeScalar>> {
+        let store = ctx.data::<SqlStore>()?;
+
+        let document_view_id = DocumentViewId::from(self.0.document_view_id.clone());
+        let operation_ids: Vec<Hash> = document_view_id
+            .iter()
+            .map(|operation_id| operation_id.as_hash().to_owned())
+            .collect();
+        let received_at = store.get_received_at(&operation_ids).await?;
+
+        received_at
+            .map(DateTimeScalar::from_unix_timestamp)
+            .transpose()
+    }
+}
+
+/// Adds the `ownerName` field to the document meta fields.
+#[derive(ExpandObject)]
+pub struct DocumentMetaOwnerName<'a>(&'a DocumentMeta);
+
+#[ExpandObjectFields]
+impl DocumentMetaOwnerName<'_> {
+    /// Name claimed by the owner of this document, empty when no name claims are configured on
+    /// this node or the owner did not claim any name.
+    #[graphql(name = "ownerName")]
+    async fn owner_name(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        let store = ctx.data::<SqlStore>()?;
+
+        let public_key = PublicKey::from(self.0.owner);
+        Ok(store.get_claimed_name(&public_key).await?)
+    }
+}
+
+/// A materialised view of a document.
+#[derive(SimpleObject)]
+pub struct DocumentHistoryItem {
+    /// The document view id of this view.
+    #[graphql(name = "viewId")]
+    pub document_view_id: DocumentViewIdScalar,
+
+    /// Ids of the operations holding the field values of this view.
+    pub operations: Vec<EntryHashScalar>,
+
+    /// Flag indicating if this is the current view of the document.
+    #[graphql(name = "isCurrent")]
+    pub is_current: bool,
+}
+
+impl From<StorageDocumentView> for DocumentHistoryItem {
+    fn from(view: StorageDocumentView) -> Self {
+        Self {
+            document_view_id: (&view.id).into(),
+            operations: view
+                .operations
+                .iter()
+                .map(|operation_id| operation_id.as_hash().to_owned().into())
+                .collect(),
+            is_current: view.is_current,
+        }
+    }
 }
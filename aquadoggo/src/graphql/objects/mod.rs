@@ -1,11 +1,20 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod aggregate;
 mod document;
+mod document_change;
 mod document_collection;
 mod document_fields;
 mod document_meta;
+mod projection;
 
-pub use document::{build_document_object, build_paginated_document_object};
+pub use aggregate::{build_aggregate_fields_object, build_aggregate_object};
+pub use document::{build_document_object, build_document_union, build_paginated_document_object};
+pub use document_change::build_document_change_object;
 pub use document_collection::build_document_collection_object;
 pub use document_fields::build_document_fields_object;
-pub use document_meta::DocumentMeta;
+pub use document_meta::{
+    DocumentHistoryItem, DocumentMeta, DocumentMetaBranches, DocumentMetaHistory,
+    DocumentMetaOwnerName, DocumentMetaTimestamps,
+};
+pub use projection::build_projection_object;
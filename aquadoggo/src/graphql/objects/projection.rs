@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, ResolverContext, TypeRef};
+use async_graphql::Value;
+use p2panda_rs::schema::{FieldType, Schema, SchemaId};
+
+use crate::db::types::ProjectionRow;
+use crate::graphql::constants;
+use crate::graphql::utils::projection_name;
+use crate::projections::{Projection, ProjectionExpression, ProjectionFunction, ProjectionSource};
+
+/// Dynamically build the GraphQL object of a projection.
+///
+/// Next to the configured fields each object contains the `documentId` and `viewId` of the
+/// projected document. Types of the fields are derived from the schemas the values are read
+/// from, falling back to strings when these are not known to the node.
+///
+/// Each generated object has a type name with the formatting `<name>Projection`.
+pub fn build_projection_object(projection: &Projection, all_schema: &[Schema]) -> Object {
+    let mut object = Object::new(projection_name(projection))
+        .description(format!(
+            "Read-only projection `{}` of `{}` documents.",
+            projection.name,
+            projection.schema_id.name()
+        ))
+        .field(
+            Field::new(
+                constants::DOCUMENT_ID_FIELD,
+                TypeRef::named_nn(constants::DOCUMENT_ID),
+                |ctx| {
+                    FieldFuture::new(async move {
+                        let row = downcast_row(&ctx);
+                        Ok(Some(FieldValue::value(row.document_id.to_string())))
+                    })
+                },
+            )
+            .description("Id of the projected document."),
+        )
+        .field(
+            Field::new(
+                constants::DOCUMENT_VIEW_ID_FIELD,
+                TypeRef::named_nn(constants::DOCUMENT_VIEW_ID),
+                |ctx| {
+                    FieldFuture::new(async move {
+                        let row = downcast_row(&ctx);
+                        Ok(Some(FieldValue::value(row.view_id.to_string())))
+                    })
+                },
+            )
+            .description("Id of the document view the values were derived from."),
+        );
+
+    for (index, field) in projection.fields.iter().enumerate() {
+        let type_name = field_type_name(&projection.schema_id, &field.expression, all_schema);
+
+        object = object.field(Field::new(
+            &field.name,
+            TypeRef::named(type_name),
+            move |ctx| {
+                FieldFuture::new(async move {
+                    let row = downcast_row(&ctx);
+
+                    let value = match &row.values[index] {
+                        Some(value) => parse_value(value, type_name),
+                        None => Value::Null,
+                    };
+
+                    Ok(Some(FieldValue::value(value)))
+                })
+            },
+        ));
+    }
+
+    object
+}
+
+/// Get the projection row passed down from the query resolver.
+fn downcast_row<'a>(ctx: &'a ResolverContext) -> &'a ProjectionRow {
+    ctx.parent_value
+        .downcast_ref::<ProjectionRow>()
+        .expect("Values passed from query parent should match expected")
+}
+
+/// Returns the name of the GraphQL scalar type of a projected field.
+fn field_type_name(
+    schema_id: &SchemaId,
+    expression: &ProjectionExpression,
+    all_schema: &[Schema],
+) -> &'static str {
+    match expression.outer_function() {
+        Some(ProjectionFunction::Length) => return TypeRef::INT,
+        Some(_) => return TypeRef::STRING,
+        None => (),
+    }
+
+    let get_field_type = |schema_id: &SchemaId, field: &str| {
+        all_schema
+            .iter()
+            .find(|schema| schema.id() == schema_id)
+            .and_then(|schema| schema.fields().get(field))
+            .cloned()
+    };
+
+    let field_type = match &expression.source {
+        ProjectionSource::Field(field) => get_field_type(schema_id, field),
+        ProjectionSource::RelatedField { relation, field } => {
+            match get_field_type(schema_id, relation) {
+                Some(FieldType::Relation(related_schema_id))
+                | Some(FieldType::PinnedRelation(related_schema_id)) => {
+                    get_field_type(&related_schema_id, field)
+                }
+                _ => None,
+            }
+        }
+    };
+
+    match field_type {
+        Some(FieldType::Boolean) => TypeRef::BOOLEAN,
+        Some(FieldType::Integer) => TypeRef::INT,
+        Some(FieldType::Float) => TypeRef::FLOAT,
+        _ => TypeRef::STRING,
+    }
+}
+
+/// Parses a value coming from a projection table into a GraphQL value of the given type.
+fn parse_value(value: &str, type_name: &str) -> Value {
+    let parsed = match type_name {
+        TypeRef::BOOLEAN => value.parse::<bool>().ok().map(Value::from),
+        TypeRef::INT => value.parse::<i64>().ok().map(Value::from),
+        TypeRef::FLOAT => value.parse::<f64>().ok().map(Value::from),
+        _ => Some(Value::from(value)),
+    };
+
+    parsed.unwrap_or(Value::Null)
+}
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, Object, TypeRef};
+use log::debug;
+use p2panda_rs::schema::Schema;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::utils::{aggregate_name, parse_filter_arguments, with_filter_arguments};
+
+/// Adds a GraphQL query for retrieving the number of documents and aggregated values of their
+/// numeric fields by schema to the passed root query object.
+///
+/// The query follows the format `aggregate_<SCHEMA_ID>(<...ARGS>)`.
+pub fn build_aggregate_query(query: Object, schema: &Schema) -> Object {
+    let schema_id = schema.id().clone();
+    let schema = schema.clone();
+
+    query.field(
+        with_filter_arguments(
+            Field::new(
+                format!("{}{}", constants::QUERY_AGGREGATE_PREFIX, schema_id),
+                TypeRef::named_nn(aggregate_name(&schema_id)),
+                move |ctx| {
+                    let schema = schema.clone();
+                    debug!(
+                        "Query to {}{} received",
+                        constants::QUERY_AGGREGATE_PREFIX,
+                        schema.id()
+                    );
+
+                    FieldFuture::new(async move {
+                        let store = ctx.data_unchecked::<SqlStore>();
+                        let filter = parse_filter_arguments(&ctx, &schema)?;
+                        let aggregates = store.aggregate(&schema, &filter).await?;
+                        Ok(Some(FieldValue::owned_any(aggregates)))
+                    })
+                },
+            ),
+            &schema_id,
+        )
+        .description(format!(
+            "Query the number of `{}` documents and aggregated values of their numeric fields. \
+            Only documents matching the filter passed into the query via the available arguments \
+            are taken into account.",
+            schema_id.name()
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{value, Response};
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::test_utils::{
+        add_schema, add_schema_and_documents, http_test_client, test_runner, TestNode,
+    };
+
+    #[rstest]
+    fn aggregate_numeric_fields(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, _) = add_schema_and_documents(
+                &mut node,
+                "sales",
+                vec![
+                    vec![
+                        ("product", "Bamboo".into(), None),
+                        ("amount", 3.into(), None),
+                        ("price", 1.5.into(), None),
+                    ],
+                    vec![
+                        ("product", "Panda plush".into(), None),
+                        ("amount", 1.into(), None),
+                        ("price", 20.0.into(), None),
+                    ],
+                    vec![
+                        ("product", "Bamboo".into(), None),
+                        ("amount", 8.into(), None),
+                        ("price", 1.0.into(), None),
+                    ],
+                ],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            all: aggregate_{type_name} {{
+                                count
+                                min {{ amount price }}
+                                max {{ amount price }}
+                                avg {{ amount }}
+                                sum {{ amount }}
+                            }},
+                            filtered: aggregate_{type_name}(filter: {{ product: {{ eq: "Bamboo" }} }}) {{
+                                count
+                                sum {{ amount }}
+                            }},
+                            empty: aggregate_{type_name}(filter: {{ amount: {{ gt: 100 }} }}) {{
+                                count
+                                max {{ price }}
+                            }}
+                        }}"#,
+                        type_name = schema.id()
+                    ),
+                }))
+                .send()
+                .await;
+
+            let response: Response = response.json().await;
+            assert!(response.is_ok(), "{:?}", response.errors);
+            assert_eq!(
+                response.data,
+                value!({
+                    "all": {
+                        "count": 3,
+                        "min": { "amount": 1.0, "price": 1.0 },
+                        "max": { "amount": 8.0, "price": 20.0 },
+                        "avg": { "amount": 4.0 },
+                        "sum": { "amount": 12.0 },
+                    },
+                    "filtered": {
+                        "count": 2,
+                        "sum": { "amount": 11.0 },
+                    },
+                    "empty": {
+                        "count": 0,
+                        "max": { "price": null },
+                    },
+                })
+            );
+        });
+    }
+
+    #[rstest]
+    fn aggregate_without_numeric_fields(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "names",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{ aggregate_{type_name} {{ count }} }}"#,
+                        type_name = schema.id()
+                    ),
+                }))
+                .send()
+                .await;
+
+            let response: Response = response.json().await;
+            assert!(response.is_ok(), "{:?}", response.errors);
+            let data = response.data.into_json().unwrap();
+            assert_eq!(
+                data[format!("aggregate_{}", schema.id())]["count"],
+                json!(0)
+            );
+        });
+    }
+}
@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, TypeRef};
+use async_graphql::Value;
+use dynamic_graphql::{FieldValue, ScalarValue};
+use log::debug;
+use p2panda_rs::hash::Hash;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::responses::LogEntryResponse;
+use crate::graphql::scalars::EntryHashScalar;
+
+/// Add "certificatePool" query to the root query object.
+pub fn build_certificate_pool_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::CERTIFICATE_POOL_QUERY,
+            TypeRef::named_nn_list(constants::LOG_ENTRY),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments.
+                    let hash = ctx.args.try_get(constants::ENTRY_HASH_ARG)?;
+                    let hash: Hash =
+                        EntryHashScalar::from_value(Value::from(hash.string()?))?.into();
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    debug!("Query to certificatePool received for entry {}", hash);
+
+                    match store.get_certificate_pool(&hash).await? {
+                        Some(entries) => {
+                            let entries = entries
+                                .into_iter()
+                                .map(|entry| FieldValue::owned_any(LogEntryResponse::from(entry)));
+                            Ok(Some(FieldValue::list(entries)))
+                        }
+                        None => Ok(FieldValue::NONE),
+                    }
+                })
+            },
+        )
+        .argument(
+            InputValue::new(
+                constants::ENTRY_HASH_ARG,
+                TypeRef::named_nn(constants::ENTRY_HASH),
+            )
+            .description("Hash of the entry."),
+        )
+        .description(
+            "Return the certificate pool of an entry, ordered by sequence number. It contains \
+            the entries linking the latest entry of the log to the given entry and that one to \
+            the first entry, which is enough to verify its membership in the log without \
+            fetching all entries. Returns null if the entry is not known.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::entry::traits::{AsEncodedEntry, AsEntry};
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::storage_provider::traits::EntryStore;
+    use p2panda_rs::test_utils::fixtures::random_hash;
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use crate::test_utils::{
+        http_test_client, populate_store, populate_store_config, test_runner, PopulateStoreConfig,
+        TestNode,
+    };
+
+    #[rstest]
+    fn certificate_pool_query(
+        #[from(populate_store_config)]
+        #[with(8, 1, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|node: TestNode| async move {
+            populate_store(&node.context.store, &config).await;
+            let public_key = config.authors[0].public_key();
+
+            let entry = node
+                .context
+                .store
+                .get_entry_at_seq_num(&public_key, &LogId::default(), &SeqNum::new(5).unwrap())
+                .await
+                .unwrap()
+                .unwrap();
+
+            let client = http_test_client(&node).await;
+            let query = |hash: String| {
+                json!({
+                    "query": format!(
+                        r#"{{
+                            certificatePool(hash: "{}") {{
+                                seqNum,
+                                hash,
+                                encodedEntry
+                            }}
+                        }}"#,
+                        hash
+                    ),
+                })
+            };
+
+            let response = client
+                .post("/graphql")
+                .json(&query(entry.hash().to_string()))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+            let expected: Vec<Value> = node
+                .context
+                .store
+                .get_certificate_pool(&entry.hash())
+                .await
+                .unwrap()
+                .unwrap()
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "seqNum": entry.seq_num().as_u64().to_string(),
+                        "hash": entry.hash().to_string(),
+                        "encodedEntry": entry.clone().encoded_entry.to_string(),
+                    })
+                })
+                .collect();
+            assert!(expected.len() < 8);
+
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({ "certificatePool": expected })
+            );
+
+            // Unknown entries return null
+            let response = client
+                .post("/graphql")
+                .json(&query(random_hash().to_string()))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({ "certificatePool": null })
+            );
+        })
+    }
+}
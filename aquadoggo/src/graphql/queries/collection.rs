@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::num::NonZeroU64;
+
 use async_graphql::dynamic::{Field, FieldFuture, Object, TypeRef};
 use log::debug;
 use p2panda_rs::schema::Schema;
@@ -11,41 +13,38 @@ use crate::graphql::utils::{collection_name, with_collection_arguments};
 /// Adds a GraphQL query for retrieving a paginated, ordered and filtered collection of documents
 /// by schema to the passed root query object.
 ///
-/// The query follows the format `all_<SCHEMA_ID>(<...ARGS>)`.
-pub fn build_collection_query(query: Object, schema: &Schema) -> Object {
+/// The query follows the format `all_<SCHEMA_ID>(<...ARGS>)`. Clients which don't pass `first`
+/// receive the given number of documents.
+pub fn build_collection_query(query: Object, schema: &Schema, page_size: NonZeroU64) -> Object {
     let schema_id = schema.id().clone();
     let schema = schema.clone();
 
-    query
-        .field(with_collection_arguments(
-            Field::new(
-                format!("{}{}", constants::QUERY_ALL_PREFIX, schema_id),
-                TypeRef::named_nn(collection_name(&schema_id)),
-                move |ctx| {
-                    let schema = schema.clone();
-                    debug!(
-                        "Query to {}{} received",
-                        constants::QUERY_ALL_PREFIX,
-                        schema.id()
-                    );
-
-                    FieldFuture::new(
-                        async move { resolve_document_collection(ctx, schema, None).await },
-                    )
-                },
-            ),
-            &schema_id,
-        ))
-        .description(format!(
-            "Query a paginated collection of `{}` documents. \
-               The requested collection is filtered and ordered following \
-               parameters passed into the query via the available arguments.",
-            schema_id.name()
-        ))
+    query.field(with_collection_arguments(
+        Field::new(
+            format!("{}{}", constants::QUERY_ALL_PREFIX, schema_id),
+            TypeRef::named_nn(collection_name(&schema_id)),
+            move |ctx| {
+                let schema = schema.clone();
+                debug!(
+                    "Query to {}{} received",
+                    constants::QUERY_ALL_PREFIX,
+                    schema.id()
+                );
+
+                FieldFuture::new(
+                    async move { resolve_document_collection(ctx, schema, None).await },
+                )
+            },
+        ),
+        &schema_id,
+        page_size,
+    ))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroU64;
+
     use async_graphql::{value, Response, Value};
     use p2panda_rs::document::DocumentViewId;
     use p2panda_rs::operation::{PinnedRelationList, RelationList};
@@ -55,10 +54,12 @@ mod tests {
     use rstest::rstest;
     use serde_json::{json, Value as JsonValue};
 
+    use crate::config::PageSizes;
     use crate::test_utils::{
-        add_document, add_schema, add_schema_and_documents, http_test_client, test_runner,
-        TestClient, TestNode,
+        add_document, add_schema, add_schema_and_documents, delete_document, http_test_client,
+        test_runner, test_runner_with_manager, TestClient, TestNode, TestNodeManager,
     };
+    use crate::Configuration;
 
     /// Make a GraphQL collection query for songs stored on the node.
     async fn query_songs(
@@ -833,6 +834,163 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn paginate_with_page_info(key_pair: KeyPair) {
+        test_runner(move |mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "schema_name",
+                vec![("bool", FieldType::Boolean)],
+                &key_pair,
+            )
+            .await;
+
+            add_document(
+                &mut node,
+                schema.id(),
+                vec![("bool", true.into())],
+                &key_pair,
+            )
+            .await;
+            add_document(
+                &mut node,
+                schema.id(),
+                vec![("bool", false.into())],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let query = |args: String| {
+                format!(
+                    r#"{{
+                        collection: all_{type_name}(first: 1{args}) {{
+                            totalCount
+                            pageInfo {{
+                                hasNextPage
+                                hasPreviousPage
+                                startCursor
+                                endCursor
+                            }}
+                            documents {{
+                                cursor
+                            }}
+                        }},
+                    }}"#,
+                    type_name = schema.id(),
+                )
+            };
+
+            // Request the first page
+            let response: Response = client
+                .post("/graphql")
+                .json(&json!({ "query": query("".into()) }))
+                .send()
+                .await
+                .json()
+                .await;
+            assert!(response.errors.is_empty(), "{:#?}", response.errors);
+
+            let data = response.data.into_json().unwrap();
+            let cursor = data["collection"]["documents"][0]["cursor"].clone();
+            assert_eq!(data["collection"]["totalCount"], json!(2));
+            assert_eq!(
+                data["collection"]["pageInfo"],
+                json!({
+                    "hasNextPage": true,
+                    "hasPreviousPage": false,
+                    "startCursor": cursor,
+                    "endCursor": cursor,
+                })
+            );
+
+            // Request the second and last page with the end cursor of the first one
+            let response: Response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": query(format!(", after: {cursor}")),
+                }))
+                .send()
+                .await
+                .json()
+                .await;
+            assert!(response.errors.is_empty(), "{:#?}", response.errors);
+
+            let data = response.data.into_json().unwrap();
+            let next_cursor = data["collection"]["documents"][0]["cursor"].clone();
+            assert_ne!(next_cursor, cursor);
+            assert_eq!(data["collection"]["totalCount"], json!(2));
+            assert_eq!(
+                data["collection"]["pageInfo"],
+                json!({
+                    "hasNextPage": false,
+                    "hasPreviousPage": false,
+                    "startCursor": next_cursor,
+                    "endCursor": next_cursor,
+                })
+            );
+        });
+    }
+
+    #[rstest]
+    fn configured_default_page_size(key_pair: KeyPair) {
+        test_runner_with_manager(move |manager: TestNodeManager| async move {
+            let config = Configuration {
+                page_sizes: PageSizes {
+                    default: NonZeroU64::new(2).unwrap(),
+                    schemas: Vec::new(),
+                },
+                ..Configuration::default()
+            };
+            let mut node = manager.create_with_config(config).await;
+
+            let schema = add_schema(
+                &mut node,
+                "schema_name",
+                vec![("bool", FieldType::Boolean)],
+                &key_pair,
+            )
+            .await;
+
+            for value in [true, false, true] {
+                add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("bool", value.into())],
+                    &key_pair,
+                )
+                .await;
+            }
+
+            // Clients which don't ask for a page size receive the configured one
+            let client = http_test_client(&node).await;
+            let response: Response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            collection: all_{type_name} {{
+                                totalCount
+                                pageInfo {{ hasNextPage }}
+                                documents {{ cursor }}
+                            }},
+                        }}"#,
+                        type_name = schema.id(),
+                    )
+                }))
+                .send()
+                .await
+                .json()
+                .await;
+            assert!(response.errors.is_empty(), "{:#?}", response.errors);
+
+            let data = response.data.into_json().unwrap();
+            assert_eq!(data["collection"]["totalCount"], json!(3));
+            assert_eq!(data["collection"]["pageInfo"]["hasNextPage"], json!(true));
+            assert_eq!(data["collection"]["documents"].as_array().unwrap().len(), 2);
+        });
+    }
+
     #[rstest]
     #[case(
         r#"fields {
@@ -1009,6 +1167,11 @@ mod tests {
     #[case("(filter: { audio: { notEq: \"aa\" } })", "")]
     #[case("(filter: { audio: { eq: \"E8\" } })", "")]
     #[case("(filter: { audio: { eq: \"\" } })", "")]
+    #[case("(filter: { title: { isNull: true } })", "")]
+    #[case(
+        "(filter: { release_year: { isSet: true }, artist: { isNull: false } })",
+        ""
+    )]
     #[case(
         "(orderDirection: DESC, orderBy: title)",
         "(orderDirection: ASC, orderBy: line)"
@@ -1021,6 +1184,15 @@ mod tests {
         "(orderDirection: ASC, orderBy: title)",
         "(orderDirection: ASC, orderBy: DOCUMENT_VIEW_ID)"
     )]
+    #[case(
+        "(orderByFields: [{ field: artist, direction: DESC }, { field: release_year }])",
+        "(orderByFields: [{ field: line }, { field: DOCUMENT_ID, direction: DESC }])"
+    )]
+    #[case("(search: \"poly\")", "(search: \"oh bondage\", first: 1)")]
+    #[case(
+        "(owner: \"2f8e50c2ede6d936ecc3144187ff1c273808185cfbc5ff3d3748d1ff7353fc96\")",
+        "(owner: \"2f8e50c2ede6d936ecc3144187ff1c273808185cfbc5ff3d3748d1ff7353fc96\")"
+    )]
     #[case("", "(filter: { line: { gt: \"a\" } })")]
     #[case("", "(filter: { line: { lte: \"a\" } })")]
     #[case("", "(filter: { line: { contains: \"Up\" } })")]
@@ -1032,6 +1204,7 @@ mod tests {
     #[case("", "(filter: { line: { notIn: [ \"Oh bondage, up yours\" ] } })")]
     #[case("", "(filter: { line: { eq: \"Oh bondage, up yours\" } })")]
     #[case("", "(filter: { line: { notEq: \"The body is good business\", notIn: [ \"Oh bondage, up yours\" ] } })")]
+    #[case("", "(filter: { line: { isSet: true } })")]
     fn valid_filter_and_order_queries_pass(
         #[case] song_args: &str,
         #[case] lyric_args: &str,
@@ -1253,6 +1426,24 @@ mod tests {
 
             assert_eq!(data["query"]["totalCount"], json!(3));
 
+            // The shorter `owner` argument gives the same result:
+            let data =
+                query_songs(&client, song_schema.id(), &format!("(owner: \"{me}\")"), "").await;
+
+            assert_eq!(data["query"]["totalCount"], json!(3));
+
+            // But someone else didn't publish any songs:
+            let someone_else = KeyPair::new().public_key().to_string();
+            let data = query_songs(
+                &client,
+                song_schema.id(),
+                &format!("(owner: \"{someone_else}\")"),
+                "",
+            )
+            .await;
+
+            assert_eq!(data["query"]["totalCount"], json!(0));
+
             // Oh yeh, i like that song lyric "This heaven gives me migraine"! I wonder if I can
             // find it....
             let data = query_lyrics(
@@ -1451,4 +1642,89 @@ mod tests {
             assert_eq!(data["query"]["documents"].as_array().unwrap().len(), 0);
         })
     }
+
+    #[rstest]
+    #[case::default("", vec![(Some("kept"), false)])]
+    #[case::include_deleted(
+        "(includeDeleted: true)",
+        vec![(Some("kept"), false), (None, true)]
+    )]
+    #[case::only_deleted(
+        "(includeDeleted: true, meta: { deleted: { eq: true } })",
+        vec![(None, true)]
+    )]
+    fn include_deleted_documents(
+        #[case] query_args: &str,
+        #[case] expected: Vec<(Option<&str>, bool)>,
+        key_pair: KeyPair,
+    ) {
+        let query_args = query_args.to_string();
+        let total_count = expected.len();
+        let expected: Vec<JsonValue> = expected
+            .into_iter()
+            .map(|(name, deleted)| {
+                // Deleted documents don't have any fields
+                json!({
+                    "fields": name.map(|name| json!({ "name": name })),
+                    "meta": { "deleted": deleted }
+                })
+            })
+            .collect();
+
+        test_runner(move |mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "tombstones",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "kept".into())],
+                &key_pair,
+            )
+            .await;
+            let view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "removed".into())],
+                &key_pair,
+            )
+            .await;
+            delete_document(&mut node, schema.id(), &view_id, &key_pair).await;
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            query: all_{}{} {{
+                                totalCount
+                                documents {{
+                                    fields {{ name }}
+                                    meta {{ deleted }}
+                                }}
+                            }}
+                        }}"#,
+                        schema.id(),
+                        query_args,
+                    ),
+                }))
+                .send()
+                .await;
+            let response: Response = response.json().await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+            let data = response.data.into_json().unwrap();
+            assert_eq!(data["query"]["totalCount"], json!(total_count));
+
+            let mut documents = data["query"]["documents"].as_array().unwrap().to_owned();
+            documents.sort_by_key(|document| document["meta"]["deleted"].to_string());
+            assert_eq!(documents, expected);
+        })
+    }
 }
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, Object, TypeRef};
+use async_graphql::Error;
+use dynamic_graphql::FieldValue;
+use log::debug;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::mutations::AdminRequest;
+use crate::graphql::responses::DeadLetterTaskResponse;
+
+/// Add "deadLetterTasks" query to the root query object.
+pub fn build_dead_letter_tasks_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::DEAD_LETTER_TASKS_QUERY,
+            TypeRef::named_nn_list_nn(constants::DEAD_LETTER_TASK),
+            |ctx| {
+                FieldFuture::new(async move {
+                    if ctx.data_opt::<AdminRequest>().is_none() {
+                        return Err(Error::new("Admin token required"));
+                    }
+
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    debug!("Query to deadLetterTasks received");
+
+                    let tasks = store
+                        .get_dead_letter_tasks()
+                        .await?
+                        .into_iter()
+                        .map(|task| FieldValue::owned_any(DeadLetterTaskResponse::from(task)));
+
+                    Ok(Some(FieldValue::list(tasks)))
+                })
+            },
+        )
+        .description(
+            "Return materializer tasks which failed too many times and are not retried anymore, \
+            the most recently failed first. Requires the admin token of the node.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::document_id;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::db::types::DeadLetterTask;
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn dead_letter_tasks_query(document_id: DocumentId) {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.admin_token = Some("secret".into());
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            node.context
+                .store
+                .insert_dead_letter_task(&DeadLetterTask {
+                    task: Task::new("reduce", TaskInput::DocumentId(document_id.clone())),
+                    attempts: 3,
+                    error: "Something went wrong".into(),
+                    failed_at: 100,
+                })
+                .await
+                .unwrap();
+
+            let client = http_test_client(&node).await;
+            let query = json!({
+                "query": r#"{
+                    deadLetterTasks {
+                        name,
+                        documentId,
+                        viewId,
+                        attempts,
+                        error,
+                        failedAt
+                    }
+                }"#,
+            });
+
+            // Requests without the admin token are rejected
+            let response: Response = client
+                .post("/graphql")
+                .json(&query)
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(response.errors[0].message, "Admin token required");
+
+            let response: Response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&query)
+                .send()
+                .await
+                .json()
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "deadLetterTasks": [{
+                        "name": "reduce",
+                        "documentId": document_id.to_string(),
+                        "viewId": null,
+                        "attempts": 3,
+                        "error": "Something went wrong",
+                        "failedAt": 100,
+                    }]
+                })
+            );
+        })
+    }
+}
@@ -92,6 +92,7 @@ fn parse_arguments(
 #[cfg(test)]
 mod test {
     use async_graphql::{value, Response, Value};
+    use dynamic_graphql::ScalarValue;
     use p2panda_rs::document::traits::AsDocument;
     use p2panda_rs::identity::KeyPair;
     use p2panda_rs::schema::FieldType;
@@ -100,7 +101,10 @@ mod test {
     use rstest::rstest;
     use serde_json::json;
 
-    use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
+    use crate::graphql::scalars::DateTimeScalar;
+    use crate::test_utils::{
+        add_document, add_schema, http_test_client, test_runner, update_document, TestNode,
+    };
 
     #[rstest]
     fn single_query(#[from(random_key_pair)] key_pair: KeyPair) {
@@ -246,6 +250,218 @@ mod test {
         });
     }
 
+    #[rstest]
+    fn document_history(#[from(random_key_pair)] key_pair: KeyPair) {
+        test_runner(move |mut node: TestNode| async move {
+            // Add schema to node.
+            let schema = add_schema(
+                &mut node,
+                "schema_name",
+                vec![("bool", FieldType::Boolean), ("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            // Publish a document and update one of its fields.
+            let first_view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("bool", true.into()), ("name", "panda".into())],
+                &key_pair,
+            )
+            .await;
+
+            let second_view_id = update_document(
+                &mut node,
+                schema.id(),
+                vec![("bool", false.into())],
+                &first_view_id,
+                &key_pair,
+            )
+            .await;
+
+            // Configure and send test query.
+            let client = http_test_client(&node).await;
+            let query = format!(
+                r#"{{
+                    document: {schema_id}(viewId: "{second_view_id}") {{
+                        meta {{
+                            history {{
+                                viewId
+                                operations
+                                isCurrent
+                            }}
+                        }}
+                    }}
+                }}"#,
+                schema_id = schema.id(),
+            );
+
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": query,
+                }))
+                .send()
+                .await;
+
+            let response: Response = response.json().await;
+            assert!(response.is_ok(), "{:#?}", response.errors);
+
+            // Both views are returned, ordered by their id. The current view contains the value
+            // of the "name" field from the first operation and the value of the "bool" field from
+            // the second one.
+            let mut current_operations =
+                vec![first_view_id.to_string(), second_view_id.to_string()];
+            current_operations.sort();
+
+            let mut expected = vec![
+                json!({
+                    "viewId": first_view_id.to_string(),
+                    "operations": [first_view_id.to_string()],
+                    "isCurrent": false,
+                }),
+                json!({
+                    "viewId": second_view_id.to_string(),
+                    "operations": current_operations,
+                    "isCurrent": true,
+                }),
+            ];
+            expected.sort_by_key(|view| view["viewId"].as_str().unwrap().to_owned());
+
+            let data = response.data.into_json().unwrap();
+            assert_eq!(data["document"]["meta"]["history"], json!(expected));
+        });
+    }
+
+    #[rstest]
+    fn document_branches(#[from(random_key_pair)] key_pair: KeyPair) {
+        test_runner(move |mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "schema_name",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            // Publish a document and update it twice concurrently.
+            let create_view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "panda".into())],
+                &key_pair,
+            )
+            .await;
+
+            let mut branches = Vec::new();
+            for name in ["bamboo", "honey"] {
+                let view_id = update_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("name", name.into())],
+                    &create_view_id,
+                    &key_pair,
+                )
+                .await;
+                branches.push(view_id.to_string());
+            }
+            branches.sort();
+
+            // Configure and send test query.
+            let client = http_test_client(&node).await;
+            let query = format!(
+                r#"{{
+                    document: {schema_id}(id: "{create_view_id}") {{
+                        meta {{
+                            branches
+                        }}
+                    }}
+                }}"#,
+                schema_id = schema.id(),
+            );
+
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": query,
+                }))
+                .send()
+                .await;
+
+            let response: Response = response.json().await;
+            assert!(response.is_ok(), "{:#?}", response.errors);
+
+            let data = response.data.into_json().unwrap();
+            assert_eq!(data["document"]["meta"]["branches"], json!(branches));
+        });
+    }
+
+    #[rstest]
+    fn document_timestamps(#[from(random_key_pair)] key_pair: KeyPair) {
+        test_runner(move |mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "schema_name",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let first_view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "panda".into())],
+                &key_pair,
+            )
+            .await;
+
+            let second_view_id = update_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "doggo".into())],
+                &first_view_id,
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let query = format!(
+                r#"{{
+                    document: {schema_id}(viewId: "{second_view_id}") {{
+                        meta {{
+                            createdAt
+                            updatedAt
+                        }}
+                    }}
+                }}"#,
+                schema_id = schema.id(),
+            );
+
+            let response: Response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": query,
+                }))
+                .send()
+                .await
+                .json()
+                .await;
+            assert!(response.is_ok(), "{:#?}", response.errors);
+
+            // Both values are set and the document was not updated before it was created
+            let data = response.data.into_json().unwrap();
+            let timestamp = |field: &str| {
+                let value = data["document"]["meta"][field].as_str().unwrap();
+                DateTimeScalar::from_value(value.into())
+                    .unwrap()
+                    .unix_timestamp()
+            };
+            assert!(timestamp("createdAt") > 0);
+            assert!(timestamp("createdAt") <= timestamp("updatedAt"));
+        });
+    }
+
     #[rstest]
     fn type_name(#[from(random_key_pair)] key_pair: KeyPair) {
         // Test availability of `__typename` on all objects.
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef};
+use async_graphql::Value;
+use dynamic_graphql::ScalarValue;
+use log::debug;
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::document::DocumentId;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::resolvers::Resolved;
+use crate::graphql::scalars::DocumentIdScalar;
+
+/// Adds a GraphQL query for retrieving many documents of any schema by their ids to the root
+/// query object.
+///
+/// All documents are fetched from the database at once. The returned list follows the order of
+/// the passed ids, documents which were not found are left out.
+///
+/// The query follows the format `documents(ids: [<DOCUMENT_ID>])`.
+pub fn build_documents_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::DOCUMENTS_QUERY,
+            TypeRef::named_nn_list_nn(constants::DOCUMENT),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments
+                    let mut document_ids: Vec<DocumentId> = Vec::new();
+                    for value in ctx
+                        .args
+                        .try_get(constants::DOCUMENT_IDS_ARG)?
+                        .list()?
+                        .iter()
+                    {
+                        let document_id =
+                            DocumentIdScalar::from_value(Value::from(value.string()?))?;
+                        document_ids.push((&document_id).into());
+                    }
+
+                    debug!(
+                        "Query to documents received for {} documents",
+                        document_ids.len()
+                    );
+
+                    let store = ctx.data_unchecked::<SqlStore>();
+                    let documents: HashMap<DocumentId, _> = store
+                        .get_documents_by_ids(&document_ids)
+                        .await?
+                        .into_iter()
+                        .map(|document| (document.id().to_owned(), document))
+                        .collect();
+
+                    // Pass the documents up in the order they were requested, the type name tells
+                    // the union which document object to resolve
+                    let documents = document_ids.iter().filter_map(|document_id| {
+                        documents.get(document_id).cloned().map(|document| {
+                            let schema_id = document.schema_id().to_string();
+                            FieldValue::owned_any(Resolved::Document(document)).with_type(schema_id)
+                        })
+                    });
+
+                    Ok(Some(FieldValue::list(documents)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(
+                constants::DOCUMENT_IDS_ARG,
+                TypeRef::named_nn_list_nn(constants::DOCUMENT_ID),
+            )
+            .description("Ids of the documents to be retrieved."),
+        )
+        .description(
+            "Query many documents of any schema by their ids. Documents are returned in the order \
+            of the passed ids, documents which were not found are left out.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{value, Response};
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::{key_pair, random_document_id};
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::test_utils::{add_schema_and_documents, http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn documents_by_ids(
+        key_pair: KeyPair,
+        #[from(random_document_id)] unknown_document_id: DocumentId,
+    ) {
+        test_runner(|mut node: TestNode| async move {
+            let (animals, animal_view_ids) = add_schema_and_documents(
+                &mut node,
+                "animals",
+                vec![
+                    vec![("name", "Panda".into(), None)],
+                    vec![("name", "Doggo".into(), None)],
+                ],
+                &key_pair,
+            )
+            .await;
+
+            let (plants, plant_view_ids) = add_schema_and_documents(
+                &mut node,
+                "plants",
+                vec![vec![("height", 12.into(), None)]],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            documents(ids: ["{doggo}", "{unknown}", "{bamboo}", "{panda}"]) {{
+                                ... on {animals} {{ fields {{ name }} }}
+                                ... on {plants} {{ fields {{ height }} }}
+                            }}
+                        }}"#,
+                        doggo = animal_view_ids[1],
+                        panda = animal_view_ids[0],
+                        bamboo = plant_view_ids[0],
+                        unknown = unknown_document_id,
+                        animals = animals.id(),
+                        plants = plants.id(),
+                    ),
+                }))
+                .send()
+                .await;
+
+            let response: Response = response.json().await;
+            assert!(response.is_ok(), "{:?}", response.errors);
+            assert_eq!(
+                response.data,
+                value!({
+                    "documents": [
+                        { "fields": { "name": "Doggo" } },
+                        { "fields": { "height": 12 } },
+                        { "fields": { "name": "Panda" } },
+                    ]
+                })
+            );
+        });
+    }
+
+    #[rstest]
+    fn documents_with_invalid_id() {
+        test_runner(|node: TestNode| async move {
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": r#"{ documents(ids: ["notAnId"]) { __typename } }"#,
+                }))
+                .send()
+                .await;
+
+            let response: Response = response.json().await;
+            assert!(response.is_err());
+        });
+    }
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::str::FromStr;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef};
+use async_graphql::Value;
+use dynamic_graphql::ScalarValue;
+use log::debug;
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::identity::PublicKey;
+use p2panda_rs::schema::SchemaId;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::resolvers::Resolved;
+use crate::graphql::scalars::PublicKeyScalar;
+
+/// Adds a GraphQL query for retrieving all documents of a schema which were created by one author
+/// to the root query object.
+///
+/// Documents are looked up by the public key which signed their CREATE operation, this lookup is
+/// backed by an index and does not filter the meta fields of all documents of the schema.
+///
+/// The query follows the format `documentsByAuthor(publicKey: <PUBLIC_KEY>, schemaId:
+/// <SCHEMA_ID>)`.
+pub fn build_documents_by_author_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::DOCUMENTS_BY_AUTHOR_QUERY,
+            TypeRef::named_nn_list_nn(constants::DOCUMENT),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments
+                    let public_key = ctx.args.try_get(constants::PUBLIC_KEY_ARG)?;
+                    let public_key: PublicKey =
+                        PublicKeyScalar::from_value(Value::from(public_key.string()?))?.into();
+                    let schema_id = ctx.args.try_get(constants::SCHEMA_ID_ARG)?;
+                    let schema_id = SchemaId::from_str(schema_id.string()?)?;
+
+                    debug!(
+                        "Query to documentsByAuthor received for {} documents of {}",
+                        schema_id, public_key
+                    );
+
+                    let store = ctx.data_unchecked::<SqlStore>();
+                    let documents = store
+                        .get_documents_by_public_key(&schema_id, &public_key)
+                        .await?;
+
+                    // The type name tells the union which document object to resolve
+                    let documents = documents.into_iter().map(|document| {
+                        let schema_id = document.schema_id().to_string();
+                        FieldValue::owned_any(Resolved::Document(document)).with_type(schema_id)
+                    });
+
+                    Ok(Some(FieldValue::list(documents)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(
+                constants::PUBLIC_KEY_ARG,
+                TypeRef::named_nn(constants::PUBLIC_KEY),
+            )
+            .description("Public key of the author who created the documents."),
+        )
+        .argument(
+            InputValue::new(constants::SCHEMA_ID_ARG, TypeRef::named_nn(TypeRef::STRING))
+                .description("Schema id of the documents to be retrieved."),
+        )
+        .description(
+            "Query all documents of a schema which were created by one author, ordered by their \
+            document id. Deleted documents are left out.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{value, Response};
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::test_utils::{
+        add_document, add_schema_and_documents, http_test_client, test_runner, TestNode,
+    };
+
+    #[rstest]
+    fn documents_by_author(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let (schema, _) = add_schema_and_documents(
+                &mut node,
+                "posts",
+                vec![vec![("title", "Hello".into(), None)]],
+                &key_pair,
+            )
+            .await;
+
+            // Another author writing in the same schema
+            let other_author = KeyPair::new();
+            add_document(
+                &mut node,
+                schema.id(),
+                vec![("title", "Not mine".into())],
+                &other_author,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let query = |public_key: String| {
+                json!({
+                    "query": format!(
+                        r#"{{
+                            documentsByAuthor(publicKey: "{}", schemaId: "{}") {{
+                                ... on {} {{ fields {{ title }} }}
+                            }}
+                        }}"#,
+                        public_key,
+                        schema.id(),
+                        schema.id(),
+                    ),
+                })
+            };
+
+            for (public_key, expected) in [
+                (key_pair.public_key(), "Hello"),
+                (other_author.public_key(), "Not mine"),
+            ] {
+                let response: Response = client
+                    .post("/graphql")
+                    .json(&query(public_key.to_string()))
+                    .send()
+                    .await
+                    .json()
+                    .await;
+
+                assert!(response.is_ok(), "{:?}", response.errors);
+                assert_eq!(
+                    response.data,
+                    value!({ "documentsByAuthor": [{ "fields": { "title": expected } }] })
+                );
+            }
+
+            // Authors without documents get an empty list
+            let response: Response = client
+                .post("/graphql")
+                .json(&query(KeyPair::new().public_key().to_string()))
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(response.data, value!({ "documentsByAuthor": [] }));
+        });
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, TypeRef};
+use async_graphql::Value;
+use dynamic_graphql::{FieldValue, ScalarValue};
+use log::debug;
+use p2panda_rs::entry::{LogId, SeqNum};
+use p2panda_rs::identity::PublicKey;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::responses::LogEntryResponse;
+use crate::graphql::scalars::{LogIdScalar, PublicKeyScalar};
+
+/// Add "entryChain" query to the root query object.
+pub fn build_entry_chain_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::ENTRY_CHAIN_QUERY,
+            TypeRef::named_nn_list_nn(constants::LOG_ENTRY),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments.
+                    let public_key = ctx.args.try_get(constants::PUBLIC_KEY_ARG)?;
+                    let public_key: PublicKey =
+                        PublicKeyScalar::from_value(Value::from(public_key.string()?))?.into();
+                    let log_id = ctx.args.try_get(constants::LOG_ID_ARG)?;
+                    let log_id: LogId =
+                        LogIdScalar::from_value(Value::from(log_id.string()?))?.into();
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    debug!(
+                        "Query to entryChain received for public key {} and log {}",
+                        public_key,
+                        log_id.as_u64()
+                    );
+
+                    let entries = store
+                        .get_entries_from(&public_key, &log_id, &SeqNum::default())
+                        .await?
+                        .into_iter()
+                        .map(|entry| FieldValue::owned_any(LogEntryResponse::from(entry)));
+
+                    Ok(Some(FieldValue::list(entries)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(
+                constants::PUBLIC_KEY_ARG,
+                TypeRef::named_nn(constants::PUBLIC_KEY),
+            )
+            .description("Public key of the author of the log."),
+        )
+        .argument(
+            InputValue::new(constants::LOG_ID_ARG, TypeRef::named_nn(constants::LOG_ID))
+                .description("Id of the log."),
+        )
+        .description(
+            "Return hashes, sequence numbers and links of all entries in a log, ordered by their \
+            sequence number. Clients can use this to recover the state of their logs.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::entry::traits::{AsEncodedEntry, AsEntry};
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::identity::KeyPair;
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use crate::test_utils::{
+        http_test_client, populate_store, populate_store_config, test_runner, PopulateStoreConfig,
+        TestNode,
+    };
+
+    #[rstest]
+    fn entry_chain_query(
+        #[from(populate_store_config)]
+        #[with(10, 2, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(|node: TestNode| async move {
+            populate_store(&node.context.store, &config).await;
+            let public_key = config.authors[0].public_key();
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            entryChain(publicKey: "{}", logId: "1") {{
+                                seqNum,
+                                hash,
+                                backlink,
+                                skiplink
+                            }}
+                        }}"#,
+                        public_key
+                    ),
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+            let expected_entries = node
+                .context
+                .store
+                .get_entries_from(&public_key, &LogId::new(1), &SeqNum::default())
+                .await
+                .unwrap();
+            assert_eq!(expected_entries.len(), 10);
+
+            let expected: Vec<Value> = expected_entries
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "seqNum": entry.seq_num().as_u64().to_string(),
+                        "hash": entry.hash().to_string(),
+                        "backlink": entry.backlink().map(|hash| hash.to_string()),
+                        "skiplink": entry.skiplink().map(|hash| hash.to_string()),
+                    })
+                })
+                .collect();
+
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({ "entryChain": expected })
+            );
+        })
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, TypeRef};
+use dynamic_graphql::FieldValue;
+use log::debug;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::responses::MaterializedAggregateGroupResponse;
+
+/// Add "materializedAggregate" query to the root query object.
+pub fn build_materialized_aggregate_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::MATERIALIZED_AGGREGATE_QUERY,
+            TypeRef::named_nn_list_nn(constants::MATERIALIZED_AGGREGATE_GROUP),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments.
+                    let name = ctx.args.try_get(constants::NAME_ARG)?.string()?.to_owned();
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    debug!("Query to materializedAggregate received for {}", name);
+
+                    let groups = store
+                        .get_materialized_aggregate(&name)
+                        .await?
+                        .into_iter()
+                        .map(|group| {
+                            FieldValue::owned_any(MaterializedAggregateGroupResponse::from(group))
+                        });
+
+                    Ok(Some(FieldValue::list(groups)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(constants::NAME_ARG, TypeRef::named_nn(TypeRef::STRING))
+                .description("Name of the aggregate as configured on this node."),
+        )
+        .description(
+            "Return the document count and summed up fields per group of a materialized \
+            aggregate. Aggregates are configured on this node and kept up-to-date by the \
+            materializer whenever a document of their schema changes.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
+    use crate::MaterializedAggregate;
+
+    #[rstest]
+    fn materialized_aggregate(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "orders",
+                vec![
+                    ("product", FieldType::String),
+                    ("amount", FieldType::Integer),
+                ],
+                &key_pair,
+            )
+            .await;
+
+            // Configure aggregate on node
+            let mut config = node.context.config.clone();
+            config.materialized_aggregates.push(MaterializedAggregate {
+                name: "sales".into(),
+                schema_id: schema.id().to_owned(),
+                group_by: Some("product".into()),
+                sum: vec!["amount".into()],
+            });
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            for (product, amount) in [("tea", 3i64), ("coffee", 1), ("tea", 2)] {
+                add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("product", product.into()), ("amount", amount.into())],
+                    &key_pair,
+                )
+                .await;
+            }
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": r#"{
+                        sales: materializedAggregate(name: "sales") {
+                            group
+                            count
+                            sums {
+                                field
+                                sum
+                            }
+                        }
+                        unknown: materializedAggregate(name: "unknown") {
+                            group
+                        }
+                    }"#,
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "sales": [
+                        {
+                            "group": "coffee",
+                            "count": 1,
+                            "sums": [{ "field": "amount", "sum": 1.0 }]
+                        },
+                        {
+                            "group": "tea",
+                            "count": 2,
+                            "sums": [{ "field": "amount", "sum": 5.0 }]
+                        }
+                    ],
+                    "unknown": []
+                })
+            );
+        })
+    }
+}
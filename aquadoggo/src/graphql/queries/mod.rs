@@ -1,9 +1,41 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod aggregate;
+mod certificate_pool;
 mod collection;
+mod dead_letter_tasks;
 mod document;
+mod documents;
+mod documents_by_author;
+mod entry_chain;
+mod materialized_aggregate;
 mod next_args;
+mod node_status;
+mod peer_authors;
+mod pending_tasks;
+mod projection;
+mod resolve_name;
+mod schema_fields;
+mod signed_blob_url;
+mod task_timeline;
+mod unique_conflicts;
 
+pub use aggregate::build_aggregate_query;
+pub use certificate_pool::build_certificate_pool_query;
 pub use collection::build_collection_query;
+pub use dead_letter_tasks::build_dead_letter_tasks_query;
 pub use document::build_document_query;
+pub use documents::build_documents_query;
+pub use documents_by_author::build_documents_by_author_query;
+pub use entry_chain::build_entry_chain_query;
+pub use materialized_aggregate::build_materialized_aggregate_query;
 pub use next_args::build_next_args_query;
+pub use node_status::build_node_status_query;
+pub use peer_authors::build_peer_authors_query;
+pub use pending_tasks::build_pending_tasks_query;
+pub use projection::build_projection_query;
+pub use resolve_name::build_resolve_name_query;
+pub use schema_fields::build_schema_fields_query;
+pub use signed_blob_url::build_signed_blob_url_query;
+pub use task_timeline::build_task_timeline_query;
+pub use unique_conflicts::build_unique_conflicts_query;
@@ -1,15 +1,18 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::str::FromStr;
+
 use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, ResolverContext, TypeRef};
-use async_graphql::Error;
+use async_graphql::{Error, Value};
 use dynamic_graphql::{FieldValue, ScalarValue};
 use log::debug;
-use p2panda_rs::api;
+use p2panda_rs::schema::SchemaId;
 
 use crate::db::SqlStore;
 use crate::graphql::constants;
 use crate::graphql::responses::NextArguments;
 use crate::graphql::scalars::{DocumentViewIdScalar, PublicKeyScalar};
+use crate::log_ids::{next_args, SharedLogIdPolicy};
 
 /// Add "nextArgs" query to the root query object.
 pub fn build_next_args_query(query: Object) -> Object {
@@ -20,14 +23,21 @@ pub fn build_next_args_query(query: Object) -> Object {
             |ctx| {
                 FieldFuture::new(async move {
                     // Parse arguments.
-                    let (public_key, document_view_id) = parse_arguments(&ctx)?;
+                    let (public_key, document_view_id, schema_id) = parse_arguments(&ctx)?;
                     let store = ctx.data_unchecked::<SqlStore>();
+                    let log_id_policy = ctx.data_unchecked::<SharedLogIdPolicy>();
+                    let public_key = public_key.into();
+
+                    // Wait for entries of this public key which are currently being published.
+                    let _guard = store.lock_public_key(&public_key).await;
 
                     // Calculate next entry's arguments.
-                    let (backlink, skiplink, seq_num, log_id) = api::next_args(
+                    let (backlink, skiplink, seq_num, log_id) = next_args(
                         store,
-                        &public_key.into(),
+                        log_id_policy.as_ref(),
+                        &public_key,
                         document_view_id.map(|id| id.into()).as_ref(),
+                        schema_id.as_ref(),
                     )
                     .await?;
 
@@ -51,6 +61,10 @@ pub fn build_next_args_query(query: Object) -> Object {
             constants::DOCUMENT_VIEW_ID_ARG,
             TypeRef::named(constants::DOCUMENT_VIEW_ID),
         ).description("Optional field for specifying an existing document next args are being requested for."))
+        .argument(InputValue::new(
+            constants::SCHEMA_ID_ARG,
+            TypeRef::named(TypeRef::STRING),
+        ).description("Optional schema id of the document, used to allocate the log id of new documents."))
         .description("Return required arguments for publishing a entry to a node."),
     )
 }
@@ -58,35 +72,39 @@ pub fn build_next_args_query(query: Object) -> Object {
 /// Parse and validate the arguments passed to next_args.
 fn parse_arguments(
     ctx: &ResolverContext,
-) -> Result<(PublicKeyScalar, Option<DocumentViewIdScalar>), Error> {
-    let mut args = ctx.field().arguments()?.into_iter().map(|(_, value)| value);
-
+) -> Result<
+    (
+        PublicKeyScalar,
+        Option<DocumentViewIdScalar>,
+        Option<SchemaId>,
+    ),
+    Error,
+> {
     // Convert and validate passed parameters.
-    let public_key = PublicKeyScalar::from_value(args.next().unwrap())?;
-    let document_view_id = match args.next() {
-        Some(value) => match value {
-            async_graphql::Value::Null => None,
-            async_graphql::Value::String(_) => Some(value),
-            _ => panic!("Unexpected value type received for viewId in nextArgs"),
-        },
-        None => None,
-    };
-    let document_view_id = match document_view_id {
-        Some(value) => {
-            let document_view_id = DocumentViewIdScalar::from_value(value)?;
+    let public_key = ctx.args.try_get(constants::PUBLIC_KEY_ARG)?;
+    let public_key = PublicKeyScalar::from_value(Value::from(public_key.string()?))?;
+
+    let document_view_id = match ctx.args.get(constants::DOCUMENT_VIEW_ID_ARG) {
+        Some(value) if !value.is_null() => {
+            let document_view_id = DocumentViewIdScalar::from_value(Value::from(value.string()?))?;
             debug!(
                 "Query to nextArgs received for public key {} and document at view {}",
                 public_key, document_view_id
             );
             Some(document_view_id)
         }
-        None => {
+        _ => {
             debug!("Query to nextArgs received for public key {}", public_key);
             None
         }
     };
 
-    Ok((public_key, document_view_id))
+    let schema_id = match ctx.args.get(constants::SCHEMA_ID_ARG) {
+        Some(value) if !value.is_null() => Some(SchemaId::from_str(value.string()?)?),
+        _ => None,
+    };
+
+    Ok((public_key, document_view_id, schema_id))
 }
 
 #[cfg(test)]
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, Object, TypeRef};
+use dynamic_graphql::FieldValue;
+use log::debug;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::responses::NodeStatusResponse;
+use crate::schema::SchemaProvider;
+use crate::status::NodeStatus;
+
+/// Add "nodeStatus" query to the root query object.
+pub fn build_node_status_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::NODE_STATUS_QUERY,
+            TypeRef::named_nn(constants::NODE_STATUS),
+            |ctx| {
+                FieldFuture::new(async move {
+                    let store = ctx.data_unchecked::<SqlStore>();
+                    let schema_provider = ctx.data_unchecked::<SchemaProvider>();
+                    let status = ctx.data_unchecked::<NodeStatus>();
+
+                    debug!("Query to nodeStatus received");
+
+                    let report = status.report(store, schema_provider).await?;
+                    Ok(Some(FieldValue::owned_any(NodeStatusResponse::from(
+                        report,
+                    ))))
+                })
+            },
+        )
+        .description(
+            "Return the status of the node, for example its version and the number of connected \
+            peers.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn node_status_query() {
+        test_runner(|node: TestNode| async move {
+            let supported_schemas = node
+                .context
+                .schema_provider
+                .supported_schema_ids()
+                .await
+                .len();
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": r#"{
+                        nodeStatus {
+                            version,
+                            supportedSchemas,
+                            connectedPeers,
+                            materializerQueueDepth,
+                            peers {
+                                peerId
+                            },
+                            dryRun {
+                                schemaId
+                            }
+                        }
+                    }"#,
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "nodeStatus": {
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "supportedSchemas": supported_schemas,
+                        "connectedPeers": 0,
+                        "materializerQueueDepth": 0,
+                        "peers": [],
+                        "dryRun": [],
+                    }
+                })
+            );
+        })
+    }
+
+    #[rstest]
+    fn document_counts(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "puppies",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            for name in ["Bubu", "Marlo"] {
+                add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("name", name.into())],
+                    &key_pair,
+                )
+                .await;
+            }
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": r#"{
+                        nodeStatus {
+                            documentCounts {
+                                schemaId,
+                                documents
+                            }
+                        }
+                    }"#,
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            let data = response.data.into_json().unwrap();
+            let counts = data["nodeStatus"]["documentCounts"].as_array().unwrap();
+            assert!(counts.contains(&json!({
+                "schemaId": schema.id().to_string(),
+                "documents": 2,
+            })));
+            assert!(counts.contains(&json!({
+                "schemaId": "schema_definition_v1",
+                "documents": 1,
+            })));
+        })
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, Object, TypeRef};
+use async_graphql::Error;
+use dynamic_graphql::FieldValue;
+use log::debug;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::mutations::AdminRequest;
+use crate::graphql::responses::PeerAuthorResponse;
+
+/// Add "peerAuthors" query to the root query object.
+pub fn build_peer_authors_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::PEER_AUTHORS_QUERY,
+            TypeRef::named_nn_list_nn(constants::PEER_AUTHOR),
+            |ctx| {
+                FieldFuture::new(async move {
+                    if ctx.data_opt::<AdminRequest>().is_none() {
+                        return Err(Error::new("Admin token required"));
+                    }
+
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    debug!("Query to peerAuthors received");
+
+                    let peer_authors =
+                        store
+                            .get_peer_authors()
+                            .await?
+                            .into_iter()
+                            .map(|peer_author| {
+                                FieldValue::owned_any(PeerAuthorResponse::from(peer_author))
+                            });
+
+                    Ok(Some(FieldValue::list(peer_authors)))
+                })
+            },
+        )
+        .description(
+            "Return which authors were received from which peers during replication, ordered by \
+            peer id. Helps finding out where unwanted data came from. Requires the admin token \
+            of the node.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::db::types::PeerAuthor;
+    use crate::test_utils::{http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn peer_authors_query(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.admin_token = Some("secret".into());
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            node.context
+                .store
+                .record_peer_authors(&[PeerAuthor {
+                    peer_id: "peer_a".into(),
+                    public_key: key_pair.public_key(),
+                    first_seen: 100,
+                    last_seen: 200,
+                    entries: 3,
+                }])
+                .await
+                .unwrap();
+
+            let client = http_test_client(&node).await;
+            let query = json!({
+                "query": r#"{
+                    peerAuthors {
+                        peerId,
+                        publicKey,
+                        firstSeen,
+                        lastSeen,
+                        entries
+                    }
+                }"#,
+            });
+
+            // Requests without the admin token are rejected
+            let response: Response = client
+                .post("/graphql")
+                .json(&query)
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(response.errors[0].message, "Admin token required");
+
+            let response: Response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&query)
+                .send()
+                .await
+                .json()
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "peerAuthors": [{
+                        "peerId": "peer_a",
+                        "publicKey": key_pair.public_key().to_string(),
+                        "firstSeen": 100,
+                        "lastSeen": 200,
+                        "entries": 3,
+                    }]
+                })
+            );
+        })
+    }
+}
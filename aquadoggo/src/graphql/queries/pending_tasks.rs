@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, Object, TypeRef};
+use async_graphql::Error;
+use dynamic_graphql::FieldValue;
+use log::debug;
+
+use crate::graphql::constants;
+use crate::graphql::mutations::AdminRequest;
+use crate::graphql::responses::PendingTaskResponse;
+use crate::status::NodeStatus;
+
+/// Add "pendingTasks" query to the root query object.
+pub fn build_pending_tasks_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::PENDING_TASKS_QUERY,
+            TypeRef::named_nn_list_nn(constants::PENDING_TASK),
+            |ctx| {
+                FieldFuture::new(async move {
+                    if ctx.data_opt::<AdminRequest>().is_none() {
+                        return Err(Error::new("Admin token required"));
+                    }
+
+                    let status = ctx.data_unchecked::<NodeStatus>();
+
+                    debug!("Query to pendingTasks received");
+
+                    let tasks =
+                        status.pending_tasks().list().into_iter().map(|pending| {
+                            FieldValue::owned_any(PendingTaskResponse::from(pending))
+                        });
+
+                    Ok(Some(FieldValue::list(tasks)))
+                })
+            },
+        )
+        .description(
+            "Return materializer tasks which are waiting in the queue or currently being \
+            processed, the longest waiting first. Can be used to find out why documents are not \
+            materialized yet. Requires the admin token of the node.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use async_graphql::Response;
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::document_id;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn pending_tasks_query(document_id: DocumentId) {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.admin_token = Some("secret".into());
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let queued_at = SystemTime::UNIX_EPOCH + Duration::from_millis(100);
+            let pending = node.context.status.pending_tasks();
+            pending.on_queued(
+                0,
+                Task::new("reduce", TaskInput::DocumentId(document_id.clone())),
+                queued_at,
+            );
+
+            let client = http_test_client(&node).await;
+            let query = json!({
+                "query": r#"{
+                    pendingTasks {
+                        name,
+                        documentId,
+                        viewId,
+                        queuedAt,
+                        startedAt
+                    }
+                }"#,
+            });
+
+            // Requests without the admin token are rejected
+            let response: Response = client
+                .post("/graphql")
+                .json(&query)
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(response.errors[0].message, "Admin token required");
+
+            let response: Response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&query)
+                .send()
+                .await
+                .json()
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "pendingTasks": [{
+                        "name": "reduce",
+                        "documentId": document_id.to_string(),
+                        "viewId": null,
+                        "queuedAt": 100,
+                        "startedAt": null,
+                    }]
+                })
+            );
+        })
+    }
+}
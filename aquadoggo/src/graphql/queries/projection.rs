@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::str::FromStr;
+
+use async_graphql::dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef};
+use log::debug;
+use p2panda_rs::document::DocumentId;
+
+use crate::db::query::DEFAULT_PAGE_SIZE;
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::utils::projection_name;
+use crate::projections::Projection;
+
+/// Adds a GraphQL query for retrieving the rows of a projection to the passed root query object.
+///
+/// The query follows the format `<name>(first: <...>, after: <...>)` and returns rows ordered by
+/// document id.
+pub fn build_projection_query(query: Object, projection: &Projection) -> Object {
+    let query_projection = projection.clone();
+
+    query.field(
+        Field::new(
+            projection.name.clone(),
+            TypeRef::named_nn_list_nn(projection_name(projection)),
+            move |ctx| {
+                let projection = query_projection.clone();
+                debug!("Query to projection {} received", projection.name);
+
+                FieldFuture::new(async move {
+                    // Parse arguments.
+                    let first = ctx.args.try_get(constants::PAGINATION_FIRST_ARG)?.u64()?;
+                    let after = match ctx.args.get(constants::PAGINATION_AFTER_ARG) {
+                        Some(value) => Some(DocumentId::from_str(value.string()?)?),
+                        None => None,
+                    };
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    let rows = store
+                        .get_projection_rows(&projection, first, after.as_ref())
+                        .await?;
+
+                    Ok(Some(FieldValue::list(
+                        rows.into_iter().map(FieldValue::owned_any),
+                    )))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(
+                constants::PAGINATION_FIRST_ARG,
+                TypeRef::named(TypeRef::INT),
+            )
+            .description("Number of rows we want from this request")
+            .default_value(DEFAULT_PAGE_SIZE),
+        )
+        .argument(
+            InputValue::new(
+                constants::PAGINATION_AFTER_ARG,
+                TypeRef::named(constants::DOCUMENT_ID),
+            )
+            .description("Document id of the row we wish to start paginating from"),
+        )
+        .description(format!(
+            "Query rows of the `{}` projection, derived from `{}` documents.",
+            projection.name,
+            projection.schema_id.name()
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::projections::{rebuild_projections, Projection, ProjectionField};
+    use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn projection_query(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "events",
+                vec![("title", FieldType::String), ("year", FieldType::Integer)],
+                &key_pair,
+            )
+            .await;
+
+            let view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("title", "Panda Party".into()), ("year", 2024.into())],
+                &key_pair,
+            )
+            .await;
+
+            // Configure projection on node
+            let projection = Projection::new(
+                "events",
+                schema.id().to_owned(),
+                vec![
+                    ProjectionField {
+                        name: "year".into(),
+                        expression: "year".parse().unwrap(),
+                    },
+                    ProjectionField {
+                        name: "title_length".into(),
+                        expression: "length(title)".parse().unwrap(),
+                    },
+                ],
+            )
+            .unwrap();
+            let mut config = node.context.config.clone();
+            config.projections.push(projection);
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+            rebuild_projections(&node.context.store, &node.context.config.projections)
+                .await
+                .unwrap();
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": r#"{
+                        events(first: 10) {
+                            documentId,
+                            year,
+                            title_length
+                        }
+                    }"#,
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "events": [{
+                        "documentId": view_id.to_string(),
+                        "year": 2024,
+                        "title_length": 11
+                    }]
+                })
+            );
+        })
+    }
+}
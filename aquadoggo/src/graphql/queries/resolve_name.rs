@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, TypeRef};
+use dynamic_graphql::{FieldValue, ScalarValue};
+use log::debug;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::scalars::PublicKeyScalar;
+
+/// Add "resolveName" query to the root query object.
+pub fn build_resolve_name_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::RESOLVE_NAME_QUERY,
+            TypeRef::named(constants::PUBLIC_KEY),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments.
+                    let name = ctx.args.try_get(constants::NAME_ARG)?.string()?.to_owned();
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    debug!("Query to resolveName received for name {}", name);
+
+                    match store.resolve_name(&name).await? {
+                        Some(public_key) => Ok(Some(FieldValue::value(
+                            PublicKeyScalar::from(public_key).to_value(),
+                        ))),
+                        None => Ok(FieldValue::NONE),
+                    }
+                })
+            },
+        )
+        .argument(
+            InputValue::new(constants::NAME_ARG, TypeRef::named_nn(TypeRef::STRING))
+                .description("Name claimed by an author."),
+        )
+        .description(
+            "Return the public key of the author owning the given name. Names are claimed in the \
+            documents of the schema configured for name claims on this node, names claimed by \
+            several authors belong to the one who created the document with the lowest id.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use crate::context::Context;
+    use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
+    use crate::NameClaims;
+
+    #[rstest]
+    fn resolve_names(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "profiles",
+                vec![("username", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            // Configure name claims on node
+            let mut config = node.context.config.clone();
+            config.name_claims = Some(NameClaims {
+                schema_id: schema.id().to_owned(),
+                field: "username".into(),
+            });
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            // Two authors claim the same name, the second one claims another name as well
+            let other_key_pair = KeyPair::new();
+            let mut claims = Vec::new();
+            for (username, key_pair) in [
+                ("panda", &key_pair),
+                ("panda", &other_key_pair),
+                ("bamboo", &other_key_pair),
+            ] {
+                let view_id = add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("username", username.into())],
+                    key_pair,
+                )
+                .await;
+                claims.push((view_id.to_string(), key_pair.public_key().to_string()));
+            }
+
+            let panda_owner = if claims[0].0 < claims[1].0 {
+                &claims[0].1
+            } else {
+                &claims[1].1
+            };
+            let first_owner_name = if panda_owner == &claims[0].1 {
+                json!("panda")
+            } else {
+                Value::Null
+            };
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            panda: resolveName(name: "panda"),
+                            bamboo: resolveName(name: "bamboo"),
+                            unknown: resolveName(name: "unknown"),
+                            profile: {}(id: "{}") {{
+                                meta {{
+                                    ownerName
+                                }}
+                            }}
+                        }}"#,
+                        schema.id(),
+                        claims[0].0,
+                    ),
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "panda": panda_owner,
+                    "bamboo": claims[2].1,
+                    "unknown": null,
+                    "profile": {
+                        "meta": {
+                            "ownerName": first_owner_name,
+                        }
+                    }
+                })
+            );
+        })
+    }
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::str::FromStr;
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, TypeRef};
+use async_graphql::Error;
+use dynamic_graphql::FieldValue;
+use log::debug;
+use p2panda_rs::schema::SchemaId;
+
+use crate::graphql::constants;
+use crate::graphql::responses::SchemaFieldsResponse;
+use crate::schema::SchemaProvider;
+
+/// Add "schemaFields" query to the root query object.
+pub fn build_schema_fields_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::SCHEMA_FIELDS_QUERY,
+            TypeRef::named_nn(constants::SCHEMA_FIELDS),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments.
+                    let schema_id = ctx.args.try_get(constants::SCHEMA_ID_ARG)?;
+                    let schema_id = SchemaId::from_str(schema_id.string()?)?;
+                    let schema_provider = ctx.data_unchecked::<SchemaProvider>();
+
+                    debug!("Query to schemaFields received for schema {}", schema_id);
+
+                    match schema_provider.get(&schema_id).await {
+                        Some(schema) => Ok(Some(FieldValue::owned_any(
+                            SchemaFieldsResponse::from(&schema),
+                        ))),
+                        None => Err(Error::new(format!(
+                            "Schema {} is not supported by this node",
+                            schema_id
+                        ))),
+                    }
+                })
+            },
+        )
+        .argument(
+            InputValue::new(constants::SCHEMA_ID_ARG, TypeRef::named_nn(TypeRef::STRING))
+                .description("Id of the schema to look up the fields of."),
+        )
+        .description(
+            "Return the description and fields of a schema supported by this node, with their \
+            types and the schemas they relate to.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::{FieldType, SchemaId};
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::test_utils::{add_schema, http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn schema_fields_query(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "gallery",
+                vec![
+                    ("title", FieldType::String),
+                    ("images", FieldType::RelationList(SchemaId::Blob(1))),
+                ],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            schemaFields(schemaId: "{}") {{
+                                description,
+                                fields {{ name, type, target, isList, isBlob }}
+                            }}
+                        }}"#,
+                        schema.id()
+                    ),
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "schemaFields": {
+                        "description": schema.description().to_string(),
+                        "fields": [
+                            {
+                                "name": "images",
+                                "type": "relation_list",
+                                "target": "blob_v1",
+                                "isList": true,
+                                "isBlob": true,
+                            },
+                            {
+                                "name": "title",
+                                "type": "str",
+                                "target": null,
+                                "isList": false,
+                                "isBlob": false,
+                            },
+                        ],
+                    }
+                })
+            );
+        })
+    }
+
+    #[rstest]
+    fn unknown_schema() {
+        test_runner(|node: TestNode| async move {
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": r#"{ schemaFields(schemaId: "venues_0020c65567ae37efea293e34a9c7d13f8f2bf23dbdc3b5c7b9ab46293111c48fc78b") { description } }"#,
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            assert_eq!(
+                response.errors[0].message,
+                "Schema venues_0020c65567ae37efea293e34a9c7d13f8f2bf23dbdc3b5c7b9ab46293111c48fc78b is not supported by this node"
+            );
+        })
+    }
+}
@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::time::Duration;
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, ResolverContext, TypeRef};
+use async_graphql::{Error, Value};
+use dynamic_graphql::ScalarValue;
+use log::debug;
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::document::{DocumentId, DocumentViewId};
+use p2panda_rs::schema::SchemaId;
+use p2panda_rs::storage_provider::traits::DocumentStore;
+
+use crate::blobs::BlobAccess;
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::mutations::AdminRequest;
+use crate::graphql::scalars::{DocumentIdScalar, DocumentViewIdScalar};
+
+/// Lifetime of signed blob URLs when no other value was requested.
+const DEFAULT_LIFETIME: u64 = 60 * 60;
+
+/// Maximum lifetime of signed blob URLs.
+const MAX_LIFETIME: u64 = 60 * 60 * 24 * 7;
+
+/// Add "signedBlobUrl" query to the root query object.
+pub fn build_signed_blob_url_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::SIGNED_BLOB_URL_QUERY,
+            TypeRef::named_nn(TypeRef::STRING),
+            |ctx| {
+                FieldFuture::new(async move {
+                    if ctx.data_opt::<AdminRequest>().is_none() {
+                        return Err(Error::new("Admin token required"));
+                    }
+
+                    let (document_id, document_view_id, lifetime) = parse_arguments(&ctx)?;
+                    let document_id: DocumentId = (&document_id).into();
+                    let document_view_id: Option<DocumentViewId> =
+                        document_view_id.map(|view_id| view_id.into());
+                    let store = ctx.data_unchecked::<SqlStore>();
+                    let blob_access = ctx.data_unchecked::<BlobAccess>();
+
+                    debug!("Query to signedBlobUrl received for blob {}", document_id);
+
+                    // Make sure the requested document (view) exists and is a blob
+                    let document = match &document_view_id {
+                        Some(view_id) => store.get_document_by_view_id(view_id).await?,
+                        None => store.get_document(&document_id).await?,
+                    };
+
+                    match document {
+                        Some(document)
+                            if document.id() == &document_id
+                                && document.schema_id() == &SchemaId::Blob(1) => {}
+                        _ => return Err(Error::new("Blob not found")),
+                    }
+
+                    let path = match document_view_id {
+                        Some(view_id) => format!("/blobs/{}/{}", document_id, view_id),
+                        None => format!("/blobs/{}", document_id),
+                    };
+
+                    let signed_path = blob_access.sign_path_for(&path, lifetime);
+                    Ok(Some(Value::from(signed_path)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(
+                constants::DOCUMENT_ID_ARG,
+                TypeRef::named_nn(constants::DOCUMENT_ID),
+            )
+            .description("Id of the blob document"),
+        )
+        .argument(
+            InputValue::new(
+                constants::DOCUMENT_VIEW_ID_ARG,
+                TypeRef::named(constants::DOCUMENT_VIEW_ID),
+            )
+            .description("Optional view id to sign the URL of a specific version of the blob"),
+        )
+        .argument(
+            InputValue::new(constants::EXPIRES_IN_ARG, TypeRef::named(TypeRef::INT))
+                .description("Seconds until the URL expires, defaults to one hour, max. one week"),
+        )
+        .description(
+            "Return a signed, expiring path under which the blob can be accessed from any origin. \
+            Requires the admin token of the node.",
+        ),
+    )
+}
+
+/// Parse and validate the arguments passed into this query.
+fn parse_arguments(
+    ctx: &ResolverContext,
+) -> Result<(DocumentIdScalar, Option<DocumentViewIdScalar>, Duration), Error> {
+    let mut document_id = None;
+    let mut document_view_id = None;
+    let mut lifetime = DEFAULT_LIFETIME;
+
+    for (name, value) in ctx.field().arguments()?.into_iter() {
+        match name.as_str() {
+            constants::DOCUMENT_ID_ARG => {
+                document_id = Some(DocumentIdScalar::from_value(value)?);
+            }
+            constants::DOCUMENT_VIEW_ID_ARG => {
+                document_view_id = Some(DocumentViewIdScalar::from_value(value)?);
+            }
+            constants::EXPIRES_IN_ARG => {
+                let seconds = match value {
+                    Value::Number(number) => number.as_u64(),
+                    _ => None,
+                };
+
+                lifetime = match seconds {
+                    Some(seconds) if seconds > 0 && seconds <= MAX_LIFETIME => seconds,
+                    _ => {
+                        return Err(Error::new(format!(
+                            "`expiresIn` needs to be between 1 and {MAX_LIFETIME} seconds"
+                        )))
+                    }
+                };
+            }
+            _ => (),
+        }
+    }
+
+    // We can unwrap here as the "id" argument is required by the GraphQL schema
+    Ok((
+        document_id.expect("Document id argument is required"),
+        document_view_id,
+        Duration::from_secs(lifetime),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use http::{header, StatusCode};
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::{document_id, key_pair};
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::config::{AllowList, Configuration};
+    use crate::materializer::tasks::blob_task;
+    use crate::materializer::TaskInput;
+    use crate::test_utils::{
+        add_blob, http_test_client, test_runner_with_manager, TestNodeManager,
+    };
+
+    #[rstest]
+    fn access_blob_via_signed_url(key_pair: KeyPair) {
+        test_runner_with_manager(|manager: TestNodeManager| async move {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let config = Configuration {
+                blobs_base_path: temp_dir.path().to_path_buf(),
+                blobs_allowed_origins: AllowList::Set(vec!["https://panda.org".into()]),
+                admin_token: Some("secret".into()),
+                ..Configuration::default()
+            };
+            let mut node = manager.create_with_config(config).await;
+
+            let blob_view_id =
+                add_blob(&mut node, b"Hello, World!", 6, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+            blob_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(blob_view_id),
+            )
+            .await
+            .unwrap();
+
+            let client = http_test_client(&node).await;
+
+            // Other origins are not allowed to access the blob
+            let response = client
+                .get(&format!("/blobs/{}", document_id))
+                .header(header::ORIGIN, "https://hotlink.com")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            // The meta data is protected as well
+            let response = client
+                .get(&format!("/blobs/{}/meta", document_id))
+                .header(header::ORIGIN, "https://hotlink.com")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let query = json!({
+                "query": format!(
+                    r#"{{ url: signedBlobUrl(id: "{}", expiresIn: 60) }}"#,
+                    document_id
+                ),
+            });
+
+            // Only requests with the admin token can sign URLs
+            let response: Response = client
+                .post("/graphql")
+                .json(&query)
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(response.errors[0].message, "Admin token required");
+
+            let response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&query)
+                .send()
+                .await;
+            let response: Response = response.json().await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+            let url = response.data.into_json().unwrap()["url"]
+                .as_str()
+                .unwrap()
+                .to_string();
+            assert!(url.starts_with(&format!("/blobs/{}?expires=", document_id)));
+
+            // Signed URLs can be accessed from any origin
+            let response = client
+                .get(&url)
+                .header(header::ORIGIN, "https://hotlink.com")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.text().await, "Hello, World!");
+
+            // Tampering with the signed URL makes it invalid
+            let response = client
+                .get(&url.replace("expires=", "expires=1"))
+                .header(header::ORIGIN, "https://hotlink.com")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        });
+    }
+
+    #[rstest]
+    fn sign_unknown_blob(document_id: DocumentId) {
+        test_runner_with_manager(|manager: TestNodeManager| async move {
+            let node = manager
+                .create_with_config(Configuration {
+                    admin_token: Some("secret".into()),
+                    ..Configuration::default()
+                })
+                .await;
+            let client = http_test_client(&node).await;
+
+            let response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&json!({
+                    "query": format!(r#"{{ url: signedBlobUrl(id: "{}") }}"#, document_id),
+                }))
+                .send()
+                .await;
+            let response: Response = response.json().await;
+            assert_eq!(response.errors[0].message, "Blob not found");
+        });
+    }
+}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::time::{Duration, SystemTime};
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, ResolverContext, TypeRef};
+use async_graphql::Error;
+use dynamic_graphql::FieldValue;
+use log::debug;
+
+use crate::graphql::constants;
+use crate::graphql::mutations::AdminRequest;
+use crate::graphql::responses::TaskTimingResponse;
+use crate::materializer::TIMELINE_RETENTION;
+use crate::status::NodeStatus;
+
+/// Time span in minutes of the returned timeline when no other value was requested.
+const DEFAULT_MINUTES: u64 = 10;
+
+/// Add "taskTimeline" query to the root query object.
+pub fn build_task_timeline_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::TASK_TIMELINE_QUERY,
+            TypeRef::named_nn_list_nn(constants::TASK_TIMING),
+            |ctx| {
+                FieldFuture::new(async move {
+                    if ctx.data_opt::<AdminRequest>().is_none() {
+                        return Err(Error::new("Admin token required"));
+                    }
+
+                    let minutes = parse_arguments(&ctx)?;
+                    let status = ctx.data_unchecked::<NodeStatus>();
+
+                    debug!("Query to taskTimeline received for {} minutes", minutes);
+
+                    let since = SystemTime::now() - Duration::from_secs(minutes * 60);
+                    let timings = status
+                        .task_timeline()
+                        .since(since)
+                        .into_iter()
+                        .map(|timing| FieldValue::owned_any(TaskTimingResponse::from(timing)));
+
+                    Ok(Some(FieldValue::list(timings)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(constants::MINUTES_ARG, TypeRef::named(TypeRef::INT)).description(
+                "Return tasks which finished within this many minutes, defaults to 10, max. 60",
+            ),
+        )
+        .description(
+            "Return the timing of recently processed materializer tasks, ordered by the time \
+            they started. Can be used to visualise the work of the materializer in a waterfall \
+            diagram and find bottlenecks. Requires the admin token of the node.",
+        ),
+    )
+}
+
+/// Parse and validate the arguments passed into this query.
+fn parse_arguments(ctx: &ResolverContext) -> Result<u64, Error> {
+    let max_minutes = TIMELINE_RETENTION.as_secs() / 60;
+
+    match ctx.args.get(constants::MINUTES_ARG) {
+        Some(value) => match value.u64() {
+            Ok(minutes) if minutes > 0 && minutes <= max_minutes => Ok(minutes),
+            _ => Err(Error::new(format!(
+                "`minutes` needs to be between 1 and {max_minutes}"
+            ))),
+        },
+        None => Ok(DEFAULT_MINUTES),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use async_graphql::Response;
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::document_id;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::materializer::{Task, TaskInput, TaskTiming};
+    use crate::test_utils::{http_test_client, test_runner, TestNode};
+
+    #[rstest]
+    fn task_timeline_query(document_id: DocumentId) {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.admin_token = Some("secret".into());
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let now = SystemTime::now();
+            let timeline = node.context.status.task_timeline();
+
+            for (name, minutes_ago) in [("reduce", 30), ("dependency", 1)] {
+                let started_at = now - Duration::from_secs(minutes_ago * 60);
+                timeline.record(TaskTiming {
+                    task: Task::new(name, TaskInput::DocumentId(document_id.clone())),
+                    queued_at: started_at - Duration::from_millis(50),
+                    started_at,
+                    finished_at: started_at + Duration::from_millis(200),
+                });
+            }
+
+            let client = http_test_client(&node).await;
+            let query = |minutes: u64| {
+                json!({
+                    "query": format!(
+                        r#"{{
+                            taskTimeline(minutes: {minutes}) {{
+                                name,
+                                documentId,
+                                viewId,
+                                duration
+                            }}
+                        }}"#
+                    ),
+                })
+            };
+
+            // Requests without the admin token are rejected
+            let response = client
+                .post("/graphql")
+                .json(&query(5))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+            assert_eq!(response.errors[0].message, "Admin token required");
+
+            let response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&query(5))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "taskTimeline": [{
+                        "name": "dependency",
+                        "documentId": document_id.to_string(),
+                        "viewId": null,
+                        "duration": 200,
+                    }]
+                })
+            );
+
+            let response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&query(60))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+            let data = response.data.into_json().unwrap();
+            assert_eq!(data["taskTimeline"].as_array().unwrap().len(), 2);
+            assert_eq!(data["taskTimeline"][0]["name"], "reduce");
+
+            // Time spans longer than the retention period are rejected
+            let response = client
+                .post("/graphql")
+                .header("Authorization", "Bearer secret")
+                .json(&query(61))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+            assert!(!response.errors.is_empty());
+        })
+    }
+}
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::str::FromStr;
+
+use async_graphql::dynamic::{Field, FieldFuture, InputValue, Object, TypeRef};
+use dynamic_graphql::FieldValue;
+use log::debug;
+use p2panda_rs::schema::SchemaId;
+
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::responses::UniqueConflictResponse;
+
+/// Add "uniqueConflicts" query to the root query object.
+pub fn build_unique_conflicts_query(query: Object) -> Object {
+    query.field(
+        Field::new(
+            constants::UNIQUE_CONFLICTS_QUERY,
+            TypeRef::named_nn_list_nn(constants::UNIQUE_CONFLICT),
+            |ctx| {
+                FieldFuture::new(async move {
+                    // Parse arguments.
+                    let schema_id = ctx.args.try_get(constants::SCHEMA_ID_ARG)?;
+                    let schema_id = SchemaId::from_str(schema_id.string()?)?;
+                    let store = ctx.data_unchecked::<SqlStore>();
+
+                    debug!("Query to uniqueConflicts received for schema {}", schema_id);
+
+                    let conflicts = store
+                        .get_unique_conflicts(&schema_id)
+                        .await?
+                        .into_iter()
+                        .map(|conflict| {
+                            FieldValue::owned_any(UniqueConflictResponse::from(conflict))
+                        });
+
+                    Ok(Some(FieldValue::list(conflicts)))
+                })
+            },
+        )
+        .argument(
+            InputValue::new(constants::SCHEMA_ID_ARG, TypeRef::named_nn(TypeRef::STRING))
+                .description("Id of the schema to look up conflicts for."),
+        )
+        .description(
+            "Return documents which violate one of the unique constraints configured on this node \
+            for the given schema.",
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Response;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::context::Context;
+    use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
+    use crate::UniqueConstraint;
+
+    #[rstest]
+    fn unique_conflicts_query(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "usernames",
+                vec![("username", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            // Configure constraint on node
+            let mut config = node.context.config.clone();
+            config.unique_constraints.push(UniqueConstraint {
+                schema_id: schema.id().to_owned(),
+                fields: vec!["username".into()],
+            });
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let mut document_ids = Vec::new();
+            for username in ["panda", "bamboo", "panda"] {
+                let view_id = add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("username", username.into())],
+                    &key_pair,
+                )
+                .await;
+                document_ids.push(view_id.to_string());
+            }
+
+            let mut conflicting = vec![document_ids[0].clone(), document_ids[2].clone()];
+            conflicting.sort();
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            uniqueConflicts(schemaId: "{}") {{
+                                constraint,
+                                winner,
+                                documents
+                            }}
+                        }}"#,
+                        schema.id()
+                    ),
+                }))
+                .send()
+                .await
+                .json::<Response>()
+                .await;
+
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "uniqueConflicts": [{
+                        "constraint": "username",
+                        "winner": conflicting[0],
+                        "documents": conflicting,
+                    }]
+                })
+            );
+        })
+    }
+}
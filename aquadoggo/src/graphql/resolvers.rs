@@ -6,14 +6,16 @@ use dynamic_graphql::FieldValue;
 use p2panda_rs::document::traits::AsDocument;
 use p2panda_rs::operation::OperationValue;
 use p2panda_rs::schema::{FieldType, Schema};
-use p2panda_rs::storage_provider::traits::DocumentStore;
 
+use crate::db::query::Select;
 use crate::db::stores::{PaginationCursor, PaginationData, RelationList};
 use crate::db::types::StorageDocument;
 use crate::db::SqlStore;
 use crate::graphql::objects::DocumentMeta;
 use crate::graphql::scalars::{DocumentIdScalar, DocumentViewIdScalar};
-use crate::graphql::utils::{get_document_from_params, gql_scalar, parse_collection_arguments};
+use crate::graphql::utils::{
+    get_document_from_params, gql_scalar, parse_collection_arguments, selected_document_fields,
+};
 use crate::schema::SchemaProvider;
 
 /// Document data passed between resolvers.
@@ -52,10 +54,14 @@ pub async fn resolve_document(
 ) -> Result<Option<FieldValue>, Error> {
     let store = ctx.data_unchecked::<SqlStore>();
 
-    let document = match get_document_from_params(store, &document_id, &document_view_id).await? {
-        Some(document) => Resolved::Document(document),
-        None => return Ok(FieldValue::NONE),
-    };
+    // Only retrieve the values of the fields selected in the query
+    let select = Select::new(&selected_document_fields(&ctx.field()));
+
+    let document =
+        match get_document_from_params(store, &document_id, &document_view_id, &select).await? {
+            Some(document) => Resolved::Document(document),
+            None => return Ok(FieldValue::NONE),
+        };
 
     // Pass it up to resolve all fields of document
     Ok(Some(FieldValue::owned_any(document)))
@@ -100,6 +106,7 @@ pub async fn resolve_document_meta(
         document_id: document.id().into(),
         document_view_id: document.view_id().into(),
         owner: document.author().to_owned().into(),
+        deleted: document.is_deleted(),
     };
 
     Ok(Some(FieldValue::owned_any(document_meta)))
@@ -137,7 +144,11 @@ pub async fn resolve_document_field(
     {
         // Relation fields are expected to resolve to the related document
         OperationValue::Relation(relation) => {
-            let document = match store.get_document(relation.document_id()).await? {
+            let select = Select::new(&selected_document_fields(&ctx.field()));
+            let document = match store
+                .get_document_with_select(relation.document_id(), &select)
+                .await?
+            {
                 Some(document) => document,
                 None => return Ok(FieldValue::NONE),
             };
@@ -147,7 +158,11 @@ pub async fn resolve_document_field(
         }
         // Pinned relation behaves the same as relation but passes along a document view id
         OperationValue::PinnedRelation(relation) => {
-            let document = match store.get_document_by_view_id(relation.view_id()).await? {
+            let select = Select::new(&selected_document_fields(&ctx.field()));
+            let document = match store
+                .get_document_by_view_id_with_select(relation.view_id(), &select)
+                .await?
+            {
                 Some(document) => document,
                 None => return Ok(FieldValue::NONE),
             };
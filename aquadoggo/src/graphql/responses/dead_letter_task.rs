@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `deadLetterTasks` query.
+use dynamic_graphql::SimpleObject;
+
+use crate::db::types::DeadLetterTask;
+use crate::graphql::scalars::{DocumentIdScalar, DocumentViewIdScalar};
+use crate::materializer::TaskInput;
+
+/// Materializer task which failed too many times and is not retried anymore.
+#[derive(SimpleObject)]
+#[graphql(name = "DeadLetterTask")]
+pub struct DeadLetterTaskResponse {
+    /// Name of the worker which processes this task.
+    pub name: String,
+
+    /// Document id the task was dispatched for.
+    #[graphql(name = "documentId")]
+    pub document_id: Option<DocumentIdScalar>,
+
+    /// Document view id the task was dispatched for.
+    #[graphql(name = "viewId")]
+    pub view_id: Option<DocumentViewIdScalar>,
+
+    /// Number of failed attempts to process this task.
+    pub attempts: u64,
+
+    /// Error message of the last failed attempt.
+    pub error: String,
+
+    /// UNIX timestamp in seconds of the last failed attempt.
+    #[graphql(name = "failedAt")]
+    pub failed_at: u64,
+}
+
+impl From<DeadLetterTask> for DeadLetterTaskResponse {
+    fn from(dead_letter_task: DeadLetterTask) -> Self {
+        let (document_id, view_id) = match dead_letter_task.task.input() {
+            TaskInput::DocumentId(document_id) => (Some(document_id.into()), None),
+            TaskInput::DocumentViewId(view_id) => (None, Some(view_id.into())),
+        };
+
+        Self {
+            name: dead_letter_task.task.worker_name().to_owned(),
+            document_id,
+            view_id,
+            attempts: dead_letter_task.attempts,
+            error: dead_letter_task.error,
+            failed_at: dead_letter_task.failed_at,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `entryChain` and `certificatePool` queries.
+use dynamic_graphql::SimpleObject;
+use p2panda_rs::entry::traits::{AsEncodedEntry, AsEntry};
+
+use crate::db::types::StorageEntry;
+use crate::graphql::scalars::{EncodedEntryScalar, EntryHashScalar, SeqNumScalar};
+
+/// Entry of a log with the hashes linking it to previous entries.
+#[derive(SimpleObject)]
+#[graphql(name = "LogEntry")]
+pub struct LogEntryResponse {
+    /// Sequence number of the entry.
+    #[graphql(name = "seqNum")]
+    pub seq_num: SeqNumScalar,
+
+    /// Hash of the entry.
+    pub hash: EntryHashScalar,
+
+    /// Hash of the entry backlink.
+    pub backlink: Option<EntryHashScalar>,
+
+    /// Hash of the entry skiplink.
+    pub skiplink: Option<EntryHashScalar>,
+
+    /// Signed and encoded entry.
+    #[graphql(name = "encodedEntry")]
+    pub encoded_entry: EncodedEntryScalar,
+}
+
+impl From<StorageEntry> for LogEntryResponse {
+    fn from(entry: StorageEntry) -> Self {
+        Self {
+            seq_num: (*entry.seq_num()).into(),
+            hash: entry.hash().into(),
+            backlink: entry.backlink().cloned().map(Into::into),
+            skiplink: entry.skiplink().cloned().map(Into::into),
+            encoded_entry: entry.encoded_entry.into(),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `materializedAggregate` query.
+use dynamic_graphql::SimpleObject;
+
+use crate::db::types::MaterializedAggregateGroup;
+
+/// Document count and summed up field values of one group of a materialized aggregate.
+#[derive(SimpleObject)]
+#[graphql(name = "MaterializedAggregateGroup")]
+pub struct MaterializedAggregateGroupResponse {
+    /// Value of the grouping field shared by all documents of this group, empty when the
+    /// aggregate is not grouped.
+    pub group: String,
+
+    /// Number of documents in this group.
+    pub count: u64,
+
+    /// Sums of the configured fields, fields which no document of this group holds a value for
+    /// are left out.
+    pub sums: Vec<AggregateSumResponse>,
+}
+
+/// Sum of the values of one field.
+#[derive(SimpleObject)]
+#[graphql(name = "AggregateSum")]
+pub struct AggregateSumResponse {
+    /// Name of the summed up field.
+    pub field: String,
+
+    /// Sum of the values of this field.
+    pub sum: f64,
+}
+
+impl From<MaterializedAggregateGroup> for MaterializedAggregateGroupResponse {
+    fn from(group: MaterializedAggregateGroup) -> Self {
+        Self {
+            group: group.group,
+            count: group.count,
+            sums: group
+                .sums
+                .into_iter()
+                .map(|(field, sum)| AggregateSumResponse { field, sum })
+                .collect(),
+        }
+    }
+}
@@ -1,5 +1,27 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod dead_letter_task;
+mod log_entry;
+mod materialized_aggregate;
 mod next_arguments;
+mod node_status;
+mod page_info;
+mod peer_author;
+mod pending_task;
+mod schema_fields;
+mod task_timing;
+mod unique_conflict;
 
+pub use dead_letter_task::DeadLetterTaskResponse;
+pub use log_entry::LogEntryResponse;
+pub use materialized_aggregate::{AggregateSumResponse, MaterializedAggregateGroupResponse};
 pub use next_arguments::NextArguments;
+pub use node_status::{
+    DocumentCountResponse, DryRunSchemaResponse, NodeStatusResponse, PeerStatusResponse,
+};
+pub use page_info::PageInfoResponse;
+pub use peer_author::PeerAuthorResponse;
+pub use pending_task::PendingTaskResponse;
+pub use schema_fields::{SchemaFieldResponse, SchemaFieldsResponse};
+pub use task_timing::TaskTimingResponse;
+pub use unique_conflict::UniqueConflictResponse;
@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `nodeStatus` query.
+use dynamic_graphql::SimpleObject;
+
+use crate::replication::DryRunSchemaStats;
+use crate::status::{DocumentCountReport, PeerReport, StatusReport};
+
+/// Status of the node.
+#[derive(SimpleObject)]
+#[graphql(name = "NodeStatus")]
+pub struct NodeStatusResponse {
+    /// Version of the node implementation.
+    pub version: String,
+
+    /// Database backend of the node, either "sqlite" or "postgres".
+    #[graphql(name = "databaseBackend")]
+    pub database_backend: String,
+
+    /// Number of schemas supported by the node.
+    #[graphql(name = "supportedSchemas")]
+    pub supported_schemas: u64,
+
+    /// Number of peers the node is currently connected to.
+    #[graphql(name = "connectedPeers")]
+    pub connected_peers: u64,
+
+    /// Number of materializer tasks which are queued up or currently being processed.
+    #[graphql(name = "materializerQueueDepth")]
+    pub materializer_queue_depth: u64,
+
+    /// Target sets negotiated for replication with connected peers.
+    pub peers: Vec<PeerStatusResponse>,
+
+    /// Number and size of entries per schema the node would have ingested, only filled when
+    /// running in replication dry-run mode.
+    #[graphql(name = "dryRun")]
+    pub dry_run: Vec<DryRunSchemaResponse>,
+
+    /// Number of documents of every supported schema.
+    #[graphql(name = "documentCounts")]
+    pub document_counts: Vec<DocumentCountResponse>,
+}
+
+/// Target set negotiated for replication with a connected peer.
+#[derive(SimpleObject)]
+#[graphql(name = "PeerStatus")]
+pub struct PeerStatusResponse {
+    /// Id of the peer.
+    #[graphql(name = "peerId")]
+    pub peer_id: String,
+
+    /// Schema ids supported by both the peer and this node.
+    #[graphql(name = "targetSet")]
+    pub target_set: Vec<String>,
+
+    /// UNIX timestamp in seconds of when the target set was negotiated.
+    #[graphql(name = "negotiatedAt")]
+    pub negotiated_at: u64,
+}
+
+/// Number and size of entries of one schema the node would have ingested.
+#[derive(SimpleObject)]
+#[graphql(name = "DryRunSchema")]
+pub struct DryRunSchemaResponse {
+    /// Id of the schema.
+    #[graphql(name = "schemaId")]
+    pub schema_id: String,
+
+    /// Number of entries.
+    pub entries: u64,
+
+    /// Size of the entries and their operations in bytes.
+    pub bytes: u64,
+}
+
+/// Number of documents of a schema stored on the node.
+#[derive(SimpleObject)]
+#[graphql(name = "DocumentCount")]
+pub struct DocumentCountResponse {
+    /// Id of the schema.
+    #[graphql(name = "schemaId")]
+    pub schema_id: String,
+
+    /// Number of documents which are not deleted.
+    pub documents: u64,
+}
+
+impl From<StatusReport> for NodeStatusResponse {
+    fn from(report: StatusReport) -> Self {
+        Self {
+            version: report.version,
+            database_backend: report.database_backend,
+            supported_schemas: report.supported_schemas,
+            connected_peers: report.connected_peers,
+            materializer_queue_depth: report.materializer_queue_depth,
+            peers: report.peers.into_iter().map(Into::into).collect(),
+            dry_run: report.dry_run.into_iter().map(Into::into).collect(),
+            document_counts: report.document_counts.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<PeerReport> for PeerStatusResponse {
+    fn from(report: PeerReport) -> Self {
+        Self {
+            peer_id: report.peer_id.to_string(),
+            target_set: report
+                .target_set
+                .iter()
+                .map(|schema_id| schema_id.to_string())
+                .collect(),
+            negotiated_at: report.negotiated_at,
+        }
+    }
+}
+
+impl From<DryRunSchemaStats> for DryRunSchemaResponse {
+    fn from(stats: DryRunSchemaStats) -> Self {
+        Self {
+            schema_id: stats.schema_id.to_string(),
+            entries: stats.entries,
+            bytes: stats.bytes,
+        }
+    }
+}
+
+impl From<DocumentCountReport> for DocumentCountResponse {
+    fn from(report: DocumentCountReport) -> Self {
+        Self {
+            schema_id: report.schema_id.to_string(),
+            documents: report.documents,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `pageInfo` field of paginated collections.
+use dynamic_graphql::SimpleObject;
+
+use crate::db::query::Cursor;
+use crate::db::stores::{PaginationCursor, PaginationData};
+
+/// Information about the current page of a paginated collection.
+#[derive(SimpleObject)]
+#[graphql(name = "PageInfo")]
+pub struct PageInfoResponse {
+    /// Boolean value denoting whether there is a next page available on this query.
+    #[graphql(name = "hasNextPage")]
+    pub has_next_page: bool,
+
+    /// Boolean value denoting whether there is a previous page available on this query.
+    #[graphql(name = "hasPreviousPage")]
+    pub has_previous_page: bool,
+
+    /// Cursor of the first document on this page.
+    #[graphql(name = "startCursor")]
+    pub start_cursor: Option<String>,
+
+    /// Cursor of the last document on this page, used to request the next page.
+    #[graphql(name = "endCursor")]
+    pub end_cursor: Option<String>,
+}
+
+impl From<PaginationData<PaginationCursor>> for PageInfoResponse {
+    fn from(page_info: PaginationData<PaginationCursor>) -> Self {
+        Self {
+            has_next_page: page_info.has_next_page,
+            has_previous_page: page_info.has_previous_page,
+            start_cursor: page_info.start_cursor.map(|cursor| cursor.encode()),
+            end_cursor: page_info.end_cursor.map(|cursor| cursor.encode()),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `peerAuthors` query.
+use dynamic_graphql::SimpleObject;
+
+use crate::db::types::PeerAuthor;
+use crate::graphql::scalars::PublicKeyScalar;
+
+/// Entries of one author which were received from one peer during replication.
+#[derive(SimpleObject)]
+#[graphql(name = "PeerAuthor")]
+pub struct PeerAuthorResponse {
+    /// Id of the peer the entries were received from.
+    #[graphql(name = "peerId")]
+    pub peer_id: String,
+
+    /// Public key of the author of the received entries.
+    #[graphql(name = "publicKey")]
+    pub public_key: PublicKeyScalar,
+
+    /// UNIX timestamp in seconds of the first received entry.
+    #[graphql(name = "firstSeen")]
+    pub first_seen: u64,
+
+    /// UNIX timestamp in seconds of the last received entry.
+    #[graphql(name = "lastSeen")]
+    pub last_seen: u64,
+
+    /// Number of received entries.
+    pub entries: u64,
+}
+
+impl From<PeerAuthor> for PeerAuthorResponse {
+    fn from(peer_author: PeerAuthor) -> Self {
+        Self {
+            peer_id: peer_author.peer_id,
+            public_key: peer_author.public_key.into(),
+            first_seen: peer_author.first_seen,
+            last_seen: peer_author.last_seen,
+            entries: peer_author.entries,
+        }
+    }
+}
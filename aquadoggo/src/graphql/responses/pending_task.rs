@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `pendingTasks` query.
+use dynamic_graphql::SimpleObject;
+
+use crate::graphql::responses::task_timing::unix_millis;
+use crate::graphql::scalars::{DocumentIdScalar, DocumentViewIdScalar};
+use crate::materializer::{PendingTask, TaskInput};
+
+/// Materializer task which is queued or currently being processed.
+#[derive(SimpleObject)]
+#[graphql(name = "PendingTask")]
+pub struct PendingTaskResponse {
+    /// Name of the worker which processes this task.
+    pub name: String,
+
+    /// Document id the task was dispatched for.
+    #[graphql(name = "documentId")]
+    pub document_id: Option<DocumentIdScalar>,
+
+    /// Document view id the task was dispatched for.
+    #[graphql(name = "viewId")]
+    pub view_id: Option<DocumentViewIdScalar>,
+
+    /// UNIX timestamp in milliseconds of when the task was queued.
+    #[graphql(name = "queuedAt")]
+    pub queued_at: u64,
+
+    /// UNIX timestamp in milliseconds of when a worker started processing the task, `null` if
+    /// the task is still waiting in the queue.
+    #[graphql(name = "startedAt")]
+    pub started_at: Option<u64>,
+}
+
+impl From<PendingTask<TaskInput>> for PendingTaskResponse {
+    fn from(pending: PendingTask<TaskInput>) -> Self {
+        let (document_id, view_id) = match pending.task.input() {
+            TaskInput::DocumentId(document_id) => (Some(document_id.into()), None),
+            TaskInput::DocumentViewId(view_id) => (None, Some(view_id.into())),
+        };
+
+        Self {
+            name: pending.task.worker_name().to_owned(),
+            document_id,
+            view_id,
+            queued_at: unix_millis(pending.queued_at),
+            started_at: pending.started_at.map(unix_millis),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `schemaFields` query.
+use dynamic_graphql::SimpleObject;
+use p2panda_rs::schema::{FieldType, Schema, SchemaId};
+
+/// Fields of a schema, for example to render generic editors for its documents.
+#[derive(SimpleObject)]
+#[graphql(name = "SchemaFields")]
+pub struct SchemaFieldsResponse {
+    /// Id of the schema.
+    #[graphql(name = "schemaId")]
+    pub schema_id: String,
+
+    /// Human-readable description of the schema given in its definition.
+    pub description: String,
+
+    /// All fields of the schema, sorted by their name.
+    pub fields: Vec<SchemaFieldResponse>,
+}
+
+/// Single field of a schema.
+#[derive(SimpleObject)]
+#[graphql(name = "SchemaField")]
+pub struct SchemaFieldResponse {
+    /// Name of the field.
+    pub name: String,
+
+    /// Type of the field without the schema it relates to, for example `str` or `relation_list`.
+    #[graphql(name = "type")]
+    pub field_type: String,
+
+    /// Id of the schema of related documents when this is a relation field.
+    pub target: Option<String>,
+
+    /// The field holds a list of relations.
+    #[graphql(name = "isList")]
+    pub is_list: bool,
+
+    /// The field relates to blobs, for example images which should be displayed with an upload
+    /// button instead of a document picker.
+    #[graphql(name = "isBlob")]
+    pub is_blob: bool,
+}
+
+impl SchemaFieldResponse {
+    fn new(name: &str, field_type: &FieldType) -> Self {
+        let (type_str, target, is_list) = match field_type {
+            FieldType::Boolean => ("bool", None, false),
+            FieldType::Bytes => ("bytes", None, false),
+            FieldType::Integer => ("int", None, false),
+            FieldType::Float => ("float", None, false),
+            FieldType::String => ("str", None, false),
+            FieldType::Relation(schema_id) => ("relation", Some(schema_id), false),
+            FieldType::RelationList(schema_id) => ("relation_list", Some(schema_id), true),
+            FieldType::PinnedRelation(schema_id) => ("pinned_relation", Some(schema_id), false),
+            FieldType::PinnedRelationList(schema_id) => {
+                ("pinned_relation_list", Some(schema_id), true)
+            }
+        };
+
+        Self {
+            name: name.to_owned(),
+            field_type: type_str.to_owned(),
+            is_blob: matches!(target, Some(SchemaId::Blob(_))),
+            target: target.map(|schema_id| schema_id.to_string()),
+            is_list,
+        }
+    }
+}
+
+impl From<&Schema> for SchemaFieldsResponse {
+    fn from(schema: &Schema) -> Self {
+        Self {
+            schema_id: schema.id().to_string(),
+            description: schema.description().to_string(),
+            fields: schema
+                .fields()
+                .iter()
+                .map(|(name, field_type)| SchemaFieldResponse::new(name, field_type))
+                .collect(),
+        }
+    }
+}
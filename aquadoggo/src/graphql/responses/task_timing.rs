@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `taskTimeline` query.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use dynamic_graphql::SimpleObject;
+
+use crate::graphql::scalars::{DocumentIdScalar, DocumentViewIdScalar};
+use crate::materializer::{TaskInput, TaskTiming};
+
+/// Timing of a materializer task which was processed recently.
+#[derive(SimpleObject)]
+#[graphql(name = "TaskTiming")]
+pub struct TaskTimingResponse {
+    /// Name of the worker which processed this task.
+    pub name: String,
+
+    /// Document id the task was dispatched for.
+    #[graphql(name = "documentId")]
+    pub document_id: Option<DocumentIdScalar>,
+
+    /// Document view id the task was dispatched for.
+    #[graphql(name = "viewId")]
+    pub view_id: Option<DocumentViewIdScalar>,
+
+    /// UNIX timestamp in milliseconds of when the task was queued.
+    #[graphql(name = "queuedAt")]
+    pub queued_at: u64,
+
+    /// UNIX timestamp in milliseconds of when a worker started processing the task.
+    #[graphql(name = "startedAt")]
+    pub started_at: u64,
+
+    /// UNIX timestamp in milliseconds of when the worker finished processing the task.
+    #[graphql(name = "finishedAt")]
+    pub finished_at: u64,
+
+    /// Milliseconds the worker spent processing the task.
+    pub duration: u64,
+}
+
+impl From<TaskTiming<TaskInput>> for TaskTimingResponse {
+    fn from(timing: TaskTiming<TaskInput>) -> Self {
+        let (document_id, view_id) = match timing.task.input() {
+            TaskInput::DocumentId(document_id) => (Some(document_id.into()), None),
+            TaskInput::DocumentViewId(view_id) => (None, Some(view_id.into())),
+        };
+
+        Self {
+            name: timing.task.worker_name().to_owned(),
+            document_id,
+            view_id,
+            queued_at: unix_millis(timing.queued_at),
+            started_at: unix_millis(timing.started_at),
+            finished_at: unix_millis(timing.finished_at),
+            duration: timing.duration().as_millis() as u64,
+        }
+    }
+}
+
+/// Returns the milliseconds passed since the UNIX epoch.
+pub(super) fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Return type for `uniqueConflicts` query.
+use dynamic_graphql::SimpleObject;
+
+use crate::db::types::UniqueConflict;
+use crate::graphql::scalars::DocumentIdScalar;
+
+/// Documents of the same schema which share the same values for a unique constraint.
+#[derive(SimpleObject)]
+#[graphql(name = "UniqueConflict")]
+pub struct UniqueConflictResponse {
+    /// Names of the fields of the violated unique constraint, separated by commas.
+    pub constraint: String,
+
+    /// Document which wins the conflict, this is the one with the lowest document id.
+    pub winner: DocumentIdScalar,
+
+    /// All conflicting documents, including the winner, sorted by their document id.
+    pub documents: Vec<DocumentIdScalar>,
+}
+
+impl From<UniqueConflict> for UniqueConflictResponse {
+    fn from(conflict: UniqueConflict) -> Self {
+        Self {
+            winner: conflict.winner().into(),
+            documents: conflict.document_ids.iter().map(Into::into).collect(),
+            constraint: conflict.constraint_name,
+        }
+    }
+}
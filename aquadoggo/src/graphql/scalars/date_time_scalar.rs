@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use dynamic_graphql::{Error, Result, Scalar, ScalarValue, Value};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Point in time, formatted as RFC 3339 string (for example `2024-07-03T09:00:00Z`).
+#[derive(Scalar, Clone, Copy, Debug, Eq, PartialEq)]
+#[graphql(name = "DateTime", validator(validate))]
+pub struct DateTimeScalar(OffsetDateTime);
+
+impl DateTimeScalar {
+    /// Returns the point in time of a UNIX timestamp in seconds.
+    pub fn from_unix_timestamp(timestamp: u64) -> Result<Self> {
+        let date_time = OffsetDateTime::from_unix_timestamp(timestamp as i64)?;
+        Ok(Self(date_time))
+    }
+
+    /// Returns the point in time as UNIX timestamp in seconds.
+    pub fn unix_timestamp(&self) -> i64 {
+        self.0.unix_timestamp()
+    }
+}
+
+impl ScalarValue for DateTimeScalar {
+    fn from_value(value: Value) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        match &value {
+            Value::String(str_value) => {
+                let date_time = OffsetDateTime::parse(str_value, &Rfc3339)?;
+                Ok(DateTimeScalar(date_time))
+            }
+            _ => Err(Error::new(format!(
+                "Expected a RFC 3339 formatted date and time, found: {value}"
+            ))),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(
+            self.0
+                .format(&Rfc3339)
+                .expect("Date and time should be representable in RFC 3339"),
+        )
+    }
+}
+
+impl From<OffsetDateTime> for DateTimeScalar {
+    fn from(date_time: OffsetDateTime) -> Self {
+        Self(date_time)
+    }
+}
+
+impl From<DateTimeScalar> for OffsetDateTime {
+    fn from(date_time: DateTimeScalar) -> OffsetDateTime {
+        date_time.0
+    }
+}
+
+/// Validation method used internally in `async-graphql` to check scalar values passed into the
+/// public api.
+fn validate(value: &Value) -> bool {
+    DateTimeScalar::from_value(value.to_owned()).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_graphql::{ScalarValue, Value};
+
+    use super::DateTimeScalar;
+
+    #[test]
+    fn scalar_type() {
+        // Convert to gql value
+        let scalar_value = DateTimeScalar::from_unix_timestamp(1720000800).unwrap();
+        let gql_value: Value = scalar_value.to_value();
+        assert_eq!(gql_value, Value::String("2024-07-03T10:00:00Z".into()));
+
+        // Convert back
+        let converted_value = DateTimeScalar::from_value(gql_value).unwrap();
+        assert_eq!(converted_value.unix_timestamp(), 1720000800);
+
+        // Other offsets are accepted as well
+        let converted_value =
+            DateTimeScalar::from_value(Value::String("2024-07-03T12:00:00+02:00".into())).unwrap();
+        assert_eq!(converted_value.unix_timestamp(), 1720000800);
+
+        // Convert invalid type
+        assert!(DateTimeScalar::from_value(Value::Boolean(true)).is_err());
+        assert!(DateTimeScalar::from_value(Value::String("yesterday".into())).is_err());
+    }
+}
@@ -8,6 +8,7 @@
 //! We use a naming convention of appending the item's GraphQL type (e.g. `Scalar`) when a p2panda
 //! item of the exact same name is being wrapped.
 mod cursor_scalar;
+mod date_time_scalar;
 mod document_id_scalar;
 mod document_view_id_scalar;
 mod encoded_entry_scalar;
@@ -19,6 +20,7 @@ mod public_key_scalar;
 mod seq_num_scalar;
 
 pub use cursor_scalar::CursorScalar;
+pub use date_time_scalar::DateTimeScalar;
 pub use document_id_scalar::DocumentIdScalar;
 pub use document_view_id_scalar::DocumentViewIdScalar;
 pub use encoded_entry_scalar::EncodedEntryScalar;
@@ -1,57 +1,118 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 //! Dynamically create and manage GraphQL schemas.
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use async_graphql::dynamic::{Field, FieldFuture, Object, Schema, TypeRef};
+use async_graphql::dynamic::{Field, FieldFuture, Object, Schema, Subscription, TypeRef};
 use async_graphql::{Request, Response, Value};
 use dynamic_graphql::internal::Registry;
 use log::{debug, info, warn};
 use p2panda_rs::Human;
 use tokio::sync::Mutex;
 
-use crate::bus::ServiceSender;
+use crate::blobs::BlobAccess;
+use crate::bus::{ServiceMessage, ServiceSender};
+use crate::config::Configuration;
 use crate::db::SqlStore;
+use crate::graphql::complexity::QueryComplexity;
+use crate::graphql::diff::GraphQLSchemaDiff;
 use crate::graphql::input_values::{
-    build_filter_input_object, build_order_enum_value, BooleanFilter, FloatFilter, HexBytesFilter,
-    IntegerFilter, MetaFilterInputObject, OrderDirection, PinnedRelationFilter,
-    PinnedRelationListFilter, RelationFilter, RelationListFilter, StringFilter,
+    build_filter_input_object, build_order_by_fields_input_object, build_order_enum_value,
+    BooleanFilter, FloatFilter, HexBytesFilter, IntegerFilter, MetaFilterInputObject,
+    OrderDirection, PinnedRelationFilter, PinnedRelationListFilter, RelationFilter,
+    RelationListFilter, StringFilter,
+};
+use crate::graphql::mutations::{
+    Admin, AllowedOperations, DeadLetters, MaxBatchEntries, MutationRoot, Publish, PublishInput,
+    PublishMany,
 };
-use crate::graphql::mutations::{MutationRoot, Publish};
 use crate::graphql::objects::{
+    build_aggregate_fields_object, build_aggregate_object, build_document_change_object,
     build_document_collection_object, build_document_fields_object, build_document_object,
-    build_paginated_document_object, DocumentMeta,
+    build_document_union, build_paginated_document_object, build_projection_object,
+    DocumentHistoryItem, DocumentMeta, DocumentMetaBranches, DocumentMetaHistory,
+    DocumentMetaOwnerName, DocumentMetaTimestamps,
 };
 use crate::graphql::queries::{
-    build_collection_query, build_document_query, build_next_args_query,
+    build_aggregate_query, build_certificate_pool_query, build_collection_query,
+    build_dead_letter_tasks_query, build_document_query, build_documents_by_author_query,
+    build_documents_query, build_entry_chain_query, build_materialized_aggregate_query,
+    build_next_args_query, build_node_status_query, build_peer_authors_query,
+    build_pending_tasks_query, build_projection_query, build_resolve_name_query,
+    build_schema_fields_query, build_signed_blob_url_query, build_task_timeline_query,
+    build_unique_conflicts_query,
+};
+use crate::graphql::responses::{
+    AggregateSumResponse, DeadLetterTaskResponse, DocumentCountResponse, DryRunSchemaResponse,
+    LogEntryResponse, MaterializedAggregateGroupResponse, NextArguments, NodeStatusResponse,
+    PageInfoResponse, PeerAuthorResponse, PeerStatusResponse, PendingTaskResponse,
+    SchemaFieldResponse, SchemaFieldsResponse, TaskTimingResponse, UniqueConflictResponse,
 };
-use crate::graphql::responses::NextArguments;
 use crate::graphql::scalars::{
-    CursorScalar, DocumentIdScalar, DocumentViewIdScalar, EncodedEntryScalar,
+    CursorScalar, DateTimeScalar, DocumentIdScalar, DocumentViewIdScalar, EncodedEntryScalar,
     EncodedOperationScalar, EntryHashScalar, HexBytesScalar, LogIdScalar, PublicKeyScalar,
     SeqNumScalar,
 };
+use crate::graphql::subscriptions::build_document_changes_subscription;
 use crate::schema::SchemaProvider;
+use crate::status::NodeStatus;
 
 /// Dynamically generates and returns a new GraphQL API root schema based on the currently
 /// registered p2panda schemas.
+///
+/// Projections, the log id policy, query limits and page sizes are taken from the given
+/// configuration.
 pub async fn build_root_schema(
     store: SqlStore,
     tx: ServiceSender,
     schema_provider: SchemaProvider,
+    blob_access: BlobAccess,
+    status: NodeStatus,
+    config: &Configuration,
 ) -> Result<Schema, async_graphql::dynamic::SchemaError> {
     let all_schema = schema_provider.all().await;
 
+    // Relation fields of all schemas count more towards the complexity of a query
+    let query_complexity = config
+        .max_query_complexity
+        .map(|max_complexity| QueryComplexity::new(max_complexity, &all_schema));
+
     // Using dynamic-graphql we create a registry and add types
     let registry = Registry::new()
         // Register mutation operations
         .register::<MutationRoot>()
         .register::<Publish>()
+        .register::<PublishMany>()
+        .register::<DeadLetters>()
+        .register::<AllowedOperations>()
+        .register::<Admin>()
         // Register responses
         .register::<NextArguments>()
+        .register::<PageInfoResponse>()
+        .register::<UniqueConflictResponse>()
+        .register::<MaterializedAggregateGroupResponse>()
+        .register::<AggregateSumResponse>()
+        .register::<DeadLetterTaskResponse>()
+        .register::<PeerAuthorResponse>()
+        .register::<LogEntryResponse>()
+        .register::<SchemaFieldsResponse>()
+        .register::<SchemaFieldResponse>()
+        .register::<NodeStatusResponse>()
+        .register::<PeerStatusResponse>()
+        .register::<DryRunSchemaResponse>()
+        .register::<DocumentCountResponse>()
+        .register::<TaskTimingResponse>()
+        .register::<PendingTaskResponse>()
         // Register objects
         .register::<DocumentMeta>()
+        .register::<DocumentMetaHistory>()
+        .register::<DocumentMetaBranches>()
+        .register::<DocumentMetaTimestamps>()
+        .register::<DocumentMetaOwnerName>()
+        .register::<DocumentHistoryItem>()
         // Register input values
+        .register::<PublishInput>()
         .register::<BooleanFilter>()
         .register::<HexBytesFilter>()
         .register::<FloatFilter>()
@@ -66,6 +127,7 @@ pub async fn build_root_schema(
         // Register scalars
         .register::<HexBytesScalar>()
         .register::<CursorScalar>()
+        .register::<DateTimeScalar>()
         .register::<DocumentIdScalar>()
         .register::<DocumentViewIdScalar>()
         .register::<EncodedEntryScalar>()
@@ -75,34 +137,48 @@ pub async fn build_root_schema(
         .register::<PublicKeyScalar>()
         .register::<SeqNumScalar>();
 
-    let mut schema_builder = Schema::build("Query", Some("MutationRoot"), None);
+    let mut schema_builder = Schema::build("Query", Some("MutationRoot"), Some("Subscription"));
 
     // Populate it with the registered types. We can now use these in any following dynamically
     // created query object fields.
     schema_builder = registry.apply_into_schema_builder(schema_builder);
 
-    // Construct the root query object
+    // Construct the root query and subscription objects
     let mut root_query = Object::new("Query");
+    let mut root_subscription = Subscription::new("Subscription");
+
+    // Add a read-only object and query for every configured projection
+    for projection in &config.projections {
+        schema_builder = schema_builder.register(build_projection_object(projection, &all_schema));
+        root_query = build_projection_query(root_query, projection);
+    }
+
+    // Construct the union of all document objects and queries for retrieving documents of any
+    // schema by their ids or by their author
+    schema_builder = schema_builder.register(build_document_union(&all_schema));
+    root_query = build_documents_query(root_query);
+    root_query = build_documents_by_author_query(root_query);
 
     // Loop through all schema retrieved from the schema store, dynamically create GraphQL objects,
     // input values and a query for the documents they describe
-    for schema in all_schema {
+    for schema in &all_schema {
         // Construct the fields type object which will be named `<schema_id>Field`
-        let document_fields_object = build_document_fields_object(&schema);
+        let document_fields_object = build_document_fields_object(schema, &config.page_sizes);
 
         // Construct the document object which contains "fields" and "meta" fields
-        let document_object = build_document_object(&schema);
+        let document_object = build_document_object(schema, &all_schema);
 
         // Construct the paginated response wrapper for this document schema type
-        let document_collection_object = build_document_collection_object(&schema);
+        let document_collection_object = build_document_collection_object(schema);
 
         // Construct the document object which contains "fields" and "meta" fields as well as
         // "cursor" pagination field
-        let paginated_document_object = build_paginated_document_object(&schema);
+        let paginated_document_object = build_paginated_document_object(schema, &all_schema);
 
         // Construct the filter and ordering input values for this schema
-        let filter_input = build_filter_input_object(&schema);
-        let order_input = build_order_enum_value(&schema);
+        let filter_input = build_filter_input_object(schema);
+        let order_input = build_order_enum_value(schema);
+        let order_by_fields_input = build_order_by_fields_input_object(schema);
 
         // Register a schema, schema fields and filter type for every schema
         schema_builder = schema_builder
@@ -111,27 +187,91 @@ pub async fn build_root_schema(
             .register(document_collection_object)
             .register(paginated_document_object)
             .register(order_input)
-            .register(filter_input);
+            .register(order_by_fields_input)
+            .register(filter_input)
+            .register(build_aggregate_object(schema))
+            .register(build_document_change_object(schema));
+
+        // Construct the aggregated values object for schemas with numeric fields
+        if let Some(aggregate_fields_object) = build_aggregate_fields_object(schema) {
+            schema_builder = schema_builder.register(aggregate_fields_object);
+        }
 
         // Add a query for each schema. It offers an interface to retrieve a single document of
         // this schema by its document id or view id. Its resolver parses and validates the passed
         // parameters, then forwards them up to the children query fields
-        root_query = build_document_query(root_query, &schema);
+        root_query = build_document_query(root_query, schema);
 
         // Add a query for retrieving all documents of a certain schema
-        root_query = build_collection_query(root_query, &schema);
+        root_query = build_collection_query(root_query, schema, config.page_sizes.get(schema.id()));
+
+        // Add a query for retrieving aggregated values of all documents of a certain schema
+        root_query = build_aggregate_query(root_query, schema);
+
+        // Add a subscription delivering all changes to documents of a certain schema
+        root_subscription = build_document_changes_subscription(root_subscription, schema);
     }
 
     // Add next args to the query object
     let root_query = build_next_args_query(root_query);
 
+    // Add entry chains of logs to the query object
+    let root_query = build_entry_chain_query(root_query);
+    let root_query = build_certificate_pool_query(root_query);
+
+    // Add unique conflicts to the query object
+    let root_query = build_unique_conflicts_query(root_query);
+
+    // Add materialized aggregates to the query object
+    let root_query = build_materialized_aggregate_query(root_query);
+
+    // Add dead-letter tasks to the query object
+    let root_query = build_dead_letter_tasks_query(root_query);
+
+    // Add authors received from peers to the query object
+    let root_query = build_peer_authors_query(root_query);
+
+    // Add signed blob URLs to the query object
+    let root_query = build_signed_blob_url_query(root_query);
+
+    // Add schema fields to the query object
+    let root_query = build_schema_fields_query(root_query);
+
+    // Add node status to the query object
+    let root_query = build_node_status_query(root_query);
+
+    // Add timing of recently processed materializer tasks to the query object
+    let root_query = build_task_timeline_query(root_query);
+
+    // Add queued and running materializer tasks to the query object
+    let root_query = build_pending_tasks_query(root_query);
+
+    // Add resolution of claimed names to the query object
+    let root_query = build_resolve_name_query(root_query);
+
+    // Reject queries which are nested too deep, for example following relations over many levels
+    if let Some(max_query_depth) = config.max_query_depth {
+        schema_builder = schema_builder.limit_depth(max_query_depth);
+    }
+
+    // Reject queries selecting too many fields or following too many relations
+    if let Some(query_complexity) = query_complexity {
+        schema_builder = schema_builder.extension(query_complexity);
+    }
+
     // Build the GraphQL schema. We can unwrap here since it will only fail if we forgot to
     // register all required types above
     schema_builder
         .register(root_query)
+        .register(root_subscription)
         .data(store)
         .data(schema_provider)
         .data(tx)
+        .data(config.log_id_policy.clone())
+        .data(blob_access)
+        .data(status)
+        .data(config.page_sizes.clone())
+        .data(MaxBatchEntries(config.max_batch_entries))
         .finish()
 }
 
@@ -149,6 +289,15 @@ pub struct GraphQLSharedData {
 
     /// Schema provider giving us access to currently known schemas.
     schema_provider: SchemaProvider,
+
+    /// Access control for blobs, used to mint signed blob URLs.
+    blob_access: BlobAccess,
+
+    /// Status of the node, used to report it via the API.
+    status: NodeStatus,
+
+    /// Configuration of the node, for example holding projections and query limits.
+    config: Configuration,
 }
 
 /// Builds new GraphQL schemas dynamically and executes the latest GraphQL schema for incoming
@@ -169,11 +318,24 @@ pub struct GraphQLSchemaManager {
 
     /// Commonly shared types for GraphQL schemas.
     shared: GraphQLSharedData,
+
+    /// Flag indicating if a GraphQL schema was built from all known p2panda schemas.
+    ready: Arc<AtomicBool>,
 }
 
 impl GraphQLSchemaManager {
     /// Returns a new instance of `GraphQLSchemaManager`.
-    pub async fn new(store: SqlStore, tx: ServiceSender, schema_provider: SchemaProvider) -> Self {
+    ///
+    /// Projections, the log id policy, query limits and page sizes are taken from the given
+    /// configuration.
+    pub async fn new(
+        store: SqlStore,
+        tx: ServiceSender,
+        schema_provider: SchemaProvider,
+        blob_access: BlobAccess,
+        status: NodeStatus,
+        config: &Configuration,
+    ) -> Self {
         // Initialize a default GraphQL schema. Used as a fallback when a node has no supported schema configured.
         let root_query = Object::new("Query").field(Field::new(
             "hello",
@@ -190,10 +352,17 @@ impl GraphQLSchemaManager {
             store,
             tx,
             schema_provider,
+            blob_access,
+            status,
+            config: config.clone(),
         };
 
         // Create manager instance and spawn internal watch task
-        let manager = Self { schemas, shared };
+        let manager = Self {
+            schemas,
+            shared,
+            ready: Arc::new(AtomicBool::new(false)),
+        };
         manager.spawn_schema_added_task().await;
 
         manager
@@ -206,21 +375,52 @@ impl GraphQLSchemaManager {
     async fn spawn_schema_added_task(&self) {
         let shared = self.shared.clone();
         let schemas = self.schemas.clone();
+        let ready = self.ready.clone();
 
         debug!("Subscribing GraphQL manager to schema provider");
         let mut on_schema_added = shared.schema_provider.on_schema_added();
 
         // Create the new GraphQL based on the current state of known p2panda application schemas
-        async fn rebuild(shared: GraphQLSharedData, schemas: GraphQLSchemas) {
-            match build_root_schema(shared.store, shared.tx, shared.schema_provider).await {
-                Ok(schema) => schemas.lock().await.push(schema),
+        async fn rebuild(shared: GraphQLSharedData, schemas: GraphQLSchemas, ready: &AtomicBool) {
+            let tx = shared.tx.clone();
+
+            match build_root_schema(
+                shared.store,
+                shared.tx,
+                shared.schema_provider,
+                shared.blob_access,
+                shared.status,
+                &shared.config,
+            )
+            .await
+            {
+                Ok(schema) => {
+                    let mut schemas = schemas.lock().await;
+
+                    // Report changes to the previous schema, the placeholder schema used before
+                    // the initial build is not compared
+                    if ready.load(Ordering::Acquire) {
+                        if let Some(previous) = schemas.last() {
+                            let diff = GraphQLSchemaDiff::new(&previous.sdl(), &schema.sdl());
+                            report_schema_diff(&tx, diff);
+                        }
+                    }
+
+                    schemas.push(schema);
+                    ready.store(true, Ordering::Release);
+                }
                 Err(err) => warn!("Can't re-build GraphQL schema: {}", err),
             }
         }
 
-        // Always build a schema right at the beginning as we don't have one yet
-        rebuild(shared.clone(), schemas.clone()).await;
-        debug!("Finished building initial GraphQL schema");
+        // Always build a schema right at the beginning from all schemas which are already known
+        // to the node, requests are rejected until this succeeded
+        rebuild(shared.clone(), schemas.clone(), &ready).await;
+        if ready.load(Ordering::Acquire) {
+            debug!("Finished building initial GraphQL schema");
+        } else {
+            warn!("Initial GraphQL schema is not ready, waiting for next schema change");
+        }
 
         // Spawn a task which reacts to newly registered p2panda schemas
         tokio::task::spawn(async move {
@@ -231,7 +431,7 @@ impl GraphQLSchemaManager {
                             "Changed schema {}, rebuilding GraphQL API",
                             schema_id.display()
                         );
-                        rebuild(shared.clone(), schemas.clone()).await;
+                        rebuild(shared.clone(), schemas.clone(), &ready).await;
                     }
                     Err(err) => {
                         panic!("Failed receiving schema updates: {}", err)
@@ -241,6 +441,23 @@ impl GraphQLSchemaManager {
         });
     }
 
+    /// Returns `true` if a GraphQL schema was built from all currently known p2panda schemas.
+    ///
+    /// Until then only a placeholder schema is available and requests should be rejected.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Returns the latest GraphQL schema, for example to serve subscriptions with it.
+    pub async fn latest(&self) -> Schema {
+        self.schemas
+            .lock()
+            .await
+            .last()
+            .expect("No schema given yet")
+            .clone()
+    }
+
     /// Executes an incoming GraphQL query.
     ///
     /// This method makes sure the GraphQL query will be executed by the latest given schema the
@@ -256,6 +473,27 @@ impl GraphQLSchemaManager {
     }
 }
 
+/// Logs the changes of a rebuilt GraphQL schema and informs other services about them.
+fn report_schema_diff(tx: &ServiceSender, diff: GraphQLSchemaDiff) {
+    if diff.is_empty() {
+        debug!("GraphQL schema did not change");
+        return;
+    }
+
+    if diff.is_breaking() {
+        warn!(
+            "GraphQL schema changed, queries of connected clients might break: {}",
+            diff
+        );
+    } else {
+        info!("GraphQL schema changed: {}", diff);
+    }
+
+    if tx.send(ServiceMessage::GraphQLSchemaChanged(diff)).is_err() {
+        // Silently fail here as we don't mind if there are no subscribers
+    }
+}
+
 impl std::fmt::Debug for GraphQLSchemaManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // `schemas` does not implement `Debug` but we can at least print the other fields
@@ -267,14 +505,28 @@ impl std::fmt::Debug for GraphQLSchemaManager {
 
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
     use async_graphql::{value, Response};
-    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::{FieldType, SchemaId};
     use p2panda_rs::test_utils::constants::PRIVATE_KEY;
-    use p2panda_rs::test_utils::fixtures::key_pair;
+    use p2panda_rs::test_utils::fixtures::{key_pair, random_key_pair};
     use rstest::rstest;
     use serde_json::{json, Value};
 
-    use crate::test_utils::{add_schema, http_test_client, test_runner, TestNode};
+    use tokio::sync::broadcast;
+    use tokio::time::timeout;
+
+    use crate::bus::ServiceMessage;
+    use crate::graphql::schema::build_root_schema;
+    use crate::graphql::GraphQLSchemaManager;
+    use crate::schema::SchemaProvider;
+    use crate::test_utils::{
+        add_schema, http_test_client, test_runner, test_runner_with_manager, TestNode,
+        TestNodeManager,
+    };
+    use crate::Configuration;
 
     #[rstest]
     fn schema_updates() {
@@ -359,4 +611,291 @@ mod test {
             );
         });
     }
+
+    #[rstest]
+    fn schema_descriptions(#[from(random_key_pair)] key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "venue",
+                vec![
+                    ("name", FieldType::String),
+                    (
+                        "visitors",
+                        FieldType::RelationList(SchemaId::SchemaDefinition(1)),
+                    ),
+                ],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{
+                            document: __type(name: "{}") {{ description }},
+                            fields: __type(name: "{}Fields") {{
+                                description,
+                                fields {{ name, description }}
+                            }},
+                        }}"#,
+                        schema.id(),
+                        schema.id(),
+                    ),
+                }))
+                .send()
+                .await;
+            let response: Response = response.json().await;
+
+            assert_eq!(
+                response.data.into_json().unwrap(),
+                json!({
+                    "document": {
+                        "description": "test schema description",
+                    },
+                    "fields": {
+                        "description": "The application fields of a `venue` document: test \
+                            schema description",
+                        "fields": [
+                            {
+                                "name": "name",
+                                "description": "The `name` field of a `venue` document, defined \
+                                    as `str`."
+                            },
+                            {
+                                "name": "visitors",
+                                "description": "The `visitors` field of a `venue` document, \
+                                    defined as `relation_list(schema_definition_v1)`."
+                            },
+                        ]
+                    }
+                }),
+                "\n{:#?}\n",
+                response.errors
+            );
+
+            // Descriptions are part of the SDL as well
+            let (tx, _) = broadcast::channel(120);
+            let root_schema = build_root_schema(
+                node.context.store.clone(),
+                tx,
+                node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await
+            .unwrap();
+
+            let sdl = root_schema.sdl();
+            assert!(sdl.contains(&format!(
+                "\"\"\"\nThe application fields of a `venue` document: test schema description\n\"\"\"\ntype {}Fields",
+                schema.id()
+            )));
+        });
+    }
+
+    #[rstest]
+    fn warm_start_from_store() {
+        test_runner(|mut node: TestNode| async move {
+            let key_pair = key_pair(PRIVATE_KEY);
+            let schema = add_schema(
+                &mut node,
+                "schema_name",
+                vec![("bool_field", FieldType::Boolean)],
+                &key_pair,
+            )
+            .await;
+
+            // Simulate a restarted node which only knows about the schemas in its database
+            let (tx, _) = broadcast::channel(120);
+            let schema_provider = SchemaProvider::new(
+                node.context.store.get_all_schema().await.unwrap(),
+                node.context.config.allow_schema_ids.clone(),
+            );
+            let manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx,
+                schema_provider,
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
+            assert!(manager.is_ready());
+
+            let response = manager
+                .execute(format!(
+                    r#"{{ schema: __type(name: "{}") {{ name }} }}"#,
+                    schema.id()
+                ))
+                .await;
+
+            assert_eq!(
+                response.data,
+                value!({
+                    "schema": {
+                        "name": schema.id().to_string()
+                    },
+                }),
+                "\n{:#?}\n",
+                response.errors
+            );
+        });
+    }
+    #[rstest]
+    fn report_schema_changes(#[from(random_key_pair)] key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let (tx, mut rx) = broadcast::channel(120);
+            let manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx,
+                node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
+            assert!(manager.is_ready());
+
+            let schema = add_schema(
+                &mut node,
+                "venue",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            // Wait for the rebuilt schema to report its changes
+            let diff = timeout(Duration::from_secs(5), async {
+                loop {
+                    if let Ok(ServiceMessage::GraphQLSchemaChanged(diff)) = rx.recv().await {
+                        break diff;
+                    }
+                }
+            })
+            .await
+            .expect("Schema changes should be reported");
+
+            assert!(diff.added.contains(&schema.id().to_string()));
+            assert!(diff.changed.contains(&"Query".to_string()));
+            assert!(diff.removed.is_empty());
+            assert!(!diff.is_breaking());
+        });
+    }
+
+    #[rstest]
+    fn limit_query_depth(#[from(random_key_pair)] key_pair: KeyPair) {
+        test_runner_with_manager(|manager: TestNodeManager| async move {
+            let config = Configuration {
+                max_query_depth: Some(3),
+                ..Configuration::default()
+            };
+            let mut node = manager.create_with_config(config).await;
+
+            let schema = add_schema(
+                &mut node,
+                "schema_name",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+
+            // Queries within the limit get executed
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{ all_{} {{ documents {{ cursor }} }} }}"#,
+                        schema.id()
+                    ),
+                }))
+                .send()
+                .await;
+            let response: Response = response.json().await;
+            assert!(response.is_ok(), "{:?}", response.errors);
+
+            // Queries nested deeper than the limit are rejected
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{ all_{} {{ documents {{ meta {{ documentId }} }} }} }}"#,
+                        schema.id()
+                    ),
+                }))
+                .send()
+                .await;
+            let response: Response = response.json().await;
+            assert_eq!(response.errors.len(), 1);
+            assert_eq!(response.errors[0].message, "Query is nested too deep.");
+        });
+    }
+
+    #[rstest]
+    fn limit_query_complexity(#[from(random_key_pair)] key_pair: KeyPair) {
+        test_runner_with_manager(|manager: TestNodeManager| async move {
+            let config = Configuration {
+                max_query_complexity: Some(12),
+                ..Configuration::default()
+            };
+            let mut node = manager.create_with_config(config).await;
+
+            let venue = add_schema(
+                &mut node,
+                "venue",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+            let event = add_schema(
+                &mut node,
+                "event",
+                vec![
+                    ("title", FieldType::String),
+                    ("venue", FieldType::Relation(venue.id().to_owned())),
+                ],
+                &key_pair,
+            )
+            .await;
+
+            let client = http_test_client(&node).await;
+
+            // Queries within the limit get executed
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{ all_{} {{ documents {{ fields {{ title }} }} }} }}"#,
+                        event.id()
+                    ),
+                }))
+                .send()
+                .await;
+            let response: Response = response.json().await;
+            assert!(response.is_ok(), "{:?}", response.errors);
+
+            // Following the relation counts more, even though only few fields are selected
+            let response = client
+                .post("/graphql")
+                .json(&json!({
+                    "query": format!(
+                        r#"{{ all_{} {{ documents {{ fields {{ venue {{ fields {{ name }} }} }} }} }} }}"#,
+                        event.id()
+                    ),
+                }))
+                .send()
+                .await;
+            let response: Response = response.json().await;
+            assert_eq!(response.errors.len(), 1);
+            assert_eq!(
+                response.errors[0].message,
+                "Query is too complex, its complexity is 15 but only 12 is allowed."
+            );
+        });
+    }
 }
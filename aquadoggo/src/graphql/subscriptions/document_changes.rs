@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::dynamic::{
+    FieldValue, InputValue, ResolverContext, Subscription, SubscriptionField,
+    SubscriptionFieldFuture, TypeRef,
+};
+use async_graphql::Error;
+use async_stream::try_stream;
+use log::debug;
+use p2panda_rs::schema::Schema;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bus::{ServiceMessage, ServiceSender};
+use crate::db::SqlStore;
+use crate::graphql::constants;
+use crate::graphql::utils::{change_name, changes_name};
+
+/// Number of changes which are looked up at once when catching up with past changes.
+const CHANGES_PAGE_SIZE: u64 = 100;
+
+/// Adds a GraphQL subscription delivering all changes to documents of a schema to the root
+/// subscription object.
+///
+/// The subscription follows the format `changes_<SCHEMA_ID>(since: <CURSOR>)`. When a cursor is
+/// passed, all changes which happened after it are delivered first before continuing with live
+/// changes. Clients can use the cursor of the last change they received to resume after a
+/// reconnect without missing any changes.
+pub fn build_document_changes_subscription(
+    subscription: Subscription,
+    schema: &Schema,
+) -> Subscription {
+    let schema_id = schema.id().clone();
+    subscription.field(
+        SubscriptionField::new(
+            changes_name(schema.id()),
+            TypeRef::named_nn(change_name(schema.id())),
+            move |ctx| {
+                let schema_id = schema_id.clone();
+                SubscriptionFieldFuture::new(async move {
+                    let store = ctx.data_unchecked::<SqlStore>().clone();
+
+                    // Subscribe to the bus before looking up changes, this way we don't miss any
+                    // document which gets materialized in between
+                    let mut rx = ctx.data_unchecked::<ServiceSender>().subscribe();
+
+                    let mut cursor = match parse_cursor(&ctx)? {
+                        Some(cursor) => cursor,
+                        None => store.get_latest_change_seq().await?,
+                    };
+
+                    debug!(
+                        "Subscription to {} received starting after change {}",
+                        ctx.field().name(),
+                        cursor
+                    );
+
+                    Ok(try_stream! {
+                        loop {
+                            // Deliver all changes since the last one we've seen. Changes are
+                            // looked up by their sequence number, so this also catches up with
+                            // changes which happened before the subscription was started. They
+                            // are fetched page by page to not load a large backlog at once
+                            loop {
+                                let changes = store
+                                    .get_documents_changed_since(
+                                        &schema_id,
+                                        cursor,
+                                        CHANGES_PAGE_SIZE,
+                                    )
+                                    .await?;
+                                let is_last_page = (changes.len() as u64) < CHANGES_PAGE_SIZE;
+
+                                for change in changes {
+                                    cursor = change.change_seq;
+                                    yield FieldValue::owned_any(change);
+                                }
+
+                                if is_last_page {
+                                    break;
+                                }
+                            }
+
+                            // Wait until the materializer processed more documents. When we lagged
+                            // behind we might have missed some, so we look up changes in any case
+                            loop {
+                                match rx.recv().await {
+                                    Ok(ServiceMessage::DocumentsChanged)
+                                    | Err(RecvError::Lagged(_)) => break,
+                                    Ok(_) => (),
+                                    Err(RecvError::Closed) => return,
+                                }
+                            }
+                        }
+                    })
+                })
+            },
+        )
+        .argument(
+            InputValue::new(
+                constants::CHANGES_SINCE_ARG,
+                TypeRef::named(TypeRef::STRING),
+            )
+            .description(
+                "Cursor of the last change seen by the client, all changes after it are delivered \
+                before live changes. When not set only changes from now on are delivered.",
+            ),
+        )
+        .description(format!(
+            "Subscribe to changes of {} documents.",
+            schema.id().name()
+        )),
+    )
+}
+
+/// Parse the optional change cursor passed into this subscription.
+fn parse_cursor(ctx: &ResolverContext) -> Result<Option<u64>, Error> {
+    match ctx.args.get(constants::CHANGES_SINCE_ARG) {
+        Some(value) => value
+            .string()?
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::new("Invalid change cursor")),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use async_graphql::{Request, Response, Value};
+    use futures::{Stream, StreamExt};
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use serde_json::{json, Value as JsonValue};
+    use tokio::sync::broadcast;
+    use tokio::time::timeout;
+
+    use crate::bus::ServiceMessage;
+    use crate::graphql::GraphQLSchemaManager;
+    use crate::test_utils::{add_document, add_schema, test_runner, TestNode};
+
+    async fn next_change(stream: &mut (impl Stream<Item = Response> + Unpin)) -> JsonValue {
+        let response = timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("Change should be delivered")
+            .unwrap();
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    #[rstest]
+    fn changes_since_cursor(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "posts",
+                vec![("title", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            for title in ["Missed", "Seen"] {
+                add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("title", title.into())],
+                    &key_pair,
+                )
+                .await;
+            }
+
+            let (tx, _) = broadcast::channel(16);
+            let manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx.clone(),
+                node.context.schema_provider.clone(),
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
+
+            // The client has seen the first document before it disconnected
+            let changes = node
+                .context
+                .store
+                .get_documents_changed_since(schema.id(), 0, 10)
+                .await
+                .unwrap();
+            let mut stream = manager.latest().await.execute_stream(Request::new(format!(
+                r#"subscription {{
+                    changes_{}(since: "{}") {{
+                        cursor
+                        document {{
+                            fields {{
+                                title
+                            }}
+                        }}
+                    }}
+                }}"#,
+                schema.id(),
+                changes[0].change_seq,
+            )));
+
+            let field = format!("changes_{}", schema.id());
+            // Changes after the cursor are delivered first
+            let data = next_change(&mut stream).await;
+            assert_eq!(data[&field]["document"]["fields"]["title"], json!("Seen"));
+
+            // Then it continues with live changes
+            add_document(
+                &mut node,
+                schema.id(),
+                vec![("title", "Live".into())],
+                &key_pair,
+            )
+            .await;
+            tx.send(ServiceMessage::DocumentsChanged).unwrap();
+            let data = next_change(&mut stream).await;
+            assert_eq!(data[&field]["document"]["fields"]["title"], json!("Live"));
+
+            // Invalid cursors are rejected
+            let mut stream = manager.latest().await.execute_stream(Request::new(format!(
+                r#"subscription {{ changes_{}(since: "abc") {{ cursor }} }}"#,
+                schema.id(),
+            )));
+            let response = stream.next().await.unwrap();
+            assert_eq!(response.data, Value::Null);
+            assert_eq!(response.errors[0].message, "Invalid change cursor");
+        })
+    }
+}
@@ -0,0 +1,5 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+mod document_changes;
+
+pub use document_changes::build_document_changes_subscription;
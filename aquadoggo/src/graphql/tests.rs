@@ -9,6 +9,7 @@ use p2panda_rs::{document::DocumentId, schema::FieldType};
 use rstest::rstest;
 use serde_json::json;
 
+use crate::graphql::utils::referenced_by_name;
 use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
 
 // Test querying application documents with scalar fields (no relations) by document id and by view
@@ -213,3 +214,117 @@ fn relation_fields() {
         assert_eq!(response.data, expected_data,);
     });
 }
+
+// Test querying the documents relating to an application document from the other side of the
+// relation.
+#[rstest]
+fn reverse_relation_fields() {
+    test_runner(|mut node: TestNode| async move {
+        let key_pair = random_key_pair();
+
+        // Add schemas to node
+        let child_schema = add_schema(
+            &mut node,
+            "child",
+            vec![("name", FieldType::String)],
+            &key_pair,
+        )
+        .await;
+
+        let parent_schema = add_schema(
+            &mut node,
+            "parent",
+            vec![
+                ("name", FieldType::String),
+                (
+                    "by_relation",
+                    FieldType::Relation(child_schema.id().clone()),
+                ),
+                (
+                    "by_pinned_relation_list",
+                    FieldType::PinnedRelationList(child_schema.id().clone()),
+                ),
+            ],
+            &key_pair,
+        )
+        .await;
+
+        // Publish child documents on node
+        let mut child_view_ids = Vec::new();
+        for name in ["panda", "bear"] {
+            let view_id = add_document(
+                &mut node,
+                child_schema.id(),
+                vec![("name", name.into())],
+                &key_pair,
+            )
+            .await;
+            child_view_ids.push(view_id);
+        }
+
+        // There is only one operation so view id = doc id
+        let child_doc_ids: Vec<DocumentId> = child_view_ids
+            .iter()
+            .map(|view_id| view_id.to_string().parse().unwrap())
+            .collect();
+
+        // Publish parent documents relating to the children in different ways
+        let parents = [
+            ("by_relation", &child_doc_ids[0], vec![]),
+            (
+                "by_pinned_relation_list",
+                &child_doc_ids[1],
+                vec![child_view_ids[0].clone()],
+            ),
+            ("unrelated", &child_doc_ids[1], vec![]),
+        ];
+        for (name, relation, pinned_relation_list) in parents {
+            add_document(
+                &mut node,
+                parent_schema.id(),
+                vec![
+                    ("name", name.into()),
+                    ("by_relation", relation.clone().into()),
+                    ("by_pinned_relation_list", pinned_relation_list.into()),
+                ],
+                &key_pair,
+            )
+            .await;
+        }
+
+        // Configure and send test query
+        let client = http_test_client(&node).await;
+        let query = format!(
+            r#"{{
+                result: {}(viewId: "{}") {{
+                    {} {{ fields {{ name }} }}
+                }}
+            }}"#,
+            child_schema.id(),
+            child_view_ids[0],
+            referenced_by_name(parent_schema.id()),
+        );
+
+        let response: Response = client
+            .post("/graphql")
+            .json(&json!({
+                "query": query,
+            }))
+            .send()
+            .await
+            .json()
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+        let data = response.data.into_json().unwrap();
+        let mut names: Vec<&str> = data["result"][referenced_by_name(parent_schema.id())]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|document| document["fields"]["name"].as_str().unwrap())
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["by_pinned_relation_list", "by_relation"]);
+    });
+}
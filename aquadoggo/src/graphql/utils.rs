@@ -4,28 +4,35 @@ use std::convert::{TryFrom, TryInto};
 use std::num::NonZeroU64;
 
 use async_graphql::dynamic::{InputValue, ObjectAccessor, ResolverContext, TypeRef, ValueAccessor};
-use async_graphql::{Error, Value};
+use async_graphql::{Error, SelectionField, Value};
 use p2panda_rs::document::{DocumentId, DocumentViewId};
 use p2panda_rs::operation::OperationValue;
 use p2panda_rs::schema::{FieldType, Schema, SchemaId};
 use p2panda_rs::storage_provider::error::DocumentStorageError;
-use p2panda_rs::storage_provider::traits::DocumentStore;
 
+use crate::config::PageSizes;
 use crate::db::query::{
-    Direction, Field, Filter, MetaField, Order, Pagination, PaginationField, Select,
+    validate_pagination, Direction, Field, Filter, MetaField, Order, Pagination, PaginationField,
+    Select,
 };
 use crate::db::stores::{PaginationCursor, Query, RelationList};
 use crate::db::types::StorageDocument;
 use crate::db::SqlStore;
 use crate::graphql::constants;
 use crate::graphql::scalars::{CursorScalar, DocumentIdScalar, DocumentViewIdScalar};
+use crate::projections::Projection;
 
 // Type name suffixes.
 const DOCUMENT_FIELDS_SUFFIX: &str = "Fields";
 const FILTER_INPUT_SUFFIX: &str = "Filter";
 const ORDER_BY_SUFFIX: &str = "OrderBy";
+const ORDER_BY_FIELD_SUFFIX: &str = "OrderByField";
 const COLLECTION_ITEM_SUFFIX: &str = "Item";
 const COLLECTION_SUFFIX: &str = "Collection";
+const CHANGE_SUFFIX: &str = "Change";
+const PROJECTION_SUFFIX: &str = "Projection";
+const AGGREGATE_SUFFIX: &str = "Aggregate";
+const AGGREGATE_FIELDS_SUFFIX: &str = "AggregateFields";
 
 /// Formats the name of a document collection type.
 pub fn collection_name(schema_id: &SchemaId) -> String {
@@ -52,6 +59,60 @@ pub fn order_by_name(schema_id: &SchemaId) -> String {
     format!("{}{ORDER_BY_SUFFIX}", schema_id)
 }
 
+/// Formats the name of an order by field input type.
+pub fn order_by_fields_name(schema_id: &SchemaId) -> String {
+    format!("{}{ORDER_BY_FIELD_SUFFIX}", schema_id)
+}
+
+/// Formats the name of an aggregate type.
+pub fn aggregate_name(schema_id: &SchemaId) -> String {
+    format!("{}{AGGREGATE_SUFFIX}", schema_id)
+}
+
+/// Formats the name of an aggregate fields type.
+pub fn aggregate_fields_name(schema_id: &SchemaId) -> String {
+    format!("{}{AGGREGATE_FIELDS_SUFFIX}", schema_id)
+}
+
+/// Formats the name of a document change type.
+pub fn change_name(schema_id: &SchemaId) -> String {
+    format!("{}{CHANGE_SUFFIX}", schema_id)
+}
+
+/// Formats the name of a subscription to the changed documents of a schema.
+pub fn changes_name(schema_id: &SchemaId) -> String {
+    format!("{}{}", constants::SUBSCRIPTION_CHANGES_PREFIX, schema_id)
+}
+
+/// Formats the name of a projection type.
+pub fn projection_name(projection: &Projection) -> String {
+    format!("{}{PROJECTION_SUFFIX}", projection.name)
+}
+
+/// Formats the name of a document field returning the documents of a schema relating to it.
+pub fn referenced_by_name(schema_id: &SchemaId) -> String {
+    format!("{}{}", constants::REFERENCED_BY_PREFIX, schema_id)
+}
+
+/// Returns all schemas with relation fields pointing at documents of the given schema.
+pub fn referencing_schemas<'a>(
+    schema_id: &'a SchemaId,
+    schemas: &'a [Schema],
+) -> impl Iterator<Item = &'a Schema> {
+    schemas.iter().filter(move |schema| {
+        schema
+            .fields()
+            .iter()
+            .any(|(_, field_type)| match field_type {
+                FieldType::Relation(related)
+                | FieldType::RelationList(related)
+                | FieldType::PinnedRelation(related)
+                | FieldType::PinnedRelationList(related) => related == schema_id,
+                _ => false,
+            })
+    })
+}
+
 /// Convert non-relation operation values into GraphQL values.
 ///
 /// Panics when given a relation field value.
@@ -120,9 +181,15 @@ pub fn parse_collection_arguments(
     schema: &Schema,
     list: &Option<RelationList>,
 ) -> Result<Query<PaginationCursor>, Error> {
-    let mut pagination = Pagination::<PaginationCursor>::default();
+    // Dynamic schemas don't fill in default values of arguments, use the configured page size
+    // when none was passed
+    let mut pagination = Pagination::<PaginationCursor> {
+        first: ctx.data_unchecked::<PageSizes>().get(schema.id()),
+        ..Pagination::default()
+    };
     let mut order = Order::default();
-    let mut filter = Filter::default();
+    let mut filter = parse_include_deleted(ctx)?;
+    let mut order_by_fields: Vec<(Field, Direction)> = Vec::new();
 
     for (name, value) in ctx.args.iter() {
         match name.as_str() {
@@ -134,21 +201,23 @@ pub fn parse_collection_arguments(
                 pagination.first = NonZeroU64::try_from(value.u64()?)?;
             }
             constants::ORDER_BY_ARG => {
-                let order_by = match value.enum_name()? {
-                    "OWNER" => Field::Meta(MetaField::Owner),
-                    "DOCUMENT_ID" => Field::Meta(MetaField::DocumentId),
-                    "DOCUMENT_VIEW_ID" => Field::Meta(MetaField::DocumentViewId),
-                    field_name => Field::new(field_name),
-                };
-                order.field = Some(order_by);
+                order.field = Some(parse_order_field(value.enum_name()?));
             }
             constants::ORDER_DIRECTION_ARG => {
-                let direction = match value.enum_name()? {
-                    "ASC" => Direction::Ascending,
-                    "DESC" => Direction::Descending,
-                    _ => panic!("Unknown order direction argument key received"),
-                };
-                order.direction = direction;
+                order.direction = parse_order_direction(value.enum_name()?);
+            }
+            constants::ORDER_BY_FIELDS_ARG => {
+                for item in value.list()?.iter() {
+                    let item = item
+                        .object()
+                        .map_err(|_| Error::new("internal: is not an object"))?;
+                    let field = parse_order_field(item.try_get("field")?.enum_name()?);
+                    let direction = match item.get("direction") {
+                        Some(direction) => parse_order_direction(direction.enum_name()?),
+                        None => Direction::Ascending,
+                    };
+                    order_by_fields.push((field, direction));
+                }
             }
             constants::META_FILTER_ARG => {
                 let filter_object = value
@@ -162,6 +231,17 @@ pub fn parse_collection_arguments(
                     .map_err(|_| Error::new("internal: is not an object"))?;
                 parse_filter(&mut filter, schema, &filter_object)?;
             }
+            constants::SEARCH_ARG => {
+                filter.add_search(value.string()?);
+            }
+            constants::OWNER_ARG => {
+                filter.add(
+                    &Field::Meta(MetaField::Owner),
+                    &OperationValue::String(value.string()?.to_owned()),
+                );
+            }
+            // Already handled when creating the filter
+            constants::INCLUDE_DELETED_ARG => (),
             _ => panic!("Unknown argument key received"),
         }
     }
@@ -171,17 +251,98 @@ pub fn parse_collection_arguments(
     let select = Select::new(fields.as_slice());
     pagination.fields = pagination_fields;
 
+    // Ordering by multiple fields replaces the single field set via `orderBy` and `orderDirection`
+    if let Some(((field, direction), then_by)) = order_by_fields.split_first() {
+        order = Order::new(field, direction);
+        for (field, direction) in then_by {
+            order = order.then_by(field, direction);
+        }
+    }
+
     // Set default ordering to document id as per specification, if we're in a root query
     if list.is_none() && order.field.is_none() {
         order.field = Some(Field::Meta(MetaField::DocumentId));
     }
 
+    validate_pagination(&pagination, &order).map_err(|err| Error::new(err.to_string()))?;
+
     // Finally put it all together
     let query = Query::new(&pagination, &select, &filter, &order);
 
     Ok(query)
 }
 
+/// Parse filter argument values based on expected keys and types.
+pub fn parse_filter_arguments(ctx: &ResolverContext, schema: &Schema) -> Result<Filter, Error> {
+    let mut filter = parse_include_deleted(ctx)?;
+
+    for (name, value) in ctx.args.iter() {
+        match name.as_str() {
+            constants::META_FILTER_ARG => {
+                let filter_object = value
+                    .object()
+                    .map_err(|_| Error::new("internal: is not an object"))?;
+                parse_meta_filter(&mut filter, &filter_object)?;
+            }
+            constants::FILTER_ARG => {
+                let filter_object = value
+                    .object()
+                    .map_err(|_| Error::new("internal: is not an object"))?;
+                parse_filter(&mut filter, schema, &filter_object)?;
+            }
+            constants::SEARCH_ARG => {
+                filter.add_search(value.string()?);
+            }
+            constants::OWNER_ARG => {
+                filter.add(
+                    &Field::Meta(MetaField::Owner),
+                    &OperationValue::String(value.string()?.to_owned()),
+                );
+            }
+            // Already handled when creating the filter
+            constants::INCLUDE_DELETED_ARG => (),
+            _ => panic!("Unknown argument key received"),
+        }
+    }
+
+    Ok(filter)
+}
+
+/// Returns the default filter, without hiding deleted documents when they were requested.
+///
+/// This is handled before all other arguments, so a `deleted` meta filter can still narrow the
+/// result down to deleted documents only.
+fn parse_include_deleted(ctx: &ResolverContext) -> Result<Filter, Error> {
+    let mut filter = Filter::default();
+
+    if let Some(value) = ctx.args.get(constants::INCLUDE_DELETED_ARG) {
+        if value.boolean()? {
+            filter.remove(&Field::Meta(MetaField::Deleted));
+        }
+    }
+
+    Ok(filter)
+}
+
+/// Parse the name of an order enum value into the field to order by.
+fn parse_order_field(name: &str) -> Field {
+    match name {
+        "OWNER" => Field::Meta(MetaField::Owner),
+        "DOCUMENT_ID" => Field::Meta(MetaField::DocumentId),
+        "DOCUMENT_VIEW_ID" => Field::Meta(MetaField::DocumentViewId),
+        field_name => Field::new(field_name),
+    }
+}
+
+/// Parse the name of an order direction enum value.
+fn parse_order_direction(name: &str) -> Direction {
+    match name {
+        "ASC" => Direction::Ascending,
+        "DESC" => Direction::Descending,
+        _ => panic!("Unknown order direction argument key received"),
+    }
+}
+
 /// Parse a filter object received from the graphql api into an abstract filter type based on the
 /// schema of the documents being queried.
 fn parse_filter(
@@ -241,6 +402,12 @@ fn parse_filter(
                 "notContains" => {
                     filter.add_not_contains(&filter_field, value.string()?);
                 }
+                "isNull" => {
+                    filter.add_is_null(&filter_field, value.boolean()?);
+                }
+                "isSet" => {
+                    filter.add_is_null(&filter_field, !value.boolean()?);
+                }
                 _ => panic!("Unknown filter type received"),
             }
         }
@@ -297,20 +464,28 @@ pub async fn get_document_from_params(
     store: &SqlStore,
     document_id: &Option<DocumentIdScalar>,
     document_view_id: &Option<DocumentViewIdScalar>,
+    select: &Select,
 ) -> Result<Option<StorageDocument>, DocumentStorageError> {
     match (document_id, document_view_id) {
         (None, Some(document_view_id)) => {
             store
-                .get_document_by_view_id(&DocumentViewId::from(document_view_id.to_owned()))
+                .get_document_by_view_id_with_select(
+                    &DocumentViewId::from(document_view_id.to_owned()),
+                    select,
+                )
+                .await
+        }
+        (Some(document_id), None) => {
+            store
+                .get_document_with_select(&DocumentId::from(document_id), select)
                 .await
         }
-        (Some(document_id), None) => store.get_document(&DocumentId::from(document_id)).await,
         _ => panic!("Invalid values passed from query field parent"),
     }
 }
 
-/// Add collection query arguments to a field.
-pub fn with_collection_arguments(
+/// Add arguments to filter documents of a collection to a field.
+pub fn with_filter_arguments(
     field: async_graphql::dynamic::Field,
     schema_id: &SchemaId,
 ) -> async_graphql::dynamic::Field {
@@ -329,6 +504,35 @@ pub fn with_collection_arguments(
             )
             .description("Filter the query based on meta field values"),
         )
+        .argument(
+            InputValue::new(constants::SEARCH_ARG, TypeRef::named(TypeRef::STRING)).description(
+                "Search for items which contain the given string in any of their string fields, \
+                ignoring upper- and lowercase",
+            ),
+        )
+        .argument(
+            InputValue::new(constants::OWNER_ARG, TypeRef::named(constants::PUBLIC_KEY))
+                .description("Only include documents which were created by the given public key"),
+        )
+        .argument(
+            InputValue::new(
+                constants::INCLUDE_DELETED_ARG,
+                TypeRef::named(TypeRef::BOOLEAN),
+            )
+            .description(
+                "Include deleted documents, they are left out by default. Deleted documents \
+                don't have any fields and can't be ordered by them",
+            ),
+        )
+}
+
+/// Add collection query arguments to a field, `page_size` is used when `first` is not passed.
+pub fn with_collection_arguments(
+    field: async_graphql::dynamic::Field,
+    schema_id: &SchemaId,
+    page_size: NonZeroU64,
+) -> async_graphql::dynamic::Field {
+    with_filter_arguments(field, schema_id)
         .argument(
             InputValue::new(
                 constants::ORDER_BY_ARG,
@@ -345,13 +549,24 @@ pub fn with_collection_arguments(
             .description("Direction which items in the collection will be ordered")
             .default_value("ASC"),
         )
+        .argument(
+            InputValue::new(
+                constants::ORDER_BY_FIELDS_ARG,
+                TypeRef::named_nn_list(order_by_fields_name(schema_id)),
+            )
+            .description(
+                "Fields and directions by which items in the collection will be ordered, items \
+                with equal values are ordered by the next field. Replaces `orderBy` and \
+                `orderDirection` when set",
+            ),
+        )
         .argument(
             InputValue::new(
                 constants::PAGINATION_FIRST_ARG,
                 TypeRef::named(TypeRef::INT),
             )
             .description("Number of paginated items we want from this request")
-            .default_value(25),
+            .default_value(page_size.get()),
         )
         .argument(
             InputValue::new(constants::PAGINATION_AFTER_ARG, TypeRef::named("Cursor"))
@@ -374,49 +589,62 @@ pub fn look_ahead_selected_fields(ctx: &ResolverContext) -> (Vec<PaginationField
 
     let pagination = selection_field
         .selection_set()
-        .filter_map(|field| match field.name() {
+        .flat_map(|field| match field.name() {
             // Remove special GraphQL meta fields
-            "__typename" => None,
+            "__typename" => vec![],
 
             // Remove all other fields which are not related to pagination
-            constants::DOCUMENTS_FIELD => None,
+            constants::DOCUMENTS_FIELD => vec![],
+
+            // Pagination fields can also be selected inside of the page info object
+            constants::PAGE_INFO_FIELD => field
+                .selection_set()
+                .filter(|field| field.name() != "__typename")
+                .map(|field| field.name().into())
+                .collect(),
 
             // Convert pagination fields finally
-            value => Some(value.into()),
+            value => vec![value.into()],
         })
         .collect::<Vec<PaginationField>>();
 
+    let selected_fields = selection_field
+        .selection_set()
+        .find(|field| field.name() == constants::DOCUMENTS_FIELD)
+        .map(|document| selected_document_fields(&document))
+        .unwrap_or_default();
+
+    (pagination, selected_fields)
+}
+
+/// Helper method to extract the selected application and meta fields of a document.
+pub fn selected_document_fields(document: &SelectionField) -> Vec<Field> {
     let mut selected_fields = Vec::new();
 
-    if let Some(document) = selection_field
+    document
         .selection_set()
-        .find(|field| field.name() == constants::DOCUMENTS_FIELD)
-    {
-        document
-            .selection_set()
-            .for_each(|field| match field.name() {
-                // Parse selected application fields
-                constants::FIELDS_FIELD => {
-                    field.selection_set().for_each(|field| match field.name() {
-                        // Remove special GraphQL meta fields
-                        "__typename" => (),
-                        field_name => {
-                            selected_fields.push(Field::Field(field_name.to_string()));
-                        }
+        .for_each(|field| match field.name() {
+            // Parse selected application fields
+            constants::FIELDS_FIELD => {
+                field.selection_set().for_each(|field| match field.name() {
+                    // Remove special GraphQL meta fields
+                    "__typename" => (),
+                    field_name => {
+                        selected_fields.push(Field::Field(field_name.to_string()));
+                    }
+                });
+            }
+            // Parse selected meta fields
+            constants::META_FIELD => {
+                field
+                    .selection_set()
+                    .filter_map(|field| field.name().try_into().ok())
+                    .for_each(|field| {
+                        selected_fields.push(Field::Meta(field));
                     });
-                }
-                // Parse selected meta fields
-                constants::META_FIELD => {
-                    field
-                        .selection_set()
-                        .filter_map(|field| field.name().try_into().ok())
-                        .for_each(|field| {
-                            selected_fields.push(Field::Meta(field));
-                        });
-                }
-                _ => (),
-            });
-    }
+            }
+            _ => (),
+        });
 
-    (pagination, selected_fields)
+    selected_fields
 }
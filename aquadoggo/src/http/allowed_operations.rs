@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use async_graphql::parser::parse_query;
+use async_graphql::parser::types::DocumentOperations;
+use async_graphql::{Request, ServerError};
+use log::{debug, error};
+use sha2::{Digest, Sha256};
+
+use crate::db::SqlStore;
+
+/// Rejects requests for GraphQL operations which were not added to the allow-list.
+///
+/// Operations are identified by the SHA256 hash of the query string or by the name of the
+/// operation which gets executed.
+pub async fn check_allowed_operation(
+    store: &SqlStore,
+    request: &Request,
+) -> Result<(), ServerError> {
+    let hash = hex::encode(Sha256::digest(request.query.as_bytes()));
+    let name = operation_name(request);
+
+    match store.is_operation_allowed(&hash, name.as_deref()).await {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            debug!(
+                "Rejected GraphQL operation {} which is not on the allow-list",
+                name.unwrap_or(hash)
+            );
+
+            let mut err = ServerError::new("Operation is not allowed on this node", None);
+            err.extensions
+                .get_or_insert_with(Default::default)
+                .set("code", "OPERATION_NOT_ALLOWED");
+            Err(err)
+        }
+        Err(err) => {
+            error!("Failed checking allow-list of GraphQL operations: {}", err);
+            Err(ServerError::new("Internal server error", None))
+        }
+    }
+}
+
+/// Returns the name of the operation which gets executed by this request.
+///
+/// Names are only returned when they refer to an operation of the query, anonymous operations do
+/// not have a name.
+fn operation_name(request: &Request) -> Option<String> {
+    let document = parse_query(&request.query).ok()?;
+
+    let operations = match document.operations {
+        DocumentOperations::Single(_) => return None,
+        DocumentOperations::Multiple(operations) => operations,
+    };
+
+    match &request.operation_name {
+        Some(name) => operations
+            .keys()
+            .find(|operation| operation.as_str() == name)
+            .map(|operation| operation.to_string()),
+        None if operations.len() == 1 => operations.keys().next().map(|name| name.to_string()),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Request;
+    use rstest::rstest;
+
+    use super::operation_name;
+
+    #[rstest]
+    #[case("{ nodeStatus { version } }", None, None)]
+    #[case("query Status { nodeStatus { version } }", None, Some("Status"))]
+    #[case(
+        "query Status { nodeStatus { version } }",
+        Some("Status"),
+        Some("Status")
+    )]
+    #[case("query Status { nodeStatus { version } }", Some("Other"), None)]
+    #[case(
+        "query A { nodeStatus { version } } query B { nodeStatus { version } }",
+        None,
+        None
+    )]
+    #[case(
+        "query A { nodeStatus { version } } query B { nodeStatus { version } }",
+        Some("B"),
+        Some("B")
+    )]
+    #[case("invalid query", None, None)]
+    fn executed_operation_name(
+        #[case] query: &str,
+        #[case] requested: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let mut request = Request::new(query);
+        if let Some(requested) = requested {
+            request = request.operation_name(requested);
+        }
+
+        assert_eq!(operation_name(&request).as_deref(), expected);
+    }
+}
@@ -1,40 +1,381 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use std::path::PathBuf;
+use std::convert::Infallible;
+use std::future::Future;
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
+use async_graphql::http::ALL_WEBSOCKET_PROTOCOLS;
 use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
-use axum::body::StreamBody;
-use axum::extract::{Extension, Path};
+use async_graphql::ServerError;
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use axum::body::{Bytes, StreamBody};
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{Extension, Path, Query};
 use axum::headers::{ETag, IfNoneMatch};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, HeaderName, StatusCode, Uri};
 use axum::response::{self, IntoResponse, Response};
-use axum::TypedHeader;
+use axum::{Json, TypedHeader};
+use futures::StreamExt;
 use http::header;
-use log::warn;
+use log::{error, warn};
 use p2panda_rs::document::traits::AsDocument;
 use p2panda_rs::document::{DocumentId, DocumentViewId};
+use p2panda_rs::operation::OperationValue;
 use p2panda_rs::schema::SchemaId;
 use p2panda_rs::storage_provider::traits::DocumentStore;
-use p2panda_rs::Human;
-use tokio::fs::File;
-use tokio_util::io::ReaderStream;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use crate::blobs::{
+    thumbnail_name, BlobKey, BlobUploadError, BlobUploadProgress, SharedBlobBackend,
+};
+use crate::graphql::AdminRequest;
+use crate::http::allowed_operations::check_allowed_operation;
 use crate::http::context::HttpServiceContext;
+use crate::http::incremental::{
+    accepts_multipart, multipart_part, split_stream, values_at, IncrementalQuery,
+    IncrementalResult, InitialPayload, SubsequentPayload, MULTIPART_CONTENT_TYPE, MULTIPART_END,
+};
+use crate::http::websocket::WebSocketExecutor;
 
-/// Handle GraphQL playground requests at the given path.
-pub async fn handle_graphql_playground(path: &str) -> impl IntoResponse {
-    response::Html(playground_source(GraphQLPlaygroundConfig::new(path)))
+/// Header containing the total length of a resumable blob upload.
+pub const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+
+/// Header containing the number of bytes received for a resumable blob upload.
+pub const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+
+/// Header containing the MIME type of a resumable blob upload.
+pub const UPLOAD_MIME_TYPE: HeaderName = HeaderName::from_static("upload-mime-type");
+
+/// Header containing the id of the blob document published after a completed upload.
+pub const BLOB_DOCUMENT_ID: HeaderName = HeaderName::from_static("blob-document-id");
+
+/// Handle GraphQL playground requests at the given paths.
+pub async fn handle_graphql_playground(path: &str, subscription_path: &str) -> impl IntoResponse {
+    response::Html(playground_source(
+        GraphQLPlaygroundConfig::new(path).subscription_endpoint(subscription_path),
+    ))
+}
+
+/// Handle GraphQL subscriptions via WebSocket.
+///
+/// Subscriptions are served by the GraphQL schema which is the latest at the time the connection
+/// gets established. Queries and mutations sent via WebSocket are aborted after the query timeout
+/// and internal errors get masked like for regular requests. As subscriptions can't be checked against registered operations they are not
+/// available in allow-list mode.
+pub async fn handle_graphql_subscription(
+    Extension(context): Extension<HttpServiceContext>,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if !context.schema.is_ready() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "GraphQL schema is not ready yet",
+        )
+            .into_response();
+    }
+
+    if context.operation_allow_list {
+        return (
+            StatusCode::FORBIDDEN,
+            "GraphQL subscriptions are not available in allow-list mode",
+        )
+            .into_response();
+    }
+
+    let executor = WebSocketExecutor::new(
+        context.schema.latest().await,
+        context.query_timeout,
+        context.mask_errors,
+    );
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| GraphQLWebSocket::new(stream, executor, protocol).serve())
+        .into_response()
 }
 
 /// Handle GraphQL requests.
+///
+/// Queries using `@defer` or `@stream` directives are delivered incrementally via a multipart
+/// response when the client accepts it.
+///
+/// Clients can send the hash of a previously registered query instead of the query string, see
+/// automatic persisted queries. In allow-list mode only registered operations get executed.
 pub async fn handle_graphql_query(
     Extension(context): Extension<HttpServiceContext>,
+    headers: HeaderMap,
     req: GraphQLRequest,
-) -> GraphQLResponse {
-    context.schema.execute(req.into_inner()).await.into()
+) -> Response {
+    // Reject requests until the GraphQL schema was built from all known p2panda schemas, clients
+    // would otherwise observe an incomplete API
+    if !context.schema.is_ready() {
+        let response: GraphQLResponse =
+            async_graphql::Response::from_errors(vec![ServerError::new(
+                "GraphQL schema is not ready yet",
+                None,
+            )])
+            .into();
+        return (StatusCode::SERVICE_UNAVAILABLE, response).into_response();
+    }
+
+    let mut request = req.into_inner();
+
+    // Look up or register queries sent via the automatic persisted queries protocol
+    if let Err(err) = context.persisted_queries.resolve(&mut request) {
+        let response: GraphQLResponse = async_graphql::Response::from_errors(vec![err]).into();
+        return response.into_response();
+    }
+
+    // Operators who sent the admin token can run any operation, including admin mutations
+    let is_admin = is_admin_request(&context, &headers);
+
+    if context.operation_allow_list && !is_admin {
+        if let Err(err) = check_allowed_operation(&context.store, &request).await {
+            let response: GraphQLResponse = async_graphql::Response::from_errors(vec![err]).into();
+            return response.into_response();
+        }
+    }
+
+    if is_admin {
+        request = request.data(AdminRequest);
+    }
+
+    if let Some(incremental) =
+        IncrementalQuery::parse(&request.query, request.operation_name.as_deref())
+    {
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok());
+
+        if accepts_multipart(accept) {
+            return incremental_response(context, request, incremental).await;
+        }
+
+        // Clients which don't support incremental delivery receive all fields at once
+        request.query = incremental.complete;
+    }
+
+    let response = execute_graphql_request(&context, request).await;
+    GraphQLResponse::from(response).into_response()
+}
+
+/// Returns true if the request came with the configured admin token as a bearer token.
+pub(crate) fn is_admin_request(context: &HttpServiceContext, headers: &HeaderMap) -> bool {
+    let admin_token = match &context.admin_token {
+        Some(admin_token) => admin_token,
+        None => return false,
+    };
+
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    // Compare hashes to not leak the token through the duration of the comparison
+    match token {
+        Some(token) => Sha256::digest(token.as_bytes()) == Sha256::digest(admin_token.as_bytes()),
+        None => false,
+    }
+}
+
+/// Execute the GraphQL request within the configured timeout.
+async fn execute_graphql_request(
+    context: &HttpServiceContext,
+    request: async_graphql::Request,
+) -> async_graphql::Response {
+    // Dropping this future, for example when the client disconnected or the timeout was reached,
+    // cancels all database queries which were started by this request
+    let execution = context.schema.execute(request);
+    let response = execute_with_timeout(execution, context.query_timeout).await;
+    if context.mask_errors {
+        mask_internal_errors(response)
+    } else {
+        response
+    }
+}
+
+/// Respond with the initial result of the query and stream the deferred fragments and remaining
+/// list items in subsequent parts of a multipart response.
+async fn incremental_response(
+    context: HttpServiceContext,
+    mut request: async_graphql::Request,
+    incremental: IncrementalQuery,
+) -> Response {
+    let variables = request.variables.clone();
+    let operation_name = request.operation_name.clone();
+
+    request.query = incremental.initial;
+    let response = execute_graphql_request(&context, request).await;
+    let mut data = response.data;
+
+    // Only continue with the subsequent parts if the initial query succeeded
+    let (streamed, deferred) = if data == async_graphql::Value::Null {
+        (Vec::new(), Vec::new())
+    } else {
+        let mut streamed = Vec::new();
+        for stream in &incremental.streams {
+            for (path, items) in split_stream(&mut data, &stream.path, stream.initial_count, vec![])
+            {
+                streamed.push(IncrementalResult {
+                    items: Some(items),
+                    path,
+                    label: stream.label.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+
+        (streamed, incremental.deferred)
+    };
+
+    let has_streamed = !streamed.is_empty();
+    let deferred_len = deferred.len();
+
+    let initial = multipart_part(&InitialPayload {
+        data,
+        errors: response.errors,
+        has_next: has_streamed || deferred_len > 0,
+    });
+
+    let streamed = has_streamed.then(|| {
+        multipart_part(&SubsequentPayload {
+            incremental: streamed,
+            has_next: deferred_len > 0,
+        })
+    });
+
+    // Deferred fragments are executed one after another while the response is streamed
+    let deferred =
+        futures::stream::iter(deferred.into_iter().enumerate()).then(move |(index, fragment)| {
+            let context = context.clone();
+            let mut request =
+                async_graphql::Request::new(fragment.query.clone()).variables(variables.clone());
+            if let Some(operation_name) = &operation_name {
+                request = request.operation_name(operation_name);
+            }
+
+            async move {
+                let response = execute_graphql_request(&context, request).await;
+
+                let mut incremental: Vec<IncrementalResult> =
+                    values_at(&response.data, &fragment.path, vec![])
+                        .into_iter()
+                        .map(|(path, data)| IncrementalResult {
+                            data: Some(data.to_owned()),
+                            path,
+                            label: fragment.label.clone(),
+                            ..Default::default()
+                        })
+                        .collect();
+
+                if !response.errors.is_empty() {
+                    match incremental.first_mut() {
+                        Some(result) => result.errors = response.errors,
+                        None => incremental.push(IncrementalResult {
+                            path: fragment
+                                .path
+                                .iter()
+                                .map(|key| key.as_str().into())
+                                .collect(),
+                            label: fragment.label.clone(),
+                            errors: response.errors,
+                            ..Default::default()
+                        }),
+                    }
+                }
+
+                Ok::<Bytes, Infallible>(multipart_part(&SubsequentPayload {
+                    incremental,
+                    has_next: index + 1 < deferred_len,
+                }))
+            }
+        });
+
+    let body = futures::stream::iter(
+        std::iter::once(initial)
+            .chain(streamed)
+            .map(Ok::<Bytes, Infallible>),
+    )
+    .chain(deferred)
+    .chain(futures::stream::once(async {
+        Ok(Bytes::from_static(MULTIPART_END.as_bytes()))
+    }));
+
+    (
+        [(header::CONTENT_TYPE, MULTIPART_CONTENT_TYPE)],
+        StreamBody::new(body),
+    )
+        .into_response()
+}
+
+/// Parts of error messages which indicate an internal failure of the node, for example of its
+/// database.
+const INTERNAL_ERROR_MARKERS: [&str; 7] = [
+    "SQL query failed",
+    "Deletion of row from table",
+    "request in storage provider",
+    "Error occured in OperationStore",
+    "Error occured in DocumentStore",
+    "A fatal error occured in",
+    "Object storage responded",
+];
+
+/// Replaces the messages of internal errors with a generic one.
+///
+/// Every masked error receives a random error id which is returned to the client in the
+/// `errorId` extension and logged next to the original message.
+pub(crate) fn mask_internal_errors(
+    mut response: async_graphql::Response,
+) -> async_graphql::Response {
+    for server_error in response.errors.iter_mut() {
+        let is_internal = INTERNAL_ERROR_MARKERS
+            .iter()
+            .any(|marker| server_error.message.contains(marker));
+
+        if !is_internal {
+            continue;
+        }
+
+        let error_id = format!("{:016x}", rand::random::<u64>());
+        error!(
+            "GraphQL request failed with internal error {}: {}",
+            error_id, server_error.message
+        );
+
+        server_error.message = "Internal server error".to_string();
+        server_error.source = None;
+        server_error
+            .extensions
+            .get_or_insert_with(Default::default)
+            .set("errorId", error_id);
+    }
+
+    response
+}
+
+/// Awaits the execution of a GraphQL request, aborting it when it exceeds the given timeout.
+pub(crate) async fn execute_with_timeout(
+    execution: impl Future<Output = async_graphql::Response>,
+    timeout: Option<Duration>,
+) -> async_graphql::Response {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return execution.await,
+    };
+
+    match tokio::time::timeout(timeout, execution).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!("GraphQL query exceeded timeout of {:?}", timeout);
+            async_graphql::Response::from_errors(vec![ServerError::new(
+                format!("Query exceeded timeout of {:?}", timeout),
+                None,
+            )])
+        }
+    }
 }
 
 /// Handle requests for a blob document served via HTTP.
@@ -43,9 +384,293 @@ pub async fn handle_graphql_query(
 pub async fn handle_blob_document(
     TypedHeader(if_none_match): TypedHeader<IfNoneMatch>,
     Extension(context): Extension<HttpServiceContext>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(signed_url): Query<SignedUrlQuery>,
+    Query(thumbnail): Query<ThumbnailQuery>,
+    Path(document_id): Path<String>,
+) -> Result<Response, BlobHttpError> {
+    check_blob_access(&context, &uri, &headers, &signed_url)?;
+    let document = find_blob_document(&context, &document_id).await?;
+
+    match thumbnail.size {
+        Some(size) => {
+            respond_with_derived_blob(if_none_match, &context, document, &thumbnail_name(&size))
+                .await
+        }
+        None => respond_with_blob(if_none_match, &context, document).await,
+    }
+}
+
+/// Handle `HEAD` requests for a blob document.
+///
+/// Returns the same headers as a regular request for the "latest" version of the document,
+/// without touching the blob data itself.
+pub async fn handle_blob_document_head(
+    TypedHeader(if_none_match): TypedHeader<IfNoneMatch>,
+    Extension(context): Extension<HttpServiceContext>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(signed_url): Query<SignedUrlQuery>,
     Path(document_id): Path<String>,
 ) -> Result<Response, BlobHttpError> {
-    let document_id: DocumentId = DocumentId::from_str(&document_id)
+    check_blob_access(&context, &uri, &headers, &signed_url)?;
+    let document = find_blob_document(&context, &document_id).await?;
+    respond_with_blob_head(if_none_match, document)
+}
+
+/// Handle requests for meta data of a blob document, returned as JSON.
+///
+/// This allows clients to decide if they want to download a potentially large blob.
+pub async fn handle_blob_document_meta(
+    Extension(context): Extension<HttpServiceContext>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(signed_url): Query<SignedUrlQuery>,
+    Path(document_id): Path<String>,
+) -> Result<Json<BlobMeta>, BlobHttpError> {
+    check_blob_access(&context, &uri, &headers, &signed_url)?;
+    let document = find_blob_document(&context, &document_id).await?;
+
+    Ok(Json(BlobMeta {
+        document_id: document.id().to_string(),
+        view_id: document.view_id().to_string(),
+        mime_type: blob_mime_type(&document)?.to_owned(),
+        length: blob_length(&document)?,
+    }))
+}
+
+/// Handle requests for a blob document view served via HTTP.
+///
+/// This method returns the version which was specified by the document view id.
+pub async fn handle_blob_view(
+    TypedHeader(if_none_match): TypedHeader<IfNoneMatch>,
+    Extension(context): Extension<HttpServiceContext>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(signed_url): Query<SignedUrlQuery>,
+    Query(thumbnail): Query<ThumbnailQuery>,
+    Path((document_id, view_id)): Path<(String, String)>,
+) -> Result<Response, BlobHttpError> {
+    check_blob_access(&context, &uri, &headers, &signed_url)?;
+    let document = find_blob_view(&context, &document_id, &view_id).await?;
+
+    match thumbnail.size {
+        Some(size) => {
+            respond_with_derived_blob(if_none_match, &context, document, &thumbnail_name(&size))
+                .await
+        }
+        None => respond_with_blob(if_none_match, &context, document).await,
+    }
+}
+
+/// Handle `HEAD` requests for a blob document view.
+pub async fn handle_blob_view_head(
+    TypedHeader(if_none_match): TypedHeader<IfNoneMatch>,
+    Extension(context): Extension<HttpServiceContext>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(signed_url): Query<SignedUrlQuery>,
+    Path((document_id, view_id)): Path<(String, String)>,
+) -> Result<Response, BlobHttpError> {
+    check_blob_access(&context, &uri, &headers, &signed_url)?;
+    let document = find_blob_view(&context, &document_id, &view_id).await?;
+    respond_with_blob_head(if_none_match, document)
+}
+
+/// Handle requests for a blob derived from a blob document view by a blob hook.
+pub async fn handle_derived_blob(
+    TypedHeader(if_none_match): TypedHeader<IfNoneMatch>,
+    Extension(context): Extension<HttpServiceContext>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(signed_url): Query<SignedUrlQuery>,
+    Path((document_id, view_id, name)): Path<(String, String, String)>,
+) -> Result<Response, BlobHttpError> {
+    check_blob_access(&context, &uri, &headers, &signed_url)?;
+    let document = find_blob_view(&context, &document_id, &view_id).await?;
+    respond_with_derived_blob(if_none_match, &context, document, &name).await
+}
+
+/// Handle requests to start a resumable blob upload.
+///
+/// Expects the total length of the blob in the `Upload-Length` header and optionally its MIME
+/// type in `Upload-Mime-Type`. Responds with the location of the new upload session.
+///
+/// Uploaded blobs get published with the identity of the node, all upload routes therefore
+/// require the admin token.
+pub async fn handle_blob_upload_create(
+    Extension(context): Extension<HttpServiceContext>,
+    headers: HeaderMap,
+) -> Result<Response, UploadHttpError> {
+    check_upload_access(&context, &headers)?;
+
+    let length = parse_header(&headers, &UPLOAD_LENGTH)?;
+    let mime_type = match headers.get(&UPLOAD_MIME_TYPE) {
+        Some(value) => value
+            .to_str()
+            .map_err(|_| UploadHttpError::InvalidHeader(UPLOAD_MIME_TYPE))?,
+        None => "application/octet-stream",
+    };
+
+    let session = context.uploads.create(length, mime_type).await?;
+
+    let headers = [
+        (header::LOCATION, format!("/blobs/uploads/{}", session.id)),
+        (UPLOAD_OFFSET, session.offset.to_string()),
+    ];
+
+    Ok((StatusCode::CREATED, headers).into_response())
+}
+
+/// Handle `HEAD` requests for a resumable blob upload.
+///
+/// Clients use this after a lost connection to find out from which offset they can continue
+/// uploading.
+pub async fn handle_blob_upload_head(
+    Extension(context): Extension<HttpServiceContext>,
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, UploadHttpError> {
+    check_upload_access(&context, &headers)?;
+
+    let session = context
+        .uploads
+        .get(&session_id)
+        .await?
+        .ok_or(BlobUploadError::NotFound)?;
+
+    let headers = [
+        (UPLOAD_OFFSET, session.offset.to_string()),
+        (UPLOAD_LENGTH, session.length.to_string()),
+        (header::CACHE_CONTROL, "no-store".to_string()),
+    ];
+
+    Ok(headers.into_response())
+}
+
+/// Handle requests appending a chunk of data to a resumable blob upload.
+///
+/// The chunk needs to start at the offset given in the `Upload-Offset` header, which has to match
+/// the current offset of the session. Responds with the new offset and, as soon as the upload is
+/// complete, with the id of the published blob document in the `Blob-Document-Id` header.
+pub async fn handle_blob_upload_patch(
+    Extension(context): Extension<HttpServiceContext>,
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, UploadHttpError> {
+    check_upload_access(&context, &headers)?;
+
+    let offset = parse_header(&headers, &UPLOAD_OFFSET)?;
+
+    let response = match context.uploads.append(&session_id, offset, &body).await? {
+        BlobUploadProgress::InProgress(offset) => {
+            let headers = [(UPLOAD_OFFSET, offset.to_string())];
+            (StatusCode::NO_CONTENT, headers).into_response()
+        }
+        BlobUploadProgress::Completed(view_id) => {
+            let headers = [
+                (UPLOAD_OFFSET, (offset + body.len() as u64).to_string()),
+                (BLOB_DOCUMENT_ID, view_id.to_string()),
+            ];
+            (StatusCode::NO_CONTENT, headers).into_response()
+        }
+    };
+
+    Ok(response)
+}
+
+/// Handle requests cancelling a resumable blob upload.
+pub async fn handle_blob_upload_delete(
+    Extension(context): Extension<HttpServiceContext>,
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, UploadHttpError> {
+    check_upload_access(&context, &headers)?;
+
+    if !context.uploads.terminate(&session_id).await? {
+        return Err(BlobUploadError::NotFound.into());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Rejects upload requests which did not come with the admin token.
+fn check_upload_access(
+    context: &HttpServiceContext,
+    headers: &HeaderMap,
+) -> Result<(), UploadHttpError> {
+    if is_admin_request(context, headers) {
+        Ok(())
+    } else {
+        Err(UploadHttpError::Unauthorized)
+    }
+}
+
+/// Meta data of a blob document.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobMeta {
+    document_id: String,
+    view_id: String,
+    mime_type: String,
+    length: i64,
+}
+
+/// Query parameters of a signed blob URL.
+#[derive(Debug, Deserialize)]
+pub struct SignedUrlQuery {
+    /// UNIX timestamp until the signature is valid.
+    expires: Option<u64>,
+
+    /// Hex-encoded signature over the path and expiry date.
+    signature: Option<String>,
+}
+
+/// Query parameters selecting a thumbnail of an image blob.
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailQuery {
+    /// Name of the configured thumbnail size, for example "small".
+    size: Option<String>,
+}
+
+/// Checks if the origin of a blob request is allowed to access it or if it came with a valid
+/// signature.
+fn check_blob_access(
+    context: &HttpServiceContext,
+    uri: &Uri,
+    headers: &HeaderMap,
+    signed_url: &SignedUrlQuery,
+) -> Result<(), BlobHttpError> {
+    let header_str = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    // Browsers do not send an `Origin` header for all embedded media, fall back to the referrer
+    let origin = header_str(header::ORIGIN).or_else(|| header_str(header::REFERER));
+
+    let signature = match (signed_url.expires, &signed_url.signature) {
+        (Some(expires), Some(signature)) => Some((expires, signature.as_str())),
+        _ => None,
+    };
+
+    let decision =
+        context
+            .blob_access
+            .check(uri.path(), header_str(header::HOST), origin, signature);
+
+    if decision.is_allowed() {
+        Ok(())
+    } else {
+        Err(BlobHttpError::Forbidden)
+    }
+}
+
+/// Returns the latest view of a blob document.
+async fn find_blob_document(
+    context: &HttpServiceContext,
+    document_id: &str,
+) -> Result<impl AsDocument, BlobHttpError> {
+    let document_id = DocumentId::from_str(document_id)
         .map_err(|err| BlobHttpError::InvalidFormat(err.into()))?;
 
     let document = context
@@ -53,27 +678,25 @@ pub async fn handle_blob_document(
         .get_document(&document_id)
         .await
         .map_err(|err| BlobHttpError::InternalError(err.into()))?
-        .ok_or_else(|| BlobHttpError::NotFound)?;
+        .ok_or(BlobHttpError::NotFound)?;
 
     // Requested document is not a blob, treat this as a "not found" error
     if document.schema_id() != &SchemaId::Blob(1) {
         return Err(BlobHttpError::NotFound);
     }
 
-    respond_with_blob(if_none_match, context.blobs_base_path, document).await
+    Ok(document)
 }
 
-/// Handle requests for a blob document view served via HTTP.
-///
-/// This method returns the version which was specified by the document view id.
-pub async fn handle_blob_view(
-    TypedHeader(if_none_match): TypedHeader<IfNoneMatch>,
-    Extension(context): Extension<HttpServiceContext>,
-    Path((document_id, view_id)): Path<(String, String)>,
-) -> Result<Response, BlobHttpError> {
-    let document_id = DocumentId::from_str(&document_id)
+/// Returns a specific view of a blob document.
+async fn find_blob_view(
+    context: &HttpServiceContext,
+    document_id: &str,
+    view_id: &str,
+) -> Result<impl AsDocument, BlobHttpError> {
+    let document_id = DocumentId::from_str(document_id)
         .map_err(|err| BlobHttpError::InvalidFormat(err.into()))?;
-    let view_id = DocumentViewId::from_str(&view_id)
+    let view_id = DocumentViewId::from_str(view_id)
         .map_err(|err| BlobHttpError::InvalidFormat(err.into()))?;
 
     let document = context
@@ -87,43 +710,154 @@ pub async fn handle_blob_view(
         return Err(BlobHttpError::NotFound);
     }
 
-    respond_with_blob(if_none_match, context.blobs_base_path, document).await
+    Ok(document)
+}
+
+/// Get MIME type of blob.
+fn blob_mime_type(document: &impl AsDocument) -> Result<&str, BlobHttpError> {
+    match document.get("mime_type") {
+        Some(OperationValue::String(value)) => Ok(value),
+        _ => Err(BlobHttpError::InternalError(anyhow!(
+            "Blob document did not contain a valid 'mime_type' field"
+        ))),
+    }
+}
+
+/// Get length of blob in bytes.
+fn blob_length(document: &impl AsDocument) -> Result<i64, BlobHttpError> {
+    match document.get("length") {
+        Some(OperationValue::Integer(value)) => Ok(*value),
+        _ => Err(BlobHttpError::InternalError(anyhow!(
+            "Blob document did not contain a valid 'length' field"
+        ))),
+    }
+}
+
+/// Parse a numeric header value of a resumable blob upload request.
+fn parse_header(headers: &HeaderMap, name: &HeaderName) -> Result<u64, UploadHttpError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| UploadHttpError::InvalidHeader(name.to_owned()))
+}
+
+/// Returns HTTP response with the headers of a blob but without a body.
+///
+/// All values are taken from the blob document, the blob data itself is not read.
+fn respond_with_blob_head(
+    if_none_match: IfNoneMatch,
+    document: impl AsDocument,
+) -> Result<Response, BlobHttpError> {
+    let etag_str = format!("\"{}\"", document.view_id());
+
+    let etag = ETag::from_str(&etag_str).map_err(|err| BlobHttpError::InternalError(err.into()))?;
+    if !if_none_match.precondition_passes(&etag) {
+        return Ok(StatusCode::NOT_MODIFIED.into_response());
+    }
+
+    let headers = [
+        (header::CONTENT_TYPE, blob_mime_type(&document)?.to_owned()),
+        (header::CONTENT_LENGTH, blob_length(&document)?.to_string()),
+        (header::ETAG, etag_str),
+    ];
+
+    Ok(headers.into_response())
+}
+
+/// Serves a blob derived from the given blob document view by the blob hook with this name.
+async fn respond_with_derived_blob(
+    if_none_match: IfNoneMatch,
+    context: &HttpServiceContext,
+    document: impl AsDocument,
+    name: &str,
+) -> Result<Response, BlobHttpError> {
+    let mime_type = context
+        .store
+        .get_derived_blob(document.view_id(), name)
+        .await
+        .map_err(|err| BlobHttpError::InternalError(err.into()))?
+        .ok_or(BlobHttpError::NotFound)?;
+
+    respond_with_blob_key(
+        if_none_match,
+        context.blobs.clone(),
+        BlobKey::derived(document.view_id(), name),
+        &mime_type,
+    )
+    .await
 }
 
 /// Returns HTTP response with the contents, ETag and given MIME type of a blob.
 ///
-/// Supports basic caching by handling "IfNoneMatch" headers matching the latest ETag.
+/// The assembled blob file gets verified against the hash of its pieces first when this is
+/// enabled in the configuration.
 async fn respond_with_blob(
     if_none_match: IfNoneMatch,
-    blobs_base_path: PathBuf,
+    context: &HttpServiceContext,
     document: impl AsDocument,
 ) -> Result<Response, BlobHttpError> {
-    let view_id = document.view_id();
+    // Clients with a cached copy don't receive the body, there is no need to verify it then
+    let blob_key = BlobKey::from(document.view_id());
+    if !if_none_match.precondition_passes(&blob_etag(&blob_key)?) {
+        return Ok(StatusCode::NOT_MODIFIED.into_response());
+    }
+
+    context
+        .blob_integrity
+        .verify(
+            &context.store,
+            &context.blobs,
+            document.view_id(),
+            blob_length(&document)? as u64,
+        )
+        .await
+        .map_err(BlobHttpError::InternalError)?;
+
+    respond_with_blob_key(
+        if_none_match,
+        context.blobs.clone(),
+        blob_key,
+        blob_mime_type(&document)?,
+    )
+    .await
+}
 
-    // Convert document view id into correct ETag value (with quotation marks defined in
-    // https://datatracker.ietf.org/doc/html/rfc7232#section-2.3)
-    let to_etag_str = || format!("\"{}\"", view_id);
+/// Returns HTTP response with the contents, ETag and given MIME type of a file stored in the blob
+/// backend.
+///
+/// Supports basic caching by handling "IfNoneMatch" headers matching the latest ETag.
+async fn respond_with_blob_key(
+    if_none_match: IfNoneMatch,
+    blobs: SharedBlobBackend,
+    blob_key: BlobKey,
+    mime_type_str: &str,
+) -> Result<Response, BlobHttpError> {
+    let to_etag_str = || format!("\"{}\"", blob_key);
 
     // Respond with 304 "not modified" if ETag still matches (document did not get updated)
-    let etag =
-        ETag::from_str(&to_etag_str()).map_err(|err| BlobHttpError::InternalError(err.into()))?;
-    if !if_none_match.precondition_passes(&etag) {
+    if !if_none_match.precondition_passes(&blob_etag(&blob_key)?) {
         return Ok(StatusCode::NOT_MODIFIED.into_response());
     }
 
-    // Get MIME type of blob
-    let mime_type_str = match document.get("mime_type") {
-        Some(p2panda_rs::operation::OperationValue::String(value)) => Ok(value),
-        _ => Err(BlobHttpError::InternalError(anyhow!(
-            "Blob document did not contain a valid 'mime_type' field"
-        ))),
-    }?;
+    // Redirect to the public URL of the blob when the backend serves it directly, for example via
+    // a CDN
+    if let Some(url) = blobs.public_url(&blob_key) {
+        let headers = [
+            (header::LOCATION, url.as_str()),
+            (header::ETAG, &to_etag_str()),
+        ];
+        return Ok((StatusCode::TEMPORARY_REDIRECT, headers).into_response());
+    }
 
-    // Get body from read-stream of stored file on file system
-    let mut file_path = blobs_base_path;
-    file_path.push(format!("{view_id}"));
-    match File::open(&file_path).await {
-        Ok(file) => {
+    // Get body from read-stream of stored blob in the backend
+    let reader = blobs
+        .get(&blob_key)
+        .await
+        .map_err(|err| BlobHttpError::InternalError(err.into()))?;
+
+    match reader {
+        Some(stream) => {
             let headers = [
                 // MIME type to allow browsers to correctly handle this specific blob format
                 (header::CONTENT_TYPE, mime_type_str),
@@ -131,17 +865,15 @@ async fn respond_with_blob(
                 (header::ETAG, &to_etag_str()),
             ];
 
-            let stream = ReaderStream::new(file);
             let body = StreamBody::new(stream);
 
             Ok((headers, body).into_response())
         }
-        Err(_) => {
+        None => {
             warn!(
-                "Data inconsistency detected: Blob document {} exists in database but not on file
-                system at path {}!",
-                view_id.display(),
-                file_path.display()
+                "Data inconsistency detected: Blob {} exists in database but not in blob storage
+                backend!",
+                blob_key,
             );
 
             Err(BlobHttpError::NotFound)
@@ -149,9 +881,18 @@ async fn respond_with_blob(
     }
 }
 
+/// Converts the blob key (the document view id for regular blobs) into the ETag of the blob.
+///
+/// ETag values need quotation marks, see https://datatracker.ietf.org/doc/html/rfc7232#section-2.3
+fn blob_etag(blob_key: &BlobKey) -> Result<ETag, BlobHttpError> {
+    ETag::from_str(&format!("\"{}\"", blob_key))
+        .map_err(|err| BlobHttpError::InternalError(err.into()))
+}
+
 #[derive(Debug)]
 pub enum BlobHttpError {
     NotFound,
+    Forbidden,
     InvalidFormat(anyhow::Error),
     InternalError(anyhow::Error),
 }
@@ -162,6 +903,11 @@ impl IntoResponse for BlobHttpError {
             BlobHttpError::NotFound => {
                 (StatusCode::NOT_FOUND, "Could not find document").into_response()
             }
+            BlobHttpError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "Blob can not be accessed from this origin",
+            )
+                .into_response(),
             BlobHttpError::InvalidFormat(err) => (
                 StatusCode::BAD_REQUEST,
                 format!("Could not parse identifier: {}", err),
@@ -176,18 +922,217 @@ impl IntoResponse for BlobHttpError {
     }
 }
 
+#[derive(Debug)]
+pub enum UploadHttpError {
+    Unauthorized,
+    InvalidHeader(HeaderName),
+    Upload(BlobUploadError),
+}
+
+impl From<BlobUploadError> for UploadHttpError {
+    fn from(err: BlobUploadError) -> Self {
+        UploadHttpError::Upload(err)
+    }
+}
+
+impl IntoResponse for UploadHttpError {
+    fn into_response(self) -> Response {
+        match self {
+            UploadHttpError::Unauthorized => {
+                (StatusCode::UNAUTHORIZED, "Admin token required").into_response()
+            }
+            UploadHttpError::InvalidHeader(name) => (
+                StatusCode::BAD_REQUEST,
+                format!("Missing or invalid '{}' header", name),
+            )
+                .into_response(),
+            UploadHttpError::Upload(err) => {
+                let status = match err {
+                    BlobUploadError::NotFound => StatusCode::NOT_FOUND,
+                    BlobUploadError::OffsetMismatch(offset) => {
+                        // Tell the client where to continue
+                        let headers = [(UPLOAD_OFFSET, offset.to_string())];
+                        return (StatusCode::CONFLICT, headers, err.to_string()).into_response();
+                    }
+                    BlobUploadError::InvalidLength => StatusCode::BAD_REQUEST,
+                    BlobUploadError::TooLarge(_) | BlobUploadError::TooManyPieces(_) => {
+                        StatusCode::PAYLOAD_TOO_LARGE
+                    }
+                    BlobUploadError::UnsupportedSchema(_) => StatusCode::FORBIDDEN,
+                    BlobUploadError::Publish(_) | BlobUploadError::Store(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+
+                (status, err.to_string()).into_response()
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use futures::stream;
     use http::{header, StatusCode};
     use p2panda_rs::document::DocumentId;
     use p2panda_rs::identity::KeyPair;
     use p2panda_rs::schema::validate::MAX_BLOB_PIECE_LENGTH;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::storage_provider::traits::OperationStore;
     use p2panda_rs::test_utils::fixtures::key_pair;
     use rstest::rstest;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
 
+    use crate::blobs::BlobKey;
+    use crate::context::Context;
     use crate::materializer::tasks::blob_task;
     use crate::materializer::TaskInput;
-    use crate::test_utils::{add_blob, http_test_client, test_runner, update_blob, TestNode};
+    use crate::test_utils::{
+        add_blob, add_document, add_schema, http_test_client, test_runner, update_blob, TestNode,
+    };
+
+    use super::{execute_with_timeout, mask_internal_errors};
+
+    #[tokio::test]
+    async fn abort_query_after_timeout() {
+        let response =
+            execute_with_timeout(std::future::pending(), Some(Duration::from_millis(10))).await;
+        assert_eq!(response.errors[0].message, "Query exceeded timeout of 10ms");
+
+        let response = execute_with_timeout(
+            async { async_graphql::Response::new(async_graphql::Value::Null) },
+            Some(Duration::from_millis(10)),
+        )
+        .await;
+        assert!(response.errors.is_empty());
+    }
+
+    #[test]
+    fn mask_internal_error_messages() {
+        let response = async_graphql::Response::from_errors(vec![
+            async_graphql::ServerError::new("SQL query failed: no such table: entries", None),
+            async_graphql::ServerError::new("Schema not found", None),
+        ]);
+
+        let response = mask_internal_errors(response);
+        assert_eq!(response.errors[0].message, "Internal server error");
+        let extensions = serde_json::to_value(&response.errors[0].extensions).unwrap();
+        assert_eq!(extensions["errorId"].as_str().unwrap().len(), 16);
+
+        // Errors caused by clients are kept as they are
+        assert_eq!(response.errors[1].message, "Schema not found");
+        assert!(response.errors[1].extensions.is_none());
+    }
+
+    #[rstest]
+    fn deliver_query_incrementally(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "animals",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            for name in ["panda", "penguin", "llama"] {
+                add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("name", name.into())],
+                    &key_pair,
+                )
+                .await;
+            }
+
+            let query = format!(
+                r#"{{
+                    collection: all_{schema_id} {{
+                        documents @stream(initialCount: 1) {{ fields {{ name }} }}
+                    }}
+                    ... @defer(label: "total") {{
+                        total: all_{schema_id} {{ totalCount }}
+                    }}
+                }}"#,
+                schema_id = schema.id()
+            );
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .post("/graphql")
+                .header(header::ACCEPT, "multipart/mixed")
+                .json(&json!({ "query": query }))
+                .send()
+                .await;
+            assert_eq!(
+                response.headers().get(header::CONTENT_TYPE).unwrap(),
+                "multipart/mixed; boundary=\"-\"; deferSpec=20220824"
+            );
+
+            let body = response.text().await;
+            assert!(body.ends_with("\r\n-----\r\n"));
+
+            let parts: Vec<serde_json::Value> = body
+                .trim_end_matches("\r\n-----\r\n")
+                .split("\r\n---\r\n")
+                .skip(1)
+                .map(|part| serde_json::from_str(part.split("\r\n\r\n").nth(1).unwrap()).unwrap())
+                .collect();
+            assert_eq!(parts.len(), 3);
+
+            // Initial response contains only the first item of the streamed list
+            assert_eq!(
+                parts[0]["data"]["collection"]["documents"]
+                    .as_array()
+                    .unwrap()
+                    .len(),
+                1
+            );
+            assert_eq!(parts[0]["data"].get("total"), None);
+            assert_eq!(parts[0]["hasNext"], true);
+
+            // Remaining items follow
+            assert_eq!(
+                parts[1]["incremental"][0]["items"]
+                    .as_array()
+                    .unwrap()
+                    .len(),
+                2
+            );
+            assert_eq!(
+                parts[1]["incremental"][0]["path"],
+                json!(["collection", "documents", 1])
+            );
+            assert_eq!(parts[1]["hasNext"], true);
+
+            // Deferred fragment is delivered last
+            assert_eq!(
+                parts[2]["incremental"][0],
+                json!({
+                    "data": { "total": { "totalCount": 3 } },
+                    "path": [],
+                    "label": "total",
+                })
+            );
+            assert_eq!(parts[2]["hasNext"], false);
+
+            // Clients which don't accept multipart responses receive everything at once
+            let response = client
+                .post("/graphql")
+                .json(&json!({ "query": query }))
+                .send()
+                .await;
+            let response: async_graphql::Response = response.json().await;
+            assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+            let data = response.data.into_json().unwrap();
+            assert_eq!(data["collection"]["documents"].as_array().unwrap().len(), 3);
+            assert_eq!(data["total"]["totalCount"], 3);
+        });
+    }
 
     #[rstest]
     fn responds_with_blob_in_http_body(key_pair: KeyPair) {
@@ -227,6 +1172,323 @@ mod tests {
         })
     }
 
+    #[rstest]
+    fn responds_to_head_requests(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let blob_data = "Hello, World!".as_bytes();
+            let blob_view_id = add_blob(&mut node, blob_data, 6, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+
+            // Headers can be requested without the blob being materialized
+            let client = http_test_client(&node).await;
+
+            for path in [
+                format!("/blobs/{}", document_id),
+                format!("/blobs/{}/{}", document_id, blob_view_id),
+            ] {
+                let response = client.head(&path).send().await;
+                let status_code = response.status();
+                let headers = response.headers();
+                let body = response.bytes().await;
+
+                assert_eq!(status_code, StatusCode::OK);
+                assert_eq!(headers["content-type"], "text/plain");
+                assert_eq!(headers["content-length"], "13");
+                assert_eq!(headers["etag"], format!("\"{}\"", blob_view_id));
+                assert!(body.is_empty());
+            }
+        })
+    }
+
+    #[rstest]
+    fn responds_with_blob_meta(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let blob_data = "Hello, World!".as_bytes();
+            let blob_view_id = add_blob(&mut node, blob_data, 6, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+
+            let client = http_test_client(&node).await;
+
+            let response = client
+                .get(&format!("/blobs/{}/meta", document_id))
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let meta: serde_json::Value = response.json().await;
+            assert_eq!(
+                meta,
+                serde_json::json!({
+                    "documentId": document_id.to_string(),
+                    "viewId": blob_view_id.to_string(),
+                    "mimeType": "text/plain",
+                    "length": 13,
+                })
+            );
+        })
+    }
+
+    #[rstest]
+    fn responds_with_derived_blob(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let blob_data = "Hello, World!".as_bytes();
+            let blob_view_id = add_blob(&mut node, blob_data, 6, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+
+            // Store a derived blob as a blob hook would do it
+            let chunks = stream::iter([Ok("HELLO, WORLD!".as_bytes().to_vec())]);
+            node.context
+                .blobs
+                .put(
+                    &BlobKey::derived(&blob_view_id, "uppercase"),
+                    13,
+                    Box::pin(chunks),
+                )
+                .await
+                .unwrap();
+            node.context
+                .store
+                .insert_derived_blob(&blob_view_id, "uppercase", "text/plain")
+                .await
+                .unwrap();
+
+            let client = http_test_client(&node).await;
+
+            // "/blobs/<document_id>/<view_id>/<name>" path
+            let response = client
+                .get(&format!(
+                    "/blobs/{}/{}/uppercase",
+                    document_id, blob_view_id
+                ))
+                .send()
+                .await;
+            let status_code = response.status();
+            let headers = response.headers();
+            let body = response.text().await;
+
+            assert_eq!(status_code, StatusCode::OK);
+            assert_eq!(headers["content-type"], "text/plain");
+            assert_eq!(body, "HELLO, WORLD!");
+
+            // Unknown derived blob
+            let response = client
+                .get(&format!(
+                    "/blobs/{}/{}/thumbnail",
+                    document_id, blob_view_id
+                ))
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+    }
+
+    #[rstest]
+    fn responds_with_thumbnail(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let blob_data = "Hello, World!".as_bytes();
+            let blob_view_id = add_blob(&mut node, blob_data, 6, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+
+            // Store a thumbnail as the thumbnail hook would do it
+            let chunks = stream::iter([Ok("Hello".as_bytes().to_vec())]);
+            node.context
+                .blobs
+                .put(
+                    &BlobKey::derived(&blob_view_id, "thumbnail_small"),
+                    5,
+                    Box::pin(chunks),
+                )
+                .await
+                .unwrap();
+            node.context
+                .store
+                .insert_derived_blob(&blob_view_id, "thumbnail_small", "text/plain")
+                .await
+                .unwrap();
+
+            let client = http_test_client(&node).await;
+
+            for path in [
+                format!("/blobs/{}?size=small", document_id),
+                format!("/blobs/{}/{}?size=small", document_id, blob_view_id),
+            ] {
+                let response = client.get(&path).send().await;
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(response.text().await, "Hello");
+            }
+
+            // Unknown thumbnail size
+            let response = client
+                .get(&format!("/blobs/{}?size=large", document_id))
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+    }
+
+    #[test]
+    fn resumable_blob_uploads() {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.admin_token = Some("secret".into());
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let client = http_test_client(&node).await;
+
+            // Uploads get published with the node's identity and require the admin token
+            for authorization in [None, Some("Bearer wrong")] {
+                let mut request = client.post("/blobs/uploads").header("Upload-Length", "13");
+                if let Some(authorization) = authorization {
+                    request = request.header("Authorization", authorization);
+                }
+                let response = request.send().await;
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            }
+
+            // Start upload session
+            let response = client
+                .post("/blobs/uploads")
+                .header("Authorization", "Bearer secret")
+                .header("Upload-Length", "13")
+                .header("Upload-Mime-Type", "text/plain")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let location = response.headers()[header::LOCATION]
+                .to_str()
+                .unwrap()
+                .to_string();
+
+            // Upload first chunk
+            let response = client
+                .patch(&location)
+                .header("Authorization", "Bearer secret")
+                .header("Upload-Offset", "0")
+                .body("Hello, ")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+            assert_eq!(response.headers()["upload-offset"], "7");
+
+            // Connection got lost, ask node where to continue
+            let response = client
+                .head(&location)
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["upload-offset"], "7");
+            assert_eq!(response.headers()["upload-length"], "13");
+
+            // Sending data from a wrong offset gets rejected
+            let response = client
+                .patch(&location)
+                .header("Authorization", "Bearer secret")
+                .header("Upload-Offset", "0")
+                .body("Hello, World!")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            assert_eq!(response.headers()["upload-offset"], "7");
+
+            // Upload last chunk, node publishes the blob
+            let response = client
+                .patch(&location)
+                .header("Authorization", "Bearer secret")
+                .header("Upload-Offset", "7")
+                .body("World!")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+            assert_eq!(response.headers()["upload-offset"], "13");
+            let document_id = response.headers()["blob-document-id"]
+                .to_str()
+                .unwrap()
+                .to_string();
+
+            // Blob document is known now, but still needs to be materialized
+            let document_view_id = document_id.parse().unwrap();
+            let operation = node
+                .context
+                .store
+                .get_operation(&document_view_id)
+                .await
+                .unwrap();
+            assert!(operation.is_some());
+
+            // Session is gone
+            let response = client
+                .head(&location)
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+    }
+
+    #[test]
+    fn cancel_blob_uploads() {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.admin_token = Some("secret".into());
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let client = http_test_client(&node).await;
+
+            // Missing length
+            let response = client
+                .post("/blobs/uploads")
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let response = client
+                .post("/blobs/uploads")
+                .header("Authorization", "Bearer secret")
+                .header("Upload-Length", "13")
+                .send()
+                .await;
+            let location = response.headers()[header::LOCATION]
+                .to_str()
+                .unwrap()
+                .to_string();
+
+            let response = client
+                .delete(&location)
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+            let response = client
+                .delete(&location)
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let response = client
+                .patch(&location)
+                .header("Authorization", "Bearer secret")
+                .header("Upload-Offset", "0")
+                .body("Hello, World!")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        })
+    }
+
     #[rstest]
     fn document_route_responds_with_latest_view(key_pair: KeyPair) {
         test_runner(|mut node: TestNode| async move {
@@ -309,6 +1571,45 @@ mod tests {
         })
     }
 
+    #[rstest]
+    fn verify_blobs_only_when_sending_them(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let mut config = node.context.config.clone();
+            config.verify_blobs = true;
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let blob_view_id =
+                add_blob(&mut node, b"Hello, World!", 6, "text/plain", &key_pair).await;
+            let document_id: DocumentId = blob_view_id.to_string().parse().unwrap();
+            blob_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(blob_view_id.clone()),
+            )
+            .await
+            .unwrap();
+
+            let client = http_test_client(&node).await;
+
+            // Clients with a cached copy of the blob receive no body, it does not get verified
+            let response = client
+                .get(&format!("/blobs/{}", document_id))
+                .header(header::IF_NONE_MATCH, format!("\"{}\"", blob_view_id))
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(node.context.blob_integrity.metrics().verified, 0);
+
+            let response = client.get(&format!("/blobs/{}", document_id)).send().await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(node.context.blob_integrity.metrics().verified, 1);
+        })
+    }
+
     #[rstest]
     fn handles_etag_and_if_none_match_precondition(key_pair: KeyPair) {
         test_runner(|mut node: TestNode| async move {
@@ -398,4 +1699,49 @@ mod tests {
             assert_eq!(response.status(), expected_status_code);
         })
     }
+
+    #[test]
+    fn automatic_persisted_queries() {
+        test_runner(|node: TestNode| async move {
+            let client = http_test_client(&node).await;
+            let query = "{ nodeStatus { connectedPeers } }";
+            let extensions = json!({
+                "persistedQuery": {
+                    "version": 1,
+                    "sha256Hash": hex::encode(Sha256::digest(query.as_bytes())),
+                }
+            });
+
+            // Hash of a query which was not registered yet
+            let response: serde_json::Value = client
+                .post("/graphql")
+                .json(&json!({ "extensions": extensions }))
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(
+                response["errors"][0]["message"],
+                json!("PersistedQueryNotFound")
+            );
+
+            // Register the query and send the hash only afterwards
+            for body in [
+                json!({ "query": query, "extensions": extensions }),
+                json!({ "extensions": extensions }),
+            ] {
+                let response: serde_json::Value = client
+                    .post("/graphql")
+                    .json(&body)
+                    .send()
+                    .await
+                    .json()
+                    .await;
+                assert_eq!(
+                    response,
+                    json!({ "data": { "nodeStatus": { "connectedPeers": 0 } } })
+                );
+            }
+        })
+    }
 }
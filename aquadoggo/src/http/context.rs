@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::blobs::{BlobAccess, BlobIntegrity, BlobUploads, SharedBlobBackend};
+use crate::config::Configuration;
 use crate::db::SqlStore;
 use crate::graphql::GraphQLSchemaManager;
+use crate::http::persisted_queries::PersistedQueries;
 
 #[derive(Clone)]
 pub struct HttpServiceContext {
@@ -13,16 +16,60 @@ pub struct HttpServiceContext {
     /// Dynamic GraphQL schema manager.
     pub schema: GraphQLSchemaManager,
 
-    /// Path of the directory where blobs should be served from.
-    pub blobs_base_path: PathBuf,
+    /// Storage backend blobs are served from.
+    pub blobs: SharedBlobBackend,
+
+    /// Access control for blobs served via HTTP.
+    pub blob_access: BlobAccess,
+
+    /// Verification of assembled blob files before they get served.
+    pub blob_integrity: BlobIntegrity,
+
+    /// Resumable blob uploads.
+    pub uploads: BlobUploads,
+
+    /// Maximum duration of a GraphQL request.
+    pub query_timeout: Option<Duration>,
+
+    /// Replace messages of internal errors in GraphQL responses with generic ones.
+    pub mask_errors: bool,
+
+    /// Only execute GraphQL operations which were registered in advance.
+    pub operation_allow_list: bool,
+
+    /// Queries registered by clients using automatic persisted queries.
+    pub persisted_queries: PersistedQueries,
+
+    /// Token authenticating admin requests, admin mutations are disabled when not set.
+    pub admin_token: Option<String>,
 }
 
 impl HttpServiceContext {
-    pub fn new(store: SqlStore, schema: GraphQLSchemaManager, blobs_base_path: PathBuf) -> Self {
+    /// Returns a new instance of `HttpServiceContext`.
+    ///
+    /// Query timeout, error masking, operation allow-list and admin token are taken from the given
+    /// configuration.
+    pub fn new(
+        store: SqlStore,
+        schema: GraphQLSchemaManager,
+        blobs: SharedBlobBackend,
+        blob_access: BlobAccess,
+        blob_integrity: BlobIntegrity,
+        uploads: BlobUploads,
+        config: &Configuration,
+    ) -> Self {
         Self {
             store,
             schema,
-            blobs_base_path,
+            blobs,
+            blob_access,
+            blob_integrity,
+            uploads,
+            query_timeout: config.query_timeout,
+            mask_errors: config.mask_errors,
+            operation_allow_list: config.operation_allow_list,
+            persisted_queries: PersistedQueries::default(),
+            admin_token: config.admin_token.clone(),
         }
     }
 }
@@ -0,0 +1,565 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Export of all documents of a schema in Apache Arrow IPC or Parquet format.
+//!
+//! Data analysis tools like pandas or polars can read these formats directly, clients therefore
+//! don't need to page through the GraphQL API to retrieve a whole dataset.
+
+use std::io::{self, Write};
+use std::num::NonZeroU64;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Error};
+use arrow_array::builder::{
+    BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder, ListBuilder, StringBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+use axum::body::{Bytes, StreamBody};
+use axum::extract::{Extension, Path, Query};
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderMap, StatusCode};
+use log::warn;
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::operation::OperationValue;
+use p2panda_rs::schema::{FieldType, Schema, SchemaId};
+use parquet::arrow::ArrowWriter;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio::task;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::db::query::{Field, Filter, MetaField, Order, Pagination, PaginationField, Select};
+use crate::db::stores::Query as StoreQuery;
+use crate::db::types::StorageDocument;
+use crate::db::SqlStore;
+use crate::http::api::is_admin_request;
+use crate::http::context::HttpServiceContext;
+
+/// Number of documents fetched from the database and written as one record batch.
+const EXPORT_PAGE_SIZE: u64 = 1000;
+
+/// Number of encoded record batches waiting to be sent before the export pauses.
+const EXPORT_CHANNEL_SIZE: usize = 2;
+
+/// File formats documents can be exported in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Apache Arrow IPC streaming format.
+    #[default]
+    Arrow,
+
+    /// Apache Parquet file format.
+    Parquet,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Arrow => "application/vnd.apache.arrow.stream",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+/// Query parameters of export requests.
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// Handle requests exporting all documents of a schema.
+///
+/// Exports read whole collections from the database and are therefore only available to requests
+/// sending the admin token. Documents are encoded page by page and streamed to the client, the
+/// node never holds the whole dataset in memory.
+pub async fn handle_export(
+    Extension(context): Extension<HttpServiceContext>,
+    Path(schema_id): Path<String>,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, ExportHttpError> {
+    if !is_admin_request(&context, &headers) {
+        return Err(ExportHttpError::Unauthorized);
+    }
+
+    let schema_id = SchemaId::from_str(&schema_id).map_err(|_| ExportHttpError::NotFound)?;
+    let schema = match &schema_id {
+        SchemaId::Application(_, view_id) => context
+            .store
+            .get_schema_by_id(view_id)
+            .await
+            .map_err(|err| ExportHttpError::InternalError(err.into()))?,
+        _ => Schema::get_system(schema_id.clone()).ok().cloned(),
+    }
+    .ok_or(ExportHttpError::NotFound)?;
+
+    let (tx, rx) = mpsc::channel(EXPORT_CHANNEL_SIZE);
+    task::spawn(async move {
+        if let Err(err) = write_export(&context.store, &schema, params.format, &tx).await {
+            warn!("Failed exporting documents of {}: {}", schema.id(), err);
+
+            // Abort the response, the client notices that the body is incomplete
+            let _ = tx
+                .send(Err(io::Error::new(io::ErrorKind::Other, err.to_string())))
+                .await;
+        }
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, params.format.content_type())],
+        StreamBody::new(ReceiverStream::new(rx)),
+    )
+        .into_response())
+}
+
+/// Channel sending encoded chunks of an export to the client.
+type ExportSender = mpsc::Sender<Result<Bytes, io::Error>>;
+
+/// Encodes all documents of the schema page by page and sends them to the client.
+async fn write_export(
+    store: &SqlStore,
+    schema: &Schema,
+    format: ExportFormat,
+    tx: &ExportSender,
+) -> Result<(), Error> {
+    let arrow_schema = Arc::new(arrow_schema(schema));
+    let sink = ExportSink::default();
+    let mut writer = ExportWriter::new(format, sink.clone(), arrow_schema.clone())?;
+
+    let mut cursor = None;
+    loop {
+        let query = StoreQuery::new(
+            &Pagination::new(
+                &NonZeroU64::new(EXPORT_PAGE_SIZE).unwrap(),
+                cursor.as_ref(),
+                &vec![PaginationField::EndCursor, PaginationField::HasNextPage],
+            ),
+            &select(schema),
+            &Filter::default(),
+            &Order::default(),
+        );
+
+        let (pagination_data, documents) = store.query(schema, &query, None).await?;
+
+        if !documents.is_empty() {
+            let documents: Vec<StorageDocument> = documents
+                .into_iter()
+                .map(|(_, document)| document)
+                .collect();
+            let batch = record_batch(schema, arrow_schema.clone(), &documents)?;
+            writer.write(&batch)?;
+        }
+
+        // Stop early when the client went away
+        if !sink.send(tx).await {
+            return Ok(());
+        }
+
+        if !pagination_data.has_next_page {
+            break;
+        }
+        cursor = pagination_data.end_cursor;
+    }
+
+    writer.finish()?;
+    sink.send(tx).await;
+
+    Ok(())
+}
+
+/// Buffer the export writers write into, drained after every page of documents.
+#[derive(Clone, Default)]
+struct ExportSink(Arc<Mutex<Vec<u8>>>);
+
+impl ExportSink {
+    /// Sends everything written so far to the client, returns false if the client went away.
+    async fn send(&self, tx: &ExportSender) -> bool {
+        let chunk = std::mem::take(&mut *self.0.lock().expect("Export sink got poisoned"));
+        if chunk.is_empty() {
+            return !tx.is_closed();
+        }
+        tx.send(Ok(Bytes::from(chunk))).await.is_ok()
+    }
+}
+
+impl Write for ExportSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .expect("Export sink got poisoned")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes record batches in the requested format.
+enum ExportWriter {
+    Arrow(StreamWriter<ExportSink>),
+    Parquet(ArrowWriter<ExportSink>),
+}
+
+impl ExportWriter {
+    fn new(
+        format: ExportFormat,
+        sink: ExportSink,
+        schema: Arc<ArrowSchema>,
+    ) -> Result<Self, Error> {
+        Ok(match format {
+            ExportFormat::Arrow => ExportWriter::Arrow(StreamWriter::try_new(sink, &schema)?),
+            ExportFormat::Parquet => {
+                ExportWriter::Parquet(ArrowWriter::try_new(sink, schema, None)?)
+            }
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<(), Error> {
+        match self {
+            ExportWriter::Arrow(writer) => writer.write(batch)?,
+            ExportWriter::Parquet(writer) => {
+                // Write every batch as its own row group, otherwise the writer keeps them in
+                // memory until the row group is full
+                writer.write(batch)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self {
+            ExportWriter::Arrow(mut writer) => writer.finish()?,
+            ExportWriter::Parquet(writer) => {
+                writer.close()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Selects the meta data and all application fields of the schema.
+fn select(schema: &Schema) -> Select {
+    let mut fields = vec![
+        Field::Meta(MetaField::DocumentId),
+        Field::Meta(MetaField::DocumentViewId),
+        Field::Meta(MetaField::Owner),
+    ];
+    fields.extend(schema.fields().keys().iter().map(|name| Field::new(name)));
+    Select::new(&fields)
+}
+
+/// Returns the Arrow data type of a p2panda field type.
+///
+/// Relations are represented by the document (view) ids they point at.
+fn data_type(field_type: &FieldType) -> DataType {
+    match field_type {
+        FieldType::Boolean => DataType::Boolean,
+        FieldType::Integer => DataType::Int64,
+        FieldType::Float => DataType::Float64,
+        FieldType::String | FieldType::Relation(_) | FieldType::PinnedRelation(_) => DataType::Utf8,
+        FieldType::Bytes => DataType::Binary,
+        FieldType::RelationList(_) | FieldType::PinnedRelationList(_) => {
+            DataType::List(Arc::new(ArrowField::new("item", DataType::Utf8, true)))
+        }
+    }
+}
+
+/// Returns the Arrow schema with columns for the meta data and all fields of the p2panda schema.
+fn arrow_schema(schema: &Schema) -> ArrowSchema {
+    let mut columns = vec![
+        ArrowField::new("documentId", DataType::Utf8, false),
+        ArrowField::new("viewId", DataType::Utf8, false),
+        ArrowField::new("owner", DataType::Utf8, false),
+    ];
+
+    columns.extend(
+        schema
+            .fields()
+            .iter()
+            .map(|(name, field_type)| ArrowField::new(name, data_type(field_type), true)),
+    );
+
+    ArrowSchema::new(columns)
+}
+
+/// Converts documents into a record batch with one row per document.
+fn record_batch(
+    schema: &Schema,
+    arrow_schema: Arc<ArrowSchema>,
+    documents: &[StorageDocument],
+) -> Result<RecordBatch, Error> {
+    let mut document_ids = StringBuilder::new();
+    let mut view_ids = StringBuilder::new();
+    let mut owners = StringBuilder::new();
+
+    for document in documents {
+        document_ids.append_value(document.id().as_str());
+        view_ids.append_value(document.view_id().to_string());
+        owners.append_value(document.author().to_string());
+    }
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(document_ids.finish()),
+        Arc::new(view_ids.finish()),
+        Arc::new(owners.finish()),
+    ];
+
+    for (name, field_type) in schema.fields().iter() {
+        let values = documents.iter().map(|document| document.get(name));
+        columns.push(column(name, field_type, values)?);
+    }
+
+    Ok(RecordBatch::try_new(arrow_schema, columns)?)
+}
+
+/// Builds the column of a field from the values of all documents.
+fn column<'a>(
+    name: &str,
+    field_type: &FieldType,
+    values: impl Iterator<Item = Option<&'a OperationValue>>,
+) -> Result<ArrayRef, Error> {
+    let invalid = || anyhow!("Unexpected value in field '{}'", name);
+
+    let column: ArrayRef = match field_type {
+        FieldType::Boolean => {
+            let mut builder = BooleanBuilder::new();
+            for value in values {
+                match value {
+                    Some(OperationValue::Boolean(value)) => builder.append_value(*value),
+                    None => builder.append_null(),
+                    _ => return Err(invalid()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        FieldType::Integer => {
+            let mut builder = Int64Builder::new();
+            for value in values {
+                match value {
+                    Some(OperationValue::Integer(value)) => builder.append_value(*value),
+                    None => builder.append_null(),
+                    _ => return Err(invalid()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        FieldType::Float => {
+            let mut builder = Float64Builder::new();
+            for value in values {
+                match value {
+                    Some(OperationValue::Float(value)) => builder.append_value(*value),
+                    None => builder.append_null(),
+                    _ => return Err(invalid()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        FieldType::Bytes => {
+            let mut builder = BinaryBuilder::new();
+            for value in values {
+                match value {
+                    Some(OperationValue::Bytes(value)) => builder.append_value(value),
+                    None => builder.append_null(),
+                    _ => return Err(invalid()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        FieldType::String | FieldType::Relation(_) | FieldType::PinnedRelation(_) => {
+            let mut builder = StringBuilder::new();
+            for value in values {
+                match value {
+                    Some(OperationValue::String(value)) => builder.append_value(value),
+                    Some(OperationValue::Relation(relation)) => {
+                        builder.append_value(relation.document_id().as_str())
+                    }
+                    Some(OperationValue::PinnedRelation(relation)) => {
+                        builder.append_value(relation.view_id().to_string())
+                    }
+                    None => builder.append_null(),
+                    _ => return Err(invalid()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        FieldType::RelationList(_) | FieldType::PinnedRelationList(_) => {
+            let mut builder = ListBuilder::new(StringBuilder::new());
+            for value in values {
+                match value {
+                    Some(OperationValue::RelationList(list)) => {
+                        for document_id in list.iter() {
+                            builder.values().append_value(document_id.as_str());
+                        }
+                        builder.append(true);
+                    }
+                    Some(OperationValue::PinnedRelationList(list)) => {
+                        for view_id in list.iter() {
+                            builder.values().append_value(view_id.to_string());
+                        }
+                        builder.append(true);
+                    }
+                    None => builder.append(false),
+                    _ => return Err(invalid()),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+    };
+
+    Ok(column)
+}
+
+#[derive(Debug)]
+pub enum ExportHttpError {
+    Unauthorized,
+    NotFound,
+    InternalError(Error),
+}
+
+impl IntoResponse for ExportHttpError {
+    fn into_response(self) -> Response {
+        match self {
+            ExportHttpError::Unauthorized => {
+                (StatusCode::UNAUTHORIZED, "Admin token required").into_response()
+            }
+            ExportHttpError::NotFound => {
+                (StatusCode::NOT_FOUND, "Could not find schema").into_response()
+            }
+            ExportHttpError::InternalError(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", err),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Array, Int64Array, StringArray};
+    use arrow_ipc::reader::StreamReader;
+    use http::StatusCode;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rstest::rstest;
+
+    use crate::context::Context;
+    use crate::test_utils::{add_document, add_schema, http_test_client, test_runner, TestNode};
+
+    /// Configures an admin token on the test node.
+    fn with_admin_token(node: &mut TestNode) {
+        let mut config = node.context.config.clone();
+        config.admin_token = Some("secret".into());
+        node.context = Context::new(
+            node.context.store.clone(),
+            KeyPair::new(),
+            config,
+            node.context.schema_provider.clone(),
+        );
+    }
+
+    #[rstest]
+    fn export_documents(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            with_admin_token(&mut node);
+
+            let schema = add_schema(
+                &mut node,
+                "animals",
+                vec![("name", FieldType::String), ("legs", FieldType::Integer)],
+                &key_pair,
+            )
+            .await;
+
+            for (name, legs) in [("panda", 4), ("penguin", 2)] {
+                add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("name", name.into()), ("legs", legs.into())],
+                    &key_pair,
+                )
+                .await;
+            }
+
+            let client = http_test_client(&node).await;
+
+            // Requests without the admin token are rejected
+            let response = client.get(&format!("/export/{}", schema.id())).send().await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            // Export in Arrow IPC format
+            let response = client
+                .get(&format!("/export/{}", schema.id()))
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = response.bytes().await;
+            let reader = StreamReader::try_new(&body[..], None).unwrap();
+            let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+            assert_eq!(batches.len(), 1);
+            assert_eq!(batches[0].num_rows(), 2);
+            assert_eq!(batches[0].num_columns(), 5);
+
+            let legs = batches[0]
+                .column_by_name("legs")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap();
+            let mut legs: Vec<i64> = legs.values().to_vec();
+            legs.sort();
+            assert_eq!(legs, vec![2, 4]);
+
+            // Export in Parquet format
+            let response = client
+                .get(&format!("/export/{}?format=parquet", schema.id()))
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = bytes::Bytes::from(response.bytes().await);
+            let reader = ParquetRecordBatchReaderBuilder::try_new(body)
+                .unwrap()
+                .build()
+                .unwrap();
+            let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+            assert_eq!(batches[0].num_rows(), 2);
+
+            let owners = batches[0]
+                .column_by_name("owner")
+                .unwrap()
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            assert_eq!(owners.value(0), key_pair.public_key().to_string());
+        });
+    }
+
+    #[rstest]
+    fn export_unknown_schema() {
+        test_runner(|mut node: TestNode| async move {
+            with_admin_token(&mut node);
+
+            let client = http_test_client(&node).await;
+            let response = client
+                .get("/export/animals_0020c65567ae37efea293e34a9c7d13f8f2bf23dbdc3b5c7b9ab46293111c48fc78b")
+                .header("Authorization", "Bearer secret")
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        });
+    }
+}
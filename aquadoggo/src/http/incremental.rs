@@ -0,0 +1,785 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Incremental delivery of GraphQL responses via `@defer` and `@stream` directives.
+//!
+//! The GraphQL engine does not support these directives, queries using them are therefore split
+//! up into multiple queries before they get executed:
+//!
+//! - The initial query contains all fields without the deferred fragments
+//! - Every deferred fragment becomes its own query, only selecting the fields leading to it
+//!
+//! Items of streamed lists are resolved together with the initial query, the ones exceeding the
+//! requested `initialCount` are delivered in subsequent parts of the response.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt::{Display, Write};
+
+use async_graphql::parser::types::{
+    Directive, DocumentOperations, ExecutableDocument, FragmentDefinition, InlineFragment,
+    OperationDefinition, Selection, SelectionSet,
+};
+use async_graphql::parser::{parse_query, Positioned};
+use async_graphql::{Name, ServerError, Value};
+use axum::body::Bytes;
+use serde::Serialize;
+
+/// Name of the directive deferring the execution of fragments.
+const DEFER_DIRECTIVE: &str = "defer";
+
+/// Name of the directive streaming the items of list fields.
+const STREAM_DIRECTIVE: &str = "stream";
+
+/// Maximum depth of nested fragment spreads, protects against cycles.
+const MAX_FRAGMENT_DEPTH: usize = 32;
+
+/// Content type of multipart responses, the boundary is a single dash.
+pub const MULTIPART_CONTENT_TYPE: &str = "multipart/mixed; boundary=\"-\"; deferSpec=20220824";
+
+/// Delimiter closing multipart responses.
+pub const MULTIPART_END: &str = "\r\n-----\r\n";
+
+/// Fragment which gets executed after the initial query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeferredFragment {
+    /// Query selecting only the fields of this fragment and the ones leading to it.
+    pub query: String,
+
+    /// Response keys of the fields leading to the fragment.
+    pub path: Vec<String>,
+
+    /// Optional label given by the client.
+    pub label: Option<String>,
+}
+
+/// List field of which only the first items are delivered in the initial response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedField {
+    /// Response keys of the fields leading to the list, including its own.
+    pub path: Vec<String>,
+
+    /// Number of items delivered in the initial response.
+    pub initial_count: usize,
+
+    /// Optional label given by the client.
+    pub label: Option<String>,
+}
+
+/// GraphQL query using `@defer` or `@stream` directives, split up for incremental delivery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncrementalQuery {
+    /// Query returning the initial response, without any deferred fragments.
+    pub initial: String,
+
+    /// Query selecting all fields at once, for clients not supporting incremental delivery.
+    pub complete: String,
+
+    /// Fragments executed after the initial query, in the order they appear in the query.
+    pub deferred: Vec<DeferredFragment>,
+
+    /// Lists of the initial response which get streamed.
+    pub streams: Vec<StreamedField>,
+}
+
+impl IncrementalQuery {
+    /// Splits up the given query when it uses `@defer` or `@stream` directives.
+    ///
+    /// Returns `None` if no directive for incremental delivery was used or the query could not
+    /// be parsed. These queries can be executed as usual and invalid ones will be rejected by the
+    /// GraphQL engine.
+    pub fn parse(query: &str, operation_name: Option<&str>) -> Option<Self> {
+        if !query.contains("@defer") && !query.contains("@stream") {
+            return None;
+        }
+
+        let document = parse_query(query).ok()?;
+        let (name, operation) = select_operation(&document, operation_name)?;
+
+        // Replace all named fragment spreads with inline fragments, this way we don't need to
+        // keep track of the fragment definitions when pruning the selection sets
+        let mut selection_set = operation.selection_set.node.clone();
+        inline_fragments(&mut selection_set, &document.fragments, 0)?;
+
+        let mut deferred = Vec::new();
+        let mut streams = Vec::new();
+        collect(&selection_set, &[], false, &mut deferred, &mut streams);
+
+        if deferred.is_empty() && streams.is_empty() {
+            return None;
+        }
+
+        let deferred = deferred
+            .into_iter()
+            .enumerate()
+            .map(|(id, (path, label))| {
+                let pruned = select_deferred(&selection_set, id, &mut 0)
+                    .expect("Deferred fragment exists in selection set");
+                DeferredFragment {
+                    query: print_operation(name, operation, &pruned),
+                    path,
+                    label,
+                }
+            })
+            .collect();
+
+        Some(Self {
+            initial: print_operation(name, operation, &strip(&selection_set, false)),
+            complete: print_operation(name, operation, &strip(&selection_set, true)),
+            deferred,
+            streams,
+        })
+    }
+}
+
+/// First part of an incrementally delivered response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitialPayload {
+    /// Data of the initial query.
+    pub data: Value,
+
+    /// Errors which occurred during execution of the initial query.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ServerError>,
+
+    /// More parts will follow.
+    pub has_next: bool,
+}
+
+/// Subsequent part of an incrementally delivered response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsequentPayload {
+    /// Results of deferred fragments or streamed items.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub incremental: Vec<IncrementalResult>,
+
+    /// More parts will follow.
+    pub has_next: bool,
+}
+
+/// Result of a deferred fragment or streamed list items.
+#[derive(Debug, Default, Serialize)]
+pub struct IncrementalResult {
+    /// Data of a deferred fragment, merged into the object under the given path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+
+    /// Streamed items, appended to the list under the given path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<Value>>,
+
+    /// Path of the object or, for streamed items, of the first item in the list.
+    pub path: Vec<Value>,
+
+    /// Optional label given by the client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// Errors which occurred during execution.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ServerError>,
+}
+
+/// Returns `true` if the client accepts incrementally delivered multipart responses.
+pub fn accepts_multipart(accept: Option<&str>) -> bool {
+    accept.is_some_and(|accept| accept.contains("multipart/mixed"))
+}
+
+/// Encodes the payload as a JSON part of a multipart response.
+pub fn multipart_part(payload: &impl Serialize) -> Bytes {
+    let mut part = b"\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n".to_vec();
+    serde_json::to_writer(&mut part, payload).expect("Payload can be serialized to JSON");
+    Bytes::from(part)
+}
+
+/// Returns the operation which will be executed.
+fn select_operation<'a>(
+    document: &'a ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<(Option<&'a Name>, &'a OperationDefinition)> {
+    match &document.operations {
+        DocumentOperations::Single(operation) => Some((None, &operation.node)),
+        DocumentOperations::Multiple(operations) => match operation_name {
+            Some(operation_name) => operations
+                .get_key_value(operation_name)
+                .map(|(name, operation)| (Some(name), &operation.node)),
+            None if operations.len() == 1 => operations
+                .iter()
+                .next()
+                .map(|(name, operation)| (Some(name), &operation.node)),
+            None => None,
+        },
+    }
+}
+
+/// Replaces all fragment spreads with the selections of their definitions.
+fn inline_fragments(
+    selection_set: &mut SelectionSet,
+    fragments: &HashMap<Name, Positioned<FragmentDefinition>>,
+    depth: usize,
+) -> Option<()> {
+    if depth > MAX_FRAGMENT_DEPTH {
+        return None;
+    }
+
+    for selection in selection_set.items.iter_mut() {
+        let pos = selection.pos;
+        match &mut selection.node {
+            Selection::Field(field) => {
+                inline_fragments(&mut field.node.selection_set.node, fragments, depth)?
+            }
+            Selection::InlineFragment(fragment) => {
+                inline_fragments(&mut fragment.node.selection_set.node, fragments, depth)?
+            }
+            Selection::FragmentSpread(spread) => {
+                let definition = &fragments.get(&spread.node.fragment_name.node)?.node;
+                let mut inline_selection_set = definition.selection_set.node.clone();
+                inline_fragments(&mut inline_selection_set, fragments, depth + 1)?;
+
+                let fragment = InlineFragment {
+                    type_condition: Some(definition.type_condition.clone()),
+                    directives: spread.node.directives.clone(),
+                    selection_set: Positioned::new(inline_selection_set, pos),
+                };
+                selection.node = Selection::InlineFragment(Positioned::new(fragment, pos));
+            }
+        }
+    }
+
+    Some(())
+}
+
+/// Returns the given directive when it is active.
+///
+/// Directives are only inactive when their `if` argument is explicitly set to `false`.
+fn find_directive<'a>(
+    directives: &'a [Positioned<Directive>],
+    name: &str,
+) -> Option<&'a Directive> {
+    directives
+        .iter()
+        .map(|directive| &directive.node)
+        .find(|directive| directive.name.node.as_str() == name)
+        .filter(|directive| argument(directive, "if") != Some(Value::Boolean(false)))
+}
+
+/// Returns the value of a directive argument, if it was given without using a variable.
+fn argument(directive: &Directive, name: &str) -> Option<Value> {
+    directive
+        .get_argument(name)
+        .and_then(|value| value.node.clone().into_const())
+}
+
+fn label(directive: &Directive) -> Option<String> {
+    match argument(directive, "label") {
+        Some(Value::String(label)) => Some(label),
+        _ => None,
+    }
+}
+
+fn without_incremental_directives(
+    directives: &[Positioned<Directive>],
+) -> Vec<Positioned<Directive>> {
+    directives
+        .iter()
+        .filter(|directive| {
+            let name = directive.node.name.node.as_str();
+            name != DEFER_DIRECTIVE && name != STREAM_DIRECTIVE
+        })
+        .cloned()
+        .collect()
+}
+
+/// Collects all deferred fragments and streamed fields in the order they appear in the query.
+///
+/// Streams inside of deferred fragments are delivered together with the fragment.
+fn collect(
+    selection_set: &SelectionSet,
+    path: &[String],
+    is_deferred: bool,
+    deferred: &mut Vec<(Vec<String>, Option<String>)>,
+    streams: &mut Vec<StreamedField>,
+) {
+    for selection in &selection_set.items {
+        match &selection.node {
+            Selection::Field(field) => {
+                let mut field_path = path.to_vec();
+                field_path.push(field.node.response_key().node.to_string());
+
+                if let Some(directive) = find_directive(&field.node.directives, STREAM_DIRECTIVE) {
+                    if !is_deferred {
+                        let initial_count = match argument(directive, "initialCount") {
+                            Some(Value::Number(number)) => number.as_u64().unwrap_or(0) as usize,
+                            _ => 0,
+                        };
+
+                        streams.push(StreamedField {
+                            path: field_path.clone(),
+                            initial_count,
+                            label: label(directive),
+                        });
+                    }
+                }
+
+                collect(
+                    &field.node.selection_set.node,
+                    &field_path,
+                    is_deferred,
+                    deferred,
+                    streams,
+                );
+            }
+            Selection::InlineFragment(fragment) => {
+                let directive = find_directive(&fragment.node.directives, DEFER_DIRECTIVE);
+                if let Some(directive) = directive {
+                    deferred.push((path.to_vec(), label(directive)));
+                }
+
+                collect(
+                    &fragment.node.selection_set.node,
+                    path,
+                    is_deferred || directive.is_some(),
+                    deferred,
+                    streams,
+                );
+            }
+            Selection::FragmentSpread(_) => (),
+        }
+    }
+}
+
+/// Removes all `@defer` and `@stream` directives from the selection set.
+///
+/// Deferred fragments are removed as well, unless they should be kept.
+fn strip(selection_set: &SelectionSet, keep_deferred: bool) -> SelectionSet {
+    let items = selection_set
+        .items
+        .iter()
+        .filter_map(|selection| {
+            let node = match &selection.node {
+                Selection::Field(field) => {
+                    let mut field = field.clone();
+                    field.node.directives = without_incremental_directives(&field.node.directives);
+                    field.node.selection_set.node =
+                        strip(&field.node.selection_set.node, keep_deferred);
+                    Selection::Field(field)
+                }
+                Selection::InlineFragment(fragment) => {
+                    if !keep_deferred
+                        && find_directive(&fragment.node.directives, DEFER_DIRECTIVE).is_some()
+                    {
+                        return None;
+                    }
+
+                    let mut fragment = fragment.clone();
+                    fragment.node.directives =
+                        without_incremental_directives(&fragment.node.directives);
+                    fragment.node.selection_set.node =
+                        strip(&fragment.node.selection_set.node, keep_deferred);
+                    Selection::InlineFragment(fragment)
+                }
+                Selection::FragmentSpread(spread) => Selection::FragmentSpread(spread.clone()),
+            };
+
+            Some(Positioned::new(node, selection.pos))
+        })
+        .collect();
+
+    SelectionSet { items }
+}
+
+/// Returns a selection set which only contains the deferred fragment with the given id and the
+/// fields leading to it.
+///
+/// Fragments are counted in the same order as they get collected.
+fn select_deferred(
+    selection_set: &SelectionSet,
+    target: usize,
+    counter: &mut usize,
+) -> Option<SelectionSet> {
+    for selection in &selection_set.items {
+        let node = match &selection.node {
+            Selection::Field(field) => {
+                match select_deferred(&field.node.selection_set.node, target, counter) {
+                    Some(pruned) => {
+                        let mut field = field.clone();
+                        field.node.directives =
+                            without_incremental_directives(&field.node.directives);
+                        field.node.selection_set.node = pruned;
+                        Some(Selection::Field(field))
+                    }
+                    None => None,
+                }
+            }
+            Selection::InlineFragment(fragment) => {
+                let pruned = if find_directive(&fragment.node.directives, DEFER_DIRECTIVE).is_some()
+                {
+                    let id = *counter;
+                    *counter += 1;
+
+                    if id == target {
+                        Some(strip(&fragment.node.selection_set.node, false))
+                    } else {
+                        select_deferred(&fragment.node.selection_set.node, target, counter)
+                    }
+                } else {
+                    select_deferred(&fragment.node.selection_set.node, target, counter)
+                };
+
+                pruned.map(|pruned| {
+                    let mut fragment = fragment.clone();
+                    fragment.node.directives =
+                        without_incremental_directives(&fragment.node.directives);
+                    fragment.node.selection_set.node = pruned;
+                    Selection::InlineFragment(fragment)
+                })
+            }
+            Selection::FragmentSpread(_) => None,
+        };
+
+        if let Some(node) = node {
+            return Some(SelectionSet {
+                items: vec![Positioned::new(node, selection.pos)],
+            });
+        }
+    }
+
+    None
+}
+
+/// Collects the names of all variables used in the selection set.
+fn used_variables(selection_set: &SelectionSet, variables: &mut HashSet<Name>) {
+    fn directive_variables(directives: &[Positioned<Directive>], variables: &mut HashSet<Name>) {
+        for directive in directives {
+            for (_, value) in &directive.node.arguments {
+                let _ = value.node.clone().into_const_with(|name| {
+                    variables.insert(name);
+                    Ok::<Value, Infallible>(Value::Null)
+                });
+            }
+        }
+    }
+
+    for selection in &selection_set.items {
+        match &selection.node {
+            Selection::Field(field) => {
+                for (_, value) in &field.node.arguments {
+                    let _ = value.node.clone().into_const_with(|name| {
+                        variables.insert(name);
+                        Ok::<Value, Infallible>(Value::Null)
+                    });
+                }
+                directive_variables(&field.node.directives, variables);
+                used_variables(&field.node.selection_set.node, variables);
+            }
+            Selection::InlineFragment(fragment) => {
+                directive_variables(&fragment.node.directives, variables);
+                used_variables(&fragment.node.selection_set.node, variables);
+            }
+            Selection::FragmentSpread(spread) => {
+                directive_variables(&spread.node.directives, variables);
+            }
+        }
+    }
+}
+
+/// Prints the operation with the given selection set as a GraphQL query string.
+///
+/// Only the variables used in the selection set are defined, otherwise the query would be
+/// rejected.
+fn print_operation(
+    name: Option<&Name>,
+    operation: &OperationDefinition,
+    selection_set: &SelectionSet,
+) -> String {
+    let mut variables = HashSet::new();
+    used_variables(selection_set, &mut variables);
+
+    let mut query = operation.ty.to_string();
+    if let Some(name) = name {
+        write!(query, " {}", name).unwrap();
+    }
+
+    let variable_definitions: Vec<String> = operation
+        .variable_definitions
+        .iter()
+        .filter(|definition| variables.contains(&definition.node.name.node))
+        .map(|definition| {
+            let mut printed = format!(
+                "${}: {}",
+                definition.node.name.node, definition.node.var_type.node
+            );
+            if let Some(default_value) = &definition.node.default_value {
+                write!(printed, " = {}", default_value.node).unwrap();
+            }
+            printed
+        })
+        .collect();
+    if !variable_definitions.is_empty() {
+        write!(query, "({})", variable_definitions.join(", ")).unwrap();
+    }
+
+    print_directives(&operation.directives, &mut query);
+    print_selection_set(selection_set, &mut query);
+    query
+}
+
+fn print_directives(directives: &[Positioned<Directive>], output: &mut String) {
+    for directive in directives {
+        write!(output, " @{}", directive.node.name.node).unwrap();
+        print_arguments(&directive.node.arguments, output);
+    }
+}
+
+fn print_arguments<V: Display>(
+    arguments: &[(Positioned<Name>, Positioned<V>)],
+    output: &mut String,
+) {
+    if arguments.is_empty() {
+        return;
+    }
+
+    let arguments: Vec<String> = arguments
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name.node, value.node))
+        .collect();
+    write!(output, "({})", arguments.join(", ")).unwrap();
+}
+
+fn print_selection_set(selection_set: &SelectionSet, output: &mut String) {
+    if selection_set.items.is_empty() {
+        return;
+    }
+
+    output.push_str(" {");
+    for selection in &selection_set.items {
+        output.push(' ');
+        match &selection.node {
+            Selection::Field(field) => {
+                if let Some(alias) = &field.node.alias {
+                    write!(output, "{}: ", alias.node).unwrap();
+                }
+                output.push_str(field.node.name.node.as_str());
+                print_arguments(&field.node.arguments, output);
+                print_directives(&field.node.directives, output);
+                print_selection_set(&field.node.selection_set.node, output);
+            }
+            Selection::InlineFragment(fragment) => {
+                output.push_str("...");
+                if let Some(type_condition) = &fragment.node.type_condition {
+                    write!(output, " on {}", type_condition.node.on.node).unwrap();
+                }
+                print_directives(&fragment.node.directives, output);
+                print_selection_set(&fragment.node.selection_set.node, output);
+            }
+            Selection::FragmentSpread(spread) => {
+                write!(output, "...{}", spread.node.fragment_name.node).unwrap();
+                print_directives(&spread.node.directives, output);
+            }
+        }
+    }
+    output.push_str(" }");
+}
+
+/// Returns all objects found under the given path of the response data, next to their concrete
+/// path including the indexes of lists.
+pub fn values_at<'a>(
+    data: &'a Value,
+    path: &[String],
+    concrete_path: Vec<Value>,
+) -> Vec<(Vec<Value>, &'a Value)> {
+    match data {
+        Value::List(items) => items
+            .iter()
+            .enumerate()
+            .flat_map(|(index, item)| {
+                let mut item_path = concrete_path.clone();
+                item_path.push(Value::from(index));
+                values_at(item, path, item_path)
+            })
+            .collect(),
+        Value::Object(map) => match path.split_first() {
+            Some((key, rest)) => match map.get(key.as_str()) {
+                Some(value) => {
+                    let mut value_path = concrete_path;
+                    value_path.push(Value::from(key.as_str()));
+                    values_at(value, rest, value_path)
+                }
+                None => Vec::new(),
+            },
+            None => vec![(concrete_path, data)],
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Removes the items exceeding the initial count from all lists under the path of the streamed
+/// field.
+///
+/// Returns the removed items next to the concrete path of the first one.
+pub fn split_stream(
+    data: &mut Value,
+    path: &[String],
+    initial_count: usize,
+    concrete_path: Vec<Value>,
+) -> Vec<(Vec<Value>, Vec<Value>)> {
+    match (data, path.split_first()) {
+        (Value::List(items), None) => {
+            if items.len() <= initial_count {
+                return Vec::new();
+            }
+
+            let mut items_path = concrete_path;
+            items_path.push(Value::from(initial_count));
+            vec![(items_path, items.split_off(initial_count))]
+        }
+        (Value::List(items), Some(_)) => items
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(index, item)| {
+                let mut item_path = concrete_path.clone();
+                item_path.push(Value::from(index));
+                split_stream(item, path, initial_count, item_path)
+            })
+            .collect(),
+        (Value::Object(map), Some((key, rest))) => match map.get_mut(key.as_str()) {
+            Some(value) => {
+                let mut value_path = concrete_path;
+                value_path.push(Value::from(key.as_str()));
+                split_stream(value, rest, initial_count, value_path)
+            }
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{value, Value};
+
+    use super::{split_stream, values_at, DeferredFragment, IncrementalQuery, StreamedField};
+
+    #[test]
+    fn split_queries() {
+        let query = "
+            query Animals($first: Int, $name: String) {
+                animals: all_animals(first: $first) {
+                    edges @stream(initialCount: 1, label: \"edges\") {
+                        node {
+                            fields { name }
+                            ... on animals @defer(label: \"friends\") {
+                                fields { friend(name: $name) { meta { owner } } }
+                            }
+                        }
+                    }
+                }
+                ...Meta @defer
+            }
+
+            fragment Meta on Query {
+                other: all_animals { totalCount }
+            }
+        ";
+
+        let incremental = IncrementalQuery::parse(query, Some("Animals")).unwrap();
+        assert_eq!(
+            incremental.initial,
+            "query Animals($first: Int) { animals: all_animals(first: $first) { edges { \
+             node { fields { name } } } } }"
+        );
+        assert_eq!(
+            incremental.complete,
+            "query Animals($first: Int, $name: String) { animals: all_animals(first: $first) { \
+             edges { node { fields { name } ... on animals { fields { friend(name: $name) { \
+             meta { owner } } } } } } } ... on Query { other: all_animals { totalCount } } }"
+        );
+        assert_eq!(
+            incremental.deferred,
+            vec![
+                DeferredFragment {
+                    query: "query Animals($first: Int, $name: String) { animals: \
+                            all_animals(first: $first) { edges { node { ... on animals { \
+                            fields { friend(name: $name) { meta { owner } } } } } } } }"
+                        .into(),
+                    path: vec!["animals".into(), "edges".into(), "node".into()],
+                    label: Some("friends".into()),
+                },
+                DeferredFragment {
+                    query: "query Animals { ... on Query { other: all_animals { totalCount } } }"
+                        .into(),
+                    path: vec![],
+                    label: None,
+                }
+            ]
+        );
+        assert_eq!(
+            incremental.streams,
+            vec![StreamedField {
+                path: vec!["animals".into(), "edges".into()],
+                initial_count: 1,
+                label: Some("edges".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn ignore_regular_queries() {
+        assert!(IncrementalQuery::parse("{ all_animals { totalCount } }", None).is_none());
+
+        // Directives can be disabled
+        assert!(IncrementalQuery::parse(
+            "{ ... @defer(if: false) { all_animals { totalCount } } }",
+            None
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn split_response_data() {
+        let mut data = value!({
+            "animals": {
+                "edges": [
+                    { "node": { "name": "panda" } },
+                    { "node": { "name": "penguin" } },
+                    { "node": { "name": "llama" } },
+                ]
+            }
+        });
+
+        let path = vec![
+            "animals".to_string(),
+            "edges".to_string(),
+            "node".to_string(),
+        ];
+        let nodes = values_at(&data, &path, vec![]);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(
+            nodes[1].0,
+            vec![
+                Value::from("animals"),
+                Value::from("edges"),
+                Value::from(1),
+                Value::from("node")
+            ]
+        );
+        assert_eq!(nodes[1].1, &value!({ "name": "penguin" }));
+
+        let streamed = split_stream(&mut data, &path[..2], 1, vec![]);
+        assert_eq!(
+            streamed,
+            vec![(
+                vec![Value::from("animals"), Value::from("edges"), Value::from(1)],
+                vec![
+                    value!({ "node": { "name": "penguin" } }),
+                    value!({ "node": { "name": "llama" } }),
+                ]
+            )]
+        );
+        assert_eq!(
+            data,
+            value!({ "animals": { "edges": [{ "node": { "name": "panda" } }] } })
+        );
+    }
+}
@@ -1,8 +1,14 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod allowed_operations;
 mod api;
 mod context;
+#[cfg(feature = "export")]
+mod export;
+mod incremental;
+mod persisted_queries;
 mod service;
+mod websocket;
 
 #[cfg(test)]
 pub use context::HttpServiceContext;
@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Automatic persisted queries following the Apollo protocol.
+//!
+//! Clients send the SHA256 hash of a query in the `persistedQuery` request extension instead of
+//! the full query string. When the hash is unknown the client retries with hash and query, which
+//! registers the query for all following requests.
+//!
+//! See: https://www.apollographql.com/docs/apollo-server/performance/apq/
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use async_graphql::{from_value, Request, ServerError};
+use lru::LruCache;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Maximum number of registered queries kept in memory.
+const PERSISTED_QUERIES_CAPACITY: usize = 1024;
+
+/// Name of the request extension containing the persisted query.
+const PERSISTED_QUERY_EXTENSION: &str = "persistedQuery";
+
+/// Only supported version of the persisted query protocol.
+const PERSISTED_QUERY_VERSION: i32 = 1;
+
+/// Content of the `persistedQuery` request extension.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedQuery {
+    version: i32,
+    sha256_hash: String,
+}
+
+/// In-memory cache of registered queries keyed by their SHA256 hash.
+///
+/// The least recently used queries get dropped when the cache is full, clients register them
+/// again on their next request.
+#[derive(Clone, Debug)]
+pub struct PersistedQueries(Arc<Mutex<LruCache<String, String>>>);
+
+impl PersistedQueries {
+    /// Returns a new cache holding at most the given number of queries.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).expect("Capacity must not be zero");
+        Self(Arc::new(Mutex::new(LruCache::new(capacity))))
+    }
+
+    /// Fills in the query string of requests containing only the hash of a registered query and
+    /// registers the queries of requests containing both.
+    ///
+    /// Requests without a `persistedQuery` extension are left untouched.
+    pub fn resolve(&self, request: &mut Request) -> Result<(), ServerError> {
+        let value = match request.extensions.remove(PERSISTED_QUERY_EXTENSION) {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+
+        let persisted_query: PersistedQuery = from_value(value)
+            .map_err(|_| ServerError::new("Invalid \"persistedQuery\" extension", None))?;
+
+        if persisted_query.version != PERSISTED_QUERY_VERSION {
+            return Err(protocol_error(
+                "PersistedQueryNotSupported",
+                "PERSISTED_QUERY_NOT_SUPPORTED",
+            ));
+        }
+
+        let mut cache = self.0.lock().expect("Persisted queries lock is poisoned");
+
+        if request.query.is_empty() {
+            match cache.get(&persisted_query.sha256_hash) {
+                Some(query) => {
+                    request.query = query.to_owned();
+                    Ok(())
+                }
+                None => Err(protocol_error(
+                    "PersistedQueryNotFound",
+                    "PERSISTED_QUERY_NOT_FOUND",
+                )),
+            }
+        } else {
+            let hash = hex::encode(Sha256::digest(request.query.as_bytes()));
+            if hash != persisted_query.sha256_hash {
+                return Err(ServerError::new("Provided sha does not match query", None));
+            }
+
+            cache.put(hash, request.query.clone());
+            Ok(())
+        }
+    }
+}
+
+impl Default for PersistedQueries {
+    fn default() -> Self {
+        Self::new(PERSISTED_QUERIES_CAPACITY)
+    }
+}
+
+/// Returns an error with the message and code Apollo clients react to.
+fn protocol_error(message: &str, code: &str) -> ServerError {
+    let mut error = ServerError::new(message, None);
+    error
+        .extensions
+        .get_or_insert_with(Default::default)
+        .set("code", code);
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{Request, Value};
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    use super::PersistedQueries;
+
+    const QUERY: &str = "{ nodeStatus { version } }";
+
+    fn request(query: &str, hash: &str, version: i32) -> Request {
+        let mut request = Request::new(query);
+        request.extensions.insert(
+            "persistedQuery".into(),
+            Value::from_json(json!({ "version": version, "sha256Hash": hash })).unwrap(),
+        );
+        request
+    }
+
+    fn error_code(error: &async_graphql::ServerError) -> Option<Value> {
+        error
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get("code").cloned())
+    }
+
+    #[test]
+    fn register_and_resolve_queries() {
+        let persisted_queries = PersistedQueries::new(10);
+        let hash = hex::encode(Sha256::digest(QUERY.as_bytes()));
+
+        // Unknown hashes are rejected, the client is expected to register the query
+        let error = persisted_queries
+            .resolve(&mut request("", &hash, 1))
+            .unwrap_err();
+        assert_eq!(error.message, "PersistedQueryNotFound");
+        assert_eq!(
+            error_code(&error),
+            Some(Value::String("PERSISTED_QUERY_NOT_FOUND".into()))
+        );
+
+        // Register the query
+        let mut registration = request(QUERY, &hash, 1);
+        assert!(persisted_queries.resolve(&mut registration).is_ok());
+        assert_eq!(registration.query, QUERY);
+        assert!(registration.extensions.is_empty());
+
+        // Resolve the query from its hash
+        let mut lookup = request("", &hash, 1);
+        assert!(persisted_queries.resolve(&mut lookup).is_ok());
+        assert_eq!(lookup.query, QUERY);
+    }
+
+    #[test]
+    fn reject_invalid_persisted_queries() {
+        let persisted_queries = PersistedQueries::new(10);
+        let hash = hex::encode(Sha256::digest(QUERY.as_bytes()));
+
+        // Hash does not match the query
+        let error = persisted_queries
+            .resolve(&mut request("{ nodeStatus { peers } }", &hash, 1))
+            .unwrap_err();
+        assert_eq!(error.message, "Provided sha does not match query");
+
+        // Unsupported protocol version
+        let error = persisted_queries
+            .resolve(&mut request(QUERY, &hash, 2))
+            .unwrap_err();
+        assert_eq!(error.message, "PersistedQueryNotSupported");
+
+        // Requests without extension are left untouched
+        let mut plain = Request::new(QUERY);
+        assert!(persisted_queries.resolve(&mut plain).is_ok());
+        assert_eq!(plain.query, QUERY);
+        assert!(plain.extensions.is_empty());
+    }
+
+    #[test]
+    fn drop_least_recently_used_queries() {
+        let persisted_queries = PersistedQueries::new(1);
+        let queries = ["{ a }", "{ b }"];
+        let hashes: Vec<String> = queries
+            .iter()
+            .map(|query| hex::encode(Sha256::digest(query.as_bytes())))
+            .collect();
+
+        for (query, hash) in queries.iter().zip(&hashes) {
+            persisted_queries
+                .resolve(&mut request(query, hash, 1))
+                .unwrap();
+        }
+
+        assert!(persisted_queries
+            .resolve(&mut request("", &hashes[0], 1))
+            .is_err());
+        assert!(persisted_queries
+            .resolve(&mut request("", &hashes[1], 1))
+            .is_ok());
+    }
+}
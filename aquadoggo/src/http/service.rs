@@ -1,47 +1,104 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
 
 use anyhow::Result;
+use axum::body::Body;
 use axum::extract::Extension;
-use axum::http::Method;
-use axum::routing::get;
+use axum::http::{Method, Request};
+use axum::middleware::{self, Next};
+use axum::routing::{get, head, post};
 use axum::Router;
-use http::header::CONTENT_TYPE;
+use http::header::{AUTHORIZATION, CONTENT_TYPE, LOCATION};
 use log::{debug, warn};
 use tower_http::cors::{Any, CorsLayer};
 
+use crate::blobs::BlobUploads;
 use crate::bus::ServiceSender;
 use crate::context::Context;
+use crate::db::statement_timeout_connections;
 use crate::graphql::GraphQLSchemaManager;
 use crate::http::api::{
-    handle_blob_document, handle_blob_view, handle_graphql_playground, handle_graphql_query,
+    handle_blob_document, handle_blob_document_head, handle_blob_document_meta,
+    handle_blob_upload_create, handle_blob_upload_delete, handle_blob_upload_head,
+    handle_blob_upload_patch, handle_blob_view, handle_blob_view_head, handle_derived_blob,
+    handle_graphql_playground, handle_graphql_query, handle_graphql_subscription, BLOB_DOCUMENT_ID,
+    UPLOAD_LENGTH, UPLOAD_MIME_TYPE, UPLOAD_OFFSET,
 };
 use crate::http::context::HttpServiceContext;
+#[cfg(feature = "export")]
+use crate::http::export;
 use crate::info_or_print;
 use crate::manager::{ServiceReadySender, Shutdown};
 
 /// Route to the GraphQL playground
 const GRAPHQL_ROUTE: &str = "/graphql";
 
+/// Route to GraphQL subscriptions via WebSocket
+const GRAPHQL_SUBSCRIPTION_ROUTE: &str = "/graphql/ws";
+
+/// Interval in which abandoned blob upload sessions get removed.
+const UPLOAD_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
 /// Build HTTP server with GraphQL API.
 pub fn build_server(http_context: HttpServiceContext) -> Router {
     // Configure CORS middleware
     let cors = CorsLayer::new()
-        .allow_methods(vec![Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers([CONTENT_TYPE])
+        .allow_methods(vec![
+            Method::GET,
+            Method::HEAD,
+            Method::POST,
+            Method::PATCH,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers([
+            AUTHORIZATION,
+            CONTENT_TYPE,
+            UPLOAD_LENGTH,
+            UPLOAD_OFFSET,
+            UPLOAD_MIME_TYPE,
+        ])
+        .expose_headers([LOCATION, UPLOAD_LENGTH, UPLOAD_OFFSET, BLOB_DOCUMENT_ID])
         .allow_credentials(false)
         .allow_origin(Any);
 
-    Router::new()
+    let router = Router::new()
         // Add GraphQL routes
         .route(
             GRAPHQL_ROUTE,
-            get(|| handle_graphql_playground(GRAPHQL_ROUTE)).post(handle_graphql_query),
+            get(|| handle_graphql_playground(GRAPHQL_ROUTE, GRAPHQL_SUBSCRIPTION_ROUTE))
+                .post(handle_graphql_query),
         )
+        .route(GRAPHQL_SUBSCRIPTION_ROUTE, get(handle_graphql_subscription))
         // Add blob routes
-        .route("/blobs/:document_id", get(handle_blob_document))
-        .route("/blobs/:document_id/:view_hash", get(handle_blob_view))
+        .route("/blobs/uploads", post(handle_blob_upload_create))
+        .route(
+            "/blobs/uploads/:session_id",
+            head(handle_blob_upload_head)
+                .patch(handle_blob_upload_patch)
+                .delete(handle_blob_upload_delete),
+        )
+        .route(
+            "/blobs/:document_id",
+            get(handle_blob_document).head(handle_blob_document_head),
+        )
+        .route("/blobs/:document_id/meta", get(handle_blob_document_meta))
+        .route(
+            "/blobs/:document_id/:view_hash",
+            get(handle_blob_view).head(handle_blob_view_head),
+        )
+        .route(
+            "/blobs/:document_id/:view_hash/:name",
+            get(handle_derived_blob),
+        );
+
+    // Add route to export documents in Arrow or Parquet format
+    #[cfg(feature = "export")]
+    let router = router.route("/export/:schema_id", get(export::handle_export));
+
+    router
         // Add middlewares
         .layer(cors)
         // Add shared context
@@ -58,19 +115,61 @@ pub async fn http_service(
     let http_port = context.config.http_port;
     let http_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), http_port);
 
-    // Prepare GraphQL manager executing incoming GraphQL queries via HTTP
-    let graphql_schema_manager =
-        GraphQLSchemaManager::new(context.store.clone(), tx, context.schema_provider.clone()).await;
+    // Statements of GraphQL queries run on their own connection pool which aborts them when they
+    // exceed the query timeout. Migrations, the materializer and replication are not affected
+    let graphql_max_connections = statement_timeout_connections(
+        &context.config.database_url,
+        context.config.database_max_connections,
+        context.config.query_timeout,
+    );
+    let graphql_store = match context.config.query_timeout {
+        Some(timeout) if graphql_max_connections > 0 => {
+            context
+                .store
+                .with_statement_timeout(
+                    &context.config.database_url,
+                    graphql_max_connections,
+                    timeout,
+                )
+                .await?
+        }
+        _ => context.store.clone(),
+    };
 
-    let blobs_base_path = &context.config.blobs_base_path;
+    // Prepare GraphQL manager executing incoming GraphQL queries via HTTP
+    let graphql_schema_manager = GraphQLSchemaManager::new(
+        graphql_store,
+        tx.clone(),
+        context.schema_provider.clone(),
+        context.blob_access.clone(),
+        context.status.clone(),
+        &context.config,
+    )
+    .await;
 
     // Introduce a new context for all HTTP routes
     let http_context = HttpServiceContext::new(
         context.store.clone(),
         graphql_schema_manager,
-        blobs_base_path.to_owned(),
+        context.blobs.clone(),
+        context.blob_access.clone(),
+        context.blob_integrity.clone(),
+        BlobUploads::new(context.clone(), tx),
+        &context.config,
     );
 
+    // Regularly remove blob uploads which were never completed
+    let uploads = http_context.uploads.clone();
+    let cleanup_handle = tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(UPLOAD_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = uploads.purge_abandoned().await {
+                warn!("Failed removing abandoned blob upload sessions: {}", err);
+            }
+        }
+    });
+
     // Start HTTP server with given port and re-attempt with random port if it was taken already
     let builder = if let Ok(builder) = axum::Server::try_bind(&http_address) {
         builder
@@ -81,7 +180,17 @@ pub async fn http_service(
         axum::Server::try_bind(&SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))?
     };
 
-    let builder = builder.serve(build_server(http_context).into_make_service());
+    // Count every request as activity, this keeps the node from releasing resources while
+    // clients are using it
+    let activity = context.activity.clone();
+    let router = build_server(http_context).layer(middleware::from_fn(
+        move |request: Request<Body>, next: Next<Body>| {
+            activity.touch();
+            next.run(request)
+        },
+    ));
+
+    let builder = builder.serve(router.into_make_service());
 
     let local_address = builder.local_addr();
     info_or_print(&format!(
@@ -100,6 +209,8 @@ pub async fn http_service(
         })
         .await?;
 
+    cleanup_handle.abort();
+
     Ok(())
 }
 
@@ -108,6 +219,7 @@ mod tests {
     use serde_json::json;
     use tokio::sync::broadcast;
 
+    use crate::blobs::BlobUploads;
     use crate::graphql::GraphQLSchemaManager;
     use crate::http::context::HttpServiceContext;
     use crate::schema::SchemaProvider;
@@ -121,12 +233,23 @@ mod tests {
         test_runner(|node: TestNode| async move {
             let (tx, _) = broadcast::channel(120);
             let schema_provider = SchemaProvider::default();
-            let graphql_schema_manager =
-                GraphQLSchemaManager::new(node.context.store.clone(), tx, schema_provider).await;
+            let graphql_schema_manager = GraphQLSchemaManager::new(
+                node.context.store.clone(),
+                tx.clone(),
+                schema_provider,
+                node.context.blob_access.clone(),
+                node.context.status.clone(),
+                &node.context.config,
+            )
+            .await;
             let context = HttpServiceContext::new(
                 node.context.store.clone(),
                 graphql_schema_manager,
-                node.context.config.blobs_base_path.clone(),
+                node.context.blobs.clone(),
+                node.context.blob_access.clone(),
+                node.context.blob_integrity.clone(),
+                BlobUploads::new(node.context.clone(), tx),
+                &node.context.config,
             );
             let client = TestClient::new(build_server(context));
 
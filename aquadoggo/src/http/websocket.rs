@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql::dynamic::Schema;
+use async_graphql::parser::parse_query;
+use async_graphql::parser::types::OperationType;
+use async_graphql::{Data, Executor, Request, Response};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+
+use crate::http::api::{execute_with_timeout, mask_internal_errors};
+
+/// Executes GraphQL operations received via WebSocket with the same query timeout and masking of
+/// internal errors as requests via HTTP.
+///
+/// Subscriptions run until the client stops them, the timeout only applies to queries and
+/// mutations.
+#[derive(Clone)]
+pub struct WebSocketExecutor {
+    schema: Schema,
+    query_timeout: Option<Duration>,
+    mask_errors: bool,
+}
+
+impl WebSocketExecutor {
+    pub fn new(schema: Schema, query_timeout: Option<Duration>, mask_errors: bool) -> Self {
+        Self {
+            schema,
+            query_timeout,
+            mask_errors,
+        }
+    }
+
+    fn mask(mask_errors: bool, response: Response) -> Response {
+        if mask_errors {
+            mask_internal_errors(response)
+        } else {
+            response
+        }
+    }
+}
+
+#[async_trait]
+impl Executor for WebSocketExecutor {
+    async fn execute(&self, request: Request) -> Response {
+        let response = execute_with_timeout(self.schema.execute(request), self.query_timeout).await;
+        Self::mask(self.mask_errors, response)
+    }
+
+    fn execute_stream(
+        &self,
+        request: Request,
+        session_data: Option<Arc<Data>>,
+    ) -> BoxStream<'static, Response> {
+        let mask_errors = self.mask_errors;
+        let query_timeout = self.query_timeout;
+        let is_subscription = is_subscription(&request);
+
+        let mut responses = self
+            .schema
+            .execute_stream_with_session_data(request, session_data.unwrap_or_default());
+
+        if is_subscription {
+            return responses
+                .map(move |response| Self::mask(mask_errors, response))
+                .boxed();
+        }
+
+        // Queries and mutations only return one response
+        stream::once(async move {
+            let execution = async move { responses.next().await.unwrap_or_default() };
+            let response = execute_with_timeout(execution, query_timeout).await;
+            Self::mask(mask_errors, response)
+        })
+        .boxed()
+    }
+}
+
+/// Returns true if the operation executed by this request is a subscription.
+fn is_subscription(request: &Request) -> bool {
+    let document = match parse_query(&request.query) {
+        Ok(document) => document,
+        Err(_) => return false,
+    };
+
+    document
+        .operations
+        .iter()
+        .filter(|(name, _)| match &request.operation_name {
+            Some(operation_name) => name.map(|name| name.as_str()) == Some(operation_name),
+            None => true,
+        })
+        .any(|(_, operation)| operation.node.ty == OperationType::Subscription)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Request;
+
+    use super::is_subscription;
+
+    #[test]
+    fn detect_subscriptions() {
+        assert!(is_subscription(&Request::new("subscription { changes }")));
+        assert!(!is_subscription(&Request::new("{ documents }")));
+        assert!(!is_subscription(&Request::new("mutation { publish }")));
+
+        // Only the executed operation counts
+        let query = "query A { documents } subscription B { changes }";
+        assert!(is_subscription(&Request::new(query).operation_name("B")));
+        assert!(!is_subscription(&Request::new(query).operation_name("A")));
+    }
+}
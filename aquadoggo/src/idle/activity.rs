@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Time of the last request or network activity, cloned instances share the same state.
+#[derive(Debug, Clone)]
+pub struct Activity(Arc<Mutex<Instant>>);
+
+impl Activity {
+    /// Returns a new instance of `Activity`, counting the current time as the last activity.
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+
+    /// Records activity at the current time.
+    pub fn touch(&self) {
+        *self.0.lock().expect("Could not acquire lock on activity") = Instant::now();
+    }
+
+    /// Returns the duration since the last activity.
+    pub fn idle_for(&self) -> Duration {
+        self.0
+            .lock()
+            .expect("Could not acquire lock on activity")
+            .elapsed()
+    }
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Activity;
+
+    #[test]
+    fn touch_resets_idle_duration() {
+        let activity = Activity::new();
+        let clone = activity.clone();
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(activity.idle_for() >= Duration::from_millis(20));
+
+        clone.touch();
+        assert!(activity.idle_for() < Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Release resources while the node is idle.
+//!
+//! Requests to the HTTP API and messages on the service bus count as activity. When there was no
+//! activity for the configured `idle_timeout`, idle database connections get closed and in-memory
+//! caches dropped. Nothing needs to be restored explicitly afterwards: connections are opened
+//! again on demand and caches fill up with the next requests.
+mod activity;
+mod service;
+
+pub use activity::Activity;
+pub use service::idle_service;
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task;
+
+use crate::bus::ServiceSender;
+use crate::context::Context;
+use crate::manager::{ServiceReadySender, Shutdown};
+
+/// Maximum interval in which the node checks if it became idle.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Releases database connections and in-memory caches when the node was idle for the configured
+/// `idle_timeout`.
+pub async fn idle_service(
+    context: Context,
+    shutdown: Shutdown,
+    tx: ServiceSender,
+    tx_ready: ServiceReadySender,
+) -> Result<()> {
+    let idle_timeout = context
+        .config
+        .idle_timeout
+        .ok_or_else(|| anyhow!("No idle timeout configured"))?;
+
+    // Count every message on the service bus as activity, for example new operations or
+    // messages exchanged with other peers
+    let mut rx = tx.subscribe();
+    let activity = context.activity.clone();
+    let bus_handle = task::spawn(async move {
+        while let Ok(_) | Err(RecvError::Lagged(_)) = rx.recv().await {
+            activity.touch();
+        }
+    });
+
+    let handle = task::spawn(async move {
+        let mut interval = tokio::time::interval(idle_timeout.min(IDLE_CHECK_INTERVAL));
+        let mut is_idle = false;
+
+        loop {
+            interval.tick().await;
+
+            if context.activity.idle_for() < idle_timeout {
+                is_idle = false;
+                continue;
+            }
+
+            // Release resources only once per idle period
+            if is_idle {
+                continue;
+            }
+            is_idle = true;
+
+            let closed = context.store.close_idle_connections().await;
+            context.blob_integrity.clear_cache().await;
+            context.store.blob_cache.clear();
+
+            info!(
+                "Node is idle, closed {} database connections and dropped caches",
+                closed
+            );
+        }
+    });
+
+    debug!("Idle service is ready");
+    if tx_ready.send(()).is_err() {
+        warn!("No subscriber informed about idle service being ready");
+    };
+
+    shutdown.await.ok();
+    bus_handle.abort();
+    handle.abort();
+
+    Ok(())
+}
@@ -12,31 +12,64 @@
     unused_qualifications
 )]
 #![allow(clippy::uninlined_format_args)]
+#[cfg(unix)]
+mod admin;
+mod aggregates;
 mod api;
+mod blobs;
 mod bus;
+#[cfg(feature = "chaos")]
+mod chaos;
 mod config;
 mod context;
 mod db;
+mod document_hooks;
+mod errors;
 mod graphql;
 mod http;
+mod idle;
+mod log_ids;
 mod manager;
 mod materializer;
 mod network;
 mod node;
+mod projections;
 #[cfg(all(test, feature = "proptests"))]
 mod proptests;
 mod replication;
 mod schema;
+mod status;
 #[cfg(test)]
 mod test_utils;
 #[cfg(test)]
 mod tests;
+mod utils;
 
 use log::{info, log_enabled, Level};
 
 pub use crate::api::{ConfigFile, LockFile, NodeEvent};
-pub use crate::config::{AllowList, Configuration};
+#[cfg(feature = "thumbnails")]
+pub use crate::blobs::ThumbnailHook;
+pub use crate::blobs::{
+    BlobBackendConfiguration, BlobCacheMetrics, BlobHook, BlobHooks, BlobReader, DerivedBlob,
+    S3Configuration,
+};
+pub use crate::config::{
+    AllowList, BlobLimits, Configuration, MaterializedAggregate, NameClaims, PageSizes,
+    UniqueConstraint,
+};
+pub use crate::db::check_database;
+pub use crate::document_hooks::{DocumentHook, DocumentHooks, MaterializedDocument};
+pub use crate::errors::{BoxError, Error};
+pub use crate::graphql::GraphQLSchemaDiff;
+pub use crate::log_ids::{LogIdPolicy, SchemaBoundLogIds, SequentialLogIds, SharedLogIdPolicy};
+pub use crate::materializer::{TaskMetrics, TASK_DURATION_BUCKETS};
 pub use crate::network::{NetworkConfiguration, Transport};
+pub use crate::projections::{
+    Projection, ProjectionError, ProjectionExpression, ProjectionField, ProjectionFunction,
+    ProjectionSource,
+};
+pub use crate::replication::Compression;
 pub use node::Node;
 
 /// Init env_logger before the test suite runs to handle logging outputs.
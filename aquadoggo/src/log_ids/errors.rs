@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::api::DomainError;
+use thiserror::Error;
+
+use crate::db::errors::SqlStoreError;
+
+/// Errors returned when calculating the arguments for the next entry.
+#[derive(Error, Debug)]
+pub enum LogIdError {
+    /// Validating the requested document or log failed.
+    #[error(transparent)]
+    Domain(#[from] DomainError),
+
+    /// Looking up the used log ids failed.
+    #[error(transparent)]
+    Store(#[from] SqlStoreError),
+
+    /// All log ids of the range given by the policy are used already.
+    #[error("No free log id left in range {0} to {1}")]
+    RangeExhausted(u64, u64),
+}
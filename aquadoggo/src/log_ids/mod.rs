@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Allocation of log ids for new documents.
+//!
+//! Every document of an author lives in its own log. When a client asks for the arguments to
+//! create a new document, the node picks the log id according to a `LogIdPolicy`:
+//!
+//! - `SequentialLogIds` (default) returns the log id following the highest one in use by the
+//!   author.
+//! - `SchemaBoundLogIds` reserves a dedicated range of log ids for every schema, derived from the
+//!   schema id. Clients which assign log ids themselves can calculate the same ranges without
+//!   asking the node.
+//!
+//! Publishing an entry only requires the log id of a new document to be free, entries created
+//! with log ids assigned by the client are accepted regardless of the configured policy.
+mod errors;
+mod next_args;
+mod policy;
+
+pub use errors::LogIdError;
+pub use next_args::next_args;
+pub use policy::{LogIdPolicy, SchemaBoundLogIds, SequentialLogIds, SharedLogIdPolicy};
@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::api;
+use p2panda_rs::document::DocumentViewId;
+use p2panda_rs::entry::{LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::PublicKey;
+use p2panda_rs::operation::traits::AsOperation;
+use p2panda_rs::schema::SchemaId;
+use p2panda_rs::storage_provider::traits::OperationStore;
+
+use crate::db::SqlStore;
+use crate::log_ids::{LogIdError, LogIdPolicy};
+
+/// Calculate the arguments for the next entry of a public key.
+///
+/// Entries of existing logs are handled exactly like `p2panda_rs::api::next_args` does, while the
+/// log id for new documents is allocated according to the given policy. When no schema id is given
+/// it is derived from the document view id, if this one is known.
+pub async fn next_args(
+    store: &SqlStore,
+    policy: &dyn LogIdPolicy,
+    public_key: &PublicKey,
+    document_view_id: Option<&DocumentViewId>,
+    schema_id: Option<&SchemaId>,
+) -> Result<(Option<Hash>, Option<Hash>, SeqNum, LogId), LogIdError> {
+    let (backlink, skiplink, seq_num, log_id) =
+        api::next_args(store, public_key, document_view_id).await?;
+
+    // Entries for existing logs keep their log id
+    if backlink.is_some() {
+        return Ok((backlink, skiplink, seq_num, log_id));
+    }
+
+    let schema_id = match (schema_id, document_view_id) {
+        (Some(schema_id), _) => Some(schema_id.to_owned()),
+        (None, Some(document_view_id)) => {
+            let operation_id = document_view_id
+                .iter()
+                .next()
+                .expect("Document view ids contain at least one operation");
+            store
+                .get_operation(operation_id)
+                .await
+                .map_err(api::DomainError::from)?
+                .map(|operation| operation.schema_id())
+        }
+        (None, None) => None,
+    };
+
+    let range = policy.range(schema_id.as_ref());
+    let next_log_id = match store.latest_log_id_in_range(public_key, &range).await? {
+        Some(latest_log_id) => latest_log_id.as_u64() + 1,
+        None => range.start,
+    };
+
+    if next_log_id >= range.end {
+        return Err(LogIdError::RangeExhausted(range.start, range.end - 1));
+    }
+
+    Ok((None, None, seq_num, LogId::new(next_log_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::api;
+    use p2panda_rs::entry::LogId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+
+    use crate::log_ids::{LogIdPolicy, SchemaBoundLogIds, SequentialLogIds};
+    use crate::test_utils::{add_document, add_schema, test_runner, TestNode};
+
+    use super::next_args;
+
+    #[rstest]
+    fn allocate_log_ids_by_policy(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "venues",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+            let public_key = key_pair.public_key();
+            let store = &node.context.store;
+
+            // Sequential policy behaves like the default p2panda implementation
+            let (_, _, _, expected_log_id) =
+                api::next_args(store, &public_key, None).await.unwrap();
+            let (_, _, _, log_id) = next_args(
+                store,
+                &SequentialLogIds,
+                &public_key,
+                None,
+                Some(schema.id()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(log_id, expected_log_id);
+
+            // Schema bound policy starts at the range of the schema
+            let range = SchemaBoundLogIds.range(Some(schema.id()));
+            let (backlink, _, seq_num, log_id) = next_args(
+                store,
+                &SchemaBoundLogIds,
+                &public_key,
+                None,
+                Some(schema.id()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(log_id, LogId::new(range.start));
+            assert_eq!(seq_num.as_u64(), 1);
+            assert_eq!(backlink, None);
+
+            // Without schema id the first range gets used
+            let (_, _, _, log_id) = next_args(store, &SchemaBoundLogIds, &public_key, None, None)
+                .await
+                .unwrap();
+            assert_eq!(log_id, expected_log_id);
+
+            // Existing logs keep their log id
+            let view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "Panda Cafe".into())],
+                &key_pair,
+            )
+            .await;
+            let (backlink, _, seq_num, log_id) = next_args(
+                &node.context.store,
+                &SchemaBoundLogIds,
+                &public_key,
+                Some(&view_id),
+                None,
+            )
+            .await
+            .unwrap();
+            assert_eq!(log_id, expected_log_id);
+            assert_eq!(seq_num.as_u64(), 2);
+            assert!(backlink.is_some());
+        });
+    }
+}
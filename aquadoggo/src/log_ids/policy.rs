@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+
+use p2panda_rs::hash::Hash;
+use p2panda_rs::schema::SchemaId;
+
+/// Number of log ids reserved for every schema by `SchemaBoundLogIds`.
+const SCHEMA_RANGE_SIZE: u64 = 1 << 32;
+
+/// Number of schema ranges, leaving the highest bit unused as log ids are compared as signed
+/// integers in the database.
+const SCHEMA_RANGES: u64 = 1 << 31;
+
+/// Policy deciding which log ids are allocated for new documents.
+pub trait LogIdPolicy: Debug + Send + Sync {
+    /// Returns the range of log ids new documents of the given schema are allocated from.
+    ///
+    /// The schema is not known when clients do not pass it to `nextArgs`. The node picks the log
+    /// id following the highest one the author already used inside of this range.
+    fn range(&self, schema_id: Option<&SchemaId>) -> Range<u64>;
+}
+
+/// Shared log id policy, as used in the node configuration.
+pub type SharedLogIdPolicy = Arc<dyn LogIdPolicy>;
+
+/// Allocates log ids in sequential order per author, independent of the schema.
+#[derive(Debug, Clone, Default)]
+pub struct SequentialLogIds;
+
+impl LogIdPolicy for SequentialLogIds {
+    fn range(&self, _schema_id: Option<&SchemaId>) -> Range<u64> {
+        0..i64::MAX as u64
+    }
+}
+
+/// Allocates log ids from a dedicated range for every schema.
+///
+/// Every range contains 2^32 log ids. The first range is used for documents where no schema was
+/// given, all others start at `(n + 1) * 2^32` where `n` is the last 4 bytes of the BLAKE3 hash of
+/// the schema id interpreted as a big-endian integer, modulo `2^31 - 1`.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaBoundLogIds;
+
+impl LogIdPolicy for SchemaBoundLogIds {
+    fn range(&self, schema_id: Option<&SchemaId>) -> Range<u64> {
+        let index = match schema_id {
+            Some(schema_id) => {
+                let hash = Hash::new_from_bytes(schema_id.to_string().as_bytes()).to_bytes();
+                let mut suffix = [0; 4];
+                suffix.copy_from_slice(&hash[hash.len() - 4..]);
+                u32::from_be_bytes(suffix) as u64 % (SCHEMA_RANGES - 1) + 1
+            }
+            None => 0,
+        };
+
+        let start = index * SCHEMA_RANGE_SIZE;
+        start..start + SCHEMA_RANGE_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::schema::SchemaId;
+    use p2panda_rs::test_utils::fixtures::schema_id;
+    use rstest::rstest;
+
+    use super::{LogIdPolicy, SchemaBoundLogIds, SequentialLogIds};
+
+    #[rstest]
+    fn schema_bound_ranges(schema_id: SchemaId) {
+        let policy = SchemaBoundLogIds;
+
+        assert_eq!(policy.range(None), 0..1 << 32);
+
+        let range = policy.range(Some(&schema_id));
+        assert_eq!(range, policy.range(Some(&schema_id)));
+        assert_eq!(range.end - range.start, 1 << 32);
+        assert!(range.start >= 1 << 32);
+        assert!(range.end <= i64::MAX as u64 + 1);
+        assert_ne!(range, policy.range(Some(&SchemaId::SchemaDefinition(1))));
+    }
+
+    #[rstest]
+    fn sequential_range(schema_id: SchemaId) {
+        let policy = SequentialLogIds;
+        assert_eq!(policy.range(None), policy.range(Some(&schema_id)));
+    }
+}
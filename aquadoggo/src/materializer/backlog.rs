@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Number of tasks waiting in the queues of the materializer, used to apply backpressure to the
+//! paths publishing new entries.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::Notify;
+
+/// Shared counter of tasks waiting to be processed, with an optional limit.
+///
+/// Publishing entries dispatches materializer tasks. When entries arrive faster than they can be
+/// materialized, for example when a peer floods the node, the queues would grow without bounds.
+/// Publishing and replication wait for the backlog to drop below the limit instead.
+#[derive(Debug, Clone, Default)]
+pub struct TaskBacklog {
+    inner: Arc<TaskBacklogInner>,
+}
+
+#[derive(Debug, Default)]
+struct TaskBacklogInner {
+    /// Number of tasks waiting in all queues.
+    queued: AtomicU64,
+
+    /// Maximum number of waiting tasks, no limit is applied when zero.
+    limit: AtomicU64,
+
+    /// Wakes up callers waiting for the backlog to shrink.
+    notify: Notify,
+}
+
+impl TaskBacklog {
+    /// Returns a new, empty backlog without a limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of waiting tasks, 0 disables the limit.
+    pub fn set_limit(&self, limit: u64) {
+        self.inner.limit.store(limit, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Returns the number of tasks waiting in all queues.
+    pub fn queued(&self) -> u64 {
+        self.inner.queued.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if the number of waiting tasks reached the limit.
+    pub fn is_full(&self) -> bool {
+        let limit = self.inner.limit.load(Ordering::SeqCst);
+        limit > 0 && self.queued() >= limit
+    }
+
+    /// Waits until the number of waiting tasks is below the limit.
+    ///
+    /// Callers stop waiting when this future gets dropped, for example when the GraphQL request
+    /// waiting for capacity exceeded its timeout or the client disconnected.
+    pub async fn wait_for_capacity(&self) {
+        loop {
+            // Register for wake-ups before checking, to not miss any in between
+            let notified = self.inner.notify.notified();
+            if !self.is_full() {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Counts a task which was moved into a queue.
+    pub(crate) fn push(&self) {
+        self.inner.queued.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Counts a task which was taken out of a queue.
+    pub(crate) fn pop(&self) {
+        self.inner.queued.fetch_sub(1, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use super::TaskBacklog;
+
+    #[tokio::test]
+    async fn wait_for_capacity() {
+        let backlog = TaskBacklog::new();
+
+        // Without a limit callers never wait
+        backlog.push();
+        backlog.push();
+        assert!(!backlog.is_full());
+
+        backlog.set_limit(2);
+        assert!(backlog.is_full());
+        assert!(
+            timeout(Duration::from_millis(50), backlog.wait_for_capacity())
+                .await
+                .is_err()
+        );
+
+        // Callers continue as soon as a task got taken out of the queue
+        let waiting = tokio::spawn({
+            let backlog = backlog.clone();
+            async move { backlog.wait_for_capacity().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        backlog.pop();
+        assert!(timeout(Duration::from_millis(50), waiting).await.is_ok());
+        assert_eq!(backlog.queued(), 1);
+    }
+}
@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::future::Future;
+use std::time::Duration;
+
+use log::{error, warn};
+
+use crate::context::Context;
+use crate::db::types::DeadLetterTask;
+use crate::materializer::worker::{Task, TaskError, TaskResult};
+use crate::materializer::TaskInput;
+use crate::utils::now;
+
+/// Maximum time to wait before retrying a critically failed task.
+const MAX_TASK_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Runs a task and keeps track of its critical failures.
+///
+/// Critical errors usually crash the node. Critically failing tasks are retried instead, waiting
+/// `task_retry_backoff` before the first retry and twice as long before every further one. Tasks
+/// failing for the number of times configured via `max_task_attempts` are moved into the
+/// dead-letter queue, where they can be inspected, retried or discarded by requests sending the
+/// admin token via the GraphQL API. Successful attempts after failed ones reset the counter.
+pub async fn with_dead_letters<W, F>(
+    name: &'static str,
+    work: W,
+    context: Context,
+    input: TaskInput,
+) -> TaskResult<TaskInput>
+where
+    W: Fn(Context, TaskInput) -> F,
+    F: Future<Output = TaskResult<TaskInput>>,
+{
+    let max_task_attempts = context.config.max_task_attempts as u64;
+    if max_task_attempts == 0 {
+        return work(context, input).await;
+    }
+
+    let task = Task::new(name, input.clone());
+
+    // Only clear the failure counter in the database when we've increased it before, successful
+    // tasks don't touch it at all
+    let mut recorded_failure = false;
+
+    loop {
+        match work(context.clone(), input.clone()).await {
+            Err(TaskError::Critical(message)) => {
+                let attempts = match context.store.record_task_failure(&task).await {
+                    Ok(attempts) => attempts,
+                    Err(_) => return Err(TaskError::Critical(message)),
+                };
+                recorded_failure = true;
+
+                if attempts < max_task_attempts {
+                    let backoff = retry_backoff(context.config.task_retry_backoff, attempts);
+                    warn!(
+                        "Retrying task {} {} in {}ms after {} failed attempts: {}",
+                        name,
+                        task.input(),
+                        backoff.as_millis(),
+                        attempts,
+                        message
+                    );
+                    tokio::time::sleep(backoff).await;
+                    continue;
+                }
+
+                let dead_letter_task = DeadLetterTask {
+                    task,
+                    attempts,
+                    error: message,
+                    failed_at: now(),
+                };
+
+                context
+                    .store
+                    .insert_dead_letter_task(&dead_letter_task)
+                    .await
+                    .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+                error!(
+                    "Moved task {} {} into dead-letter queue after {} failed attempts: {}",
+                    name,
+                    dead_letter_task.task.input(),
+                    attempts,
+                    dead_letter_task.error
+                );
+
+                return Err(TaskError::Failure(dead_letter_task.error));
+            }
+            result => {
+                if result.is_ok() && recorded_failure {
+                    context
+                        .store
+                        .clear_task_failures(&task)
+                        .await
+                        .map_err(|err| TaskError::Critical(err.to_string()))?;
+                }
+
+                return result;
+            }
+        }
+    }
+}
+
+/// Returns the time to wait before retrying a task after the given number of failed attempts,
+/// doubling with every attempt.
+fn retry_backoff(base: Duration, attempts: u64) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1) as u32);
+    base.saturating_mul(factor).min(MAX_TASK_RETRY_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use p2panda_rs::document::DocumentId;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::document_id;
+    use rstest::rstest;
+
+    use crate::context::Context;
+    use crate::materializer::worker::{Task, TaskError, TaskResult};
+    use crate::materializer::TaskInput;
+    use crate::test_utils::{test_runner, TestNode};
+
+    use super::{retry_backoff, with_dead_letters, MAX_TASK_RETRY_BACKOFF};
+
+    async fn failing_task(_context: Context, _input: TaskInput) -> TaskResult<TaskInput> {
+        Err(TaskError::Critical("Something went wrong".into()))
+    }
+
+    /// Returns a context retrying tasks quickly.
+    fn fast_retries(context: &Context) -> Context {
+        let mut config = context.config.clone();
+        config.task_retry_backoff = Duration::from_millis(10);
+        Context::new(
+            context.store.clone(),
+            KeyPair::new(),
+            config,
+            context.schema_provider.clone(),
+        )
+    }
+
+    #[rstest]
+    fn move_task_into_dead_letters_after_max_attempts(document_id: DocumentId) {
+        test_runner(|node: TestNode| async move {
+            let context = fast_retries(&node.context);
+            let input = TaskInput::DocumentId(document_id);
+
+            // Default configuration allows three attempts
+            let result =
+                with_dead_letters("reduce", failing_task, context.clone(), input.clone()).await;
+            assert!(matches!(result, Err(TaskError::Failure(_))));
+
+            let dead_letter_tasks = node.context.store.get_dead_letter_tasks().await.unwrap();
+            assert_eq!(dead_letter_tasks.len(), 1);
+            assert_eq!(dead_letter_tasks[0].task, Task::new("reduce", input));
+            assert_eq!(dead_letter_tasks[0].attempts, 3);
+            assert_eq!(dead_letter_tasks[0].error, "Something went wrong");
+        });
+    }
+
+    #[rstest]
+    fn retry_failed_tasks(document_id: DocumentId) {
+        test_runner(|node: TestNode| async move {
+            let context = fast_retries(&node.context);
+            let input = TaskInput::DocumentId(document_id);
+
+            // Task fails critically once and succeeds on its second attempt
+            let attempts = Arc::new(AtomicU64::new(0));
+            let flaky_task = |_context: Context, _input: TaskInput| {
+                let attempts = attempts.clone();
+                async move {
+                    match attempts.fetch_add(1, Ordering::SeqCst) {
+                        0 => Err(TaskError::Critical("Database not available".into())),
+                        _ => Ok(None),
+                    }
+                }
+            };
+
+            let result = with_dead_letters("reduce", flaky_task, context, input.clone()).await;
+            assert!(result.is_ok());
+            assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+            // Failure counter was reset after the successful attempt
+            let task = Task::new("reduce", input);
+            assert_eq!(
+                node.context.store.record_task_failure(&task).await.unwrap(),
+                1
+            );
+            assert!(node
+                .context
+                .store
+                .get_dead_letter_tasks()
+                .await
+                .unwrap()
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let base = Duration::from_secs(1);
+        assert_eq!(retry_backoff(base, 1), Duration::from_secs(1));
+        assert_eq!(retry_backoff(base, 2), Duration::from_secs(2));
+        assert_eq!(retry_backoff(base, 4), Duration::from_secs(8));
+        assert_eq!(retry_backoff(base, 40), MAX_TASK_RETRY_BACKOFF);
+    }
+}
@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Counters and histograms of processed tasks, for example to monitor the materializer from
+//! outside and spot backlogs of reduce tasks.
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upper bounds of the buckets of task duration histograms.
+pub const TASK_DURATION_BUCKETS: [Duration; 8] = [
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+];
+
+/// Counters and duration histogram of one task type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskMetrics {
+    /// Number of tasks which were moved into the queue.
+    pub queued: u64,
+
+    /// Number of tasks which were processed successfully.
+    pub completed: u64,
+
+    /// Number of tasks which were aborted or failed critically.
+    pub failed: u64,
+
+    /// Number of tasks currently waiting in the queue.
+    pub queue_depth: u64,
+
+    /// Number of processed tasks per duration bucket.
+    ///
+    /// Every value counts the tasks which took at most the duration of the bucket in
+    /// `TASK_DURATION_BUCKETS` with the same index and longer than the one before. The last value
+    /// counts all tasks which took longer than the largest bucket.
+    pub duration_buckets: [u64; TASK_DURATION_BUCKETS.len() + 1],
+
+    /// Total time spent processing tasks.
+    pub duration_sum: Duration,
+}
+
+/// Shared metrics of all task types, keyed by the name of their worker pool.
+#[derive(Debug, Clone, Default)]
+pub struct MaterializerMetrics {
+    tasks: Arc<Mutex<BTreeMap<String, TaskMetrics>>>,
+}
+
+impl MaterializerMetrics {
+    /// Returns new metrics without any recorded tasks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current state of the metrics of all task types.
+    pub fn snapshot(&self) -> BTreeMap<String, TaskMetrics> {
+        self.tasks
+            .lock()
+            .expect("Could not acquire lock on materializer metrics")
+            .clone()
+    }
+
+    /// Counts a task which was moved into the queue.
+    pub(crate) fn on_queued(&self, name: &str) {
+        self.update(name, |metrics| {
+            metrics.queued += 1;
+            metrics.queue_depth += 1;
+        });
+    }
+
+    /// Counts a task which was taken out of the queue by a worker.
+    pub(crate) fn on_started(&self, name: &str) {
+        self.update(name, |metrics| {
+            metrics.queue_depth = metrics.queue_depth.saturating_sub(1);
+        });
+    }
+
+    /// Counts a processed task and records how long it took.
+    pub(crate) fn on_finished(&self, name: &str, duration: Duration, failed: bool) {
+        self.update(name, |metrics| {
+            if failed {
+                metrics.failed += 1;
+            } else {
+                metrics.completed += 1;
+            }
+
+            let bucket = TASK_DURATION_BUCKETS
+                .iter()
+                .position(|bound| duration <= *bound)
+                .unwrap_or(TASK_DURATION_BUCKETS.len());
+            metrics.duration_buckets[bucket] += 1;
+            metrics.duration_sum += duration;
+        });
+    }
+
+    fn update(&self, name: &str, update: impl FnOnce(&mut TaskMetrics)) {
+        let mut tasks = self
+            .tasks
+            .lock()
+            .expect("Could not acquire lock on materializer metrics");
+        update(tasks.entry(name.to_owned()).or_default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::MaterializerMetrics;
+
+    #[test]
+    fn record_task_metrics() {
+        let metrics = MaterializerMetrics::new();
+
+        metrics.on_queued("reduce");
+        metrics.on_queued("reduce");
+        metrics.on_queued("blob");
+        metrics.on_started("reduce");
+        metrics.on_finished("reduce", Duration::from_millis(3), false);
+        metrics.on_started("blob");
+        metrics.on_finished("blob", Duration::from_secs(10), true);
+
+        let snapshot = metrics.snapshot();
+        let reduce = &snapshot["reduce"];
+        assert_eq!(reduce.queued, 2);
+        assert_eq!(reduce.completed, 1);
+        assert_eq!(reduce.failed, 0);
+        assert_eq!(reduce.queue_depth, 1);
+        assert_eq!(reduce.duration_buckets, [0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(reduce.duration_sum, Duration::from_millis(3));
+
+        // Tasks slower than the largest bucket land in the last one
+        let blob = &snapshot["blob"];
+        assert_eq!(blob.failed, 1);
+        assert_eq!(blob.queue_depth, 0);
+        assert_eq!(blob.duration_buckets[8], 1);
+    }
+}
@@ -1,10 +1,20 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod backlog;
+mod dead_letters;
 mod input;
+mod metrics;
+mod pending;
 mod service;
 pub(crate) mod tasks;
+mod timeline;
 mod worker;
 
+pub use backlog::TaskBacklog;
 pub use input::TaskInput;
+pub use metrics::{MaterializerMetrics, TaskMetrics, TASK_DURATION_BUCKETS};
+pub use pending::{PendingTask, PendingTasks};
 pub use service::materializer_service;
+pub(crate) use service::TASK_NAMES;
+pub use timeline::{TaskTimeline, TaskTiming, TIMELINE_RETENTION};
 pub use worker::Task;
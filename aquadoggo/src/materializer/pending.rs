@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Tasks which are currently waiting in a queue or being processed, for example to find out why
+//! a document is not materialized yet.
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use crate::materializer::worker::Task;
+
+/// Task which was moved into a queue and did not finish yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTask<IN> {
+    /// Pending task.
+    pub task: Task<IN>,
+
+    /// Time when the task was moved into the queue.
+    pub queued_at: SystemTime,
+
+    /// Time when a worker started processing the task, `None` if it is still waiting.
+    pub started_at: Option<SystemTime>,
+}
+
+/// Pending tasks, keyed by the name of their worker pool and their id in its queue.
+type PendingTasksMap<IN> = BTreeMap<(String, u64), PendingTask<IN>>;
+
+/// In-memory record of all queued and running tasks.
+#[derive(Debug, Clone)]
+pub struct PendingTasks<IN> {
+    tasks: Arc<Mutex<PendingTasksMap<IN>>>,
+}
+
+impl<IN> PendingTasks<IN>
+where
+    IN: Clone,
+{
+    /// Returns a new, empty record.
+    pub fn new() -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Returns all pending tasks, ordered by the time they were queued.
+    pub fn list(&self) -> Vec<PendingTask<IN>> {
+        let mut tasks: Vec<PendingTask<IN>> = self.lock().values().cloned().collect();
+        tasks.sort_by_key(|pending| pending.queued_at);
+        tasks
+    }
+
+    /// Records a task which was moved into the queue of a worker pool under the given id.
+    pub(crate) fn on_queued(&self, id: u64, task: Task<IN>, queued_at: SystemTime) {
+        let key = (task.worker_name().to_owned(), id);
+        self.lock().insert(
+            key,
+            PendingTask {
+                task,
+                queued_at,
+                started_at: None,
+            },
+        );
+    }
+
+    /// Marks a queued task as being processed by a worker.
+    pub(crate) fn on_started(&self, name: &str, id: u64) {
+        if let Some(pending) = self.lock().get_mut(&(name.to_owned(), id)) {
+            pending.started_at = Some(SystemTime::now());
+        }
+    }
+
+    /// Removes a task after a worker finished processing it.
+    pub(crate) fn on_finished(&self, name: &str, id: u64) {
+        self.lock().remove(&(name.to_owned(), id));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PendingTasksMap<IN>> {
+        self.tasks
+            .lock()
+            .expect("Could not acquire lock on pending tasks")
+    }
+}
+
+impl<IN> Default for PendingTasks<IN>
+where
+    IN: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::materializer::worker::Task;
+
+    use super::PendingTasks;
+
+    #[test]
+    fn record_pending_tasks() {
+        let pending = PendingTasks::new();
+        let now = SystemTime::now();
+
+        pending.on_queued(0, Task::new("reduce", 1), now);
+        pending.on_queued(0, Task::new("blob", 2), now - Duration::from_secs(1));
+        pending.on_queued(1, Task::new("reduce", 3), now + Duration::from_secs(1));
+
+        pending.on_started("reduce", 0);
+        pending.on_finished("blob", 0);
+
+        // Ids are only unique per worker pool
+        let tasks = pending.list();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].task, Task::new("reduce", 1));
+        assert!(tasks[0].started_at.is_some());
+        assert_eq!(tasks[1].task, Task::new("reduce", 3));
+        assert!(tasks[1].started_at.is_none());
+    }
+}
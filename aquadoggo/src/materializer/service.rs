@@ -1,18 +1,50 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use anyhow::Result;
-use log::{debug, warn};
+use log::{debug, info, warn};
+use p2panda_rs::document::traits::AsDocument;
 use p2panda_rs::storage_provider::traits::OperationStore;
 use tokio::task;
 
+use crate::aggregates::rebuild_aggregates;
 use crate::bus::{ServiceMessage, ServiceSender};
 use crate::context::Context;
+use crate::db::stores::document::DOCUMENTS_PAGE_SIZE;
 use crate::manager::{ServiceReadySender, Shutdown};
+use crate::materializer::dead_letters::with_dead_letters;
 use crate::materializer::tasks::{
-    blob_task, dependency_task, garbage_collection_task, reduce_task, schema_task,
+    blob_hooks_task, blob_task, dependency_task, document_hooks_task, garbage_collection_task,
+    reduce_task, schema_task,
 };
 use crate::materializer::worker::{Factory, Task, TaskStatus};
 use crate::materializer::TaskInput;
+use crate::projections::rebuild_projections;
+
+/// Names of all materialization task types, each having their own pool of workers.
+pub(crate) const TASK_NAMES: [&str; 7] = [
+    "reduce",
+    "dependency",
+    "schema",
+    "blob",
+    "blob_hooks",
+    "document_hooks",
+    "garbage_collection",
+];
+
+/// Returns the priority of a materialization task type when none is configured.
+///
+/// Schema tasks come first as new schemas need to be known before documents following them can be
+/// queried, then the tasks materializing documents. Blob and cleanup tasks get the remaining
+/// resources.
+fn default_task_priority(name: &str) -> u8 {
+    match name {
+        "schema" => 2,
+        "reduce" | "dependency" => 1,
+        _ => 0,
+    }
+}
 
 /// Capacity of the internal broadcast channels used inside the worker factory.
 ///
@@ -20,6 +52,13 @@ use crate::materializer::TaskInput;
 /// queues the channels can handle at once.
 const CHANNEL_CAPACITY: usize = 512_000;
 
+/// Interval in which blob pieces which are not referred to by any blob anymore get purged.
+const BLOB_PIECE_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum age of unreferenced blob pieces before they get purged. Pieces get published before
+/// the blob referring to them, this gives clients and replication enough time to complete it.
+const BLOB_PIECE_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60 * 24);
+
 /// The materializer service waits for incoming new operations to transform them into actual useful
 /// application- and system data, like document views or schemas.
 ///
@@ -33,15 +72,73 @@ pub async fn materializer_service(
     tx_ready: ServiceReadySender,
 ) -> Result<()> {
     // Create worker factory with task queue
-    let pool_size = context.config.worker_pool_size as usize;
-    let mut factory = Factory::<TaskInput, Context>::new(context.clone(), CHANNEL_CAPACITY);
+    let config = &context.config;
+    let pool_size = |name: &str| {
+        let default = match name {
+            "blob" => config.blob_worker_pool_size,
+            _ => config.worker_pool_size,
+        };
+        config
+            .worker_pool_sizes
+            .get(name)
+            .copied()
+            .unwrap_or(default) as usize
+    };
+    let mut factory = Factory::<TaskInput, Context>::new(context.clone(), CHANNEL_CAPACITY)
+        .with_timeline(context.status.task_timeline())
+        .with_backlog(context.status.task_backlog())
+        .with_metrics(context.status.materializer_metrics())
+        .with_pending_tasks(context.status.pending_tasks());
+
+    // Publishing new entries waits while too many tasks are queued
+    context
+        .status
+        .task_backlog()
+        .set_limit(config.task_queue_limit);
 
     // Register worker functions in factory
-    factory.register("reduce", pool_size, reduce_task);
-    factory.register("dependency", pool_size, dependency_task);
-    factory.register("schema", pool_size, schema_task);
-    factory.register("blob", pool_size, blob_task);
-    factory.register("garbage_collection", pool_size, garbage_collection_task);
+    factory.register("reduce", pool_size("reduce"), |context, input| {
+        with_dead_letters("reduce", reduce_task, context, input)
+    });
+    factory.register("dependency", pool_size("dependency"), |context, input| {
+        with_dead_letters("dependency", dependency_task, context, input)
+    });
+    factory.register("schema", pool_size("schema"), |context, input| {
+        with_dead_letters("schema", schema_task, context, input)
+    });
+    factory.register("blob", pool_size("blob"), |context, input| {
+        with_dead_letters("blob", blob_task, context, input)
+    });
+    factory.register("blob_hooks", pool_size("blob_hooks"), |context, input| {
+        with_dead_letters("blob_hooks", blob_hooks_task, context, input)
+    });
+    factory.register(
+        "document_hooks",
+        pool_size("document_hooks"),
+        |context, input| with_dead_letters("document_hooks", document_hooks_task, context, input),
+    );
+    factory.register(
+        "garbage_collection",
+        pool_size("garbage_collection"),
+        |context, input| {
+            with_dead_letters(
+                "garbage_collection",
+                garbage_collection_task,
+                context,
+                input,
+            )
+        },
+    );
+
+    // Workers only start tasks when no tasks of a higher priority are waiting
+    for name in TASK_NAMES {
+        let priority = config
+            .task_priorities
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| default_task_priority(name));
+        factory.set_priority(name, priority);
+    }
 
     // Get a listener for error signal from factory
     let on_error = factory.on_error();
@@ -49,6 +146,7 @@ pub async fn materializer_service(
     // Subscribe to status changes of tasks
     let mut on_task_status_change = factory.on_task_status_change();
     let store = context.store.clone();
+    let tx_status = tx.clone();
 
     // Keep track of status changes and persist it in the database. This allows us to pick up
     // uncompleted tasks next time we start the node.
@@ -66,6 +164,13 @@ pub async fn materializer_service(
                         .remove_task(&task)
                         .await
                         .expect("Failed removing completed task from database");
+
+                    // Inform subscribers of document changes that they can pick up new changes
+                    if task.worker_name() == "reduce"
+                        && tx_status.send(ServiceMessage::DocumentsChanged).is_err()
+                    {
+                        // Silently fail here as we don't mind if there are no subscribers
+                    }
                 }
                 Err(err) => {
                     panic!("Failed receiving task status updates: {}", err)
@@ -74,6 +179,90 @@ pub async fn materializer_service(
         }
     });
 
+    // Fill projection tables and aggregates with the latest state of all documents. This needs to
+    // happen before any task gets dispatched to not miss any updates
+    rebuild_projections(&context.store, &context.config.projections).await?;
+    rebuild_aggregates(&context.store, &context.config.materialized_aggregates).await?;
+
+    // Fill the lookup table of claimed names from scratch, the configured schema might have
+    // changed since last time
+    context.store.clear_name_claims().await?;
+    if let Some(name_claims) = &context.config.name_claims {
+        let mut after = None;
+        loop {
+            let documents = context
+                .store
+                .get_documents_by_schema_paginated(
+                    &name_claims.schema_id,
+                    DOCUMENTS_PAGE_SIZE,
+                    after.as_ref(),
+                )
+                .await?;
+
+            for document in &documents {
+                if let Some(name) = name_claims.claimed_name(document) {
+                    context
+                        .store
+                        .update_name_claim(document.id(), Some((&name, document.author())))
+                        .await?;
+                }
+            }
+
+            match documents.last() {
+                Some(document) if documents.len() as u64 == DOCUMENTS_PAGE_SIZE => {
+                    after = Some(document.id().to_owned());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    // Fill the unique index from scratch as well, constraints might have been added or changed
+    // since last time
+    context.store.clear_unique_index().await?;
+    for constraint in &context.config.unique_constraints {
+        let mut after = None;
+        loop {
+            let documents = context
+                .store
+                .get_documents_by_schema_paginated(
+                    &constraint.schema_id,
+                    DOCUMENTS_PAGE_SIZE,
+                    after.as_ref(),
+                )
+                .await?;
+
+            for document in &documents {
+                if let Some(value) = constraint.value(document) {
+                    context
+                        .store
+                        .update_unique_index(
+                            &constraint.schema_id,
+                            &constraint.name(),
+                            document.id(),
+                            Some(&value),
+                        )
+                        .await?;
+                }
+            }
+
+            match documents.last() {
+                Some(document) if documents.len() as u64 == DOCUMENTS_PAGE_SIZE => {
+                    after = Some(document.id().to_owned());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    // Failed attempts are only counted within one run of a task, reset them for tasks which were
+    // interrupted by the last shutdown
+    context
+        .store
+        .clear_all_task_failures()
+        .await
+        .expect("Failed clearing task failures in database");
+
     // Reschedule tasks from last time which did not complete
     let tasks = context
         .store
@@ -96,32 +285,40 @@ pub async fn materializer_service(
         // Listen to incoming new entries and operations and move them into task queue
         task::spawn(async move {
             loop {
-                if let Ok(ServiceMessage::NewOperation(operation_id)) = rx.recv().await {
-                    // Resolve document id of regarding operation
-                    let document_id = context
-                        .store
-                        .get_document_id_by_operation_id(&operation_id)
-                        .await
-                        .unwrap_or_else(|_| {
-                            panic!(
-                            "Failed database query when retrieving document id by operation_id {}",
-                            operation_id
-                        )
-                        });
-
-                    match document_id {
-                        Some(document_id) => {
-                            // Dispatch "reduce" task which will materialize the regarding document.
-                            factory.queue(Task::new("reduce", TaskInput::DocumentId(document_id)))
-                        }
-                        None => {
-                            // Panic when we couldn't find the regarding document in the database. We can
-                            // safely assure that this is due to a critical bug affecting the database
-                            // integrity. Panicking here will close `handle` and by that signal a node
-                            // shutdown.
-                            panic!("Could not find document for operation_id {}", operation_id);
-                        }
-                    };
+                match rx.recv().await {
+                    Ok(ServiceMessage::NewOperation(operation_id)) => {
+                        // Resolve document id of regarding operation
+                        let document_id = context
+                            .store
+                            .get_document_id_by_operation_id(&operation_id)
+                            .await
+                            .unwrap_or_else(|_| {
+                                panic!(
+                                "Failed database query when retrieving document id by operation_id {}",
+                                operation_id
+                            )
+                            });
+
+                        match document_id {
+                            Some(document_id) => {
+                                // Dispatch "reduce" task which will materialize the regarding document.
+                                factory
+                                    .queue(Task::new("reduce", TaskInput::DocumentId(document_id)))
+                            }
+                            None => {
+                                // Panic when we couldn't find the regarding document in the database. We can
+                                // safely assure that this is due to a critical bug affecting the database
+                                // integrity. Panicking here will close `handle` and by that signal a node
+                                // shutdown.
+                                panic!("Could not find document for operation_id {}", operation_id);
+                            }
+                        };
+                    }
+                    Ok(ServiceMessage::RetryTask(task)) => {
+                        // Dispatch task which was moved out of the dead-letter queue
+                        factory.queue(task)
+                    }
+                    _ => (),
                 }
             }
         })
@@ -144,6 +341,29 @@ pub async fn materializer_service(
         let _ = tx.send(ServiceMessage::NewOperation(id));
     }
 
+    // Regularly purge blob pieces which are not needed anymore, for example after a blob got
+    // updated with new pieces
+    let store = context.store.clone();
+    let cleanup_handle = task::spawn(async move {
+        let mut interval = tokio::time::interval(BLOB_PIECE_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let received_before = (SystemTime::now() - BLOB_PIECE_GRACE_PERIOD)
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+
+            match store.purge_unreferenced_blob_pieces(received_before).await {
+                Ok(purged) if !purged.is_empty() => {
+                    info!("Purged {} unreferenced blob pieces", purged.len());
+                }
+                Ok(_) => (),
+                Err(err) => warn!("Failed purging unreferenced blob pieces: {}", err),
+            }
+        }
+    });
+
     // Wait until we received the application shutdown signal or handle closed
     tokio::select! {
         _ = handle => (),
@@ -152,6 +372,8 @@ pub async fn materializer_service(
         _ = on_error => (),
     }
 
+    cleanup_handle.abort();
+
     Ok(())
 }
 
@@ -170,17 +392,19 @@ mod tests {
     use p2panda_rs::test_utils::fixtures::{key_pair, operation, operation_fields, schema};
     use p2panda_rs::test_utils::memory_store::helpers::send_to_store;
     use rstest::rstest;
+    use tokio::runtime::Runtime;
     use tokio::sync::{broadcast, oneshot};
     use tokio::task;
 
     use crate::context::Context;
+    use crate::db::{connection_pool, create_database, run_pending_migrations, SqlStore};
     use crate::materializer::{Task, TaskInput};
     use crate::schema::SchemaProvider;
     use crate::test_utils::{
-        doggo_fields, doggo_schema, populate_store, populate_store_config, test_runner,
-        PopulateStoreConfig, TestNode,
+        add_document, add_schema, doggo_fields, doggo_schema, populate_store,
+        populate_store_config, test_runner, PopulateStoreConfig, TestNode,
     };
-    use crate::Configuration;
+    use crate::{Configuration, UniqueConstraint};
 
     use super::materializer_service;
 
@@ -584,4 +808,176 @@ mod tests {
             assert_eq!(document.id(), &entry_encoded.hash().into());
         });
     }
+
+    #[rstest]
+    fn resume_materialization_after_crash(
+        #[from(populate_store_config)]
+        #[with(3, 8, vec![KeyPair::new()])]
+        config: PopulateStoreConfig,
+    ) {
+        // Keep the database in a file so it outlives the runtimes of both node sessions
+        let dir = tempfile::tempdir().unwrap();
+        let database_url = format!("sqlite://{}/db.sqlite3", dir.path().display());
+
+        let open_store = |database_url: String| async move {
+            create_database(&database_url).await.unwrap();
+            let pool = connection_pool(&database_url, 1, None).await.unwrap();
+            run_pending_migrations(&pool).await.unwrap();
+            SqlStore::new(pool)
+        };
+
+        let start_materializer = |store: SqlStore| async move {
+            let context = Context::new(
+                store,
+                KeyPair::new(),
+                Configuration::default(),
+                SchemaProvider::default(),
+            );
+            let shutdown = task::spawn(async {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            });
+            let (tx, _) = broadcast::channel(1024);
+            let (tx_ready, rx_ready) = oneshot::channel::<()>();
+
+            task::spawn(async move {
+                materializer_service(context, shutdown, tx, tx_ready)
+                    .await
+                    .unwrap();
+            });
+
+            if rx_ready.await.is_err() {
+                panic!("Service dropped");
+            }
+        };
+
+        // Store operations and their pending "reduce" tasks, then kill the runtime while the
+        // materializer is still working on them
+        let runtime = Runtime::new().unwrap();
+        let documents = runtime.block_on(async {
+            let store = open_store(database_url.clone()).await;
+            let documents = populate_store(&store, &config).await;
+            for document in &documents {
+                store
+                    .insert_task(&Task::new(
+                        "reduce",
+                        TaskInput::DocumentId(document.id().to_owned()),
+                    ))
+                    .await
+                    .unwrap();
+            }
+
+            start_materializer(store.clone()).await;
+            while store.count_tasks().await.unwrap() == documents.len() as u64 {
+                task::yield_now().await;
+            }
+
+            documents
+        });
+        runtime.shutdown_background();
+
+        // Restarting the node picks up all unfinished work
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let store = open_store(database_url.clone()).await;
+            start_materializer(store.clone()).await;
+
+            for _ in 0..100 {
+                if store.count_tasks().await.unwrap() == 0 {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            assert_eq!(store.count_tasks().await.unwrap(), 0);
+
+            for document in &documents {
+                let materialized = store
+                    .get_document(document.id())
+                    .await
+                    .unwrap()
+                    .expect("Document to be materialized");
+                assert_eq!(materialized.view_id(), document.view_id());
+            }
+        });
+        runtime.shutdown_background();
+    }
+
+    #[rstest]
+    fn backfill_unique_index_on_start(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            // Create documents while no unique constraint is configured yet
+            let schema = add_schema(
+                &mut node,
+                "usernames",
+                vec![("username", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            let mut document_ids = Vec::new();
+            for username in ["panda", "bamboo", "panda"] {
+                let view_id = add_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("username", username.into())],
+                    &key_pair,
+                )
+                .await;
+                let document_id: DocumentId = view_id.to_string().parse().unwrap();
+                document_ids.push(document_id);
+            }
+
+            assert!(node
+                .context
+                .store
+                .get_unique_conflicts(schema.id())
+                .await
+                .unwrap()
+                .is_empty());
+
+            // Start materializer service with a constraint configured
+            let mut config = Configuration::default();
+            config.unique_constraints.push(UniqueConstraint {
+                schema_id: schema.id().to_owned(),
+                fields: vec!["username".into()],
+            });
+            let context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+            let shutdown = task::spawn(async {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            });
+            let (tx, _) = broadcast::channel(1024);
+            let (tx_ready, rx_ready) = oneshot::channel::<()>();
+
+            tokio::spawn(async move {
+                materializer_service(context, shutdown, tx, tx_ready)
+                    .await
+                    .unwrap();
+            });
+
+            if rx_ready.await.is_err() {
+                panic!("Service dropped");
+            }
+
+            // Documents which existed before got added to the index
+            let conflicts = node
+                .context
+                .store
+                .get_unique_conflicts(schema.id())
+                .await
+                .unwrap();
+            let mut conflicting = vec![document_ids[0].clone(), document_ids[2].clone()];
+            conflicting.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            assert_eq!(conflicts.len(), 1);
+            assert_eq!(conflicts[0].constraint_name, "username");
+            assert_eq!(conflicts[0].document_ids, conflicting);
+        });
+    }
 }
@@ -1,19 +1,17 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use futures::{pin_mut, StreamExt};
 use log::{debug, info};
 use p2panda_rs::document::traits::AsDocument;
 use p2panda_rs::operation::OperationValue;
 use p2panda_rs::schema::SchemaId;
 use p2panda_rs::storage_provider::traits::DocumentStore;
-use tokio::fs::OpenOptions;
-use tokio::io::AsyncWriteExt;
 
+use crate::blobs::{assemble_blob, BlobBackendError, BlobKey};
 use crate::context::Context;
 use crate::materializer::worker::{TaskError, TaskResult};
-use crate::materializer::TaskInput;
+use crate::materializer::{Task, TaskInput};
 
-/// A blob task assembles and persists blobs to the filesystem.
+/// A blob task assembles and persists blobs to the configured storage backend.
 ///
 /// Blob tasks are dispatched whenever a blob document has its dependencies (pieces) available in
 /// the store.
@@ -41,84 +39,87 @@ pub async fn blob_task(context: Context, input: TaskInput) -> TaskResult<TaskInp
                 )));
             }
 
-            // Determine a path for this blob file on the file system
-            let blob_view_path = context
-                .config
-                .blobs_base_path
-                .join(blob_document.view_id().to_string());
+            // Drop the previous view of this blob from the cache, it got updated
+            context.store.blob_cache.invalidate(blob_document.id());
+
+            let blob_key = BlobKey::from(blob_document.view_id());
+
+            let expected_blob_length = match blob_document.get("length").unwrap() {
+                OperationValue::Integer(length) => *length as u64,
+                _ => unreachable!(),
+            };
 
             // Check if the blob has already been fully materialized and return early from this task
             // with an error if it has.
-            let is_blob_materialized =
-                match OpenOptions::new().read(true).open(&blob_view_path).await {
-                    Ok(file) => {
-                        let metadata = file
-                            .metadata()
-                            .await
-                            .expect("Can retrieve blob file metadata");
-
-                        let expected_blob_length = match blob_document.get("length").unwrap() {
-                            OperationValue::Integer(length) => length,
-                            _ => unreachable!(),
-                        };
-
-                        metadata.len() == *expected_blob_length as u64
-                    }
-                    Err(_) => false,
-                };
+            let is_blob_materialized = match context.blobs.size(&blob_key).await {
+                Ok(Some(size)) => size == expected_blob_length,
+                _ => false,
+            };
             if is_blob_materialized {
                 return Err(TaskError::Failure(format!(
-                    "Blob file already exists at {}",
-                    blob_view_path.display()
+                    "Blob {} was already materialized",
+                    blob_document.view_id()
                 )));
             }
 
-            // Get a stream of raw blob data
-            let mut blob_stream = context
+            // Write the blob to the storage backend
+            info!("Creating blob {}", blob_document.view_id());
+
+            // Read from the stream of pieces, chunk by chunk, and hand every part over to the
+            // backend. This should put less pressure on our systems memory and allow writing
+            // large blob files
+            let hash = assemble_blob(
+                &context.store,
+                &context.blobs,
+                blob_document.view_id(),
+                expected_blob_length,
+            )
+            .await
+            .map_err(|err| match err {
+                // We don't raise a critical error here, as it is possible that not all blob
+                // pieces are available yet for materialisation
+                BlobBackendError::InvalidData(err) => TaskError::Failure(format!(
+                    "Blob data is invalid and can not be materialised: {}",
+                    err
+                )),
+                // Errors of the filesystem or object storage are usually transient and should
+                // not crash the node either
+                err => TaskError::Failure(format!(
+                    "Error occurred when writing blob {}: {}",
+                    blob_document.view_id(),
+                    err
+                )),
+            })?;
+
+            // Remember the hash of the blob data to verify the assembled file later
+            context
                 .store
-                .get_blob_by_view_id(blob_document.view_id())
+                .insert_blob_hash(blob_document.view_id(), &hash)
                 .await
-                // We don't raise a critical error here, as it is possible that this method returns an
-                // error, for example when not all blob pieces are available yet for materialisation
-                .map_err(|err| TaskError::Failure(err.to_string()))?
-                .expect("Blob data exists at this point");
-
-            // Write the blob to the filesystem
-            info!("Creating blob at path {}", blob_view_path.display());
-
-            let mut file = OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(&blob_view_path)
-                .await
-                .map_err(|err| {
-                    TaskError::Critical(format!(
-                        "Could not create blob file @ {}: {}",
-                        blob_view_path.display(),
-                        err
-                    ))
-                })?;
-
-            // Read from the stream, chunk by chunk, and write every part to the file. This should put
-            // less pressure on our systems memory and allow writing large blob files
-            let stream = blob_stream.read_all();
-            pin_mut!(stream);
-
-            while let Some(value) = stream.next().await {
-                match value {
-                    Ok(buf) => file.write_all(&buf).await.map_err(|err| {
-                        TaskError::Critical(format!(
-                            "Error occurred when writing to blob file @ {}: {}",
-                            blob_view_path.display(),
-                            err
-                        ))
-                    }),
-                    Err(err) => Err(TaskError::Failure(format!(
-                        "Blob data is invalid and can not be materialised: {}",
-                        err
-                    ))),
-                }?;
+                .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+            // Dispatch post-processing of this blob when hooks were registered for its MIME type
+            let mime_type = match blob_document.get("mime_type").unwrap() {
+                OperationValue::String(mime_type) => mime_type,
+                _ => unreachable!(),
+            };
+
+            if context
+                .config
+                .blob_hooks
+                .for_mime_type(mime_type)
+                .next()
+                .is_some()
+            {
+                debug!(
+                    "Dispatch blob_hooks task for blob: {}",
+                    blob_document.view_id()
+                );
+
+                return Ok(Some(vec![Task::new(
+                    "blob_hooks",
+                    TaskInput::DocumentViewId(blob_document.view_id().to_owned()),
+                )]));
             }
         }
         // If the blob document did not exist yet in the store we fail this task.
@@ -139,9 +140,13 @@ mod tests {
     use rstest::rstest;
     use tokio::fs;
 
+    use crate::config::Configuration;
     use crate::materializer::tasks::blob_task;
+    use crate::materializer::worker::TaskError;
     use crate::materializer::TaskInput;
-    use crate::test_utils::{add_blob, test_runner, TestNode};
+    use crate::test_utils::{
+        add_blob, test_runner, test_runner_with_manager, TestNode, TestNodeManager,
+    };
 
     #[rstest]
     fn materializes_blob_to_filesystem(key_pair: KeyPair) {
@@ -226,6 +231,29 @@ mod tests {
         })
     }
 
+    #[rstest]
+    fn storage_errors_do_not_crash_the_node(key_pair: KeyPair) {
+        test_runner_with_manager(|manager: TestNodeManager| async move {
+            // Blobs can't be written into a directory which is actually a file
+            let temp_file = tempfile::NamedTempFile::new().unwrap();
+            let config = Configuration {
+                blobs_base_path: temp_file.path().to_path_buf(),
+                ..Configuration::default()
+            };
+            let mut node = manager.create_with_config(config).await;
+
+            let blob_view_id =
+                add_blob(&mut node, b"Hello, World!", 5, "plain/text", &key_pair).await;
+
+            let result = blob_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(blob_view_id),
+            )
+            .await;
+            assert!(matches!(result, Err(TaskError::Failure(_))), "{:?}", result);
+        })
+    }
+
     #[rstest]
     fn rejects_incorrect_schema(key_pair: KeyPair) {
         test_runner(|mut node: TestNode| async move {
@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use futures::stream;
+use log::{debug, info, warn};
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::operation::OperationValue;
+use p2panda_rs::schema::SchemaId;
+use p2panda_rs::storage_provider::traits::DocumentStore;
+
+use crate::blobs::BlobKey;
+use crate::context::Context;
+use crate::materializer::worker::{TaskError, TaskResult};
+use crate::materializer::TaskInput;
+
+/// A blob hooks task runs all registered post-processing hooks on a materialized blob and
+/// persists their results as derived blobs.
+///
+/// Blob hooks tasks are dispatched by blob tasks after a blob was written to the storage backend.
+/// They run in their own worker pool to keep potentially expensive processing, like transcoding
+/// videos, off the materialization hot path.
+pub async fn blob_hooks_task(context: Context, input: TaskInput) -> TaskResult<TaskInput> {
+    debug!("Working on {}", input);
+
+    let input_view_id = match input {
+        TaskInput::DocumentViewId(view_id) => view_id,
+        _ => return Err(TaskError::Critical("Invalid task input".into())),
+    };
+
+    let blob_document = context
+        .store
+        .get_document_by_view_id(&input_view_id)
+        .await
+        .map_err(|err| TaskError::Failure(err.to_string()))?
+        .ok_or_else(|| TaskError::Failure("Blob does not exist (yet)".into()))?;
+
+    if !matches!(blob_document.schema_id(), SchemaId::Blob(_)) {
+        return Err(TaskError::Critical(format!(
+            "Unexpected system schema id: {}",
+            blob_document.schema_id()
+        )));
+    }
+
+    let mime_type = match blob_document.get("mime_type").unwrap() {
+        OperationValue::String(mime_type) => mime_type,
+        _ => unreachable!(),
+    };
+
+    for hook in context.config.blob_hooks.for_mime_type(mime_type) {
+        let reader = context
+            .blobs
+            .get(&BlobKey::from(&input_view_id))
+            .await
+            .map_err(|err| TaskError::Failure(err.to_string()))?
+            .ok_or_else(|| TaskError::Failure("Blob was not materialized (yet)".into()))?;
+
+        // Failing hooks do not stop the other ones from running
+        let derived_blob = match hook.process(mime_type, reader).await {
+            Ok(Some(derived_blob)) => derived_blob,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!(
+                    "Blob hook '{}' failed on blob {}: {}",
+                    hook.name(),
+                    input_view_id,
+                    err
+                );
+                continue;
+            }
+        };
+
+        info!(
+            "Creating derived blob '{}' of blob {}",
+            hook.name(),
+            input_view_id
+        );
+
+        let length = derived_blob.data.len() as u64;
+        let chunks = stream::iter([Ok(derived_blob.data)]);
+        context
+            .blobs
+            .put(
+                &BlobKey::derived(&input_view_id, hook.name()),
+                length,
+                Box::pin(chunks),
+            )
+            .await
+            .map_err(|err| TaskError::Failure(err.to_string()))?;
+
+        context
+            .store
+            .insert_derived_blob(&input_view_id, hook.name(), &derived_blob.mime_type)
+            .await
+            .map_err(|err| TaskError::Critical(err.to_string()))?;
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+    use tokio::fs;
+
+    use crate::blobs::{BlobHook, BlobReader, DerivedBlob};
+    use crate::context::Context;
+    use crate::materializer::tasks::{blob_hooks_task, blob_task};
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{add_blob, test_runner, TestNode};
+
+    /// Hook turning text into upper case letters.
+    #[derive(Debug)]
+    struct UppercaseHook;
+
+    #[async_trait]
+    impl BlobHook for UppercaseHook {
+        fn name(&self) -> &str {
+            "uppercase"
+        }
+
+        fn accepts(&self, mime_type: &str) -> bool {
+            mime_type == "text/plain"
+        }
+
+        async fn process(
+            &self,
+            _mime_type: &str,
+            mut data: BlobReader,
+        ) -> Result<Option<DerivedBlob>> {
+            let mut buf = vec![];
+            while let Some(chunk) = data.next().await {
+                buf.extend_from_slice(&chunk?);
+            }
+
+            Ok(Some(DerivedBlob {
+                mime_type: "text/plain".into(),
+                data: buf.to_ascii_uppercase(),
+            }))
+        }
+    }
+
+    #[rstest]
+    fn creates_derived_blobs(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            // Register hook on node
+            let mut config = node.context.config.clone();
+            config.blob_hooks.register(UppercaseHook);
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let blob_view_id =
+                add_blob(&mut node, b"Hello, World!", 6, "text/plain", &key_pair).await;
+
+            // Blob task dispatches a blob hooks task as there is a hook for "text/plain"
+            let next_tasks = blob_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(blob_view_id.clone()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                next_tasks,
+                Some(vec![Task::new(
+                    "blob_hooks",
+                    TaskInput::DocumentViewId(blob_view_id.clone())
+                )])
+            );
+
+            let result = blob_hooks_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(blob_view_id.clone()),
+            )
+            .await;
+            assert!(result.is_ok());
+
+            // Derived blob was written next to the original one
+            let blob_path = node
+                .context
+                .config
+                .blobs_base_path
+                .join(format!("{}.uppercase", blob_view_id));
+            let data = fs::read(blob_path).await.unwrap();
+            assert_eq!(data, b"HELLO, WORLD!");
+
+            let mime_type = node
+                .context
+                .store
+                .get_derived_blob(&blob_view_id, "uppercase")
+                .await
+                .unwrap();
+            assert_eq!(mime_type, Some("text/plain".into()));
+        })
+    }
+
+    #[rstest]
+    fn no_hooks_for_mime_type(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let blob_view_id =
+                add_blob(&mut node, b"Hello, World!", 6, "text/plain", &key_pair).await;
+
+            // No hooks are registered, so no follow-up task is dispatched
+            let next_tasks = blob_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(blob_view_id.clone()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(next_tasks, None);
+        })
+    }
+}
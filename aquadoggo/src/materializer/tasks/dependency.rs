@@ -8,6 +8,7 @@ use p2panda_rs::schema::{FieldType, SchemaId};
 use p2panda_rs::storage_provider::traits::DocumentStore;
 
 use crate::context::Context;
+use crate::db::stores::document::DOCUMENTS_PAGE_SIZE;
 use crate::materializer::worker::{Task, TaskError, TaskResult};
 use crate::materializer::TaskInput;
 
@@ -161,15 +162,15 @@ async fn get_relation_task(
 
     match context
         .store
-        .get_document_by_view_id(&document_view_id)
+        .document_view_exists(&document_view_id)
         .await
         .map_err(|err| TaskError::Critical(err.to_string()))?
     {
-        Some(_) => {
+        true => {
             debug!("View found for pinned relation: {}", document_view_id);
             Ok(None)
         }
-        None => {
+        false => {
             debug!("No view found for pinned relation: {}", document_view_id);
             Ok(Some(Task::new(
                 "reduce",
@@ -218,20 +219,34 @@ async fn get_inverse_relation_tasks(
     // Find all documents which follow these "parent" schemas
     for parent_schema_id in parent_schema_ids {
         // @TODO: Use a more efficient SQL query here, this does too much
-        let parent_documents = context
-            .store
-            .get_documents_by_schema(&parent_schema_id)
-            .await
-            .map_err(|err| TaskError::Critical(err.to_string()))?;
-
-        for parent_document in parent_documents {
-            // Dispatch dependency tasks from the latest document view of each parent document. We
-            // _only_ materialise historical document views when at least one _latest_ document
-            // view points at it.
-            tasks.push(Task::new(
-                "dependency",
-                TaskInput::DocumentViewId(parent_document.view_id().to_owned()),
-            ));
+        let mut after = None;
+        loop {
+            let parent_documents = context
+                .store
+                .get_documents_by_schema_paginated(
+                    &parent_schema_id,
+                    DOCUMENTS_PAGE_SIZE,
+                    after.as_ref(),
+                )
+                .await
+                .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+            for parent_document in &parent_documents {
+                // Dispatch dependency tasks from the latest document view of each parent document.
+                // We _only_ materialise historical document views when at least one _latest_
+                // document view points at it.
+                tasks.push(Task::new(
+                    "dependency",
+                    TaskInput::DocumentViewId(parent_document.view_id().to_owned()),
+                ));
+            }
+
+            match parent_documents.last() {
+                Some(document) if parent_documents.len() as u64 == DOCUMENTS_PAGE_SIZE => {
+                    after = Some(document.id().to_owned());
+                }
+                _ => break,
+            }
         }
     }
 
@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use log::{debug, warn};
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::storage_provider::traits::DocumentStore;
+
+use crate::context::Context;
+use crate::document_hooks::MaterializedDocument;
+use crate::materializer::worker::{TaskError, TaskResult};
+use crate::materializer::TaskInput;
+
+/// A document hooks task runs all registered document hooks on a materialized document view.
+///
+/// Document hooks tasks are dispatched by reduce tasks after the latest view of a document was
+/// stored. They run in their own worker pool to keep application-defined work, like updating a
+/// search index, off the materialization hot path.
+pub async fn document_hooks_task(context: Context, input: TaskInput) -> TaskResult<TaskInput> {
+    debug!("Working on {}", input);
+
+    let input_view_id = match input {
+        TaskInput::DocumentViewId(view_id) => view_id,
+        _ => return Err(TaskError::Critical("Invalid task input".into())),
+    };
+
+    let document = context
+        .store
+        .get_document_by_view_id(&input_view_id)
+        .await
+        .map_err(|err| TaskError::Failure(err.to_string()))?
+        .ok_or_else(|| TaskError::Failure("Document view does not exist (anymore)".into()))?;
+
+    let fields = match document.fields() {
+        Some(fields) => fields.to_owned(),
+        None => return Ok(None),
+    };
+
+    let materialized_document = MaterializedDocument {
+        id: document.id().to_owned(),
+        view_id: document.view_id().to_owned(),
+        author: document.author().to_owned(),
+        schema_id: document.schema_id().to_owned(),
+        fields,
+    };
+
+    for hook in context
+        .config
+        .document_hooks
+        .for_schema(document.schema_id())
+    {
+        // Failing hooks do not stop the other ones from running
+        if let Err(err) = hook.on_materialized(&materialized_document).await {
+            warn!(
+                "Document hook '{}' failed on document view {}: {}",
+                hook.name(),
+                input_view_id,
+                err
+            );
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use p2panda_rs::document::{DocumentId, DocumentViewId};
+    use p2panda_rs::entry::traits::AsEncodedEntry;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::{OperationAction, OperationBuilder, OperationValue};
+    use p2panda_rs::schema::{FieldType, SchemaId};
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use p2panda_rs::test_utils::memory_store::helpers::send_to_store;
+    use rstest::rstest;
+
+    use crate::context::Context;
+    use crate::document_hooks::{DocumentHook, MaterializedDocument};
+    use crate::materializer::tasks::{document_hooks_task, reduce_task};
+    use crate::materializer::{Task, TaskInput};
+    use crate::test_utils::{add_document, add_schema, test_runner, TestNode};
+
+    /// Hook recording the names of all materialized documents.
+    #[derive(Debug, Clone)]
+    struct RecordHook(SchemaId, Arc<Mutex<Vec<OperationValue>>>);
+
+    #[async_trait]
+    impl DocumentHook for RecordHook {
+        fn name(&self) -> &str {
+            "record"
+        }
+
+        fn accepts(&self, schema_id: &SchemaId) -> bool {
+            schema_id == &self.0
+        }
+
+        async fn on_materialized(&self, document: &MaterializedDocument) -> Result<()> {
+            let name = document.fields.get("name").unwrap().value().to_owned();
+            self.1.lock().unwrap().push(name);
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    fn runs_document_hooks(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "venues",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+
+            // Register hook on node
+            let recorded = Arc::new(Mutex::new(Vec::new()));
+            let config = node.context.config.clone();
+            config
+                .document_hooks
+                .register(RecordHook(schema.id().to_owned(), recorded.clone()));
+            node.context = Context::new(
+                node.context.store.clone(),
+                KeyPair::new(),
+                config,
+                node.context.schema_provider.clone(),
+            );
+
+            let view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "Panda Cafe".into())],
+                &key_pair,
+            )
+            .await;
+            let document_id: DocumentId = view_id.to_string().parse().unwrap();
+
+            // Publish an update without materializing it yet
+            let operation = OperationBuilder::new(schema.id())
+                .action(OperationAction::Update)
+                .fields(&[("name", "Doggo Cafe".into())])
+                .previous(&view_id)
+                .build()
+                .unwrap();
+            let (entry, _) = send_to_store(&node.context.store, &operation, &schema, &key_pair)
+                .await
+                .unwrap();
+            let view_id = DocumentViewId::from(entry.hash());
+
+            // Reduce task dispatches a document hooks task as there is a hook for this schema
+            let next_tasks = reduce_task(node.context.clone(), TaskInput::DocumentId(document_id))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(next_tasks.contains(&Task::new(
+                "document_hooks",
+                TaskInput::DocumentViewId(view_id.clone())
+            )));
+
+            let result = document_hooks_task(
+                node.context.clone(),
+                TaskInput::DocumentViewId(view_id.clone()),
+            )
+            .await;
+            assert!(result.is_ok());
+            assert_eq!(
+                recorded.lock().unwrap().as_slice(),
+                [OperationValue::String("Doggo Cafe".into())]
+            );
+        })
+    }
+
+    #[rstest]
+    fn no_hooks_for_schema(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "venues",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+            let view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "Panda Cafe".into())],
+                &key_pair,
+            )
+            .await;
+
+            // No hooks are registered, so no follow-up task is dispatched
+            let operation = OperationBuilder::new(schema.id())
+                .action(OperationAction::Update)
+                .fields(&[("name", "Doggo Cafe".into())])
+                .previous(&view_id)
+                .build()
+                .unwrap();
+            send_to_store(&node.context.store, &operation, &schema, &key_pair)
+                .await
+                .unwrap();
+
+            let document_id: DocumentId = view_id.to_string().parse().unwrap();
+            let next_tasks = reduce_task(node.context.clone(), TaskInput::DocumentId(document_id))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(next_tasks
+                .iter()
+                .all(|task| task.worker_name() != "document_hooks"));
+        })
+    }
+}
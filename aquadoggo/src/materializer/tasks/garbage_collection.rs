@@ -1,7 +1,5 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use tokio::fs::{remove_file, try_exists};
-
 use log::debug;
 use p2panda_rs::document::DocumentViewId;
 use p2panda_rs::operation::traits::AsOperation;
@@ -9,6 +7,7 @@ use p2panda_rs::schema::SchemaId;
 use p2panda_rs::storage_provider::traits::OperationStore;
 use p2panda_rs::Human;
 
+use crate::blobs::BlobKey;
 use crate::context::Context;
 use crate::materializer::worker::{TaskError, TaskResult};
 use crate::materializer::{Task, TaskInput};
@@ -135,16 +134,38 @@ pub async fn garbage_collection_task(context: Context, input: TaskInput) -> Task
                 }
             }
 
-            // We now remove all deleted blob views from the filesystem.
+            // We now remove all deleted blob views from the storage backend.
             if is_blob {
                 for view_id in deleted_views {
-                    // Delete this blob view from the filesystem also
-                    let blob_view_path = context.config.blobs_base_path.join(view_id.to_string());
-                    if let Ok(true) = try_exists(&blob_view_path).await {
-                        remove_file(blob_view_path)
+                    let is_removed = context
+                        .blobs
+                        .delete(&view_id.into())
+                        .await
+                        .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+                    if is_removed {
+                        debug!("Deleted blob view from storage backend: {}", view_id);
+                    }
+
+                    context
+                        .store
+                        .delete_blob_hash(view_id)
+                        .await
+                        .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+                    // Remove all blobs which were derived from this view by blob hooks as well
+                    let derived_blob_names = context
+                        .store
+                        .delete_derived_blobs(view_id)
+                        .await
+                        .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+                    for name in derived_blob_names {
+                        context
+                            .blobs
+                            .delete(&BlobKey::derived(view_id, &name))
                             .await
                             .map_err(|err| TaskError::Critical(err.to_string()))?;
-                        debug!("Deleted blob view from filesystem: {}", view_id);
                     }
                 }
             }
@@ -1,13 +1,17 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 mod blob;
+mod blob_hooks;
 mod dependency;
+mod document_hooks;
 mod garbage_collection;
 mod reduce;
 mod schema;
 
 pub use blob::blob_task;
+pub use blob_hooks::blob_hooks_task;
 pub use dependency::dependency_task;
+pub use document_hooks::document_hooks_task;
 pub use garbage_collection::garbage_collection_task;
 pub use reduce::reduce_task;
 pub use schema::schema_task;
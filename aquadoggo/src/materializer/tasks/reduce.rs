@@ -8,9 +8,12 @@ use p2panda_rs::operation::OperationId;
 use p2panda_rs::storage_provider::traits::{DocumentStore, EntryStore, LogStore, OperationStore};
 use p2panda_rs::{Human, WithId};
 
+use crate::aggregates::update_aggregates;
 use crate::context::Context;
+use crate::db::types::{StorageDocument, StorageOperation};
 use crate::materializer::worker::{Task, TaskError, TaskResult};
 use crate::materializer::TaskInput;
+use crate::projections::update_projections;
 
 /// Build a materialised view for a document by reducing the document's operation graph and storing
 /// it to disk.
@@ -47,7 +50,9 @@ pub async fn reduce_task(context: Context, input: TaskInput) -> TaskResult<TaskI
         .map_err(|err| TaskError::Critical(err.to_string()))?;
 
     match &input {
-        TaskInput::DocumentId(_) => reduce_document(&context, &operations).await,
+        TaskInput::DocumentId(document_id) => {
+            reduce_document(&context, document_id, &operations).await
+        }
         TaskInput::DocumentViewId(view_id) => {
             reduce_document_view(&context, &document_id, view_id, &operations).await
         }
@@ -117,14 +122,11 @@ async fn reduce_document_view<O: AsOperation + WithId<OperationId> + WithPublicK
     };
 
     // Make sure to not materialize and store document view twice
-    // @TODO: This can be a more efficient storage method. See issue:
-    // https://github.com/p2panda/aquadoggo/issues/431
     let document_view_exists = context
         .store
-        .get_document_by_view_id(document_view_id)
+        .document_view_exists(document_view_id)
         .await
-        .map_err(|err| TaskError::Critical(err.to_string()))?
-        .is_some();
+        .map_err(|err| TaskError::Critical(err.to_string()))?;
 
     if document_view_exists {
         return Ok(None);
@@ -184,23 +186,28 @@ async fn reduce_document_view<O: AsOperation + WithId<OperationId> + WithPublicK
 /// Helper method to reduce an operation graph to the latest document view, returning the
 /// `DocumentViewId` of the just created new document view.
 ///
+/// New operations are applied on top of the last stored view when possible, the whole operation
+/// graph is only reduced again when they branch off or merge concurrent edits.
+///
 /// It returns `None` if either that document view reached "deleted" status or we don't have enough
 /// operations to materialise.
-async fn reduce_document<O: AsOperation + WithId<OperationId> + WithPublicKey>(
+async fn reduce_document(
     context: &Context,
-    operations: &Vec<O>,
+    document_id: &DocumentId,
+    operations: &Vec<StorageOperation>,
 ) -> Result<Option<Vec<Task<TaskInput>>>, TaskError> {
+    if let Some(document) = reduce_document_incrementally(context, document_id, operations).await? {
+        return store_document(context, &document).await.map(Some);
+    }
+
     match DocumentBuilder::from(operations).build() {
         Ok((document, operations)) => {
             // Make sure to not materialize and store document view twice
-            // @TODO: This can be a more efficient storage method. See issue:
-            // https://github.com/p2panda/aquadoggo/issues/431
             let document_view_exists = context
                 .store
-                .get_document_by_view_id(document.view_id())
+                .document_view_exists(document.view_id())
                 .await
-                .map_err(|err| TaskError::Critical(err.to_string()))?
-                .is_some();
+                .map_err(|err| TaskError::Critical(err.to_string()))?;
 
             if document_view_exists {
                 return Ok(None);
@@ -216,65 +223,229 @@ async fn reduce_document<O: AsOperation + WithId<OperationId> + WithPublicKey>(
                     .map_err(|err| TaskError::Critical(err.to_string()))?;
             }
 
-            // Insert this document into storage. If it already existed, this will update its
-            // current view
-            context
-                .store
-                .insert_document(&document)
-                .await
-                .map_err(|err| TaskError::Critical(err.to_string()))?;
+            store_document(context, &document).await.map(Some)
+        }
+        Err(err) => {
+            // There is not enough operations yet to materialise this view. Maybe next time!
+            debug!("Document materialization error: {}", err);
 
-            let mut tasks = vec![];
+            Ok(None)
+        }
+    }
+}
 
-            if document.is_deleted() {
-                info!(
-                    "Deleted {} final view {}",
-                    document.display(),
-                    document.view_id().display()
-                );
-            } else if document.is_edited() {
-                info!(
-                    "Updated {} latest view {}",
-                    document.display(),
-                    document.view_id().display()
-                );
-            } else {
-                info!("Created {}", document.display());
-            };
+/// Helper method to apply operations which were not reduced yet on top of the last stored view of
+/// a document.
+///
+/// This is only possible when every new operation points at the view created by the one before,
+/// starting from the stored view. As the stored view consists of all tips of the operation graph,
+/// the new operations come after all other operations in the topologically sorted graph then.
+///
+/// Returns `None` if the document needs to be reduced from all its operations.
+async fn reduce_document_incrementally(
+    context: &Context,
+    document_id: &DocumentId,
+    operations: &[StorageOperation],
+) -> Result<Option<StorageDocument>, TaskError> {
+    let (indexed, mut new_operations): (Vec<_>, Vec<_>) = operations
+        .iter()
+        .partition(|operation| operation.sorted_index.is_some());
 
-            if document.is_deleted() || document.is_edited() {
-                debug!(
-                    "Dispatch garbage collection task for document with id: {}",
-                    document.id()
-                );
+    if new_operations.is_empty() {
+        return Ok(None);
+    }
 
-                tasks.push(Task::new(
-                    "garbage_collection",
-                    TaskInput::DocumentId(document.id().to_owned()),
-                ))
-            }
+    let mut document = match context
+        .store
+        .get_document(document_id)
+        .await
+        .map_err(|err| TaskError::Critical(err.to_string()))?
+    {
+        Some(document) => document,
+        None => return Ok(None),
+    };
 
-            if !document.is_deleted() {
-                debug!(
-                    "Dispatch dependency task for view with id: {}",
-                    document.view_id()
-                );
+    let mut sorted_index = indexed.len();
+    while !new_operations.is_empty() {
+        // Exactly one operation needs to continue the current view, otherwise the graph branches
+        let mut next_operations = new_operations
+            .iter()
+            .enumerate()
+            .filter(|(_, operation)| operation.previous() == Some(document.view_id().to_owned()));
+
+        let position = match (next_operations.next(), next_operations.next()) {
+            (Some((position, _)), None) => position,
+            _ => return Ok(None),
+        };
+
+        let operation = new_operations.remove(position);
+        if document.commit(&operation.id, operation).is_err() {
+            return Ok(None);
+        }
 
-                tasks.push(Task::new(
-                    "dependency",
-                    TaskInput::DocumentViewId(document.view_id().to_owned()),
-                ));
-            }
+        context
+            .store
+            .update_operation_index(&operation.id, sorted_index as i32)
+            .await
+            .map_err(|err| TaskError::Critical(err.to_string()))?;
+        sorted_index += 1;
+    }
 
-            Ok(Some(tasks))
-        }
-        Err(err) => {
-            // There is not enough operations yet to materialise this view. Maybe next time!
-            debug!("Document materialization error: {}", err);
+    debug!(
+        "Applied {} new operations on {} incrementally",
+        sorted_index - indexed.len(),
+        document.id().display()
+    );
 
-            Ok(None)
+    Ok(Some(document))
+}
+
+/// Helper method to store the latest view of a document and update everything depending on it.
+///
+/// Returns the tasks to dispatch next.
+async fn store_document(
+    context: &Context,
+    document: &impl AsDocument,
+) -> Result<Vec<Task<TaskInput>>, TaskError> {
+    // Insert this document into storage. If it already existed, this will update its current view
+    context
+        .store
+        .insert_document(document)
+        .await
+        .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+    // Keep the index of unique field values up-to-date with the latest document view
+    update_unique_index(context, document).await?;
+
+    // Keep the lookup table of names claimed by authors up-to-date
+    update_name_claim(context, document).await?;
+
+    // Update projections reading values from this document
+    update_projections(&context.store, &context.config.projections, document)
+        .await
+        .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+    // Update the totals of aggregates over the documents of this schema
+    update_aggregates(
+        &context.store,
+        &context.config.materialized_aggregates,
+        document,
+    )
+    .await
+    .map_err(|err| TaskError::Critical(err.to_string()))?;
+
+    let mut tasks = vec![];
+
+    if document.is_deleted() {
+        info!(
+            "Deleted {} final view {}",
+            document.id().display(),
+            document.view_id().display()
+        );
+    } else if document.is_edited() {
+        info!(
+            "Updated {} latest view {}",
+            document.id().display(),
+            document.view_id().display()
+        );
+    } else {
+        info!("Created {}", document.id().display());
+    };
+
+    if document.is_deleted() || document.is_edited() {
+        debug!(
+            "Dispatch garbage collection task for document with id: {}",
+            document.id()
+        );
+
+        tasks.push(Task::new(
+            "garbage_collection",
+            TaskInput::DocumentId(document.id().to_owned()),
+        ))
+    }
+
+    if !document.is_deleted() {
+        debug!(
+            "Dispatch dependency task for view with id: {}",
+            document.view_id()
+        );
+
+        tasks.push(Task::new(
+            "dependency",
+            TaskInput::DocumentViewId(document.view_id().to_owned()),
+        ));
+
+        if !context
+            .config
+            .document_hooks
+            .for_schema(document.schema_id())
+            .is_empty()
+        {
+            debug!(
+                "Dispatch document_hooks task for view with id: {}",
+                document.view_id()
+            );
+
+            tasks.push(Task::new(
+                "document_hooks",
+                TaskInput::DocumentViewId(document.view_id().to_owned()),
+            ));
         }
     }
+
+    Ok(tasks)
+}
+
+/// Helper method to update the unique index for all constraints configured for the schema of this
+/// document.
+///
+/// Deleted documents and documents which do not contain all fields of a constraint are removed
+/// from the index.
+async fn update_unique_index(
+    context: &Context,
+    document: &impl AsDocument,
+) -> Result<(), TaskError> {
+    let constraints = context
+        .config
+        .unique_constraints
+        .iter()
+        .filter(|constraint| &constraint.schema_id == document.schema_id());
+
+    for constraint in constraints {
+        let value = constraint.value(document);
+
+        context
+            .store
+            .update_unique_index(
+                document.schema_id(),
+                &constraint.name(),
+                document.id(),
+                value.as_deref(),
+            )
+            .await
+            .map_err(|err| TaskError::Critical(err.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Helper method to update the name claimed by the author of this document, if its schema holds
+/// the name claims configured on this node.
+async fn update_name_claim(context: &Context, document: &impl AsDocument) -> Result<(), TaskError> {
+    let name_claims = match &context.config.name_claims {
+        Some(name_claims) if &name_claims.schema_id == document.schema_id() => name_claims,
+        _ => return Ok(()),
+    };
+
+    let name = name_claims.claimed_name(document);
+    context
+        .store
+        .update_name_claim(
+            document.id(),
+            name.as_deref().map(|name| (name, document.author())),
+        )
+        .await
+        .map_err(|err| TaskError::Critical(err.to_string()))
 }
 
 #[cfg(test)]
@@ -285,12 +456,12 @@ mod tests {
     };
     use p2panda_rs::identity::KeyPair;
     use p2panda_rs::operation::traits::AsOperation;
-    use p2panda_rs::operation::OperationValue;
-    use p2panda_rs::schema::Schema;
+    use p2panda_rs::operation::{OperationAction, OperationBuilder, OperationId, OperationValue};
+    use p2panda_rs::schema::{FieldType, Schema};
     use p2panda_rs::storage_provider::traits::{DocumentStore, OperationStore};
     use p2panda_rs::test_utils::constants;
     use p2panda_rs::test_utils::fixtures::{
-        operation, operation_fields, random_document_id, random_document_view_id, schema,
+        key_pair, operation, operation_fields, random_document_id, random_document_view_id, schema,
     };
     use p2panda_rs::test_utils::memory_store::helpers::send_to_store;
     use p2panda_rs::WithId;
@@ -299,8 +470,8 @@ mod tests {
     use crate::materializer::tasks::reduce_task;
     use crate::materializer::TaskInput;
     use crate::test_utils::{
-        doggo_fields, doggo_schema, generate_key_pairs, populate_store, populate_store_config,
-        test_runner, PopulateStoreConfig, TestNode,
+        add_document, add_schema, doggo_fields, doggo_schema, generate_key_pairs, populate_store,
+        populate_store_config, test_runner, update_document, PopulateStoreConfig, TestNode,
     };
 
     #[rstest]
@@ -703,4 +874,86 @@ mod tests {
             assert_eq!(document_view_fields, *expected_document.fields().unwrap());
         })
     }
+
+    /// Checks that the stored document and the sorted operations are the same as when reducing all
+    /// operations at once.
+    async fn assert_reduced_from_all_operations(node: &TestNode, document_id: &DocumentId) {
+        let operations = node
+            .context
+            .store
+            .get_operations_by_document_id(document_id)
+            .await
+            .unwrap();
+        let (expected_document, expected_operations) =
+            DocumentBuilder::from(&operations).build().unwrap();
+
+        let document = node
+            .context
+            .store
+            .get_document(document_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.view_id(), expected_document.view_id());
+        assert_eq!(document.fields(), expected_document.fields());
+
+        let operation_ids: Vec<&OperationId> =
+            operations.iter().map(WithId::<OperationId>::id).collect();
+        let expected_operation_ids: Vec<&OperationId> = expected_operations
+            .iter()
+            .map(|(operation_id, _, _)| operation_id)
+            .collect();
+        assert_eq!(operation_ids, expected_operation_ids);
+    }
+
+    #[rstest]
+    fn reduces_documents_incrementally(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let schema = add_schema(
+                &mut node,
+                "venues",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+            let mut view_id = add_document(
+                &mut node,
+                schema.id(),
+                vec![("name", "Panda Cafe".into())],
+                &key_pair,
+            )
+            .await;
+            let document_id: DocumentId = view_id.to_string().parse().unwrap();
+
+            // Every update continues the last view and gets applied on top of it
+            for name in ["Doggo Cafe", "Doggo Bar"] {
+                view_id = update_document(
+                    &mut node,
+                    schema.id(),
+                    vec![("name", name.into())],
+                    &view_id,
+                    &key_pair,
+                )
+                .await;
+            }
+            assert_reduced_from_all_operations(&node, &document_id).await;
+
+            // Two concurrent updates branch off, the whole graph gets reduced again
+            for name in ["Panda Bar", "Panda Pub"] {
+                let operation = OperationBuilder::new(schema.id())
+                    .action(OperationAction::Update)
+                    .fields(&[("name", name.into())])
+                    .previous(&view_id)
+                    .build()
+                    .unwrap();
+                send_to_store(&node.context.store, &operation, &schema, &key_pair)
+                    .await
+                    .unwrap();
+            }
+            let input = TaskInput::DocumentId(document_id.clone());
+            reduce_task(node.context.clone(), input).await.unwrap();
+
+            assert_reduced_from_all_operations(&node, &document_id).await;
+        })
+    }
 }
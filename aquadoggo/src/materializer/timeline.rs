@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Timing data of recently processed tasks, for example to visualise them in a waterfall diagram
+//! and find bottlenecks during materialisation.
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crate::materializer::worker::Task;
+
+/// Duration for which processed tasks are kept in the timeline.
+pub const TIMELINE_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum number of processed tasks kept in the timeline, older ones get dropped first.
+const MAX_TIMELINE_ENTRIES: usize = 100_000;
+
+/// Timing of a task which was processed by a worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskTiming<IN> {
+    /// Processed task.
+    pub task: Task<IN>,
+
+    /// Time when the task was moved into the queue.
+    pub queued_at: SystemTime,
+
+    /// Time when a worker started processing the task.
+    pub started_at: SystemTime,
+
+    /// Time when the worker finished processing the task.
+    pub finished_at: SystemTime,
+}
+
+impl<IN> TaskTiming<IN> {
+    /// Returns the time the worker spent processing the task.
+    pub fn duration(&self) -> Duration {
+        self.finished_at
+            .duration_since(self.started_at)
+            .unwrap_or_default()
+    }
+}
+
+/// In-memory record of the tasks processed within the last hour.
+#[derive(Debug, Clone)]
+pub struct TaskTimeline<IN> {
+    entries: Arc<Mutex<VecDeque<TaskTiming<IN>>>>,
+}
+
+impl<IN> TaskTimeline<IN>
+where
+    IN: Clone,
+{
+    /// Returns a new, empty timeline.
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Adds a processed task to the timeline and drops entries older than the retention period.
+    pub fn record(&self, timing: TaskTiming<IN>) {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("Could not acquire lock on task timeline");
+
+        let retained_since = timing.finished_at - TIMELINE_RETENTION;
+        while let Some(entry) = entries.front() {
+            if entry.finished_at >= retained_since && entries.len() < MAX_TIMELINE_ENTRIES {
+                break;
+            }
+
+            entries.pop_front();
+        }
+
+        entries.push_back(timing);
+    }
+
+    /// Returns all tasks which finished after the given time, ordered by the time they started.
+    pub fn since(&self, since: SystemTime) -> Vec<TaskTiming<IN>> {
+        let entries = self
+            .entries
+            .lock()
+            .expect("Could not acquire lock on task timeline");
+
+        let mut timings: Vec<TaskTiming<IN>> = entries
+            .iter()
+            .filter(|entry| entry.finished_at >= since)
+            .cloned()
+            .collect();
+        timings.sort_by_key(|entry| entry.started_at);
+        timings
+    }
+}
+
+impl<IN> Default for TaskTimeline<IN>
+where
+    IN: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::materializer::worker::Task;
+
+    use super::{TaskTimeline, TaskTiming, TIMELINE_RETENTION};
+
+    fn timing(input: usize, started_at: SystemTime) -> TaskTiming<usize> {
+        TaskTiming {
+            task: Task::new("reduce", input),
+            queued_at: started_at - Duration::from_secs(1),
+            started_at,
+            finished_at: started_at + Duration::from_secs(2),
+        }
+    }
+
+    #[test]
+    fn record_timings() {
+        let timeline = TaskTimeline::new();
+        let now = SystemTime::now();
+
+        timeline.record(timing(1, now - TIMELINE_RETENTION * 2));
+        timeline.record(timing(2, now - Duration::from_secs(60)));
+        timeline.record(timing(3, now - Duration::from_secs(120)));
+
+        // The first task was dropped as it is older than the retention period
+        let timings = timeline.since(now - TIMELINE_RETENTION * 3);
+        assert_eq!(timings.len(), 2);
+
+        // Tasks are ordered by the time they started
+        assert_eq!(timings[0].task.input(), &3);
+        assert_eq!(timings[1].task.input(), &2);
+        assert_eq!(timings[0].duration(), Duration::from_secs(2));
+
+        // Only tasks which finished after the given time are returned
+        let timings = timeline.since(now - Duration::from_secs(90));
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].task.input(), &2);
+    }
+}
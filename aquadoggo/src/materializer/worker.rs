@@ -18,6 +18,10 @@
 //!
 //! Tasks can also dispatch subsequent tasks as soon as they finished successfully.
 //!
+//! Worker pools can be given a priority. Workers hold back their next task as long as tasks are
+//! waiting in the queue of any worker pool with a higher priority, this way important work gets
+//! all available resources first.
+//!
 //! The `Factory` struct is the main interface in this module, managing all workers and tasks. It
 //! registers worker pools with the regarding worker functions, adds new task to queues, schedules
 //! and processes them.
@@ -65,8 +69,7 @@
 //! --------------------
 //!
 //! The internal queue of "square" contains now: [{Task 1}, {Task 2}, {Task 4}]. Task 3 got
-//! internally "batched" as it contains the same input data as Task 1, which means that Task 1 will
-//! be re-scheduled after it finished.
+//! dropped as it contains the same input data as Task 1 which is still waiting in the queue.
 //!
 //! 3. Process tasks
 //!
@@ -74,8 +77,13 @@
 //! concurrently. After one of them finishes, the next free worker will eventually take Task 4 from
 //! the queue and process it.
 //!
-//! Task 1 results in "25", Task 2 in "64", Task 4 in "9". Later Task 1 gets re-scheduled to
-//! account for the duplicate Task 3 (again resulting in "25").
+//! Task 1 results in "25", Task 2 in "64", Task 4 in "9".
+//!
+//! 4. Queue duplicate task while it is processed
+//!
+//! If a task with the input "5" gets queued again _while_ Task 1 is being processed, it got
+//! internally "batched", which means that Task 1 will be re-scheduled after it finished (again
+//! resulting in "25").
 //!
 //! In this example that might look redundant but in a more complex system the input might be the
 //! same, but the worker function might have access to a database with possibily diverging state
@@ -87,6 +95,7 @@ use std::future::Future;
 use std::hash::Hash;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 use deadqueue::unlimited::Queue;
 use log::{debug, error, info};
@@ -95,6 +104,18 @@ use tokio::sync::broadcast::{channel, Receiver, Sender};
 use tokio::task;
 use triggered::{Listener, Trigger};
 
+use crate::materializer::backlog::TaskBacklog;
+use crate::materializer::metrics::MaterializerMetrics;
+use crate::materializer::pending::PendingTasks;
+use crate::materializer::timeline::{TaskTimeline, TaskTiming};
+
+/// Interval in which workers check again if tasks of worker pools with a higher priority are
+/// still waiting.
+const PRIORITY_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Priorities of all registered worker pools, together with their queues.
+type Priorities<IN> = Arc<Mutex<HashMap<WorkerName, (u8, Arc<Queue<QueueItem<IN>>>)>>>;
+
 /// A task holding a generic input value and the name of the worker which will process it
 /// eventually.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -147,6 +168,9 @@ pub type WorkerName = String;
 
 /// Flags for queue items to define post-completion actions.
 enum PostAction {
+    /// Task is still waiting in the queue, duplicates can be dropped.
+    Queued,
+
     /// Moves the completed task into the queue again.
     Requeue,
 
@@ -165,6 +189,9 @@ where
     /// This allows us to detect duplicate tasks by checking if there is already a task in our
     /// queue with the same input hash.
     ///
+    /// Duplicates arriving while the task is still waiting in the queue are dropped, the task will
+    /// see their changes anyhow when it gets processed.
+    ///
     /// An additional flag can be used to indicate that we want to requeue the same task again
     /// after it completed. This is useful to account for more events which arrived _while_ the
     /// task was processed. It is enough to only remember one of the potentially many events
@@ -236,6 +263,9 @@ where
 
     /// Task input values which get passed over to the worker function.
     input: IN,
+
+    /// Time when this item was moved into the queue.
+    queued_at: SystemTime,
 }
 
 impl<IN> Display for QueueItem<IN>
@@ -253,11 +283,14 @@ where
 {
     /// Returns a new queue item.
     pub fn new(id: u64, input: IN) -> Self {
-        Self { id, input }
+        Self {
+            id,
+            input,
+            queued_at: SystemTime::now(),
+        }
     }
 
     /// Returns unique identifier of this queue item.
-    #[allow(dead_code)]
     pub fn id(&self) -> u64 {
         self.id
     }
@@ -266,6 +299,11 @@ where
     pub fn input(&self) -> IN {
         self.input.clone()
     }
+
+    /// Returns the time when this item was moved into the queue.
+    pub fn queued_at(&self) -> SystemTime {
+        self.queued_at
+    }
 }
 
 /// This factory serves as a main entry interface to dispatch, schedule and process tasks.
@@ -286,6 +324,21 @@ where
     /// Broadcast channel to inform callbacks about pending or completed tasks.
     tx_status: Sender<TaskStatus<IN>>,
 
+    /// Timing data of processed tasks.
+    timeline: TaskTimeline<IN>,
+
+    /// Priorities of all registered worker pools.
+    priorities: Priorities<IN>,
+
+    /// Number of tasks waiting in all queues.
+    backlog: TaskBacklog,
+
+    /// Counters and durations of queued and processed tasks.
+    metrics: MaterializerMetrics,
+
+    /// Queued tasks and tasks currently being processed.
+    pending: PendingTasks<IN>,
+
     /// Sender of error signal.
     error_signal: Trigger,
 
@@ -318,11 +371,48 @@ where
             managers: HashMap::new(),
             tx,
             tx_status,
+            timeline: TaskTimeline::new(),
+            priorities: Arc::new(Mutex::new(HashMap::new())),
+            backlog: TaskBacklog::new(),
+            metrics: MaterializerMetrics::new(),
+            pending: PendingTasks::new(),
             error_signal,
             error_handle,
         }
     }
 
+    /// Records the timing of all processed tasks in the given timeline.
+    ///
+    /// This needs to be set before any worker pools get registered.
+    pub fn with_timeline(mut self, timeline: TaskTimeline<IN>) -> Self {
+        self.timeline = timeline;
+        self
+    }
+
+    /// Counts the tasks waiting in all queues in the given backlog.
+    ///
+    /// This needs to be set before any worker pools get registered.
+    pub fn with_backlog(mut self, backlog: TaskBacklog) -> Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Records counters and durations of all queued and processed tasks in the given metrics.
+    ///
+    /// This needs to be set before any worker pools get registered.
+    pub fn with_metrics(mut self, metrics: MaterializerMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Records all queued and running tasks in the given record.
+    ///
+    /// This needs to be set before any worker pools get registered.
+    pub fn with_pending_tasks(mut self, pending: PendingTasks<IN>) -> Self {
+        self.pending = pending;
+        self
+    }
+
     /// Registers a new worker pool with a dedicated worker function.
     ///
     /// Choose a worker pool size fitting the work and computational resources you have at hand to
@@ -343,6 +433,10 @@ where
             panic!("Can not create task manager twice");
         } else {
             let new_manager = WorkerManager::new();
+            self.priorities
+                .lock()
+                .expect("Could not acquire lock on worker priorities")
+                .insert(name.into(), (0, new_manager.queue.clone()));
             self.managers.insert(name.into(), new_manager);
         }
 
@@ -352,6 +446,22 @@ where
         self.spawn_workers(name, pool_size, work);
     }
 
+    /// Sets the priority of a registered worker pool, defaults to 0.
+    ///
+    /// Workers of this pool only start their next task when no tasks are waiting in the queues of
+    /// worker pools with a higher priority.
+    pub fn set_priority(&mut self, name: &str, priority: u8) {
+        match self
+            .priorities
+            .lock()
+            .expect("Could not acquire lock on worker priorities")
+            .get_mut(name)
+        {
+            Some(entry) => entry.0 = priority,
+            None => panic!("Unknown worker name"),
+        }
+    }
+
     /// Queues up a new task in the regarding worker queue.
     ///
     /// Tasks with duplicate input values which already exist in the queue will be internally
@@ -401,6 +511,9 @@ where
         let input_index = manager.input_index.clone();
         let name = String::from(name);
         let queue = manager.queue.clone();
+        let backlog = self.backlog.clone();
+        let metrics = self.metrics.clone();
+        let pending = self.pending.clone();
 
         // Create handle for error signal
         let error_signal = self.error_signal.clone();
@@ -436,18 +549,28 @@ where
                                         // Generate a unique id for this new task and add it to queue
                                         debug!("Sending materializer {} task with input {} to the task queue.", task.worker_name(), task.input());
                                         let next_id = counter.fetch_add(1, Ordering::Relaxed);
-                                        queue.push(QueueItem::new(next_id, task.1.clone()));
-                                        index.insert(task.1, PostAction::Idle);
+                                        let item = QueueItem::new(next_id, task.1.clone());
+                                        pending.on_queued(next_id, task.clone(), item.queued_at());
+                                        queue.push(item);
+                                        backlog.push();
+                                        metrics.on_queued(&name);
+                                        index.insert(task.1, PostAction::Queued);
+                                    }
+                                    Some(PostAction::Queued) => {
+                                        // 2. The same task is still waiting in the queue and will
+                                        // account for this one as well, let's drop it
+                                        debug!("Duplicate materializer {} task with input {} is still waiting in the queue, dropping this task.", task.worker_name(), task.input());
+                                        continue;
                                     }
                                     Some(PostAction::Idle) => {
-                                        // 2. This is the first duplicate coming in, let's set the
+                                        // 3. This is the first duplicate coming in, let's set the
                                         // requeue flag to indicate that more work needs to be done
                                         // when the current task completes
                                         debug!("Duplicate materializer {} task already in progress, setting re-queue flag for task with input {} and not adding this task to the queue.", task.worker_name(), task.input());
                                         index.insert(task.1, PostAction::Requeue);
                                     }
                                     Some(PostAction::Requeue) => {
-                                        // 3. We observed already one duplicate task coming in, let's
+                                        // 4. We observed already one duplicate task coming in, let's
                                         // ignore this one
                                         debug!("Materializer {} task with input {} not sent to queue as a task for this document has already been re-queued.", task.worker_name(), task.input());
                                         continue;
@@ -500,6 +623,21 @@ where
             // Create handle to send task status updates
             let tx_status = self.tx_status.clone();
 
+            // Create handle to record task timings
+            let timeline = self.timeline.clone();
+
+            // Create handle to check for waiting tasks with a higher priority
+            let priorities = self.priorities.clone();
+
+            // Create handle to count waiting tasks
+            let backlog = self.backlog.clone();
+
+            // Create handle to record task metrics
+            let metrics = self.metrics.clone();
+
+            // Create handle to record running tasks
+            let pending = self.pending.clone();
+
             task::spawn(async move {
                 // Inform status subscribers that we just completed a task
                 let on_complete = |input: IN| {
@@ -513,15 +651,57 @@ where
                 loop {
                     // Wait until there is a new task arriving in the queue
                     let item = queue.pop().await;
+                    backlog.pop();
+                    metrics.on_started(&name);
+
+                    // Give way to waiting tasks of worker pools with a higher priority
+                    while has_higher_priority_tasks(&priorities, &name) {
+                        tokio::time::sleep(PRIORITY_BACKOFF).await;
+                    }
+
+                    // Mark task as in progress, duplicates arriving from now on need to requeue it
+                    match input_index.lock() {
+                        Ok(mut index) => {
+                            index.insert(item.input(), PostAction::Idle);
+                        }
+                        Err(err) => {
+                            error!(
+                                "Error while locking input index in worker {} for task {:?}: {}",
+                                name, item, err
+                            );
+
+                            error_signal.trigger();
+                        }
+                    }
 
                     // Take this task and do work ..
+                    pending.on_started(&name, item.id());
+                    let started_at = SystemTime::now();
                     let result = work.call(context.clone(), item.input()).await;
 
+                    let timing = TaskTiming {
+                        task: Task::new(&name, item.input()),
+                        queued_at: item.queued_at(),
+                        started_at,
+                        finished_at: SystemTime::now(),
+                    };
+                    metrics.on_finished(&name, timing.duration(), result.is_err());
+                    timeline.record(timing);
+                    pending.on_finished(&name, item.id());
+
                     // Check the result
                     match result {
                         Ok(Some(list)) => {
                             // Tasks succeeded and dispatches new, subsequent tasks
                             for task in list {
+                                // Announce subsequent tasks as pending before this task completes.
+                                // Subscribers persisting pending tasks this way never lose them
+                                // when the node crashes in between
+                                if tx_status.send(TaskStatus::Pending(task.clone())).is_err() {
+                                    // Silently fail here since an error only occurs here when
+                                    // there are no subscribers, but we don't mind that.
+                                }
+
                                 if let Err(err) = tx.send(task) {
                                     error!("Error while broadcasting task: {}", err);
                                     error_signal.trigger();
@@ -548,7 +728,7 @@ where
                         Ok(mut index) => match index.remove(&item.input()) {
                             Some(PostAction::Idle) => false,
                             Some(PostAction::Requeue) => true,
-                            None => {
+                            Some(PostAction::Queued) | None => {
                                 error!("Incosistency detected in queue input index");
                                 error_signal.trigger();
                                 false
@@ -581,6 +761,26 @@ where
     }
 }
 
+/// Returns true if tasks are waiting in the queue of a worker pool with a higher priority than the
+/// given one.
+fn has_higher_priority_tasks<IN>(priorities: &Priorities<IN>, name: &str) -> bool
+where
+    IN: Send + Sync + Clone + Display + 'static,
+{
+    let priorities = priorities
+        .lock()
+        .expect("Could not acquire lock on worker priorities");
+
+    let priority = match priorities.get(name) {
+        Some((priority, _)) => *priority,
+        None => return false,
+    };
+
+    priorities
+        .values()
+        .any(|(other, queue)| *other > priority && !queue.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -591,6 +791,8 @@ mod tests {
     use rand::seq::SliceRandom;
     use rand::Rng;
 
+    use crate::materializer::MaterializerMetrics;
+
     use super::{Factory, Task, TaskError, TaskResult, TaskStatus};
 
     #[tokio::test]
@@ -675,10 +877,121 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(100)).await;
         assert!(factory.is_empty("one"));
 
-        // We expect a total of 12 recorded status messages:
+        // We expect a total of 15 recorded status messages:
         // - 3x "one" and 3x "two" tasks have been scheduled
+        // - 3x "two" tasks have been announced by the "one" worker
         // - 3x "one" and 3x "two" tasks have been completed
-        assert_eq!(messages.lock().unwrap().len(), 12);
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 15);
+
+        // Subsequent tasks are pending before the task dispatching them completed
+        for i in 0..3 {
+            let position = |status: TaskStatus<Input>| {
+                messages
+                    .iter()
+                    .position(|message| message == &status)
+                    .unwrap()
+            };
+
+            assert!(
+                position(TaskStatus::Pending(Task::new("two", i)))
+                    < position(TaskStatus::Completed(Task::new("one", i)))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn prioritise_worker_pools() {
+        type Input = usize;
+        type Data = Arc<Mutex<Vec<String>>>;
+
+        // Record the order in which tasks got processed
+        let processed = Arc::new(Mutex::new(Vec::new()));
+        let mut factory = Factory::<Input, Data>::new(processed.clone(), 1024);
+
+        factory.register("low", 1, |processed: Data, input: Input| async move {
+            processed.lock().unwrap().push(format!("low {input}"));
+            Ok(None)
+        });
+        factory.register("high", 1, |processed: Data, input: Input| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            processed.lock().unwrap().push(format!("high {input}"));
+            Ok(None)
+        });
+        factory.set_priority("high", 1);
+
+        // Queue tasks with a higher priority first, the task with a lower priority waits until
+        // all of them are taken from the queue and runs next to the last one
+        for i in 0..3 {
+            factory.queue(Task::new("high", i));
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        factory.queue(Task::new("low", 0));
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(
+            processed.lock().unwrap().as_slice(),
+            ["high 0", "high 1", "low 0", "high 2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn deduplicate_tasks() {
+        type Input = usize;
+        type Data = Arc<Mutex<Vec<Input>>>;
+
+        // Record the order in which tasks got processed
+        let processed = Arc::new(Mutex::new(Vec::new()));
+        let mut factory = Factory::<Input, Data>::new(processed.clone(), 1024);
+
+        factory.register("one", 1, |processed: Data, input: Input| async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            processed.lock().unwrap().push(input);
+            Ok(None)
+        });
+
+        factory.queue(Task::new("one", 0));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Duplicates of a task waiting in the queue are dropped, a duplicate of the task in
+        // progress requeues it once it completed
+        for input in [1, 1, 1, 0] {
+            factory.queue(Task::new("one", input));
+        }
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(processed.lock().unwrap().as_slice(), [0, 1, 0]);
+        assert!(factory.is_empty("one"));
+    }
+
+    #[tokio::test]
+    async fn record_metrics() {
+        type Input = usize;
+        type Data = usize;
+
+        let metrics = MaterializerMetrics::new();
+        let mut factory = Factory::<Input, Data>::new(1, 1024).with_metrics(metrics.clone());
+
+        factory.register("one", 1, |_, input: Input| async move {
+            if input == 0 {
+                Err(TaskError::Failure("Invalid input".into()))
+            } else {
+                Ok(None)
+            }
+        });
+
+        for i in 0..3 {
+            factory.queue(Task::new("one", i));
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let metrics = &metrics.snapshot()["one"];
+        assert_eq!(metrics.queued, 3);
+        assert_eq!(metrics.completed, 2);
+        assert_eq!(metrics.failed, 1);
+        assert_eq!(metrics.queue_depth, 0);
+        assert_eq!(metrics.duration_buckets.iter().sum::<u64>(), 3);
     }
 
     #[tokio::test]
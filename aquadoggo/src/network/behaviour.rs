@@ -1,15 +1,24 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+#[cfg(any(feature = "mdns", feature = "relay"))]
 use std::time::Duration;
 
 use anyhow::Result;
 use libp2p::allow_block_list;
 use libp2p::allow_block_list::{AllowedPeers, BlockedPeers};
 use libp2p::identity::Keypair;
+#[cfg(feature = "mdns")]
+use libp2p::mdns;
 use libp2p::swarm::behaviour::toggle::Toggle;
+#[cfg(not(all(feature = "mdns", feature = "relay")))]
+use libp2p::swarm::dummy;
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::{connection_limits, dcutr, identify, mdns, relay, rendezvous};
+use libp2p::{connection_limits, identify};
+#[cfg(feature = "relay")]
+use libp2p::{dcutr, relay, rendezvous};
 use log::debug;
+#[cfg(not(all(feature = "mdns", feature = "relay")))]
+use log::warn;
 
 use crate::network::config::NODE_NAMESPACE;
 use crate::network::peers;
@@ -17,16 +26,53 @@ use crate::network::NetworkConfiguration;
 use crate::AllowList;
 
 /// How often do we broadcast mDNS queries into the network.
+#[cfg(feature = "mdns")]
 const MDNS_QUERY_INTERVAL: Duration = Duration::from_secs(5);
 
 /// The reservation of a relayed connection becomes invalid after this time and it's the
 /// responsibility of the client to refresh.
+#[cfg(feature = "relay")]
 const RELAY_RESERVATION_DURATION: Duration = Duration::from_secs(60 * 60); // 1 hour
 
 /// A successfully established, relayed connection becomes invalid after this time and it's the
 /// responsibility of the client to refresh.
+#[cfg(feature = "relay")]
 const RELAY_MAX_CIRCUIT_DURATION: Duration = Duration::from_secs(60 * 60); // 1 hour
 
+// Behaviours of networking features which were not enabled at compile time are replaced with a
+// dummy behaviour which does nothing. This keeps the composition of the network behaviour the
+// same for all feature combinations.
+
+#[cfg(feature = "mdns")]
+type MdnsBehaviour = mdns::tokio::Behaviour;
+#[cfg(not(feature = "mdns"))]
+type MdnsBehaviour = dummy::Behaviour;
+
+#[cfg(feature = "relay")]
+type RelayClientBehaviour = relay::client::Behaviour;
+#[cfg(not(feature = "relay"))]
+type RelayClientBehaviour = dummy::Behaviour;
+
+#[cfg(feature = "relay")]
+type RelayServerBehaviour = relay::Behaviour;
+#[cfg(not(feature = "relay"))]
+type RelayServerBehaviour = dummy::Behaviour;
+
+#[cfg(feature = "relay")]
+type RendezvousClientBehaviour = rendezvous::client::Behaviour;
+#[cfg(not(feature = "relay"))]
+type RendezvousClientBehaviour = dummy::Behaviour;
+
+#[cfg(feature = "relay")]
+type RendezvousServerBehaviour = rendezvous::server::Behaviour;
+#[cfg(not(feature = "relay"))]
+type RendezvousServerBehaviour = dummy::Behaviour;
+
+#[cfg(feature = "relay")]
+type DcutrBehaviour = dcutr::Behaviour;
+#[cfg(not(feature = "relay"))]
+type DcutrBehaviour = dummy::Behaviour;
+
 /// Network behaviour for the aquadoggo node.
 ///
 /// In libp2p all different behaviours are "merged" into one "main behaviour" with help of the
@@ -50,29 +96,29 @@ pub struct P2pandaBehaviour {
     pub limits: connection_limits::Behaviour,
 
     /// Automatically discover peers on the local network via multicast DNS.
-    pub mdns: Toggle<mdns::tokio::Behaviour>,
+    pub mdns: Toggle<MdnsBehaviour>,
 
     /// Communicate with remote peers via a relay server when a direct peer-to-peer connection is
     /// not possible.
-    pub relay_client: Toggle<relay::client::Behaviour>,
+    pub relay_client: Toggle<RelayClientBehaviour>,
 
     /// Serve as a relay point for remote peers to establish connectivity when a direct
     /// peer-to-peer connection is not possible first.
-    pub relay_server: Toggle<relay::Behaviour>,
+    pub relay_server: Toggle<RelayServerBehaviour>,
 
     /// Register with a rendezvous server and query remote peer addresses.
-    pub rendezvous_client: Toggle<rendezvous::client::Behaviour>,
+    pub rendezvous_client: Toggle<RendezvousClientBehaviour>,
 
     /// Serve as a rendezvous point for remote peers to register their external addresses and query
     /// the addresses of other peers.
-    pub rendezvous_server: Toggle<rendezvous::server::Behaviour>,
+    pub rendezvous_server: Toggle<RendezvousServerBehaviour>,
 
     /// Allow two peers behind NAT to communicate directly by utilizing a technique called hole
     /// punching.
     ///
     /// The technique relies on the two peers synchronizing and simultaneously opening connections
     /// to each other to their predicted external address with help of a third-party relay server.
-    pub dcutr: Toggle<dcutr::Behaviour>,
+    pub dcutr: Toggle<DcutrBehaviour>,
 
     /// Allow connections based on an allow list of peer ids.
     pub allowed_peers: Toggle<allow_block_list::Behaviour<AllowedPeers>>,
@@ -90,8 +136,9 @@ impl P2pandaBehaviour {
     pub fn new(
         network_config: &NetworkConfiguration,
         key_pair: &Keypair,
-        relay_client: Option<relay::client::Behaviour>,
+        relay_client: Option<RelayClientBehaviour>,
     ) -> Result<Self> {
+        #[cfg(any(feature = "mdns", feature = "relay"))]
         let peer_id = key_pair.public().to_peer_id();
 
         // Create an identify server behaviour with default configuration if a rendezvous server
@@ -107,6 +154,7 @@ impl P2pandaBehaviour {
         };
 
         // Create an mDNS behaviour with default configuration if the mDNS flag is set
+        #[cfg(feature = "mdns")]
         let mdns = if network_config.mdns {
             debug!("mDNS network behaviour enabled");
             Some(mdns::Behaviour::new(
@@ -120,11 +168,20 @@ impl P2pandaBehaviour {
             None
         };
 
+        #[cfg(not(feature = "mdns"))]
+        let mdns: Option<MdnsBehaviour> = {
+            if network_config.mdns {
+                warn!("mDNS not supported by this build, enable the 'mdns' feature");
+            }
+            None
+        };
+
         // Create a limit behaviour with default configuration.
         let limits = connection_limits::Behaviour::new(network_config.connection_limits());
 
         // Create a rendezvous client behaviour with default configuration if a rendezvous server
         // address has been provided
+        #[cfg(feature = "relay")]
         let rendezvous_client = if !network_config.relay_addresses.is_empty() {
             debug!("Rendezvous client network behaviour enabled");
             Some(rendezvous::client::Behaviour::new(key_pair.clone()))
@@ -134,6 +191,7 @@ impl P2pandaBehaviour {
 
         // Create a rendezvous server behaviour with default configuration if the rendezvous server
         // flag is set
+        #[cfg(feature = "relay")]
         let rendezvous_server = if network_config.relay_mode {
             debug!("Rendezvous server network behaviour enabled");
             Some(rendezvous::server::Behaviour::new(
@@ -149,6 +207,7 @@ impl P2pandaBehaviour {
 
         // Create a relay server behaviour with default configuration if the relay server flag is
         // set
+        #[cfg(feature = "relay")]
         let relay_server = if network_config.relay_mode {
             debug!("Relay server network behaviour enabled");
             Some(relay::Behaviour::new(
@@ -164,12 +223,26 @@ impl P2pandaBehaviour {
         };
 
         // Create UDP holepunching behaviour (DCUtR) if the flag is set
+        #[cfg(feature = "relay")]
         let dcutr = if network_config.relay_mode || relay_client.is_some() {
             Some(dcutr::Behaviour::new(peer_id))
         } else {
             None
         };
 
+        #[cfg(not(feature = "relay"))]
+        let (rendezvous_client, rendezvous_server, relay_server, dcutr): (
+            Option<RendezvousClientBehaviour>,
+            Option<RendezvousServerBehaviour>,
+            Option<RelayServerBehaviour>,
+            Option<DcutrBehaviour>,
+        ) = {
+            if network_config.relay_mode || !network_config.relay_addresses.is_empty() {
+                warn!("Relays not supported by this build, enable the 'relay' feature");
+            }
+            (None, None, None, None)
+        };
+
         // Construct behaviour to manage an allow list of peers when configured
         let allowed_peers = match &network_config.allow_peer_ids {
             AllowList::Wildcard => None,
@@ -178,6 +251,11 @@ impl P2pandaBehaviour {
                 for peer_id in allow_peer_ids {
                     allowed_peers.allow_peer(*peer_id)
                 }
+
+                // Members of our federation group are always allowed to connect
+                for peer_id in &network_config.federation_peer_ids {
+                    allowed_peers.allow_peer(*peer_id)
+                }
                 Some(allowed_peers)
             }
         };
@@ -216,13 +294,19 @@ impl P2pandaBehaviour {
 #[derive(Debug)]
 pub enum Event {
     Identify(identify::Event),
+    #[cfg(feature = "mdns")]
     Mdns(mdns::Event),
+    #[cfg(feature = "relay")]
     RelayClient(relay::client::Event),
+    #[cfg(feature = "relay")]
     #[allow(dead_code)]
     RelayServer(relay::Event),
+    #[cfg(feature = "relay")]
     RendezvousClient(rendezvous::client::Event),
+    #[cfg(feature = "relay")]
     #[allow(dead_code)]
     RendezvousServer(rendezvous::server::Event),
+    #[cfg(feature = "relay")]
     Dcutr(dcutr::Event),
     Peers(peers::Event),
     Void,
@@ -234,36 +318,42 @@ impl From<identify::Event> for Event {
     }
 }
 
+#[cfg(feature = "mdns")]
 impl From<mdns::Event> for Event {
     fn from(e: mdns::Event) -> Self {
         Event::Mdns(e)
     }
 }
 
+#[cfg(feature = "relay")]
 impl From<relay::client::Event> for Event {
     fn from(e: relay::client::Event) -> Self {
         Event::RelayClient(e)
     }
 }
 
+#[cfg(feature = "relay")]
 impl From<relay::Event> for Event {
     fn from(e: relay::Event) -> Self {
         Event::RelayServer(e)
     }
 }
 
+#[cfg(feature = "relay")]
 impl From<rendezvous::client::Event> for Event {
     fn from(e: rendezvous::client::Event) -> Self {
         Event::RendezvousClient(e)
     }
 }
 
+#[cfg(feature = "relay")]
 impl From<rendezvous::server::Event> for Event {
     fn from(e: rendezvous::server::Event) -> Self {
         Event::RendezvousServer(e)
     }
 }
 
+#[cfg(feature = "relay")]
 impl From<dcutr::Event> for Event {
     fn from(e: dcutr::Event) -> Self {
         Event::Dcutr(e)
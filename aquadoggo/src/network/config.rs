@@ -1,12 +1,14 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::num::{NonZeroU8, NonZeroUsize};
 use std::str::FromStr;
 
 use anyhow::Error;
 use libp2p::connection_limits::ConnectionLimits;
 use libp2p::multiaddr::Protocol;
 use libp2p::pnet::PreSharedKey;
+use libp2p::swarm::Config as SwarmConfig;
 use libp2p::{Multiaddr, PeerId};
 use serde::{Deserialize, Deserializer, Serialize};
 
@@ -71,12 +73,28 @@ pub struct NetworkConfiguration {
     /// known number of excluded nodes.
     pub block_peer_ids: Vec<PeerId>,
 
+    /// List of trusted peers forming a federation group with your node.
+    ///
+    /// Nodes of the same federation group automatically share their schema allow-lists and the
+    /// addresses of the peers they know about with each other. Schema ids allowed by any member
+    /// get added to the allow-list of your node, this way a group of nodes can be kept in sync
+    /// without configuring every one of them by hand.
+    ///
+    /// Members are identified by their peer id, which gets authenticated during the handshake of
+    /// every connection. Federation peers are always allowed to connect to your node, even when
+    /// they are not contained in the allow list of peers.
+    pub federation_peer_ids: Vec<PeerId>,
+
     /// List of relay addresses.
     ///
     /// A relay helps discover other nodes on the internet (also known as "rendesvouz" or
     /// "bootstrap" server) and helps establishing direct p2p connections when node is behind a
     /// firewall or NAT (also known as "holepunching").
     ///
+    /// As soon as a relay accepted our circuit reservation the relayed address is advertised to
+    /// other peers, this way they can reach the node via the relay when a direct connection is not
+    /// possible.
+    ///
     /// WARNING: This will potentially expose your IP address on the network. Do only connect to
     /// trusted relays or make sure your IP address is hidden via a VPN or proxy if you're
     /// concerned about leaking your IP.
@@ -134,13 +152,14 @@ pub struct NetworkConfiguration {
 impl Default for NetworkConfiguration {
     fn default() -> Self {
         Self {
-            transport: Transport::QUIC,
+            transport: Transport::default(),
             psk: None,
             port: 2022,
             mdns: true,
             direct_node_addresses: Vec::new(),
             allow_peer_ids: AllowList::<PeerId>::Wildcard,
             block_peer_ids: Vec::new(),
+            federation_peer_ids: Vec::new(),
             relay_addresses: Vec::new(),
             relay_mode: false,
             notify_handler_buffer_size: 128,
@@ -165,6 +184,21 @@ impl NetworkConfiguration {
             .with_max_established_incoming(Some(self.max_connections_in))
             .with_max_established_per_peer(Some(self.max_connections_per_peer))
     }
+
+    /// Apply dial concurrency and buffer sizes to the given swarm configuration.
+    pub fn swarm_config(&self, config: SwarmConfig) -> SwarmConfig {
+        let config = match NonZeroU8::new(self.dial_concurrency_factor) {
+            Some(factor) => config.with_dial_concurrency_factor(factor),
+            None => config,
+        };
+
+        let config = match NonZeroUsize::new(self.notify_handler_buffer_size) {
+            Some(size) => config.with_notify_handler_buffer_size(size),
+            None => config,
+        };
+
+        config.with_per_connection_event_buffer_size(self.per_connection_event_buffer_size)
+    }
 }
 
 /// Helper struct for handling ambiguous string addresses which may need resolving via
@@ -251,11 +285,12 @@ impl std::fmt::Display for PeerAddress {
 /// Enum representing transport protocol types.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
 pub enum Transport {
-    #[default]
-    /// UDP/QUIC transport protocol
+    /// UDP/QUIC transport protocol, default when built with the `quic` feature
+    #[cfg_attr(feature = "quic", default)]
     QUIC,
 
     /// TCP transport protocol
+    #[cfg_attr(not(feature = "quic"), default)]
     TCP,
 }
 
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use libp2p::{Multiaddr, PeerId};
+use serde::ser::SerializeSeq;
+use serde::Serialize;
+
+use crate::replication::{MessageType, SchemaIdSet};
+
+/// Integer indicating federation messages in the wire message format.
+pub const FEDERATION_TYPE: MessageType = 20;
+
+/// Message exchanged between members of a federation group.
+///
+/// Members send this message to each other whenever they connect, sharing their schema allow-list
+/// and the addresses of the peers they know about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FederationMessage {
+    /// Schema ids allowed by the sending node, empty if it accepts all schema ids.
+    pub allow_schema_ids: SchemaIdSet,
+
+    /// Peers known by the sending node and the addresses under which they can be reached.
+    pub peers: Vec<(PeerId, Vec<Multiaddr>)>,
+}
+
+impl FederationMessage {
+    /// Returns a new federation message.
+    pub fn new(allow_schema_ids: SchemaIdSet, peers: Vec<(PeerId, Vec<Multiaddr>)>) -> Self {
+        Self {
+            allow_schema_ids,
+            peers,
+        }
+    }
+}
+
+impl Serialize for FederationMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let peers: Vec<(String, Vec<String>)> = self
+            .peers
+            .iter()
+            .map(|(peer_id, addresses)| {
+                (
+                    peer_id.to_string(),
+                    addresses
+                        .iter()
+                        .map(|address| address.to_string())
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let mut seq = serializer.serialize_seq(Some(3))?;
+        seq.serialize_element(&FEDERATION_TYPE)?;
+        seq.serialize_element(&self.allow_schema_ids)?;
+        seq.serialize_element(&peers)?;
+        seq.end()
+    }
+}
@@ -2,8 +2,10 @@
 
 mod behaviour;
 mod config;
+mod federation;
 pub mod identity;
 mod peers;
+#[cfg(feature = "relay")]
 mod relay;
 mod service;
 mod shutdown;
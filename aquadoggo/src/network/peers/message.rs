@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use libp2p::{Multiaddr, PeerId};
 use p2panda_rs::entry::{EncodedEntry, LogId, SeqNum};
 use p2panda_rs::identity::PublicKey;
 use p2panda_rs::operation::EncodedOperation;
@@ -7,9 +8,11 @@ use p2panda_rs::Validate;
 use serde::de::Visitor;
 use serde::{Deserialize, Serialize};
 
+use crate::network::federation::{FederationMessage, FEDERATION_TYPE};
 use crate::replication::{
-    Announcement, AnnouncementMessage, Message, Mode, SchemaIdSet, SessionId, SyncMessage,
-    ANNOUNCE_TYPE, ENTRY_TYPE, HAVE_TYPE, SYNC_DONE_TYPE, SYNC_REQUEST_TYPE,
+    Announcement, AnnouncementMessage, Compression, Message, Mode, SchemaIdSet, SessionId,
+    SyncMessage, ANNOUNCE_TYPE, COMPRESSED_ENTRIES_TYPE, ENTRY_TYPE, HAVE_TYPE, SYNC_DONE_TYPE,
+    SYNC_REQUEST_TYPE,
 };
 
 /// p2panda protocol messages which can be sent over the wire.
@@ -21,6 +24,9 @@ pub enum PeerMessage {
 
     /// Replication status and data exchange.
     SyncMessage(SyncMessage),
+
+    /// Allow-lists and known peers shared between members of a federation group.
+    Federation(FederationMessage),
 }
 
 impl<'de> Deserialize<'de> for PeerMessage {
@@ -63,11 +69,28 @@ impl<'de> Deserialize<'de> for PeerMessage {
                             serde::de::Error::custom("invalid target set in announce message")
                         })?;
 
+                        // Compression algorithms are optional, ignore the ones we don't know
+                        let supported_compressions: Vec<Compression> =
+                            seq.next_element()?.unwrap_or_default();
+                        let supported_compressions = supported_compressions
+                            .into_iter()
+                            .filter(|compression| compression != &Compression::Unknown)
+                            .collect();
+
+                        // Replication modes are optional as well, ignore the ones we don't know
+                        let supported_modes: Vec<Mode> = seq.next_element()?.unwrap_or_default();
+                        let supported_modes = supported_modes
+                            .into_iter()
+                            .filter(|mode| mode != &Mode::Unknown)
+                            .collect();
+
                         PeerMessage::Announce(AnnouncementMessage(
                             protocol_version,
                             Announcement {
                                 supported_schema_ids,
                                 timestamp,
+                                supported_compressions,
+                                supported_modes,
                             },
                         ))
                     }
@@ -144,6 +167,62 @@ impl<'de> Deserialize<'de> for PeerMessage {
                             Message::Have(log_heights),
                         ))
                     }
+                    COMPRESSED_ENTRIES_TYPE => {
+                        let session_id: SessionId = seq.next_element()?.ok_or_else(|| {
+                            serde::de::Error::custom("missing session id in replication message")
+                        })?;
+
+                        let compression: Compression = seq.next_element()?.ok_or_else(|| {
+                            serde::de::Error::custom(
+                                "missing compression in compressed entries message",
+                            )
+                        })?;
+
+                        let bytes: serde_bytes::ByteBuf = seq.next_element()?.ok_or_else(|| {
+                            serde::de::Error::custom("missing bytes in compressed entries message")
+                        })?;
+
+                        PeerMessage::SyncMessage(SyncMessage::new(
+                            session_id,
+                            Message::CompressedEntries(compression, bytes.into_vec()),
+                        ))
+                    }
+                    FEDERATION_TYPE => {
+                        let allow_schema_ids: SchemaIdSet =
+                            seq.next_element()?.ok_or_else(|| {
+                                serde::de::Error::custom("missing allow-list in federation message")
+                            })?;
+                        allow_schema_ids.validate().map_err(|_| {
+                            serde::de::Error::custom("invalid allow-list in federation message")
+                        })?;
+
+                        let peers: Vec<(String, Vec<String>)> =
+                            seq.next_element()?.ok_or_else(|| {
+                                serde::de::Error::custom("missing peers in federation message")
+                            })?;
+                        let peers = peers
+                            .into_iter()
+                            .map(|(peer_id, addresses)| {
+                                let peer_id: PeerId = peer_id.parse().map_err(|_| {
+                                    serde::de::Error::custom(
+                                        "invalid peer id in federation message",
+                                    )
+                                })?;
+                                let addresses = addresses
+                                    .iter()
+                                    .map(|address| address.parse::<Multiaddr>())
+                                    .collect::<Result<Vec<Multiaddr>, _>>()
+                                    .map_err(|_| {
+                                        serde::de::Error::custom(
+                                            "invalid address in federation message",
+                                        )
+                                    })?;
+                                Ok((peer_id, addresses))
+                            })
+                            .collect::<Result<Vec<(PeerId, Vec<Multiaddr>)>, A::Error>>()?;
+
+                        PeerMessage::Federation(FederationMessage::new(allow_schema_ids, peers))
+                    }
                     _ => return Err(serde::de::Error::custom("unknown message type")),
                 };
 
@@ -167,14 +246,16 @@ impl<'de> Deserialize<'de> for PeerMessage {
 mod tests {
     use ciborium::cbor;
     use ciborium::value::{Error, Value};
+    use libp2p::{Multiaddr, PeerId};
     use p2panda_rs::entry::{LogId, SeqNum};
     use p2panda_rs::identity::PublicKey;
-    use p2panda_rs::serde::{deserialize_into, serialize_value};
+    use p2panda_rs::serde::{deserialize_into, serialize_from, serialize_value};
     use p2panda_rs::test_utils::fixtures::public_key;
     use rstest::rstest;
 
+    use crate::network::federation::FederationMessage;
     use crate::replication::{
-        Announcement, AnnouncementMessage, Message, Mode, SchemaIdSet, SyncMessage,
+        Announcement, AnnouncementMessage, Compression, Message, Mode, SchemaIdSet, SyncMessage,
     };
     use crate::test_utils::helpers::random_schema_id_set;
 
@@ -196,10 +277,63 @@ mod tests {
             .unwrap(),
             PeerMessage::Announce(AnnouncementMessage::new(Announcement {
                 timestamp: 12345678,
+                supported_schema_ids: supported_schema_ids.clone(),
+                supported_compressions: vec![],
+                supported_modes: vec![],
+            }))
+        );
+
+        // Unknown compression algorithms are ignored
+        assert_eq!(
+            deserialize_into::<PeerMessage>(&serialize_value(cbor!([
+                0,
+                1,
+                12345678,
+                supported_schema_ids.clone(),
+                [0, 99]
+            ])))
+            .unwrap(),
+            PeerMessage::Announce(AnnouncementMessage::new(Announcement {
+                timestamp: 12345678,
+                supported_schema_ids: supported_schema_ids.clone(),
+                supported_compressions: vec![Compression::Deflate],
+                supported_modes: vec![],
+            }))
+        );
+
+        // Unknown replication modes are ignored
+        assert_eq!(
+            deserialize_into::<PeerMessage>(&serialize_value(cbor!([
+                0,
+                1,
+                12345678,
                 supported_schema_ids,
+                [],
+                [2, 99]
+            ])))
+            .unwrap(),
+            PeerMessage::Announce(AnnouncementMessage::new(Announcement {
+                timestamp: 12345678,
+                supported_schema_ids,
+                supported_compressions: vec![],
+                supported_modes: vec![Mode::Shallow],
             }))
         );
 
+        assert_eq!(
+            deserialize_into::<PeerMessage>(&serialize_value(cbor!([
+                4,
+                12,
+                0,
+                serde_bytes::Bytes::new(&[1, 2, 3])
+            ])))
+            .unwrap(),
+            PeerMessage::SyncMessage(SyncMessage::new(
+                12,
+                Message::CompressedEntries(Compression::Deflate, vec![1, 2, 3])
+            ))
+        );
+
         assert_eq!(
             deserialize_into::<PeerMessage>(&serialize_value(cbor!([1, 12, 0, target_set])))
                 .unwrap(),
@@ -238,6 +372,29 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn federation_message(#[from(random_schema_id_set)] allow_schema_ids: SchemaIdSet) {
+        let peer_id = PeerId::random();
+        let address: Multiaddr = "/ip4/192.0.2.16/udp/2022/quic-v1".parse().unwrap();
+        let message = PeerMessage::Federation(FederationMessage::new(
+            allow_schema_ids.clone(),
+            vec![(peer_id, vec![address.clone()])],
+        ));
+
+        assert_eq!(
+            serialize_from(message.clone()),
+            serialize_value(cbor!([
+                20,
+                allow_schema_ids,
+                [[peer_id.to_string(), [address.to_string()]]]
+            ]))
+        );
+        assert_eq!(
+            deserialize_into::<PeerMessage>(&serialize_from(message.clone())).unwrap(),
+            message
+        );
+    }
+
     #[rstest]
     #[should_panic(expected = "invalid message type")]
     #[case::invalid_message_type(cbor!([]))]
@@ -246,13 +403,17 @@ mod tests {
     #[should_panic(expected = "missing timestamp in announce message")]
     #[case::announce_missing_timestamp(cbor!([0, 122]))]
     #[should_panic(expected = "too many fields for p2panda message")]
-    #[case::announce_too_many_fields(cbor!([0, 1, 0, ["schema_field_definition_v1"], "too much"]))]
+    #[case::announce_too_many_fields(cbor!([0, 1, 0, ["schema_field_definition_v1"], [], [], "too much"]))]
     #[should_panic(expected = "missing session id in replication message")]
     #[case::sync_only_message_type(cbor!([1]))]
     #[should_panic(expected = "empty target set in sync request")]
     #[case::sync_only_message_type(cbor!([1, 0, 0, []]))]
     #[should_panic(expected = "too many fields for p2panda message")]
     #[case::sync_too_many_fields(cbor!([1, 0, 0, ["schema_field_definition_v1"], "too much"]))]
+    #[should_panic(expected = "missing peers in federation message")]
+    #[case::federation_missing_peers(cbor!([20, []]))]
+    #[should_panic(expected = "invalid peer id in federation message")]
+    #[case::federation_invalid_peer_id(cbor!([20, [], [["panda", []]]]))]
     fn deserialize_invalid_messages(#[case] cbor: Result<Value, Error>) {
         // Check the cbor is valid
         assert!(cbor.is_ok());
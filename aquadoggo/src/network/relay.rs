@@ -31,6 +31,9 @@ pub struct Relay {
 
     /// Was our relay circuit reservation accepted.
     pub(crate) reservation_accepted: bool,
+
+    /// Are we advertising the relayed address to other peers.
+    pub(crate) advertised: bool,
 }
 
 impl Relay {
@@ -44,6 +47,7 @@ impl Relay {
             registering: false,
             registered: false,
             reservation_accepted: false,
+            advertised: false,
         }
     }
 
@@ -84,6 +88,51 @@ impl Relay {
         Ok(true)
     }
 
+    /// Advertise the relayed address we are reachable at via this relay.
+    ///
+    /// Other peers learn about this address via the identify protocol and when discovering us in
+    /// the rendezvous namespace of the relay, this way we can be reached even when we're behind a
+    /// NAT.
+    pub fn advertise(
+        &mut self,
+        swarm: &mut Swarm<P2pandaBehaviour>,
+    ) -> Result<bool, anyhow::Error> {
+        if !self.reservation_accepted || self.advertised {
+            return Ok(false);
+        }
+
+        swarm.add_external_address(self.circuit_addr());
+        self.advertised = true;
+
+        // Registrations only contain the addresses we knew about when registering, refresh it to
+        // include the relayed address as well
+        if self.registered {
+            swarm
+                .behaviour_mut()
+                .rendezvous_client
+                .as_mut()
+                .expect("Relay client behaviour exists")
+                .register(
+                    rendezvous::Namespace::from_static(NODE_NAMESPACE),
+                    self.peer_id,
+                    None, // Default ttl is 7200s
+                )?;
+        }
+
+        Ok(true)
+    }
+
+    /// Stop advertising the relayed address, for example after the connection to the relay
+    /// closed.
+    pub fn withdraw(&mut self, swarm: &mut Swarm<P2pandaBehaviour>) {
+        if self.advertised {
+            swarm.remove_external_address(&self.circuit_addr());
+            self.advertised = false;
+        }
+
+        self.reservation_accepted = false;
+    }
+
     /// Start discovering peers also registered at the same namespace.
     pub fn discover(&mut self, swarm: &mut Swarm<P2pandaBehaviour>) -> bool {
         if self.reservation_accepted && self.registered && !self.discovering {
@@ -2,16 +2,23 @@
 
 use std::collections::HashMap;
 use std::net::Ipv4Addr;
+#[cfg(any(feature = "mdns", feature = "relay"))]
 use std::num::NonZeroU8;
 use std::time::Duration;
 
 use anyhow::Result;
+#[cfg(feature = "mdns")]
+use libp2p::mdns;
 use libp2p::multiaddr::Protocol;
+#[cfg(feature = "relay")]
 use libp2p::rendezvous::Registration;
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::SwarmEvent;
-use libp2p::{dcutr, identify, mdns, relay, rendezvous, Multiaddr, PeerId, Swarm};
+#[cfg(feature = "relay")]
+use libp2p::{dcutr, relay, rendezvous};
+use libp2p::{identify, Multiaddr, PeerId, Swarm};
 use log::{debug, info, trace, warn};
+use p2panda_rs::schema::SchemaId;
 use tokio::task;
 use tokio::time::interval;
 use tokio_stream::wrappers::{BroadcastStream, IntervalStream};
@@ -22,10 +29,15 @@ use crate::context::Context;
 use crate::manager::{ServiceReadySender, Shutdown};
 use crate::network::behaviour::{Event, P2pandaBehaviour};
 use crate::network::config::Transport;
+use crate::network::federation::FederationMessage;
+#[cfg(feature = "relay")]
 use crate::network::relay::Relay;
-use crate::network::swarm::{build_quic_swarm, build_tcp_swarm};
+#[cfg(feature = "quic")]
+use crate::network::swarm::build_quic_swarm;
+use crate::network::swarm::build_tcp_swarm;
 use crate::network::utils::{dial_known_peer, is_known_peer_address};
-use crate::network::{identity, peers, utils, ShutdownHandler};
+use crate::network::{identity, peers, utils, Peer, PeerMessage, ShutdownHandler};
+use crate::replication::SchemaIdSet;
 use crate::{info_or_print, NetworkConfiguration};
 
 /// Interval at which we attempt to dial known peers and relays.
@@ -38,6 +50,7 @@ const REDIAL_INTERVAL: Duration = Duration::from_secs(20);
 /// - Discovering and connecting to other nodes via a known relay node
 /// - Upgrade relayed connections to direct connections (NAT traversal)
 /// - Routing replication messages to connected nodes
+/// - Sharing schema allow-lists and known peers with members of the federation group
 ///
 /// Can perform in "relay" mode, which means in addition to the usual node networking behaviours
 /// this node will also be able to act as a relay for other nodes with restricted connectivity.
@@ -58,8 +71,17 @@ pub async fn network_service(
         network_config.transport = Transport::TCP;
     }
 
+    #[cfg(not(feature = "quic"))]
+    if network_config.transport == Transport::QUIC {
+        warn!("QUIC transport protocol not supported by this build, switching to TCP");
+        network_config.transport = Transport::TCP;
+    }
+
     let mut swarm = match network_config.transport {
+        #[cfg(feature = "quic")]
         Transport::QUIC => build_quic_swarm(&network_config, key_pair),
+        #[cfg(not(feature = "quic"))]
+        Transport::QUIC => unreachable!("Transport was switched to TCP"),
         Transport::TCP => build_tcp_swarm(&network_config, key_pair),
     }?;
 
@@ -108,6 +130,7 @@ pub async fn network_service(
 
     // Spawn main event loop handling all p2panda and libp2p network events.
     spawn_event_loop(
+        context.clone(),
         swarm,
         network_config.to_owned(),
         local_peer_id,
@@ -120,6 +143,9 @@ pub async fn network_service(
 
 /// Main loop polling the async swarm event stream and incoming service messages stream.
 struct EventLoop {
+    /// Node context giving access to the schema provider and store.
+    context: Context,
+
     /// libp2p swarm.
     swarm: Swarm<P2pandaBehaviour>,
 
@@ -133,8 +159,12 @@ struct EventLoop {
     known_peers: HashMap<Multiaddr, PeerId>,
 
     /// Relays for which we have discovered a PeerId via the identify behaviour.
+    #[cfg(feature = "relay")]
     relays: HashMap<PeerId, Relay>,
 
+    /// Currently connected members of our federation group.
+    federation_peers: HashMap<PeerId, Peer>,
+
     /// Scheduler which triggers known peer redial attempts.
     redial_scheduler: IntervalStream,
 
@@ -156,6 +186,7 @@ struct EventLoop {
 
 impl EventLoop {
     pub fn new(
+        context: Context,
         swarm: Swarm<P2pandaBehaviour>,
         network_config: NetworkConfiguration,
         local_peer_id: PeerId,
@@ -163,6 +194,7 @@ impl EventLoop {
         shutdown_handler: ShutdownHandler,
     ) -> Self {
         Self {
+            context,
             swarm,
             network_config,
             redial_scheduler: IntervalStream::new(interval(REDIAL_INTERVAL)),
@@ -170,7 +202,9 @@ impl EventLoop {
             rx: BroadcastStream::new(tx.subscribe()),
             tx,
             known_peers: HashMap::new(),
+            #[cfg(feature = "relay")]
             relays: HashMap::new(),
+            federation_peers: HashMap::new(),
             shutdown_handler,
             learned_port: false,
             learned_observed_addr: false,
@@ -223,10 +257,14 @@ impl EventLoop {
                             }
                         }
                         SwarmEvent::Behaviour(Event::Identify(event)) => self.handle_identify_events(&event).await,
+                        #[cfg(feature = "mdns")]
                         SwarmEvent::Behaviour(Event::Mdns(event)) => self.handle_mdns_events(&event).await,
+                        #[cfg(feature = "relay")]
                         SwarmEvent::Behaviour(Event::RendezvousClient(event)) => self.handle_rendezvous_client_events(&event).await,
                         SwarmEvent::Behaviour(Event::Peers(event)) => self.handle_peers_events(&event).await,
+                        #[cfg(feature = "relay")]
                         SwarmEvent::Behaviour(Event::RelayClient(event)) => self.handle_relay_client_events(&event).await,
+                        #[cfg(feature = "relay")]
                         SwarmEvent::Behaviour(Event::Dcutr(event)) => self.handle_dcutr_events(&event).await,
                         event => self.handle_swarm_events(event).await,
 
@@ -257,6 +295,7 @@ impl EventLoop {
     /// if we are currently not connected to the target peer.
     async fn attempt_dial_known_addresses(&mut self) {
         // Attempt to dial all relay addresses.
+        #[cfg(feature = "relay")]
         for relay_address in self.network_config.relay_addresses.iter_mut() {
             dial_known_peer(
                 &mut self.swarm,
@@ -303,13 +342,25 @@ impl EventLoop {
     async fn handle_peers_events(&mut self, event: &peers::Event) {
         match event {
             peers::Event::PeerConnected(peer) => {
+                if self.network_config.federation_peer_ids.contains(&peer.id()) {
+                    self.federation_peers.insert(peer.id(), *peer);
+                    self.send_federation_message(*peer);
+                }
+
                 // Inform other services about new peer
                 self.send_service_message(ServiceMessage::PeerConnected(*peer));
             }
             peers::Event::PeerDisconnected(peer) => {
+                if self.federation_peers.get(&peer.id()) == Some(peer) {
+                    self.federation_peers.remove(&peer.id());
+                }
+
                 // Inform other services about peer leaving
                 self.send_service_message(ServiceMessage::PeerDisconnected(*peer));
             }
+            peers::Event::MessageReceived(peer, PeerMessage::Federation(message)) => {
+                self.handle_federation_message(*peer, message).await;
+            }
             peers::Event::MessageReceived(peer, message) => {
                 // Inform other services about received messages from peer
                 self.send_service_message(ServiceMessage::ReceivedMessage(*peer, message.clone()))
@@ -317,6 +368,97 @@ impl EventLoop {
         }
     }
 
+    /// Share our schema allow-list and known peers with a member of our federation group.
+    fn send_federation_message(&mut self, peer: Peer) {
+        let allow_schema_ids = SchemaIdSet::new(&self.context.schema_provider.allow_list());
+
+        let mut peers: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+        for (address, peer_id) in &self.known_peers {
+            if peer_id != &peer.id() {
+                peers.entry(*peer_id).or_default().push(address.clone());
+            }
+        }
+
+        debug!("Send federation message to peer {}", peer.id());
+        self.swarm.behaviour_mut().peers.send_message(
+            peer,
+            PeerMessage::Federation(FederationMessage::new(
+                allow_schema_ids,
+                peers.into_iter().collect(),
+            )),
+        );
+    }
+
+    /// Extend our schema allow-list and connect to peers shared by a member of our federation
+    /// group.
+    ///
+    /// The identity of the sending peer was authenticated during the handshake of the connection,
+    /// messages from peers which are not part of our federation group are ignored.
+    async fn handle_federation_message(&mut self, peer: Peer, message: &FederationMessage) {
+        if !self.network_config.federation_peer_ids.contains(&peer.id()) {
+            debug!(
+                "Ignore federation message from peer {} outside of our federation group",
+                peer.id()
+            );
+            return;
+        }
+
+        let schema_ids: Vec<SchemaId> = message.allow_schema_ids.iter().cloned().collect();
+        let added_schema_ids = self.context.schema_provider.extend_allow_list(&schema_ids);
+
+        for schema_id in &added_schema_ids {
+            // Schemas might have been materialized already before they got allowed, make them
+            // available right away
+            if let SchemaId::Application(_, view_id) = schema_id {
+                match self.context.store.get_schema_by_id(view_id).await {
+                    Ok(Some(schema)) => {
+                        if let Err(err) = self.context.schema_provider.update(schema).await {
+                            warn!("Failed adding federated schema {}: {}", schema_id, err);
+                        }
+                    }
+                    Ok(None) => (),
+                    Err(err) => warn!("Failed loading federated schema {}: {}", schema_id, err),
+                }
+            }
+        }
+
+        // Pass the extended allow-list on to the other members of our federation group
+        if !added_schema_ids.is_empty() {
+            let members: Vec<Peer> = self
+                .federation_peers
+                .values()
+                .filter(|member| member.id() != peer.id())
+                .copied()
+                .collect();
+            for member in members {
+                self.send_federation_message(member);
+            }
+        }
+
+        for (peer_id, addresses) in &message.peers {
+            if peer_id == &self.local_peer_id
+                || addresses.is_empty()
+                || self.swarm.is_connected(peer_id)
+            {
+                continue;
+            }
+
+            // Peers shared within the federation group are trusted by its members
+            if let Some(allowed_peers) = self.swarm.behaviour_mut().allowed_peers.as_mut() {
+                allowed_peers.allow_peer(*peer_id);
+            }
+
+            debug!("Dial peer {} shared by federation group", peer_id);
+            let opts = DialOpts::peer_id(*peer_id)
+                .addresses(addresses.clone())
+                .build();
+            if let Err(err) = self.swarm.dial(opts) {
+                debug!("Error dialing peer {}: {}", peer_id, err);
+            }
+        }
+    }
+
+    #[cfg(feature = "relay")]
     async fn handle_rendezvous_client_events(&mut self, event: &rendezvous::client::Event) {
         match event {
             rendezvous::client::Event::Discovered {
@@ -383,6 +525,7 @@ impl EventLoop {
 
     async fn handle_identify_events(&mut self, event: &identify::Event) {
         match event {
+            #[cfg_attr(not(feature = "relay"), allow(unused_variables))]
             identify::Event::Received {
                 info: identify::Info { observed_addr, .. },
                 peer_id,
@@ -408,6 +551,7 @@ impl EventLoop {
                 // to avoid multiple connections being established to the same peer.
 
                 // Check if the identified peer is one of our configured relay addresses.
+                #[cfg(feature = "relay")]
                 if let Some(relay) = self.relays.get_mut(peer_id) {
                     if !self.learned_observed_addr || !relay.told_addr {
                         return;
@@ -424,6 +568,7 @@ impl EventLoop {
                     };
                 }
             }
+            #[cfg(feature = "relay")]
             identify::Event::Sent { peer_id } => {
                 if let Some(relay) = self.relays.get_mut(peer_id) {
                     if !relay.told_addr {
@@ -451,12 +596,19 @@ impl EventLoop {
         }
     }
 
+    #[cfg(feature = "mdns")]
     async fn handle_mdns_events(&mut self, event: &mdns::Event) {
         match event {
             mdns::Event::Discovered(list) => {
                 for (peer_id, _) in list {
                     debug!("mDNS discovered a new peer: {peer_id}");
 
+                    // mDNS reports every address of a peer separately, do not dial it again when
+                    // we're already connected.
+                    if self.swarm.is_connected(peer_id) {
+                        continue;
+                    }
+
                     // Dial discovered peer.
                     let dial_opts = DialOpts::peer_id(*peer_id)
                         .override_dial_concurrency_factor(NonZeroU8::new(1).expect("Is nonzero u8"))
@@ -472,6 +624,7 @@ impl EventLoop {
         }
     }
 
+    #[cfg(feature = "relay")]
     async fn handle_relay_client_events(&mut self, event: &relay::client::Event) {
         match event {
             relay::client::Event::ReservationReqAccepted { relay_peer_id, .. } => {
@@ -479,6 +632,14 @@ impl EventLoop {
 
                 if let Some(relay) = self.relays.get_mut(relay_peer_id) {
                     relay.reservation_accepted = true;
+
+                    // Let other peers know that we can be reached via this relay.
+                    match relay.advertise(&mut self.swarm) {
+                        Ok(true) => info!("Advertising relayed address {}", relay.circuit_addr()),
+                        Ok(false) => (),
+                        Err(e) => debug!("Error advertising relayed address: {}", e),
+                    }
+
                     // Attempt to start discovering peers at the configured namespace.
                     if relay.discover(&mut self.swarm) {
                         info!(
@@ -492,6 +653,7 @@ impl EventLoop {
         }
     }
 
+    #[cfg(feature = "relay")]
     async fn handle_dcutr_events(&mut self, event: &dcutr::Event) {
         match &event.result {
             Ok(connection_id) => {
@@ -519,6 +681,7 @@ impl EventLoop {
                 );
 
                 // Check if the connected peer is one of our relay addresses.
+                #[cfg(feature = "relay")]
                 if let Some(addr) = is_known_peer_address(
                     &mut self.network_config.relay_addresses,
                     &[endpoint.get_remote_address().to_owned()],
@@ -545,12 +708,13 @@ impl EventLoop {
                     self.known_peers.insert(addr, peer_id);
                 }
             }
+            #[cfg_attr(not(feature = "relay"), allow(unused_variables))]
             SwarmEvent::ConnectionClosed {
                 peer_id,
                 connection_id,
                 endpoint,
+                num_established,
                 cause,
-                ..
             } => {
                 debug!(
                     "Connection closed with peer {}({}) at {}: {}",
@@ -564,6 +728,14 @@ impl EventLoop {
 
                 // Remove this peer address from our known peers.
                 self.known_peers.remove(endpoint.get_remote_address());
+
+                // We can't be reached via a relay anymore when we lost all connections to it.
+                #[cfg(feature = "relay")]
+                if num_established == 0 {
+                    if let Some(relay) = self.relays.get_mut(&peer_id) {
+                        relay.withdraw(&mut self.swarm);
+                    }
+                }
             }
             event => trace!("{event:?}"),
         }
@@ -571,6 +743,7 @@ impl EventLoop {
 }
 
 pub async fn spawn_event_loop(
+    context: Context,
     swarm: Swarm<P2pandaBehaviour>,
     network_config: NetworkConfiguration,
     local_peer_id: PeerId,
@@ -582,6 +755,7 @@ pub async fn spawn_event_loop(
 
     // Spawn a task to run swarm in event loop
     let event_loop = EventLoop::new(
+        context,
         swarm,
         network_config,
         local_peer_id,
@@ -34,24 +34,34 @@ pub fn build_tcp_swarm(
                 .multiplex(yamux_config)
         })?;
 
+    #[cfg(feature = "relay")]
     let swarm = if !network_config.relay_mode && !network_config.relay_addresses.is_empty() {
         swarm
             .with_relay_client(noise::Config::new, yamux::Config::default)?
             .with_behaviour(|key_pair, relay_client| {
                 P2pandaBehaviour::new(network_config, key_pair, Some(relay_client)).unwrap()
             })?
+            .with_swarm_config(|config| network_config.swarm_config(config))
             .build()
     } else {
         swarm
             .with_behaviour(|key_pair| {
                 P2pandaBehaviour::new(network_config, key_pair, None).unwrap()
             })?
+            .with_swarm_config(|config| network_config.swarm_config(config))
             .build()
     };
 
+    #[cfg(not(feature = "relay"))]
+    let swarm = swarm
+        .with_behaviour(|key_pair| P2pandaBehaviour::new(network_config, key_pair, None).unwrap())?
+        .with_swarm_config(|config| network_config.swarm_config(config))
+        .build();
+
     Ok(swarm)
 }
 
+#[cfg(feature = "quic")]
 pub fn build_quic_swarm(
     network_config: &NetworkConfiguration,
     key_pair: Keypair,
@@ -60,20 +70,29 @@ pub fn build_quic_swarm(
         .with_tokio()
         .with_quic();
 
+    #[cfg(feature = "relay")]
     let swarm = if !network_config.relay_mode && !network_config.relay_addresses.is_empty() {
         swarm
             .with_relay_client(noise::Config::new, yamux::Config::default)?
             .with_behaviour(|key_pair, relay_client| {
                 P2pandaBehaviour::new(network_config, key_pair, Some(relay_client)).unwrap()
             })?
+            .with_swarm_config(|config| network_config.swarm_config(config))
             .build()
     } else {
         swarm
             .with_behaviour(|key_pair| {
                 P2pandaBehaviour::new(network_config, key_pair, None).unwrap()
             })?
+            .with_swarm_config(|config| network_config.swarm_config(config))
             .build()
     };
 
+    #[cfg(not(feature = "relay"))]
+    let swarm = swarm
+        .with_behaviour(|key_pair| P2pandaBehaviour::new(network_config, key_pair, None).unwrap())?
+        .with_swarm_config(|config| network_config.swarm_config(config))
+        .build();
+
     Ok(swarm)
 }
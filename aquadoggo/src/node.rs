@@ -1,21 +1,34 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::collections::BTreeMap;
+
 use anyhow::Result;
+use log::warn;
 use p2panda_rs::identity::KeyPair;
 use tokio::sync::mpsc::Receiver;
 
+#[cfg(unix)]
+use crate::admin::admin_service;
 use crate::api::{NodeEvent, NodeInterface};
+use crate::blobs::BlobCacheMetrics;
 use crate::bus::ServiceMessage;
 use crate::config::Configuration;
 use crate::context::Context;
+use crate::db::journal::Journal;
 use crate::db::SqlStore;
-use crate::db::{connection_pool, create_database, run_pending_migrations, Pool};
+use crate::db::{
+    connection_pool, create_database, run_pending_migrations, statement_timeout_connections, Pool,
+};
+use crate::document_hooks::DocumentHook;
+use crate::errors::Error;
 use crate::http::http_service;
+use crate::idle::idle_service;
 use crate::manager::ServiceManager;
-use crate::materializer::materializer_service;
+use crate::materializer::{materializer_service, TaskMetrics};
 use crate::network::network_service;
 use crate::replication::replication_service;
 use crate::schema::SchemaProvider;
+use crate::status::status_service;
 use crate::LockFile;
 
 /// Capacity of the internal broadcast channel used to communicate between services.
@@ -29,8 +42,14 @@ async fn initialize_db(config: &Configuration) -> Result<Pool> {
     // Create database when not existing
     create_database(&config.database_url).await?;
 
-    // Create connection pool
-    let pool = connection_pool(&config.database_url, config.database_max_connections).await?;
+    // Create connection pool, leaving connections for the pool of GraphQL statements
+    let max_connections = config.database_max_connections
+        - statement_timeout_connections(
+            &config.database_url,
+            config.database_max_connections,
+            config.query_timeout,
+        );
+    let pool = connection_pool(&config.database_url, max_connections, None).await?;
 
     // Run pending migrations
     run_pending_migrations(&pool).await?;
@@ -49,23 +68,52 @@ pub struct Node {
 impl Node {
     /// Start p2panda node with your configuration. This method can be used to run the node within
     /// other applications.
-    pub async fn start(key_pair: KeyPair, config: Configuration) -> Self {
+    ///
+    /// Returns an error if the database could not be prepared or one of the services failed to
+    /// start.
+    pub async fn start(key_pair: KeyPair, config: Configuration) -> Result<Self, Error> {
         // Initialize database and get connection pool
         let pool = initialize_db(&config)
             .await
-            .expect("Could not initialize database");
+            .map_err(|err| Error::storage("Could not initialize database", err))?;
 
         // Prepare storage and schema providers using connection pool
-        let store = SqlStore::new(pool.clone());
+        let store = SqlStore::new(pool.clone())
+            .with_journal(Journal::new(config.journal_path.clone()))
+            .with_blob_limits(config.blob_limits.clone())
+            .with_blob_cache(config.blob_cache_size_bytes);
 
         // Initiate the SchemaProvider with all currently known schema from the store.
         //
         // If a list of allowed schema ids is provided then only schema identified in this list
         // will be added to the provider and supported by the node.
-        let application_schema = store.get_all_schema().await.unwrap();
+        let application_schema = store
+            .get_all_schema()
+            .await
+            .map_err(|err| Error::storage("Could not load schemas from database", err))?;
         let schema_provider =
             SchemaProvider::new(application_schema, config.allow_schema_ids.clone());
 
+        // Remove entries which were left without their operation when the node stopped while
+        // publishing, they get published again when replaying the journal
+        let removed_entries = store
+            .remove_entries_without_operation()
+            .await
+            .map_err(|err| Error::storage("Could not remove entries without operation", err))?;
+        if !removed_entries.is_empty() {
+            warn!(
+                "Removed {} entries without operation from database",
+                removed_entries.len()
+            );
+        }
+
+        // Insert entries which got lost in the database after a power failure. The materializer
+        // picks up their operations when it starts
+        store
+            .replay_journal(&schema_provider)
+            .await
+            .map_err(|err| Error::storage("Could not replay operation journal", err))?;
+
         // Create service manager with shared data between services
         let context = Context::new(store, key_pair, config, schema_provider);
         let mut manager =
@@ -77,17 +125,26 @@ impl Node {
             .await
             .is_err()
         {
-            panic!("Failed starting materialiser service");
+            return Err(Error::Service("materializer"));
+        }
+
+        // Start status service tracking the node's state for the GraphQL API
+        if manager.add("status", status_service).await.is_err() {
+            return Err(Error::Service("status"));
         }
 
         // Start HTTP server with GraphQL API
         if manager.add("http", http_service).await.is_err() {
-            panic!("Failed starting HTTP service");
+            return Err(Error::Network {
+                message: "Failed starting HTTP service".into(),
+            });
         }
 
         // Start network service
         if manager.add("network", network_service).await.is_err() {
-            panic!("Failed starting network service");
+            return Err(Error::Network {
+                message: "Failed starting network service".into(),
+            });
         }
 
         // Start replication service syncing data with other nodes
@@ -96,14 +153,28 @@ impl Node {
             .await
             .is_err()
         {
-            panic!("Failed starting replication service");
+            return Err(Error::Service("replication"));
+        }
+
+        // Start admin console on unix socket when configured
+        #[cfg(unix)]
+        if context.config.admin_socket_path.is_some()
+            && manager.add("admin", admin_service).await.is_err()
+        {
+            return Err(Error::Service("admin"));
+        }
+
+        // Start releasing resources while the node is idle when configured
+        if context.config.idle_timeout.is_some() && manager.add("idle", idle_service).await.is_err()
+        {
+            return Err(Error::Service("idle"));
         }
 
         // Create a low-level interface which can be exposed so developers can interact with the
         // internal store and service bus
         let api = NodeInterface::new(context, manager.get_sender());
 
-        Self { pool, manager, api }
+        Ok(Self { pool, manager, api })
     }
 
     /// This future resolves when at least one system service stopped.
@@ -129,8 +200,11 @@ impl Node {
     /// with schema data can be created with the p2panda command line tool `fishy`.
     ///
     /// Returns `true` if migration took place or `false` if no migration was required.
-    pub async fn migrate(&self, lock_file: LockFile) -> Result<bool> {
-        self.api.migrate(lock_file).await
+    pub async fn migrate(&self, lock_file: LockFile) -> Result<bool, Error> {
+        self.api
+            .migrate(lock_file)
+            .await
+            .map_err(|err| Error::validation("Could not migrate lock file", err))
     }
 
     /// Subscribe to channel reporting on significant node events which can be interesting for
@@ -138,4 +212,68 @@ impl Node {
     pub async fn subscribe(&self) -> Receiver<NodeEvent> {
         self.api.subscribe().await
     }
+
+    /// Returns counters on blob files which were verified before they got served via HTTP.
+    ///
+    /// Verification needs to be enabled with the `verify_blobs` configuration option.
+    pub fn blob_cache_metrics(&self) -> BlobCacheMetrics {
+        self.api.blob_cache_metrics()
+    }
+
+    /// Registers a hook which runs every time a document of the schemas it accepts got
+    /// materialized, for example to keep a search index up-to-date or send notifications.
+    ///
+    /// Hooks only see documents which get materialized after they were registered. Use
+    /// `Configuration::document_hooks` to register hooks before the node starts.
+    ///
+    /// Panics if the name of the hook is already taken by another hook.
+    pub fn register_document_hook(&self, hook: impl DocumentHook + 'static) {
+        self.api.register_document_hook(hook)
+    }
+
+    /// Returns counters and duration histograms of materialization tasks, keyed by their task
+    /// type, for example "reduce" or "blob".
+    ///
+    /// A growing queue depth of "reduce" tasks indicates that documents can not be materialized
+    /// as fast as entries arrive.
+    pub fn materializer_metrics(&self) -> BTreeMap<String, TaskMetrics> {
+        self.api.materializer_metrics()
+    }
+
+    /// Returns the number of materialization tasks which are queued up or currently being
+    /// processed.
+    ///
+    /// A steadily growing number indicates that the node can not keep up with incoming data.
+    pub async fn pending_tasks(&self) -> Result<u64, Error> {
+        self.api
+            .pending_tasks()
+            .await
+            .map_err(|err| Error::storage("Could not count pending tasks", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::{Configuration, Error, Node};
+
+    #[tokio::test]
+    async fn start_with_invalid_database() {
+        let config = Configuration {
+            database_url: "unknown://database".into(),
+            ..Configuration::default()
+        };
+
+        let result = Node::start(KeyPair::new(), config).await;
+        let Err(err) = result else {
+            panic!("Expected node to fail starting");
+        };
+
+        assert!(matches!(err, Error::Storage { .. }));
+        assert_eq!(err.to_string(), "Could not initialize database");
+        assert!(err.source().is_some());
+    }
 }
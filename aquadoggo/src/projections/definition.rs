@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::str::FromStr;
+
+use p2panda_rs::schema::SchemaId;
+
+use crate::projections::ProjectionError;
+
+/// Maximum length of projection and field names.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Named projection of the documents of one schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    /// Name of the projection, used for its database table and GraphQL query.
+    pub name: String,
+
+    /// Schema of the documents this projection is derived from.
+    pub schema_id: SchemaId,
+
+    /// Projected fields.
+    pub fields: Vec<ProjectionField>,
+}
+
+impl Projection {
+    /// Returns a new projection after validating its name and fields.
+    pub fn new(
+        name: &str,
+        schema_id: SchemaId,
+        fields: Vec<ProjectionField>,
+    ) -> Result<Self, ProjectionError> {
+        validate_name(name)?;
+
+        for (index, field) in fields.iter().enumerate() {
+            validate_name(&field.name)?;
+
+            if fields[..index].iter().any(|other| other.name == field.name) {
+                return Err(ProjectionError::DuplicateField(field.name.clone()));
+            }
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            schema_id,
+            fields,
+        })
+    }
+
+    /// Returns the name of the database table holding the rows of this projection.
+    pub fn table_name(&self) -> String {
+        format!("projection_{}", self.name)
+    }
+
+    /// Returns `true` if any field of this projection reads from related documents.
+    pub fn has_relations(&self) -> bool {
+        self.fields.iter().any(|field| {
+            matches!(
+                field.expression.source,
+                ProjectionSource::RelatedField { .. }
+            )
+        })
+    }
+}
+
+/// Single field of a projection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectionField {
+    /// Name of the projected field.
+    pub name: String,
+
+    /// Expression computing the value of this field.
+    pub expression: ProjectionExpression,
+}
+
+/// Expression computing the value of a projected field.
+///
+/// Expressions are written as a field name (`username`), a relation field followed by a field of
+/// the related document (`author.username`), optionally wrapped by any number of functions
+/// (`lower(author.username)`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectionExpression {
+    /// Document field the value is read from.
+    pub source: ProjectionSource,
+
+    /// Functions applied to the value, innermost first.
+    pub functions: Vec<ProjectionFunction>,
+}
+
+impl ProjectionExpression {
+    /// Returns the function applied last to the value, if any.
+    pub fn outer_function(&self) -> Option<&ProjectionFunction> {
+        self.functions.last()
+    }
+}
+
+impl FromStr for ProjectionExpression {
+    type Err = ProjectionError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || ProjectionError::InvalidExpression(value.to_owned());
+
+        // Unwrap functions from the outside to the inside
+        let mut functions = Vec::new();
+        let mut inner = value.trim();
+        while let Some(open) = inner.find('(') {
+            let function = inner[..open].trim();
+            inner = inner[open + 1..]
+                .trim()
+                .strip_suffix(')')
+                .ok_or_else(invalid)?
+                .trim();
+            functions.push(function.parse()?);
+        }
+        functions.reverse();
+
+        let source = match inner.split_once('.') {
+            Some((relation, field)) => ProjectionSource::RelatedField {
+                relation: relation.to_owned(),
+                field: field.to_owned(),
+            },
+            None => ProjectionSource::Field(inner.to_owned()),
+        };
+
+        let is_valid = match &source {
+            ProjectionSource::Field(field) => is_field_name(field),
+            ProjectionSource::RelatedField { relation, field } => {
+                is_field_name(relation) && is_field_name(field)
+            }
+        };
+
+        if !is_valid {
+            return Err(invalid());
+        }
+
+        Ok(Self { source, functions })
+    }
+}
+
+/// Document field a projected value is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectionSource {
+    /// Field of the projected document itself.
+    Field(String),
+
+    /// Field of a document the projected document points at with a (pinned) relation.
+    RelatedField {
+        /// Relation field of the projected document.
+        relation: String,
+
+        /// Field of the related document.
+        field: String,
+    },
+}
+
+/// Function which can be applied to projected values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionFunction {
+    /// Number of characters of the value.
+    Length,
+
+    /// Value converted to lowercase.
+    Lower,
+
+    /// Value converted to uppercase.
+    Upper,
+}
+
+impl ProjectionFunction {
+    /// Applies this function to a value.
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            ProjectionFunction::Length => value.chars().count().to_string(),
+            ProjectionFunction::Lower => value.to_lowercase(),
+            ProjectionFunction::Upper => value.to_uppercase(),
+        }
+    }
+}
+
+impl FromStr for ProjectionFunction {
+    type Err = ProjectionError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "length" => Ok(ProjectionFunction::Length),
+            "lower" => Ok(ProjectionFunction::Lower),
+            "upper" => Ok(ProjectionFunction::Upper),
+            _ => Err(ProjectionError::UnknownFunction(value.to_owned())),
+        }
+    }
+}
+
+/// Projection and projected field names are used as database identifiers and need to be
+/// lowercase ASCII.
+fn validate_name(name: &str) -> Result<(), ProjectionError> {
+    let mut chars = name.chars();
+
+    let is_valid = name.len() <= MAX_NAME_LENGTH
+        && chars.next().is_some_and(|char| char.is_ascii_lowercase())
+        && chars.all(|char| char.is_ascii_lowercase() || char.is_ascii_digit() || char == '_');
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(ProjectionError::InvalidName(name.to_owned()))
+    }
+}
+
+/// Checks if the given string could be the name of a field in a p2panda schema.
+fn is_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().is_some_and(|char| char.is_ascii_alphabetic())
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::schema::SchemaId;
+    use p2panda_rs::test_utils::fixtures::schema_id;
+    use rstest::rstest;
+
+    use crate::projections::ProjectionError;
+
+    use super::{
+        Projection, ProjectionExpression, ProjectionField, ProjectionFunction, ProjectionSource,
+    };
+
+    #[rstest]
+    #[case("username", ProjectionSource::Field("username".into()), vec![])]
+    #[case(
+        "author.name",
+        ProjectionSource::RelatedField { relation: "author".into(), field: "name".into() },
+        vec![]
+    )]
+    #[case(
+        "length(lower( author.name ))",
+        ProjectionSource::RelatedField { relation: "author".into(), field: "name".into() },
+        vec![ProjectionFunction::Lower, ProjectionFunction::Length]
+    )]
+    fn parse_expressions(
+        #[case] value: &str,
+        #[case] source: ProjectionSource,
+        #[case] functions: Vec<ProjectionFunction>,
+    ) {
+        let expression: ProjectionExpression = value.parse().unwrap();
+        assert_eq!(expression, ProjectionExpression { source, functions });
+    }
+
+    #[rstest]
+    #[case("", ProjectionError::InvalidExpression("".into()))]
+    #[case("lower(name", ProjectionError::InvalidExpression("lower(name".into()))]
+    #[case("a.b.c", ProjectionError::InvalidExpression("a.b.c".into()))]
+    #[case("reverse(name)", ProjectionError::UnknownFunction("reverse".into()))]
+    fn invalid_expressions(#[case] value: &str, #[case] expected: ProjectionError) {
+        assert_eq!(value.parse::<ProjectionExpression>(), Err(expected));
+    }
+
+    #[rstest]
+    fn validate_projections(schema_id: SchemaId) {
+        let field = |name: &str| ProjectionField {
+            name: name.into(),
+            expression: "username".parse().unwrap(),
+        };
+
+        assert!(Projection::new("profiles", schema_id.clone(), vec![field("name")]).is_ok());
+        assert_eq!(
+            Projection::new("Profiles", schema_id.clone(), vec![field("name")]),
+            Err(ProjectionError::InvalidName("Profiles".into()))
+        );
+        assert_eq!(
+            Projection::new("profiles", schema_id, vec![field("name"), field("name")]),
+            Err(ProjectionError::DuplicateField("name".into()))
+        );
+    }
+}
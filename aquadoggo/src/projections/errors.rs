@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use thiserror::Error;
+
+/// Errors returned when parsing or validating projection definitions.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProjectionError {
+    /// Name of a projection or projected field is not a valid identifier.
+    #[error("Invalid projection name '{0}'")]
+    InvalidName(String),
+
+    /// Expression of a projected field could not be parsed.
+    #[error("Invalid projection expression '{0}'")]
+    InvalidExpression(String),
+
+    /// Function used in an expression is not known.
+    #[error("Unknown projection function '{0}'")]
+    UnknownFunction(String),
+
+    /// Two fields of the same projection share a name.
+    #[error("Duplicate field '{0}' in projection")]
+    DuplicateField(String),
+}
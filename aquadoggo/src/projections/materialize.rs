@@ -0,0 +1,286 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use p2panda_rs::document::traits::AsDocument;
+use p2panda_rs::operation::OperationValue;
+use p2panda_rs::storage_provider::traits::DocumentStore;
+
+use crate::db::errors::SqlStoreError;
+use crate::db::models::utils::parse_value_to_string_vec;
+use crate::db::stores::document::DOCUMENTS_PAGE_SIZE;
+use crate::db::types::ProjectionRow;
+use crate::db::SqlStore;
+use crate::projections::{Projection, ProjectionSource};
+
+/// Re-creates the tables of all given projections and fills them with the latest views of the
+/// documents of their schemas.
+///
+/// Projections only contain derived data, rebuilding them on every start makes sure that the
+/// tables always reflect the current configuration.
+pub async fn rebuild_projections(
+    store: &SqlStore,
+    projections: &[Projection],
+) -> Result<(), SqlStoreError> {
+    for projection in projections {
+        store.rebuild_projection_table(projection).await?;
+
+        let mut after = None;
+        loop {
+            let documents = store
+                .get_documents_by_schema_paginated(
+                    &projection.schema_id,
+                    DOCUMENTS_PAGE_SIZE,
+                    after.as_ref(),
+                )
+                .await?;
+
+            for document in &documents {
+                update_projection_row(store, projection, document).await?;
+            }
+
+            match documents.last() {
+                Some(document) if documents.len() as u64 == DOCUMENTS_PAGE_SIZE => {
+                    after = Some(document.id().to_owned());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Updates all projections affected by a new view of the given document.
+///
+/// Next to the projections of the document's own schema this also updates the rows of all
+/// documents which read values from this document via a relation.
+pub async fn update_projections(
+    store: &SqlStore,
+    projections: &[Projection],
+    document: &impl AsDocument,
+) -> Result<(), SqlStoreError> {
+    for projection in projections
+        .iter()
+        .filter(|projection| &projection.schema_id == document.schema_id())
+    {
+        update_projection_row(store, projection, document).await?;
+    }
+
+    if !projections
+        .iter()
+        .any(|projection| projection.has_relations())
+    {
+        return Ok(());
+    }
+
+    for (projection_name, document_id) in store
+        .get_projected_documents_by_relation(document.id())
+        .await?
+    {
+        let projection = projections
+            .iter()
+            .find(|projection| projection.name == projection_name);
+
+        if let (Some(projection), Some(projected_document)) =
+            (projection, store.get_document(&document_id).await?)
+        {
+            update_projection_row(store, projection, &projected_document).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the values of a document for the given projection and writes them into its table.
+///
+/// Deleted documents are removed from the projection.
+async fn update_projection_row(
+    store: &SqlStore,
+    projection: &Projection,
+    document: &impl AsDocument,
+) -> Result<(), SqlStoreError> {
+    if document.is_deleted() {
+        return store.delete_projection_row(projection, document.id()).await;
+    }
+
+    let mut values = Vec::with_capacity(projection.fields.len());
+    let mut related_document_ids = Vec::new();
+
+    for field in &projection.fields {
+        let value = match &field.expression.source {
+            ProjectionSource::Field(name) => document.get(name).map(value_to_string),
+            ProjectionSource::RelatedField { relation, field } => {
+                let related_document = match document.get(relation) {
+                    Some(OperationValue::Relation(relation)) => {
+                        // Remember unpinned relations, their values change with every update of
+                        // the related document
+                        related_document_ids.push(relation.document_id().to_owned());
+                        store.get_document(relation.document_id()).await?
+                    }
+                    Some(OperationValue::PinnedRelation(relation)) => {
+                        store.get_document_by_view_id(relation.view_id()).await?
+                    }
+                    _ => None,
+                };
+
+                related_document
+                    .filter(|related_document| !related_document.is_deleted())
+                    .and_then(|related_document| related_document.get(field).map(value_to_string))
+            }
+        };
+
+        let value = value.map(|value| {
+            field
+                .expression
+                .functions
+                .iter()
+                .fold(value, |value, function| function.apply(&value))
+        });
+
+        values.push(value);
+    }
+
+    let row = ProjectionRow {
+        document_id: document.id().to_owned(),
+        view_id: document.view_id().to_owned(),
+        values,
+    };
+
+    store
+        .upsert_projection_row(projection, &row, &related_document_ids)
+        .await
+}
+
+/// Converts a document field value into its textual representation.
+///
+/// Lists of relations are joined with commas.
+fn value_to_string(value: &OperationValue) -> String {
+    parse_value_to_string_vec(value)
+        .into_iter()
+        .flatten()
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::{OperationValue, Relation};
+    use p2panda_rs::schema::FieldType;
+    use p2panda_rs::storage_provider::traits::DocumentStore;
+    use p2panda_rs::test_utils::fixtures::key_pair;
+    use rstest::rstest;
+
+    use crate::projections::{Projection, ProjectionField};
+    use crate::test_utils::{add_document, add_schema, test_runner, update_document, TestNode};
+
+    use super::{rebuild_projections, update_projections};
+
+    #[rstest]
+    fn materialize_projection(key_pair: KeyPair) {
+        test_runner(|mut node: TestNode| async move {
+            let authors = add_schema(
+                &mut node,
+                "authors",
+                vec![("name", FieldType::String)],
+                &key_pair,
+            )
+            .await;
+            let books = add_schema(
+                &mut node,
+                "books",
+                vec![
+                    ("title", FieldType::String),
+                    ("author", FieldType::Relation(authors.id().to_owned())),
+                ],
+                &key_pair,
+            )
+            .await;
+
+            let author_view_id = add_document(
+                &mut node,
+                authors.id(),
+                vec![("name", "Panda".into())],
+                &key_pair,
+            )
+            .await;
+            let author_id = author_view_id.to_string().parse().unwrap();
+            add_document(
+                &mut node,
+                books.id(),
+                vec![
+                    ("title", "Bamboo".into()),
+                    ("author", OperationValue::Relation(Relation::new(author_id))),
+                ],
+                &key_pair,
+            )
+            .await;
+
+            let field = |name: &str, expression: &str| ProjectionField {
+                name: name.into(),
+                expression: expression.parse().unwrap(),
+            };
+            let projection = Projection::new(
+                "books",
+                books.id().to_owned(),
+                vec![
+                    field("title", "upper(title)"),
+                    field("author_name", "author.name"),
+                    field("title_length", "length(title)"),
+                    field("missing", "year"),
+                ],
+            )
+            .unwrap();
+            let projections = vec![projection.clone()];
+
+            let store = &node.context.store;
+            rebuild_projections(store, &projections).await.unwrap();
+
+            let rows = store
+                .get_projection_rows(&projection, 10, None)
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+            assert_eq!(
+                rows[0].values,
+                vec![
+                    Some("BAMBOO".to_string()),
+                    Some("Panda".to_string()),
+                    Some("6".to_string()),
+                    None
+                ]
+            );
+
+            // Updating the related document also updates the projected value
+            let author_view_id = update_document(
+                &mut node,
+                authors.id(),
+                vec![("name", "Red Panda".into())],
+                &author_view_id,
+                &key_pair,
+            )
+            .await;
+            let store = &node.context.store;
+            let author = store
+                .get_document_by_view_id(&author_view_id)
+                .await
+                .unwrap()
+                .unwrap();
+            update_projections(store, &projections, &author)
+                .await
+                .unwrap();
+
+            let rows = store
+                .get_projection_rows(&projection, 10, None)
+                .await
+                .unwrap();
+            assert_eq!(rows[0].values[1], Some("Red Panda".to_string()));
+
+            // Pagination continues after the given document id
+            let rows = store
+                .get_projection_rows(&projection, 10, Some(&rows[0].document_id))
+                .await
+                .unwrap();
+            assert!(rows.is_empty());
+        });
+    }
+}
@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Server-side projections of documents.
+//!
+//! Operators can define named projections in the node configuration which contain a subset of
+//! the fields of a schema, fields of related documents and simple computed values. The
+//! materializer keeps every projection up-to-date in a dedicated database table and the GraphQL
+//! API exposes them as read-only types, which makes dashboard-style queries cheap.
+mod definition;
+mod errors;
+mod materialize;
+
+pub use definition::{
+    Projection, ProjectionExpression, ProjectionField, ProjectionFunction, ProjectionSource,
+};
+pub use errors::ProjectionError;
+pub use materialize::{rebuild_projections, update_projections};
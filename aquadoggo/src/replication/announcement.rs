@@ -1,19 +1,12 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use std::time::{SystemTime, UNIX_EPOCH};
-
 use serde::ser::SerializeSeq;
 use serde::Serialize;
 
-use crate::replication::{SchemaIdSet, ANNOUNCE_TYPE, REPLICATION_PROTOCOL_VERSION};
-
-/// U64 timestamp from UNIX epoch until now.
-pub fn now() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("System time invalid, operation system time configured before UNIX epoch")
-        .as_secs()
-}
+use crate::replication::{
+    Compression, Mode, SchemaIdSet, ANNOUNCE_TYPE, REPLICATION_PROTOCOL_VERSION,
+};
+use crate::utils::now;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Announcement {
@@ -23,13 +16,25 @@ pub struct Announcement {
     /// Timestamp of this announcement. Helps to understand if we can override the previous
     /// announcement with a newer one.
     pub timestamp: u64,
+
+    /// Compression algorithms this peer accepts for exchanging entries.
+    pub supported_compressions: Vec<Compression>,
+
+    /// Replication modes this peer serves next to the default log height mode.
+    pub supported_modes: Vec<Mode>,
 }
 
 impl Announcement {
-    pub fn new(supported_schema_ids: SchemaIdSet) -> Self {
+    pub fn new(
+        supported_schema_ids: SchemaIdSet,
+        supported_compressions: Vec<Compression>,
+        supported_modes: Vec<Mode>,
+    ) -> Self {
         Self {
             timestamp: now(),
             supported_schema_ids,
+            supported_compressions,
+            supported_modes,
         }
     }
 }
@@ -59,11 +64,29 @@ impl Serialize for AnnouncementMessage {
     where
         S: serde::Serializer,
     {
-        let mut seq = serializer.serialize_seq(Some(4))?;
+        // Supported compression algorithms and replication modes are optional, leave them out
+        // when there are none to stay compatible with peers which don't know about them
+        let compressions = &self.1.supported_compressions;
+        let modes = &self.1.supported_modes;
+        let len = if !modes.is_empty() {
+            6
+        } else if !compressions.is_empty() {
+            5
+        } else {
+            4
+        };
+
+        let mut seq = serializer.serialize_seq(Some(len))?;
         seq.serialize_element(&ANNOUNCE_TYPE)?;
         seq.serialize_element(&self.0)?;
         seq.serialize_element(&self.1.timestamp)?;
         seq.serialize_element(&self.1.supported_schema_ids)?;
+        if len > 4 {
+            seq.serialize_element(compressions)?;
+        }
+        if len > 5 {
+            seq.serialize_element(modes)?;
+        }
         seq.end()
     }
 }
@@ -74,17 +97,47 @@ mod tests {
     use p2panda_rs::serde::{serialize_from, serialize_value};
     use rstest::rstest;
 
-    use crate::replication::SchemaIdSet;
+    use crate::replication::{Compression, Mode, SchemaIdSet};
     use crate::test_utils::helpers::random_schema_id_set;
 
     use super::{Announcement, AnnouncementMessage};
 
     #[rstest]
     fn serialize(#[from(random_schema_id_set)] supported_schema_ids: SchemaIdSet) {
-        let announcement = Announcement::new(supported_schema_ids.clone());
+        let announcement = Announcement::new(supported_schema_ids.clone(), vec![], vec![]);
         assert_eq!(
             serialize_from(AnnouncementMessage::new(announcement.clone())),
             serialize_value(cbor!([0, 1, announcement.timestamp, supported_schema_ids]))
         );
+
+        let announcement = Announcement::new(
+            supported_schema_ids.clone(),
+            vec![Compression::Deflate],
+            vec![],
+        );
+        assert_eq!(
+            serialize_from(AnnouncementMessage::new(announcement.clone())),
+            serialize_value(cbor!([
+                0,
+                1,
+                announcement.timestamp,
+                supported_schema_ids,
+                [0]
+            ]))
+        );
+
+        let announcement =
+            Announcement::new(supported_schema_ids.clone(), vec![], vec![Mode::Shallow]);
+        assert_eq!(
+            serialize_from(AnnouncementMessage::new(announcement.clone())),
+            serialize_value(cbor!([
+                0,
+                1,
+                announcement.timestamp,
+                supported_schema_ids,
+                [],
+                [2]
+            ]))
+        );
     }
 }
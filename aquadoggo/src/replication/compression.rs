@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use p2panda_rs::entry::traits::AsEncodedEntry;
+use p2panda_rs::entry::EncodedEntry;
+use p2panda_rs::operation::EncodedOperation;
+use p2panda_rs::Human;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::replication::errors::CompressionError;
+
+/// Compression level used for DEFLATE, this is a good trade-off between speed and size.
+const DEFLATE_LEVEL: u8 = 6;
+
+/// Maximum size of a batch of entries after decompressing it.
+///
+/// This protects the node from "compression bombs" sent by remote peers.
+pub const MAX_DECOMPRESSED_SIZE: usize = 32 * 1024 * 1024;
+
+/// Maximum number of entries which are compressed together into one message.
+pub const MAX_COMPRESSED_BATCH_LENGTH: usize = 64;
+
+/// Entries and operations which get compressed together into one message.
+pub type EntryBatch = Vec<(EncodedEntry, Option<EncodedOperation>)>;
+
+/// Compression algorithms which can be negotiated between peers to exchange entries.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Compression {
+    /// DEFLATE compression as specified in RFC 1951.
+    Deflate,
+
+    /// Compression algorithm announced by a remote peer which is not supported by this node.
+    Unknown,
+}
+
+impl Compression {
+    /// Returns the human-readable name of the compression algorithm.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Compression::Deflate => "deflate",
+            Compression::Unknown => "unknown",
+        }
+    }
+
+    /// Returns the integer used to identify the compression algorithm on the wire.
+    pub fn as_u64(&self) -> u64 {
+        match self {
+            Compression::Deflate => 0,
+            Compression::Unknown => unreachable!("Can't create an unknown compression algorithm"),
+        }
+    }
+
+    /// Encodes and compresses a batch of entries.
+    pub fn compress(&self, entries: &EntryBatch) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(entries, &mut bytes)
+            .expect("Entries can always be encoded as CBOR");
+
+        match self {
+            Compression::Deflate => compress_to_vec(&bytes, DEFLATE_LEVEL),
+            Compression::Unknown => unreachable!("Can't compress with unknown algorithm"),
+        }
+    }
+
+    /// Decompresses and decodes a batch of entries.
+    pub fn decompress(&self, bytes: &[u8]) -> Result<EntryBatch, CompressionError> {
+        let bytes = match self {
+            Compression::Deflate => decompress_to_vec_with_limit(bytes, MAX_DECOMPRESSED_SIZE)
+                .map_err(|err| CompressionError::Decompression(format!("{:?}", err.status)))?,
+            Compression::Unknown => return Err(CompressionError::Unsupported),
+        };
+
+        ciborium::de::from_reader(&bytes[..])
+            .map_err(|err| CompressionError::Decompression(err.to_string()))
+    }
+}
+
+impl From<u64> for Compression {
+    fn from(value: u64) -> Self {
+        match value {
+            0 => Compression::Deflate,
+            _ => Compression::Unknown,
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "deflate" => Ok(Compression::Deflate),
+            _ => Err(anyhow!("Unknown compression algorithm '{}'", value)),
+        }
+    }
+}
+
+impl Serialize for Compression {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(self.as_u64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Compression {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let compression = u64::deserialize(deserializer)?;
+        Ok(compression.into())
+    }
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Human for Compression {
+    fn display(&self) -> String {
+        self.as_str().to_owned()
+    }
+}
+
+/// Number of bytes of entries exchanged with a peer, before and after compressing them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CompressionStats {
+    /// Size of the encoded entries before compression.
+    pub uncompressed_bytes: u64,
+
+    /// Size of the entries after compression, as they were sent over the wire.
+    pub compressed_bytes: u64,
+}
+
+impl CompressionStats {
+    /// Adds the size of a compressed batch of entries to the stats.
+    pub fn add(&mut self, uncompressed_bytes: usize, compressed_bytes: usize) {
+        self.uncompressed_bytes += uncompressed_bytes as u64;
+        self.compressed_bytes += compressed_bytes as u64;
+    }
+
+    /// Returns the ratio of uncompressed to compressed bytes or `None` if nothing was compressed
+    /// yet.
+    pub fn ratio(&self) -> Option<f64> {
+        if self.compressed_bytes == 0 {
+            return None;
+        }
+
+        Some(self.uncompressed_bytes as f64 / self.compressed_bytes as f64)
+    }
+}
+
+/// Returns the size of a batch of entries before compression.
+pub fn batch_size(entries: &EntryBatch) -> usize {
+    entries
+        .iter()
+        .map(|(entry, operation)| {
+            entry.size() as usize
+                + operation
+                    .as_ref()
+                    .map_or(0, |operation| operation.size() as usize)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::EncodedEntry;
+    use p2panda_rs::operation::EncodedOperation;
+    use p2panda_rs::test_utils::fixtures::{encoded_entry, encoded_operation};
+    use rstest::rstest;
+
+    use super::{batch_size, Compression, CompressionStats};
+
+    #[rstest]
+    fn compress_and_decompress(encoded_entry: EncodedEntry, encoded_operation: EncodedOperation) {
+        let entries = vec![(encoded_entry.clone(), Some(encoded_operation.clone())); 10];
+
+        let bytes = Compression::Deflate.compress(&entries);
+        assert!(bytes.len() < batch_size(&entries));
+        assert_eq!(Compression::Deflate.decompress(&bytes).unwrap(), entries);
+
+        // Garbage can not be decompressed
+        assert!(Compression::Deflate.decompress(&[1, 2, 3]).is_err());
+        assert!(Compression::Unknown.decompress(&bytes).is_err());
+    }
+
+    #[test]
+    fn u64_representation() {
+        assert_eq!(Compression::Deflate.as_u64(), 0);
+        assert_eq!(Compression::from(0), Compression::Deflate);
+        assert_eq!(Compression::from(12), Compression::Unknown);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Compression::Deflate.to_string(), "deflate");
+        assert_eq!(Compression::from(12).to_string(), "unknown");
+    }
+
+    #[test]
+    fn compression_ratio() {
+        let mut stats = CompressionStats::default();
+        assert_eq!(stats.ratio(), None);
+
+        stats.add(300, 100);
+        stats.add(100, 100);
+        assert_eq!(stats.ratio(), Some(2.0));
+    }
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Statistics about the entries a node in replication dry-run mode would have ingested.
+//!
+//! In this mode the node takes part in replication sessions as usual but does not persist any of
+//! the received entries. Operators can use this to evaluate the cost of joining a network or
+//! enabling a schema before committing disk space.
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use p2panda_rs::hash::Hash;
+use p2panda_rs::schema::SchemaId;
+
+/// Number and size of entries of one schema a node would have ingested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunSchemaStats {
+    /// Id of the schema the operations of these entries follow.
+    pub schema_id: SchemaId,
+
+    /// Number of entries.
+    pub entries: u64,
+
+    /// Size of the entries and their operations in bytes.
+    pub bytes: u64,
+}
+
+/// In-memory record of all entries received while running in dry-run mode.
+///
+/// As nothing gets persisted, peers send us the same entries in every replication session again.
+/// The hashes of all observed entries are kept to count each of them only once.
+#[derive(Debug, Clone, Default)]
+pub struct DryRunStats {
+    inner: Arc<Mutex<DryRunStatsInner>>,
+}
+
+#[derive(Debug, Default)]
+struct DryRunStatsInner {
+    /// Hashes of all entries observed so far.
+    observed: HashSet<Hash>,
+
+    /// Number and size of observed entries per schema.
+    schemas: BTreeMap<SchemaId, (u64, u64)>,
+}
+
+impl DryRunStats {
+    /// Records an entry we would have ingested.
+    ///
+    /// Returns false if the entry was already observed before.
+    pub fn record(&self, entry_hash: Hash, schema_id: &SchemaId, bytes: u64) -> bool {
+        let mut inner = self
+            .inner
+            .lock()
+            .expect("Could not acquire lock on dry-run stats");
+
+        if !inner.observed.insert(entry_hash) {
+            return false;
+        }
+
+        let (entries, total_bytes) = inner.schemas.entry(schema_id.to_owned()).or_default();
+        *entries += 1;
+        *total_bytes += bytes;
+
+        true
+    }
+
+    /// Returns the number and size of observed entries per schema, ordered by schema id.
+    pub fn report(&self) -> Vec<DryRunSchemaStats> {
+        self.inner
+            .lock()
+            .expect("Could not acquire lock on dry-run stats")
+            .schemas
+            .iter()
+            .map(|(schema_id, (entries, bytes))| DryRunSchemaStats {
+                schema_id: schema_id.to_owned(),
+                entries: *entries,
+                bytes: *bytes,
+            })
+            .collect()
+    }
+}
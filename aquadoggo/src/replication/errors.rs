@@ -28,6 +28,9 @@ pub enum ReplicationError {
     #[error("Replication strategy failed with error: {0}")]
     StrategyFailed(String),
 
+    #[error(transparent)]
+    Compression(#[from] CompressionError),
+
     #[error("Incoming data could not be ingested: {0}")]
     Validation(#[from] IngestError),
 }
@@ -52,6 +55,18 @@ pub enum IngestError {
 
     #[error("Duplicate entry received: {0}")]
     DuplicateEntry(Hash),
+
+    #[error("Invalid entry signatures: {0}")]
+    InvalidSignatures(String),
+}
+
+#[derive(Error, Debug)]
+pub enum CompressionError {
+    #[error("Remote peer sent entries with unsupported compression")]
+    Unsupported,
+
+    #[error("Decompressing entries failed: {0}")]
+    Decompression(String),
 }
 
 #[derive(Error, Debug)]
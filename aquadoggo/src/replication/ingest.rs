@@ -1,23 +1,34 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use bamboo_rs_core_ed25519_yasmf::entry::verify::batch::verify_batch_signatures;
 use log::trace;
-use p2panda_rs::api::publish;
-use p2panda_rs::entry::traits::AsEncodedEntry;
+use p2panda_rs::entry::decode::decode_entry;
+use p2panda_rs::entry::traits::{AsEncodedEntry, AsEntry};
 use p2panda_rs::entry::EncodedEntry;
+use p2panda_rs::identity::PublicKey;
 use p2panda_rs::operation::decode::decode_operation;
 use p2panda_rs::operation::traits::Schematic;
 use p2panda_rs::operation::{EncodedOperation, OperationId};
 use p2panda_rs::storage_provider::traits::EntryStore;
+use tokio::task;
 
 use crate::bus::{ServiceMessage, ServiceSender};
 use crate::db::SqlStore;
+use crate::materializer::TaskBacklog;
 use crate::replication::errors::IngestError;
+use crate::replication::DryRunStats;
 use crate::schema::SchemaProvider;
 
 #[derive(Debug, Clone)]
 pub struct SyncIngest {
     tx: ServiceSender,
     pub schema_provider: SchemaProvider,
+
+    /// Record of received entries when running in dry-run mode, nothing gets published then.
+    dry_run: Option<DryRunStats>,
+
+    /// Waiting materializer tasks, ingesting entries pauses while too many tasks are queued.
+    backlog: TaskBacklog,
 }
 
 impl SyncIngest {
@@ -25,15 +36,33 @@ impl SyncIngest {
         Self {
             tx,
             schema_provider,
+            dry_run: None,
+            backlog: TaskBacklog::new(),
         }
     }
 
+    /// Records received entries in the given statistics instead of publishing them.
+    pub fn with_dry_run(mut self, stats: DryRunStats) -> Self {
+        self.dry_run = Some(stats);
+        self
+    }
+
+    /// Waits with publishing received entries while the materializer is behind.
+    pub fn with_backlog(mut self, backlog: TaskBacklog) -> Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Validates and publishes an entry received from another peer.
+    ///
+    /// Returns the public key of the entry's author or `None` in dry-run mode, where the entry
+    /// only gets recorded.
     pub async fn handle_entry(
         &self,
         store: &SqlStore,
         encoded_entry: &EncodedEntry,
         encoded_operation: &EncodedOperation,
-    ) -> Result<(), IngestError> {
+    ) -> Result<Option<PublicKey>, IngestError> {
         trace!("Received entry and operation: {}", encoded_entry.hash());
 
         // Check if we already have this entry. This can happen if another peer sent it to us
@@ -61,6 +90,19 @@ impl SyncIngest {
             return Err(IngestError::UnsupportedSchema);
         }
 
+        // In dry-run mode we only verify the signature and record what we would have published.
+        // The schema doesn't need to be materialized for this as we never ingested its definition
+        if let Some(stats) = &self.dry_run {
+            verify_signatures(std::slice::from_ref(encoded_entry)).await?;
+
+            let bytes = encoded_entry.size() + encoded_operation.size();
+            if stats.record(encoded_entry.hash(), plain_operation.schema_id(), bytes) {
+                trace!("Recorded entry {} in dry-run mode", encoded_entry.hash());
+            }
+
+            return Ok(None);
+        }
+
         // Retrieve the schema if it has been materialized on the node.
         let schema = self
             .schema_provider
@@ -72,14 +114,22 @@ impl SyncIngest {
         // PUBLISH THE ENTRY AND OPERATION //
         /////////////////////////////////////
 
-        let _ = publish(
-            store,
-            &schema,
-            encoded_entry,
-            &plain_operation,
-            encoded_operation,
-        )
-        .await?;
+        // Don't accept more entries while the materializer can't keep up with them
+        self.backlog.wait_for_capacity().await;
+
+        // Entries of the same author are published one after another. Decoding the entry verifies
+        // its signature, this is CPU-heavy and runs in a blocking thread
+        let entry = {
+            let encoded_entry = encoded_entry.clone();
+            task::spawn_blocking(move || decode_entry(&encoded_entry))
+                .await
+                .map_err(|err| IngestError::InvalidSignatures(err.to_string()))??
+        };
+        let _guard = store.lock_public_key(entry.public_key()).await;
+
+        let _ = store
+            .publish(&schema, encoded_entry, &plain_operation, encoded_operation)
+            .await?;
 
         ////////////////////////////////////////
         // SEND THE OPERATION TO MATERIALIZER //
@@ -99,21 +149,41 @@ impl SyncIngest {
             // tests in other places to check if messages arrive.
         };
 
-        Ok(())
+        Ok(Some(entry.public_key().to_owned()))
     }
 }
 
+/// Verifies the signatures of a batch of entries.
+///
+/// Signatures are checked with batched ed25519 verification spread over all CPU cores. This runs
+/// in a blocking thread to not stall the async runtime when receiving many entries during large
+/// syncs.
+pub async fn verify_signatures(entries: &[EncodedEntry]) -> Result<(), IngestError> {
+    let entries_bytes: Vec<Vec<u8>> = entries.iter().map(|entry| entry.into_bytes()).collect();
+
+    task::spawn_blocking(move || verify_batch_signatures(&entries_bytes))
+        .await
+        .map_err(|err| IngestError::InvalidSignatures(err.to_string()))?
+        .map_err(|err| IngestError::InvalidSignatures(err.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
-    use p2panda_rs::entry::EncodedEntry;
+    use p2panda_rs::entry::encode::sign_and_encode_entry;
+    use p2panda_rs::entry::traits::AsEncodedEntry;
+    use p2panda_rs::entry::{EncodedEntry, LogId, SeqNum};
+    use p2panda_rs::identity::KeyPair;
     use p2panda_rs::operation::EncodedOperation;
     use p2panda_rs::schema::Schema;
+    use p2panda_rs::storage_provider::traits::EntryStore;
     use p2panda_rs::test_utils::fixtures::{encoded_entry, encoded_operation, schema};
     use rstest::rstest;
     use tokio::sync::broadcast;
 
     use crate::replication::errors::IngestError;
-    use crate::replication::SyncIngest;
+    use crate::replication::{DryRunStats, SyncIngest};
+
+    use super::verify_signatures;
     use crate::test_utils::{test_runner_with_manager, TestNodeManager};
     use crate::{AllowList, Configuration};
 
@@ -144,6 +214,44 @@ mod tests {
         })
     }
 
+    #[rstest]
+    fn record_entries_in_dry_run(encoded_entry: EncodedEntry, encoded_operation: EncodedOperation) {
+        test_runner_with_manager(move |manager: TestNodeManager| async move {
+            let node = manager.create().await;
+
+            let (tx, _rx) = broadcast::channel(8);
+            let stats = DryRunStats::default();
+            let ingest = SyncIngest::new(node.context.schema_provider.clone(), tx.clone())
+                .with_dry_run(stats.clone());
+
+            // Receiving the same entry twice counts it only once, even though the schema is not
+            // materialized on this node
+            for _ in 0..2 {
+                let result = ingest
+                    .handle_entry(&node.context.store, &encoded_entry, &encoded_operation)
+                    .await;
+                assert!(matches!(result, Ok(None)));
+            }
+
+            let report = stats.report();
+            assert_eq!(report.len(), 1);
+            assert_eq!(report[0].entries, 1);
+            assert_eq!(
+                report[0].bytes,
+                encoded_entry.size() + encoded_operation.size()
+            );
+
+            // Nothing got persisted
+            let entry = node
+                .context
+                .store
+                .get_entry(&encoded_entry.hash())
+                .await
+                .unwrap();
+            assert!(entry.is_none());
+        })
+    }
+
     #[rstest]
     fn allow_supported_schema_ids(
         schema: Schema,
@@ -170,4 +278,34 @@ mod tests {
             assert!(result.is_ok());
         });
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn verify_batch_of_signatures(encoded_operation: EncodedOperation) {
+        let mut entries: Vec<EncodedEntry> = (0..10)
+            .map(|_| {
+                sign_and_encode_entry(
+                    &LogId::default(),
+                    &SeqNum::default(),
+                    None,
+                    None,
+                    &encoded_operation,
+                    &KeyPair::new(),
+                )
+                .unwrap()
+            })
+            .collect();
+        assert!(verify_signatures(&entries).await.is_ok());
+
+        // Tamper with the signature of one entry
+        let mut bytes = entries[5].into_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        entries[5] = EncodedEntry::from_bytes(&bytes);
+
+        assert!(matches!(
+            verify_signatures(&entries).await,
+            Err(IngestError::InvalidSignatures(_))
+        ));
+    }
 }
@@ -6,6 +6,7 @@ use std::hash::Hash;
 use anyhow::Result;
 use log::{debug, trace, warn};
 use p2panda_rs::entry::EncodedEntry;
+use p2panda_rs::identity::PublicKey;
 use p2panda_rs::operation::EncodedOperation;
 use p2panda_rs::Human;
 
@@ -17,7 +18,7 @@ use crate::replication::{
 
 pub const INITIAL_SESSION_ID: SessionId = 0;
 
-pub const SUPPORTED_MODES: [Mode; 1] = [Mode::LogHeight];
+pub const SUPPORTED_MODES: [Mode; 2] = [Mode::LogHeight, Mode::Shallow];
 
 pub const SUPPORT_LIVE_MODE: bool = false;
 
@@ -32,6 +33,9 @@ fn to_sync_messages(session_id: SessionId, messages: Vec<Message>) -> Vec<SyncMe
 pub struct SyncResult {
     pub messages: Vec<SyncMessage>,
     pub is_done: bool,
+
+    /// Author of the entry which got ingested while handling the message.
+    pub ingested_author: Option<PublicKey>,
 }
 
 impl SyncResult {
@@ -39,6 +43,7 @@ impl SyncResult {
         Self {
             is_done,
             messages: to_sync_messages(session_id, messages),
+            ingested_author: None,
         }
     }
 }
@@ -292,6 +297,7 @@ where
         Ok(SyncResult {
             messages: all_messages,
             is_done: false,
+            ingested_author: None,
         })
     }
 
@@ -354,6 +360,7 @@ where
         Ok(SyncResult {
             messages: all_messages,
             is_done: false,
+            ingested_author: None,
         })
     }
 
@@ -480,13 +487,19 @@ where
                 )
                 .await
             {
+                Ok(public_key) => Ok(SyncResult {
+                    messages: vec![],
+                    is_done: session.state == SessionState::Done,
+                    ingested_author: public_key,
+                }),
                 // When duplicate entries arrive at a node, or a schema is not materialized yet,
                 // we don't want to treat as an error. This is expected behavior which may occur
                 // when concurrent sync sessions are running.
-                Ok(_) | Err(IngestError::DuplicateEntry(_)) | Err(IngestError::SchemaNotFound) => {
+                Err(IngestError::DuplicateEntry(_)) | Err(IngestError::SchemaNotFound) => {
                     Ok(SyncResult {
                         messages: vec![],
                         is_done: session.state == SessionState::Done,
+                        ingested_author: None,
                     })
                 }
                 Err(err) => Err(ReplicationError::Validation(err)),
@@ -528,6 +541,7 @@ where
 
 #[cfg(test)]
 mod tests {
+    use p2panda_rs::identity::PublicKey;
     use p2panda_rs::Human;
     use rstest::rstest;
     use tokio::sync::broadcast;
@@ -1152,6 +1166,12 @@ mod tests {
             );
 
             // Remote receives `Have`, `Entry` `SyncDone` messages from local
+            let authors: Vec<PublicKey> = config_a
+                .authors
+                .iter()
+                .map(|key_pair| key_pair.public_key())
+                .collect();
+
             for (index, message) in result_have.messages.iter().enumerate() {
                 let result = manager_b
                     .handle_message(&peer_id_local, message)
@@ -1163,6 +1183,14 @@ mod tests {
 
                 // We don't expect any messages anymore, Manager B didn't have any data
                 assert!(result.messages.is_empty());
+
+                // The author of every ingested entry is returned
+                match message.message() {
+                    Message::Entry(_, _) => {
+                        assert!(authors.contains(&result.ingested_author.unwrap()))
+                    }
+                    _ => assert_eq!(result.ingested_author, None),
+                }
             }
         })
     }
@@ -9,8 +9,8 @@ use serde::ser::SerializeSeq;
 use serde::Serialize;
 
 use crate::replication::{
-    MessageType, Mode, SchemaIdSet, SessionId, ENTRY_TYPE, HAVE_TYPE, SYNC_DONE_TYPE,
-    SYNC_REQUEST_TYPE,
+    Compression, MessageType, Mode, SchemaIdSet, SessionId, COMPRESSED_ENTRIES_TYPE, ENTRY_TYPE,
+    HAVE_TYPE, SYNC_DONE_TYPE, SYNC_REQUEST_TYPE,
 };
 
 pub type LiveMode = bool;
@@ -23,6 +23,7 @@ pub enum Message {
     Entry(EncodedEntry, Option<EncodedOperation>),
     SyncDone(LiveMode),
     Have(Vec<LogHeights>),
+    CompressedEntries(Compression, Vec<u8>),
 }
 
 impl Message {
@@ -32,6 +33,7 @@ impl Message {
             Message::Entry(_, _) => ENTRY_TYPE,
             Message::SyncDone(_) => SYNC_DONE_TYPE,
             Message::Have(_) => HAVE_TYPE,
+            Message::CompressedEntries(_, _) => COMPRESSED_ENTRIES_TYPE,
         }
     }
 }
@@ -46,6 +48,13 @@ impl Human for Message {
                     .collect();
                 format!("Have({log_heights:?})")
             }
+            Message::CompressedEntries(compression, bytes) => {
+                format!(
+                    "CompressedEntries({}, {} bytes)",
+                    compression.display(),
+                    bytes.len()
+                )
+            }
             message => format!("{message:?}"),
         }
     }
@@ -113,6 +122,12 @@ impl Serialize for SyncMessage {
                 seq.serialize_element(log_heights)?;
                 seq.end()
             }
+            Message::CompressedEntries(compression, bytes) => {
+                let mut seq = serialize_header(serializer.serialize_seq(Some(4))?)?;
+                seq.serialize_element(compression)?;
+                seq.serialize_element(serde_bytes::Bytes::new(bytes))?;
+                seq.end()
+            }
         }
     }
 }
@@ -126,7 +141,7 @@ mod tests {
     use p2panda_rs::test_utils::fixtures::public_key;
     use rstest::rstest;
 
-    use crate::replication::{Mode, SchemaIdSet};
+    use crate::replication::{Compression, Mode, SchemaIdSet};
     use crate::test_utils::helpers::random_schema_id_set;
 
     use super::{Message, SyncMessage};
@@ -160,5 +175,13 @@ mod tests {
                 )]
             ]))
         );
+
+        assert_eq!(
+            serialize_from(SyncMessage::new(
+                51,
+                Message::CompressedEntries(Compression::Deflate, vec![1, 2, 3])
+            )),
+            serialize_value(cbor!([4, 51, 0, serde_bytes::Bytes::new(&[1, 2, 3])]))
+        );
     }
 }
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 mod announcement;
+mod compression;
+mod dry_run;
 pub mod errors;
 mod ingest;
 mod manager;
@@ -12,7 +14,9 @@ mod session;
 mod strategies;
 pub mod traits;
 
-pub use announcement::{now, Announcement, AnnouncementMessage};
+pub use announcement::{Announcement, AnnouncementMessage};
+pub use compression::{Compression, CompressionStats, EntryBatch};
+pub use dry_run::{DryRunSchemaStats, DryRunStats};
 pub use ingest::SyncIngest;
 pub use manager::SyncManager;
 pub use message::{LogHeights, Message, SyncMessage};
@@ -20,7 +24,9 @@ pub use mode::Mode;
 pub use schema_id_set::SchemaIdSet;
 pub use service::replication_service;
 pub use session::{Session, SessionId, SessionState};
-pub use strategies::{LogHeightStrategy, SetReconciliationStrategy, StrategyResult};
+pub use strategies::{
+    LogHeightStrategy, SetReconciliationStrategy, ShallowStrategy, StrategyResult,
+};
 
 pub type MessageType = u64;
 
@@ -29,6 +35,7 @@ pub const ANNOUNCE_TYPE: MessageType = 0;
 pub const SYNC_REQUEST_TYPE: MessageType = 1;
 pub const ENTRY_TYPE: MessageType = 2;
 pub const SYNC_DONE_TYPE: MessageType = 3;
+pub const COMPRESSED_ENTRIES_TYPE: MessageType = 4;
 pub const HAVE_TYPE: MessageType = 10;
 
 /// Currently supported p2panda replication protocol version.
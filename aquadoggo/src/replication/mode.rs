@@ -9,6 +9,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub enum Mode {
     LogHeight,
     SetReconciliation,
+    Shallow,
     Unknown,
 }
 
@@ -17,6 +18,7 @@ impl Mode {
         match self {
             Mode::LogHeight => "log-height",
             Mode::SetReconciliation => "set-reconciliation",
+            Mode::Shallow => "shallow",
             Mode::Unknown => "unknown",
         }
     }
@@ -25,6 +27,7 @@ impl Mode {
         match self {
             Mode::LogHeight => 0,
             Mode::SetReconciliation => 1,
+            Mode::Shallow => 2,
             Mode::Unknown => unreachable!("Can't create an unknown replication mode"),
         }
     }
@@ -35,6 +38,7 @@ impl From<u64> for Mode {
         match value {
             0 => Mode::LogHeight,
             1 => Mode::SetReconciliation,
+            2 => Mode::Shallow,
             _ => Mode::Unknown,
         }
     }
@@ -83,6 +87,7 @@ mod tests {
     fn u64_representation() {
         assert_eq!(Mode::LogHeight.as_u64(), 0);
         assert_eq!(Mode::SetReconciliation.as_u64(), 1);
+        assert_eq!(Mode::Shallow.as_u64(), 2);
     }
 
     #[test]
@@ -1,31 +1,44 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use std::collections::HashMap;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use libp2p::PeerId;
 use log::{debug, info, trace, warn};
+use p2panda_rs::entry::EncodedEntry;
+use p2panda_rs::identity::PublicKey;
+use p2panda_rs::schema::SchemaId;
 use p2panda_rs::Human;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 use tokio::task;
 use tokio::time::interval;
 use tokio_stream::wrappers::{BroadcastStream, IntervalStream};
 use tokio_stream::StreamExt;
 
 use crate::bus::{ServiceMessage, ServiceSender};
+#[cfg(feature = "chaos")]
+use crate::chaos::Faults;
+use crate::config::Configuration;
 use crate::context::Context;
+use crate::db::types::PeerAuthor;
 use crate::db::SqlStore;
 use crate::manager::{ServiceReadySender, Shutdown};
 use crate::network::identity::to_libp2p_peer_id;
 use crate::network::{Peer, PeerMessage};
-use crate::replication::errors::ReplicationError;
+use crate::replication::compression::{batch_size, MAX_COMPRESSED_BATCH_LENGTH};
+use crate::replication::errors::{CompressionError, ReplicationError};
+use crate::replication::ingest::verify_signatures;
+use crate::replication::manager::SUPPORTED_MODES;
 use crate::replication::{
-    now, Announcement, AnnouncementMessage, Message, Mode, SchemaIdSet, Session, SessionId,
-    SyncIngest, SyncManager, SyncMessage,
+    Announcement, AnnouncementMessage, Compression, CompressionStats, EntryBatch, Message, Mode,
+    SchemaIdSet, Session, SessionId, SyncIngest, SyncManager, SyncMessage,
 };
 use crate::schema::SchemaProvider;
+use crate::status::NodeStatus;
+use crate::utils::now;
+use crate::AllowList;
 
 /// Maximum number of peers we replicate with at one a time.
 const MAX_PEER_SAMPLE: usize = 3;
@@ -36,6 +49,10 @@ const MAX_SESSIONS_PER_PEER: usize = 3;
 /// How often does the scheduler check for initiating replication sessions with peers.
 const UPDATE_INTERVAL: Duration = Duration::from_secs(5);
 
+/// How long a negotiated target set stays valid before we compute it again, even when neither we
+/// nor the peer announced anything new.
+const NEGOTIATION_EXPIRY: Duration = Duration::from_secs(10 * 60);
+
 pub async fn replication_service(
     context: Context,
     shutdown: Shutdown,
@@ -47,8 +64,10 @@ pub async fn replication_service(
     let manager = ConnectionManager::new(
         &context.schema_provider,
         &context.store,
+        &context.status,
         &tx,
         to_libp2p_peer_id(&context.key_pair.public_key()),
+        &context.config,
     );
     let handle = task::spawn(manager.run());
 
@@ -82,20 +101,66 @@ struct PeerStatus {
 
     /// Number of failed replication sessions.
     failed_count: usize,
+
+    /// Size of entries we've sent compressed to this peer.
+    sent_compression: CompressionStats,
+
+    /// Size of compressed entries we've received from this peer.
+    received_compression: CompressionStats,
+
+    /// Point in time from which on we announce ourselves and initiate replication sessions with
+    /// this peer.
+    ready_at: Instant,
+
+    /// Target set we've negotiated with this peer based on both announcements.
+    negotiated: Option<NegotiatedTargetSet>,
 }
 
 impl PeerStatus {
-    pub fn new(peer: Peer) -> Self {
+    pub fn new(peer: Peer, ready_at: Instant) -> Self {
         Self {
             peer,
+            ready_at,
             announcement: None,
             sent_our_announcement_timestamp: 0,
             successful_count: 0,
             failed_count: 0,
+            sent_compression: CompressionStats::default(),
+            received_compression: CompressionStats::default(),
+            negotiated: None,
         }
     }
 }
 
+/// Cached intersection of our and a peer's supported schema ids.
+///
+/// The target set only changes when one of both sides announces a new state, until then we can
+/// reuse it for every replication session we initiate with that peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NegotiatedTargetSet {
+    /// Schema ids supported by both sides.
+    target_set: SchemaIdSet,
+
+    /// Timestamp of our announcement this target set was negotiated with.
+    local_timestamp: u64,
+
+    /// Timestamp of the peer's announcement this target set was negotiated with.
+    remote_timestamp: u64,
+
+    /// Point in time after which we negotiate the target set again.
+    expires_at: Instant,
+}
+
+impl NegotiatedTargetSet {
+    /// Returns true if the target set was negotiated with the given announcements and did not
+    /// expire yet.
+    fn is_valid(&self, local: &Announcement, remote: &Announcement, now: Instant) -> bool {
+        self.local_timestamp == local.timestamp
+            && self.remote_timestamp == remote.timestamp
+            && self.expires_at > now
+    }
+}
+
 /// Coordinates peer connections and replication sessions.
 ///
 /// This entails:
@@ -127,9 +192,41 @@ struct ConnectionManager {
     /// Provider to retrieve our currently supported schema ids.
     schema_provider: SchemaProvider,
 
+    /// Supported schema ids we announce to peers and replicate with them.
+    replicate_schema_ids: AllowList<SchemaId>,
+
+    /// Peers we accept announcements and replication sessions from.
+    allow_peer_ids: AllowList<PeerId>,
+
+    /// Peers we never announce ourselves to or replicate with.
+    block_peer_ids: Vec<PeerId>,
+
+    /// Store to record which authors we received from which peers.
+    store: SqlStore,
+
+    /// Entries of authors received from peers since they were last written to the store.
+    peer_authors: HashMap<(PeerId, PublicKey), PeerAuthor>,
+
+    /// Status of the node, informed about the target sets we negotiated with peers.
+    status: NodeStatus,
+
     /// Our latest announcement state we want to propagate to all current and future peers. It
     /// contains a list of schema ids we're supporting as a node.
     announcement: Option<Announcement>,
+
+    /// Compression algorithm we use for sending entries to peers which support it.
+    compression: Option<Compression>,
+
+    /// End of the warm-up window after startup. Announcements and first replication attempts with
+    /// peers connecting before are spread over the remaining window.
+    warmup_until: Instant,
+
+    /// True if we request only the current state of documents from peers supporting it.
+    shallow: bool,
+
+    /// Faults injected into messages exchanged with other peers.
+    #[cfg(feature = "chaos")]
+    faults: Faults,
 }
 
 impl ConnectionManager {
@@ -137,11 +234,17 @@ impl ConnectionManager {
     pub fn new(
         schema_provider: &SchemaProvider,
         store: &SqlStore,
+        status: &NodeStatus,
         tx: &ServiceSender,
         local_peer_id: PeerId,
+        config: &Configuration,
     ) -> Self {
         let local_peer = Peer::new_local_peer(local_peer_id);
-        let ingest = SyncIngest::new(schema_provider.clone(), tx.clone());
+        let mut ingest = SyncIngest::new(schema_provider.clone(), tx.clone())
+            .with_backlog(status.task_backlog());
+        if config.replication_dry_run {
+            ingest = ingest.with_dry_run(status.dry_run_stats());
+        }
         let sync_manager = SyncManager::new(store.clone(), ingest, local_peer);
         let scheduler = IntervalStream::new(interval(UPDATE_INTERVAL));
 
@@ -152,26 +255,68 @@ impl ConnectionManager {
             tx: tx.clone(),
             rx: BroadcastStream::new(tx.subscribe()),
             schema_provider: schema_provider.clone(),
+            replicate_schema_ids: config.replicate_schema_ids.clone(),
+            allow_peer_ids: config.replication_allow_peer_ids.clone(),
+            block_peer_ids: config.replication_block_peer_ids.clone(),
+            store: store.clone(),
+            peer_authors: HashMap::new(),
+            status: status.clone(),
             announcement: None,
+            compression: config.replication_compression,
+            warmup_until: Instant::now() + config.replication_warmup,
+            shallow: config.replication_shallow,
+            #[cfg(feature = "chaos")]
+            faults: store.faults.clone(),
         }
     }
 
-    /// Returns set of schema ids we are interested in and support on this node.
+    /// Returns set of schema ids we support on this node and are interested in replicating.
     async fn supported_schema_ids(&self) -> SchemaIdSet {
-        let supported_schema_ids = self.schema_provider.supported_schema_ids().await;
+        let mut supported_schema_ids = self.schema_provider.supported_schema_ids().await;
+        if let AllowList::Set(replicate_schema_ids) = &self.replicate_schema_ids {
+            supported_schema_ids.retain(|schema_id| replicate_schema_ids.contains(schema_id));
+        }
         SchemaIdSet::new(&supported_schema_ids)
     }
 
+    /// Returns true if we replicate with the given peer.
+    fn is_peer_allowed(&self, peer_id: &PeerId) -> bool {
+        if self.block_peer_ids.contains(peer_id) {
+            return false;
+        }
+
+        match &self.allow_peer_ids {
+            AllowList::Wildcard => true,
+            AllowList::Set(allow_peer_ids) => allow_peer_ids.contains(peer_id),
+        }
+    }
+
     /// Register a new peer connection on the manager.
     async fn on_connection_established(&mut self, peer: Peer) {
         info!("Established connection with peer: {}", peer.display());
 
+        // Peers we don't replicate with are never registered, this way we neither announce
+        // ourselves to them nor initiate replication sessions
+        if !self.is_peer_allowed(&peer.id()) {
+            debug!("Ignore peer {} not allowed to replicate", peer.display());
+            return;
+        }
+
         match self.peers.get(&peer) {
             Some(_) => {
                 warn!("Peer already known: {}", peer.display());
             }
             None => {
-                self.peers.insert(peer, PeerStatus::new(peer));
+                let ready_at = ready_at(Instant::now(), self.warmup_until, thread_rng().gen());
+                if ready_at > Instant::now() {
+                    debug!(
+                        "Delay announcement and replication with peer {} by {}s during warm-up",
+                        peer.display(),
+                        (ready_at - Instant::now()).as_secs()
+                    );
+                }
+
+                self.peers.insert(peer, PeerStatus::new(peer, ready_at));
                 self.on_update().await;
             }
         }
@@ -184,6 +329,9 @@ impl ConnectionManager {
 
         // Check if we can establish replication sessions with peers
         self.update_sessions().await;
+
+        // Write authors received from peers during running sessions to the store
+        self.flush_peer_authors().await;
     }
 
     /// Handle a peer connection closing.
@@ -237,10 +385,59 @@ impl ConnectionManager {
         }
     }
 
-    /// Route incoming replication messages to the right session.
+    /// Handle incoming replication messages, unpacking compressed entries first.
     async fn on_replication_message(&mut self, peer: Peer, message: SyncMessage) {
         let session_id = message.session_id();
 
+        let messages = match message.message() {
+            Message::CompressedEntries(compression, bytes) => {
+                let entries = match self.decompress_entries(&peer, compression, bytes) {
+                    Ok(entries) => entries,
+                    Err(err) => {
+                        self.on_replication_error(peer, session_id, err.into())
+                            .await;
+                        return;
+                    }
+                };
+
+                // Verify all signatures of the batch at once before ingesting the entries
+                let encoded_entries: Vec<EncodedEntry> =
+                    entries.iter().map(|(entry, _)| entry.clone()).collect();
+
+                match verify_signatures(&encoded_entries).await {
+                    Ok(()) => entries
+                        .into_iter()
+                        .map(|(entry_bytes, operation_bytes)| {
+                            SyncMessage::new(
+                                session_id,
+                                Message::Entry(entry_bytes, operation_bytes),
+                            )
+                        })
+                        .collect(),
+                    Err(err) => {
+                        self.on_replication_error(peer, session_id, err.into())
+                            .await;
+                        return;
+                    }
+                }
+            }
+            _ => vec![message],
+        };
+
+        for message in messages {
+            // Stop processing the remaining entries as soon as the session failed
+            if !self.route_replication_message(peer, message).await {
+                return;
+            }
+        }
+    }
+
+    /// Route incoming replication messages to the right session.
+    ///
+    /// Returns `false` if handling the message failed.
+    async fn route_replication_message(&mut self, peer: Peer, message: SyncMessage) -> bool {
+        let session_id = message.session_id();
+
         // If this is a SyncRequest message first we check if the contained target set matches our
         // own locally configured one.
         if let Message::SyncRequest(_, target_set) = message.message() {
@@ -260,36 +457,186 @@ impl ConnectionManager {
                 self.on_replication_error(peer, session_id, ReplicationError::UnsupportedTargetSet)
                     .await;
 
-                return;
+                return false;
             }
         }
 
         match self.sync_manager.handle_message(&peer, &message).await {
             Ok(result) => {
-                for message in result.messages {
+                for message in self.compress_messages(&peer, result.messages) {
                     self.send_service_message(ServiceMessage::SentMessage(
                         peer,
                         PeerMessage::SyncMessage(message),
                     ));
                 }
 
+                if let Some(public_key) = result.ingested_author {
+                    self.on_author_received(peer, &public_key);
+                }
+
                 if result.is_done {
                     self.on_replication_finished(peer, session_id).await;
                 }
+
+                true
             }
             Err(err) => {
                 self.on_replication_error(peer, session_id, err).await;
+                false
             }
         }
     }
 
+    /// Returns the compression algorithm we can use for sending entries to that peer.
+    fn negotiated_compression(&self, peer: &Peer) -> Option<Compression> {
+        let compression = self.compression?;
+        let announcement = self.peers.get(peer)?.announcement.as_ref()?;
+
+        if announcement.supported_compressions.contains(&compression) {
+            Some(compression)
+        } else {
+            None
+        }
+    }
+
+    /// Compresses consecutive entries into batches if the peer supports our compression.
+    fn compress_messages(&mut self, peer: &Peer, messages: Vec<SyncMessage>) -> Vec<SyncMessage> {
+        let compression = match self.negotiated_compression(peer) {
+            Some(compression) => compression,
+            None => return messages,
+        };
+
+        let mut stats = CompressionStats::default();
+        let mut compressed_messages = Vec::new();
+        let mut batch: Option<(SessionId, EntryBatch)> = None;
+
+        // Replace the entries collected so far with one compressed message
+        let mut flush = |batch: &mut Option<(SessionId, EntryBatch)>, result: &mut Vec<_>| {
+            if let Some((session_id, entries)) = batch.take() {
+                let bytes = compression.compress(&entries);
+                stats.add(batch_size(&entries), bytes.len());
+                result.push(SyncMessage::new(
+                    session_id,
+                    Message::CompressedEntries(compression, bytes),
+                ));
+            }
+        };
+
+        for message in messages {
+            match message.message() {
+                Message::Entry(entry_bytes, operation_bytes) => {
+                    let (_, entries) =
+                        batch.get_or_insert_with(|| (message.session_id(), Vec::new()));
+                    entries.push((entry_bytes.clone(), operation_bytes.clone()));
+
+                    if entries.len() == MAX_COMPRESSED_BATCH_LENGTH {
+                        flush(&mut batch, &mut compressed_messages);
+                    }
+                }
+                _ => {
+                    flush(&mut batch, &mut compressed_messages);
+                    compressed_messages.push(message);
+                }
+            }
+        }
+
+        flush(&mut batch, &mut compressed_messages);
+
+        if let Some(status) = self.peers.get_mut(peer) {
+            status.sent_compression.uncompressed_bytes += stats.uncompressed_bytes;
+            status.sent_compression.compressed_bytes += stats.compressed_bytes;
+        }
+
+        compressed_messages
+    }
+
+    /// Decompresses a batch of entries received from a peer.
+    fn decompress_entries(
+        &mut self,
+        peer: &Peer,
+        compression: &Compression,
+        bytes: &[u8],
+    ) -> Result<EntryBatch, CompressionError> {
+        if self.compression.as_ref() != Some(compression) {
+            return Err(CompressionError::Unsupported);
+        }
+
+        let entries = compression.decompress(bytes)?;
+
+        if let Some(status) = self.peers.get_mut(peer) {
+            status
+                .received_compression
+                .add(batch_size(&entries), bytes.len());
+        }
+
+        Ok(entries)
+    }
+
+    /// Remember that we received an entry of this author from this peer.
+    ///
+    /// This helps operators to understand where data came from, for example when investigating
+    /// spam. Entries are only counted here, the counts get written to the store when the session
+    /// ends or with the next scheduler beat.
+    fn on_author_received(&mut self, peer: Peer, public_key: &PublicKey) {
+        let timestamp = now();
+
+        self.peer_authors
+            .entry((peer.id(), public_key.to_owned()))
+            .and_modify(|peer_author| {
+                peer_author.last_seen = timestamp;
+                peer_author.entries += 1;
+            })
+            .or_insert_with(|| PeerAuthor {
+                peer_id: peer.id().to_string(),
+                public_key: public_key.to_owned(),
+                first_seen: timestamp,
+                last_seen: timestamp,
+                entries: 1,
+            });
+    }
+
+    /// Writes the entries of authors received from peers since the last call to the store.
+    async fn flush_peer_authors(&mut self) {
+        if self.peer_authors.is_empty() {
+            return;
+        }
+
+        let peer_authors: Vec<PeerAuthor> = self
+            .peer_authors
+            .drain()
+            .map(|(_, peer_author)| peer_author)
+            .collect();
+
+        if let Err(err) = self.store.record_peer_authors(&peer_authors).await {
+            warn!("Could not record authors received from peers: {}", err);
+        }
+    }
+
     /// Handle successful replication sessions.
     async fn on_replication_finished(&mut self, peer: Peer, _session_id: SessionId) {
         debug!("Finished replication with peer {}", peer.display());
 
+        self.flush_peer_authors().await;
+
         match self.peers.get_mut(&peer) {
             Some(status) => {
                 status.successful_count += 1;
+
+                if let Some(ratio) = status.sent_compression.ratio() {
+                    debug!(
+                        "Compression ratio of entries sent to peer {}: {:.2}",
+                        peer.display(),
+                        ratio
+                    );
+                }
+
+                if let Some(ratio) = status.received_compression.ratio() {
+                    debug!(
+                        "Compression ratio of entries received from peer {}: {:.2}",
+                        peer.display(),
+                        ratio
+                    );
+                }
             }
             None => {
                 panic!("Tried to access unknown peer");
@@ -320,6 +667,7 @@ impl ConnectionManager {
         }
 
         self.sync_manager.remove_session(&peer, &session_id);
+        self.flush_peer_authors().await;
 
         // Inform network service about error, so it can accordingly react
         self.send_service_message(ServiceMessage::ReplicationFailed(peer));
@@ -328,22 +676,77 @@ impl ConnectionManager {
     /// Generates our new announcement state we can then propagate to all known and future peers.
     async fn update_announcement(&mut self) {
         let supported_schema_ids = self.supported_schema_ids().await;
-        self.announcement = Some(Announcement::new(supported_schema_ids));
+        self.announcement = Some(Announcement::new(
+            supported_schema_ids,
+            self.compression.into_iter().collect(),
+            SUPPORTED_MODES
+                .iter()
+                .filter(|mode| mode != &&Mode::LogHeight)
+                .cloned()
+                .collect(),
+        ));
+    }
+
+    /// Negotiate the target sets with all peers we received an announcement from.
+    ///
+    /// Target sets are cached per peer and only negotiated again when we or the peer announced a
+    /// new state or the cached one expired.
+    fn negotiate_target_sets(&mut self) {
+        let local_announcement = self
+            .announcement
+            .as_ref()
+            .expect("Announcement state needs to be set with 'update_announcement'");
+
+        let current_time = Instant::now();
+        for (peer, status) in self.peers.iter_mut() {
+            let remote_announcement = match &status.announcement {
+                Some(announcement) => announcement,
+                None => continue,
+            };
+
+            if status.negotiated.as_ref().is_some_and(|negotiated| {
+                negotiated.is_valid(local_announcement, remote_announcement, current_time)
+            }) {
+                continue;
+            }
+
+            trace!("Negotiate target set with peer {}", peer.display());
+
+            let target_set = SchemaIdSet::from_intersection(
+                &local_announcement.supported_schema_ids,
+                &remote_announcement.supported_schema_ids,
+            );
+
+            self.status.on_target_set_negotiated(
+                peer.id(),
+                target_set.iter().cloned().collect(),
+                now(),
+            );
+
+            status.negotiated = Some(NegotiatedTargetSet {
+                target_set,
+                local_timestamp: local_announcement.timestamp,
+                remote_timestamp: remote_announcement.timestamp,
+                expires_at: current_time + NEGOTIATION_EXPIRY,
+            });
+        }
     }
 
     /// Determine if we can attempt new replication sessions with the peers we currently know
     /// about.
     async fn update_sessions(&mut self) {
-        let local_supported_schema_ids = &self
-            .announcement
-            .as_ref()
-            .expect("Announcement state needs to be set with 'update_announcement'")
-            .supported_schema_ids;
+        self.negotiate_target_sets();
 
         // De-duplicate peer connections based on peer ids as we only need to pick one connection
         // per peer.
         let mut dedup_peers: HashMap<PeerId, (Peer, PeerStatus)> = HashMap::new();
+        let current_time = Instant::now();
         for (peer, peer_status) in self.peers.iter() {
+            // Wait with peers connected during warm-up until it is their turn
+            if peer_status.ready_at > current_time {
+                continue;
+            }
+
             dedup_peers.insert(peer.id(), (*peer, peer_status.to_owned()));
         }
 
@@ -353,21 +756,11 @@ impl ConnectionManager {
             .filter_map(|(peer, status)| {
                 let sessions = self.sync_manager.get_sessions(peer);
 
-                // 1. Did we already receive this peers announcement state? If not we can't do
-                //    anything yet and need to wait.
-                let remote_supported_schema_ids: SchemaIdSet =
-                    if let Some(announcement) = status.clone().announcement {
-                        announcement.supported_schema_ids
-                    } else {
-                        return None;
-                    };
-
-                // 2. Calculate intersection of local and remote schema id sets. Do we have any
-                //    supported schema id's in common?
-                let target_set = SchemaIdSet::from_intersection(
-                    local_supported_schema_ids,
-                    &remote_supported_schema_ids,
-                );
+                // 1. Did we already receive this peers announcement state and negotiated a
+                //    target set? If not we can't do anything yet and need to wait.
+                let target_set = status.negotiated.as_ref()?.target_set.clone();
+
+                // 2. Do we have any supported schema id's in common?
                 if target_set.is_empty() {
                     return None;
                 }
@@ -413,7 +806,12 @@ impl ConnectionManager {
             .as_ref()
             .expect("Announcement state needs to be set with 'update_announcement'");
 
+        let current_time = Instant::now();
         for (peer, status) in &self.peers {
+            if status.ready_at > current_time {
+                continue;
+            }
+
             if status.sent_our_announcement_timestamp < local_announcement.timestamp {
                 self.send_service_message(ServiceMessage::SentMessage(
                     *peer,
@@ -423,17 +821,38 @@ impl ConnectionManager {
         }
 
         for (_, status) in self.peers.iter_mut() {
+            if status.ready_at > current_time {
+                continue;
+            }
+
             if status.sent_our_announcement_timestamp < local_announcement.timestamp {
                 status.sent_our_announcement_timestamp = now();
             }
         }
     }
 
+    /// Returns the replication mode we use for initiating sessions with that peer.
+    fn negotiated_mode(&self, peer: &Peer) -> Mode {
+        let supports_shallow = self
+            .peers
+            .get(peer)
+            .and_then(|status| status.announcement.as_ref())
+            .is_some_and(|announcement| announcement.supported_modes.contains(&Mode::Shallow));
+
+        if self.shallow && supports_shallow {
+            Mode::Shallow
+        } else {
+            Mode::LogHeight
+        }
+    }
+
     /// Initiate a new replication session with remote peer.
     async fn initiate_replication(&mut self, peer: &Peer, target_set: &SchemaIdSet) {
+        let mode = self.negotiated_mode(peer);
+
         match self
             .sync_manager
-            .initiate_session(peer, target_set, &Mode::LogHeight)
+            .initiate_session(peer, target_set, &mode)
             .await
         {
             Ok(messages) => {
@@ -452,6 +871,11 @@ impl ConnectionManager {
 
     /// Handles incoming messages from other services via the bus.
     async fn handle_service_message(&mut self, message: ServiceMessage) {
+        #[cfg(feature = "chaos")]
+        if matches!(message, ServiceMessage::ReceivedMessage(..)) && self.faults.drop_message() {
+            return;
+        }
+
         match message {
             ServiceMessage::PeerConnected(peer) => {
                 self.on_connection_established(peer).await;
@@ -459,6 +883,12 @@ impl ConnectionManager {
             ServiceMessage::PeerDisconnected(peer) => {
                 self.on_connection_closed(peer).await;
             }
+            ServiceMessage::ReceivedMessage(peer, _) if !self.is_peer_allowed(&peer.id()) => {
+                debug!(
+                    "Ignore message from peer {} not allowed to replicate",
+                    peer.display()
+                );
+            }
             ServiceMessage::ReceivedMessage(peer, message) => match message {
                 PeerMessage::SyncMessage(message) => {
                     self.on_replication_message(peer, message).await;
@@ -466,6 +896,8 @@ impl ConnectionManager {
                 PeerMessage::Announce(message) => {
                     self.on_announcement_message(peer, message).await;
                 }
+                // Federation messages are handled by the network service
+                PeerMessage::Federation(_) => (),
             },
             _ => (), // Ignore all other messages
         }
@@ -473,6 +905,11 @@ impl ConnectionManager {
 
     /// Sends a message on the bus to other services.
     fn send_service_message(&self, message: ServiceMessage) {
+        #[cfg(feature = "chaos")]
+        if matches!(message, ServiceMessage::SentMessage(..)) && self.faults.drop_message() {
+            return;
+        }
+
         if self.tx.send(message).is_err() {
             // Silently fail here as we don't care if the message was received at this
             // point
@@ -497,6 +934,7 @@ impl ConnectionManager {
                     }
                     // Command channel closed, thus shutting down the network event loop
                     None => {
+                        self.flush_peer_authors().await;
                         return
                     },
                 },
@@ -515,29 +953,52 @@ impl ConnectionManager {
     }
 }
 
+/// Returns the point in time from which on we announce ourselves and replicate with a newly
+/// connected peer.
+///
+/// Peers connecting before the end of the warm-up window are placed within the remaining window,
+/// depending on the given fraction between 0 and 1, all others are served immediately. Peers which
+/// are not ready yet get picked up by one of the next scheduler beats.
+fn ready_at(now: Instant, warmup_until: Instant, fraction: f64) -> Instant {
+    if now >= warmup_until {
+        return now;
+    }
+
+    now + (warmup_until - now).mul_f64(fraction.clamp(0.0, 1.0))
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
+    use std::time::{Duration, Instant};
 
     use libp2p::swarm::ConnectionId;
     use libp2p::PeerId;
     use p2panda_rs::document::DocumentViewId;
+    use p2panda_rs::entry::EncodedEntry;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::operation::EncodedOperation;
     use p2panda_rs::schema::{SchemaId, SchemaName};
-    use p2panda_rs::test_utils::fixtures::random_document_view_id;
+    use p2panda_rs::test_utils::fixtures::{
+        encoded_entry, encoded_operation, key_pair, random_document_view_id,
+    };
     use rstest::rstest;
     use tokio::sync::broadcast;
 
     use crate::bus::ServiceMessage;
+    use crate::config::Configuration;
     use crate::network::{Peer, PeerMessage};
     use crate::replication::service::PeerStatus;
     use crate::replication::{
-        Announcement, AnnouncementMessage, Message, Mode, SchemaIdSet, SyncMessage,
+        Announcement, AnnouncementMessage, Compression, Message, Mode, SchemaIdSet, SyncMessage,
     };
     use crate::schema::SchemaProvider;
+    #[cfg(feature = "chaos")]
+    use crate::test_utils::{inject_faults, FaultSettings};
     use crate::test_utils::{test_runner, TestNode};
     use crate::AllowList;
 
-    use super::ConnectionManager;
+    use super::{ready_at, ConnectionManager};
 
     #[test]
     fn peer_lifetime() {
@@ -552,8 +1013,10 @@ mod tests {
             let mut manager = ConnectionManager::new(
                 &node.context.schema_provider,
                 &node.context.store,
+                &node.context.status,
                 &tx,
                 local_peer_id,
+                &Configuration::default(),
             );
 
             let supported_schema_ids = manager.supported_schema_ids().await;
@@ -582,14 +1045,16 @@ mod tests {
                 Ok(ServiceMessage::SentMessage(
                     remote_peer,
                     PeerMessage::Announce(AnnouncementMessage::new(Announcement::new(
-                        supported_schema_ids.clone()
+                        supported_schema_ids.clone(),
+                        vec![],
+                        vec![Mode::Shallow],
                     )))
                 ))
             );
 
             // Peer informs us about its target set
             assert_eq!(status.announcement, None);
-            let announcement = Announcement::new(supported_schema_ids.clone());
+            let announcement = Announcement::new(supported_schema_ids.clone(), vec![], vec![]);
             manager
                 .handle_service_message(ServiceMessage::ReceivedMessage(
                     remote_peer,
@@ -615,6 +1080,413 @@ mod tests {
         });
     }
 
+    #[test]
+    fn cache_negotiated_target_sets() {
+        let local_peer_id =
+            PeerId::from_str("12D3KooWD3JAiSNrVGxjC7vJCcjwS8egbtJV9kzrstxLRKiwb9UY").unwrap();
+        let remote_peer_id =
+            PeerId::from_str("12D3KooWCqtLMJQLY3sm9rpDampJ2nPLswPPZto3mrRY7794QATF").unwrap();
+
+        test_runner(move |node: TestNode| async move {
+            let (tx, _rx) = broadcast::channel::<ServiceMessage>(10);
+
+            let mut manager = ConnectionManager::new(
+                &node.context.schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration::default(),
+            );
+            manager.update_announcement().await;
+
+            let remote_peer = Peer::new(remote_peer_id, ConnectionId::new_unchecked(1));
+            manager
+                .handle_service_message(ServiceMessage::PeerConnected(remote_peer))
+                .await;
+
+            // Nothing to negotiate before the peer announced itself
+            assert_eq!(manager.peers.get(&remote_peer).unwrap().negotiated, None);
+
+            let target_set = SchemaIdSet::new(&[SchemaId::SchemaDefinition(1)]);
+            let mut announcement = Announcement::new(target_set.clone(), vec![], vec![]);
+            announcement.timestamp = 1;
+            manager
+                .handle_service_message(ServiceMessage::ReceivedMessage(
+                    remote_peer,
+                    PeerMessage::Announce(AnnouncementMessage::new(announcement)),
+                ))
+                .await;
+            manager.update_sessions().await;
+
+            let negotiated = manager
+                .peers
+                .get(&remote_peer)
+                .unwrap()
+                .negotiated
+                .clone()
+                .expect("Target set to be negotiated");
+            assert_eq!(negotiated.target_set, target_set);
+            assert_eq!(negotiated.remote_timestamp, 1);
+
+            // Negotiated target set is exposed in the node status
+            let peers = node.context.status.peers();
+            assert_eq!(peers.len(), 1);
+            assert_eq!(peers[0].peer_id, remote_peer_id);
+            assert_eq!(peers[0].target_set, vec![SchemaId::SchemaDefinition(1)]);
+
+            // Cached target set is kept as long as no side announced anything new
+            manager.update_sessions().await;
+            assert_eq!(
+                manager.peers.get(&remote_peer).unwrap().negotiated,
+                Some(negotiated.clone())
+            );
+
+            // Peer announces a new state and we negotiate again
+            let target_set = SchemaIdSet::new(&[SchemaId::SchemaFieldDefinition(1)]);
+            let mut announcement = Announcement::new(target_set.clone(), vec![], vec![]);
+            announcement.timestamp = 2;
+            manager
+                .handle_service_message(ServiceMessage::ReceivedMessage(
+                    remote_peer,
+                    PeerMessage::Announce(AnnouncementMessage::new(announcement)),
+                ))
+                .await;
+            manager.update_sessions().await;
+
+            let negotiated = manager
+                .peers
+                .get(&remote_peer)
+                .unwrap()
+                .negotiated
+                .clone()
+                .expect("Target set to be negotiated");
+            assert_eq!(negotiated.target_set, target_set);
+            assert_eq!(negotiated.remote_timestamp, 2);
+
+            // Expired target sets get negotiated again as well
+            manager
+                .peers
+                .get_mut(&remote_peer)
+                .unwrap()
+                .negotiated
+                .as_mut()
+                .unwrap()
+                .expires_at = Instant::now();
+            manager.update_sessions().await;
+            assert!(
+                manager
+                    .peers
+                    .get(&remote_peer)
+                    .unwrap()
+                    .negotiated
+                    .as_ref()
+                    .unwrap()
+                    .expires_at
+                    > Instant::now()
+            );
+        });
+    }
+
+    #[test]
+    fn replicate_selected_schemas() {
+        let local_peer_id =
+            PeerId::from_str("12D3KooWD3JAiSNrVGxjC7vJCcjwS8egbtJV9kzrstxLRKiwb9UY").unwrap();
+        let remote_peer_id =
+            PeerId::from_str("12D3KooWCqtLMJQLY3sm9rpDampJ2nPLswPPZto3mrRY7794QATF").unwrap();
+
+        test_runner(move |node: TestNode| async move {
+            let (tx, _rx) = broadcast::channel::<ServiceMessage>(10);
+
+            let mut manager = ConnectionManager::new(
+                &node.context.schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration {
+                    replicate_schema_ids: AllowList::Set(vec![SchemaId::SchemaDefinition(1)]),
+                    ..Configuration::default()
+                },
+            );
+            manager.update_announcement().await;
+
+            // We only announce the supported schemas we want to replicate
+            let replicated_set = SchemaIdSet::new(&[SchemaId::SchemaDefinition(1)]);
+            assert_eq!(manager.supported_schema_ids().await, replicated_set);
+
+            let remote_peer = Peer::new(remote_peer_id, ConnectionId::new_unchecked(1));
+            manager
+                .handle_service_message(ServiceMessage::PeerConnected(remote_peer))
+                .await;
+
+            // Target set does not contain schemas we don't replicate, even if both sides support
+            // them
+            let remote_set = SchemaIdSet::new(&[
+                SchemaId::SchemaDefinition(1),
+                SchemaId::SchemaFieldDefinition(1),
+            ]);
+            manager
+                .handle_service_message(ServiceMessage::ReceivedMessage(
+                    remote_peer,
+                    PeerMessage::Announce(AnnouncementMessage::new(Announcement::new(
+                        remote_set,
+                        vec![],
+                        vec![],
+                    ))),
+                ))
+                .await;
+            manager.update_sessions().await;
+
+            let negotiated = manager
+                .peers
+                .get(&remote_peer)
+                .unwrap()
+                .negotiated
+                .clone()
+                .expect("Target set to be negotiated");
+            assert_eq!(negotiated.target_set, replicated_set);
+
+            // Schemas we don't replicate are still supported by the node
+            assert!(node
+                .context
+                .schema_provider
+                .supported_schema_ids()
+                .await
+                .contains(&SchemaId::SchemaFieldDefinition(1)));
+
+            // Nothing is announced when replication is disabled
+            let manager = ConnectionManager::new(
+                &node.context.schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration {
+                    replicate_schema_ids: AllowList::Set(vec![]),
+                    ..Configuration::default()
+                },
+            );
+            assert!(manager.supported_schema_ids().await.is_empty());
+        });
+    }
+
+    #[test]
+    fn ignore_peers_not_allowed_to_replicate() {
+        let local_peer_id =
+            PeerId::from_str("12D3KooWD3JAiSNrVGxjC7vJCcjwS8egbtJV9kzrstxLRKiwb9UY").unwrap();
+        let allowed_peer_id =
+            PeerId::from_str("12D3KooWCqtLMJQLY3sm9rpDampJ2nPLswPPZto3mrRY7794QATF").unwrap();
+        let other_peer_id =
+            PeerId::from_str("12D3KooWL8NK5LiLBRVMxq8P8yf5ALQDqDZ8Lkm8WfCDk5aqrT6N").unwrap();
+
+        test_runner(move |node: TestNode| async move {
+            for (allow_peer_ids, block_peer_ids) in [
+                (AllowList::Set(vec![allowed_peer_id]), vec![]),
+                (AllowList::Wildcard, vec![other_peer_id]),
+            ] {
+                let (tx, mut rx) = broadcast::channel::<ServiceMessage>(10);
+
+                let mut manager = ConnectionManager::new(
+                    &node.context.schema_provider,
+                    &node.context.store,
+                    &node.context.status,
+                    &tx,
+                    local_peer_id,
+                    &Configuration {
+                        replication_allow_peer_ids: allow_peer_ids.clone(),
+                        replication_block_peer_ids: block_peer_ids.clone(),
+                        ..Configuration::default()
+                    },
+                );
+                let supported_schema_ids = manager.supported_schema_ids().await;
+                manager.update_announcement().await;
+
+                // We neither register nor announce ourselves to peers we don't replicate with
+                let other_peer = Peer::new(other_peer_id, ConnectionId::new_unchecked(1));
+                manager
+                    .handle_service_message(ServiceMessage::PeerConnected(other_peer))
+                    .await;
+                assert_eq!(manager.peers.len(), 0);
+                assert_eq!(rx.len(), 0);
+
+                // Their sync requests are ignored
+                manager
+                    .handle_service_message(ServiceMessage::ReceivedMessage(
+                        other_peer,
+                        PeerMessage::SyncMessage(SyncMessage::new(
+                            0,
+                            Message::SyncRequest(Mode::LogHeight, supported_schema_ids.clone()),
+                        )),
+                    ))
+                    .await;
+                assert_eq!(rx.len(), 0);
+                assert_eq!(manager.sync_manager.get_sessions(&other_peer).len(), 0);
+
+                // Allowed peers are replicated with as usual
+                let allowed_peer = Peer::new(allowed_peer_id, ConnectionId::new_unchecked(2));
+                manager
+                    .handle_service_message(ServiceMessage::PeerConnected(allowed_peer))
+                    .await;
+                assert_eq!(manager.peers.len(), 1);
+                assert_eq!(rx.len(), 1);
+                rx.recv().await.unwrap();
+            }
+        });
+    }
+
+    #[rstest]
+    fn count_peer_authors_in_memory(key_pair: KeyPair) {
+        let local_peer_id =
+            PeerId::from_str("12D3KooWD3JAiSNrVGxjC7vJCcjwS8egbtJV9kzrstxLRKiwb9UY").unwrap();
+        let remote_peer_id =
+            PeerId::from_str("12D3KooWCqtLMJQLY3sm9rpDampJ2nPLswPPZto3mrRY7794QATF").unwrap();
+
+        test_runner(move |node: TestNode| async move {
+            let (tx, _rx) = broadcast::channel::<ServiceMessage>(10);
+
+            let mut manager = ConnectionManager::new(
+                &node.context.schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration::default(),
+            );
+
+            let remote_peer = Peer::new(remote_peer_id, ConnectionId::new_unchecked(1));
+            manager.on_author_received(remote_peer, &key_pair.public_key());
+            manager.on_author_received(remote_peer, &key_pair.public_key());
+
+            // Nothing gets written to the database for every single entry
+            let store = &node.context.store;
+            assert!(store.get_peer_authors().await.unwrap().is_empty());
+
+            manager.flush_peer_authors().await;
+            let peer_authors = store.get_peer_authors().await.unwrap();
+            assert_eq!(peer_authors.len(), 1);
+            assert_eq!(peer_authors[0].peer_id, remote_peer_id.to_string());
+            assert_eq!(peer_authors[0].entries, 2);
+
+            // Counts start from zero again after they were written
+            manager.on_author_received(remote_peer, &key_pair.public_key());
+            manager.flush_peer_authors().await;
+            assert_eq!(store.get_peer_authors().await.unwrap()[0].entries, 3);
+        });
+    }
+
+    #[test]
+    fn ready_at_within_warmup() {
+        let now = Instant::now();
+        let warmup_until = now + Duration::from_secs(60);
+
+        assert_eq!(ready_at(now, warmup_until, 0.0), now);
+        assert_eq!(
+            ready_at(now, warmup_until, 0.5),
+            now + Duration::from_secs(30)
+        );
+        assert_eq!(ready_at(now, warmup_until, 1.0), warmup_until);
+
+        // Peers connecting after the warm-up window are served immediately
+        let later = warmup_until + Duration::from_secs(1);
+        assert_eq!(ready_at(later, warmup_until, 0.5), later);
+    }
+
+    #[test]
+    fn delay_peers_during_warmup() {
+        let local_peer_id =
+            PeerId::from_str("12D3KooWD3JAiSNrVGxjC7vJCcjwS8egbtJV9kzrstxLRKiwb9UY").unwrap();
+        let remote_peer_id =
+            PeerId::from_str("12D3KooWCqtLMJQLY3sm9rpDampJ2nPLswPPZto3mrRY7794QATF").unwrap();
+
+        test_runner(move |node: TestNode| async move {
+            let (tx, rx) = broadcast::channel::<ServiceMessage>(10);
+
+            let mut manager = ConnectionManager::new(
+                &node.context.schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration {
+                    replication_warmup: Duration::from_secs(60 * 60),
+                    ..Configuration::default()
+                },
+            );
+            manager.update_announcement().await;
+
+            let remote_peer = Peer::new(remote_peer_id, ConnectionId::new_unchecked(1));
+            manager
+                .handle_service_message(ServiceMessage::PeerConnected(remote_peer))
+                .await;
+
+            // Manager does not announce itself to peer yet
+            assert_eq!(manager.peers.len(), 1);
+            assert_eq!(rx.len(), 0);
+
+            // Manager announces itself on the next beat after it is the peer's turn
+            manager.peers.get_mut(&remote_peer).unwrap().ready_at = Instant::now();
+            manager.on_update().await;
+            assert_eq!(rx.len(), 1);
+        });
+    }
+
+    #[cfg(feature = "chaos")]
+    #[test]
+    fn drop_messages_on_injected_faults() {
+        let local_peer_id =
+            PeerId::from_str("12D3KooWD3JAiSNrVGxjC7vJCcjwS8egbtJV9kzrstxLRKiwb9UY").unwrap();
+        let remote_peer_id =
+            PeerId::from_str("12D3KooWCqtLMJQLY3sm9rpDampJ2nPLswPPZto3mrRY7794QATF").unwrap();
+
+        test_runner(move |node: TestNode| async move {
+            let (tx, rx) = broadcast::channel::<ServiceMessage>(10);
+
+            let mut manager = ConnectionManager::new(
+                &node.context.schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration::default(),
+            );
+            manager.update_announcement().await;
+
+            inject_faults(
+                &node,
+                FaultSettings {
+                    message_drop_rate: 1.0,
+                    ..FaultSettings::default()
+                },
+            );
+
+            // Our announcement to the new peer gets dropped
+            let remote_peer = Peer::new(remote_peer_id, ConnectionId::new_unchecked(1));
+            manager
+                .handle_service_message(ServiceMessage::PeerConnected(remote_peer))
+                .await;
+            assert_eq!(rx.len(), 0);
+
+            // .. and so does the announcement of the peer
+            let supported_schema_ids = manager.supported_schema_ids().await;
+            manager
+                .handle_service_message(ServiceMessage::ReceivedMessage(
+                    remote_peer,
+                    PeerMessage::Announce(AnnouncementMessage::new(Announcement::new(
+                        supported_schema_ids,
+                        vec![],
+                        vec![],
+                    ))),
+                ))
+                .await;
+            let status = manager
+                .peers
+                .get(&remote_peer)
+                .expect("Peer to be registered in connection manager");
+            assert_eq!(status.announcement, None);
+        });
+    }
+
     #[rstest]
     fn unsupported_schema(#[from(random_document_view_id)] document_view_id: DocumentViewId) {
         let local_peer_id =
@@ -626,15 +1498,21 @@ mod tests {
             let (tx, mut rx) = broadcast::channel::<ServiceMessage>(10);
 
             let schema_provider = SchemaProvider::new(vec![], AllowList::Set(vec![]));
-            let mut manager =
-                ConnectionManager::new(&schema_provider, &node.context.store, &tx, local_peer_id);
+            let mut manager = ConnectionManager::new(
+                &schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration::default(),
+            );
             manager.update_announcement().await;
 
             let remote_peer = Peer::new(remote_peer_id, ConnectionId::new_unchecked(1));
 
             manager
                 .peers
-                .insert(remote_peer, PeerStatus::new(remote_peer));
+                .insert(remote_peer, PeerStatus::new(remote_peer, Instant::now()));
 
             let unsupported_schema_id = SchemaId::new_application(
                 &SchemaName::new("bad_schema").unwrap(),
@@ -661,4 +1539,70 @@ mod tests {
             assert_eq!(manager.sync_manager.get_sessions(&remote_peer).len(), 0);
         });
     }
+
+    #[rstest]
+    fn compress_entries(encoded_entry: EncodedEntry, encoded_operation: EncodedOperation) {
+        let local_peer_id =
+            PeerId::from_str("12D3KooWD3JAiSNrVGxjC7vJCcjwS8egbtJV9kzrstxLRKiwb9UY").unwrap();
+        let remote_peer_id =
+            PeerId::from_str("12D3KooWCqtLMJQLY3sm9rpDampJ2nPLswPPZto3mrRY7794QATF").unwrap();
+
+        test_runner(move |node: TestNode| async move {
+            let (tx, _rx) = broadcast::channel::<ServiceMessage>(10);
+
+            let mut manager = ConnectionManager::new(
+                &node.context.schema_provider,
+                &node.context.store,
+                &node.context.status,
+                &tx,
+                local_peer_id,
+                &Configuration {
+                    replication_compression: Some(Compression::Deflate),
+                    ..Configuration::default()
+                },
+            );
+            manager.update_announcement().await;
+            let supported_schema_ids = manager.supported_schema_ids().await;
+
+            let remote_peer = Peer::new(remote_peer_id, ConnectionId::new_unchecked(1));
+            manager
+                .peers
+                .insert(remote_peer, PeerStatus::new(remote_peer, Instant::now()));
+
+            let entry = Message::Entry(encoded_entry.clone(), Some(encoded_operation.clone()));
+            let messages = vec![
+                SyncMessage::new(0, entry.clone()),
+                SyncMessage::new(0, entry.clone()),
+                SyncMessage::new(0, Message::SyncDone(false)),
+            ];
+
+            // Peer did not announce support for compression yet
+            assert_eq!(
+                manager.compress_messages(&remote_peer, messages.clone()),
+                messages
+            );
+
+            manager.peers.get_mut(&remote_peer).unwrap().announcement = Some(Announcement::new(
+                supported_schema_ids,
+                vec![Compression::Deflate],
+                vec![],
+            ));
+
+            let compressed = manager.compress_messages(&remote_peer, messages.clone());
+            assert_eq!(compressed.len(), 2);
+            assert_eq!(compressed[1], messages[2]);
+
+            let entries = match compressed[0].message() {
+                Message::CompressedEntries(compression, bytes) => manager
+                    .decompress_entries(&remote_peer, compression, bytes)
+                    .unwrap(),
+                _ => panic!("Expected compressed entries"),
+            };
+            assert_eq!(entries, vec![(encoded_entry, Some(encoded_operation)); 2]);
+
+            let status = manager.peers.get(&remote_peer).unwrap();
+            assert!(status.sent_compression.ratio().is_some());
+            assert_eq!(status.sent_compression, status.received_compression);
+        });
+    }
 }
@@ -9,7 +9,8 @@ use crate::db::SqlStore;
 use crate::replication::errors::ReplicationError;
 use crate::replication::traits::Strategy;
 use crate::replication::{
-    LogHeightStrategy, Message, Mode, SchemaIdSet, SetReconciliationStrategy, StrategyResult,
+    LogHeightStrategy, Message, Mode, SchemaIdSet, SetReconciliationStrategy, ShallowStrategy,
+    StrategyResult,
 };
 use crate::schema::SchemaProvider;
 
@@ -61,6 +62,7 @@ impl Session {
         let strategy: Box<dyn Strategy> = match mode {
             Mode::LogHeight => Box::new(LogHeightStrategy::new(target_set, schema_provider)),
             Mode::SetReconciliation => Box::new(SetReconciliationStrategy::new()),
+            Mode::Shallow => Box::new(ShallowStrategy::new(target_set, schema_provider)),
             Mode::Unknown => panic!("Unknown replication mode"),
         };
 
@@ -22,7 +22,7 @@ use crate::replication::traits::Strategy;
 use crate::replication::{LogHeights, Message, Mode, SchemaIdSet, StrategyResult};
 use crate::schema::SchemaProvider;
 
-type SortedIndex = i32;
+pub(super) type SortedIndex = i32;
 
 fn has_blob_relation(schema: &Schema) -> bool {
     for (_, field_type) in schema.fields().iter() {
@@ -43,7 +43,7 @@ fn has_blob_relation(schema: &Schema) -> bool {
 
 /// Retrieve entries from the store, group the result by document id and then sub-order them by
 /// their sorted index.
-async fn retrieve_entries(
+pub(super) async fn retrieve_entries(
     store: &SqlStore,
     remote_needs: &[LogHeights],
 ) -> Vec<(StorageEntry, DocumentId, SortedIndex)> {
@@ -113,7 +113,7 @@ impl LogHeightStrategy {
     ///
     /// For example, a target set including the schema id `[img_0020, blob_v1]` would look at all
     /// `img_0020` documents and only include blobs which they relate to.
-    async fn included_document_ids(&self, store: &SqlStore) -> Vec<DocumentId> {
+    pub(super) async fn included_document_ids(&self, store: &SqlStore) -> Vec<DocumentId> {
         let wants_blobs = self.target_set().contains(&SchemaId::Blob(1));
         let wants_blob_pieces = self.target_set().contains(&SchemaId::BlobPiece(1));
         let mut all_target_documents = vec![];
@@ -181,7 +181,7 @@ impl LogHeightStrategy {
 
     // Calculate the heights of all logs which contain contributions to documents in the current
     // `SchemaIdSet`.
-    async fn local_log_heights(
+    pub(super) async fn local_log_heights(
         &self,
         store: &SqlStore,
         included_documents: &[DocumentId],
@@ -199,7 +199,7 @@ impl LogHeightStrategy {
     // the remote needs to bring their state in line with our own. The returned entries are
     // grouped by document and ordered by the `sorted_index` of the operations they carry. With
     // this ordering they can be ingested and validated easily on the remote.
-    async fn entry_responses(
+    pub(super) async fn entry_responses(
         &self,
         store: &SqlStore,
         remote_log_heights: &[LogHeights],
@@ -218,23 +218,27 @@ impl LogHeightStrategy {
 
         let entries = retrieve_entries(store, &remote_needs).await;
 
-        // Compose the actual messages.
-        entries
-            .iter()
-            .map(|(entry, _, _)| {
-                trace!(
-                    "Prepare message containing entry at {:?} on {:?} for {}",
-                    entry.seq_num(),
-                    entry.log_id(),
-                    entry.public_key().display()
-                );
-
-                Message::Entry(entry.clone().encoded_entry, entry.payload().cloned())
-            })
-            .collect()
+        entry_messages(&entries)
     }
 }
 
+/// Compose entry messages from retrieved entries, keeping their order.
+pub(super) fn entry_messages(entries: &[(StorageEntry, DocumentId, SortedIndex)]) -> Vec<Message> {
+    entries
+        .iter()
+        .map(|(entry, _, _)| {
+            trace!(
+                "Prepare message containing entry at {:?} on {:?} for {}",
+                entry.seq_num(),
+                entry.log_id(),
+                entry.public_key().display()
+            );
+
+            Message::Entry(entry.clone().encoded_entry, entry.payload().cloned())
+        })
+        .collect()
+}
+
 #[async_trait]
 impl Strategy for LogHeightStrategy {
     fn mode(&self) -> Mode {
@@ -3,10 +3,12 @@
 mod diff;
 mod log_height;
 mod set_reconciliation;
+mod shallow;
 
 pub use diff::diff_log_heights;
 pub use log_height::LogHeightStrategy;
 pub use set_reconciliation::SetReconciliationStrategy;
+pub use shallow::ShallowStrategy;
 
 use crate::replication::Message;
 
@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use p2panda_rs::document::DocumentId;
+use p2panda_rs::entry::LogId;
+use p2panda_rs::identity::PublicKey;
+use sqlx::query_scalar;
+
+use crate::db::SqlStore;
+use crate::replication::errors::ReplicationError;
+use crate::replication::strategies::diff_log_heights;
+use crate::replication::strategies::log_height::{entry_messages, retrieve_entries};
+use crate::replication::traits::Strategy;
+use crate::replication::{
+    LogHeightStrategy, LogHeights, Message, Mode, SchemaIdSet, StrategyResult,
+};
+use crate::schema::SchemaProvider;
+
+/// Replication strategy for light nodes which are only interested in the current state of
+/// documents.
+///
+/// It works like the log height strategy but leaves out documents which got deleted, unless the
+/// remote peer already holds parts of them and needs the tombstone to bring its view in line. The
+/// full history of all other documents is still sent, as bamboo entries can only be verified
+/// against their backlinks and skiplinks.
+#[derive(Clone, Debug)]
+pub struct ShallowStrategy {
+    log_height: LogHeightStrategy,
+    received_remote_have: bool,
+    sent_have: bool,
+}
+
+impl ShallowStrategy {
+    pub fn new(target_set: &SchemaIdSet, schema_provider: SchemaProvider) -> Self {
+        Self {
+            log_height: LogHeightStrategy::new(target_set, schema_provider),
+            received_remote_have: false,
+            sent_have: false,
+        }
+    }
+
+    /// Calculate the documents which should be included for the remote peer.
+    ///
+    /// Deleted documents are only included when the remote announced a log of them in its `Have`
+    /// message.
+    async fn included_document_ids(
+        &self,
+        store: &SqlStore,
+        remote_log_heights: &[LogHeights],
+    ) -> Vec<DocumentId> {
+        let included_document_ids = self.log_height.included_document_ids(store).await;
+        let deleted_document_ids = get_deleted_document_ids(store, &included_document_ids).await;
+
+        let remote_logs: HashSet<(PublicKey, LogId)> = remote_log_heights
+            .iter()
+            .flat_map(|(public_key, log_heights)| {
+                log_heights
+                    .iter()
+                    .map(move |(log_id, _)| (public_key.to_owned(), log_id.to_owned()))
+            })
+            .collect();
+
+        let mut shallow_document_ids = Vec::with_capacity(included_document_ids.len());
+        for document_id in included_document_ids {
+            if deleted_document_ids.contains(&document_id) {
+                let log_heights = self
+                    .log_height
+                    .local_log_heights(store, std::slice::from_ref(&document_id))
+                    .await;
+
+                let is_known_by_remote = log_heights.iter().any(|(public_key, log_heights)| {
+                    log_heights.iter().any(|(log_id, _)| {
+                        remote_logs.contains(&(public_key.to_owned(), log_id.to_owned()))
+                    })
+                });
+
+                if !is_known_by_remote {
+                    continue;
+                }
+            }
+
+            shallow_document_ids.push(document_id);
+        }
+
+        shallow_document_ids
+    }
+
+    // Prepare entry responses based on a remotes log heights, leaving out deleted documents the
+    // remote doesn't know about.
+    async fn entry_responses(
+        &self,
+        store: &SqlStore,
+        remote_log_heights: &[LogHeights],
+    ) -> Vec<Message> {
+        let included_document_ids = self.included_document_ids(store, remote_log_heights).await;
+
+        let local_log_heights = self
+            .log_height
+            .local_log_heights(store, &included_document_ids)
+            .await;
+
+        let remote_needs = diff_log_heights(
+            &local_log_heights,
+            &remote_log_heights.iter().cloned().collect(),
+        );
+
+        let entries = retrieve_entries(store, &remote_needs).await;
+
+        entry_messages(&entries)
+    }
+}
+
+#[async_trait]
+impl Strategy for ShallowStrategy {
+    fn mode(&self) -> Mode {
+        Mode::Shallow
+    }
+
+    fn target_set(&self) -> SchemaIdSet {
+        self.log_height.target_set()
+    }
+
+    async fn initial_messages(&mut self, store: &SqlStore) -> StrategyResult {
+        self.sent_have = true;
+        self.log_height.initial_messages(store).await
+    }
+
+    async fn handle_message(
+        &mut self,
+        store: &SqlStore,
+        message: &Message,
+    ) -> Result<StrategyResult, ReplicationError> {
+        let mut result = StrategyResult {
+            is_local_done: false,
+            messages: vec![],
+        };
+
+        // Send our Have message to remote if we haven't done it yet
+        if !self.sent_have {
+            result.merge(self.initial_messages(store).await);
+        }
+
+        match message {
+            Message::Have(remote_log_heights) => {
+                if self.received_remote_have {
+                    return Err(ReplicationError::StrategyFailed(
+                        "Received Have from remote message twice".into(),
+                    ));
+                }
+
+                let response = self.entry_responses(store, remote_log_heights).await;
+                result.messages.extend(response);
+                result.is_local_done = true;
+
+                self.received_remote_have = true;
+            }
+            _ => {
+                return Err(ReplicationError::StrategyFailed(
+                    "Received unknown message type".into(),
+                ));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Returns the ids of all given documents which got deleted.
+async fn get_deleted_document_ids(
+    store: &SqlStore,
+    document_ids: &[DocumentId],
+) -> HashSet<DocumentId> {
+    if document_ids.is_empty() {
+        return HashSet::new();
+    }
+
+    let document_ids_str: String = document_ids
+        .iter()
+        .map(|document_id| format!("'{}'", document_id.as_str()))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    query_scalar::<_, String>(&format!(
+        "
+        SELECT
+            documents.document_id
+        FROM
+            documents
+        WHERE
+            documents.document_id IN ({document_ids_str})
+            AND documents.is_deleted = true
+        "
+    ))
+    .fetch_all(&store.pool)
+    .await
+    .expect("No fatal database error to occur")
+    .iter()
+    .map(|id_string| {
+        id_string
+            .parse()
+            .expect("All document id strings stored in the database are valid")
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::SeqNum;
+    use p2panda_rs::identity::KeyPair;
+    use rstest::rstest;
+
+    use crate::replication::strategies::ShallowStrategy;
+    use crate::replication::{LogHeightStrategy, Message, SchemaIdSet};
+    use crate::test_utils::{
+        populate_and_materialize, populate_store_config, test_runner, PopulateStoreConfig, TestNode,
+    };
+
+    #[rstest]
+    fn leaves_out_unknown_deleted_documents(
+        #[from(populate_store_config)]
+        #[with(5, 2, vec![KeyPair::new()], true)] // logs include tombstone operation
+        config: PopulateStoreConfig,
+    ) {
+        test_runner(move |mut node: TestNode| async move {
+            let target_set = SchemaIdSet::new(&[config.schema.id().to_owned()]);
+            populate_and_materialize(&mut node, &config).await;
+            let store = &node.context.store;
+
+            let log_height =
+                LogHeightStrategy::new(&target_set, node.context.schema_provider.clone());
+            let shallow = ShallowStrategy::new(&target_set, node.context.schema_provider.clone());
+
+            // A full replication sends all entries including the tombstones
+            assert_eq!(log_height.entry_responses(store, &[]).await.len(), 10);
+
+            // Remote doesn't know about any of the deleted documents
+            assert!(shallow.entry_responses(store, &[]).await.is_empty());
+
+            // Remote holds the beginning of one document and receives the rest of it
+            let (public_key, log_heights) = log_height
+                .local_log_heights(store, &log_height.included_document_ids(store).await)
+                .await
+                .into_iter()
+                .next()
+                .unwrap();
+            let (log_id, _) = log_heights[0];
+            let remote_log_heights = vec![(public_key, vec![(log_id, SeqNum::new(1).unwrap())])];
+
+            let responses = shallow.entry_responses(store, &remote_log_heights).await;
+            assert_eq!(responses.len(), 4);
+            assert!(responses
+                .iter()
+                .all(|message| matches!(message, Message::Entry(_, _))));
+        });
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 
 use anyhow::{bail, Result};
 use log::{debug, info, trace};
@@ -22,7 +22,9 @@ pub struct SchemaProvider {
 
     /// Optional list of allowed schema ids. When not empty, only these schema ids will be accepted
     /// on this node, if not set _all_ schema ids are accepted (wildcard).
-    allow_schema_ids: AllowList<SchemaId>,
+    ///
+    /// The list can be extended during runtime by other nodes of the same federation group.
+    allow_schema_ids: Arc<RwLock<AllowList<SchemaId>>>,
 
     /// Sender for broadcast channel informing subscribers about updated schemas.
     tx: Sender<SchemaId>,
@@ -59,7 +61,7 @@ impl SchemaProvider {
 
         Self {
             schemas: Arc::new(Mutex::new(index)),
-            allow_schema_ids,
+            allow_schema_ids: Arc::new(RwLock::new(allow_schema_ids)),
             tx,
         }
     }
@@ -84,7 +86,7 @@ impl SchemaProvider {
     /// Returns `true` if a schema was updated or it already existed in its current state, and
     /// `false` if it was inserted.
     pub async fn update(&self, schema: Schema) -> Result<bool> {
-        if let AllowList::Set(allow_schema_ids) = &self.allow_schema_ids {
+        if let AllowList::Set(allow_schema_ids) = &*self.allow_schema_ids() {
             if !allow_schema_ids.contains(schema.id()) {
                 bail!("Attempted to add unsupported schema to schema provider");
             }
@@ -117,8 +119,9 @@ impl SchemaProvider {
     /// If no allow-list was set it returns the list of all currently known schema ids. If an
     /// allo-wlist was set it directly returns the list itself.
     pub async fn supported_schema_ids(&self) -> Vec<SchemaId> {
-        match &self.allow_schema_ids {
-            AllowList::Set(schema_ids) => schema_ids.clone(),
+        let allow_schema_ids = self.allow_schema_ids().clone();
+        match allow_schema_ids {
+            AllowList::Set(schema_ids) => schema_ids,
             AllowList::Wildcard => self
                 .all()
                 .await
@@ -131,7 +134,58 @@ impl SchemaProvider {
     /// Returns true if an allow-list of supported schema ids was provided through user
     /// configuration.
     pub fn is_allow_list_active(&self) -> bool {
-        matches!(self.allow_schema_ids, AllowList::Set(_))
+        matches!(*self.allow_schema_ids(), AllowList::Set(_))
+    }
+
+    /// Returns the allow-list of supported schema ids, it is empty if all schema ids are accepted.
+    pub fn allow_list(&self) -> Vec<SchemaId> {
+        match &*self.allow_schema_ids() {
+            AllowList::Set(schema_ids) => schema_ids.clone(),
+            AllowList::Wildcard => Vec::new(),
+        }
+    }
+
+    /// Adds the given schema ids to the allow-list of supported schema ids.
+    ///
+    /// Subscribers get informed about every newly allowed schema id. Nothing changes when no
+    /// allow-list was set, as all schema ids are accepted already.
+    ///
+    /// Returns the schema ids which were not supported before.
+    pub fn extend_allow_list(&self, schema_ids: &[SchemaId]) -> Vec<SchemaId> {
+        let mut allow_schema_ids = self
+            .allow_schema_ids
+            .write()
+            .expect("Allow-list lock is not poisoned");
+
+        let allowed = match &mut *allow_schema_ids {
+            AllowList::Wildcard => return Vec::new(),
+            AllowList::Set(allowed) => allowed,
+        };
+
+        let mut added = Vec::new();
+        for schema_id in schema_ids {
+            if !allowed.contains(schema_id) && !added.contains(schema_id) {
+                added.push(schema_id.to_owned());
+            }
+        }
+        allowed.extend(added.iter().cloned());
+        drop(allow_schema_ids);
+
+        for schema_id in &added {
+            info!("Allowing {} on this node", schema_id.display());
+
+            if self.tx.send(schema_id.to_owned()).is_err() {
+                debug!("No subscriber has been informed about allowed schema");
+            }
+        }
+
+        added
+    }
+
+    fn allow_schema_ids(&self) -> RwLockReadGuard<'_, AllowList<SchemaId>> {
+        self.allow_schema_ids
+            .read()
+            .expect("Allow-list lock is not poisoned")
     }
 }
 
@@ -222,4 +276,44 @@ mod test {
 
         assert!(provider.get(&new_schema_id).await.is_none());
     }
+
+    #[tokio::test]
+    async fn extend_allow_list() {
+        let provider = SchemaProvider::new(vec![], AllowList::Set(vec![]));
+        let mut rx = provider.on_schema_added();
+        let new_schema_id = SchemaId::Application(
+            SchemaName::new("test_schema").unwrap(),
+            random_document_view_id(),
+        );
+        let new_schema = Schema::new(
+            &new_schema_id,
+            "description",
+            &[("test_field", FieldType::String)],
+        )
+        .unwrap();
+
+        assert_eq!(
+            provider.extend_allow_list(&[new_schema_id.clone(), new_schema_id.clone()]),
+            vec![new_schema_id.clone()]
+        );
+        assert_eq!(rx.recv().await.unwrap(), new_schema_id);
+        assert_eq!(provider.allow_list(), vec![new_schema_id.clone()]);
+        assert_eq!(
+            provider.supported_schema_ids().await,
+            vec![new_schema_id.clone()]
+        );
+
+        // Schema ids are only added once
+        assert!(provider.extend_allow_list(&[new_schema_id]).is_empty());
+
+        // Newly allowed schemas can be added to the provider
+        assert!(provider.update(new_schema).await.is_ok());
+
+        // Nothing changes when all schema ids are accepted
+        let provider = SchemaProvider::default();
+        assert!(provider
+            .extend_allow_list(&[SchemaId::SchemaDefinition(1)])
+            .is_empty());
+        assert!(provider.allow_list().is_empty());
+    }
 }
@@ -0,0 +1,12 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Status of the running node, for example to display it in a client or monitor it from outside.
+//!
+//! Some values are tracked by the status service while the node is running, for example the
+//! connected peers, others are read from the database and schema provider when a report is
+//! requested.
+mod node_status;
+mod service;
+
+pub use node_status::{DocumentCountReport, NodeStatus, PeerReport, StatusReport};
+pub use service::status_service;
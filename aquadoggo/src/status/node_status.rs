@@ -0,0 +1,301 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use libp2p::PeerId;
+use p2panda_rs::schema::SchemaId;
+use sqlx::any::AnyKind;
+
+use crate::db::errors::SqlStoreError;
+use crate::db::SqlStore;
+use crate::materializer::{
+    MaterializerMetrics, PendingTasks, TaskBacklog, TaskInput, TaskTimeline,
+};
+use crate::network::Peer;
+use crate::replication::{DryRunSchemaStats, DryRunStats};
+use crate::schema::SchemaProvider;
+
+/// Version of this node implementation.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Snapshot of the node's status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusReport {
+    /// Version of the node implementation.
+    pub version: String,
+
+    /// Database backend the node is using, either "sqlite" or "postgres".
+    pub database_backend: String,
+
+    /// Number of schemas supported by this node.
+    pub supported_schemas: u64,
+
+    /// Number of distinct peers the node is connected to.
+    pub connected_peers: u64,
+
+    /// Number of materializer tasks which are queued up or currently being processed.
+    pub materializer_queue_depth: u64,
+
+    /// Target sets negotiated with connected peers, ordered by peer id.
+    pub peers: Vec<PeerReport>,
+
+    /// Number and size of entries per schema the node would have ingested when running in
+    /// replication dry-run mode, ordered by schema id.
+    pub dry_run: Vec<DryRunSchemaStats>,
+
+    /// Number of documents of every supported schema, ordered by schema id.
+    pub document_counts: Vec<DocumentCountReport>,
+}
+
+/// Number of documents of a schema stored on the node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentCountReport {
+    /// Id of the schema.
+    pub schema_id: SchemaId,
+
+    /// Number of documents which are not deleted.
+    pub documents: u64,
+}
+
+/// Target set negotiated for replication with a connected peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerReport {
+    /// Id of the peer.
+    pub peer_id: PeerId,
+
+    /// Schema ids supported by both the peer and this node.
+    pub target_set: Vec<SchemaId>,
+
+    /// UNIX timestamp in seconds of when the target set was negotiated.
+    pub negotiated_at: u64,
+}
+
+/// Aggregates the status of the node from its services.
+#[derive(Debug, Clone, Default)]
+pub struct NodeStatus {
+    /// Currently established connections to other peers.
+    ///
+    /// One peer can be connected to us via multiple connections at the same time.
+    connections: Arc<Mutex<HashSet<Peer>>>,
+
+    /// Target sets the replication service negotiated with peers.
+    negotiated: Arc<Mutex<HashMap<PeerId, PeerReport>>>,
+
+    /// Timing data of recently processed materializer tasks.
+    task_timeline: TaskTimeline<TaskInput>,
+
+    /// Number of materializer tasks waiting to be processed.
+    task_backlog: TaskBacklog,
+
+    /// Counters and durations of materializer tasks.
+    materializer_metrics: MaterializerMetrics,
+
+    /// Materializer tasks which are queued or currently being processed.
+    pending_tasks: PendingTasks<TaskInput>,
+
+    /// Entries received while running in replication dry-run mode.
+    dry_run_stats: DryRunStats,
+}
+
+impl NodeStatus {
+    /// Returns a new instance of `NodeStatus`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an established connection to a peer.
+    pub fn on_peer_connected(&self, peer: Peer) {
+        self.connections
+            .lock()
+            .expect("Could not acquire lock on node status")
+            .insert(peer);
+    }
+
+    /// Registers a closed connection to a peer.
+    ///
+    /// The negotiated target set is forgotten as soon as the last connection to that peer closed.
+    pub fn on_peer_disconnected(&self, peer: &Peer) {
+        let mut connections = self
+            .connections
+            .lock()
+            .expect("Could not acquire lock on node status");
+        connections.remove(peer);
+
+        if !connections
+            .iter()
+            .any(|connection| connection.id() == peer.id())
+        {
+            self.negotiated
+                .lock()
+                .expect("Could not acquire lock on node status")
+                .remove(&peer.id());
+        }
+    }
+
+    /// Registers a target set negotiated with a peer, replacing the previous one.
+    pub fn on_target_set_negotiated(
+        &self,
+        peer_id: PeerId,
+        target_set: Vec<SchemaId>,
+        negotiated_at: u64,
+    ) {
+        self.negotiated
+            .lock()
+            .expect("Could not acquire lock on node status")
+            .insert(
+                peer_id,
+                PeerReport {
+                    peer_id,
+                    target_set,
+                    negotiated_at,
+                },
+            );
+    }
+
+    /// Returns the target sets negotiated with peers, ordered by peer id.
+    pub fn peers(&self) -> Vec<PeerReport> {
+        let mut peers: Vec<PeerReport> = self
+            .negotiated
+            .lock()
+            .expect("Could not acquire lock on node status")
+            .values()
+            .cloned()
+            .collect();
+        peers.sort_by_key(|report| report.peer_id.to_string());
+        peers
+    }
+
+    /// Returns the number of distinct peers the node is connected to.
+    pub fn connected_peers(&self) -> u64 {
+        self.connections
+            .lock()
+            .expect("Could not acquire lock on node status")
+            .iter()
+            .map(|peer| peer.id())
+            .collect::<HashSet<_>>()
+            .len() as u64
+    }
+
+    /// Returns the timeline of recently processed materializer tasks.
+    pub fn task_timeline(&self) -> TaskTimeline<TaskInput> {
+        self.task_timeline.clone()
+    }
+
+    /// Returns the number of materializer tasks waiting to be processed.
+    pub fn task_backlog(&self) -> TaskBacklog {
+        self.task_backlog.clone()
+    }
+
+    /// Returns the counters and durations of materializer tasks.
+    pub fn materializer_metrics(&self) -> MaterializerMetrics {
+        self.materializer_metrics.clone()
+    }
+
+    /// Returns the materializer tasks which are queued or currently being processed.
+    pub fn pending_tasks(&self) -> PendingTasks<TaskInput> {
+        self.pending_tasks.clone()
+    }
+
+    /// Returns the record of entries received while running in replication dry-run mode.
+    pub fn dry_run_stats(&self) -> DryRunStats {
+        self.dry_run_stats.clone()
+    }
+
+    /// Returns the current status of the node.
+    pub async fn report(
+        &self,
+        store: &SqlStore,
+        schema_provider: &SchemaProvider,
+    ) -> Result<StatusReport, SqlStoreError> {
+        let database_backend = match store.pool.any_kind() {
+            AnyKind::Postgres => "postgres",
+            AnyKind::Sqlite => "sqlite",
+        };
+
+        let mut supported_schema_ids = schema_provider.supported_schema_ids().await;
+        supported_schema_ids.sort();
+
+        let mut document_counts = Vec::with_capacity(supported_schema_ids.len());
+        for schema_id in &supported_schema_ids {
+            document_counts.push(DocumentCountReport {
+                schema_id: schema_id.to_owned(),
+                documents: store.count_documents_by_schema(schema_id).await?,
+            });
+        }
+
+        Ok(StatusReport {
+            version: VERSION.to_owned(),
+            database_backend: database_backend.to_owned(),
+            supported_schemas: supported_schema_ids.len() as u64,
+            connected_peers: self.connected_peers(),
+            materializer_queue_depth: store.count_tasks().await?,
+            peers: self.peers(),
+            dry_run: self.dry_run_stats.report(),
+            document_counts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libp2p::swarm::ConnectionId;
+    use libp2p::PeerId;
+    use p2panda_rs::schema::SchemaId;
+
+    use crate::network::Peer;
+
+    use super::{NodeStatus, PeerReport};
+
+    #[test]
+    fn count_distinct_peers() {
+        let status = NodeStatus::new();
+        let peer_id = PeerId::random();
+        let peer_a_1 = Peer::new(peer_id, ConnectionId::new_unchecked(1));
+        let peer_a_2 = Peer::new(peer_id, ConnectionId::new_unchecked(2));
+        let peer_b = Peer::new(PeerId::random(), ConnectionId::new_unchecked(3));
+
+        status.on_peer_connected(peer_a_1);
+        status.on_peer_connected(peer_a_2);
+        status.on_peer_connected(peer_b);
+        assert_eq!(status.connected_peers(), 2);
+
+        // Peer is still connected via a second connection
+        status.on_peer_disconnected(&peer_a_1);
+        assert_eq!(status.connected_peers(), 2);
+
+        status.on_peer_disconnected(&peer_a_2);
+        status.on_peer_disconnected(&peer_b);
+        assert_eq!(status.connected_peers(), 0);
+    }
+
+    #[test]
+    fn forget_negotiated_target_sets() {
+        let status = NodeStatus::new();
+        let peer_id = PeerId::random();
+        let peer_1 = Peer::new(peer_id, ConnectionId::new_unchecked(1));
+        let peer_2 = Peer::new(peer_id, ConnectionId::new_unchecked(2));
+
+        status.on_peer_connected(peer_1);
+        status.on_peer_connected(peer_2);
+        status.on_target_set_negotiated(peer_id, vec![SchemaId::SchemaDefinition(1)], 1);
+        status.on_target_set_negotiated(peer_id, vec![SchemaId::SchemaFieldDefinition(1)], 2);
+
+        // Latest negotiation replaces the previous one
+        assert_eq!(
+            status.peers(),
+            vec![PeerReport {
+                peer_id,
+                target_set: vec![SchemaId::SchemaFieldDefinition(1)],
+                negotiated_at: 2,
+            }]
+        );
+
+        // Target set is kept as long as the peer is still connected
+        status.on_peer_disconnected(&peer_1);
+        assert_eq!(status.peers().len(), 1);
+
+        status.on_peer_disconnected(&peer_2);
+        assert!(status.peers().is_empty());
+    }
+}
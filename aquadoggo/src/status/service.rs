@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use anyhow::Result;
+use log::{debug, warn};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task;
+
+use crate::bus::{ServiceMessage, ServiceSender};
+use crate::context::Context;
+use crate::manager::{ServiceReadySender, Shutdown};
+
+/// Keeps the node status up-to-date with events from other services.
+pub async fn status_service(
+    context: Context,
+    shutdown: Shutdown,
+    tx: ServiceSender,
+    tx_ready: ServiceReadySender,
+) -> Result<()> {
+    let status = context.status.clone();
+    let mut rx = tx.subscribe();
+
+    let handle = task::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(ServiceMessage::PeerConnected(peer)) => status.on_peer_connected(peer),
+                Ok(ServiceMessage::PeerDisconnected(peer)) => status.on_peer_disconnected(&peer),
+                Ok(_) | Err(RecvError::Lagged(_)) => (),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    debug!("Status service is ready");
+    if tx_ready.send(()).is_err() {
+        warn!("No subscriber informed about status service being ready");
+    };
+
+    shutdown.await.ok();
+    handle.abort();
+
+    Ok(())
+}
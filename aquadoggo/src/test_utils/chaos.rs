@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+pub use crate::chaos::FaultSettings;
+use crate::test_utils::TestNode;
+
+/// Inject faults into the storage and network of a test node.
+pub fn inject_faults(node: &TestNode, settings: FaultSettings) {
+    node.context.store.faults.set(settings);
+}
+
+/// Stop injecting faults into the storage and network of a test node.
+pub fn reset_faults(node: &TestNode) {
+    node.context.store.faults.reset();
+}
@@ -13,6 +13,7 @@ use tokio::sync::broadcast;
 use tower::make::Shared;
 use tower_service::Service;
 
+use crate::blobs::BlobUploads;
 use crate::graphql::GraphQLSchemaManager;
 use crate::http::{build_server, HttpServiceContext};
 use crate::test_utils::TestNode;
@@ -59,11 +60,29 @@ impl TestClient {
         }
     }
 
+    pub(crate) fn head(&self, url: &str) -> RequestBuilder {
+        RequestBuilder {
+            builder: self.client.head(format!("http://{}{}", self.addr, url)),
+        }
+    }
+
     pub(crate) fn post(&self, url: &str) -> RequestBuilder {
         RequestBuilder {
             builder: self.client.post(format!("http://{}{}", self.addr, url)),
         }
     }
+
+    pub(crate) fn patch(&self, url: &str) -> RequestBuilder {
+        RequestBuilder {
+            builder: self.client.patch(format!("http://{}{}", self.addr, url)),
+        }
+    }
+
+    pub(crate) fn delete(&self, url: &str) -> RequestBuilder {
+        RequestBuilder {
+            builder: self.client.delete(format!("http://{}{}", self.addr, url)),
+        }
+    }
 }
 
 /// Configures a test client that can be used for HTTP API testing.
@@ -72,15 +91,22 @@ pub async fn http_test_client(node: &TestNode) -> TestClient {
 
     let manager = GraphQLSchemaManager::new(
         node.context.store.clone(),
-        tx,
+        tx.clone(),
         node.context.schema_provider.clone(),
+        node.context.blob_access.clone(),
+        node.context.status.clone(),
+        &node.context.config,
     )
     .await;
 
     let http_context = HttpServiceContext::new(
         node.context.store.clone(),
         manager,
-        node.context.config.blobs_base_path.to_path_buf(),
+        node.context.blobs.clone(),
+        node.context.blob_access.clone(),
+        node.context.blob_integrity.clone(),
+        BlobUploads::new(node.context.clone(), tx),
+        &node.context.config,
     );
 
     TestClient::new(build_server(http_context))
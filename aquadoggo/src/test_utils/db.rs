@@ -10,7 +10,9 @@ async fn db_from_config(config: TestConfiguration) -> (TestConfiguration, Pool)
     drop_database(&config).await;
     create_database(&config.database_url).await.unwrap();
 
-    let pool = connection_pool(&config.database_url, 1).await.unwrap();
+    let pool = connection_pool(&config.database_url, 1, None)
+        .await
+        .unwrap();
 
     if run_pending_migrations(&pool).await.is_err() {
         pool.close().await;
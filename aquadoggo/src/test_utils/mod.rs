@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+#[cfg(feature = "chaos")]
+mod chaos;
 mod client;
 mod config;
 mod db;
@@ -7,6 +9,8 @@ pub mod helpers;
 mod node;
 mod runner;
 
+#[cfg(feature = "chaos")]
+pub use chaos::{inject_faults, reset_faults, FaultSettings};
 pub use client::{http_test_client, TestClient};
 pub use config::TestConfiguration;
 pub use db::{initialize_db, initialize_sqlite_db};
@@ -49,7 +49,7 @@ async fn e2e() {
     //
     // Nodes are the workhorses of the p2panda network, we thank you for all your efforts 🙏🏻.
 
-    let aquadoggo = Node::start(key_pair, config.clone()).await; // 🐬🐕
+    let aquadoggo = Node::start(key_pair, config.clone()).await.unwrap(); // 🐬🐕
 
     // Create some authors.
     //
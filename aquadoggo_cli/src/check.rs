@@ -0,0 +1,371 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::convert::TryInto;
+use std::fmt::{self, Display};
+use std::net::{Ipv4Addr, TcpListener, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use aquadoggo::{check_database, ConfigFile, Configuration, Transport};
+use colored::Colorize;
+
+use crate::key_pair::load_key_pair_from_file;
+use crate::utils::absolute_path;
+
+/// Severity of a finding while checking the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    /// Setting is valid.
+    Ok,
+
+    /// Node will start but might not behave as expected.
+    Warning,
+
+    /// Node will fail to start.
+    Error,
+}
+
+/// Single finding while checking the configuration.
+#[derive(Debug)]
+struct Diagnostic {
+    level: Level,
+    message: String,
+
+    /// Suggestion on how to resolve the issue.
+    hint: Option<String>,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self.level {
+            Level::Ok => "✔".green(),
+            Level::Warning => "!".yellow(),
+            Level::Error => "✘".red(),
+        };
+
+        write!(f, "{} {}", symbol, self.message)?;
+
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  → {}", hint.dimmed())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Collection of all findings while checking the configuration.
+#[derive(Debug, Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn ok(&mut self, message: impl Into<String>) {
+        self.push(Level::Ok, message.into(), None);
+    }
+
+    fn warning(&mut self, message: impl Into<String>, hint: impl Into<String>) {
+        self.push(Level::Warning, message.into(), Some(hint.into()));
+    }
+
+    fn error(&mut self, message: impl Into<String>, hint: impl Into<String>) {
+        self.push(Level::Error, message.into(), Some(hint.into()));
+    }
+
+    fn push(&mut self, level: Level, message: String, hint: Option<String>) {
+        self.0.push(Diagnostic {
+            level,
+            message,
+            hint,
+        });
+    }
+
+    fn count(&self, level: Level) -> usize {
+        self.0
+            .iter()
+            .filter(|diagnostic| diagnostic.level == level)
+            .count()
+    }
+}
+
+/// Validates the full configuration and prints diagnostics to the user without starting the node.
+///
+/// Returns an error when at least one check failed, that is when the node would not be able to
+/// start with this configuration.
+pub async fn check_config(config_file_path: Option<PathBuf>, config: ConfigFile) -> Result<()> {
+    match &config_file_path {
+        Some(path) => println!(
+            "Checking config file {}\n",
+            absolute_path(path).display().to_string().blue()
+        ),
+        None => println!("Checking configuration (no config file provided)\n"),
+    }
+
+    let mut diagnostics = Diagnostics::default();
+
+    check_values(&config, &mut diagnostics);
+    check_blobs_path(&config, &mut diagnostics);
+    check_private_key(&config, &mut diagnostics);
+    check_ports(&config, &mut diagnostics);
+    check_addresses(&config, &mut diagnostics);
+    check_database_url(&config, &mut diagnostics).await;
+
+    for diagnostic in &diagnostics.0 {
+        println!("{diagnostic}");
+    }
+
+    let errors = diagnostics.count(Level::Error);
+    let warnings = diagnostics.count(Level::Warning);
+    println!();
+
+    if errors > 0 {
+        bail!("Configuration check failed with {errors} error(s) and {warnings} warning(s)");
+    }
+
+    println!(
+        "{}",
+        format!("Configuration is valid ({warnings} warning(s))").green()
+    );
+
+    Ok(())
+}
+
+/// Checks if all values can be converted into the `aquadoggo` configuration format.
+fn check_values(config: &ConfigFile, diagnostics: &mut Diagnostics) {
+    let node_config: Result<Configuration> = config.clone().try_into().map_err(Into::into);
+
+    match node_config {
+        Ok(_) => diagnostics.ok("All configuration values are valid"),
+        Err(err) => diagnostics.error(
+            format!("{err:#}"),
+            "Fix the value in your config file, environment variables or command line arguments",
+        ),
+    }
+}
+
+/// Checks if the blobs directory exists and is writable.
+fn check_blobs_path(config: &ConfigFile, diagnostics: &mut Diagnostics) {
+    if config.blobs_backend != "filesystem" {
+        return;
+    }
+
+    let path = match &config.blobs_base_path {
+        Some(path) => absolute_path(path),
+        None => {
+            diagnostics.warning(
+                "Blobs are stored in a temporary directory and not persisted",
+                "Set `blobs_base_path` to a directory to persist blobs",
+            );
+            return;
+        }
+    };
+
+    if !path.exists() {
+        diagnostics.error(
+            format!("Blobs directory {} does not exist", path.display()),
+            "Create the directory or set `blobs_base_path` to an existing one",
+        );
+    } else if !path.is_dir() {
+        diagnostics.error(
+            format!("Blobs path {} is not a directory", path.display()),
+            "Set `blobs_base_path` to a directory",
+        );
+    } else if !is_writable(&path) {
+        diagnostics.error(
+            format!("Blobs directory {} is not writable", path.display()),
+            "Check the permissions of the directory",
+        );
+    } else {
+        diagnostics.ok(format!("Blobs directory {} is writable", path.display()));
+    }
+}
+
+/// Checks if the private key file can be loaded or created.
+fn check_private_key(config: &ConfigFile, diagnostics: &mut Diagnostics) {
+    let path = match &config.private_key {
+        Some(path) => absolute_path(path),
+        None => {
+            diagnostics.ok("Ephemeral private key will be generated for this session");
+            return;
+        }
+    };
+
+    if path.is_file() {
+        match load_key_pair_from_file(path.clone()) {
+            Ok(_) => diagnostics.ok(format!("Private key {} is valid", path.display())),
+            Err(err) => diagnostics.error(
+                format!("Private key {} could not be loaded: {err}", path.display()),
+                "Make sure the file contains a hex-encoded ed25519 private key or remove it to \
+                generate a new one",
+            ),
+        }
+
+        return;
+    }
+
+    if path.exists() {
+        diagnostics.error(
+            format!("Private key path {} is not a file", path.display()),
+            "Set `private_key` to a file path",
+        );
+        return;
+    }
+
+    match path.parent() {
+        Some(dir) if dir.is_dir() && is_writable(dir) => diagnostics.ok(format!(
+            "New private key will be generated and stored at {}",
+            path.display()
+        )),
+        Some(dir) if dir.is_dir() => diagnostics.error(
+            format!("Directory {} is not writable", dir.display()),
+            "Check the permissions of the directory to store the private key in it",
+        ),
+        _ => diagnostics.error(
+            format!(
+                "Directory to store private key {} in does not exist",
+                path.display()
+            ),
+            "Create the directory or set `private_key` to a path in an existing one",
+        ),
+    }
+}
+
+/// Checks if the configured ports are still free.
+fn check_ports(config: &ConfigFile, diagnostics: &mut Diagnostics) {
+    if TcpListener::bind((Ipv4Addr::UNSPECIFIED, config.http_port)).is_ok() {
+        diagnostics.ok(format!("HTTP port {} is free", config.http_port));
+    } else {
+        diagnostics.warning(
+            format!("HTTP port {} is already in use", config.http_port),
+            "The node will fall back to a random port, set `http_port` to a free port to make \
+            the API reachable under a known address",
+        );
+    }
+
+    let (is_free, protocol) = match config.transport {
+        Transport::QUIC => (
+            UdpSocket::bind((Ipv4Addr::UNSPECIFIED, config.node_port)).is_ok(),
+            "UDP",
+        ),
+        Transport::TCP => (
+            TcpListener::bind((Ipv4Addr::UNSPECIFIED, config.node_port)).is_ok(),
+            "TCP",
+        ),
+    };
+
+    if is_free {
+        diagnostics.ok(format!(
+            "Node port {} ({protocol}) is free",
+            config.node_port
+        ));
+    } else {
+        diagnostics.warning(
+            format!(
+                "Node port {} ({protocol}) is already in use",
+                config.node_port
+            ),
+            "The node will fall back to a random port, set `node_port` to a free port to make \
+            the node reachable under a known address",
+        );
+    }
+}
+
+/// Checks if the relay and direct node addresses can be resolved.
+fn check_addresses(config: &ConfigFile, diagnostics: &mut Diagnostics) {
+    let addresses = config
+        .relay_addresses
+        .iter()
+        .map(|address| ("relay_addresses", address))
+        .chain(
+            config
+                .direct_node_addresses
+                .iter()
+                .map(|address| ("direct_node_addresses", address)),
+        );
+
+    for (key, address) in addresses {
+        let resolved = address
+            .to_socket_addrs()
+            .map(|mut socket_addrs| socket_addrs.next());
+
+        match resolved {
+            Ok(Some(socket_addr)) => {
+                diagnostics.ok(format!("Address '{address}' resolves to {socket_addr}"))
+            }
+            Ok(None) => diagnostics.error(
+                format!("Address '{address}' in `{key}` did not resolve to any IP address"),
+                "Check if the hostname is correct",
+            ),
+            Err(err) => diagnostics.error(
+                format!("Invalid address '{address}' in `{key}`: {err}"),
+                "Addresses need to be given as IP:PORT or HOSTNAME:PORT, for example \
+                \"192.0.2.0:2022\"",
+            ),
+        }
+    }
+}
+
+/// Checks if the database can be reached.
+async fn check_database_url(config: &ConfigFile, diagnostics: &mut Diagnostics) {
+    let url = &config.database_url;
+    if url == "sqlite::memory:" || url.contains("mode=memory") {
+        diagnostics.ok("Database is kept in memory, data is not persisted");
+        return;
+    }
+
+    match check_database(url).await {
+        Ok(true) => diagnostics.ok("Database is reachable"),
+        Ok(false) => diagnostics.ok("Database does not exist yet and will be created on start"),
+        Err(err) => diagnostics.error(
+            format!("Database could not be reached: {err}"),
+            "Check the `database_url` connection string and make sure the database server is \
+            running",
+        ),
+    }
+}
+
+/// Returns true if files can be created in the given directory.
+fn is_writable(dir: &Path) -> bool {
+    tempfile::tempfile_in(dir).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use aquadoggo::ConfigFile;
+    use tempfile::TempDir;
+
+    use super::{check_addresses, check_blobs_path, check_private_key, Diagnostics, Level};
+
+    #[test]
+    fn diagnose_invalid_paths_and_addresses() {
+        let tmp_dir = TempDir::new().unwrap();
+        let private_key_path = tmp_dir.path().join("private-key.txt");
+        std::fs::write(&private_key_path, "not a private key").unwrap();
+
+        let config = ConfigFile {
+            blobs_base_path: Some(tmp_dir.path().join("does-not-exist")),
+            private_key: Some(private_key_path),
+            relay_addresses: vec!["127.0.0.1:2022".into(), "no-port".into()],
+            ..ConfigFile::default()
+        };
+
+        let mut diagnostics = Diagnostics::default();
+        check_blobs_path(&config, &mut diagnostics);
+        check_private_key(&config, &mut diagnostics);
+        check_addresses(&config, &mut diagnostics);
+
+        let levels: Vec<Level> = diagnostics.0.iter().map(|d| d.level).collect();
+        assert_eq!(
+            levels,
+            vec![Level::Error, Level::Error, Level::Ok, Level::Error]
+        );
+
+        // A valid directory passes the check
+        let config = ConfigFile {
+            blobs_base_path: Some(tmp_dir.path().to_path_buf()),
+            ..ConfigFile::default()
+        };
+
+        let mut diagnostics = Diagnostics::default();
+        check_blobs_path(&config, &mut diagnostics);
+        assert_eq!(diagnostics.count(Level::Error), 0);
+    }
+}
@@ -3,8 +3,8 @@
 use std::path::PathBuf;
 
 use anyhow::{bail, Result};
-use aquadoggo::{AllowList, ConfigFile, Configuration};
-use clap::{crate_version, Parser};
+use aquadoggo::{AllowList, BlobBackendConfiguration, ConfigFile, Configuration};
+use clap::{crate_version, Parser, Subcommand};
 use colored::Colorize;
 use directories::ProjectDirs;
 use figment::providers::{Env, Format, Serialized, Toml};
@@ -25,11 +25,13 @@ type ConfigFilePath = Option<PathBuf>;
 /// ones).
 ///
 /// Returns a partly unchecked configuration object which results from all of these sources. It
-/// still needs to be converted for aquadoggo as it might still contain invalid values.
-pub fn load_config() -> Result<(ConfigFilePath, ConfigFile)> {
+/// still needs to be converted for aquadoggo as it might still contain invalid values. Next to it
+/// the optional subcommand is returned which was passed via the command line.
+pub fn load_config() -> Result<(Option<Command>, ConfigFilePath, ConfigFile)> {
     // Parse command line arguments and CONFIG environment variable first to get optional config
     // file path
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    let command = cli.command.take();
 
     // Determine if a config file path was provided or if we should look for it in common locations
     let config_file_path: ConfigFilePath = match &cli.config {
@@ -53,7 +55,7 @@ pub fn load_config() -> Result<(ConfigFilePath, ConfigFile)> {
         .merge(Serialized::defaults(cli))
         .extract()?;
 
-    Ok((config_file_path, config))
+    Ok((command, config_file_path, config))
 }
 
 /// Configuration derived from command line arguments.
@@ -69,6 +71,10 @@ pub fn load_config() -> Result<(ConfigFilePath, ConfigFile)> {
     version
 )]
 struct Cli {
+    #[command(subcommand)]
+    #[serde(skip)]
+    command: Option<Command>,
+
     /// Path to an optional "config.toml" file for further configuration.
     ///
     /// When not set the program will try to find a `config.toml` file in the same folder the
@@ -141,6 +147,14 @@ struct Cli {
     #[serde(skip_serializing_if = "Option::is_none")]
     blobs_base_path: Option<PathBuf>,
 
+    /// Storage backend for blob files, either "filesystem" or "s3". Defaults to "filesystem".
+    ///
+    /// When set to "s3", blob files are stored in an S3-compatible object storage bucket. Use the
+    /// config file or environment variables to configure the `blobs_s3_*` connection settings.
+    #[arg(long, value_name = "BACKEND")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blobs_backend: Option<String>,
+
     /// Path to persist your ed25519 private key file. Defaults to an ephemeral key only for this
     /// current session.
     ///
@@ -253,6 +267,21 @@ struct Cli {
     log_level: Option<String>,
 }
 
+/// Subcommands of the command line interface. Without any subcommand the node gets started.
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Inspect the configuration of the node.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+/// Subcommands to inspect the configuration of the node.
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigCommand {
+    /// Validate the full configuration and print diagnostics without starting the node.
+    Check,
+}
+
 /// Clap converts wildcard symbols from command line arguments (for example --supported-schema-ids
 /// "*") into an array, (["*"]), but we need it to be just a string ("*").
 fn serialize_with_wildcard<S>(
@@ -372,6 +401,16 @@ pub fn print_config(
         "PostgreSQL".into()
     };
 
+    let blobs_backend = match &config.blobs_backend {
+        BlobBackendConfiguration::Filesystem => format!(
+            "filesystem: {}",
+            absolute_path(&config.blobs_base_path).display()
+        ),
+        BlobBackendConfiguration::S3(s3_config) => {
+            format!("S3: {} ({})", s3_config.bucket, s3_config.endpoint)
+        }
+    };
+
     let mdns = if config.network.mdns {
         "enabled"
     } else {
@@ -400,6 +439,7 @@ pub fn print_config(
     format!(
         r"Allow schema IDs: {}
 Database URL: {}
+Blobs: {}
 mDNS: {}
 Private key: {}
 Relay mode: {}
@@ -409,6 +449,7 @@ Node is ready!
 ",
         allow_schema_ids.blue(),
         database_url.blue(),
+        blobs_backend.blue(),
         mdns.blue(),
         private_key.blue(),
         relay_mode.blue(),
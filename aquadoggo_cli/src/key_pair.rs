@@ -65,7 +65,7 @@ fn save_key_pair_to_file(key_pair: &KeyPair, path: PathBuf) -> Result<()> {
 /// Loads a private key from a file at the given path and derives ed25519 key pair from it.
 ///
 /// The private key in the file needs to be represented as a hex-encoded string.
-fn load_key_pair_from_file(path: PathBuf) -> Result<KeyPair> {
+pub fn load_key_pair_from_file(path: PathBuf) -> Result<KeyPair> {
     let mut file = File::open(path)?;
     let mut private_key_hex = String::new();
     file.read_to_string(&mut private_key_hex)?;
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+mod check;
 mod config;
 mod key_pair;
 mod utils;
@@ -8,17 +9,24 @@ use std::convert::TryInto;
 use std::str::FromStr;
 
 use anyhow::Context;
-use aquadoggo::{AllowList, Configuration, Node, Transport};
+use aquadoggo::{AllowList, BlobBackendConfiguration, Configuration, Node, Transport};
 use env_logger::WriteStyle;
 use log::{warn, LevelFilter};
 
-use crate::config::{load_config, print_config};
+use crate::check::check_config;
+use crate::config::{load_config, print_config, Command, ConfigCommand};
 use crate::key_pair::{generate_ephemeral_key_pair, generate_or_load_key_pair};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Load configuration from command line arguments, environment variables and .toml file
-    let (config_file_path, config) = load_config().context("Could not load configuration")?;
+    let (command, config_file_path, config) =
+        load_config().context("Could not load configuration")?;
+
+    // Only validate the configuration without starting the node
+    if let Some(Command::Config(ConfigCommand::Check)) = command {
+        return check_config(config_file_path, config).await;
+    }
 
     // Remember if user did not set a blobs directory path, which means that it will default to a
     // temporary one
@@ -57,7 +65,9 @@ async fn main() -> anyhow::Result<()> {
     show_warnings(&node_config, is_temporary_blobs_path);
 
     // Start p2panda node in async runtime
-    let node = Node::start(key_pair, node_config).await;
+    let node = Node::start(key_pair, node_config)
+        .await
+        .context("Could not start node")?;
 
     // Run this until [CTRL] + [C] got pressed or something went wrong
     tokio::select! {
@@ -106,7 +116,9 @@ fn show_warnings(config: &Configuration, is_temporary_blobs_path: bool) {
     let is_temporary_database =
         config.database_url == "sqlite::memory:" || config.database_url.contains("mode=memory");
 
-    if !is_temporary_database && is_temporary_blobs_path {
+    let is_filesystem_blobs_backend = config.blobs_backend == BlobBackendConfiguration::Filesystem;
+
+    if !is_temporary_database && is_filesystem_blobs_backend && is_temporary_blobs_path {
         warn!(
             "Your database is persisted but blobs _are not_ which might result in unrecoverable
         data inconsistency (blob operations are stored but the files themselves are _not_). It is
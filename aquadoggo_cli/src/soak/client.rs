@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use p2panda_rs::document::DocumentViewId;
+use p2panda_rs::entry::encode::sign_and_encode_entry;
+use p2panda_rs::entry::traits::AsEncodedEntry;
+use p2panda_rs::entry::{LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::KeyPair;
+use p2panda_rs::operation::encode::encode_operation;
+use p2panda_rs::operation::traits::Actionable;
+use p2panda_rs::operation::Operation;
+use p2panda_rs::schema::{FieldType, Schema, SchemaId, SchemaName};
+use serde_json::{json, Value};
+
+/// GraphQL client talking to the HTTP API of the node under test.
+#[derive(Debug, Clone)]
+pub struct GraphQLClient {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+impl GraphQLClient {
+    pub fn new(http_port: u16) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: format!("http://127.0.0.1:{http_port}/graphql"),
+        }
+    }
+
+    /// Sends a GraphQL query and returns the data of the response.
+    pub async fn query(&self, query: &str) -> Result<Value> {
+        let mut response: Value = self
+            .client
+            .post(&self.endpoint)
+            .json(&json!({ "query": query }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(errors) = response.get("errors") {
+            bail!("GraphQL request failed: {errors}");
+        }
+
+        Ok(response["data"].take())
+    }
+
+    /// Signs and publishes an operation, returns the id of the published operation.
+    pub async fn publish(
+        &self,
+        key_pair: &KeyPair,
+        operation: &Operation,
+    ) -> Result<DocumentViewId> {
+        let view_id = operation
+            .previous()
+            .map(|view_id| format!(", viewId: \"{view_id}\""))
+            .unwrap_or_default();
+
+        let response = self
+            .query(&format!(
+                "{{
+                    nextArgs(publicKey: \"{}\"{view_id}) {{
+                        logId
+                        seqNum
+                        backlink
+                        skiplink
+                    }}
+                }}",
+                key_pair.public_key()
+            ))
+            .await?;
+
+        let next_args = &response["nextArgs"];
+        let log_id = LogId::from_str(next_args["logId"].as_str().context("Missing log id")?)?;
+        let seq_num = SeqNum::from_str(next_args["seqNum"].as_str().context("Missing seq num")?)?;
+        let backlink = next_args["backlink"].as_str().map(Hash::new).transpose()?;
+        let skiplink = next_args["skiplink"].as_str().map(Hash::new).transpose()?;
+
+        let encoded_operation = encode_operation(operation)?;
+        let encoded_entry = sign_and_encode_entry(
+            &log_id,
+            &seq_num,
+            skiplink.as_ref(),
+            backlink.as_ref(),
+            &encoded_operation,
+            key_pair,
+        )?;
+
+        self.query(&format!(
+            "mutation {{
+                publish(entry: \"{encoded_entry}\", operation: \"{encoded_operation}\") {{
+                    logId
+                }}
+            }}"
+        ))
+        .await?;
+
+        Ok(encoded_entry.hash().into())
+    }
+
+    /// Publishes a new schema and waits until the node materialized it.
+    pub async fn create_schema(
+        &self,
+        key_pair: &KeyPair,
+        name: &str,
+        fields: Vec<(&str, FieldType)>,
+    ) -> Result<SchemaId> {
+        let mut field_ids = Vec::new();
+        for (field_name, field_type) in fields {
+            let operation = Schema::create_field(field_name, field_type);
+            field_ids.push(self.publish(key_pair, &operation).await?);
+        }
+
+        let operation = Schema::create(name, "Documents published during soak test", field_ids);
+        let view_id = self.publish(key_pair, &operation).await?;
+        let schema_id =
+            SchemaId::Application(SchemaName::new(name).map_err(|err| anyhow!(err))?, view_id);
+
+        // Schemas are materialized in the background, wait until they show up in the API
+        for _ in 0..100 {
+            let query = format!("{{ query: all_{schema_id}(first: 1) {{ totalCount }} }}");
+            if self.query(&query).await.is_ok() {
+                return Ok(schema_id);
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        bail!("Schema {schema_id} was not materialized in time")
+    }
+}
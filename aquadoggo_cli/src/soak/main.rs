@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Soak test running a node under continuous synthetic load for a long time.
+//!
+//! Publishers create and update documents while queriers read them through the GraphQL API. At
+//! the end of every sampling window the memory growth, the number of pending materialization
+//! tasks and the latency percentiles get reported and compared against thresholds. The run fails
+//! as soon as one threshold is exceeded, this helps to catch slow leaks which would otherwise only
+//! show up after weeks of uptime.
+mod client;
+mod metrics;
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use aquadoggo::{Configuration, Node};
+use clap::Parser;
+use p2panda_rs::document::DocumentViewId;
+use p2panda_rs::identity::KeyPair;
+use p2panda_rs::operation::{OperationAction, OperationBuilder, OperationValue};
+use p2panda_rs::schema::{FieldType, SchemaId};
+use tempfile::TempDir;
+
+use crate::client::GraphQLClient;
+use crate::metrics::{format_bytes, resident_memory, Recorder, Sample, Thresholds};
+
+/// Number of times we check for remaining materialization tasks before shutting down the node.
+const SHUTDOWN_ATTEMPTS: usize = 100;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "aquadoggo-soak",
+    about = "Runs an aquadoggo node under synthetic load to measure its long-term stability",
+    version
+)]
+struct Cli {
+    /// Duration of the soak test in seconds.
+    #[arg(long, value_name = "SECONDS", default_value_t = 3600)]
+    duration: u64,
+
+    /// Time in seconds before the memory baseline is taken, allowing caches and connection pools
+    /// to fill up first.
+    #[arg(long, value_name = "SECONDS", default_value_t = 60)]
+    warmup: u64,
+
+    /// Interval in seconds in which measurements are taken and compared against the thresholds.
+    #[arg(long, value_name = "SECONDS", default_value_t = 10)]
+    sample_interval: u64,
+
+    /// Number of concurrent publishers creating and updating documents.
+    #[arg(long, value_name = "NUM", default_value_t = 4)]
+    publishers: usize,
+
+    /// Number of concurrent queriers reading documents.
+    #[arg(long, value_name = "NUM", default_value_t = 4)]
+    queriers: usize,
+
+    /// Pause in milliseconds between two requests of every publisher and querier.
+    #[arg(long, value_name = "MILLISECONDS", default_value_t = 50)]
+    request_interval: u64,
+
+    /// URL / connection string to PostgreSQL or SQLite database. Defaults to an SQLite database
+    /// in a temporary directory.
+    #[arg(short = 'd', long, value_name = "CONNECTION_STRING")]
+    database_url: Option<String>,
+
+    /// HTTP port of the node under test.
+    #[arg(short = 'p', long, value_name = "PORT", default_value_t = 2030)]
+    http_port: u16,
+
+    /// Maximum growth of resident memory in MiB after warm-up.
+    #[arg(long, value_name = "MIB", default_value_t = 256)]
+    max_memory_growth: u64,
+
+    /// Maximum number of pending materialization tasks.
+    #[arg(long, value_name = "NUM", default_value_t = 1000)]
+    max_pending_tasks: u64,
+
+    /// Maximum 99th percentile latency in milliseconds of publish and query requests.
+    #[arg(long, value_name = "MILLISECONDS", default_value_t = 1000)]
+    max_p99_latency: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+
+    let cli = Cli::parse();
+    let thresholds = Thresholds {
+        max_memory_growth: cli.max_memory_growth * 1024 * 1024,
+        max_pending_tasks: cli.max_pending_tasks,
+        max_p99_latency: Duration::from_millis(cli.max_p99_latency),
+    };
+
+    // Start node under test, isolated from other nodes on the network
+    let temp_dir = TempDir::new().context("Could not create temporary directory")?;
+    let mut config = Configuration {
+        database_url: cli.database_url.clone().unwrap_or(format!(
+            "sqlite:{}?mode=rwc",
+            temp_dir.path().join("soak.sqlite3").display()
+        )),
+        http_port: cli.http_port,
+        blobs_base_path: temp_dir.path().to_path_buf(),
+        ..Configuration::default()
+    };
+    config.network.port = 0;
+    config.network.mdns = false;
+
+    let node = Node::start(KeyPair::new(), config)
+        .await
+        .context("Could not start node")?;
+    let client = GraphQLClient::new(cli.http_port);
+
+    let schema_id = client
+        .create_schema(
+            &KeyPair::new(),
+            "soak_messages",
+            vec![
+                ("message", FieldType::String),
+                ("counter", FieldType::Integer),
+            ],
+        )
+        .await
+        .context("Could not create schema for soak test")?;
+
+    println!(
+        "Soak test running for {}s with {} publishers and {} queriers",
+        cli.duration, cli.publishers, cli.queriers
+    );
+
+    // Generate load in the background
+    let request_interval = Duration::from_millis(cli.request_interval);
+    let publish_recorder = Recorder::default();
+    let query_recorder = Recorder::default();
+    let mut load_handles = Vec::new();
+
+    for _ in 0..cli.publishers {
+        load_handles.push(tokio::spawn(publisher(
+            client.clone(),
+            schema_id.clone(),
+            publish_recorder.clone(),
+            request_interval,
+        )));
+    }
+
+    for _ in 0..cli.queriers {
+        load_handles.push(tokio::spawn(querier(
+            client.clone(),
+            schema_id.clone(),
+            query_recorder.clone(),
+            request_interval,
+        )));
+    }
+
+    // Take measurements until the time is up or a threshold was exceeded
+    let started = Instant::now();
+    let warmup = Duration::from_secs(cli.warmup);
+    let mut memory_baseline = None;
+    let mut interval = tokio::time::interval(Duration::from_secs(cli.sample_interval));
+    interval.tick().await;
+
+    let result = loop {
+        tokio::select! {
+            _ = interval.tick() => (),
+            _ = node.on_exit() => break Err(anyhow!("Node stopped unexpectedly")),
+        }
+
+        let sample = Sample {
+            memory: resident_memory(),
+            pending_tasks: node.pending_tasks().await?,
+            publish: publish_recorder.take(),
+            query: query_recorder.take(),
+        };
+
+        if memory_baseline.is_none() && started.elapsed() >= warmup {
+            memory_baseline = sample.memory;
+        }
+
+        println!(
+            "[{:>6}s] memory {}, pending tasks {}\n  publish: {}\n  query:   {}",
+            started.elapsed().as_secs(),
+            sample
+                .memory
+                .map(format_bytes)
+                .unwrap_or_else(|| "unknown".into()),
+            sample.pending_tasks,
+            sample.publish,
+            sample.query
+        );
+
+        let violations = thresholds.check(&sample, memory_baseline);
+        if !violations.is_empty() {
+            break Err(anyhow!(violations.join("\n")));
+        }
+
+        if started.elapsed() >= Duration::from_secs(cli.duration) {
+            break Ok(());
+        }
+    };
+
+    // Stop generating load and wait for the node to process what is left before shutting it down
+    for handle in load_handles {
+        handle.abort();
+    }
+
+    for _ in 0..SHUTDOWN_ATTEMPTS {
+        if node.pending_tasks().await? == 0 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    node.shutdown().await;
+
+    match result {
+        Ok(()) => {
+            println!("Soak test passed");
+            Ok(())
+        }
+        Err(err) => bail!("Soak test failed: {err}"),
+    }
+}
+
+/// Creates documents and keeps updating the latest one.
+async fn publisher(
+    client: GraphQLClient,
+    schema_id: SchemaId,
+    recorder: Recorder,
+    request_interval: Duration,
+) {
+    let key_pair = KeyPair::new();
+    let mut counter: i64 = 0;
+    let mut latest: Option<DocumentViewId> = None;
+
+    loop {
+        let fields: &[(&str, OperationValue)] = &[
+            ("message", format!("Soak test message {counter}").into()),
+            ("counter", counter.into()),
+        ];
+
+        // Create a new document every ten operations, otherwise update the latest one
+        let operation = match &latest {
+            Some(view_id) if counter % 10 != 0 => OperationBuilder::new(&schema_id)
+                .action(OperationAction::Update)
+                .previous(view_id)
+                .fields(fields)
+                .build(),
+            _ => OperationBuilder::new(&schema_id).fields(fields).build(),
+        }
+        .expect("Build operation");
+
+        let now = Instant::now();
+        match client.publish(&key_pair, &operation).await {
+            Ok(view_id) => {
+                recorder.record(now.elapsed());
+                latest = Some(view_id);
+            }
+            Err(_) => recorder.record_error(),
+        }
+
+        counter += 1;
+        tokio::time::sleep(request_interval).await;
+    }
+}
+
+/// Queries the latest documents.
+async fn querier(
+    client: GraphQLClient,
+    schema_id: SchemaId,
+    recorder: Recorder,
+    request_interval: Duration,
+) {
+    let query = format!(
+        "{{
+            query: all_{schema_id}(first: 25, orderBy: counter, orderDirection: DESC) {{
+                totalCount
+                documents {{
+                    meta {{ viewId }}
+                    fields {{ message counter }}
+                }}
+            }}
+        }}"
+    );
+
+    loop {
+        let now = Instant::now();
+        match client.query(&query).await {
+            Ok(_) => recorder.record(now.elapsed()),
+            Err(_) => recorder.record_error(),
+        }
+
+        tokio::time::sleep(request_interval).await;
+    }
+}
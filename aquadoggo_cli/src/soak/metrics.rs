@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Latencies and errors of requests recorded within one sampling window.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder(Arc<Mutex<Window>>);
+
+#[derive(Debug, Default)]
+struct Window {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+impl Recorder {
+    /// Records the latency of a successful request.
+    pub fn record(&self, latency: Duration) {
+        self.0
+            .lock()
+            .expect("Could not acquire lock on recorder")
+            .latencies
+            .push(latency);
+    }
+
+    /// Records a failed request.
+    pub fn record_error(&self) {
+        self.0
+            .lock()
+            .expect("Could not acquire lock on recorder")
+            .errors += 1;
+    }
+
+    /// Returns statistics of all recorded requests and starts a new sampling window.
+    ///
+    /// Recorded values are discarded afterwards, this keeps the memory usage of the soak test
+    /// itself constant.
+    pub fn take(&self) -> Stats {
+        let window =
+            std::mem::take(&mut *self.0.lock().expect("Could not acquire lock on recorder"));
+
+        let mut latencies = window.latencies;
+        latencies.sort();
+
+        Stats {
+            requests: latencies.len() as u64,
+            errors: window.errors,
+            p50: percentile(&latencies, 50.0),
+            p95: percentile(&latencies, 95.0),
+            p99: percentile(&latencies, 99.0),
+        }
+    }
+}
+
+/// Statistics of requests within one sampling window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of successful requests.
+    pub requests: u64,
+
+    /// Number of failed requests.
+    pub errors: u64,
+
+    /// Latency percentiles of successful requests, `None` if there were no requests.
+    pub p50: Option<Duration>,
+    pub p95: Option<Duration>,
+    pub p99: Option<Duration>,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = |latency: Option<Duration>| match latency {
+            Some(latency) => format!("{}ms", latency.as_millis()),
+            None => "-".into(),
+        };
+
+        write!(
+            f,
+            "{} ok, {} errors, p50 {}, p95 {}, p99 {}",
+            self.requests,
+            self.errors,
+            format(self.p50),
+            format(self.p95),
+            format(self.p99)
+        )
+    }
+}
+
+/// Returns the value below which the given percentage of the sorted latencies fall.
+///
+/// Uses the "nearest rank" method.
+fn percentile(sorted: &[Duration], percentage: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = ((percentage / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// State of the node and the load generators measured at the end of a sampling window.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sample {
+    /// Resident memory of the process in bytes, if it can be measured on this platform.
+    pub memory: Option<u64>,
+
+    /// Number of materialization tasks which are queued up or currently being processed.
+    pub pending_tasks: u64,
+
+    /// Statistics of publishing operations.
+    pub publish: Stats,
+
+    /// Statistics of querying documents.
+    pub query: Stats,
+}
+
+/// Limits which fail the soak test when they are exceeded.
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    /// Maximum growth of resident memory in bytes compared to the baseline after warm-up.
+    pub max_memory_growth: u64,
+
+    /// Maximum number of pending materialization tasks.
+    pub max_pending_tasks: u64,
+
+    /// Maximum 99th percentile latency of publish and query requests.
+    pub max_p99_latency: Duration,
+}
+
+impl Thresholds {
+    /// Returns a description of every threshold the sample exceeds.
+    ///
+    /// Memory growth is only checked when a baseline was taken.
+    pub fn check(&self, sample: &Sample, memory_baseline: Option<u64>) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if let (Some(baseline), Some(memory)) = (memory_baseline, sample.memory) {
+            let growth = memory.saturating_sub(baseline);
+            if growth > self.max_memory_growth {
+                violations.push(format!(
+                    "Memory grew by {} since warm-up, exceeding limit of {}",
+                    format_bytes(growth),
+                    format_bytes(self.max_memory_growth)
+                ));
+            }
+        }
+
+        if sample.pending_tasks > self.max_pending_tasks {
+            violations.push(format!(
+                "{} pending materialization tasks, exceeding limit of {}",
+                sample.pending_tasks, self.max_pending_tasks
+            ));
+        }
+
+        for (name, stats) in [("publish", sample.publish), ("query", sample.query)] {
+            if let Some(p99) = stats.p99 {
+                if p99 > self.max_p99_latency {
+                    violations.push(format!(
+                        "p99 {} latency of {}ms, exceeding limit of {}ms",
+                        name,
+                        p99.as_millis(),
+                        self.max_p99_latency.as_millis()
+                    ));
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// Returns the resident memory of this process in bytes.
+///
+/// Only supported on Linux, returns `None` on other platforms.
+pub fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+
+    // Value is given in kilobytes, for example "VmRSS:     12345 kB"
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Returns a human-readable representation of a number of bytes.
+pub fn format_bytes(bytes: u64) -> String {
+    format!("{:.1}MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{percentile, Recorder, Sample, Stats, Thresholds};
+
+    #[test]
+    fn percentiles() {
+        let latencies: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+
+        assert_eq!(
+            percentile(&latencies, 50.0),
+            Some(Duration::from_millis(50))
+        );
+        assert_eq!(
+            percentile(&latencies, 99.0),
+            Some(Duration::from_millis(99))
+        );
+        assert_eq!(
+            percentile(&latencies, 100.0),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            percentile(&latencies[..1], 99.0),
+            Some(Duration::from_millis(1))
+        );
+        assert_eq!(percentile(&[], 99.0), None);
+    }
+
+    #[test]
+    fn take_starts_new_window() {
+        let recorder = Recorder::default();
+        recorder.record(Duration::from_millis(20));
+        recorder.record(Duration::from_millis(10));
+        recorder.record_error();
+
+        let stats = recorder.take();
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.p50, Some(Duration::from_millis(10)));
+        assert_eq!(stats.p99, Some(Duration::from_millis(20)));
+
+        assert_eq!(recorder.take(), Stats::default());
+    }
+
+    #[test]
+    fn check_thresholds() {
+        let thresholds = Thresholds {
+            max_memory_growth: 1024,
+            max_pending_tasks: 10,
+            max_p99_latency: Duration::from_millis(100),
+        };
+
+        let mut sample = Sample {
+            memory: Some(2048),
+            pending_tasks: 10,
+            query: Stats {
+                p99: Some(Duration::from_millis(100)),
+                ..Stats::default()
+            },
+            ..Sample::default()
+        };
+        assert!(thresholds.check(&sample, Some(1024)).is_empty());
+
+        // Memory growth is not checked before there is a baseline
+        sample.memory = Some(4096);
+        assert!(thresholds.check(&sample, None).is_empty());
+        assert_eq!(thresholds.check(&sample, Some(1024)).len(), 1);
+
+        sample.pending_tasks = 11;
+        sample.publish.p99 = Some(Duration::from_millis(101));
+        assert_eq!(thresholds.check(&sample, Some(1024)).len(), 3);
+    }
+}